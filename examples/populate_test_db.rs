@@ -24,6 +24,8 @@ fn make_trade(token_id: &str, side: &str, shares: f64, price: f64) -> TradeRecor
         is_live: Some(false),
         aggregation_count: None,
         aggregation_window_ms: None,
+        order_id: None,
+        contributing_traders: None,
     }
 }
 
@@ -33,6 +33,8 @@ fn main() -> anyhow::Result<()> {
             is_live: Some(true),
             aggregation_count: None, // Not aggregated
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         };
         store.insert_trade(&trade)?;
     }
@@ -58,6 +60,8 @@ fn main() -> anyhow::Result<()> {
             is_live: Some(true),
             aggregation_count: Some(3), // This order aggregated 3 trades
             aggregation_window_ms: Some(500),
+            order_id: None,
+            contributing_traders: None,
         };
         store.insert_trade(&trade)?;
     }
@@ -9,6 +9,7 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use crate::models::{OrderInfo, ParsedEvent};
+use crate::settings::CopyPriceRange;
 
 /// Configuration for trade aggregation behavior
 #[derive(Debug, Clone)]
@@ -21,6 +22,12 @@ pub struct AggregationConfig {
     pub max_pending_usd: f64,
     /// Share count threshold that bypasses aggregation (default: 4000 shares)
     pub bypass_threshold: f64,
+    /// Keep each trader's trades in separate windows keyed by (trader, token_id, side)
+    /// so aggregated orders stay attributed to one trader (default: false)
+    pub aggregate_per_trader: bool,
+    /// Max absolute difference between a new trade's price and the pending window's
+    /// average price; beyond it the window is flushed and a fresh one started (default: None = never)
+    pub max_price_drift: Option<f64>,
 }
 
 impl Default for AggregationConfig {
@@ -30,6 +37,8 @@ impl Default for AggregationConfig {
             min_trades: 2,
             max_pending_usd: 500.0,
             bypass_threshold: 4000.0,
+            aggregate_per_trader: false,
+            max_price_drift: None,
         }
     }
 }
@@ -79,6 +88,11 @@ impl PendingTrade {
     pub fn aggregation_key(&self) -> String {
         format!("{}:{}", self.token_id, self.side)
     }
+
+    /// Create aggregation key from trader, token_id and side
+    pub fn trader_aggregation_key(&self) -> String {
+        format!("{}:{}:{}", self.trader, self.token_id, self.side)
+    }
 }
 
 /// Represents the result of aggregating multiple trades
@@ -90,16 +104,20 @@ pub struct AggregatedTrade {
     pub side: String,
     /// Total shares across all aggregated trades
     pub total_shares: f64,
-    /// Weighted average price
+    /// Share-weighted average price: sum(shares * price) / sum(shares), not a mean of prices
     pub avg_price: f64,
     /// Number of trades aggregated
     pub trade_count: usize,
-    /// Total USD value
+    /// Total USD value: sum(shares * price), i.e. total_shares * avg_price
     pub total_usd: f64,
     /// Timestamp of first trade in the aggregation
     pub first_trade_time: Instant,
-    /// List of trader addresses involved
+    /// Trader addresses involved, deduped in first-seen order (persisted as `contributing_traders`)
     pub traders: Vec<String>,
+    /// Configured aggregation window (zero when built outside a `TradeAggregator`)
+    pub window_duration: Duration,
+    /// Time from the first trade in the window until it was flushed
+    pub window_elapsed: Duration,
 }
 
 impl AggregatedTrade {
@@ -108,9 +126,14 @@ impl AggregatedTrade {
     /// Creates a synthetic event with:
     /// - block_number: 0 (synthetic)
     /// - tx_hash: "AGG_{trade_count}_{token_id_prefix}"
-    /// - trader_address: first trader in the aggregation
+    /// - trader_address: first trader in the aggregation (the only one in per-trader mode)
     /// - trader_label: "AGGREGATED"
     /// - trader_min_shares: 0.0 (already passed threshold checks)
+    /// - trader_max_bet_usd: None (spans multiple traders)
+    /// - trader_scaling_ratio: 0.0 (global SCALING_RATIO)
+    /// - trader_probabilistic_sizing: None (global USE_PROBABILISTIC_SIZING)
+    /// - trader_copy_price_range: unset (global MIN_COPY_PRICE/MAX_COPY_PRICE)
+    /// - trader_observe: false (observed traders' fills never reach the aggregator)
     pub fn to_parsed_event(&self) -> ParsedEvent {
         let token_id_prefix = if self.token_id.len() > 10 {
             &self.token_id[..10]
@@ -124,10 +147,16 @@ impl AggregatedTrade {
             trader_address: self.traders.first().cloned().unwrap_or_default(),
             trader_label: "AGGREGATED".to_string(),
             trader_min_shares: 0.0, // Already passed min_shares checks
+            trader_max_bet_usd: None,
+            trader_scaling_ratio: 0.0,
+            trader_probabilistic_sizing: None,
+            trader_copy_price_range: CopyPriceRange::default(),
+            trader_observe: false,
             order: OrderInfo {
                 order_type: format!("{}_FILL", self.side),
                 clob_token_id: Arc::from(self.token_id.as_str()),
-                usd_value: self.total_usd,
+                // From the same two fields as the order, so usd_value / shares is exactly its price
+                usd_value: self.total_shares * self.avg_price,
                 shares: self.total_shares,
                 price_per_share: self.avg_price,
             },
@@ -144,23 +173,16 @@ impl AggregatedTrade {
         let side = trades[0].side.clone();
         let first_trade_time = trades[0].timestamp;
 
-        let mut total_value = 0.0;
-        let mut total_shares = 0.0;
         let mut traders = Vec::new();
-
         for trade in &trades {
-            total_value += trade.usd_value();
-            total_shares += trade.shares;
             if !traders.contains(&trade.trader) {
                 traders.push(trade.trader.clone());
             }
         }
 
-        let avg_price = if total_shares > 0.0 {
-            total_value / total_shares
-        } else {
-            0.0
-        };
+        let total_shares: f64 = trades.iter().map(|t| t.shares).sum();
+        let total_value: f64 = trades.iter().map(|t| t.usd_value()).sum();
+        let avg_price = pending_avg_price(&trades).unwrap_or(0.0);
 
         Some(Self {
             token_id,
@@ -171,14 +193,22 @@ impl AggregatedTrade {
             total_usd: total_value,
             first_trade_time,
             traders,
+            window_duration: Duration::ZERO,
+            window_elapsed: first_trade_time.elapsed(),
         })
     }
 }
 
+/// Share-weighted average price of pending trades (None if empty or zero shares)
+fn pending_avg_price(trades: &[PendingTrade]) -> Option<f64> {
+    let shares: f64 = trades.iter().map(|t| t.shares).sum();
+    (shares > 0.0).then(|| trades.iter().map(|t| t.usd_value()).sum::<f64>() / shares)
+}
+
 /// Main aggregator that manages pending trades and produces aggregated trades
 pub struct TradeAggregator {
     config: AggregationConfig,
-    /// Pending trades grouped by (token_id, side)
+    /// Pending trades grouped by (token_id, side), or (trader, token_id, side) in per-trader mode
     pending: HashMap<String, Vec<PendingTrade>>,
 }
 
@@ -194,6 +224,9 @@ impl TradeAggregator {
     /// Add a trade to the aggregator
     /// Returns Some(AggregatedTrade) if the trade should be executed immediately
     /// Returns None if the trade is added to the pending window
+    ///
+    /// If the trade's price drifted beyond `max_price_drift` from the pending window's
+    /// average, the stale window is returned for execution and the trade starts a new one.
     pub fn add_trade(
         &mut self,
         token_id: String,
@@ -205,12 +238,26 @@ impl TradeAggregator {
         // Check bypass threshold - large trades execute immediately
         if shares >= self.config.bypass_threshold {
             let trade = PendingTrade::new(token_id, side, shares, price, trader);
-            return AggregatedTrade::from_trades(vec![trade]);
+            return self.aggregate(vec![trade]);
         }
 
         // Add to pending trades
         let trade = PendingTrade::new(token_id, side, shares, price, trader);
-        let key = trade.aggregation_key();
+        let key = if self.config.aggregate_per_trader {
+            trade.trader_aggregation_key()
+        } else {
+            trade.aggregation_key()
+        };
+
+        // Price moved too far: execute the stale window as-is rather than blending prices
+        if let Some(max_drift) = self.config.max_price_drift
+            && let Some(avg_price) = self.pending.get(&key).and_then(|trades| pending_avg_price(trades))
+            && (trade.price - avg_price).abs() > max_drift
+        {
+            let stale = self.flush_key_force(&key);
+            self.pending.insert(key, vec![trade]);
+            return stale;
+        }
 
         let pending_trades = self.pending.entry(key.clone()).or_insert_with(Vec::new);
         pending_trades.push(trade);
@@ -224,12 +271,31 @@ impl TradeAggregator {
         None
     }
 
+    /// Park a trade the order worker deferred (MIN_SUBMIT_INTERVAL_MS) in its pending window
+    /// Skips the bypass and USD thresholds, so it's only submitted when the window expires
+    pub fn defer_trade(
+        &mut self,
+        token_id: String,
+        side: String,
+        shares: f64,
+        price: f64,
+        trader: String,
+    ) {
+        let trade = PendingTrade::new(token_id, side, shares, price, trader);
+        let key = if self.config.aggregate_per_trader {
+            trade.trader_aggregation_key()
+        } else {
+            trade.aggregation_key()
+        };
+        self.pending.entry(key).or_default().push(trade);
+    }
+
     /// Flush pending trades for a specific key (used for USD threshold flush)
     /// Only flushes if min_trades threshold is met (to encourage aggregation)
     fn flush_key_if_ready(&mut self, key: &str) -> Option<AggregatedTrade> {
         if let Some(trades) = self.pending.remove(key) {
             if trades.len() >= self.config.min_trades {
-                return AggregatedTrade::from_trades(trades);
+                return self.aggregate(trades);
             } else {
                 // Put back if not enough trades yet - wait for more or window expiry
                 self.pending.insert(key.to_string(), trades);
@@ -241,7 +307,14 @@ impl TradeAggregator {
     /// Force flush all pending trades for a specific key (window expired)
     /// Executes regardless of trade count - window timeout means no more trades coming
     fn flush_key_force(&mut self, key: &str) -> Option<AggregatedTrade> {
-        self.pending.remove(key).and_then(AggregatedTrade::from_trades)
+        let trades = self.pending.remove(key)?;
+        self.aggregate(trades)
+    }
+
+    /// Combine a flushed window's trades, stamped with the configured window duration
+    fn aggregate(&self, trades: Vec<PendingTrade>) -> Option<AggregatedTrade> {
+        let window_duration = self.config.window_duration;
+        AggregatedTrade::from_trades(trades).map(|agg| AggregatedTrade { window_duration, ..agg })
     }
 
     /// Check and flush expired windows
@@ -274,9 +347,10 @@ impl TradeAggregator {
     /// Flush all pending trades (used during shutdown)
     /// Force flushes everything - no point waiting on shutdown
     pub fn flush_all(&mut self) -> Vec<AggregatedTrade> {
-        self.pending
-            .drain()
-            .filter_map(|(_, trades)| AggregatedTrade::from_trades(trades))
+        let pending = std::mem::take(&mut self.pending);
+        pending
+            .into_values()
+            .filter_map(|trades| self.aggregate(trades))
             .collect()
     }
 
@@ -298,6 +372,8 @@ mod tests {
         assert_eq!(config.min_trades, 2);
         assert_eq!(config.max_pending_usd, 500.0);
         assert_eq!(config.bypass_threshold, 4000.0);
+        assert!(!config.aggregate_per_trader);
+        assert_eq!(config.max_price_drift, None);
     }
 
     #[test]
@@ -307,12 +383,16 @@ mod tests {
             min_trades: 3,
             max_pending_usd: 1000.0,
             bypass_threshold: 5000.0,
+            aggregate_per_trader: true,
+            max_price_drift: Some(0.05),
         };
 
         assert_eq!(config.window_duration, Duration::from_millis(1000));
         assert_eq!(config.min_trades, 3);
         assert_eq!(config.max_pending_usd, 1000.0);
         assert_eq!(config.bypass_threshold, 5000.0);
+        assert!(config.aggregate_per_trader);
+        assert_eq!(config.max_price_drift, Some(0.05));
     }
 
     #[test]
@@ -641,6 +721,30 @@ mod tests {
         assert_eq!(aggregator.pending_count(), 0);
     }
 
+    #[test]
+    fn test_flushed_window_records_window_timing() {
+        let config = AggregationConfig {
+            window_duration: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let mut aggregator = TradeAggregator::new(config);
+
+        aggregator.add_trade("0xabc123".to_string(), "BUY".to_string(), 100.0, 0.40, "0xtrader1".to_string());
+        std::thread::sleep(Duration::from_millis(80));
+
+        let expired = aggregator.flush_expired();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].window_duration, Duration::from_millis(50));
+        assert!(expired[0].window_elapsed >= Duration::from_millis(80));
+
+        // Bypassed trades never waited in a window
+        let bypass = aggregator
+            .add_trade("0xabc123".to_string(), "BUY".to_string(), 5000.0, 0.40, "0xtrader1".to_string())
+            .unwrap();
+        assert_eq!(bypass.window_duration, Duration::from_millis(50));
+        assert!(bypass.window_elapsed < Duration::from_millis(50));
+    }
+
     #[test]
     fn test_aggregator_flush_all() {
         let config = AggregationConfig::default();
@@ -809,6 +913,53 @@ mod tests {
         assert_eq!(event.order.usd_value, 140.0);
     }
 
+    #[test]
+    fn test_avg_price_is_share_weighted_not_mean() {
+        // 10 shares at 0.90 and 990 shares at 0.10: the mean of prices would be 0.50
+        let trades = vec![
+            PendingTrade::new("0xabc".to_string(), "BUY".to_string(), 10.0, 0.90, "0xsmall".to_string()),
+            PendingTrade::new("0xabc".to_string(), "BUY".to_string(), 990.0, 0.10, "0xlarge".to_string()),
+        ];
+
+        let aggregated = AggregatedTrade::from_trades(trades).unwrap();
+
+        // (10*0.90 + 990*0.10) / 1000 = 108 / 1000
+        assert_eq!(aggregated.total_shares, 1000.0);
+        assert!((aggregated.avg_price - 0.108).abs() < 1e-12);
+        assert!((aggregated.total_usd - 108.0).abs() < 1e-9);
+
+        let event = aggregated.to_parsed_event();
+        assert!((event.order.price_per_share - 0.108).abs() < 1e-12);
+        assert!((event.order.usd_value - 108.0).abs() < 1e-9);
+        assert_eq!(event.order.usd_value, event.order.shares * event.order.price_per_share);
+    }
+
+    #[test]
+    fn test_avg_price_weighted_through_aggregator_flush() {
+        let config = AggregationConfig {
+            window_duration: Duration::from_secs(60),
+            min_trades: 1,
+            max_pending_usd: f64::MAX,
+            ..AggregationConfig::default()
+        };
+        let mut aggregator = TradeAggregator::new(config);
+
+        // Fractional sizes and prices across three orders of magnitude
+        for (shares, price) in [(0.5, 0.99), (12.25, 0.42), (3000.0, 0.03)] {
+            assert!(aggregator.add_trade("0xmix".to_string(), "SELL".to_string(), shares, price, "0xwhale".to_string()).is_none());
+        }
+
+        let flushed = aggregator.flush_all();
+        assert_eq!(flushed.len(), 1);
+        let agg = &flushed[0];
+        let usd = 0.5 * 0.99 + 12.25 * 0.42 + 3000.0 * 0.03;
+        assert!((agg.total_shares - 3012.75).abs() < 1e-9);
+        assert!((agg.avg_price - usd / 3012.75).abs() < 1e-12);
+        // Far from the 0.48 mean of the three prices
+        assert!(agg.avg_price < 0.04);
+        assert!((agg.to_parsed_event().order.usd_value - usd).abs() < 1e-9);
+    }
+
     #[test]
     fn test_aggregated_trade_to_parsed_event_sell() {
         let trade = PendingTrade::new(
@@ -827,4 +978,134 @@ mod tests {
         assert_eq!(event.order.price_per_share, 0.75);
         assert_eq!(event.order.usd_value, 375.0);
     }
+
+    /// Two traders' overlapping trades on the same token and side
+    fn add_overlapping_trades(aggregator: &mut TradeAggregator) {
+        for (shares, trader) in [(100.0, "0xtrader1"), (200.0, "0xtrader2"), (50.0, "0xtrader1")] {
+            aggregator.add_trade(
+                "0xabc123".to_string(),
+                "BUY".to_string(),
+                shares,
+                0.50,
+                trader.to_string(),
+            );
+        }
+    }
+
+    #[test]
+    fn test_per_trader_mode_flushes_traders_separately() {
+        let mut aggregator = TradeAggregator::new(AggregationConfig {
+            aggregate_per_trader: true,
+            ..Default::default()
+        });
+        add_overlapping_trades(&mut aggregator);
+
+        let mut flushed = aggregator.flush_all();
+        flushed.sort_by(|a, b| a.traders.cmp(&b.traders));
+
+        assert_eq!(flushed.len(), 2);
+        assert_eq!(flushed[0].traders, vec!["0xtrader1".to_string()]);
+        assert_eq!(flushed[0].total_shares, 150.0);
+        assert_eq!(flushed[0].trade_count, 2);
+        assert_eq!(flushed[1].traders, vec!["0xtrader2".to_string()]);
+        assert_eq!(flushed[1].total_shares, 200.0);
+
+        // Each synthetic event is attributed to its own trader
+        assert_eq!(flushed[0].to_parsed_event().trader_address, "0xtrader1");
+        assert_eq!(flushed[1].to_parsed_event().trader_address, "0xtrader2");
+    }
+
+    #[test]
+    fn test_default_mode_combines_traders() {
+        let mut aggregator = TradeAggregator::new(AggregationConfig::default());
+        add_overlapping_trades(&mut aggregator);
+
+        let flushed = aggregator.flush_all();
+
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0].trade_count, 3);
+        assert_eq!(flushed[0].total_shares, 350.0);
+        assert_eq!(flushed[0].traders, vec!["0xtrader1".to_string(), "0xtrader2".to_string()]);
+    }
+
+    fn drift_aggregator() -> TradeAggregator {
+        TradeAggregator::new(AggregationConfig {
+            max_price_drift: Some(0.05),
+            ..Default::default()
+        })
+    }
+
+    fn add_buy(aggregator: &mut TradeAggregator, shares: f64, price: f64) -> Option<AggregatedTrade> {
+        aggregator.add_trade(
+            "0xabc123".to_string(),
+            "BUY".to_string(),
+            shares,
+            price,
+            "0xtrader".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_price_drift_within_threshold_blends() {
+        let mut aggregator = drift_aggregator();
+
+        assert!(add_buy(&mut aggregator, 100.0, 0.50).is_none());
+        assert!(add_buy(&mut aggregator, 100.0, 0.54).is_none()); // 0.04 from avg 0.50
+        assert!(add_buy(&mut aggregator, 100.0, 0.56).is_none()); // 0.04 from avg 0.52
+        assert_eq!(aggregator.pending_count(), 3);
+
+        let flushed = aggregator.flush_all();
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0].trade_count, 3);
+        assert!((flushed[0].avg_price - 0.5333333333333333).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_price_drift_beyond_threshold_flushes_early() {
+        let mut aggregator = drift_aggregator();
+
+        add_buy(&mut aggregator, 100.0, 0.50);
+        add_buy(&mut aggregator, 100.0, 0.52);
+
+        // 0.10 above the 0.51 average: the old window is returned, the new trade starts fresh
+        let stale = add_buy(&mut aggregator, 50.0, 0.61).expect("stale window flushed");
+        assert_eq!(stale.trade_count, 2);
+        assert_eq!(stale.total_shares, 200.0);
+        assert!((stale.avg_price - 0.51).abs() < 1e-9);
+        assert_eq!(aggregator.pending_count(), 1);
+
+        let fresh = aggregator.flush_all();
+        assert_eq!(fresh[0].total_shares, 50.0);
+        assert_eq!(fresh[0].avg_price, 0.61);
+    }
+
+    #[test]
+    fn test_price_drift_disabled_by_default() {
+        let mut aggregator = TradeAggregator::new(AggregationConfig::default());
+
+        add_buy(&mut aggregator, 100.0, 0.20);
+        assert!(add_buy(&mut aggregator, 100.0, 0.80).is_none());
+        assert_eq!(aggregator.pending_count(), 2);
+    }
+
+    #[test]
+    fn test_deferred_trade_waits_for_window() {
+        let mut aggregator = TradeAggregator::new(AggregationConfig {
+            window_duration: Duration::from_millis(50),
+            ..Default::default()
+        });
+
+        // Above the bypass threshold, yet parked until the window expires
+        aggregator.defer_trade("0xabc123".to_string(), "BUY".to_string(), 5000.0, 0.05, "0xtrader".to_string());
+        assert_eq!(aggregator.pending_count(), 1);
+        assert!(aggregator.flush_expired().is_empty());
+
+        // Later trades on the token coalesce with it ($255 pending stays under the $500 flush)
+        assert!(add_buy(&mut aggregator, 100.0, 0.05).is_none());
+        std::thread::sleep(Duration::from_millis(60));
+        let flushed = aggregator.flush_expired();
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0].trade_count, 2);
+        assert_eq!(flushed[0].total_shares, 5100.0);
+    }
 }
@@ -4,24 +4,41 @@
 /// This module is optional - only starts if API_ENABLED=true in settings
 
 use axum::{
-    extract::{Query, State},
+    extract::{Query, State, rejection::QueryRejection},
     http::StatusCode,
     response::{IntoResponse, Json},
     routing::{get, post},
     Router,
 };
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Instant;
 
+use crate::config::diff::TradersDiff;
 use crate::config::reloadable::ReloadableTraders;
-use crate::persistence::{TradeStore, TradeRecord};
+use crate::config::traders::validate_and_normalize_address;
+use crate::event_metrics;
+use crate::flatten::{FlattenRequest, FlattenResponse};
+use crate::persistence::{Position, TradeFilter, TradeRecord, TradeStore};
+use crate::recent_trades::RecentTrades;
+use crate::pnl::{calculate_cost_basis, calculate_unrealized_pnl, RealizedPnl};
+use crate::prices::{PriceCache, PriceFeed, PriceInfo, MARKET_WS_URL};
+use crate::settings::{API_PRICE_TTL_SECS, FEE_PER_TRADE_USD};
+use crate::ws_health::{WsHealth, WsStatus};
 
 /// API server configuration
 #[derive(Debug, Clone)]
 pub struct ApiConfig {
     pub enabled: bool,
     pub port: u16,
+    /// Price /pnl positions from the CLOB market WebSocket (REST until the feed has a price)
+    pub live_prices: bool,
+    /// Estimated USD fee per fill, deducted from realized P&L (FEE_PER_TRADE_USD)
+    pub fee_per_trade_usd: f64,
+    /// Seconds /positions and /pnl reuse a fetched price (API_PRICE_TTL_SECS)
+    pub price_ttl_secs: u64,
 }
 
 impl Default for ApiConfig {
@@ -29,6 +46,9 @@ impl Default for ApiConfig {
         Self {
             enabled: false,
             port: 8080,
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
         }
     }
 }
@@ -37,25 +57,66 @@ impl Default for ApiConfig {
 #[derive(Clone)]
 struct AppState {
     db_path: Option<String>,
+    /// The last trades the persistence worker recorded, answering most /trades queries (None = always the DB)
+    recent_trades: Option<Arc<RecentTrades>>,
     start_time: Instant,
     /// Optional reloadable traders config for the /reload endpoint
     traders: Option<ReloadableTraders>,
+    /// Shared with the order engine; set by /pause, cleared by /resume
+    trading_paused: Arc<AtomicBool>,
+    /// Live prices for held tokens (API_LIVE_PRICES)
+    price_feed: Option<PriceFeed>,
+    /// Prices shared by /positions and /pnl, created off the runtime on first use (see `with_price_cache`)
+    prices: Arc<once_cell::sync::OnceCell<Mutex<PriceCache>>>,
+    price_ttl_secs: u64,
+    /// Fee estimate applied to realized P&L
+    fee_per_trade_usd: f64,
+    /// WebSocket liveness (None = not tracked, /health reports ok)
+    ws_health: Option<Arc<WsHealth>>,
+    /// The bot's flatten task, which closes every position (None = /flatten unavailable)
+    flatten_tx: Option<tokio::sync::mpsc::UnboundedSender<FlattenRequest>>,
 }
 
 /// Health check response
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 struct HealthResponse {
+    /// "ok", or "degraded" (HTTP 503) when the WebSocket is stalled or unconfirmed
     status: String,
     uptime_seconds: u64,
+    #[serde(default)]
+    trading_paused: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    websocket: Option<WsStatus>,
 }
 
-/// Position response (matches Position from TradeStore)
+/// Position response (Position from TradeStore plus live prices)
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 struct PositionResponse {
     token_id: String,
     net_shares: f64,
     avg_entry_price: Option<f64>,
     trade_count: i32,
+    /// Best bid/ask; null with ?prices=false or when no price could be fetched
+    bid_price: Option<f64>,
+    ask_price: Option<f64>,
+    /// Same calculation as position_monitor; null without a price or entry
+    unrealized_pnl: Option<f64>,
+    /// FIFO realized P&L of this token so far, net of fees
+    realized_pnl: f64,
+    /// realized_pnl + unrealized_pnl; null when unrealized_pnl is
+    total_pnl: Option<f64>,
+}
+
+/// Positions response with ?detail=true: open positions, closed tokens and the same totals as /pnl
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct PositionsResponse {
+    positions: Vec<PositionResponse>,
+    /// Tokens with realized P&L and nothing left open, by token ID
+    closed: Vec<TokenPnlResponse>,
+    realized_pnl: f64,
+    /// Priced positions only; 0 with ?prices=false
+    unrealized_pnl: f64,
+    total_pnl: f64,
 }
 
 /// Trade response (simplified from TradeRecord)
@@ -79,6 +140,35 @@ struct TradeResponse {
     is_live: Option<bool>,
     aggregation_count: Option<u32>,
     aggregation_window_ms: Option<u64>,
+    order_id: Option<String>,
+    contributing_traders: Option<Vec<String>>,
+}
+
+impl From<TradeRecord> for TradeResponse {
+    fn from(t: TradeRecord) -> Self {
+        Self {
+            timestamp_ms: t.timestamp_ms,
+            block_number: t.block_number,
+            tx_hash: t.tx_hash,
+            trader_address: t.trader_address,
+            token_id: t.token_id,
+            side: t.side,
+            whale_shares: t.whale_shares,
+            whale_price: t.whale_price,
+            whale_usd: t.whale_usd,
+            our_shares: t.our_shares,
+            our_price: t.our_price,
+            our_usd: t.our_usd,
+            fill_pct: t.fill_pct,
+            status: t.status,
+            latency_ms: t.latency_ms,
+            is_live: t.is_live,
+            aggregation_count: t.aggregation_count,
+            aggregation_window_ms: t.aggregation_window_ms,
+            order_id: t.order_id,
+            contributing_traders: t.contributing_traders,
+        }
+    }
 }
 
 /// Stats response
@@ -88,7 +178,78 @@ struct StatsResponse {
     aggregated_orders: u32,
     total_trades_combined: u32,
     avg_trades_per_aggregation: f64,
+    avg_window_ms: f64,
     total_positions: usize,
+    trading_paused: bool,
+}
+
+/// Per-trader response (stats plus FIFO realized P&L)
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct TraderResponse {
+    address: String,
+    label: String,
+    total_trades: u32,
+    successful_trades: u32,
+    failed_trades: u32,
+    total_copied_usd: f64,
+    realized_pnl: f64,
+    winning_trades: u32,
+    losing_trades: u32,
+    win_rate: Option<f64>,
+}
+
+/// One token's P&L breakdown in a /pnl response
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct TokenPnlResponse {
+    token_id: String,
+    /// 0 for a fully closed token
+    net_shares: f64,
+    realized_pnl: f64,
+    /// 0 for a closed token; null for an open position without a price or entry
+    unrealized_pnl: Option<f64>,
+    /// realized_pnl + unrealized_pnl; null when unrealized_pnl is
+    total_pnl: Option<f64>,
+}
+
+/// Account P&L response
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct PnlResponse {
+    /// Token filter, if one was given
+    token: Option<String>,
+    realized_pnl: f64,
+    unrealized_pnl: f64,
+    total_pnl: f64,
+    /// Cost basis of open positions with a live price
+    total_cost_basis: f64,
+    /// Open positions (matching the filter)
+    position_count: usize,
+    /// Open positions a price could be fetched for (unrealized P&L covers only these)
+    priced_positions: usize,
+    /// Per-token breakdown of the open positions
+    positions: Vec<TokenPnlResponse>,
+    /// Tokens with realized P&L and nothing left open, by token ID
+    closed: Vec<TokenPnlResponse>,
+}
+
+/// Query parameters for /positions endpoint
+#[derive(Debug, Deserialize)]
+struct PositionsQuery {
+    /// Fetch bid/ask and unrealized P&L (?prices=false skips it for a fast response)
+    #[serde(default = "default_prices")]
+    prices: bool,
+    /// Return an object with closed tokens and totals instead of the bare positions array
+    #[serde(default)]
+    detail: bool,
+}
+
+fn default_prices() -> bool {
+    true
+}
+
+/// Query parameters for /pnl endpoint
+#[derive(Debug, Deserialize)]
+struct PnlQuery {
+    token: Option<String>,
 }
 
 /// Query parameters for /trades endpoint
@@ -97,6 +258,34 @@ struct TradesQuery {
     #[serde(default = "default_limit")]
     limit: usize,
     since: Option<i64>,
+    /// Trader address (40 hex chars, 0x optional)
+    trader: Option<String>,
+    token: Option<String>,
+    /// Stored status, e.g. SUCCESS, PARTIAL, FAILED, SKIPPED (case-insensitive)
+    status: Option<String>,
+}
+
+impl TradesQuery {
+    /// Store filter; Err is the message for a 400 response
+    fn filter(self) -> Result<TradeFilter, String> {
+        let non_empty = |v: Option<String>| v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        let trader = non_empty(self.trader)
+            .map(|t| validate_and_normalize_address(&t).map_err(|e| format!("Invalid trader: {}", e)))
+            .transpose()?;
+        Ok(TradeFilter {
+            trader,
+            token: non_empty(self.token),
+            status: non_empty(self.status),
+            since_ms: self.since,
+        })
+    }
+}
+
+/// Query parameters for /stats endpoint
+#[derive(Debug, Deserialize)]
+struct StatsQuery {
+    /// Only count trades at or after this Unix ms timestamp (default: all time)
+    since: Option<i64>,
 }
 
 fn default_limit() -> usize {
@@ -104,21 +293,30 @@ fn default_limit() -> usize {
 }
 
 /// Health check endpoint
-/// Returns bot status and uptime
+/// Returns bot status and uptime; 503 "degraded" if the WebSocket is stalled or unconfirmed
 async fn health_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     let uptime = state.start_time.elapsed().as_secs();
+    let websocket = state.ws_health.as_ref().map(|h| h.status());
+    let healthy = websocket.as_ref().is_none_or(|ws| ws.healthy);
 
     let response = HealthResponse {
-        status: "ok".to_string(),
+        status: if healthy { "ok" } else { "degraded" }.to_string(),
         uptime_seconds: uptime,
+        trading_paused: state.trading_paused.load(Ordering::Relaxed),
+        websocket,
     };
 
-    Json(response)
+    let code = if healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (code, Json(response))
 }
 
 /// Positions endpoint
-/// Returns current positions from TradeStore
-async fn positions_handler(State(state): State<Arc<AppState>>) -> axum::response::Response {
+/// Returns current positions from TradeStore with bid/ask and unrealized P&L (unless ?prices=false);
+/// ?detail=true wraps them in an object with closed tokens and P&L totals
+async fn positions_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<PositionsQuery>,
+) -> axum::response::Response {
     let db_path = match &state.db_path {
         Some(p) => p.clone(),
         None => {
@@ -130,45 +328,106 @@ async fn positions_handler(State(state): State<Arc<AppState>>) -> axum::response
         }
     };
 
-    // Create a TradeStore connection for this request
-    let store = match TradeStore::new(&db_path) {
-        Ok(s) => s,
-        Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(serde_json::json!({"error": format!("Failed to connect to database: {}", e)})),
-            )
-                .into_response();
+    // Blocking HTTP client and SQLite: keep them off the async runtime
+    let result = tokio::task::spawn_blocking(move || {
+        if params.prices {
+            with_price_cache(&state, |prices| compute_positions(&db_path, Some(prices), state.fee_per_trade_usd))
+        } else {
+            compute_positions(&db_path, None, state.fee_per_trade_usd)
         }
-    };
+    })
+    .await;
 
-    match store.get_positions() {
-        Ok(positions) => {
-            let response: Vec<PositionResponse> = positions
-                .into_iter()
-                .map(|p| PositionResponse {
-                    token_id: p.token_id,
-                    net_shares: p.net_shares,
-                    avg_entry_price: p.avg_entry_price,
-                    trade_count: p.trade_count,
-                })
-                .collect();
-            Json(response).into_response()
-        }
-        Err(e) => (
+    match result {
+        Ok(Ok(response)) if params.detail => Json(response).into_response(),
+        Ok(Ok(response)) => Json(response.positions).into_response(),
+        Ok(Err(e)) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(serde_json::json!({"error": format!("Failed to get positions: {}", e)})),
         )
             .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": format!("Positions task failed: {}", e)})),
+        )
+            .into_response(),
     }
 }
 
+/// Open positions, priced from `prices` when given, with closed tokens and totals computed as in /pnl
+/// Blocking: opens the database and fetches prices; tokens without a price get null fields
+fn compute_positions(db_path: &str, prices: Option<&mut PriceCache>, fee_per_trade: f64) -> anyhow::Result<PositionsResponse> {
+    let store = TradeStore::new(db_path)?;
+    let positions = store.get_positions()?;
+    let realized_by_token = store.get_realized_pnl_by_token(fee_per_trade)?;
+
+    let quotes = match prices {
+        Some(cache) => {
+            let token_ids: Vec<&str> = positions.iter().map(|p| p.token_id.as_str()).collect();
+            cache.track_tokens(&token_ids);
+            cache.get_or_fetch_many(&token_ids)
+        }
+        None => HashMap::new(),
+    };
+
+    let held: Vec<&Position> = positions.iter().collect();
+    let pnl = summarize_pnl(None, &held, &quotes, &realized_by_token);
+
+    // summarize_pnl lists open tokens in position order
+    let rows = positions
+        .into_iter()
+        .zip(pnl.positions)
+        .map(|(p, token_pnl)| {
+            let quote = quotes.get(&p.token_id);
+            PositionResponse {
+                bid_price: quote.map(|q| q.bid_price),
+                ask_price: quote.map(|q| q.ask_price),
+                unrealized_pnl: token_pnl.unrealized_pnl,
+                realized_pnl: token_pnl.realized_pnl,
+                total_pnl: token_pnl.total_pnl,
+                token_id: p.token_id,
+                net_shares: p.net_shares,
+                avg_entry_price: p.avg_entry_price,
+                trade_count: p.trade_count,
+            }
+        })
+        .collect();
+
+    Ok(PositionsResponse {
+        positions: rows,
+        closed: pnl.closed,
+        realized_pnl: pnl.realized_pnl,
+        unrealized_pnl: pnl.unrealized_pnl,
+        total_pnl: pnl.total_pnl,
+    })
+}
+
 /// Trades endpoint
 /// Returns trade history with optional filters
 async fn trades_handler(
     State(state): State<Arc<AppState>>,
-    Query(params): Query<TradesQuery>,
+    params: Result<Query<TradesQuery>, QueryRejection>,
 ) -> axum::response::Response {
+    // Malformed limit/since (or an invalid trader) is the caller's mistake, not a server error
+    let bad_request = |error: String| {
+        (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": error}))).into_response()
+    };
+    let Query(params) = match params {
+        Ok(params) => params,
+        Err(rejection) => return bad_request(rejection.body_text()),
+    };
+    let limit = params.limit;
+    let filter = match params.filter() {
+        Ok(filter) => filter,
+        Err(e) => return bad_request(e),
+    };
+
+    // The last trades are usually still in memory
+    if let Some(trades) = state.recent_trades.as_ref().and_then(|buffer| buffer.query(&filter, limit)) {
+        let response: Vec<TradeResponse> = trades.into_iter().map(TradeResponse::from).collect();
+        return Json(response).into_response();
+    }
+
     let db_path = match &state.db_path {
         Some(p) => p.clone(),
         None => {
@@ -192,41 +451,9 @@ async fn trades_handler(
         }
     };
 
-    match store.get_recent_trades(params.limit) {
+    match store.get_trades_filtered(&filter, limit) {
         Ok(trades) => {
-            // Filter by timestamp if since parameter is provided
-            let filtered: Vec<TradeRecord> = if let Some(since_ms) = params.since {
-                trades
-                    .into_iter()
-                    .filter(|t| t.timestamp_ms >= since_ms)
-                    .collect()
-            } else {
-                trades
-            };
-
-            let response: Vec<TradeResponse> = filtered
-                .into_iter()
-                .map(|t| TradeResponse {
-                    timestamp_ms: t.timestamp_ms,
-                    block_number: t.block_number,
-                    tx_hash: t.tx_hash,
-                    trader_address: t.trader_address,
-                    token_id: t.token_id,
-                    side: t.side,
-                    whale_shares: t.whale_shares,
-                    whale_price: t.whale_price,
-                    whale_usd: t.whale_usd,
-                    our_shares: t.our_shares,
-                    our_price: t.our_price,
-                    our_usd: t.our_usd,
-                    fill_pct: t.fill_pct,
-                    status: t.status,
-                    latency_ms: t.latency_ms,
-                    is_live: t.is_live,
-                    aggregation_count: t.aggregation_count,
-                    aggregation_window_ms: t.aggregation_window_ms,
-                })
-                .collect();
+            let response: Vec<TradeResponse> = trades.into_iter().map(TradeResponse::from).collect();
             Json(response).into_response()
         }
         Err(e) => (
@@ -238,8 +465,18 @@ async fn trades_handler(
 }
 
 /// Stats endpoint
-/// Returns aggregation stats and overall statistics
-async fn stats_handler(State(state): State<Arc<AppState>>) -> axum::response::Response {
+/// Returns aggregation stats (optionally since a timestamp) and overall statistics
+async fn stats_handler(
+    State(state): State<Arc<AppState>>,
+    params: Result<Query<StatsQuery>, QueryRejection>,
+) -> axum::response::Response {
+    let Query(params) = match params {
+        Ok(params) => params,
+        Err(rejection) => {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": rejection.body_text()}))).into_response();
+        }
+    };
+
     let db_path = match &state.db_path {
         Some(p) => p.clone(),
         None => {
@@ -263,7 +500,7 @@ async fn stats_handler(State(state): State<Arc<AppState>>) -> axum::response::Re
         }
     };
 
-    match store.get_aggregation_stats() {
+    match store.get_aggregation_stats_since(params.since.unwrap_or(i64::MIN)) {
         Ok(agg_stats) => {
             // Also get positions count
             let positions_count = store.get_positions().map(|p| p.len()).unwrap_or(0);
@@ -273,7 +510,9 @@ async fn stats_handler(State(state): State<Arc<AppState>>) -> axum::response::Re
                 aggregated_orders: agg_stats.aggregated_orders,
                 total_trades_combined: agg_stats.total_trades_combined,
                 avg_trades_per_aggregation: agg_stats.avg_trades_per_aggregation,
+                avg_window_ms: agg_stats.avg_window_ms,
                 total_positions: positions_count,
+                trading_paused: state.trading_paused.load(Ordering::Relaxed),
             };
             Json(response).into_response()
         }
@@ -285,12 +524,220 @@ async fn stats_handler(State(state): State<Arc<AppState>>) -> axum::response::Re
     }
 }
 
+/// Traders endpoint
+/// Returns per-trader stats with realized P&L and win rate
+async fn traders_handler(State(state): State<Arc<AppState>>) -> axum::response::Response {
+    let db_path = match &state.db_path {
+        Some(p) => p.clone(),
+        None => {
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(serde_json::json!({"error": "Database not available"})),
+            )
+                .into_response();
+        }
+    };
+
+    // Create a TradeStore connection for this request
+    let store = match TradeStore::new(&db_path) {
+        Ok(s) => s,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": format!("Failed to connect to database: {}", e)})),
+            )
+                .into_response();
+        }
+    };
+
+    let result = store.get_all_trader_stats().and_then(|stats| {
+        let pnl = store.get_realized_pnl_by_trader(state.fee_per_trade_usd)?;
+        Ok((stats, pnl))
+    });
+
+    match result {
+        Ok((stats, pnl)) => {
+            let response: Vec<TraderResponse> = stats
+                .into_iter()
+                .map(|(address, label, total, successful, failed, copied_usd, _, _)| {
+                    let realized = pnl.get(&address).copied().unwrap_or_default();
+                    TraderResponse {
+                        address,
+                        label,
+                        total_trades: total,
+                        successful_trades: successful,
+                        failed_trades: failed,
+                        total_copied_usd: copied_usd,
+                        realized_pnl: realized.realized_pnl,
+                        winning_trades: realized.winning_closes,
+                        losing_trades: realized.losing_closes,
+                        win_rate: realized.win_rate(),
+                    }
+                })
+                .collect();
+            Json(response).into_response()
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": format!("Failed to get traders: {}", e)})),
+        )
+            .into_response(),
+    }
+}
+
+/// Run `f` with the price cache shared by /positions and /pnl, backed by the live feed when API_LIVE_PRICES is on
+/// Blocking: the cache is built on first use, so call this on a blocking thread (its HTTP client is synchronous)
+fn with_price_cache<T>(state: &AppState, f: impl FnOnce(&mut PriceCache) -> T) -> T {
+    let prices = state.prices.get_or_init(|| {
+        let cache = PriceCache::new(state.price_ttl_secs);
+        Mutex::new(match state.price_feed.clone() {
+            Some(feed) => cache.with_feed(feed),
+            None => cache,
+        })
+    });
+    f(&mut prices.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Realized (FIFO) plus unrealized P&L, computed the same way as position_monitor,
+/// in total and per token; tokens closed out completely are listed with their realized P&L only
+/// Blocking: opens the database and fetches prices
+fn compute_pnl(
+    db_path: &str,
+    token: Option<String>,
+    prices: &mut PriceCache,
+    fee_per_trade: f64,
+) -> anyhow::Result<PnlResponse> {
+    let store = TradeStore::new(db_path)?;
+
+    let realized_by_token: HashMap<String, RealizedPnl> = store
+        .get_realized_pnl_by_token(fee_per_trade)?
+        .into_iter()
+        .filter(|(token_id, _)| token.as_ref().is_none_or(|t| t == token_id))
+        .collect();
+
+    let all_positions = store.get_positions()?;
+    // Feed follows every held token, not just the one queried, so ?token= doesn't resubscribe
+    let held: Vec<&str> = all_positions.iter().map(|p| p.token_id.as_str()).collect();
+    prices.track_tokens(&held);
+
+    let positions: Vec<&Position> = all_positions
+        .iter()
+        .filter(|p| token.as_ref().is_none_or(|t| *t == p.token_id))
+        .collect();
+
+    let token_ids: Vec<&str> = positions.iter().map(|p| p.token_id.as_str()).collect();
+    let prices = prices.get_or_fetch_many(&token_ids);
+
+    Ok(summarize_pnl(token, &positions, &prices, &realized_by_token))
+}
+
+/// P&L totals and per-token breakdown of `positions` priced from `prices`
+/// Tokens in `realized_by_token` with closed lots and no open position are listed as closed
+fn summarize_pnl(
+    token: Option<String>,
+    positions: &[&Position],
+    prices: &HashMap<String, PriceInfo>,
+    realized_by_token: &HashMap<String, RealizedPnl>,
+) -> PnlResponse {
+    let realized_pnl = realized_by_token.values().map(|pnl| pnl.realized_pnl).sum::<f64>();
+    let mut unrealized_pnl = 0.0;
+    let mut total_cost_basis = 0.0;
+    let mut priced_positions = 0;
+    let mut by_position = Vec::with_capacity(positions.len());
+    for pos in positions {
+        let realized = realized_by_token.get(&pos.token_id).map_or(0.0, |pnl| pnl.realized_pnl);
+        let mut unrealized = None;
+        if let Some(price) = prices.get(&pos.token_id) {
+            if let Some(basis) = calculate_cost_basis(pos.net_shares, pos.avg_entry_price) {
+                total_cost_basis += basis;
+            }
+            unrealized = calculate_unrealized_pnl(pos.net_shares, pos.avg_entry_price, price.bid_price, price.ask_price);
+            unrealized_pnl += unrealized.unwrap_or(0.0);
+            priced_positions += 1;
+        }
+        by_position.push(TokenPnlResponse {
+            token_id: pos.token_id.clone(),
+            net_shares: pos.net_shares,
+            realized_pnl: realized,
+            unrealized_pnl: unrealized,
+            total_pnl: unrealized.map(|u| realized + u),
+        });
+    }
+
+    // SELLs with no BUY to match leave an empty entry; only tokens that actually closed lots count
+    let mut closed: Vec<TokenPnlResponse> = realized_by_token
+        .iter()
+        .filter(|(token_id, pnl)| {
+            pnl.win_rate().is_some() && !positions.iter().any(|p| &p.token_id == *token_id)
+        })
+        .map(|(token_id, pnl)| TokenPnlResponse {
+            token_id: token_id.clone(),
+            net_shares: 0.0,
+            realized_pnl: pnl.realized_pnl,
+            unrealized_pnl: Some(0.0),
+            total_pnl: Some(pnl.realized_pnl),
+        })
+        .collect();
+    closed.sort_by(|a, b| a.token_id.cmp(&b.token_id));
+
+    PnlResponse {
+        token,
+        realized_pnl,
+        unrealized_pnl,
+        total_pnl: realized_pnl + unrealized_pnl,
+        total_cost_basis,
+        position_count: positions.len(),
+        priced_positions,
+        positions: by_position,
+        closed,
+    }
+}
+
+/// P&L endpoint
+/// Returns realized, unrealized and total P&L, optionally for one token (?token=<id>)
+async fn pnl_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<PnlQuery>,
+) -> axum::response::Response {
+    let db_path = match &state.db_path {
+        Some(p) => p.clone(),
+        None => {
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(serde_json::json!({"error": "Database not available"})),
+            )
+                .into_response();
+        }
+    };
+
+    // Blocking HTTP client and SQLite: keep them off the async runtime
+    let pnl = tokio::task::spawn_blocking(move || {
+        with_price_cache(&state, |prices| compute_pnl(&db_path, params.token, prices, state.fee_per_trade_usd))
+    });
+    match pnl.await {
+        Ok(Ok(response)) => Json(response).into_response(),
+        Ok(Err(e)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": format!("Failed to compute P&L: {}", e)})),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": format!("P&L task failed: {}", e)})),
+        )
+            .into_response(),
+    }
+}
+
 /// Reload response
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 struct ReloadResponse {
     success: bool,
     changed: bool,
     message: String,
+    /// What the reload changed (absent when the reload failed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<TradersDiff>,
 }
 
 /// Reload endpoint
@@ -305,6 +752,7 @@ async fn reload_handler(State(state): State<Arc<AppState>>) -> axum::response::R
                     success: false,
                     changed: false,
                     message: "Reload not available (traders config not set)".to_string(),
+                    diff: None,
                 }),
             )
                 .into_response();
@@ -312,16 +760,17 @@ async fn reload_handler(State(state): State<Arc<AppState>>) -> axum::response::R
     };
 
     match traders.reload().await {
-        Ok(changed) => {
-            let message = if changed {
-                "Configuration reloaded successfully. WebSocket will reconnect with new traders."
+        Ok(diff) => {
+            let message = if diff.is_empty() {
+                "Configuration unchanged.".to_string()
             } else {
-                "Configuration unchanged."
+                format!("Configuration reloaded: {}. WebSocket will reconnect if the trader list changed.", diff.summary())
             };
             Json(ReloadResponse {
                 success: true,
-                changed,
-                message: message.to_string(),
+                changed: !diff.is_empty(),
+                message,
+                diff: Some(diff),
             })
             .into_response()
         }
@@ -331,12 +780,79 @@ async fn reload_handler(State(state): State<Arc<AppState>>) -> axum::response::R
                 success: false,
                 changed: false,
                 message: format!("Failed to reload configuration: {}", e),
+                diff: None,
             }),
         )
             .into_response(),
     }
 }
 
+/// Pause/resume response
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct PauseResponse {
+    trading_paused: bool,
+}
+
+/// Pause endpoint
+/// Stops new copy orders (SKIPPED_PAUSED) without touching the WebSocket
+async fn pause_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    if !state.trading_paused.swap(true, Ordering::Relaxed) {
+        println!("⏸️  Trading paused via API");
+    }
+    Json(PauseResponse { trading_paused: true })
+}
+
+/// Resume endpoint
+/// Re-enables copy orders after /pause
+async fn resume_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    if state.trading_paused.swap(false, Ordering::Relaxed) {
+        println!("▶️  Trading resumed via API");
+    }
+    Json(PauseResponse { trading_paused: false })
+}
+
+/// Query parameters for /flatten
+#[derive(Debug, Deserialize)]
+struct FlattenQuery {
+    /// Must be true: flattening sells (or buys back) every position
+    #[serde(default)]
+    confirm: bool,
+}
+
+/// Flatten endpoint
+/// Closes every position through the bot's order engine; requires ?confirm=true
+async fn flatten_handler(
+    State(state): State<Arc<AppState>>,
+    params: Result<Query<FlattenQuery>, QueryRejection>,
+) -> axum::response::Response {
+    let error = |status: StatusCode, error: String| (status, Json(serde_json::json!({"error": error}))).into_response();
+    match params {
+        Ok(Query(params)) if params.confirm => {}
+        Ok(_) => return error(StatusCode::BAD_REQUEST, "This closes every position; repeat with ?confirm=true".to_string()),
+        Err(rejection) => return error(StatusCode::BAD_REQUEST, rejection.body_text()),
+    }
+    let Some(flatten_tx) = &state.flatten_tx else {
+        return error(StatusCode::SERVICE_UNAVAILABLE, "Flatten not available (order engine not running)".to_string());
+    };
+
+    let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+    if flatten_tx.send(reply_tx).is_err() {
+        return error(StatusCode::SERVICE_UNAVAILABLE, "Flatten task stopped".to_string());
+    }
+    println!("🧯 Flatten requested via API");
+    match reply_rx.await {
+        Ok(Ok(orders)) => Json(FlattenResponse { orders }).into_response(),
+        Ok(Err(e)) => error(StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to flatten: {}", e)),
+        Err(_) => error(StatusCode::SERVICE_UNAVAILABLE, "Flatten task stopped".to_string()),
+    }
+}
+
+/// Metrics endpoint
+/// Returns per-event processing times: lifetime counts plus rolling average and percentiles per phase
+async fn metrics_handler() -> impl IntoResponse {
+    Json(serde_json::json!({ "event_processing": event_metrics::snapshot() }))
+}
+
 /// Creates the API router with all endpoints
 fn create_router(state: Arc<AppState>) -> Router {
     Router::new()
@@ -344,7 +860,13 @@ fn create_router(state: Arc<AppState>) -> Router {
         .route("/positions", get(positions_handler))
         .route("/trades", get(trades_handler))
         .route("/stats", get(stats_handler))
+        .route("/traders", get(traders_handler))
+        .route("/pnl", get(pnl_handler))
         .route("/reload", post(reload_handler))
+        .route("/pause", post(pause_handler))
+        .route("/resume", post(resume_handler))
+        .route("/flatten", post(flatten_handler))
+        .route("/metrics", get(metrics_handler))
         .with_state(state)
 }
 
@@ -354,15 +876,23 @@ pub async fn start_api_server(
     config: ApiConfig,
     db_path: Option<String>,
 ) -> Result<tokio::task::JoinHandle<()>, Box<dyn std::error::Error + Send + Sync>> {
-    start_api_server_with_reload(config, db_path, None).await
+    start_api_server_with_reload(config, db_path, None, None, Arc::default(), None, None).await
 }
 
 /// Starts the HTTP API server with optional reload support
+/// `recent_trades` is the persistence worker's in-memory buffer of the last trades;
+/// `trading_paused` is the flag the order engine checks before submitting;
+/// `ws_health` (updated by the WS loop) makes /health a readiness probe;
+/// `flatten_tx` reaches the task that closes every position for /flatten
 /// Returns a JoinHandle that can be awaited for graceful shutdown
 pub async fn start_api_server_with_reload(
     config: ApiConfig,
     db_path: Option<String>,
+    recent_trades: Option<Arc<RecentTrades>>,
     traders: Option<ReloadableTraders>,
+    trading_paused: Arc<AtomicBool>,
+    ws_health: Option<Arc<WsHealth>>,
+    flatten_tx: Option<tokio::sync::mpsc::UnboundedSender<FlattenRequest>>,
 ) -> Result<tokio::task::JoinHandle<()>, Box<dyn std::error::Error + Send + Sync>> {
     if !config.enabled {
         return Err("API is disabled".into());
@@ -370,8 +900,16 @@ pub async fn start_api_server_with_reload(
 
     let state = Arc::new(AppState {
         db_path,
+        recent_trades,
         start_time: Instant::now(),
         traders,
+        trading_paused,
+        price_feed: config.live_prices.then(|| PriceFeed::start(MARKET_WS_URL)),
+        prices: Arc::default(),
+        price_ttl_secs: config.price_ttl_secs,
+        fee_per_trade_usd: config.fee_per_trade_usd,
+        ws_health,
+        flatten_tx,
     });
 
     let app = create_router(state);
@@ -389,12 +927,14 @@ pub async fn start_api_server_with_reload(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::persistence::TradeRecord;
 
     #[test]
     fn test_api_config_default() {
         let config = ApiConfig::default();
         assert!(!config.enabled, "API should be disabled by default");
         assert_eq!(config.port, 8080, "Default port should be 8080");
+        assert_eq!(config.price_ttl_secs, API_PRICE_TTL_SECS);
     }
 
     #[test]
@@ -402,6 +942,8 @@ mod tests {
         let response = HealthResponse {
             status: "ok".to_string(),
             uptime_seconds: 123,
+            trading_paused: false,
+            websocket: None,
         };
 
         assert_eq!(response.status, "ok");
@@ -413,6 +955,8 @@ mod tests {
         let response = HealthResponse {
             status: "ok".to_string(),
             uptime_seconds: 123,
+            trading_paused: false,
+            websocket: None,
         };
 
         let json = serde_json::to_string(&response).unwrap();
@@ -427,6 +971,7 @@ mod tests {
 
         assert_eq!(response.status, "ok");
         assert_eq!(response.uptime_seconds, 456);
+        assert!(!response.trading_paused);
     }
 
     #[tokio::test]
@@ -444,6 +989,9 @@ mod tests {
         let config = ApiConfig {
             enabled: true,
             port: 18080, // Use a different port for testing
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
         };
 
         let handle = start_api_server(config.clone(), None).await.unwrap();
@@ -474,6 +1022,9 @@ mod tests {
         let config = ApiConfig {
             enabled: true,
             port: 18081,
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
         };
 
         let handle = start_api_server(config.clone(), None).await.unwrap();
@@ -513,6 +1064,9 @@ mod tests {
         let config = ApiConfig {
             enabled: true,
             port: 18082,
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
         };
 
         let handle = start_api_server(config.clone(), None).await.unwrap();
@@ -532,8 +1086,6 @@ mod tests {
 
     // Helper to create a test database with sample data
     fn create_test_db_with_data() -> (tempfile::TempDir, String) {
-        use crate::persistence::TradeRecord;
-
         let temp_dir = tempfile::TempDir::new().unwrap();
         let db_path = temp_dir.path().join("test.db");
         let store = TradeStore::new(&db_path).unwrap();
@@ -559,6 +1111,8 @@ mod tests {
                 is_live: Some(false),
                 aggregation_count: if i == 2 { Some(2) } else { None },
                 aggregation_window_ms: if i == 2 { Some(500) } else { None },
+                order_id: None,
+                contributing_traders: None,
             };
             store.insert_trade(&record).unwrap();
         }
@@ -573,6 +1127,9 @@ mod tests {
         let config = ApiConfig {
             enabled: true,
             port: 18083,
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
         };
 
         let handle = start_api_server(config.clone(), Some(db_path)).await.unwrap();
@@ -587,17 +1144,79 @@ mod tests {
 
         assert_eq!(response.status(), 200);
 
-        let positions: Vec<PositionResponse> = response.json().await.unwrap();
-        assert!(!positions.is_empty(), "Should have at least one position");
+        // A bare array unless ?detail=true
+        let body: Vec<PositionResponse> = response.json().await.unwrap();
+        assert!(!body.is_empty(), "Should have at least one position");
+
+        let response = client
+            .get(format!("http://127.0.0.1:{}/positions?prices=false&detail=true", config.port))
+            .send()
+            .await
+            .unwrap();
+        let detail: PositionsResponse = response.json().await.unwrap();
+        assert_eq!(detail.positions.len(), body.len());
+
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_positions_endpoint_without_prices() {
+        let (_temp_dir, db_path) = create_test_db_with_data();
+
+        let config = ApiConfig {
+            enabled: true,
+            port: 18093,
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
+        };
+
+        let handle = start_api_server(config.clone(), Some(db_path)).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let client = reqwest::Client::new();
+        let response = client
+            .get(format!("http://127.0.0.1:{}/positions?prices=false&detail=true", config.port))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 200);
+
+        let body: PositionsResponse = response.json().await.unwrap();
+        assert!(!body.positions.is_empty());
+        assert_eq!(body.unrealized_pnl, 0.0);
+        for pos in &body.positions {
+            assert_eq!(pos.bid_price, None);
+            assert_eq!(pos.ask_price, None);
+            assert_eq!(pos.unrealized_pnl, None);
+        }
 
         handle.abort();
     }
 
+    #[test]
+    fn test_compute_positions_price_failure_degrades_to_null() {
+        let (_temp_dir, db_path) = create_test_db_with_data();
+
+        // Nothing listens here: every price fetch fails
+        let mut prices = PriceCache::with_host(30, "http://127.0.0.1:9");
+        let positions = compute_positions(&db_path, Some(&mut prices), 0.0).unwrap().positions;
+
+        assert!(!positions.is_empty());
+        assert!(positions[0].net_shares != 0.0);
+        assert_eq!(positions[0].bid_price, None);
+        assert_eq!(positions[0].unrealized_pnl, None);
+    }
+
     #[tokio::test]
     async fn test_positions_endpoint_without_database() {
         let config = ApiConfig {
             enabled: true,
             port: 18084,
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
         };
 
         let handle = start_api_server(config.clone(), None).await.unwrap();
@@ -622,6 +1241,9 @@ mod tests {
         let config = ApiConfig {
             enabled: true,
             port: 18085,
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
         };
 
         let handle = start_api_server(config.clone(), Some(db_path)).await.unwrap();
@@ -642,6 +1264,55 @@ mod tests {
         handle.abort();
     }
 
+    #[tokio::test]
+    async fn test_trades_endpoint_answers_from_recent_trades() {
+        let config = ApiConfig {
+            enabled: true,
+            port: 18101,
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
+        };
+        let recent_trades = Arc::new(RecentTrades::new(10, 0));
+        for i in 0..2 {
+            recent_trades.push(TradeRecord {
+                timestamp_ms: 1706000000000 + i,
+                block_number: 12345678,
+                tx_hash: format!("0xmem{}", i),
+                trader_address: "0x1234567890abcdef1234567890abcdef12345678".to_string(),
+                token_id: "token0".to_string(),
+                side: "BUY".to_string(),
+                whale_shares: 100.0,
+                whale_price: 0.50,
+                whale_usd: 50.0,
+                our_shares: Some(10.0),
+                our_price: Some(0.51),
+                our_usd: Some(5.1),
+                fill_pct: Some(100.0),
+                status: "SUCCESS".to_string(),
+                latency_ms: None,
+                is_live: Some(false),
+                aggregation_count: None,
+                aggregation_window_ms: None,
+                order_id: None,
+                contributing_traders: None,
+            });
+        }
+
+        // No database: only the buffer can answer
+        let handle = start_api_server_with_reload(config.clone(), None, Some(recent_trades), None, Arc::default(), None, None)
+            .await
+            .unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let response = reqwest::get(format!("http://127.0.0.1:{}/trades?limit=1", config.port)).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let trades: Vec<TradeResponse> = response.json().await.unwrap();
+        assert_eq!(trades.iter().map(|t| t.tx_hash.as_str()).collect::<Vec<_>>(), ["0xmem1"]);
+
+        handle.abort();
+    }
+
     #[tokio::test]
     async fn test_trades_endpoint_with_limit() {
         let (_temp_dir, db_path) = create_test_db_with_data();
@@ -649,6 +1320,9 @@ mod tests {
         let config = ApiConfig {
             enabled: true,
             port: 18086,
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
         };
 
         let handle = start_api_server(config.clone(), Some(db_path)).await.unwrap();
@@ -676,6 +1350,9 @@ mod tests {
         let config = ApiConfig {
             enabled: true,
             port: 18087,
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
         };
 
         let handle = start_api_server(config.clone(), Some(db_path)).await.unwrap();
@@ -700,6 +1377,78 @@ mod tests {
         handle.abort();
     }
 
+    #[tokio::test]
+    async fn test_trades_endpoint_filters() {
+        let (_temp_dir, db_path) = create_test_db_with_data();
+
+        let config = ApiConfig {
+            enabled: true,
+            port: 18095,
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
+        };
+
+        let handle = start_api_server(config.clone(), Some(db_path)).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let client = reqwest::Client::new();
+        let fetch = |query: &'static str| {
+            let client = client.clone();
+            async move {
+                let response = client
+                    .get(format!("http://127.0.0.1:{}/trades?{}", config.port, query))
+                    .send()
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), 200, "{}", query);
+                response.json::<Vec<TradeResponse>>().await.unwrap()
+            }
+        };
+
+        // Test data: three SUCCESS trades by one trader, on token0, token1, token0
+        assert_eq!(fetch("token=token0").await.len(), 2);
+        assert_eq!(fetch("trader=1234567890ABCDEF1234567890ABCDEF12345678").await.len(), 3);
+        assert_eq!(fetch("trader=0x0000000000000000000000000000000000000001").await.len(), 0);
+        assert_eq!(fetch("status=success").await.len(), 3);
+        assert_eq!(fetch("status=FAILED").await.len(), 0);
+        let combined = fetch("token=token0&status=SUCCESS&since=1706000000001&limit=5").await;
+        assert_eq!(combined.len(), 1);
+        assert_eq!(combined[0].tx_hash, "0xtx2");
+
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_trades_endpoint_rejects_malformed_params() {
+        let (_temp_dir, db_path) = create_test_db_with_data();
+
+        let config = ApiConfig {
+            enabled: true,
+            port: 18096,
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
+        };
+
+        let handle = start_api_server(config.clone(), Some(db_path)).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let client = reqwest::Client::new();
+        for query in ["since=yesterday", "limit=-1", "limit=ten", "trader=0x1234"] {
+            let response = client
+                .get(format!("http://127.0.0.1:{}/trades?{}", config.port, query))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 400, "{}", query);
+            let body: serde_json::Value = response.json().await.unwrap();
+            assert!(body["error"].is_string(), "{}: {}", query, body);
+        }
+
+        handle.abort();
+    }
+
     #[tokio::test]
     async fn test_stats_endpoint_returns_stats() {
         let (_temp_dir, db_path) = create_test_db_with_data();
@@ -707,6 +1456,9 @@ mod tests {
         let config = ApiConfig {
             enabled: true,
             port: 18088,
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
         };
 
         let handle = start_api_server(config.clone(), Some(db_path)).await.unwrap();
@@ -724,7 +1476,50 @@ mod tests {
         let stats: StatsResponse = response.json().await.unwrap();
         assert_eq!(stats.total_orders, 3);
         assert_eq!(stats.aggregated_orders, 1); // One trade has aggregation_count = 2
+        assert_eq!(stats.avg_window_ms, 500.0);
         assert!(stats.total_positions > 0);
+        assert!(!stats.trading_paused);
+
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_stats_endpoint_with_since_window() {
+        let (_temp_dir, db_path) = create_test_db_with_data();
+
+        let config = ApiConfig {
+            enabled: true,
+            port: 18097,
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
+        };
+
+        let handle = start_api_server(config.clone(), Some(db_path)).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let client = reqwest::Client::new();
+        let fetch = |query: &'static str| {
+            let client = client.clone();
+            async move {
+                client
+                    .get(format!("http://127.0.0.1:{}/stats?{}", config.port, query))
+                    .send()
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // Only the aggregated trade (timestamp ...002) is in the window
+        let stats: StatsResponse = fetch("since=1706000000002").await.json().await.unwrap();
+        assert_eq!(stats.total_orders, 1);
+        assert_eq!(stats.aggregated_orders, 1);
+
+        let stats: StatsResponse = fetch("since=1706000000003").await.json().await.unwrap();
+        assert_eq!(stats.total_orders, 0);
+        assert_eq!(stats.aggregated_orders, 0);
+
+        assert_eq!(fetch("since=today").await.status(), 400);
 
         handle.abort();
     }
@@ -734,6 +1529,9 @@ mod tests {
         let config = ApiConfig {
             enabled: true,
             port: 18089,
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
         };
 
         let handle = start_api_server(config.clone(), None).await.unwrap();
@@ -750,4 +1548,376 @@ mod tests {
 
         handle.abort();
     }
+
+    #[tokio::test]
+    async fn test_traders_endpoint_returns_realized_pnl() {
+        let (_temp_dir, db_path) = create_test_db_with_data();
+        let trader = "0x1234567890abcdef1234567890abcdef12345678";
+        let store = TradeStore::new(&db_path).unwrap();
+        store.upsert_trader_stats(trader, "whale", 3, 3, 0, 15.3, None, 0).unwrap();
+
+        // SELL on token0 closes the oldest BUY lot at a profit
+        store.insert_trade(&TradeRecord {
+            timestamp_ms: 1706000000010,
+            block_number: 12345679,
+            tx_hash: "0xtx_sell".to_string(),
+            trader_address: trader.to_string(),
+            token_id: "token0".to_string(),
+            side: "SELL".to_string(),
+            whale_shares: 100.0,
+            whale_price: 0.60,
+            whale_usd: 60.0,
+            our_shares: Some(10.0),
+            our_price: Some(0.61),
+            our_usd: Some(6.1),
+            fill_pct: Some(100.0),
+            status: "SUCCESS".to_string(),
+            latency_ms: Some(85),
+            is_live: Some(false),
+            aggregation_count: None,
+            aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
+        }).unwrap();
+        drop(store);
+
+        let config = ApiConfig {
+            enabled: true,
+            port: 18090,
+            live_prices: false,
+            fee_per_trade_usd: 0.0, // Fee-free: expected P&L is the price difference only
+            price_ttl_secs: API_PRICE_TTL_SECS,
+        };
+
+        let handle = start_api_server(config.clone(), Some(db_path)).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let client = reqwest::Client::new();
+        let response = client
+            .get(format!("http://127.0.0.1:{}/traders", config.port))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 200);
+
+        let traders: Vec<TraderResponse> = response.json().await.unwrap();
+        assert_eq!(traders.len(), 1);
+        assert_eq!(traders[0].label, "whale");
+        // token0: BUY 10 @ 0.51 twice, SELL 10 @ 0.61 closes the first lot (+1.00)
+        assert!((traders[0].realized_pnl - 1.0).abs() < 1e-9);
+        assert_eq!(traders[0].winning_trades, 1);
+        assert_eq!(traders[0].win_rate, Some(1.0));
+
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_pause_and_resume_toggle_shared_flag() {
+        let config = ApiConfig {
+            enabled: true,
+            port: 18091,
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
+        };
+        let paused = Arc::new(AtomicBool::new(false));
+
+        let handle = start_api_server_with_reload(config.clone(), None, None, None, Arc::clone(&paused), None, None)
+            .await
+            .unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let client = reqwest::Client::new();
+        let base = format!("http://127.0.0.1:{}", config.port);
+
+        let response = client.post(format!("{}/pause", base)).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        let body: PauseResponse = response.json().await.unwrap();
+        assert!(body.trading_paused);
+        assert!(paused.load(Ordering::Relaxed));
+
+        let health: HealthResponse = client.get(format!("{}/health", base)).send().await.unwrap().json().await.unwrap();
+        assert!(health.trading_paused);
+
+        let response = client.post(format!("{}/resume", base)).send().await.unwrap();
+        let body: PauseResponse = response.json().await.unwrap();
+        assert!(!body.trading_paused);
+        assert!(!paused.load(Ordering::Relaxed));
+
+        let health: HealthResponse = client.get(format!("{}/health", base)).send().await.unwrap().json().await.unwrap();
+        assert!(!health.trading_paused);
+
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_flatten_requires_confirmation() {
+        use crate::flatten::{FlattenResult, FlattenSide};
+
+        let config = ApiConfig {
+            enabled: true,
+            port: 18099,
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
+        };
+        let (flatten_tx, mut flatten_rx) = tokio::sync::mpsc::unbounded_channel::<FlattenRequest>();
+        // Stand-in for the bot's flatten task
+        tokio::spawn(async move {
+            while let Some(reply) = flatten_rx.recv().await {
+                let _ = reply.send(Ok(vec![FlattenResult {
+                    token_id: "tok".to_string(),
+                    side: FlattenSide::Sell,
+                    shares: 12.5,
+                    price: Some(0.4),
+                    status: "200 OK".to_string(),
+                }]));
+            }
+        });
+
+        let handle = start_api_server_with_reload(config.clone(), None, None, None, Arc::default(), None, Some(flatten_tx))
+            .await
+            .unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let client = reqwest::Client::new();
+        let url = format!("http://127.0.0.1:{}/flatten", config.port);
+        for unconfirmed in [url.clone(), format!("{}?confirm=false", url), format!("{}?confirm=yes", url)] {
+            let response = client.post(&unconfirmed).send().await.unwrap();
+            assert_eq!(response.status(), 400, "{}", unconfirmed);
+        }
+
+        let response = client.post(format!("{}?confirm=true", url)).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        let body: FlattenResponse = response.json().await.unwrap();
+        assert_eq!(body.orders.len(), 1);
+        assert_eq!((body.orders[0].side, body.orders[0].shares), (FlattenSide::Sell, 12.5));
+
+        handle.abort();
+
+        // Without a flatten task (no order engine) the endpoint is unavailable
+        let config = ApiConfig { port: 18100, ..config };
+        let handle = start_api_server_with_reload(config.clone(), None, None, None, Arc::default(), None, None).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        let response = client.post(format!("http://127.0.0.1:{}/flatten?confirm=true", config.port)).send().await.unwrap();
+        assert_eq!(response.status(), 503);
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_health_reports_degraded_websocket() {
+        let config = ApiConfig {
+            enabled: true,
+            port: 18094,
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
+        };
+        let ws_health = Arc::new(WsHealth::new(std::time::Duration::from_secs(60), Vec::new()));
+
+        let handle = start_api_server_with_reload(config.clone(), None, None, None, Arc::default(), Some(Arc::clone(&ws_health)), None)
+            .await
+            .unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let url = format!("http://127.0.0.1:{}/health", config.port);
+        let client = reqwest::Client::new();
+
+        // Nothing received and no subscription yet
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), 503);
+        let health: HealthResponse = response.json().await.unwrap();
+        assert_eq!(health.status, "degraded");
+        let ws = health.websocket.unwrap();
+        assert!(!ws.subscription_confirmed);
+        assert_eq!(ws.last_message_age_secs, None);
+        assert_eq!(ws.issues.len(), 2);
+
+        ws_health.record_message();
+        ws_health.record_subscription_confirmed();
+
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        let health: HealthResponse = response.json().await.unwrap();
+        assert_eq!(health.status, "ok");
+        let ws = health.websocket.unwrap();
+        assert!(ws.healthy && ws.subscription_confirmed);
+        assert_eq!(ws.last_message_age_secs, Some(0));
+        assert_eq!(ws.stale_after_secs, 60);
+
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_pnl_endpoint_filters_by_token() {
+        let (_temp_dir, db_path) = create_test_db_with_data();
+        let store = TradeStore::new(&db_path).unwrap();
+
+        // tokenX: fully closed round trip, +1.00 realized and no open position
+        for (i, (side, price)) in [("BUY", 0.40), ("SELL", 0.50)].into_iter().enumerate() {
+            store.insert_trade(&TradeRecord {
+                timestamp_ms: 1706000000100 + i as i64,
+                block_number: 12345680,
+                tx_hash: format!("0xtx_x{}", i),
+                trader_address: "0x1234567890abcdef1234567890abcdef12345678".to_string(),
+                token_id: "tokenX".to_string(),
+                side: side.to_string(),
+                whale_shares: 100.0,
+                whale_price: price,
+                whale_usd: 100.0 * price,
+                our_shares: Some(10.0),
+                our_price: Some(price),
+                our_usd: Some(10.0 * price),
+                fill_pct: Some(100.0),
+                status: "SUCCESS".to_string(),
+                latency_ms: Some(85),
+                is_live: Some(false),
+                aggregation_count: None,
+                aggregation_window_ms: None,
+                order_id: None,
+                contributing_traders: None,
+            }).unwrap();
+        }
+        drop(store);
+
+        let config = ApiConfig {
+            enabled: true,
+            port: 18092,
+            live_prices: false,
+            fee_per_trade_usd: 0.0, // Fee-free: expected P&L is the price difference only
+            price_ttl_secs: API_PRICE_TTL_SECS,
+        };
+
+        let handle = start_api_server(config.clone(), Some(db_path)).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let client = reqwest::Client::new();
+        let response = client
+            .get(format!("http://127.0.0.1:{}/pnl?token=tokenX", config.port))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 200);
+
+        let pnl: PnlResponse = response.json().await.unwrap();
+        assert_eq!(pnl.token.as_deref(), Some("tokenX"));
+        assert!((pnl.realized_pnl - 1.0).abs() < 1e-9);
+        assert_eq!(pnl.unrealized_pnl, 0.0);
+        assert!((pnl.total_pnl - 1.0).abs() < 1e-9);
+        assert_eq!(pnl.position_count, 0);
+        assert_eq!(pnl.priced_positions, 0);
+        assert!(pnl.positions.is_empty());
+        // Closed out, so listed with its realized P&L only
+        assert_eq!(pnl.closed.len(), 1);
+        assert_eq!(pnl.closed[0].token_id, "tokenX");
+        assert_eq!((pnl.closed[0].net_shares, pnl.closed[0].unrealized_pnl), (0.0, Some(0.0)));
+        assert!((pnl.closed[0].realized_pnl - 1.0).abs() < 1e-9);
+
+        handle.abort();
+    }
+
+    #[test]
+    fn test_compute_pnl_splits_open_and_closed_tokens() {
+        let (_temp_dir, db_path) = create_test_db_with_data();
+        let store = TradeStore::new(&db_path).unwrap();
+        // token0 (long 20 @ 0.51): sell 5 @ 0.61 for +0.50; tokenX: bought and sold out for -0.20
+        for (i, (token, side, shares, price)) in [
+            ("token0", "SELL", 5.0, 0.61),
+            ("tokenX", "BUY", 4.0, 0.30),
+            ("tokenX", "SELL", 4.0, 0.25),
+        ].into_iter().enumerate() {
+            store.insert_trade(&TradeRecord {
+                timestamp_ms: 1706000000100 + i as i64,
+                block_number: 12345680,
+                tx_hash: format!("0xtx_split{}", i),
+                trader_address: "0x1234567890abcdef1234567890abcdef12345678".to_string(),
+                token_id: token.to_string(),
+                side: side.to_string(),
+                whale_shares: 100.0,
+                whale_price: price,
+                whale_usd: 100.0 * price,
+                our_shares: Some(shares),
+                our_price: Some(price),
+                our_usd: Some(shares * price),
+                fill_pct: Some(100.0),
+                status: "SUCCESS".to_string(),
+                latency_ms: Some(85),
+                is_live: Some(false),
+                aggregation_count: None,
+                aggregation_window_ms: None,
+                order_id: None,
+                contributing_traders: None,
+            }).unwrap();
+        }
+        drop(store);
+
+        // Nothing listens here: open positions stay unpriced
+        let mut prices = PriceCache::with_host(30, "http://127.0.0.1:9");
+        let pnl = compute_pnl(&db_path, None, &mut prices, 0.0).unwrap();
+
+        // token1 is short from an unmatched SELL: open, nothing realized
+        let open: HashMap<&str, &TokenPnlResponse> = pnl.positions.iter().map(|p| (p.token_id.as_str(), p)).collect();
+        assert_eq!(open.len(), 2);
+        assert!((open["token0"].realized_pnl - 0.5).abs() < 1e-9);
+        assert_eq!((open["token0"].unrealized_pnl, open["token0"].total_pnl), (None, None));
+        assert_eq!(open["token1"].realized_pnl, 0.0);
+
+        assert_eq!(pnl.closed.len(), 1);
+        let closed = &pnl.closed[0];
+        assert_eq!((closed.token_id.as_str(), closed.net_shares), ("tokenX", 0.0));
+        assert!((closed.realized_pnl + 0.2).abs() < 1e-9);
+        assert_eq!(closed.unrealized_pnl, Some(0.0));
+        assert_eq!(closed.total_pnl, Some(closed.realized_pnl));
+
+        // The aggregate is the sum of both sections
+        assert!((pnl.realized_pnl - 0.3).abs() < 1e-9);
+        assert_eq!((pnl.unrealized_pnl, pnl.position_count, pnl.priced_positions), (0.0, 2, 0));
+
+        // /positions reports the same per-token figures, closed tokens and totals
+        let positions = compute_positions(&db_path, None, 0.0).unwrap();
+        assert_eq!(positions.positions.len(), 2);
+        let token0 = positions.positions.iter().find(|p| p.token_id == "token0").unwrap();
+        assert!((token0.realized_pnl - 0.5).abs() < 1e-9);
+        assert_eq!(token0.total_pnl, None);
+        assert_eq!(positions.closed, pnl.closed);
+        assert_eq!(
+            (positions.realized_pnl, positions.unrealized_pnl, positions.total_pnl),
+            (pnl.realized_pnl, pnl.unrealized_pnl, pnl.total_pnl)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_reports_event_processing() {
+        let config = ApiConfig {
+            enabled: true,
+            port: 18098,
+            live_prices: false,
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            price_ttl_secs: API_PRICE_TTL_SECS,
+        };
+
+        let handle = start_api_server(config.clone(), None).await.unwrap();
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let client = reqwest::Client::new();
+        let response = client
+            .get(format!("http://127.0.0.1:{}/metrics", config.port))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 200);
+
+        let metrics: serde_json::Value = response.json().await.unwrap();
+        let events = &metrics["event_processing"];
+        assert!(events["events"].is_u64());
+        assert!(events["total"]["p95_ms"].is_number());
+        for phase in ["lookup", "submit", "book", "db"] {
+            assert!(events["phases"][phase]["avg_ms"].is_number(), "{}", phase);
+        }
+
+        handle.abort();
+    }
 }
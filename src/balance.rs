@@ -0,0 +1,169 @@
+//! USDC balance check for BUY orders
+//!
+//! Caches the funder wallet's on-chain USDC balance for a short TTL so the order
+//! path can skip BUYs it cannot pay for instead of collecting exchange rejections.
+//! A failed balance fetch never blocks trading - the exchange remains the final check.
+
+use anyhow::Result;
+use reqwest::blocking::Client;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::portfolio::fetch_usdc_balance;
+
+/// How long a fetched balance is trusted before refetching
+pub const BALANCE_CACHE_TTL: Duration = Duration::from_secs(15);
+
+/// Source of the wallet's USDC balance (mockable in tests)
+pub trait UsdcBalanceSource: Send + Sync {
+    fn usdc_balance(&self) -> Result<f64>;
+}
+
+/// Reads USDC.e + native USDC via Polygon RPC
+pub struct RpcUsdcBalance {
+    client: Client,
+    wallet: String,
+}
+
+impl RpcUsdcBalance {
+    /// Must not be called from within a tokio runtime (creates a blocking client)
+    pub fn new(wallet: &str) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .expect("Failed to create HTTP client");
+        Self { client, wallet: wallet.to_string() }
+    }
+}
+
+impl UsdcBalanceSource for RpcUsdcBalance {
+    fn usdc_balance(&self) -> Result<f64> {
+        fetch_usdc_balance(&self.client, &self.wallet)
+    }
+}
+
+/// Cached USDC balance with a reserve that BUYs may not dip into
+pub struct BalanceTracker {
+    source: Box<dyn UsdcBalanceSource>,
+    /// USD kept untouched (USDC_RESERVE)
+    reserve_usd: f64,
+    ttl: Duration,
+    cache: Mutex<Option<(f64, Instant)>>,
+}
+
+impl BalanceTracker {
+    pub fn new(source: Box<dyn UsdcBalanceSource>, reserve_usd: f64, ttl: Duration) -> Self {
+        Self {
+            source,
+            reserve_usd,
+            ttl,
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// USDC balance, refetched when the cached value is older than the TTL
+    pub fn balance(&self) -> Result<f64> {
+        let mut cache = self.cache.lock().unwrap();
+        if let Some((balance, fetched_at)) = *cache
+            && fetched_at.elapsed() < self.ttl
+        {
+            return Ok(balance);
+        }
+        let balance = self.source.usdc_balance()?;
+        *cache = Some((balance, Instant::now()));
+        Ok(balance)
+    }
+
+    /// Balance minus the reserve (may be negative)
+    pub fn available_usd(&self) -> Result<f64> {
+        Ok(self.balance()? - self.reserve_usd)
+    }
+
+    /// Available USD if it does not cover `cost_usd`; None if affordable or the balance is unknown
+    pub fn insufficient(&self, cost_usd: f64) -> Option<f64> {
+        match self.available_usd() {
+            Ok(available) if cost_usd > available => Some(available),
+            Ok(_) => None,
+            Err(e) => {
+                eprintln!("⚠️ USDC balance check failed (allowing order): {}", e);
+                None
+            }
+        }
+    }
+
+    /// Deduct a submitted BUY from the cached balance so orders within the TTL see it
+    pub fn record_spend(&self, usd: f64) {
+        if let Some((balance, _)) = self.cache.lock().unwrap().as_mut() {
+            *balance -= usd;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Fixed balance that counts fetches
+    struct MockBalance {
+        balance: Option<f64>,
+        fetches: Arc<AtomicUsize>,
+    }
+
+    impl UsdcBalanceSource for MockBalance {
+        fn usdc_balance(&self) -> Result<f64> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            self.balance.ok_or_else(|| anyhow::anyhow!("rpc down"))
+        }
+    }
+
+    fn tracker(balance: Option<f64>, reserve_usd: f64) -> (BalanceTracker, Arc<AtomicUsize>) {
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let source = MockBalance { balance, fetches: fetches.clone() };
+        (BalanceTracker::new(Box::new(source), reserve_usd, BALANCE_CACHE_TTL), fetches)
+    }
+
+    #[test]
+    fn test_insufficient_at_above_below_cost() {
+        // $100 balance, $10 reserve -> $90 available
+        let (t, _) = tracker(Some(100.0), 10.0);
+        assert_eq!(t.insufficient(90.0), None, "exactly available is allowed");
+        assert_eq!(t.insufficient(50.0), None);
+        assert_eq!(t.insufficient(90.01), Some(90.0));
+    }
+
+    #[test]
+    fn test_reserve_above_balance_blocks_everything() {
+        let (t, _) = tracker(Some(5.0), 10.0);
+        assert_eq!(t.insufficient(0.5), Some(-5.0));
+    }
+
+    #[test]
+    fn test_fetch_failure_allows_order() {
+        let (t, _) = tracker(None, 0.0);
+        assert_eq!(t.insufficient(1_000_000.0), None);
+    }
+
+    #[test]
+    fn test_balance_cached_within_ttl() {
+        let (t, fetches) = tracker(Some(100.0), 0.0);
+        t.balance().unwrap();
+        t.balance().unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        let source = MockBalance { balance: Some(100.0), fetches: fetches.clone() };
+        let expiring = BalanceTracker::new(Box::new(source), 0.0, Duration::ZERO);
+        expiring.balance().unwrap();
+        expiring.balance().unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_record_spend_reduces_cached_balance() {
+        let (t, _) = tracker(Some(100.0), 0.0);
+        assert_eq!(t.insufficient(80.0), None);
+        t.record_spend(80.0);
+        assert_eq!(t.insufficient(30.0), Some(20.0));
+    }
+}
@@ -0,0 +1,449 @@
+// backtest.rs - Replay recorded whale trades through the sizing and risk rules
+//
+// Reads whale trades from the trades table, sizes each one again with
+// get_tier_params + calculate_safe_size under the given parameters, runs it past
+// the risk guard (at the recorded timestamps) and compares the simulated outcome
+// with what the bot actually did. Nothing is submitted and the database is only read.
+//
+// Simulated orders are assumed to fill in full at their limit price. P&L is
+// estimated by marking open positions at each token's last recorded whale price.
+// Legacy matches_optimized.csv logs: load them with import_csv first.
+//
+// --from-raw-log replays a RAW_EVENT_LOG file instead: each line is decoded with the
+// bot's own parse_event against the configured traders (TRADER_ADDRESSES, traders.json,
+// ...), so the whale trades are exactly what the bot saw. There is no recorded outcome.
+//
+// Usage:
+//   cargo run --bin backtest                                  # Current defaults
+//   cargo run --bin backtest -- --db test.db --limit 5000
+//   cargo run --bin backtest -- --scaling 0.1 --min-shares 500 --max-bet 25
+//   cargo run --bin backtest -- --from-raw-log raw_events.jsonl
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use pm_whale_follower::config::traders::TradersConfig;
+use pm_whale_follower::event_parser::parse_event;
+use pm_whale_follower::models::ParsedEvent;
+use pm_whale_follower::persistence::{TradeRecord, TradeStore};
+use pm_whale_follower::raw_event_log;
+use pm_whale_follower::risk_guard::{RiskGuard, RiskGuardConfig, SafetyDecision};
+use pm_whale_follower::settings::{
+    calculate_safe_size, default_scaling_ratio, get_tier_params, SizeFloor, SizingInput, MIN_WHALE_SHARES_TO_COPY,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+#[derive(Parser)]
+#[command(name = "backtest")]
+#[command(about = "Replay recorded whale trades through sizing and risk rules")]
+struct Args {
+    /// Database path
+    #[arg(long, default_value = "trades.db")]
+    db: String,
+
+    /// Most recent whale trades to replay
+    #[arg(long, default_value = "10000")]
+    limit: usize,
+
+    /// Scaling ratio applied to whale size (default: SCALING_RATIO)
+    #[arg(long)]
+    scaling: Option<f64>,
+
+    /// Minimum whale shares to copy
+    #[arg(long, default_value_t = MIN_WHALE_SHARES_TO_COPY)]
+    min_shares: f64,
+
+    /// Maximum USD per order
+    #[arg(long)]
+    max_bet: Option<f64>,
+
+    /// Replay a RAW_EVENT_LOG file through parse_event instead of reading the database
+    #[arg(long, value_name = "FILE")]
+    from_raw_log: Option<String>,
+}
+
+/// Sizing parameters under test
+struct BacktestParams {
+    scaling_ratio: f64,
+    min_whale_shares: f64,
+    max_bet_usd: Option<f64>,
+    /// Smallest order placed (MIN_SHARE_COUNT / MIN_CASH_VALUE env)
+    size_floor: SizeFloor,
+}
+
+/// A simulated order, assumed filled in full at its limit price
+struct SimFill {
+    shares: f64,
+    price: f64,
+    /// Risk guard asked for a live book check (no book in history, so it is allowed)
+    needed_book: bool,
+}
+
+/// Cash and share positions built up from fills
+#[derive(Default)]
+struct Ledger {
+    cash: f64,
+    shares: HashMap<String, f64>,
+    fills: usize,
+    volume_usd: f64,
+}
+
+impl Ledger {
+    fn held(&self, token_id: &str) -> f64 {
+        self.shares.get(token_id).copied().unwrap_or(0.0)
+    }
+
+    fn apply(&mut self, token_id: &str, is_buy: bool, shares: f64, price: f64) {
+        let signed = if is_buy { shares } else { -shares };
+        *self.shares.entry(token_id.to_string()).or_insert(0.0) += signed;
+        self.cash -= signed * price;
+        self.fills += 1;
+        self.volume_usd += shares * price;
+    }
+
+    /// Cash plus open positions valued at `marks` (tokens without a mark count as zero)
+    fn pnl(&self, marks: &HashMap<String, f64>) -> f64 {
+        let open_value: f64 = self.shares.iter()
+            .map(|(token, shares)| shares * marks.get(token).copied().unwrap_or(0.0))
+            .sum();
+        self.cash + open_value
+    }
+}
+
+#[derive(Default)]
+struct Report {
+    trades: usize,
+    simulated: Ledger,
+    recorded: Ledger,
+    /// Simulated skips by reason
+    skips: BTreeMap<String, usize>,
+    /// Recorded trades by status
+    recorded_statuses: BTreeMap<String, usize>,
+    /// Orders the risk guard would have checked against the live book (assumed deep enough)
+    book_checks: usize,
+    /// Last recorded whale price per token
+    marks: HashMap<String, f64>,
+}
+
+/// Replay `trades` (oldest first) and compare the simulated fills with the recorded ones
+fn simulate(trades: &[TradeRecord], params: &BacktestParams, guard: &mut RiskGuard) -> Report {
+    let mut report = Report { trades: trades.len(), ..Default::default() };
+    let start = Instant::now();
+    let first_ts = trades.first().map(|t| t.timestamp_ms).unwrap_or(0);
+
+    for trade in trades {
+        let is_buy = trade.side == "BUY";
+        report.marks.insert(trade.token_id.clone(), trade.whale_price);
+        *report.recorded_statuses.entry(trade.status.clone()).or_insert(0) += 1;
+        if let (Some(shares), Some(price)) = (trade.our_shares, trade.our_price)
+            && shares > 0.0
+        {
+            report.recorded.apply(&trade.token_id, is_buy, shares, price);
+        }
+
+        let at = start + Duration::from_millis((trade.timestamp_ms - first_ts).max(0) as u64);
+        match simulate_trade(trade, is_buy, params, guard, at, &report.simulated) {
+            Ok(fill) => {
+                report.book_checks += usize::from(fill.needed_book);
+                report.simulated.apply(&trade.token_id, is_buy, fill.shares, fill.price);
+            }
+            Err(reason) => *report.skips.entry(reason).or_insert(0) += 1,
+        }
+    }
+    report
+}
+
+/// Size one whale trade; Err is the skip reason
+fn simulate_trade(
+    trade: &TradeRecord,
+    is_buy: bool,
+    params: &BacktestParams,
+    guard: &mut RiskGuard,
+    at: Instant,
+    ledger: &Ledger,
+) -> std::result::Result<SimFill, String> {
+    let held = ledger.held(&trade.token_id);
+    if !is_buy && held <= 0.0 {
+        return Err("SKIPPED_NO_POSITION".into());
+    }
+    if trade.whale_shares < params.min_whale_shares {
+        return Err("SKIPPED_SMALL".into());
+    }
+
+    let eval = guard.check_fast_at(&trade.token_id, trade.whale_shares, at);
+    if eval.decision == SafetyDecision::Block {
+        return Err(format!("RISK_BLOCKED:{}", eval.reason.as_str()));
+    }
+
+    let (buffer, _, size_multiplier) = get_tier_params(trade.whale_shares, is_buy, &trade.token_id);
+    let price = if is_buy {
+        (trade.whale_price + buffer).min(0.99)
+    } else {
+        (trade.whale_price - buffer).max(0.01)
+    };
+    let max_bet_shares = params.max_bet_usd
+        .filter(|usd| *usd > 0.0)
+        .map(|usd| usd / price.max(0.01));
+
+    let (shares, _) = calculate_safe_size(SizingInput {
+        whale_shares: trade.whale_shares,
+        scaling_ratio: params.scaling_ratio,
+        price,
+        size_multiplier,
+        max_bet_shares,
+        trader_max_shares: None,
+        target: None,
+        floor: params.size_floor,
+    });
+    if shares <= 0.0 {
+        return Err("SKIPPED_PROB".into());
+    }
+    Ok(SimFill {
+        shares: if is_buy { shares } else { shares.min(held) },
+        price,
+        needed_book: eval.decision == SafetyDecision::FetchBook,
+    })
+}
+
+/// Status of trades loaded from a raw event log (nothing was recorded for them)
+const RAW_EVENT_STATUS: &str = "RAW_EVENT";
+
+/// Whale trade as the bot would have recorded it for `evt`
+fn raw_event_trade(evt: &ParsedEvent, received_ms: i64) -> TradeRecord {
+    TradeRecord {
+        timestamp_ms: received_ms,
+        block_number: evt.block_number,
+        tx_hash: evt.tx_hash.clone(),
+        trader_address: evt.trader_address.clone(),
+        token_id: evt.order.clob_token_id.to_string(),
+        side: if evt.order.order_type.starts_with("BUY") { "BUY".to_string() } else { "SELL".to_string() },
+        whale_shares: evt.order.shares,
+        whale_price: evt.order.price_per_share,
+        whale_usd: evt.order.usd_value,
+        our_shares: None,
+        our_price: None,
+        our_usd: None,
+        fill_pct: None,
+        status: RAW_EVENT_STATUS.to_string(),
+        latency_ms: None,
+        is_live: None,
+        aggregation_count: None,
+        aggregation_window_ms: None,
+        order_id: None,
+        contributing_traders: None,
+    }
+}
+
+/// Decode a raw event log into whale trades (oldest first), printing how many lines were used
+fn load_raw_log(path: &str, traders: &TradersConfig) -> Result<Vec<TradeRecord>> {
+    let (events, malformed) = raw_event_log::read(path)?;
+    let trades: Vec<TradeRecord> = events.iter()
+        .filter_map(|raw| parse_event(raw.to_message(), Some(traders)).map(|evt| raw_event_trade(&evt, raw.received_ms)))
+        .collect();
+    println!(
+        "Raw log {}: {} events, {} whale fills decoded, {} malformed lines",
+        path, events.len(), trades.len(), malformed,
+    );
+    Ok(trades)
+}
+
+fn print_report(report: &Report, params: &BacktestParams) {
+    println!("Backtest: {} whale trades", report.trades);
+    println!(
+        "Params:   scaling {:.4}, min whale shares {:.0}, max bet {}",
+        params.scaling_ratio,
+        params.min_whale_shares,
+        params.max_bet_usd.map_or("none".to_string(), |usd| format!("${:.2}", usd)),
+    );
+    println!();
+    println!("{:<20} {:>12} {:>12}", "", "Simulated", "Recorded");
+    println!("{:<20} {:>12} {:>12}", "Fills", report.simulated.fills, report.recorded.fills);
+    println!(
+        "{:<20} {:>12.2} {:>12.2}",
+        "Volume (USD)", report.simulated.volume_usd, report.recorded.volume_usd,
+    );
+    println!(
+        "{:<20} {:>12.2} {:>12.2}",
+        "Est. P&L (USD)", report.simulated.pnl(&report.marks), report.recorded.pnl(&report.marks),
+    );
+
+    println!("\nSimulated skips:");
+    for (reason, count) in &report.skips {
+        println!("  {:<30} {:>6}", reason, count);
+    }
+    println!("\nRecorded statuses:");
+    for (status, count) in &report.recorded_statuses {
+        println!("  {:<30} {:>6}", status, count);
+    }
+    if report.book_checks > 0 {
+        println!(
+            "\n{} orders would have needed a live book check (not in history, assumed deep enough)",
+            report.book_checks,
+        );
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let trades = if let Some(raw_log) = &args.from_raw_log {
+        dotenvy::dotenv().ok();
+        let traders = TradersConfig::load().map_err(anyhow::Error::msg)?;
+        load_raw_log(raw_log, &traders)?
+    } else {
+        if !Path::new(&args.db).exists() {
+            bail!("Database not found: {}", args.db);
+        }
+        let store = TradeStore::new(&args.db)?;
+        let mut trades = store.get_recent_trades(args.limit)?;
+        trades.reverse();
+        trades
+    };
+
+    let params = BacktestParams {
+        scaling_ratio: args.scaling.unwrap_or_else(default_scaling_ratio),
+        min_whale_shares: args.min_shares,
+        max_bet_usd: args.max_bet,
+        size_floor: SizeFloor::from_env()?,
+    };
+    let mut guard = RiskGuard::new(RiskGuardConfig::default());
+    let report = simulate(&trades, &params, &mut guard);
+    print_report(&report, &params);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn whale_trade(ts_secs: i64, token: &str, side: &str, shares: f64, price: f64) -> TradeRecord {
+        TradeRecord {
+            timestamp_ms: ts_secs * 1000,
+            block_number: 0,
+            tx_hash: format!("0x{}", ts_secs),
+            trader_address: "0xwhale".to_string(),
+            token_id: token.to_string(),
+            side: side.to_string(),
+            whale_shares: shares,
+            whale_price: price,
+            whale_usd: shares * price,
+            our_shares: None,
+            our_price: None,
+            our_usd: None,
+            fill_pct: None,
+            status: "SKIPPED".to_string(),
+            latency_ms: None,
+            is_live: Some(false),
+            aggregation_count: None,
+            aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
+        }
+    }
+
+    fn params(scaling_ratio: f64, min_whale_shares: f64, max_bet_usd: Option<f64>) -> BacktestParams {
+        BacktestParams { scaling_ratio, min_whale_shares, max_bet_usd, size_floor: SizeFloor::default() }
+    }
+
+    fn run(trades: &[TradeRecord], params: &BacktestParams) -> Report {
+        simulate(trades, params, &mut RiskGuard::new(RiskGuardConfig::default()))
+    }
+
+    #[test]
+    fn test_args_defaults() {
+        let args = Args::parse_from(["backtest"]);
+        assert_eq!(args.db, "trades.db");
+        assert_eq!(args.min_shares, MIN_WHALE_SHARES_TO_COPY);
+        assert!(args.scaling.is_none());
+        assert!(args.max_bet.is_none());
+        assert!(args.from_raw_log.is_none());
+    }
+
+    #[test]
+    fn test_raw_log_replays_through_parse_event() {
+        use pm_whale_follower::config::traders::TraderConfig;
+        use pm_whale_follower::raw_event_log::{RawEvent, RawEventLog};
+        use pm_whale_follower::settings::ORDERS_FILLED_EVENT_SIGNATURE;
+
+        // BUY of 500 shares for $250 by 0xdef4..., and the same fill from an untracked trader
+        let notification = |trader: &str| serde_json::json!({
+            "params": { "result": {
+                "topics": [ORDERS_FILLED_EVENT_SIGNATURE, format!("0x{:064x}", 0), format!("0x000000000000000000000000{}", trader)],
+                "data": format!("0x{:064x}{:064x}{:064x}{:064x}", 0, 0x123456, 250_000_000u64, 500_000_000u64),
+                "blockNumber": "0x1234",
+                "transactionHash": "0xabcdef",
+            }}
+        }).to_string();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("raw.jsonl");
+        let log = RawEventLog::new(&path);
+        log.append(&RawEvent::from_message(&notification("def456def456789012345678901234567890def4"), 2_000).unwrap()).unwrap();
+        log.append(&RawEvent::from_message(&notification("0000000000000000000000000000000000000bad"), 1_000).unwrap()).unwrap();
+
+        let traders = TradersConfig::new(vec![
+            TraderConfig::new("0xdef456def456789012345678901234567890def4", "Whale").unwrap(),
+        ]);
+        let trades = load_raw_log(path.to_str().unwrap(), &traders).unwrap();
+        assert_eq!(trades.len(), 1);
+        let trade = &trades[0];
+        assert_eq!((trade.side.as_str(), trade.token_id.as_str()), ("BUY", "1193046"));
+        assert_eq!((trade.whale_shares, trade.whale_price), (500.0, 0.5));
+        assert_eq!((trade.block_number, trade.timestamp_ms), (0x1234, 2_000));
+
+        let report = run(&trades, &params(0.1, 10.0, None));
+        assert_eq!(report.simulated.fills, 1);
+        assert_eq!(report.recorded_statuses.get(RAW_EVENT_STATUS), Some(&1));
+    }
+
+    #[test]
+    fn test_min_shares_and_sell_without_position_skip() {
+        let trades = vec![
+            whale_trade(0, "tok", "SELL", 1000.0, 0.5),
+            whale_trade(1, "tok", "BUY", 400.0, 0.5),
+        ];
+        let report = run(&trades, &params(0.1, 500.0, None));
+        assert_eq!(report.simulated.fills, 0);
+        assert_eq!(report.skips.get("SKIPPED_NO_POSITION"), Some(&1));
+        assert_eq!(report.skips.get("SKIPPED_SMALL"), Some(&1));
+    }
+
+    #[test]
+    fn test_max_bet_caps_order_usd() {
+        // 1500 shares: 1000+ tier (no buffer, 1.0x) -> 150 shares @ 0.50 = $75 uncapped
+        let trades = vec![whale_trade(0, "tok", "BUY", 1500.0, 0.5)];
+        let uncapped = run(&trades, &params(0.1, 10.0, None));
+        assert!((uncapped.simulated.volume_usd - 75.0).abs() < 1e-9);
+
+        let capped = run(&trades, &params(0.1, 10.0, Some(20.0)));
+        assert!((capped.simulated.volume_usd - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_pnl_marks_at_last_whale_price() {
+        let mut buy = whale_trade(0, "tok", "BUY", 1500.0, 0.5);
+        buy.our_shares = Some(100.0);
+        buy.our_price = Some(0.5);
+        buy.status = "SUCCESS".to_string();
+        let trades = vec![buy, whale_trade(60, "tok", "BUY", 1500.0, 0.7)];
+        let report = run(&trades, &params(0.1, 10.0, None));
+
+        // Recorded: 100 @ 0.50 marked at 0.70
+        assert_eq!(report.recorded.fills, 1);
+        assert!((report.recorded.pnl(&report.marks) - 20.0).abs() < 1e-9);
+        // Simulated: 150 @ 0.50 + 150 @ 0.70, marked at 0.70
+        assert_eq!(report.simulated.fills, 2);
+        assert!((report.simulated.pnl(&report.marks) - 30.0).abs() < 1e-9);
+        assert_eq!(report.recorded_statuses.get("SUCCESS"), Some(&1));
+    }
+
+    #[test]
+    fn test_risk_guard_uses_recorded_timestamps() {
+        // Five large trades: within the sequence window they need a book check, spread out they don't
+        let burst: Vec<_> = (0..5).map(|i| whale_trade(i, "tok", "BUY", 2500.0, 0.5)).collect();
+        assert_eq!(run(&burst, &params(0.1, 10.0, None)).book_checks, 1);
+
+        let spread: Vec<_> = (0..5).map(|i| whale_trade(i * 120, "tok", "BUY", 2500.0, 0.5)).collect();
+        assert_eq!(run(&spread, &params(0.1, 10.0, None)).book_checks, 0);
+    }
+}
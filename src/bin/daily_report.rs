@@ -0,0 +1,447 @@
+// daily_report.rs - Summary of one UTC day of trading from the trades database
+//
+// Usage:
+//   cargo run --bin daily_report                              # Today (UTC)
+//   cargo run --bin daily_report -- --date 2024-06-01         # Specific day
+//   cargo run --bin daily_report -- --db test.db --json       # JSON output
+
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, Utc};
+use clap::Parser;
+use pm_whale_follower::persistence::{TradeRecord, TradeStore};
+use pm_whale_follower::pnl::{realized_pnl_by_token, realized_pnl_by_trader};
+use pm_whale_follower::settings::fee_per_trade_usd;
+use std::collections::{BTreeMap, HashMap};
+
+/// Number of markets listed in the top-markets section
+const TOP_MARKETS: usize = 5;
+
+#[derive(Parser)]
+#[command(name = "daily_report")]
+#[command(about = "Daily summary of trades, volume and realized P&L")]
+struct Args {
+    /// Database path
+    #[arg(long, default_value = "trades.db")]
+    db: String,
+
+    /// UTC day to report (YYYY-MM-DD), defaults to today
+    #[arg(long)]
+    date: Option<NaiveDate>,
+
+    /// Output as JSON instead of a table
+    #[arg(long)]
+    json: bool,
+}
+
+/// Per-trader totals for the day
+#[derive(Debug, Clone, PartialEq)]
+struct TraderDay {
+    address: String,
+    label: String,
+    trades: u32,
+    copied_usd: f64,
+    realized_pnl: f64,
+}
+
+/// Per-market totals for the day
+#[derive(Debug, Clone, PartialEq)]
+struct MarketDay {
+    token_id: String,
+    trades: u32,
+    copied_usd: f64,
+}
+
+/// The SHADOW_MODE strategy's orders for the day, each treated as filled at its limit price
+#[derive(Debug, Clone, PartialEq)]
+struct ShadowDay {
+    orders: u32,
+    usd: f64,
+    realized_pnl: f64,
+}
+
+/// Everything the report prints
+#[derive(Debug, Clone, PartialEq)]
+struct DailyReport {
+    date: NaiveDate,
+    total_trades: u32,
+    by_status: BTreeMap<String, u32>,
+    buys: u32,
+    sells: u32,
+    copied_usd: f64,
+    realized_pnl: f64,
+    traders: Vec<TraderDay>,
+    top_markets: Vec<MarketDay>,
+    /// None when the shadow strategy placed nothing that day
+    shadow: Option<ShadowDay>,
+}
+
+/// Millisecond bounds of a UTC day: start inclusive, end (next midnight) exclusive
+fn day_range_ms(date: NaiveDate) -> (i64, i64) {
+    let start = date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp_millis();
+    (start, start + 24 * 60 * 60 * 1000)
+}
+
+/// Our filled USD for a trade (0 when nothing was copied)
+fn copied_usd(trade: &TradeRecord) -> f64 {
+    match trade.our_shares {
+        Some(shares) if shares > 0.0 => trade.our_usd.unwrap_or(0.0),
+        _ => 0.0,
+    }
+}
+
+/// Build the report for one day
+///
+/// Realized P&L for the day is the FIFO total over fills before the day ends minus the
+/// total over fills before it starts, so SELLs today are matched against BUYs from any
+/// earlier day. `fee_per_trade` USD is charged per fill.
+fn build_report(store: &TradeStore, date: NaiveDate, fee_per_trade: f64) -> Result<DailyReport> {
+    let (start_ms, end_ms) = day_range_ms(date);
+    let trades = store.get_trades_between(start_ms, end_ms)?;
+
+    let fills_before_start = store.get_fills_before(start_ms)?;
+    let fills_before_end = store.get_fills_before(end_ms)?;
+
+    let total_pnl = |fills| -> f64 {
+        realized_pnl_by_token(fills, fee_per_trade).values().map(|p| p.realized_pnl).sum()
+    };
+    let realized_pnl = total_pnl(&fills_before_end) - total_pnl(&fills_before_start);
+
+    // Same FIFO matching over the shadow strategy's own fills
+    let shadow_before_start = store.get_shadow_fills_before(start_ms)?;
+    let shadow_before_end = store.get_shadow_fills_before(end_ms)?;
+    let shadow_today = &shadow_before_end[shadow_before_start.len()..];
+    let shadow = (!shadow_today.is_empty()).then(|| ShadowDay {
+        orders: shadow_today.len() as u32,
+        usd: shadow_today.iter().map(|f| f.shares * f.price).sum(),
+        realized_pnl: total_pnl(&shadow_before_end) - total_pnl(&shadow_before_start),
+    });
+
+    let pnl_before = realized_pnl_by_trader(&fills_before_start, fee_per_trade);
+    let pnl_after = realized_pnl_by_trader(&fills_before_end, fee_per_trade);
+
+    let labels: HashMap<String, String> = store
+        .get_all_trader_stats()?
+        .into_iter()
+        .map(|(address, label, ..)| (address, label))
+        .collect();
+
+    let mut by_status: BTreeMap<String, u32> = BTreeMap::new();
+    let mut traders: BTreeMap<String, TraderDay> = BTreeMap::new();
+    let mut markets: HashMap<String, MarketDay> = HashMap::new();
+    let (mut buys, mut sells) = (0u32, 0u32);
+    let mut total_copied = 0.0;
+
+    for trade in &trades {
+        *by_status.entry(trade.status.clone()).or_default() += 1;
+        match trade.side.as_str() {
+            "BUY" => buys += 1,
+            "SELL" => sells += 1,
+            _ => {}
+        }
+
+        let usd = copied_usd(trade);
+        total_copied += usd;
+
+        let trader = traders.entry(trade.trader_address.clone()).or_insert_with(|| TraderDay {
+            address: trade.trader_address.clone(),
+            label: labels.get(&trade.trader_address).cloned().unwrap_or_default(),
+            trades: 0,
+            copied_usd: 0.0,
+            realized_pnl: 0.0,
+        });
+        trader.trades += 1;
+        trader.copied_usd += usd;
+
+        let market = markets.entry(trade.token_id.clone()).or_insert_with(|| MarketDay {
+            token_id: trade.token_id.clone(),
+            trades: 0,
+            copied_usd: 0.0,
+        });
+        market.trades += 1;
+        market.copied_usd += usd;
+    }
+
+    let mut traders: Vec<TraderDay> = traders.into_values().collect();
+    for trader in &mut traders {
+        let after = pnl_after.get(&trader.address).map_or(0.0, |p| p.realized_pnl);
+        let before = pnl_before.get(&trader.address).map_or(0.0, |p| p.realized_pnl);
+        trader.realized_pnl = after - before;
+    }
+    traders.sort_by(|a, b| b.copied_usd.total_cmp(&a.copied_usd));
+
+    let mut top_markets: Vec<MarketDay> = markets.into_values().collect();
+    top_markets.sort_by(|a, b| {
+        b.copied_usd
+            .total_cmp(&a.copied_usd)
+            .then(b.trades.cmp(&a.trades))
+            .then(a.token_id.cmp(&b.token_id))
+    });
+    top_markets.truncate(TOP_MARKETS);
+
+    Ok(DailyReport {
+        date,
+        total_trades: trades.len() as u32,
+        by_status,
+        buys,
+        sells,
+        copied_usd: total_copied,
+        realized_pnl,
+        traders,
+        top_markets,
+        shadow,
+    })
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let date = args.date.unwrap_or_else(|| Utc::now().date_naive());
+
+    let store = TradeStore::new(&args.db)
+        .with_context(|| format!("Failed to open database {}", args.db))?;
+    let report = build_report(&store, date, fee_per_trade_usd())?;
+
+    if args.json {
+        print_json(&report)?;
+    } else {
+        print_table(&report);
+    }
+
+    Ok(())
+}
+
+/// Shorten long ids/labels for table columns
+fn truncate(s: &str, max_len: usize) -> String {
+    if s.len() > max_len {
+        format!("{}...", &s[..max_len - 3])
+    } else {
+        s.to_string()
+    }
+}
+
+/// Print the report as tables
+fn print_table(report: &DailyReport) {
+    println!("\n=== DAILY REPORT {} (UTC) ===\n", report.date);
+
+    if report.total_trades == 0 {
+        println!("No trades recorded.");
+        return;
+    }
+
+    println!("Trades:        {} ({} BUY / {} SELL)", report.total_trades, report.buys, report.sells);
+    println!("Copied USD:    ${:.2}", report.copied_usd);
+    println!("Realized P&L:  ${:+.2}", report.realized_pnl);
+    if let Some(shadow) = &report.shadow {
+        println!(
+            "Shadow P&L:    ${:+.2} ({} orders, ${:.2}; live ${:+.2})",
+            shadow.realized_pnl, shadow.orders, shadow.usd, report.realized_pnl
+        );
+    }
+
+    println!("\nBy status:");
+    for (status, count) in &report.by_status {
+        println!("  {:<30} {:>6}", status, count);
+    }
+
+    println!("\n{:<20} {:>8} {:>12} {:>12}", "Trader", "Trades", "Copied USD", "Realized");
+    println!("{}", "-".repeat(55));
+    for trader in &report.traders {
+        let name = if trader.label.is_empty() { &trader.address } else { &trader.label };
+        println!(
+            "{:<20} {:>8} {:>12.2} {:>+12.2}",
+            truncate(name, 20),
+            trader.trades,
+            trader.copied_usd,
+            trader.realized_pnl,
+        );
+    }
+
+    println!("\nTop {} markets by volume:", TOP_MARKETS);
+    println!("{:<20} {:>8} {:>12}", "Token", "Trades", "Copied USD");
+    println!("{}", "-".repeat(42));
+    for market in &report.top_markets {
+        println!(
+            "{:<20} {:>8} {:>12.2}",
+            truncate(&market.token_id, 20),
+            market.trades,
+            market.copied_usd,
+        );
+    }
+}
+
+/// Print the report as JSON
+fn print_json(report: &DailyReport) -> Result<()> {
+    use serde_json::json;
+
+    let traders: Vec<_> = report
+        .traders
+        .iter()
+        .map(|t| {
+            json!({
+                "address": t.address,
+                "label": t.label,
+                "trades": t.trades,
+                "copied_usd": t.copied_usd,
+                "realized_pnl": t.realized_pnl,
+            })
+        })
+        .collect();
+
+    let markets: Vec<_> = report
+        .top_markets
+        .iter()
+        .map(|m| {
+            json!({
+                "token_id": m.token_id,
+                "trades": m.trades,
+                "copied_usd": m.copied_usd,
+            })
+        })
+        .collect();
+
+    let out = json!({
+        "date": report.date.to_string(),
+        "total_trades": report.total_trades,
+        "by_status": report.by_status,
+        "buys": report.buys,
+        "sells": report.sells,
+        "copied_usd": report.copied_usd,
+        "realized_pnl": report.realized_pnl,
+        "traders": traders,
+        "top_markets": markets,
+        "shadow": report.shadow.as_ref().map(|s| json!({
+            "orders": s.orders,
+            "usd": s.usd,
+            "realized_pnl": s.realized_pnl,
+        })),
+    });
+
+    println!("{}", serde_json::to_string_pretty(&out)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(ts: i64, token: &str, side: &str, shares: Option<f64>, price: f64) -> TradeRecord {
+        TradeRecord {
+            timestamp_ms: ts,
+            block_number: 1,
+            tx_hash: format!("0x{:x}", rand::random::<u64>()),
+            trader_address: "0xabc".to_string(),
+            token_id: token.to_string(),
+            side: side.to_string(),
+            whale_shares: 100.0,
+            whale_price: price,
+            whale_usd: 100.0 * price,
+            our_shares: shares,
+            our_price: shares.map(|_| price),
+            our_usd: shares.map(|s| s * price),
+            fill_pct: shares.map(|_| 100.0),
+            status: if shares.is_some() { "SUCCESS" } else { "SKIPPED_SMALL" }.to_string(),
+            latency_ms: None,
+            is_live: Some(true),
+            aggregation_count: None,
+            aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
+        }
+    }
+
+    fn day() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, 1).unwrap()
+    }
+
+    #[test]
+    fn test_args_defaults() {
+        let args = Args::parse_from(["daily_report"]);
+        assert_eq!(args.db, "trades.db");
+        assert!(args.date.is_none());
+        assert!(!args.json);
+    }
+
+    #[test]
+    fn test_args_date_and_json() {
+        let args = Args::parse_from(["daily_report", "--date", "2024-06-01", "--json"]);
+        assert_eq!(args.date, Some(day()));
+        assert!(args.json);
+
+        assert!(Args::try_parse_from(["daily_report", "--date", "06/01/2024"]).is_err());
+    }
+
+    #[test]
+    fn test_day_range_ms() {
+        let (start, end) = day_range_ms(day());
+        assert_eq!(start, 1_717_200_000_000);
+        assert_eq!(end - start, 86_400_000);
+        assert_eq!(day_range_ms(day().succ_opt().unwrap()).0, end);
+    }
+
+    #[test]
+    fn test_build_report_day_boundaries_and_totals() {
+        let store = TradeStore::new(":memory:").unwrap();
+        let (start, end) = day_range_ms(day());
+
+        // Bought the day before, sold on the day: P&L lands on the day of the sell
+        store.insert_trade(&trade(start - 1, "tokA", "BUY", Some(10.0), 0.40)).unwrap();
+        store.insert_trade(&trade(start, "tokA", "SELL", Some(10.0), 0.60)).unwrap();
+        store.insert_trade(&trade(start + 1_000, "tokB", "BUY", Some(20.0), 0.50)).unwrap();
+        store.insert_trade(&trade(end - 1, "tokC", "BUY", None, 0.30)).unwrap();
+        // Next day's first millisecond belongs to the next report
+        store.insert_trade(&trade(end, "tokB", "SELL", Some(20.0), 0.90)).unwrap();
+
+        let report = build_report(&store, day(), 0.0).unwrap();
+        assert_eq!(report.total_trades, 3);
+        assert_eq!((report.buys, report.sells), (2, 1));
+        assert_eq!(report.by_status.get("SUCCESS"), Some(&2));
+        assert_eq!(report.by_status.get("SKIPPED_SMALL"), Some(&1));
+        assert!((report.copied_usd - 16.0).abs() < 1e-9);
+        assert!((report.realized_pnl - 2.0).abs() < 1e-9);
+
+        assert_eq!(report.traders.len(), 1);
+        assert_eq!(report.traders[0].trades, 3);
+        assert!((report.traders[0].realized_pnl - 2.0).abs() < 1e-9);
+
+        let tokens: Vec<&str> = report.top_markets.iter().map(|m| m.token_id.as_str()).collect();
+        assert_eq!(tokens, vec!["tokB", "tokA", "tokC"]);
+    }
+
+    #[test]
+    fn test_top_markets_capped() {
+        let store = TradeStore::new(":memory:").unwrap();
+        let (start, _) = day_range_ms(day());
+        for i in 0..8 {
+            let t = trade(start + i, &format!("tok{}", i), "BUY", Some(10.0 + i as f64), 0.5);
+            store.insert_trade(&t).unwrap();
+        }
+
+        let report = build_report(&store, day(), 0.0).unwrap();
+        assert_eq!(report.top_markets.len(), TOP_MARKETS);
+        assert_eq!(report.top_markets[0].token_id, "tok7");
+        assert_eq!(report.shadow, None);
+    }
+
+    #[test]
+    fn test_shadow_pnl_reported_next_to_live() {
+        let store = TradeStore::new(":memory:").unwrap();
+        let (start, _) = day_range_ms(day());
+        let shadow = |ts: i64, side: &str, shares: f64, price: f64| TradeRecord {
+            status: "SHADOW".to_string(),
+            ..trade(ts, "tokA", side, Some(shares), price)
+        };
+
+        store.insert_trade(&trade(start + 1, "tokA", "BUY", Some(10.0), 0.40)).unwrap();
+        store.insert_trade(&trade(start + 2, "tokA", "SELL", Some(10.0), 0.60)).unwrap();
+        // The shadow bought earlier and bigger
+        store.insert_trade(&shadow(start - 1, "BUY", 50.0, 0.40)).unwrap();
+        store.insert_trade(&shadow(start + 2, "SELL", 50.0, 0.60)).unwrap();
+
+        let report = build_report(&store, day(), 0.0).unwrap();
+        assert_eq!(report.total_trades, 2, "shadow rows are not live trades");
+        assert!((report.realized_pnl - 2.0).abs() < 1e-9);
+        let shadow = report.shadow.unwrap();
+        assert_eq!(shadow.orders, 1);
+        assert!((shadow.usd - 30.0).abs() < 1e-9);
+        assert!((shadow.realized_pnl - 10.0).abs() < 1e-9);
+    }
+}
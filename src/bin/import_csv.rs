@@ -258,6 +258,8 @@ fn parse_csv_row(row: &csv::StringRecord) -> Result<TradeRecord> {
         is_live,
         aggregation_count: None,
         aggregation_window_ms: None,
+        order_id: None,
+        contributing_traders: None,
     })
 }
 
@@ -465,6 +467,8 @@ mod tests {
             is_live: Some(false),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         };
 
         let trades = vec![trade];
@@ -505,6 +509,8 @@ mod tests {
             is_live: Some(false),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         };
 
         let trade2 = trade1.clone();
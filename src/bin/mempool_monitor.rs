@@ -14,8 +14,6 @@ use serde_json::Value;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt::Write as _;
-use std::fs::{File, OpenOptions};
-use std::io::Write;
 use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
@@ -28,12 +26,12 @@ use pm_whale_follower::models::{OrderInfo, SizeType, ResubmitRequest};
 use pm_whale_follower::tennis_markets;
 use pm_whale_follower::soccer_markets;
 use pm_whale_follower::market_cache;
+use pm_whale_follower::csv_log::{self, CSV_HEADER, CsvLog};
 
 // ============================================================================
 // Mempool-specific constants (not in shared config)
 // ============================================================================
 
-const GAMMA_API_BASE: &str = "https://gamma-api.polymarket.com";
 
 const WATCH_ADDRESSES: [&str; 4] = [
     "0xB768891e3130F6dF18214Ac804d4DB76c2C37730",
@@ -56,6 +54,11 @@ static WHALE_PADDED_LOWER: Lazy<Vec<u8>> = Lazy::new(|| {
     format!("000000000000000000000000{}", addr.trim_start_matches("0x").to_lowercase()).into_bytes()
 });
 
+/// Followed whale as a 0x-prefixed address (attributes resubmitted orders)
+fn whale_address() -> String {
+    format!("0x{}", String::from_utf8_lossy(&WHALE_FILTER_LOWER))
+}
+
 // Pre-built SIMD finders (initialized lazily with env var values)
 static WHALE_FILTER_FINDER: Lazy<memmem::Finder<'static>> =
     Lazy::new(|| {
@@ -137,7 +140,6 @@ impl OrderEngine {
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv().ok();
-    ensure_csv()?;
 
     // Initialize all market caches at startup
     market_cache::init_caches();
@@ -146,10 +148,12 @@ async fn main() -> Result<()> {
     let _cache_refresh_handle = market_cache::spawn_cache_refresh_task();
 
     let cfg = Config::from_env()?;
+    csv_log::init(CsvLog::new(&cfg.csv_file, CSV_HEADER, cfg.csv_max_bytes))?;
 
     let (client, creds) = build_worker_state(
         cfg.private_key.clone(),
         cfg.funder_address.clone(),
+        &cfg.api_endpoints.clob,
         ".clob_market_cache.json",
         ".clob_creds.json",
     ).await?;
@@ -167,7 +171,7 @@ async fn main() -> Result<()> {
     start_order_worker(order_rx, client_arc.clone(), prepared_creds, cfg.enable_trading, cfg.mock_trading, cb_config, resubmit_tx.clone());
 
     // Spawn async resubmitter worker
-    tokio::spawn(resubmit_worker(resubmit_rx, client_arc, creds_arc));
+    tokio::spawn(resubmit_worker(resubmit_rx, client_arc, creds_arc, cfg.resubmit_policy, cfg.gtd_expiry));
 
     let order_engine = OrderEngine {
         tx: order_tx,
@@ -182,7 +186,7 @@ async fn main() -> Result<()> {
     println!("   Whale: 0x{}", std::str::from_utf8(&WHALE_FILTER_LOWER).unwrap());
 
     loop {
-        if let Err(e) = run_mempool_loop(&cfg.wss_url, &order_engine).await {
+        if let Err(e) = run_mempool_loop(&cfg.wss_urls[0], &order_engine, &cfg.api_endpoints).await {
             eprintln!("⚠️ WS error: {e}. Reconnecting...");
             tokio::time::sleep(WS_RECONNECT_DELAY).await;
         }
@@ -196,12 +200,13 @@ async fn main() -> Result<()> {
 async fn build_worker_state(
     private_key: String,
     funder: Option<String>,
+    host: &str,
     cache_path: &str,
     creds_path: &str,
 ) -> Result<(RustClobClient, ApiCreds)> {
     let cache_path = cache_path.to_string();
     let creds_path = creds_path.to_string();
-    let host = CLOB_API_BASE.to_string();
+    let host = host.to_string();
 
     tokio::task::spawn_blocking(move || -> Result<(RustClobClient, ApiCreds)> {
         let mut client = RustClobClient::new(&host, 137, &private_key, funder.as_deref())?
@@ -367,6 +372,7 @@ fn process_order(
                             let max_price = (limit_price + resubmit_buffer).min(0.99);
                             let req = ResubmitRequest {
                                 token_id: info.clob_token_id.to_string(),  // Arc<str> -> String
+                                trader_address: whale_address(),
                                 whale_price,
                                 failed_price: limit_price,  // Start at same price (already filled some)
                                 size: (remaining_shares * 100.0).floor() / 100.0,
@@ -396,6 +402,7 @@ fn process_order(
                 let rounded_size = (my_shares * 100.0).floor() / 100.0;
                 let req = ResubmitRequest {
                     token_id: info.clob_token_id.to_string(),  // Arc<str> -> String
+                    trader_address: whale_address(),
                     whale_price,
                     failed_price: limit_price,
                     size: rounded_size,
@@ -469,7 +476,7 @@ fn fetch_book_depth_blocking(
     side: TradeSide,
     threshold: f64,
 ) -> Result<f64, &'static str> {
-    let url = format!("{}/book?token_id={}", CLOB_API_BASE, token_id);
+    let url = format!("{}/book?token_id={}", client.host(), token_id);
     let resp = client.http_client()
         .get(&url)
         .timeout(Duration::from_millis(500))
@@ -499,7 +506,7 @@ fn fetch_book_depth_blocking(
 // Mempool WebSocket Loop
 // ============================================================================
 
-async fn run_mempool_loop(wss_url: &str, order_engine: &OrderEngine) -> Result<()> {
+async fn run_mempool_loop(wss_url: &str, order_engine: &OrderEngine, endpoints: &ApiEndpoints) -> Result<()> {
     let (mut ws, _) = connect_async(wss_url).await?;
 
     let payload = serde_json::json!({
@@ -516,6 +523,7 @@ async fn run_mempool_loop(wss_url: &str, order_engine: &OrderEngine) -> Result<(
     ws.send(Message::Text(payload)).await?;
 
     let http_client = reqwest::Client::builder().no_proxy().build()?;
+    let endpoints = Arc::new(endpoints.clone());
     let mut sub_id: Option<String> = None;
 
     loop {
@@ -542,8 +550,9 @@ async fn run_mempool_loop(wss_url: &str, order_engine: &OrderEngine) -> Result<(
                 if let Some(evt) = parse_mempool_tx(&text, &sub_id, timestamp) {
                     let engine = order_engine.clone();
                     let client = http_client.clone();
+                    let endpoints = Arc::clone(&endpoints);
                     tokio::spawn(async move {
-                        handle_event(evt, &engine, &client).await;
+                        handle_event(evt, &engine, &client, &endpoints).await;
                     });
                 }
             }
@@ -556,8 +565,9 @@ async fn run_mempool_loop(wss_url: &str, order_engine: &OrderEngine) -> Result<(
                     if let Some(evt) = parse_mempool_tx(&text, &sub_id, timestamp) {
                         let engine = order_engine.clone();
                         let client = http_client.clone();
+                        let endpoints = Arc::clone(&endpoints);
                         tokio::spawn(async move {
-                            handle_event(evt, &engine, &client).await;
+                            handle_event(evt, &engine, &client, &endpoints).await;
                         });
                     }
                 }
@@ -748,18 +758,18 @@ fn hex_decode_32(hex: &[u8], out: &mut [u8; 32]) -> bool {
 // Event Handling & Logging
 // ============================================================================
 
-async fn handle_event(evt: ParsedEvent, order_engine: &OrderEngine, http_client: &reqwest::Client) {
+async fn handle_event(evt: ParsedEvent, order_engine: &OrderEngine, http_client: &reqwest::Client, endpoints: &ApiEndpoints) {
     // Get is_live from cache first, fallback to API if cache miss
     let is_live = match market_cache::get_is_live(&evt.order.clob_token_id) {
         Some(v) => Some(v),
-        None => fetch_is_live(&evt.order.clob_token_id, http_client).await,
+        None => fetch_is_live(&endpoints.gamma, &evt.order.clob_token_id, http_client).await,
     };
 
     let status = order_engine.submit(evt.clone(), is_live).await;
 
     tokio::time::sleep(Duration::from_secs_f32(2.8)).await;
 
-    let bests = fetch_best_book(&evt.order.clob_token_id, &evt.order.order_type, http_client).await;
+    let bests = fetch_best_book(&endpoints.clob, &evt.order.clob_token_id, &evt.order.order_type, http_client).await;
     let ((bp, bs), (sp, ss)) = bests.unwrap_or_else(|| (("N/A".into(), "N/A".into()), ("N/A".into(), "N/A".into())));
 
     let is_live = is_live.unwrap_or(false);
@@ -814,8 +824,8 @@ async fn handle_event(evt: ParsedEvent, order_engine: &OrderEngine, http_client:
     let _ = tokio::task::spawn_blocking(move || append_csv_row(row)).await;
 }
 
-async fn fetch_best_book(token_id: &str, order_type: &str, client: &reqwest::Client) -> Option<((String, String), (String, String))> {
-    let url = format!("{}/book?token_id={}", CLOB_API_BASE, token_id);
+async fn fetch_best_book(clob_base: &str, token_id: &str, order_type: &str, client: &reqwest::Client) -> Option<((String, String), (String, String))> {
+    let url = format!("{}/book?token_id={}", clob_base, token_id);
     let resp = client.get(&url).timeout(BOOK_REQ_TIMEOUT).send().await.ok()?;
     if !resp.status().is_success() { return None; }
 
@@ -865,15 +875,15 @@ async fn fetch_best_book(token_id: &str, order_type: &str, client: &reqwest::Cli
     Some(((best_price, best_size), (second_price, second_size)))
 }
 
-async fn fetch_is_live(token_id: &str, client: &reqwest::Client) -> Option<bool> {
+async fn fetch_is_live(gamma_base: &str, token_id: &str, client: &reqwest::Client) -> Option<bool> {
     // Fetch market info to get slug
-    let market_url = format!("{}/markets?clob_token_ids={}", GAMMA_API_BASE, token_id);
+    let market_url = format!("{}/markets?clob_token_ids={}", gamma_base, token_id);
     let resp = client.get(&market_url).timeout(Duration::from_secs(2)).send().await.ok()?;
     let val: Value = resp.json().await.ok()?;
     let slug = val.get(0)?.get("slug")?.as_str()?.to_string();
 
     // Fetch live status from events API
-    let event_url = format!("{}/events/slug/{}", GAMMA_API_BASE, slug);
+    let event_url = format!("{}/events/slug/{}", gamma_base, slug);
     let resp = client.get(&event_url).timeout(Duration::from_secs(2)).send().await.ok()?;
     let val: Value = resp.json().await.ok()?;
 
@@ -888,20 +898,18 @@ async fn resubmit_worker(
     mut rx: mpsc::UnboundedReceiver<ResubmitRequest>,
     client: Arc<RustClobClient>,
     creds: Arc<PreparedCreds>,
+    policy: ResubmitPolicy,
+    gtd_expiry: GtdExpiry,
 ) {
     println!("🔄 Resubmitter worker started");
 
     while let Some(req) = rx.recv().await {
         // Calculate max attempts and is_last_attempt FIRST (needed for max_price check)
-        let max_attempts = get_max_resubmit_attempts(req.whale_shares);
+        let max_attempts = policy.max_attempts(req.whale_shares);
         let is_last_attempt = req.attempt >= max_attempts;
 
         // Calculate increment: chase only if should_increment_price returns true
-        let increment = if should_increment_price(req.whale_shares, req.attempt) {
-            RESUBMIT_PRICE_INCREMENT
-        } else {
-            0.0  // Flat retry
-        };
+        let increment = policy.increment(req.whale_shares, req.attempt);
         let new_price = if req.side_is_buy {
             (req.failed_price + increment).min(0.99)
         } else {
@@ -926,11 +934,11 @@ async fn resubmit_worker(
         let attempt = req.attempt;
         let whale_price = req.whale_price;
         let max_price = req.max_price;
-        let is_live = req.is_live;
+        let gtd_expiry_secs = gtd_expiry.secs(req.is_live);
 
         // Submit order: FAK for early attempts, GTD with expiry for last attempt
         let result = tokio::task::spawn_blocking(move || {
-            submit_resubmit_order_sync(&client_clone, &creds_clone, &token_id, new_price, size, is_live, is_last_attempt)
+            submit_resubmit_order_sync(&client_clone, &creds_clone, &token_id, new_price, size, gtd_expiry_secs, is_last_attempt)
         }).await;
 
         match result {
@@ -955,6 +963,7 @@ async fn resubmit_worker(
                         );
                         let next_req = ResubmitRequest {
                             token_id: req.token_id.clone(),
+                            trader_address: req.trader_address.clone(),
                             whale_price,
                             failed_price: new_price,
                             size: remaining,
@@ -966,7 +975,7 @@ async fn resubmit_worker(
                             original_size: req.original_size,
                             is_live: req.is_live,
                         };
-                        let _ = process_resubmit_chain(&client, &creds, next_req).await;
+                        let _ = process_resubmit_chain(&client, &creds, &policy, gtd_expiry, next_req).await;
                     } else {
                         println!(
                             "\x1b[32m🔄 Resubmit SUCCESS: attempt {} @ {:.2} | filled {:.2}/{:.2} ({:.0}%)\x1b[0m",
@@ -981,6 +990,7 @@ async fn resubmit_worker(
                     // Re-queue with updated price
                     let next_req = ResubmitRequest {
                         token_id: req.token_id,
+                        trader_address: req.trader_address,
                         whale_price,
                         failed_price: new_price,
                         size: req.size,
@@ -993,11 +1003,7 @@ async fn resubmit_worker(
                         is_live: req.is_live,
                     };
                     // Process remaining attempts inline (no delay for speed)
-                    let next_increment = if should_increment_price(req.whale_shares, attempt + 1) {
-                        RESUBMIT_PRICE_INCREMENT
-                    } else {
-                        0.0
-                    };
+                    let next_increment = policy.increment(req.whale_shares, attempt + 1);
                     println!(
                         "🔄 Resubmit attempt {} failed (FAK), retrying immediately @ {:.2} (max: {})",
                         attempt, new_price + next_increment, max_attempts
@@ -1005,6 +1011,8 @@ async fn resubmit_worker(
                     let _ = process_resubmit_chain(
                         &client,
                         &creds,
+                        &policy,
+                        gtd_expiry,
                         next_req,
                     ).await;
                 } else {
@@ -1044,9 +1052,11 @@ async fn resubmit_worker(
 async fn process_resubmit_chain(
     client: &Arc<RustClobClient>,
     creds: &Arc<PreparedCreds>,
+    policy: &ResubmitPolicy,
+    gtd_expiry: GtdExpiry,
     mut req: ResubmitRequest,
 ) {
-    let max_attempts = get_max_resubmit_attempts(req.whale_shares);
+    let max_attempts = policy.max_attempts(req.whale_shares);
 
     while req.attempt <= max_attempts {
         // Calculate is_last_attempt FIRST (needed for max_price check and order type)
@@ -1054,11 +1064,7 @@ async fn process_resubmit_chain(
         let is_last_attempt = attempt >= max_attempts;
 
         // Calculate increment: chase only if should_increment_price returns true
-        let increment = if should_increment_price(req.whale_shares, req.attempt) {
-            RESUBMIT_PRICE_INCREMENT
-        } else {
-            0.0  // Flat retry
-        };
+        let increment = policy.increment(req.whale_shares, req.attempt);
         let new_price = if req.side_is_buy {
             (req.failed_price + increment).min(0.99)
         } else {
@@ -1079,11 +1085,11 @@ async fn process_resubmit_chain(
         let creds_clone = (*creds).clone();
         let token_id = req.token_id.clone();
         let size = req.size;
-        let is_live = req.is_live;
+        let gtd_expiry_secs = gtd_expiry.secs(req.is_live);
 
         // Submit order: FAK for early attempts, GTD with expiry for last attempt
         let result = tokio::task::spawn_blocking(move || {
-            submit_resubmit_order_sync(&client_clone, &creds_clone, &token_id, new_price, size, is_live, is_last_attempt)
+            submit_resubmit_order_sync(&client_clone, &creds_clone, &token_id, new_price, size, gtd_expiry_secs, is_last_attempt)
         }).await;
 
         match result {
@@ -1165,7 +1171,7 @@ async fn process_resubmit_chain(
 }
 
 /// Synchronous order submission for resubmits (called via spawn_blocking)
-/// Now uses GTD with dynamic expiry based on market liveness (only on last attempt)
+/// Uses GTD with the market's configured expiry (GTD_EXPIRY_LIVE_SECS / GTD_EXPIRY_SECS) on the last attempt
 /// Returns (success, body_text, filled_shares)
 fn submit_resubmit_order_sync(
     client: &RustClobClient,
@@ -1173,21 +1179,19 @@ fn submit_resubmit_order_sync(
     token_id: &str,
     price: f64,
     size: f64,
-    is_live: bool,
+    gtd_expiry_secs: u64,
     is_last_attempt: bool,
 ) -> anyhow::Result<(bool, String, f64)> {
     use std::time::{SystemTime, UNIX_EPOCH};
-    use pm_whale_follower::settings::get_gtd_expiry_secs;
 
     let mut client = client.clone();
 
     // Only use GTD with expiry on the LAST attempt; earlier attempts use FAK
     let (expiration, order_type) = if is_last_attempt {
-        let expiry_secs = get_gtd_expiry_secs(is_live);
         let expiry_timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
-            .as_secs() + expiry_secs;
+            .as_secs() + gtd_expiry_secs;
         (Some(expiry_timestamp.to_string()), "GTD")
     } else {
         (None, "FAK")
@@ -1238,18 +1242,8 @@ fn submit_resubmit_order_sync(
 // CSV Helpers
 // ============================================================================
 
-fn ensure_csv() -> Result<()> {
-    if !Path::new(CSV_FILE).exists() {
-        let mut f = File::create(CSV_FILE)?;
-        writeln!(f, "timestamp,block,clob_asset_id,usd_value,shares,price_per_share,direction,order_status,best_price,best_size,second_price,second_size,tx_hash,is_live")?;
-    }
-    Ok(())
-}
-
 fn append_csv_row(row: String) {
-    if let Ok(mut f) = OpenOptions::new().append(true).create(true).open(CSV_FILE) {
-        let _ = writeln!(f, "{}", row);
-    }
+    csv_log::append_row(&row);
 }
 
 #[inline]
@@ -1,25 +1,43 @@
 // position_monitor.rs - CLI tool for monitoring current positions from trade database
 //
 // Usage:
-//   cargo run --bin position_monitor                    # Monitor with live P&L and daily P&L change
+//   cargo run --bin position_monitor                    # Watch live P&L and daily P&L change (Ctrl-C to exit)
+//   cargo run --bin position_monitor -- --interval 30   # Refresh every 30 seconds (default: 10)
 //   cargo run --bin position_monitor -- --db test.db    # Use custom database
 //   cargo run --bin position_monitor -- --no-prices     # Show positions without prices/P&L
 //   cargo run --bin position_monitor -- --ttl 60        # Set price cache TTL to 60 seconds
+//   cargo run --bin position_monitor -- --live          # Stream prices over WebSocket in watch mode
 //   cargo run --bin position_monitor -- --once          # Single snapshot and exit
 //   cargo run --bin position_monitor -- --stats         # Show aggregation statistics
+//   cargo run --bin position_monitor -- --stats --since 1706000000  # ...for trades since a Unix timestamp
 //   cargo run --bin position_monitor -- --json          # Output portfolio data in JSON format
+//   cargo run --bin position_monitor -- --flatten --confirm  # Close every position through the running bot
 //
 // Features:
 //   - Daily P&L tracking: Snapshots portfolio value at start of each day (UTC)
 //   - Snapshot file: Stored as .portfolio_snapshot.json in same directory as database
 //   - JSON output: Use --json flag for machine-readable output
+//   - Watch mode: Table re-renders every --interval seconds; prices are only refetched
+//     once the --ttl cache entry expires. --json and --stats always run once.
+//   - Live prices: --live subscribes to the held tokens on the CLOB market WebSocket;
+//     REST is only used for tokens the feed has no price for yet.
+//   - Flatten: --flatten asks the running bot's API (POST /flatten, API_ENABLED) to submit a
+//     full-exit GTD for every position; it does nothing without --confirm.
 
 use anyhow::Result;
 use clap::Parser;
+use pm_whale_follower::flatten::request_flatten;
 use pm_whale_follower::persistence::{TradeStore, Position, AggregationStats};
-use pm_whale_follower::prices::{PriceCache, PriceInfo};
+use pm_whale_follower::pnl::{calculate_cost_basis, calculate_position_value, calculate_unrealized_pnl};
+use pm_whale_follower::prices::{PriceCache, PriceFeed, PriceInfo, MARKET_WS_URL};
+use pm_whale_follower::settings::fee_per_trade_usd;
 use serde::{Serialize, Deserialize};
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+/// ANSI sequence: clear screen and move cursor home
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
 
 /// Daily snapshot of portfolio state for tracking day-over-day changes
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,67 +80,6 @@ struct PositionWithPrice {
     price_info: Option<PriceInfo>,
 }
 
-/// Calculate the market value of a position
-///
-/// # Arguments
-/// * `net_shares` - Net shares (positive for long, negative for short)
-/// * `bid_price` - Current bid price (what you'd sell at)
-/// * `ask_price` - Current ask price (what you'd buy at)
-///
-/// # Returns
-/// * `f64` - Position value in USD
-fn calculate_position_value(net_shares: f64, bid_price: f64, ask_price: f64) -> f64 {
-    if net_shares > 0.0 {
-        // LONG position: value is what we'd get selling at bid
-        net_shares * bid_price
-    } else if net_shares < 0.0 {
-        // SHORT position: value is the obligation (cost to close at ask)
-        net_shares.abs() * ask_price
-    } else {
-        // Zero shares
-        0.0
-    }
-}
-
-/// Calculate the cost basis of a position
-///
-/// # Arguments
-/// * `net_shares` - Net shares (positive for long, negative for short)
-/// * `avg_entry_price` - Average entry price
-///
-/// # Returns
-/// * `Option<f64>` - Cost basis in USD, or None if avg_entry_price is None
-fn calculate_cost_basis(net_shares: f64, avg_entry_price: Option<f64>) -> Option<f64> {
-    avg_entry_price.map(|entry| net_shares.abs() * entry)
-}
-
-/// Calculate unrealized P&L for a position
-///
-/// # Arguments
-/// * `net_shares` - Net shares (positive for long, negative for short)
-/// * `avg_entry` - Average entry price
-/// * `bid_price` - Current bid price (what you'd sell at)
-/// * `ask_price` - Current ask price (what you'd buy at)
-///
-/// # Returns
-/// * `Option<f64>` - Unrealized P&L in USD, or None if avg_entry is None
-fn calculate_unrealized_pnl(
-    net_shares: f64,
-    avg_entry: Option<f64>,
-    bid_price: f64,
-    ask_price: f64,
-) -> Option<f64> {
-    avg_entry.map(|entry| {
-        if net_shares > 0.0 {
-            // LONG position: use bid price (what you'd sell at)
-            (bid_price - entry) * net_shares
-        } else {
-            // SHORT position: use ask price (what you'd buy at to close)
-            (entry - ask_price) * net_shares.abs()
-        }
-    })
-}
-
 /// Portfolio summary aggregating all positions
 #[derive(Debug, Clone, PartialEq)]
 struct PortfolioSummary {
@@ -196,10 +153,18 @@ struct Args {
     #[arg(long)]
     once: bool,
 
+    /// Refresh interval in seconds for watch mode
+    #[arg(long, default_value = "10")]
+    interval: u64,
+
     /// Show aggregation statistics
     #[arg(long)]
     stats: bool,
 
+    /// With --stats, only count trades since this Unix timestamp (seconds)
+    #[arg(long, requires = "stats")]
+    since: Option<i64>,
+
     /// Skip price fetching (show positions only)
     #[arg(long)]
     no_prices: bool,
@@ -211,6 +176,22 @@ struct Args {
     /// Output in JSON format
     #[arg(long)]
     json: bool,
+
+    /// Stream prices from the CLOB market WebSocket (watch mode)
+    #[arg(long)]
+    live: bool,
+
+    /// Close every position through the running bot's API (POST /flatten); needs --confirm
+    #[arg(long, requires = "confirm")]
+    flatten: bool,
+
+    /// Confirm --flatten
+    #[arg(long, requires = "flatten")]
+    confirm: bool,
+
+    /// Bot API for --flatten (default: http://127.0.0.1:$API_PORT, port 8080 if unset)
+    #[arg(long, requires = "flatten")]
+    api_url: Option<String>,
 }
 
 /// Get the path for the daily snapshot file
@@ -390,73 +371,131 @@ fn to_portfolio_json(
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+fn main() -> Result<()> {
     let args = Args::parse();
 
+    if args.flatten {
+        let api_url = args.api_url.clone().unwrap_or_else(|| {
+            format!("http://127.0.0.1:{}", std::env::var("API_PORT").unwrap_or_else(|_| "8080".to_string()))
+        });
+        let response = request_flatten(&api_url)?;
+        println!("Flatten: {} position(s)", response.orders.len());
+        for order in &response.orders {
+            let price = order.price.map_or("-".to_string(), |p| format!("{:.2}", p));
+            println!("  {} {:>10.2} @ {:>5} {}  {}", order.side.as_str(), order.shares, price, order.token_id, order.status);
+        }
+        return Ok(());
+    }
+
     // Open database read-only
     let store = TradeStore::new(&args.db)?;
 
     if args.stats {
         // Display aggregation statistics
-        let stats = store.get_aggregation_stats()?;
-        print_aggregation_stats(&stats);
-    } else {
-        // Fetch positions
-        let positions = store.get_positions()?;
-
-        // Enrich positions with price data (unless --no-prices is set)
-        let positions_with_prices = if args.no_prices {
-            // No price fetching - create PositionWithPrice with None
-            positions
-                .into_iter()
-                .map(|pos| PositionWithPrice {
-                    position: pos,
-                    price_info: None,
-                })
-                .collect()
-        } else {
-            // Fetch prices for all positions
-            fetch_prices_for_positions(positions, args.ttl)
+        let stats = match args.since {
+            Some(since_ts) => store.get_aggregation_stats_since(since_ts * 1000)?,
+            None => store.get_aggregation_stats()?,
         };
+        print_aggregation_stats(&stats, fee_per_trade_usd());
+        return Ok(());
+    }
 
-        // Calculate portfolio summary
-        let summary = calculate_portfolio_summary(&positions_with_prices);
+    // Shared across refreshes so prices are only refetched after the TTL expires
+    let mut cache = PriceCache::new(args.ttl);
 
-        // Get or create daily snapshot
-        let snapshot_path = get_snapshot_path(&args.db);
-        let snapshot = check_and_update_snapshot(&snapshot_path, &summary);
+    if args.once || args.json {
+        return render(&args, &store, &mut cache);
+    }
 
-        // Output based on format flag
-        if args.json {
-            // JSON output
-            let portfolio_json = to_portfolio_json(&positions_with_prices, &summary, &snapshot);
-            let json_output = serde_json::to_string_pretty(&portfolio_json)?;
-            println!("{}", json_output);
-        } else {
-            // Table output
-            print_table_with_pnl(&positions_with_prices, &snapshot);
+    if args.live && !args.no_prices {
+        cache = cache.with_feed(PriceFeed::start(MARKET_WS_URL));
+    }
+
+    let ctrl_c = ctrl_c_channel();
+    let interval = Duration::from_secs(args.interval.max(1));
+
+    loop {
+        print!("{}", CLEAR_SCREEN);
+        render(&args, &store, &mut cache)?;
+        println!("\nRefreshing every {}s (Ctrl-C to exit)", interval.as_secs());
+
+        match ctrl_c.recv_timeout(interval) {
+            Ok(()) => break,
+            Err(RecvTimeoutError::Timeout) => {}
+            // Signal handler unavailable: keep refreshing, exit via default SIGINT
+            Err(RecvTimeoutError::Disconnected) => std::thread::sleep(interval),
         }
     }
 
     Ok(())
 }
 
-/// Fetch prices for all positions using batch API
-fn fetch_prices_for_positions(positions: Vec<Position>, ttl_seconds: u64) -> Vec<PositionWithPrice> {
-    // Create price cache
-    let mut cache = PriceCache::new(ttl_seconds);
+/// Channel that receives a message when Ctrl-C is pressed
+fn ctrl_c_channel() -> Receiver<()> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to create signal runtime");
+        if runtime.block_on(tokio::signal::ctrl_c()).is_ok() {
+            let _ = tx.send(());
+        }
+    });
+    rx
+}
+
+/// Load positions, refresh prices and print the table (or JSON)
+fn render(args: &Args, store: &TradeStore, cache: &mut PriceCache) -> Result<()> {
+    // Fetch positions
+    let positions = store.get_positions()?;
+
+    // Enrich positions with price data (unless --no-prices is set)
+    let positions_with_prices = if args.no_prices {
+        // No price fetching - create PositionWithPrice with None
+        positions
+            .into_iter()
+            .map(|pos| PositionWithPrice {
+                position: pos,
+                price_info: None,
+            })
+            .collect()
+    } else {
+        // Fetch prices for all positions
+        fetch_prices_for_positions(positions, cache)
+    };
+
+    // Calculate portfolio summary
+    let summary = calculate_portfolio_summary(&positions_with_prices);
+
+    // Get or create daily snapshot (re-evaluated each refresh so it rolls over at midnight UTC)
+    let snapshot_path = get_snapshot_path(&args.db);
+    let snapshot = check_and_update_snapshot(&snapshot_path, &summary);
+
+    // Output based on format flag
+    if args.json {
+        // JSON output
+        let portfolio_json = to_portfolio_json(&positions_with_prices, &summary, &snapshot);
+        let json_output = serde_json::to_string_pretty(&portfolio_json)?;
+        println!("{}", json_output);
+    } else {
+        // Table output
+        print_table_with_pnl(&positions_with_prices, &snapshot);
+    }
+
+    Ok(())
+}
 
+/// Fetch prices for all positions using batch API
+fn fetch_prices_for_positions(positions: Vec<Position>, cache: &mut PriceCache) -> Vec<PositionWithPrice> {
     // Extract token IDs
     let token_ids: Vec<&str> = positions.iter().map(|p| p.token_id.as_str()).collect();
 
-    // Batch fetch prices with fallback
-    let mut price_map = std::collections::HashMap::new();
-    for token_id in &token_ids {
-        if let Some(price) = cache.get_or_fetch_price_with_fallback(token_id) {
-            price_map.insert(token_id.to_string(), price);
-        }
-    }
+    // Follow the held set with the live feed (no-op without --live)
+    cache.track_tokens(&token_ids);
+
+    // One /books request for all positions (stale cache used on failure)
+    let price_map = cache.get_or_fetch_many(&token_ids);
 
     // Enrich positions with prices
     positions
@@ -567,7 +606,8 @@ fn truncate_token_id(token_id: &str) -> String {
 }
 
 /// Print aggregation statistics in a formatted display
-fn print_aggregation_stats(stats: &AggregationStats) {
+/// `fee_per_trade` is the estimated USD fee per order (FEE_PER_TRADE_USD)
+fn print_aggregation_stats(stats: &AggregationStats, fee_per_trade: f64) {
     println!("\n=== AGGREGATION STATISTICS ===\n");
 
     if stats.total_orders == 0 {
@@ -579,32 +619,33 @@ fn print_aggregation_stats(stats: &AggregationStats) {
     let aggregation_pct = (stats.aggregated_orders as f64 / stats.total_orders as f64) * 100.0;
 
     // Calculate estimated fees saved
-    let fees_saved = calculate_fees_saved(stats.total_trades_combined, stats.aggregated_orders);
+    let fees_saved = calculate_fees_saved(stats.total_trades_combined, stats.aggregated_orders, fee_per_trade);
 
     println!("Total orders:          {}", stats.total_orders);
     println!("Aggregated orders:     {} ({:.1}%)", stats.aggregated_orders, aggregation_pct);
     println!("Avg trades per agg:    {:.1}", stats.avg_trades_per_aggregation);
+    println!("Avg window:            {:.0}ms", stats.avg_window_ms);
     println!("Estimated fees saved:  ${:.2}", fees_saved);
     println!();
 }
 
 /// Calculate estimated fees saved through aggregation
 ///
-/// Assumes $0.02 per trade saved by aggregation.
-/// Formula: (total_trades_combined - aggregated_orders) * $0.02
+/// Formula: (total_trades_combined - aggregated_orders) * fee_per_trade
 ///
 /// # Arguments
 /// * `total_trades_combined` - Total individual trades combined through aggregation
 /// * `aggregated_orders` - Number of aggregated orders executed
+/// * `fee_per_trade` - Estimated USD fee per order (0 on fee-free markets)
 ///
 /// # Returns
 /// * `f64` - Estimated fees saved in USD
-fn calculate_fees_saved(total_trades_combined: u32, aggregated_orders: u32) -> f64 {
+fn calculate_fees_saved(total_trades_combined: u32, aggregated_orders: u32, fee_per_trade: f64) -> f64 {
     if total_trades_combined < aggregated_orders {
         return 0.0;
     }
     let trades_saved = total_trades_combined - aggregated_orders;
-    trades_saved as f64 * 0.02
+    trades_saved as f64 * fee_per_trade
 }
 
 #[cfg(test)]
@@ -642,9 +683,10 @@ mod tests {
             aggregated_orders: 45,
             total_trades_combined: 126,
             avg_trades_per_aggregation: 2.8,
+            avg_window_ms: 640.0,
         };
 
-        print_aggregation_stats(&stats); // Should not panic
+        print_aggregation_stats(&stats, 0.02); // Should not panic
     }
 
     #[test]
@@ -656,22 +698,32 @@ mod tests {
             aggregated_orders: 0,
             total_trades_combined: 0,
             avg_trades_per_aggregation: 0.0,
+            avg_window_ms: 0.0,
         };
 
-        print_aggregation_stats(&stats); // Should not panic
+        print_aggregation_stats(&stats, 0.02); // Should not panic
     }
 
     #[test]
     fn test_calculate_fees_saved() {
         // Test basic fee calculation
-        let result = calculate_fees_saved(100, 30);
+        let result = calculate_fees_saved(100, 30, 0.02);
         assert!((result - 1.40).abs() < 0.001, "Expected 1.40, got {}", result);
 
         // Test no aggregation
-        assert_eq!(calculate_fees_saved(50, 50), 0.0);
+        assert_eq!(calculate_fees_saved(50, 50, 0.02), 0.0);
 
         // Test zero orders
-        assert_eq!(calculate_fees_saved(0, 0), 0.0);
+        assert_eq!(calculate_fees_saved(0, 0, 0.02), 0.0);
+    }
+
+    #[test]
+    fn test_calculate_fees_saved_custom_fee() {
+        // Fee-free markets save nothing
+        assert_eq!(calculate_fees_saved(100, 30, 0.0), 0.0);
+
+        let result = calculate_fees_saved(100, 30, 0.05);
+        assert!((result - 3.50).abs() < 0.001, "Expected 3.50, got {}", result);
     }
 
     #[test]
@@ -683,6 +735,15 @@ mod tests {
         assert_eq!(args.stats, false);
         assert_eq!(args.no_prices, false);
         assert_eq!(args.ttl, 30);
+        assert_eq!(args.interval, 10);
+        assert!(!args.live);
+    }
+
+    #[test]
+    fn test_args_custom_interval() {
+        let args = Args::parse_from(["position_monitor", "--interval", "5"]);
+        assert_eq!(args.interval, 5);
+        assert!(!args.once);
     }
 
     #[test]
@@ -691,6 +752,15 @@ mod tests {
         assert_eq!(args.no_prices, true);
     }
 
+    #[test]
+    fn test_args_flatten_needs_confirm() {
+        assert!(Args::try_parse_from(["position_monitor", "--flatten"]).is_err());
+        assert!(Args::try_parse_from(["position_monitor", "--confirm"]).is_err());
+        let args = Args::try_parse_from(["position_monitor", "--flatten", "--confirm", "--api-url", "http://127.0.0.1:9000"]).unwrap();
+        assert!(args.flatten && args.confirm);
+        assert_eq!(args.api_url.as_deref(), Some("http://127.0.0.1:9000"));
+    }
+
     #[test]
     fn test_args_custom_ttl() {
         let args = Args::parse_from(&["position_monitor", "--ttl", "60"]);
@@ -712,6 +782,15 @@ mod tests {
         assert_eq!(args.once, true);
     }
 
+    #[test]
+    fn test_args_stats_since() {
+        let args = Args::parse_from(["position_monitor", "--stats", "--since", "1706000000"]);
+        assert!(args.stats);
+        assert_eq!(args.since, Some(1706000000));
+        // --since only applies to --stats
+        assert!(Args::try_parse_from(["position_monitor", "--since", "1706000000"]).is_err());
+    }
+
     #[test]
     fn test_calculate_unrealized_pnl_long_profit() {
         // LONG position with profit
@@ -0,0 +1,206 @@
+// send_test_order.rs - Sign and (optionally) submit one order by hand
+//
+// Usage:
+//   cargo run --bin send_test_order -- --token ID --price 0.05 --size 5 --dry-run
+//   cargo run --bin send_test_order -- --token ID --price 0.05 --size 5 --side BUY --type GTC --confirm
+//
+// Checks credentials, EIP-712 signing and CLOB connectivity end-to-end without waiting
+// for a whale event or opening the WebSocket. --dry-run signs the order and prints the
+// request body without posting it; anything else refuses to post unless --confirm is given.
+// Uses the same PRIVATE_KEY / FUNDER_ADDRESS / CLOB_API_BASE and .clob_creds.json as pm_bot.
+
+use anyhow::{Result, anyhow};
+use clap::Parser;
+use dotenvy::dotenv;
+use pm_whale_follower::settings::{ApiEndpoints, GtdExpiry};
+use pm_whale_follower::{ApiCreds, OrderArgs, OrderResponse, PreparedCreds, RustClobClient};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Order types the CLOB accepts
+const ORDER_TYPES: [&str; 4] = ["FAK", "FOK", "GTC", "GTD"];
+
+#[derive(Parser, Debug)]
+#[command(name = "send_test_order")]
+#[command(about = "Sign and submit a single order to test credentials and connectivity")]
+struct Args {
+    /// CLOB token ID
+    #[arg(long)]
+    token: String,
+
+    /// Limit price (0 < price < 1)
+    #[arg(long)]
+    price: f64,
+
+    /// Size in shares
+    #[arg(long)]
+    size: f64,
+
+    /// BUY or SELL
+    #[arg(long, default_value = "BUY")]
+    side: String,
+
+    /// FAK, FOK, GTC or GTD (GTD expires after GTD_EXPIRY_SECS)
+    #[arg(long = "type", default_value = "FAK")]
+    order_type: String,
+
+    /// Sign the order and print the request body without posting it
+    #[arg(long, conflicts_with = "confirm")]
+    dry_run: bool,
+
+    /// Actually post the order (real funds)
+    #[arg(long)]
+    confirm: bool,
+}
+
+/// Validate the arguments and build the order; `gtd_expiry_secs` only applies to GTD
+fn build_order_args(args: &Args, now_secs: u64, gtd_expiry_secs: u64) -> Result<OrderArgs> {
+    if !(args.price.is_finite() && args.price > 0.0 && args.price < 1.0) {
+        return Err(anyhow!("--price must be between 0 and 1 (exclusive), got {}", args.price));
+    }
+    if !(args.size.is_finite() && args.size > 0.0) {
+        return Err(anyhow!("--size must be positive, got {}", args.size));
+    }
+    let side = args.side.to_uppercase();
+    if side != "BUY" && side != "SELL" {
+        return Err(anyhow!("--side must be BUY or SELL, got {}", args.side));
+    }
+    let order_type = args.order_type.to_uppercase();
+    if !ORDER_TYPES.contains(&order_type.as_str()) {
+        return Err(anyhow!("--type must be one of {}, got {}", ORDER_TYPES.join(", "), args.order_type));
+    }
+
+    let expiration = if order_type == "GTD" { now_secs + gtd_expiry_secs } else { 0 };
+    Ok(OrderArgs {
+        token_id: args.token.trim().to_string(),
+        price: args.price,
+        size: args.size,
+        side,
+        fee_rate_bps: None,
+        nonce: Some(0),
+        expiration: Some(expiration.to_string()),
+        taker: None,
+        order_type: Some(order_type),
+    })
+}
+
+fn main() -> Result<()> {
+    dotenv().ok();
+    let args = Args::parse();
+
+    let now_secs = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let order = build_order_args(&args, now_secs, GtdExpiry::from_env()?.secs(false))?;
+    let order_type = order.order_type.clone().unwrap_or_default();
+
+    if !args.dry_run && !args.confirm {
+        return Err(anyhow!("Refusing to post a real order without --confirm (use --dry-run to only sign)"));
+    }
+
+    let private_key = std::env::var("PRIVATE_KEY")?;
+    let funder = if std::env::var("USE_SEPARATE_FUNDER").is_ok() {
+        std::env::var("FUNDER_ADDRESS").ok()
+    } else {
+        None
+    };
+    let host = ApiEndpoints::from_env().clob;
+
+    println!("🧪 Test Order");
+    println!("=============\n");
+    println!("   Host:  {}", host);
+    println!("   Token: {}", order.token_id);
+    println!("   {} {} {:.2} @ {:.2} (max ${:.2})", order.side, order_type, order.size, order.price, order.size * order.price);
+    if order_type == "GTD" {
+        println!("   Expires: {}", order.expiration.as_deref().unwrap_or_default());
+    }
+    println!();
+
+    let mut client = RustClobClient::new(&host, 137, &private_key, funder.as_deref())?
+        .with_cache_path(".clob_market_cache.json");
+    let _ = client.load_cache();
+
+    // Load or derive creds
+    let creds_path = ".clob_creds.json";
+    let creds: ApiCreds = if Path::new(creds_path).exists() {
+        let data = std::fs::read_to_string(creds_path)?;
+        serde_json::from_str(&data)?
+    } else {
+        let derived = client.derive_api_key(0)?;
+        std::fs::write(creds_path, serde_json::to_string_pretty(&derived)?)?;
+        derived
+    };
+    let prepared = PreparedCreds::from_api_creds(&creds)?;
+
+    let signed = client.create_order(order)?;
+    let body = signed.post_body(&creds.api_key, &order_type);
+    println!("✍️  Signed. Request body:\n{}\n", body);
+
+    if args.dry_run {
+        println!("🔍 Dry run: not posted");
+        return Ok(());
+    }
+
+    println!("📤 Posting order...");
+    let resp = client.post_order_fast(body, &prepared)?;
+    let status = resp.status();
+    let body_text = resp.text().unwrap_or_default();
+
+    println!("📥 Response:");
+    println!("   Status: {}", status);
+    match serde_json::from_str::<serde_json::Value>(&body_text) {
+        Ok(v) => println!("   Body:\n{}", serde_json::to_string_pretty(&v)?),
+        Err(_) => println!("   Body: {}", body_text),
+    }
+
+    match serde_json::from_str::<OrderResponse>(&body_text) {
+        Ok(parsed) if status.is_success() && parsed.success => {
+            println!("\n✅ Order accepted: {} ({})", parsed.order_id, parsed.status);
+            Ok(())
+        }
+        Ok(parsed) => Err(anyhow!("Order rejected ({}): {}", status, parsed.error_msg)),
+        Err(_) => Err(anyhow!("Order failed ({})", status)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse args, filling in --price 0.05 and --size 5 unless given
+    fn args(extra: &[&str]) -> Args {
+        let mut argv = vec!["send_test_order", "--token", "123"];
+        argv.extend_from_slice(extra);
+        for (flag, value) in [("--price", "0.05"), ("--size", "5")] {
+            if !extra.contains(&flag) {
+                argv.extend([flag, value]);
+            }
+        }
+        Args::parse_from(argv)
+    }
+
+    #[test]
+    fn test_defaults_to_buy_fak_without_expiry() {
+        let order = build_order_args(&args(&[]), 1_000, 1_800).unwrap();
+        assert_eq!(order.side, "BUY");
+        assert_eq!(order.order_type.as_deref(), Some("FAK"));
+        assert_eq!(order.expiration.as_deref(), Some("0"));
+    }
+
+    #[test]
+    fn test_gtd_expires_after_configured_secs() {
+        let order = build_order_args(&args(&["--side", "sell", "--type", "gtd"]), 1_000, 1_800).unwrap();
+        assert_eq!(order.side, "SELL");
+        assert_eq!(order.order_type.as_deref(), Some("GTD"));
+        assert_eq!(order.expiration.as_deref(), Some("2800"));
+    }
+
+    #[test]
+    fn test_rejects_bad_values() {
+        for extra in [&["--side", "HOLD"][..], &["--type", "IOC"], &["--price", "1.5"], &["--size", "0"]] {
+            assert!(build_order_args(&args(extra), 1_000, 1_800).is_err(), "{:?}", extra);
+        }
+        // --dry-run and --confirm are mutually exclusive
+        assert!(Args::try_parse_from([
+            "send_test_order", "--token", "1", "--price", "0.5", "--size", "5", "--dry-run", "--confirm",
+        ]).is_err());
+    }
+}
@@ -5,11 +5,24 @@
 //   cargo run --bin trade_history -- --db test.db     # Use custom database
 //   cargo run --bin trade_history -- --limit 100      # Show more trades
 //   cargo run --bin trade_history -- --refresh        # Enrich with live market data
-
-use anyhow::Result;
+//   cargo run --bin trade_history -- --format parquet --out trades.parquet
+
+use anyhow::{Context, Result};
+use arrow::array::{
+    ArrayRef, BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray,
+    TimestampMillisecondArray, UInt32Array, UInt64Array,
+};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
 use clap::Parser;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
 use pm_whale_follower::persistence::TradeStore;
+use pm_whale_follower::pnl::{open_lots_by_token, realized_pnl_by_token, Fill};
+use pm_whale_follower::settings::fee_per_trade_usd;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 #[derive(Parser)]
 #[command(name = "trade_history")]
@@ -39,10 +52,14 @@ struct Args {
     #[arg(long)]
     since: Option<i64>,
 
-    /// Output format: table, csv, json
+    /// Output format: table, csv, json, parquet
     #[arg(long, default_value = "table")]
     format: String,
 
+    /// Output file (required for --format parquet)
+    #[arg(long)]
+    out: Option<PathBuf>,
+
     /// Refresh trade data with live market information
     #[arg(long)]
     refresh: bool,
@@ -55,13 +72,17 @@ async fn main() -> Result<()> {
     // Open database read-only
     let store = TradeStore::new(&args.db)?;
 
-    // Fetch trades
-    let mut trades = store.get_recent_trades(args.limit)?;
+    // Fetch trades (a full trader address is filtered in SQL via the trader index)
+    let trader_address = args.trader.as_deref().and_then(full_trader_address);
+    let mut trades = match &trader_address {
+        Some(address) => store.get_trades_by_trader(address, args.limit)?,
+        None => store.get_recent_trades(args.limit)?,
+    };
 
-    // Apply filters
+    // Apply remaining filters (partial trader addresses are matched here)
     trades = apply_filters(
         trades,
-        args.trader.as_deref(),
+        if trader_address.is_some() { None } else { args.trader.as_deref() },
         args.token.as_deref(),
         args.status.as_deref(),
         args.since,
@@ -83,12 +104,25 @@ async fn main() -> Result<()> {
     match args.format.to_lowercase().as_str() {
         "csv" => print_csv(&trades),
         "json" => print_json(&trades)?,
+        "parquet" => {
+            let out = args.out.as_deref().context("--format parquet requires --out FILE")?;
+            write_parquet(&trades, out)?;
+            eprintln!("Wrote {} trades to {}", trades.len(), out.display());
+        }
         _ => print_table(&trades, enriched_data.as_ref()),
     }
 
     Ok(())
 }
 
+/// Normalize `--trader` if it is a complete address (stored lowercase)
+/// Partial addresses return None and are matched by substring in `apply_filters`
+fn full_trader_address(trader: &str) -> Option<String> {
+    let hex = trader.strip_prefix("0x").or_else(|| trader.strip_prefix("0X"))?;
+    (hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| format!("0x{}", hex.to_lowercase()))
+}
+
 /// Apply filters to trades
 fn apply_filters(
     trades: Vec<pm_whale_follower::persistence::TradeRecord>,
@@ -164,8 +198,10 @@ fn maybe_collect_enriched_data(
 }
 
 /// Collect enriched data for a list of token IDs
-/// Fetches market info and current prices, handling errors gracefully
+/// Market names come from the bot's market name cache when fresh; misses are fetched
+/// and written back. Current prices are always fetched. Errors are handled gracefully.
 fn collect_enriched_data(token_ids: &[String]) -> HashMap<String, EnrichedData> {
+    use pm_whale_follower::market_cache;
     use pm_whale_follower::market_info::MarketInfo;
     use pm_whale_follower::prices::PriceCache;
 
@@ -175,14 +211,23 @@ fn collect_enriched_data(token_ids: &[String]) -> HashMap<String, EnrichedData>
         return enriched;
     }
 
+    let caches = market_cache::global_caches();
+    caches.load_market_names();
     let market_info = MarketInfo::new();
     let mut price_cache = PriceCache::new(30);
+    let mut fetched_names = false;
 
     for token_id in token_ids {
-        // Fetch market info
-        let (market_title, outcome) = match market_info.fetch(token_id) {
-            Ok(Some(metadata)) => (Some(metadata.title), Some(metadata.outcome)),
-            _ => (None, None),
+        // Market info: cache first, then Gamma
+        let metadata = caches.get_market_name(token_id).or_else(|| {
+            let metadata = market_info.fetch(token_id).ok().flatten()?;
+            caches.set_market_name(token_id.clone(), metadata.clone());
+            fetched_names = true;
+            Some(metadata)
+        });
+        let (market_title, outcome) = match metadata {
+            Some(metadata) => (Some(metadata.title), Some(metadata.outcome)),
+            None => (None, None),
         };
 
         // Fetch current prices
@@ -202,6 +247,10 @@ fn collect_enriched_data(token_ids: &[String]) -> HashMap<String, EnrichedData>
         );
     }
 
+    if fetched_names && let Err(e) = caches.save_market_names() {
+        eprintln!("Warning: Failed to save market name cache: {}", e);
+    }
+
     enriched
 }
 
@@ -294,7 +343,7 @@ fn print_table(
     println!("\nTotal trades: {}", trades.len());
 
     // Print summary statistics
-    print_summary(trades);
+    print_summary(trades, enriched_data);
 }
 
 /// Format market display from enriched data
@@ -395,6 +444,74 @@ fn print_csv(trades: &[pm_whale_follower::persistence::TradeRecord]) {
 }
 
 /// Print trades in JSON format
+/// Arrow schema for Parquet export
+/// `timestamp` is derived from `timestamp_ms` as a UTC timestamp for pandas/DuckDB
+fn parquet_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("timestamp_ms", DataType::Int64, false),
+        Field::new("timestamp", DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())), false),
+        Field::new("block_number", DataType::UInt64, false),
+        Field::new("tx_hash", DataType::Utf8, false),
+        Field::new("trader_address", DataType::Utf8, false),
+        Field::new("token_id", DataType::Utf8, false),
+        Field::new("side", DataType::Utf8, false),
+        Field::new("whale_shares", DataType::Float64, false),
+        Field::new("whale_price", DataType::Float64, false),
+        Field::new("whale_usd", DataType::Float64, false),
+        Field::new("our_shares", DataType::Float64, true),
+        Field::new("our_price", DataType::Float64, true),
+        Field::new("our_usd", DataType::Float64, true),
+        Field::new("fill_pct", DataType::Float64, true),
+        Field::new("status", DataType::Utf8, false),
+        Field::new("latency_ms", DataType::Int64, true),
+        Field::new("is_live", DataType::Boolean, true),
+        Field::new("aggregation_count", DataType::UInt32, true),
+        Field::new("aggregation_window_ms", DataType::UInt64, true),
+    ])
+}
+
+/// Write trades to a Snappy-compressed Parquet file
+fn write_parquet(trades: &[pm_whale_follower::persistence::TradeRecord], path: &Path) -> Result<()> {
+    let schema = Arc::new(parquet_schema());
+    let timestamps: Vec<i64> = trades.iter().map(|t| t.timestamp_ms).collect();
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(Int64Array::from(timestamps.clone())),
+        Arc::new(TimestampMillisecondArray::from(timestamps).with_timezone("UTC")),
+        Arc::new(UInt64Array::from_iter_values(trades.iter().map(|t| t.block_number))),
+        Arc::new(StringArray::from_iter_values(trades.iter().map(|t| &t.tx_hash))),
+        Arc::new(StringArray::from_iter_values(trades.iter().map(|t| &t.trader_address))),
+        Arc::new(StringArray::from_iter_values(trades.iter().map(|t| &t.token_id))),
+        Arc::new(StringArray::from_iter_values(trades.iter().map(|t| &t.side))),
+        Arc::new(Float64Array::from_iter_values(trades.iter().map(|t| t.whale_shares))),
+        Arc::new(Float64Array::from_iter_values(trades.iter().map(|t| t.whale_price))),
+        Arc::new(Float64Array::from_iter_values(trades.iter().map(|t| t.whale_usd))),
+        Arc::new(Float64Array::from_iter(trades.iter().map(|t| t.our_shares))),
+        Arc::new(Float64Array::from_iter(trades.iter().map(|t| t.our_price))),
+        Arc::new(Float64Array::from_iter(trades.iter().map(|t| t.our_usd))),
+        Arc::new(Float64Array::from_iter(trades.iter().map(|t| t.fill_pct))),
+        Arc::new(StringArray::from_iter_values(trades.iter().map(|t| &t.status))),
+        Arc::new(Int64Array::from_iter(trades.iter().map(|t| t.latency_ms))),
+        Arc::new(BooleanArray::from_iter(trades.iter().map(|t| t.is_live))),
+        Arc::new(UInt32Array::from_iter(trades.iter().map(|t| t.aggregation_count))),
+        Arc::new(UInt64Array::from_iter(trades.iter().map(|t| t.aggregation_window_ms))),
+    ];
+    let batch = RecordBatch::try_new(schema.clone(), columns)
+        .context("Failed to build record batch")?;
+
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(file, schema, Some(props))
+        .context("Failed to create Parquet writer")?;
+    writer.write(&batch).context("Failed to write Parquet data")?;
+    writer.close().context("Failed to finalize Parquet file")?;
+
+    Ok(())
+}
+
 fn print_json(trades: &[pm_whale_follower::persistence::TradeRecord]) -> Result<()> {
     use serde_json::json;
 
@@ -427,8 +544,11 @@ fn print_json(trades: &[pm_whale_follower::persistence::TradeRecord]) -> Result<
     Ok(())
 }
 
-/// Print summary statistics
-fn print_summary(trades: &[pm_whale_follower::persistence::TradeRecord]) {
+/// Print summary statistics; P&L uses current prices from `enriched_data` (--refresh) if given
+fn print_summary(
+    trades: &[pm_whale_follower::persistence::TradeRecord],
+    enriched_data: Option<&HashMap<String, EnrichedData>>,
+) {
     if trades.is_empty() {
         return;
     }
@@ -470,7 +590,9 @@ fn print_summary(trades: &[pm_whale_follower::persistence::TradeRecord]) {
         println!("\nVolume:");
         println!("  Whale total: ${:.2}", total_whale_usd);
         println!("  Our total:   ${:.2}", total_our_usd);
-        println!("  Scaling:     {:.2}%", (total_our_usd / total_whale_usd) * 100.0);
+        if total_whale_usd > 0.0 {
+            println!("  Scaling:     {:.2}%", (total_our_usd / total_whale_usd) * 100.0);
+        }
     }
 
     // Calculate average latency
@@ -492,6 +614,76 @@ fn print_summary(trades: &[pm_whale_follower::persistence::TradeRecord]) {
         let avg_fill = fill_pcts.iter().sum::<f64>() / fill_pcts.len() as f64;
         println!("\nAverage fill: {:.1}%", avg_fill);
     }
+
+    let Some(pnl) = summarize_pnl(trades, enriched_data, fee_per_trade_usd()) else { return };
+    println!("\nP&L (FIFO over the trades shown):");
+    println!("  Realized:   ${:+.2}", pnl.realized);
+    match pnl.unrealized {
+        Some(unrealized) => {
+            let unpriced = if pnl.unpriced_tokens > 0 {
+                format!(", {} without a price", pnl.unpriced_tokens)
+            } else {
+                String::new()
+            };
+            println!("  Unrealized: ${:+.2} ({} open token(s){})", unrealized, pnl.open_tokens, unpriced);
+            println!("  Total:      ${:+.2}", pnl.realized + unrealized);
+        }
+        None if pnl.open_tokens == 0 => println!("  Total:      ${:+.2}", pnl.realized),
+        None => println!("  Unrealized: N/A ({} open token(s); --refresh fetches current prices)", pnl.open_tokens),
+    }
+}
+
+/// Realized and unrealized P&L of the trades shown by `print_summary`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PnlSummary {
+    /// SELLs matched against earlier BUYs among the shown trades, net of fees
+    realized: f64,
+    /// Open lots valued at the current bid (None when no open token has a price)
+    unrealized: Option<f64>,
+    /// Tokens with shares still open
+    open_tokens: usize,
+    /// Open tokens left out of `unrealized` for lack of a bid
+    unpriced_tokens: usize,
+}
+
+/// FIFO P&L of `trades` (any order) charging `fee_per_trade` USD per fill; None without fills
+fn summarize_pnl(
+    trades: &[pm_whale_follower::persistence::TradeRecord],
+    enriched_data: Option<&HashMap<String, EnrichedData>>,
+    fee_per_trade: f64,
+) -> Option<PnlSummary> {
+    let mut filled: Vec<_> = trades.iter()
+        .filter(|t| t.our_shares.is_some_and(|shares| shares > 0.0) && t.our_price.is_some())
+        .collect();
+    if filled.is_empty() {
+        return None;
+    }
+    filled.sort_by_key(|t| t.timestamp_ms);
+    let fills: Vec<Fill> = filled.iter()
+        .map(|t| Fill {
+            trader_address: t.trader_address.clone(),
+            token_id: t.token_id.clone(),
+            side: t.side.clone(),
+            shares: t.our_shares.unwrap_or_default(),
+            price: t.our_price.unwrap_or_default(),
+        })
+        .collect();
+
+    let realized = realized_pnl_by_token(&fills, fee_per_trade).values().map(|p| p.realized_pnl).sum();
+    let open = open_lots_by_token(&fills, fee_per_trade);
+    let mut unrealized = None;
+    let mut unpriced_tokens = 0;
+    for (token_id, lots) in &open {
+        match enriched_data.and_then(|e| e.get(token_id)).and_then(|e| e.current_bid) {
+            Some(bid) => {
+                let token_pnl: f64 = lots.iter().map(|lot| (bid - lot.price) * lot.shares).sum();
+                *unrealized.get_or_insert(0.0) += token_pnl;
+            }
+            None => unpriced_tokens += 1,
+        }
+    }
+
+    Some(PnlSummary { realized, unrealized, open_tokens: open.len(), unpriced_tokens })
 }
 
 #[cfg(test)]
@@ -529,6 +721,8 @@ mod tests {
                 is_live: Some(true),
                 aggregation_count: None,
                 aggregation_window_ms: None,
+                order_id: None,
+                contributing_traders: None,
             },
             TradeRecord {
                 timestamp_ms: 1704067260000, // 2024-01-01 00:01:00
@@ -549,6 +743,8 @@ mod tests {
                 is_live: Some(true),
                 aggregation_count: None,
                 aggregation_window_ms: None,
+                order_id: None,
+                contributing_traders: None,
             },
         ];
         print_table(&trades, None); // Should not panic
@@ -577,6 +773,8 @@ mod tests {
                 is_live: Some(true),
                 aggregation_count: None,
                 aggregation_window_ms: None,
+                order_id: None,
+                contributing_traders: None,
             },
         ];
         print_table(&trades, None); // Should not panic
@@ -622,6 +820,15 @@ mod tests {
         assert_eq!(filtered.len(), 2);
     }
 
+    #[test]
+    fn test_full_trader_address() {
+        let addr = "0xAbCdEf0123456789abcdef0123456789ABCDEF01";
+        assert_eq!(full_trader_address(addr), Some(addr.to_lowercase()));
+        assert_eq!(full_trader_address("trader1"), None);
+        assert_eq!(full_trader_address("0xabcdef"), None);
+        assert_eq!(full_trader_address("0xzzcdef0123456789abcdef0123456789abcdef01"), None);
+    }
+
     #[test]
     fn test_apply_filters_by_trader() {
         let trades = vec![
@@ -720,7 +927,7 @@ mod tests {
     fn test_print_summary_empty() {
         // Verify summary handles empty trades gracefully
         let trades = vec![];
-        print_summary(&trades); // Should not panic
+        print_summary(&trades, None); // Should not panic
     }
 
     #[test]
@@ -731,7 +938,7 @@ mod tests {
             create_test_trade("0xtrader2", "token2", "FAILED", 1704067260000),
             create_test_trade("0xtrader1", "token3", "SUCCESS", 1704067320000),
         ];
-        print_summary(&trades); // Should not panic
+        print_summary(&trades, None); // Should not panic
     }
 
     #[test]
@@ -742,7 +949,68 @@ mod tests {
         ];
         trades[0].side = "SELL".to_string();
         trades.push(create_test_trade("0xtrader2", "token2", "SUCCESS", 1704067260000));
-        print_summary(&trades); // Should not panic
+        print_summary(&trades, None); // Should not panic
+    }
+
+    /// Filled trade of `shares` @ `price` (newest first, as the store returns them)
+    fn filled_trade(token: &str, side: &str, shares: f64, price: f64, timestamp_ms: i64) -> TradeRecord {
+        let mut trade = create_test_trade("0xtrader1", token, "SUCCESS", timestamp_ms);
+        trade.side = side.to_string();
+        trade.our_shares = Some(shares);
+        trade.our_price = Some(price);
+        trade.our_usd = Some(shares * price);
+        trade
+    }
+
+    fn unfilled_trade(token: &str, timestamp_ms: i64) -> TradeRecord {
+        let mut trade = create_test_trade("0xtrader1", token, "FAILED", timestamp_ms);
+        trade.our_shares = None;
+        trade.our_price = None;
+        trade.our_usd = None;
+        trade
+    }
+
+    fn priced(bid: Option<f64>) -> EnrichedData {
+        EnrichedData { market_title: None, outcome: None, current_bid: bid, current_ask: bid.map(|b| b + 0.01) }
+    }
+
+    #[test]
+    fn test_summarize_pnl_realized_and_unrealized() {
+        // Newest first: 10 @ 0.40 and 10 @ 0.60 bought, 15 sold @ 0.50, 20 of token2 bought @ 0.30
+        let trades = vec![
+            filled_trade("token2", "BUY", 20.0, 0.30, 4_000),
+            filled_trade("token1", "SELL", 15.0, 0.50, 3_000),
+            filled_trade("token1", "BUY", 10.0, 0.60, 2_000),
+            filled_trade("token1", "BUY", 10.0, 0.40, 1_000),
+            unfilled_trade("token3", 5_000),
+        ];
+
+        // Realized: 10 x +0.10 and 5 x -0.10; token1 keeps 5 @ 0.60 open
+        let no_prices = summarize_pnl(&trades, None, 0.0).unwrap();
+        assert!((no_prices.realized - 0.5).abs() < 1e-9);
+        assert_eq!((no_prices.unrealized, no_prices.open_tokens, no_prices.unpriced_tokens), (None, 2, 2));
+
+        // token1 bid 0.70: 5 x +0.10; token2 has no book
+        let enriched = HashMap::from([
+            ("token1".to_string(), priced(Some(0.70))),
+            ("token2".to_string(), priced(None)),
+        ]);
+        let summary = summarize_pnl(&trades, Some(&enriched), 0.0).unwrap();
+        assert!((summary.unrealized.unwrap() - 0.5).abs() < 1e-9);
+        assert_eq!((summary.open_tokens, summary.unpriced_tokens), (2, 1));
+
+        // A 0.02 fee per fill: 3 fills into the matched shares' cost and one off the SELL
+        let with_fee = summarize_pnl(&trades, Some(&enriched), 0.02).unwrap();
+        assert!((with_fee.realized - (0.5 - 10.0 * 0.002 - 5.0 * 0.002 - 0.02)).abs() < 1e-9);
+        assert!((with_fee.unrealized.unwrap() - 5.0 * (0.10 - 0.002)).abs() < 1e-9);
+
+        print_summary(&trades, Some(&enriched)); // Should not panic
+    }
+
+    #[test]
+    fn test_summarize_pnl_without_fills() {
+        assert_eq!(summarize_pnl(&[unfilled_trade("token1", 1_000)], None, 0.0), None);
+        assert_eq!(summarize_pnl(&[], None, 0.0), None);
     }
 
     #[test]
@@ -1146,6 +1414,50 @@ mod tests {
     }
 
     // Helper function to create test trades
+    #[test]
+    fn test_write_parquet_round_trip() {
+        use arrow::array::Array;
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let mut failed = create_test_trade("0xtrader2", "token2", "FAILED", 1704067260000);
+        failed.our_shares = None;
+        failed.our_price = None;
+        failed.latency_ms = None;
+        let trades = vec![
+            create_test_trade("0xtrader1", "token1", "SUCCESS", 1704067200000),
+            failed,
+            create_test_trade("0xtrader1", "token3", "SUCCESS", 1704067320000),
+        ];
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        write_parquet(&trades, file.path()).unwrap();
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(file.path()).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<RecordBatch> = reader.collect::<std::result::Result<_, _>>().unwrap();
+        let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+        assert_eq!(rows, 3);
+
+        let batch = &batches[0];
+        assert_eq!(batch.schema().as_ref(), &parquet_schema());
+
+        let timestamps = batch.column_by_name("timestamp").unwrap()
+            .as_any().downcast_ref::<TimestampMillisecondArray>().unwrap();
+        assert_eq!(timestamps.value(1), 1704067260000);
+        assert_eq!(timestamps.timezone(), Some("UTC"));
+
+        let our_price = batch.column_by_name("our_price").unwrap()
+            .as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(our_price.value(0), 0.46);
+        assert!(our_price.is_null(1));
+
+        let tokens = batch.column_by_name("token_id").unwrap()
+            .as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(tokens.value(2), "token3");
+    }
+
     fn create_test_trade(trader: &str, token: &str, status: &str, timestamp_ms: i64) -> TradeRecord {
         TradeRecord {
             timestamp_ms,
@@ -1166,6 +1478,8 @@ mod tests {
             is_live: Some(true),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         }
     }
 }
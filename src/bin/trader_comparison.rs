@@ -8,6 +8,7 @@
 
 use anyhow::Result;
 use clap::Parser;
+use pm_whale_follower::config::traders::address_to_checksummed;
 use pm_whale_follower::persistence::TradeStore;
 
 #[derive(Parser)]
@@ -256,7 +257,7 @@ fn print_csv(stats: &[TraderStats]) {
         println!(
             "{},{},{},{},{:.1},{:.1},{:.2},{:.1}",
             stat.label,
-            stat.address,
+            address_to_checksummed(&stat.address),
             stat.observed_trades,
             stat.copied_trades,
             stat.copy_rate(),
@@ -276,7 +277,7 @@ fn print_json(stats: &[TraderStats]) -> Result<()> {
         .map(|stat| {
             json!({
                 "trader": stat.label,
-                "address": stat.address,
+                "address": address_to_checksummed(&stat.address),
                 "observed_trades": stat.observed_trades,
                 "copied_trades": stat.copied_trades,
                 "successful_trades": stat.successful_trades,
@@ -548,6 +549,8 @@ mod tests {
             is_live: Some(true),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         };
 
         let trade2 = TradeRecord {
@@ -569,6 +572,8 @@ mod tests {
             is_live: Some(true),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         };
 
         let trade3 = TradeRecord {
@@ -590,6 +595,8 @@ mod tests {
             is_live: Some(true),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         };
 
         store.insert_trade(&trade1).unwrap();
@@ -638,6 +645,8 @@ mod tests {
             is_live: Some(true),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         };
 
         let trade2 = TradeRecord {
@@ -659,6 +668,8 @@ mod tests {
             is_live: Some(true),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         };
 
         let trade3 = TradeRecord {
@@ -680,6 +691,8 @@ mod tests {
             is_live: Some(true),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         };
 
         store.insert_trade(&trade1).unwrap();
@@ -737,6 +750,8 @@ mod tests {
             is_live: Some(true),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         };
 
         let trade2 = TradeRecord {
@@ -758,6 +773,8 @@ mod tests {
             is_live: Some(true),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         };
 
         let trade3 = TradeRecord {
@@ -779,6 +796,8 @@ mod tests {
             is_live: Some(true),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         };
 
         store.insert_trade(&trade1).unwrap();
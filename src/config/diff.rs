@@ -0,0 +1,131 @@
+//! Trader configuration diffs
+//! Describes what a reload changed: traders added, removed, or with modified settings
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use super::traders::{TraderConfig, TradersConfig};
+
+/// A trader identified by address and label
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraderRef {
+    pub address: String,
+    pub label: String,
+}
+
+impl From<&TraderConfig> for TraderRef {
+    fn from(trader: &TraderConfig) -> Self {
+        Self { address: trader.address.clone(), label: trader.label.clone() }
+    }
+}
+
+/// One setting that differs between the old and new version of a trader
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// A trader present before and after the reload whose settings changed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraderModification {
+    pub address: String,
+    /// Label after the reload (a label change is also listed in `changes`)
+    pub label: String,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Differences between two trader configurations, matched by address
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TradersDiff {
+    pub added: Vec<TraderRef>,
+    pub removed: Vec<TraderRef>,
+    pub modified: Vec<TraderModification>,
+}
+
+impl TradersDiff {
+    /// Compare `old` against `new`; ordering of traders in the file is ignored
+    pub fn between(old: &TradersConfig, new: &TradersConfig) -> Self {
+        let mut diff = Self::default();
+
+        for trader in new.iter() {
+            match old.get_by_address(&trader.address) {
+                None => diff.added.push(trader.into()),
+                Some(previous) => {
+                    let changes = field_changes(previous, trader);
+                    if !changes.is_empty() {
+                        diff.modified.push(TraderModification {
+                            address: trader.address.clone(),
+                            label: trader.label.clone(),
+                            changes,
+                        });
+                    }
+                }
+            }
+        }
+        diff.removed = old.iter()
+            .filter(|t| new.get_by_address(&t.address).is_none())
+            .map(TraderRef::from)
+            .collect();
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+
+    /// One-line summary for logs, e.g. `+1 added (Whale2); ~1 modified (Whale1: enabled true -> false)`
+    pub fn summary(&self) -> String {
+        if self.is_empty() {
+            return "no changes".to_string();
+        }
+
+        let labels = |traders: &[TraderRef]| {
+            traders.iter().map(|t| t.label.as_str()).collect::<Vec<_>>().join(", ")
+        };
+        let mut parts = Vec::new();
+        if !self.added.is_empty() {
+            parts.push(format!("+{} added ({})", self.added.len(), labels(&self.added)));
+        }
+        if !self.removed.is_empty() {
+            parts.push(format!("-{} removed ({})", self.removed.len(), labels(&self.removed)));
+        }
+        if !self.modified.is_empty() {
+            let details = self.modified.iter()
+                .map(|m| {
+                    let fields = m.changes.iter()
+                        .map(|c| format!("{} {} -> {}", c.field, c.old, c.new))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    format!("{}: {}", m.label, fields)
+                })
+                .collect::<Vec<_>>()
+                .join("; ");
+            parts.push(format!("~{} modified ({})", self.modified.len(), details));
+        }
+        parts.join("; ")
+    }
+}
+
+/// Settings that differ between two versions of the same trader
+fn field_changes(old: &TraderConfig, new: &TraderConfig) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    let mut compare = |field: &str, old: Value, new: Value| {
+        if old != new {
+            changes.push(FieldChange { field: field.to_string(), old, new });
+        }
+    };
+
+    compare("label", old.label.clone().into(), new.label.clone().into());
+    compare("enabled", old.enabled.into(), new.enabled.into());
+    compare("scaling_ratio", old.scaling_ratio.into(), new.scaling_ratio.into());
+    compare("min_shares", old.min_shares.into(), new.min_shares.into());
+    compare("max_bet_usd", old.max_bet_usd.into(), new.max_bet_usd.into());
+    compare("probabilistic_sizing", old.probabilistic_sizing.into(), new.probabilistic_sizing.into());
+    compare("min_copy_price", old.min_copy_price.into(), new.min_copy_price.into());
+    compare("max_copy_price", old.max_copy_price.into(), new.max_copy_price.into());
+    compare("observe", old.observe.into(), new.observe.into());
+
+    changes
+}
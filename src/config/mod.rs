@@ -3,6 +3,7 @@
 
 pub mod traders;
 pub mod reloadable;
+pub mod diff;
 
 #[cfg(test)]
 mod tests {
@@ -111,6 +112,40 @@ mod tests {
         assert_eq!(result.unwrap(), "abc123def456789012345678901234567890abcd");
     }
 
+    // =========================================================================
+    // Test Suite: EIP-55 Checksums
+    // =========================================================================
+
+    #[test]
+    fn test_strict_address_valid_checksum() {
+        let result = validate_and_normalize_address_strict("0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359");
+        assert_eq!(result.unwrap(), "fb6916095ca1df60bb79ce92ce3ea74c37c5d359");
+    }
+
+    #[test]
+    fn test_strict_address_invalid_checksum() {
+        // Last character's case flipped
+        let result = validate_and_normalize_address_strict("0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d35A");
+        assert!(result.unwrap_err().contains("checksum"));
+        // The lenient check still accepts it
+        assert!(validate_and_normalize_address("0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d35A").is_ok());
+    }
+
+    #[test]
+    fn test_strict_address_accepts_single_case() {
+        let result = validate_and_normalize_address_strict("0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359");
+        assert_eq!(result.unwrap(), "fb6916095ca1df60bb79ce92ce3ea74c37c5d359");
+        assert!(validate_and_normalize_address_strict("0xFB6916095CA1DF60BB79CE92CE3EA74C37C5D359").is_ok());
+    }
+
+    #[test]
+    fn test_address_to_checksummed() {
+        let expected = "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359";
+        assert_eq!(address_to_checksummed("fb6916095ca1df60bb79ce92ce3ea74c37c5d359"), expected);
+        assert_eq!(address_to_checksummed(" 0XFB6916095CA1DF60BB79CE92CE3EA74C37C5D359 "), expected);
+        assert_eq!(address_to_checksummed("0x123"), "0x123");
+    }
+
     // =========================================================================
     // Test Suite: Topic Hex Generation
     // =========================================================================
@@ -613,6 +648,103 @@ mod tests {
         assert_eq!(trader.scaling_ratio, 0.02); // default
         assert_eq!(trader.min_shares, 0.0); // default
         assert!(trader.enabled); // default
+        assert_eq!(trader.max_bet_usd, None); // default
+    }
+
+    #[test]
+    fn test_from_file_with_max_bet_usd() {
+        use std::io::Write;
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        let json = r#"[
+            {
+                "address": "abc123def456789012345678901234567890abcd",
+                "max_bet_usd": 25.0
+            }
+        ]"#;
+        file.write_all(json.as_bytes()).unwrap();
+        file.flush().unwrap();
+
+        let config = TradersConfig::from_file(file.path()).unwrap();
+        let trader = config.iter().next().unwrap();
+        assert_eq!(trader.max_bet_usd, Some(25.0));
+    }
+
+    #[test]
+    fn test_from_file_with_probabilistic_sizing() {
+        use std::io::Write;
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        let json = r#"[
+            {
+                "address": "abc123def456789012345678901234567890abcd",
+                "probabilistic_sizing": false
+            },
+            {
+                "address": "def456abc123789012345678901234567890abcd"
+            }
+        ]"#;
+        file.write_all(json.as_bytes()).unwrap();
+        file.flush().unwrap();
+
+        let config = TradersConfig::from_file(file.path()).unwrap();
+        let mut traders = config.iter();
+        assert_eq!(traders.next().unwrap().probabilistic_sizing, Some(false));
+        assert_eq!(traders.next().unwrap().probabilistic_sizing, None); // default: global setting
+    }
+
+    #[test]
+    fn test_from_file_with_copy_price_range() {
+        use std::io::Write;
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        let json = r#"[
+            {
+                "address": "abc123def456789012345678901234567890abcd",
+                "label": "Banded",
+                "min_copy_price": 0.10,
+                "max_copy_price": 0.90
+            },
+            {
+                "address": "def456abc123789012345678901234567890abcd",
+                "label": "Open"
+            }
+        ]"#;
+        file.write_all(json.as_bytes()).unwrap();
+        file.flush().unwrap();
+
+        let config = TradersConfig::from_file(file.path()).unwrap();
+        let mut traders = config.iter();
+        let banded = traders.next().unwrap().copy_price_range();
+        assert_eq!((banded.min, banded.max), (Some(0.10), Some(0.90)));
+        // default: global MIN_COPY_PRICE / MAX_COPY_PRICE
+        assert_eq!(traders.next().unwrap().copy_price_range(), Default::default());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_from_file_with_observe() {
+        use std::io::Write;
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        let json = r#"[
+            {"address": "abc123def456789012345678901234567890abcd", "label": "Candidate", "observe": true},
+            {"address": "def456abc123789012345678901234567890abcd", "label": "Copied"}
+        ]"#;
+        file.write_all(json.as_bytes()).unwrap();
+        file.flush().unwrap();
+
+        let config = TradersConfig::from_file(file.path()).unwrap();
+        let observe: Vec<bool> = config.iter().map(|t| t.observe).collect();
+        assert_eq!(observe, vec![true, false]); // default: copied
+        // Observed traders stay in the topic filter
+        assert_eq!(config.build_topic_filter().len(), 2);
+    }
+
+    #[test]
+    fn test_validate_rejects_inverted_copy_price_range() {
+        let mut trader = TraderConfig::new("abc123def456789012345678901234567890abcd", "Whale1").unwrap();
+        trader.min_copy_price = Some(0.90);
+        trader.max_copy_price = Some(0.10);
+
+        let err = TradersConfig::new(vec![trader]).validate().unwrap_err();
+        assert!(err.contains("Whale1") && err.contains("above max copy price"), "{}", err);
     }
 
     #[test]
@@ -768,4 +900,179 @@ mod tests {
         }
         assert_eq!(count, 3);
     }
+
+    // =========================================================================
+    // Test Suite: TOML File Loading
+    // =========================================================================
+
+    fn write_temp_file(suffix: &str, contents: &str) -> tempfile::NamedTempFile {
+        use std::io::Write;
+        let mut file = tempfile::Builder::new().suffix(suffix).tempfile().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file.flush().unwrap();
+        file
+    }
+
+    #[test]
+    fn test_from_toml_file_matches_equivalent_json() {
+        let json = write_temp_file(".json", r#"[
+            {
+                "address": "0xABC123def456789012345678901234567890abcd",
+                "label": "Whale1",
+                "scaling_ratio": 0.03,
+                "min_shares": 100.0,
+                "enabled": true,
+                "max_bet_usd": 40.0
+            },
+            {
+                "address": "def456abc123789012345678901234567890abcd",
+                "label": "Whale2",
+                "enabled": false
+            },
+            {
+                "address": "abc123def456789012345678901234567890abcd",
+                "label": "Duplicate"
+            }
+        ]"#);
+        let toml = write_temp_file(".toml", r#"
+            # Comments are the main reason to prefer TOML
+            [[trader]]
+            address = "0xABC123def456789012345678901234567890abcd"
+            label = "Whale1"
+            scaling_ratio = 0.03
+            min_shares = 100.0
+            enabled = true
+            max_bet_usd = 40.0
+
+            [[trader]]
+            address = "def456abc123789012345678901234567890abcd"
+            label = "Whale2"
+            enabled = false
+
+            [[trader]]
+            address = "abc123def456789012345678901234567890abcd"
+            label = "Duplicate"
+        "#);
+
+        let from_json = TradersConfig::from_file(json.path()).unwrap();
+        let from_toml = TradersConfig::from_toml_file(toml.path()).unwrap();
+        assert_eq!(from_json, from_toml);
+        assert_eq!(from_toml.len(), 2); // duplicate skipped
+    }
+
+    #[test]
+    fn test_from_path_detects_extension() {
+        let toml = write_temp_file(".toml", r#"
+            [[trader]]
+            address = "abc123def456789012345678901234567890abcd"
+        "#);
+        let json = write_temp_file(".json", r#"[{"address": "abc123def456789012345678901234567890abcd"}]"#);
+
+        let from_toml = TradersConfig::from_path(toml.path()).unwrap();
+        let from_json = TradersConfig::from_path(json.path()).unwrap();
+        assert_eq!(from_toml, from_json);
+        assert_eq!(from_toml.iter().next().unwrap().label, "Trader"); // default
+    }
+
+    #[test]
+    fn test_from_toml_file_errors() {
+        let empty = write_temp_file(".toml", "# no traders\n");
+        let err = TradersConfig::from_toml_file(empty.path()).unwrap_err();
+        assert!(err.contains("no trader configurations"));
+
+        let invalid = write_temp_file(".toml", "[[trader]]\naddress = \"0x123\"\n");
+        let err = TradersConfig::from_toml_file(invalid.path()).unwrap_err();
+        assert!(err.contains("Invalid address at index 0"));
+
+        let malformed = write_temp_file(".toml", "[[trader]\n");
+        let err = TradersConfig::from_toml_file(malformed.path()).unwrap_err();
+        assert!(err.contains("Failed to parse TOML"));
+    }
+
+    // =========================================================================
+    // Test Suite: Reload Diff
+    // =========================================================================
+
+    const WHALE1: &str = "abc123def456789012345678901234567890abcd";
+    const WHALE2: &str = "def456abc123789012345678901234567890abcd";
+    const WHALE3: &str = "1111111111111111111111111111111111111111";
+
+    fn trader(address: &str, label: &str) -> TraderConfig {
+        TraderConfig::new(address, label).unwrap()
+    }
+
+    #[test]
+    fn test_diff_identical_configs_is_empty() {
+        let old = TradersConfig::new(vec![trader(WHALE1, "Whale1"), trader(WHALE2, "Whale2")]);
+        // Reordering the file is not a change
+        let new = TradersConfig::new(vec![trader(WHALE2, "Whale2"), trader(WHALE1, "Whale1")]);
+
+        let diff = super::diff::TradersDiff::between(&old, &new);
+        assert!(diff.is_empty());
+        assert_eq!(diff.summary(), "no changes");
+    }
+
+    #[test]
+    fn test_diff_detects_added_and_removed() {
+        let old = TradersConfig::new(vec![trader(WHALE1, "Whale1"), trader(WHALE2, "Whale2")]);
+        let new = TradersConfig::new(vec![trader(WHALE1, "Whale1"), trader(WHALE3, "Whale3")]);
+
+        let diff = super::diff::TradersDiff::between(&old, &new);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].address, WHALE3);
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].label, "Whale2");
+        assert!(diff.modified.is_empty());
+        assert_eq!(diff.summary(), "+1 added (Whale3); -1 removed (Whale2)");
+    }
+
+    #[test]
+    fn test_diff_detects_enabled_flip_and_scaling_change() {
+        let mut before = trader(WHALE1, "Whale1");
+        before.scaling_ratio = 0.02;
+        let mut after = before.clone();
+        after.enabled = false;
+        after.scaling_ratio = 0.05;
+        let untouched = trader(WHALE2, "Whale2");
+
+        let old = TradersConfig::new(vec![before, untouched.clone()]);
+        let new = TradersConfig::new(vec![after, untouched]);
+
+        let diff = super::diff::TradersDiff::between(&old, &new);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        assert_eq!(diff.modified.len(), 1);
+
+        let modified = &diff.modified[0];
+        assert_eq!(modified.address, WHALE1);
+        let fields: Vec<(&str, String, String)> = modified.changes.iter()
+            .map(|c| (c.field.as_str(), c.old.to_string(), c.new.to_string()))
+            .collect();
+        assert_eq!(fields, vec![
+            ("enabled", "true".to_string(), "false".to_string()),
+            ("scaling_ratio", "0.02".to_string(), "0.05".to_string()),
+        ]);
+        assert_eq!(
+            diff.summary(),
+            "~1 modified (Whale1: enabled true -> false, scaling_ratio 0.02 -> 0.05)"
+        );
+    }
+
+    #[test]
+    fn test_diff_reports_optional_fields_and_serializes() {
+        let before = trader(WHALE1, "Whale1");
+        let mut after = trader(WHALE1, "Whale1");
+        after.max_bet_usd = Some(50.0);
+        after.probabilistic_sizing = Some(false);
+
+        let diff = super::diff::TradersDiff::between(
+            &TradersConfig::new(vec![before]),
+            &TradersConfig::new(vec![after]),
+        );
+        let json = serde_json::to_value(&diff).unwrap();
+        assert_eq!(json["added"], serde_json::json!([]));
+        assert_eq!(json["modified"][0]["changes"], serde_json::json!([
+            { "field": "max_bet_usd", "old": null, "new": 50.0 },
+            { "field": "probabilistic_sizing", "old": null, "new": false },
+        ]));
+    }
 }
@@ -3,6 +3,7 @@
 
 use std::sync::Arc;
 use tokio::sync::{RwLock, watch};
+use super::diff::TradersDiff;
 use super::traders::TradersConfig;
 
 /// Shared, reloadable traders configuration
@@ -37,25 +38,31 @@ impl ReloadableTraders {
     }
 
     /// Reloads the configuration from disk/environment
-    /// Returns Ok(true) if the configuration changed, Ok(false) if unchanged
-    pub async fn reload(&self) -> Result<bool, String> {
-        let current = self.config.read().await;
-        let (new_config, changed) = current.reload()?;
-        drop(current);
+    /// Returns what changed; an empty diff leaves the config (and generation) untouched
+    pub async fn reload(&self) -> Result<TradersDiff, String> {
+        self.apply(TradersConfig::load()?).await
+    }
 
-        if changed {
-            let mut write_guard = self.config.write().await;
-            *write_guard = new_config;
-            drop(write_guard);
+    /// Validates `new_config` and swaps it in if it differs from the current one
+    ///
+    /// All-or-nothing: if validation fails the previous config stays active, the
+    /// generation counter is not incremented and the error lists every problem found.
+    pub async fn apply(&self, new_config: TradersConfig) -> Result<TradersDiff, String> {
+        new_config.validate()
+            .map_err(|e| format!("Invalid trader configuration, keeping the current one:\n{}", e))?;
+
+        // Hold the write lock across diff and swap so concurrent reloads can't interleave
+        let mut current = self.config.write().await;
+        let diff = TradersDiff::between(&current, &new_config);
+        if !diff.is_empty() {
+            *current = new_config;
+            drop(current);
 
             // Notify subscribers of the change
             let new_gen = *self.change_rx.borrow() + 1;
             let _ = self.change_tx.send(new_gen);
-
-            Ok(true)
-        } else {
-            Ok(false)
         }
+        Ok(diff)
     }
 
     /// Returns a receiver that notifies when config changes
@@ -101,4 +108,62 @@ mod tests {
         let mut rx = reloadable.subscribe();
         assert_eq!(*rx.borrow(), 0);
     }
+
+    #[tokio::test]
+    async fn test_apply_swaps_valid_config_and_bumps_generation() {
+        let reloadable = ReloadableTraders::new(create_test_config());
+        let added = TraderConfig::new("def456abc123789012345678901234567890abcd", "Second").unwrap();
+        let mut traders: Vec<TraderConfig> = reloadable.read().await.iter().cloned().collect();
+        traders.push(added);
+
+        let diff = reloadable.apply(TradersConfig::new(traders)).await.unwrap();
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(reloadable.read().await.len(), 2);
+        assert_eq!(reloadable.generation(), 1);
+
+        // Same config again: no swap, no new generation
+        let same = reloadable.read().await.clone();
+        assert!(reloadable.apply(same).await.unwrap().is_empty());
+        assert_eq!(reloadable.generation(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_reload_keeps_previous_config() {
+        let reloadable = ReloadableTraders::new(create_test_config());
+        let before = reloadable.read().await.clone();
+        let topic_filter = before.build_topic_filter();
+
+        let mut bad_ratio = TraderConfig::new("def456abc123789012345678901234567890abcd", "Second").unwrap();
+        bad_ratio.scaling_ratio = 5.0;
+        let mut bad_address = TraderConfig::new("1111111111111111111111111111111111111111", "Third").unwrap();
+        bad_address.address = "0xnot-an-address".to_string();
+        let duplicate_label = TraderConfig::new("2222222222222222222222222222222222222222", "testtrader").unwrap();
+
+        let invalid = [
+            TradersConfig::new(vec![bad_ratio]),
+            TradersConfig::new(vec![bad_address]),
+            TradersConfig::new(vec![before.iter().next().unwrap().clone(), duplicate_label]),
+            TradersConfig::new(vec![]),
+        ];
+        for config in invalid {
+            let err = reloadable.apply(config).await.unwrap_err();
+            assert!(err.starts_with("Invalid trader configuration"), "{}", err);
+        }
+
+        assert_eq!(*reloadable.read().await, before);
+        assert_eq!(reloadable.read().await.build_topic_filter(), topic_filter);
+        assert_eq!(reloadable.generation(), 0);
+    }
+
+    #[test]
+    fn test_validate_reports_each_problem() {
+        let mut first = TraderConfig::new("abc123def456789012345678901234567890abcd", "Whale").unwrap();
+        first.scaling_ratio = 0.0;
+        let second = TraderConfig::new("def456abc123789012345678901234567890abcd", "WHALE").unwrap();
+
+        let err = TradersConfig::new(vec![first, second]).validate().unwrap_err();
+        assert!(err.contains("scaling_ratio 0 must be greater than 0"), "{}", err);
+        assert!(err.contains("duplicate label \"WHALE\""), "{}", err);
+        assert_eq!(err.lines().count(), 2);
+    }
 }
@@ -6,8 +6,11 @@ use std::env;
 use std::fs;
 use std::path::Path;
 use serde::{Deserialize, Serialize};
+use super::diff::TradersDiff;
+use crate::settings::CopyPriceRange;
 
 /// JSON representation of trader configuration for file parsing
+/// (also used for each `[[trader]]` table in TOML files)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TraderConfigJson {
     pub address: String,
@@ -19,6 +22,23 @@ pub struct TraderConfigJson {
     pub min_shares: f64,
     #[serde(default = "default_enabled")]
     pub enabled: bool,
+    #[serde(default)]
+    pub max_bet_usd: Option<f64>,
+    #[serde(default)]
+    pub probabilistic_sizing: Option<bool>,
+    #[serde(default)]
+    pub min_copy_price: Option<f64>,
+    #[serde(default)]
+    pub max_copy_price: Option<f64>,
+    #[serde(default)]
+    pub observe: bool,
+}
+
+/// TOML file layout: an array of `[[trader]]` tables
+#[derive(Debug, Deserialize)]
+struct TradersToml {
+    #[serde(default)]
+    trader: Vec<TraderConfigJson>,
 }
 
 fn default_label() -> String {
@@ -26,7 +46,7 @@ fn default_label() -> String {
 }
 
 fn default_scaling_ratio() -> f64 {
-    0.02
+    crate::settings::default_scaling_ratio()
 }
 
 fn default_enabled() -> bool {
@@ -34,7 +54,7 @@ fn default_enabled() -> bool {
 }
 
 /// Configuration for a single trader to monitor
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TraderConfig {
     /// Normalized 40-character hex address (no 0x prefix, lowercase)
     pub address: String,
@@ -42,12 +62,23 @@ pub struct TraderConfig {
     pub label: String,
     /// Zero-padded 64-character hex for WebSocket topic filtering
     pub topic_hex: String,
-    /// Per-trader scaling ratio for position sizing (default: 0.02)
+    /// Per-trader scaling ratio for position sizing (default: SCALING_RATIO env, else 0.02)
     pub scaling_ratio: f64,
     /// Minimum whale shares required to copy this trader's trades (default: 0.0)
     pub min_shares: f64,
     /// Whether this trader is enabled for monitoring (default: true)
     pub enabled: bool,
+    /// Maximum USD per copied trade for this trader (default: None = no cap)
+    pub max_bet_usd: Option<f64>,
+    /// Round sub-floor copies up probabilistically (true) or always to the floor (false)
+    /// (default: None = global USE_PROBABILISTIC_SIZING)
+    pub probabilistic_sizing: Option<bool>,
+    /// Skip this trader's fills priced below this (default: None = global MIN_COPY_PRICE)
+    pub min_copy_price: Option<f64>,
+    /// Skip this trader's fills priced above this (default: None = global MAX_COPY_PRICE)
+    pub max_copy_price: Option<f64>,
+    /// Observe only: record this trader's fills as OBSERVED but never copy them (default: false)
+    pub observe: bool,
 }
 
 impl TraderConfig {
@@ -68,11 +99,21 @@ impl TraderConfig {
             address: normalized_address,
             label: label.to_string(),
             topic_hex,
-            scaling_ratio: 0.02,
+            scaling_ratio: default_scaling_ratio(),
             min_shares: 0.0,
             enabled: true,
+            max_bet_usd: None,
+            probabilistic_sizing: None,
+            min_copy_price: None,
+            max_copy_price: None,
+            observe: false,
         })
     }
+
+    /// This trader's copy price bounds (unset bounds fall back to the global ones)
+    pub fn copy_price_range(&self) -> CopyPriceRange {
+        CopyPriceRange { min: self.min_copy_price, max: self.max_copy_price }
+    }
 }
 
 /// Validates and normalizes an Ethereum address
@@ -127,6 +168,72 @@ pub fn validate_and_normalize_address(input: &str) -> Result<String, String> {
     Ok(without_prefix.to_lowercase())
 }
 
+/// Like [`validate_and_normalize_address`], but a `0x`-prefixed mixed-case address must carry
+/// a valid EIP-55 checksum, so a typo in a checksummed address is caught instead of copied
+///
+/// All-lowercase and all-uppercase input has no checksum to check and is accepted as before.
+///
+/// # Examples
+/// ```
+/// use pm_whale_follower::config::traders::validate_and_normalize_address_strict;
+///
+/// let addr = validate_and_normalize_address_strict("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").unwrap();
+/// assert_eq!(addr, "5aaeb6053f3e94c9b9a09f33669435e7ef1beaed");
+/// assert!(validate_and_normalize_address_strict("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD").is_err());
+/// ```
+pub fn validate_and_normalize_address_strict(input: &str) -> Result<String, String> {
+    let normalized = validate_and_normalize_address(input)?;
+    let trimmed = input.trim();
+    let Some(hex) = trimmed.strip_prefix("0x") else { return Ok(normalized) };
+    let mixed_case = hex.chars().any(|c| c.is_ascii_lowercase()) && hex.chars().any(|c| c.is_ascii_uppercase());
+    if mixed_case {
+        let expected = address_to_checksummed(&normalized);
+        if expected != trimmed {
+            return Err(format!("Address checksum mismatch (EIP-55): expected {}", expected));
+        }
+    }
+    Ok(normalized)
+}
+
+/// Whether config loading checks EIP-55 checksums (STRICT_ADDRESS_CHECKSUM=true)
+fn strict_checksum_enabled() -> bool {
+    env::var("STRICT_ADDRESS_CHECKSUM")
+        .is_ok_and(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+}
+
+/// Validates a configured trader address, strictly if STRICT_ADDRESS_CHECKSUM is set
+fn validate_config_address(input: &str) -> Result<String, String> {
+    if strict_checksum_enabled() {
+        validate_and_normalize_address_strict(input)
+    } else {
+        validate_and_normalize_address(input)
+    }
+}
+
+/// EIP-55 checksummed `0x` form of an address, for display in logs and CLI output
+///
+/// Anything that isn't a valid address is returned trimmed but otherwise unchanged.
+/// Topic matching and lookups keep using the lowercase form.
+///
+/// # Examples
+/// ```
+/// use pm_whale_follower::config::traders::address_to_checksummed;
+///
+/// assert_eq!(
+///     address_to_checksummed("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"),
+///     "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+/// );
+/// ```
+pub fn address_to_checksummed(address: &str) -> String {
+    match validate_and_normalize_address(address) {
+        Ok(normalized) => normalized
+            .parse::<alloy::primitives::Address>()
+            .map(|a| a.to_checksum(None))
+            .unwrap_or_else(|_| format!("0x{}", normalized)),
+        Err(_) => address.trim().to_string(),
+    }
+}
+
 /// Converts a normalized 40-character address to a 66-character topic hex for WebSocket filtering
 ///
 /// # Arguments
@@ -151,7 +258,7 @@ pub fn address_to_topic_hex(address: &str) -> String {
 }
 
 /// Configuration for multiple traders to monitor
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TradersConfig {
     traders: Vec<TraderConfig>,
     topic_map: HashMap<String, usize>, // topic_hex -> index in traders vec
@@ -177,11 +284,13 @@ impl TradersConfig {
     }
 
     /// Reloads trader configuration from the same sources as load()
-    /// Returns the new config and a bool indicating if it changed
-    pub fn reload(&self) -> Result<(Self, bool), String> {
+    /// Returns the new config and what changed relative to this one
+    /// (any trader setting, not just the address list); fails if the new config is invalid
+    pub fn reload(&self) -> Result<(Self, TradersDiff), String> {
         let new_config = Self::load()?;
-        let changed = self.build_topic_filter() != new_config.build_topic_filter();
-        Ok((new_config, changed))
+        new_config.validate()?;
+        let diff = TradersDiff::between(self, &new_config);
+        Ok((new_config, diff))
     }
 
     /// Returns the number of configured traders
@@ -195,7 +304,7 @@ impl TradersConfig {
     }
 
     /// Builds a vector of topic hex strings for WebSocket subscription filtering
-    /// Only includes enabled traders
+    /// Only includes enabled traders (observed ones included, so their fills are still seen)
     pub fn build_topic_filter(&self) -> Vec<String> {
         self.traders
             .iter()
@@ -225,6 +334,48 @@ impl TradersConfig {
         self.traders.iter()
     }
 
+    /// Checks the whole configuration before it is used or swapped in on reload:
+    /// at least one trader, normalized addresses, unique labels (case-insensitive)
+    /// and scaling_ratio in (0, 1]
+    ///
+    /// # Returns
+    /// * `Err(String)` - Every problem found, one per line
+    pub fn validate(&self) -> Result<(), String> {
+        let mut errors = Vec::new();
+        if self.traders.is_empty() {
+            errors.push("no traders configured".to_string());
+        }
+
+        let mut labels: HashMap<String, &str> = HashMap::new();
+        for trader in &self.traders {
+            match validate_and_normalize_address(&trader.address) {
+                Ok(normalized) if normalized == trader.address && trader.topic_hex == address_to_topic_hex(&normalized) => {}
+                Ok(_) => errors.push(format!("{} ({}): address is not normalized", trader.label, trader.address)),
+                Err(e) => errors.push(format!("{} ({}): {}", trader.label, trader.address, e)),
+            }
+
+            if let Some(first) = labels.insert(trader.label.to_lowercase(), &trader.address) {
+                errors.push(format!(
+                    "duplicate label \"{}\" ({} and {}); give each trader a distinct label",
+                    trader.label, first, trader.address
+                ));
+            }
+
+            if !(trader.scaling_ratio > 0.0 && trader.scaling_ratio <= 1.0) {
+                errors.push(format!(
+                    "{} ({}): scaling_ratio {} must be greater than 0 and at most 1",
+                    trader.label, trader.address, trader.scaling_ratio
+                ));
+            }
+
+            if let Err(e) = trader.copy_price_range().validate() {
+                errors.push(format!("{} ({}): {}", trader.label, trader.address, e));
+            }
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors.join("\n")) }
+    }
+
     /// Parses trader addresses from TRADER_ADDRESSES environment variable
     ///
     /// Format: comma-separated addresses (with optional 0x prefix)
@@ -254,7 +405,7 @@ impl TradersConfig {
             }
 
             // Validate and normalize address
-            let normalized = validate_and_normalize_address(addr)
+            let normalized = validate_config_address(addr)
                 .map_err(|e| format!("Invalid address at position {}: {} - {}", idx + 1, addr, e))?;
 
             // Skip duplicates
@@ -278,7 +429,7 @@ impl TradersConfig {
     /// Loads trader configuration with fallback chain:
     /// 1. Try TRADER_ADDRESSES environment variable (new multi-trader format)
     /// 2. Try TARGET_WHALE_ADDRESS environment variable (legacy single trader)
-    /// 3. Try traders.json file (if no env vars are set)
+    /// 3. Try traders.json, then traders.toml (if no env vars are set)
     /// 4. Error if none of the above are available
     ///
     /// # Returns
@@ -295,7 +446,7 @@ impl TradersConfig {
         // Priority 2: Try TARGET_WHALE_ADDRESS env var (legacy)
         if let Ok(legacy_address) = env::var("TARGET_WHALE_ADDRESS") {
             if !legacy_address.trim().is_empty() {
-                let normalized = validate_and_normalize_address(&legacy_address)
+                let normalized = validate_config_address(&legacy_address)
                     .map_err(|e| format!("Invalid TARGET_WHALE_ADDRESS: {}", e))?;
 
                 let config = TraderConfig::new(&normalized, "Legacy")?;
@@ -303,10 +454,12 @@ impl TradersConfig {
             }
         }
 
-        // Priority 3: Try traders.json file (only if no env vars are set)
-        let json_path = Path::new("traders.json");
-        if json_path.exists() {
-            return Self::from_file(json_path);
+        // Priority 3: Try traders.json / traders.toml (only if no env vars are set)
+        for file in ["traders.json", "traders.toml"] {
+            let path = Path::new(file);
+            if path.exists() {
+                return Self::from_path(path);
+            }
         }
 
         // No configuration found
@@ -314,7 +467,7 @@ impl TradersConfig {
             "No trader configuration found. Either:\n\
              1. Set TRADER_ADDRESSES environment variable (comma-separated addresses)\n\
              2. Set TARGET_WHALE_ADDRESS environment variable (single address)\n\
-             3. Create a traders.json file with trader configurations".to_string()
+             3. Create a traders.json or traders.toml file with trader configurations".to_string()
         )
     }
 
@@ -328,7 +481,8 @@ impl TradersConfig {
     ///     "label": "Whale1",
     ///     "scaling_ratio": 0.02,
     ///     "min_shares": 100.0,
-    ///     "enabled": true
+    ///     "enabled": true,
+    ///     "max_bet_usd": 50.0
     ///   }
     /// ]
     /// ```
@@ -354,14 +508,69 @@ impl TradersConfig {
             return Err("JSON file contains no trader configurations".to_string());
         }
 
-        // Convert JSON configs to TraderConfig, validating addresses and deduplicating
+        Self::from_entries(json_configs)
+    }
+
+    /// Loads trader configuration from a TOML file
+    ///
+    /// Expected TOML format:
+    /// ```toml
+    /// [[trader]]
+    /// address = "abc123..."
+    /// label = "Whale1"
+    /// scaling_ratio = 0.02
+    /// min_shares = 100.0
+    /// enabled = true
+    /// ```
+    ///
+    /// # Arguments
+    /// * `path` - Path to the TOML file
+    ///
+    /// # Returns
+    /// * `Ok(TradersConfig)` - Loaded configuration
+    /// * `Err(String)` - Error if file cannot be read or parsed
+    pub fn from_toml_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+
+        // Read file contents
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read file {}: {}", path.display(), e))?;
+
+        // Parse TOML
+        let parsed: TradersToml = toml::from_str(&contents)
+            .map_err(|e| format!("Failed to parse TOML: {}", e))?;
+
+        if parsed.trader.is_empty() {
+            return Err("TOML file contains no trader configurations".to_string());
+        }
+
+        Self::from_entries(parsed.trader)
+    }
+
+    /// Loads trader configuration from a file, picking the parser by extension
+    /// (`.toml` uses TOML, anything else is parsed as JSON)
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let is_toml = path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+
+        if is_toml {
+            Self::from_toml_file(path)
+        } else {
+            Self::from_file(path)
+        }
+    }
+
+    /// Converts parsed file entries to TradersConfig, validating addresses and deduplicating
+    fn from_entries(entries: Vec<TraderConfigJson>) -> Result<Self, String> {
         let mut seen = HashSet::new();
         let mut traders = Vec::new();
 
-        for (idx, json_config) in json_configs.iter().enumerate() {
+        for (idx, entry) in entries.iter().enumerate() {
             // Validate and normalize address
-            let normalized = validate_and_normalize_address(&json_config.address)
-                .map_err(|e| format!("Invalid address at index {}: {} - {}", idx, json_config.address, e))?;
+            let normalized = validate_config_address(&entry.address)
+                .map_err(|e| format!("Invalid address at index {}: {} - {}", idx, entry.address, e))?;
 
             // Skip duplicates
             if !seen.insert(normalized.clone()) {
@@ -369,10 +578,15 @@ impl TradersConfig {
             }
 
             // Build TraderConfig
-            let mut config = TraderConfig::new(&normalized, &json_config.label)?;
-            config.scaling_ratio = json_config.scaling_ratio;
-            config.min_shares = json_config.min_shares;
-            config.enabled = json_config.enabled;
+            let mut config = TraderConfig::new(&normalized, &entry.label)?;
+            config.scaling_ratio = entry.scaling_ratio;
+            config.min_shares = entry.min_shares;
+            config.enabled = entry.enabled;
+            config.max_bet_usd = entry.max_bet_usd;
+            config.probabilistic_sizing = entry.probabilistic_sizing;
+            config.min_copy_price = entry.min_copy_price;
+            config.max_copy_price = entry.max_copy_price;
+            config.observe = entry.observe;
 
             traders.push(config);
         }
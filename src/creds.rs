@@ -0,0 +1,307 @@
+//! Shared CLOB API credentials with re-derivation on auth failure
+//!
+//! The order worker, resubmitter and shutdown canceller all sign with the same
+//! credentials. If the exchange starts answering 401 (expired or revoked API key),
+//! the first worker to see it re-derives the key, rewrites the creds file and swaps
+//! the new credentials in for everyone. Re-derivation is rate limited so a bad key
+//! can't turn every order into a derive-api-key call.
+//!
+//! The creds file records the signer its key was derived for, so switching to another
+//! key (EXECUTION_PRIVATE_KEY) derives a new API key instead of reusing the old one.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::{ApiCreds, PreparedCreds};
+
+/// Minimum time between two re-derivations
+pub const CREDS_REFRESH_MIN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Creds file contents: the API key plus the signer it belongs to
+#[derive(Serialize, Deserialize)]
+struct StoredCreds {
+    #[serde(flatten)]
+    creds: ApiCreds,
+    /// Missing in files written before the signer was recorded
+    #[serde(default)]
+    address: Option<String>,
+}
+
+/// Write `creds` to `path`, recording `signer` when known
+fn write_creds(path: &Path, signer: Option<&str>, creds: &ApiCreds) -> Result<()> {
+    let stored = StoredCreds { creds: creds.clone(), address: signer.map(str::to_string) };
+    std::fs::write(path, serde_json::to_string_pretty(&stored)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Credentials for `signer` from `path`, or freshly derived (and saved) when the file is
+/// missing, unreadable, or was written for another signer
+pub fn load_or_derive(path: &Path, signer: &str, derive: impl FnOnce() -> Result<ApiCreds>) -> Result<ApiCreds> {
+    let stored = std::fs::read_to_string(path).ok()
+        .and_then(|data| serde_json::from_str::<StoredCreds>(&data).ok());
+    match stored {
+        Some(stored) if stored.address.as_deref().is_some_and(|a| a.eq_ignore_ascii_case(signer)) => {
+            return Ok(stored.creds);
+        }
+        Some(stored) => println!(
+            "🔑 {} holds credentials for {}, deriving a key for signer {}",
+            path.display(), stored.address.as_deref().unwrap_or("an unrecorded signer"), signer
+        ),
+        None => {}
+    }
+    let derived = derive().context("Failed to derive API credentials")?;
+    write_creds(path, Some(signer), &derived)?;
+    Ok(derived)
+}
+
+pub struct SharedCreds {
+    current: RwLock<Arc<PreparedCreds>>,
+    /// Where refreshed credentials are written (None = keep in memory only)
+    path: Option<PathBuf>,
+    /// Signer recorded with refreshed credentials
+    signer: Option<String>,
+    min_interval: Duration,
+    last_refresh: Mutex<Option<Instant>>,
+}
+
+impl SharedCreds {
+    pub fn new(creds: PreparedCreds, path: Option<PathBuf>, min_interval: Duration) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(creds)),
+            path,
+            signer: None,
+            min_interval,
+            last_refresh: Mutex::new(None),
+        }
+    }
+
+    /// Record `signer` alongside credentials written on refresh (see `load_or_derive`)
+    pub fn with_signer(mut self, signer: &str) -> Self {
+        self.signer = Some(signer.to_string());
+        self
+    }
+
+    /// Credentials to sign the next request with
+    pub fn current(&self) -> Arc<PreparedCreds> {
+        Arc::clone(&self.current.read().unwrap())
+    }
+
+    /// Re-derive credentials with `derive`, persist them and swap them in
+    ///
+    /// # Returns
+    /// * `Ok(None)` - A refresh already ran within the minimum interval; nothing derived
+    pub fn refresh(&self, derive: impl FnOnce() -> Result<ApiCreds>) -> Result<Option<Arc<PreparedCreds>>> {
+        let mut last_refresh = self.last_refresh.lock().unwrap();
+        if last_refresh.is_some_and(|at| at.elapsed() < self.min_interval) {
+            return Ok(None);
+        }
+        *last_refresh = Some(Instant::now());
+
+        let derived = derive().context("Failed to re-derive API credentials")?;
+        let prepared = Arc::new(PreparedCreds::from_api_creds(&derived)?);
+        if let Some(path) = &self.path {
+            write_creds(path, self.signer.as_deref(), &derived)?;
+        }
+        *self.current.write().unwrap() = Arc::clone(&prepared);
+        Ok(Some(prepared))
+    }
+}
+
+/// True for responses that mean our API credentials were rejected
+pub fn is_auth_failure(http_status: u16) -> bool {
+    http_status == 401
+}
+
+/// HTTP status of an order response (`status_of` for `with_auth_retry`)
+pub fn response_status(resp: &reqwest::blocking::Response) -> u16 {
+    resp.status().as_u16()
+}
+
+/// Run `submit`, and if it comes back 401, retry it once with fresh credentials
+///
+/// Another worker may already have refreshed since `submit` started; then its
+/// credentials are reused instead of deriving again. If a refresh is rate limited
+/// or fails, the original 401 result is returned.
+pub fn with_auth_retry<T>(
+    creds: &SharedCreds,
+    status_of: impl Fn(&T) -> u16,
+    derive: impl FnOnce() -> Result<ApiCreds>,
+    mut submit: impl FnMut(&PreparedCreds) -> Result<T>,
+) -> Result<T> {
+    let used = creds.current();
+    let result = submit(&used)?;
+    if !is_auth_failure(status_of(&result)) {
+        return Ok(result);
+    }
+
+    let latest = creds.current();
+    let fresh = if !Arc::ptr_eq(&latest, &used) {
+        latest
+    } else {
+        match creds.refresh(derive) {
+            Ok(Some(fresh)) => {
+                println!("🔑 API credentials rejected (401) - re-derived and retrying");
+                fresh
+            }
+            Ok(None) => return Ok(result),
+            Err(e) => {
+                eprintln!("⚠️ API credentials rejected (401) and refresh failed: {}", e);
+                return Ok(result);
+            }
+        }
+    };
+    submit(&fresh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    fn api_creds(key: &str) -> ApiCreds {
+        ApiCreds {
+            api_key: key.to_string(),
+            api_secret: "c2VjcmV0".to_string(),
+            api_passphrase: "pass".to_string(),
+        }
+    }
+
+    fn shared(key: &str, path: Option<PathBuf>) -> SharedCreds {
+        let prepared = PreparedCreds::from_api_creds(&api_creds(key)).unwrap();
+        SharedCreds::new(prepared, path, CREDS_REFRESH_MIN_INTERVAL)
+    }
+
+    /// Exchange that rejects every key except "fresh"
+    fn submit_to_exchange<'a>(keys_seen: &'a RefCell<Vec<String>>) -> impl FnMut(&PreparedCreds) -> Result<u16> + 'a {
+        move |creds| {
+            keys_seen.borrow_mut().push(creds.api_key.clone());
+            Ok(if creds.api_key == "fresh" { 200 } else { 401 })
+        }
+    }
+
+    #[test]
+    fn test_401_rederives_once_and_retries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".clob_creds.json");
+        let creds = shared("expired", Some(path.clone()));
+        let derives = Cell::new(0);
+        let keys_seen = RefCell::new(Vec::new());
+
+        let status = with_auth_retry(
+            &creds,
+            |status| *status,
+            || { derives.set(derives.get() + 1); Ok(api_creds("fresh")) },
+            submit_to_exchange(&keys_seen),
+        ).unwrap();
+
+        assert_eq!(status, 200);
+        assert_eq!(derives.get(), 1);
+        assert_eq!(*keys_seen.borrow(), vec!["expired", "fresh"]);
+        assert_eq!(creds.current().api_key, "fresh");
+
+        let written: ApiCreds = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written.api_key, "fresh");
+    }
+
+    #[test]
+    fn test_load_or_derive_is_keyed_by_signer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".clob_creds.json");
+        let derives = Cell::new(0);
+        let derive = |key: &'static str| {
+            let derives = &derives;
+            move || { derives.set(derives.get() + 1); Ok(api_creds(key)) }
+        };
+
+        // No file: derived and saved for the signer
+        assert_eq!(load_or_derive(&path, "0xAAA", derive("a")).unwrap().api_key, "a");
+        // Same signer (any case): read back, no derive
+        assert_eq!(load_or_derive(&path, "0xaaa", derive("unused")).unwrap().api_key, "a");
+        assert_eq!(derives.get(), 1);
+
+        // Another signer: its own key replaces the file
+        assert_eq!(load_or_derive(&path, "0xBBB", derive("b")).unwrap().api_key, "b");
+        assert_eq!(derives.get(), 2);
+        let stored: StoredCreds = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(stored.address.as_deref(), Some("0xBBB"));
+
+        // A file without a signer (older format) is re-derived once
+        std::fs::write(&path, serde_json::to_string(&api_creds("legacy")).unwrap()).unwrap();
+        assert_eq!(load_or_derive(&path, "0xBBB", derive("b")).unwrap().api_key, "b");
+        assert_eq!(load_or_derive(&path, "0xBBB", derive("unused")).unwrap().api_key, "b");
+        assert_eq!(derives.get(), 3);
+
+        // The file stays readable as plain ApiCreds for the other binaries
+        let plain: ApiCreds = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(plain.api_key, "b");
+    }
+
+    #[test]
+    fn test_refresh_keeps_signer_in_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".clob_creds.json");
+        let creds = shared("expired", Some(path.clone())).with_signer("0xAAA");
+        creds.refresh(|| Ok(api_creds("fresh"))).unwrap();
+
+        // Read back without deriving again
+        let loaded = load_or_derive(&path, "0xaaa", || panic!("should not derive")).unwrap();
+        assert_eq!(loaded.api_key, "fresh");
+    }
+
+    #[test]
+    fn test_refresh_rate_limited() {
+        let creds = shared("expired", None);
+        let derives = Cell::new(0);
+        let keys_seen = RefCell::new(Vec::new());
+
+        // Derived key is rejected too: one derive, one retry, still 401
+        let derive_bad = || { derives.set(derives.get() + 1); Ok(api_creds("also-bad")) };
+        let status = with_auth_retry(&creds, |s| *s, derive_bad, submit_to_exchange(&keys_seen)).unwrap();
+        assert_eq!(status, 401);
+
+        // Within the minimum interval: no second derive, no retry
+        let derive_bad = || { derives.set(derives.get() + 1); Ok(api_creds("also-bad")) };
+        let status = with_auth_retry(&creds, |s| *s, derive_bad, submit_to_exchange(&keys_seen)).unwrap();
+        assert_eq!(status, 401);
+        assert_eq!(derives.get(), 1);
+        assert_eq!(*keys_seen.borrow(), vec!["expired", "also-bad", "also-bad"]);
+    }
+
+    #[test]
+    fn test_success_does_not_rederive() {
+        let creds = shared("fresh", None);
+        let keys_seen = RefCell::new(Vec::new());
+        let status = with_auth_retry(
+            &creds,
+            |s| *s,
+            || panic!("should not derive"),
+            submit_to_exchange(&keys_seen),
+        ).unwrap();
+        assert_eq!(status, 200);
+        assert_eq!(keys_seen.borrow().len(), 1);
+    }
+
+    #[test]
+    fn test_reuses_creds_refreshed_by_another_worker() {
+        let creds = shared("expired", None);
+        let keys_seen = RefCell::new(Vec::new());
+        let mut exchange = submit_to_exchange(&keys_seen);
+        let status = with_auth_retry(
+            &creds,
+            |s| *s,
+            || panic!("should not derive"),
+            |used: &PreparedCreds| {
+                // Another worker swaps in fresh creds while our request is in flight
+                if used.api_key == "expired" {
+                    creds.refresh(|| Ok(api_creds("fresh"))).unwrap();
+                }
+                exchange(used)
+            },
+        ).unwrap();
+        assert_eq!(status, 200);
+        assert_eq!(*keys_seen.borrow(), vec!["expired", "fresh"]);
+    }
+}
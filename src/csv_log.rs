@@ -0,0 +1,174 @@
+//! Trade CSV log (CSV_FILE) with optional size-based rotation
+//!
+//! Rows are appended from `spawn_blocking` threads, so appends and rotation share a
+//! mutex: a row never lands in a file that is being renamed. When the file reaches
+//! CSV_MAX_BYTES it is renamed with a UTC timestamp suffix and a fresh file is started
+//! with the header row.
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::OnceCell;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Header row of the trade CSV
+pub const CSV_HEADER: &str = "timestamp,block,clob_asset_id,usd_value,shares,price_per_share,direction,order_status,best_price,best_size,second_price,second_size,tx_hash,is_live";
+
+pub struct CsvLog {
+    path: PathBuf,
+    header: &'static str,
+    /// Rotate once the file reaches this size (None = grow forever)
+    max_bytes: Option<u64>,
+    write_lock: Mutex<()>,
+}
+
+impl CsvLog {
+    pub fn new(path: impl Into<PathBuf>, header: &'static str, max_bytes: Option<u64>) -> Self {
+        Self {
+            path: path.into(),
+            header,
+            max_bytes: max_bytes.filter(|b| *b > 0),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Create the file with its header row if it doesn't exist
+    pub fn ensure(&self) -> io::Result<()> {
+        let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.create_if_missing()
+    }
+
+    /// Append one row, rotating first if the file has reached `max_bytes`
+    /// Returns the rotated file's path when a rotation happened
+    pub fn append(&self, row: &str) -> io::Result<Option<PathBuf>> {
+        let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        let rotated = match self.max_bytes {
+            Some(max) if fs::metadata(&self.path).is_ok_and(|m| m.len() >= max) => {
+                let target = self.rotated_path(Utc::now());
+                fs::rename(&self.path, &target)?;
+                Some(target)
+            }
+            _ => None,
+        };
+
+        self.create_if_missing()?;
+        let mut f = OpenOptions::new().append(true).open(&self.path)?;
+        writeln!(f, "{}", row)?;
+        Ok(rotated)
+    }
+
+    fn create_if_missing(&self) -> io::Result<()> {
+        if !self.path.exists() {
+            let mut f = File::create(&self.path)?;
+            writeln!(f, "{}", self.header)?;
+        }
+        Ok(())
+    }
+
+    /// `name.YYYYMMDD-HHMMSS.csv` next to the log, with `-N` added if that name is taken
+    fn rotated_path(&self, now: DateTime<Utc>) -> PathBuf {
+        let stem = self.path.file_stem().and_then(|s| s.to_str()).unwrap_or("trades");
+        let ext = self.path.extension().and_then(|s| s.to_str()).unwrap_or("csv");
+        let stamp = now.format("%Y%m%d-%H%M%S");
+
+        let mut candidate = self.path.with_file_name(format!("{}.{}.{}", stem, stamp, ext));
+        let mut n = 1;
+        while candidate.exists() {
+            candidate = self.path.with_file_name(format!("{}.{}-{}.{}", stem, stamp, n, ext));
+            n += 1;
+        }
+        candidate
+    }
+}
+
+static CSV_LOG: OnceCell<CsvLog> = OnceCell::new();
+
+/// Set the process-wide trade CSV and create it if missing (first call wins)
+pub fn init(log: CsvLog) -> io::Result<()> {
+    let log = CSV_LOG.get_or_init(|| log);
+    log.ensure()
+}
+
+/// Append a row to the process-wide trade CSV (no-op before `init`)
+pub fn append_row(row: &str) {
+    let Some(log) = CSV_LOG.get() else { return };
+    match log.append(row) {
+        Ok(Some(rotated)) => println!("Rotated CSV log to {}", rotated.display()),
+        Ok(None) => {}
+        Err(e) => eprintln!("Warning: Failed to write CSV row to {}: {}", log.path().display(), e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(path: &Path) -> Vec<String> {
+        fs::read_to_string(path).unwrap().lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_append_creates_file_with_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trades.csv");
+        let log = CsvLog::new(&path, "a,b", None);
+
+        assert_eq!(log.append("1,2").unwrap(), None);
+        assert_eq!(log.append("3,4").unwrap(), None);
+        assert_eq!(lines(&path), vec!["a,b", "1,2", "3,4"]);
+    }
+
+    #[test]
+    fn test_rotation_past_threshold() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trades.csv");
+        // Header (4 bytes) + one row (4 bytes) reaches the threshold
+        let log = CsvLog::new(&path, "a,b", Some(8));
+        log.ensure().unwrap();
+
+        assert_eq!(log.append("1,2").unwrap(), None);
+        let rotated = log.append("3,4").unwrap().expect("second row should rotate");
+
+        assert_eq!(lines(&rotated), vec!["a,b", "1,2"]);
+        assert_eq!(lines(&path), vec!["a,b", "3,4"]);
+
+        let name = rotated.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("trades.") && name.ends_with(".csv"), "{}", name);
+    }
+
+    #[test]
+    fn test_rotated_names_do_not_collide() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trades.csv");
+        let log = CsvLog::new(&path, "a,b", Some(1));
+
+        let first = log.append("1,2").unwrap();
+        let second = log.append("3,4").unwrap();
+        let third = log.append("5,6").unwrap();
+
+        // First append writes the header and row; the next two each rotate a full file
+        assert_eq!(first, None);
+        let (second, third) = (second.unwrap(), third.unwrap());
+        assert_ne!(second, third);
+        assert_eq!(lines(&third), vec!["a,b", "3,4"]);
+        assert_eq!(lines(&path), vec!["a,b", "5,6"]);
+    }
+
+    #[test]
+    fn test_zero_threshold_disables_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trades.csv");
+        let log = CsvLog::new(&path, "a,b", Some(0));
+
+        for _ in 0..3 {
+            assert_eq!(log.append("1,2").unwrap(), None);
+        }
+        assert_eq!(lines(&path).len(), 4);
+    }
+}
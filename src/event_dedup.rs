@@ -0,0 +1,120 @@
+//! Short-lived dedup of whale events
+//!
+//! Some providers deliver the same log as both a Text and a Binary frame, or resend recent
+//! logs after a reconnect. Events are keyed on (tx_hash, token_id, order_type); a key seen
+//! again within the window (WS_DEDUP_WINDOW_MS) is dropped before an order is placed.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::models::ParsedEvent;
+
+type DedupKey = (String, Arc<str>, String);
+
+#[derive(Debug)]
+pub struct EventDedup {
+    /// Zero disables dedup
+    window: Duration,
+    /// Key -> when it was first seen
+    seen: HashMap<DedupKey, Instant>,
+}
+
+impl EventDedup {
+    pub fn new(window: Duration) -> Self {
+        Self { window, seen: HashMap::new() }
+    }
+
+    /// True if this event should be handled: first time seen, or first seen over a window ago
+    pub fn first_seen(&mut self, evt: &ParsedEvent) -> bool {
+        self.first_seen_at(evt, Instant::now())
+    }
+
+    pub fn first_seen_at(&mut self, evt: &ParsedEvent, now: Instant) -> bool {
+        if self.window.is_zero() {
+            return true;
+        }
+
+        let window = self.window;
+        self.seen.retain(|_, first| now.saturating_duration_since(*first) < window);
+
+        let key = (evt.tx_hash.clone(), Arc::clone(&evt.order.clob_token_id), evt.order.order_type.clone());
+        if self.seen.contains_key(&key) {
+            return false;
+        }
+        self.seen.insert(key, now);
+        true
+    }
+
+    /// Keys currently inside the window
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OrderInfo;
+
+    fn event(tx_hash: &str, token_id: &str, order_type: &str) -> ParsedEvent {
+        ParsedEvent {
+            block_number: 1,
+            tx_hash: tx_hash.to_string(),
+            trader_address: String::new(),
+            trader_label: String::new(),
+            trader_min_shares: 0.0,
+            trader_max_bet_usd: None,
+            trader_scaling_ratio: 0.0,
+            trader_probabilistic_sizing: None,
+            trader_copy_price_range: Default::default(),
+            trader_observe: false,
+            order: OrderInfo {
+                order_type: order_type.to_string(),
+                clob_token_id: Arc::from(token_id),
+                usd_value: 50.0,
+                shares: 100.0,
+                price_per_share: 0.5,
+            },
+        }
+    }
+
+    #[test]
+    fn test_key_includes_token_and_order_type() {
+        let mut dedup = EventDedup::new(Duration::from_secs(30));
+        let t0 = Instant::now();
+
+        assert!(dedup.first_seen_at(&event("0xabc", "tokenA", "BUY_FILL"), t0));
+        assert!(dedup.first_seen_at(&event("0xabc", "tokenB", "BUY_FILL"), t0));
+        assert!(dedup.first_seen_at(&event("0xabc", "tokenA", "SELL_FILL"), t0));
+        assert!(dedup.first_seen_at(&event("0xdef", "tokenA", "BUY_FILL"), t0));
+        assert!(!dedup.first_seen_at(&event("0xabc", "tokenA", "BUY_FILL"), t0));
+        assert_eq!(dedup.len(), 4);
+    }
+
+    #[test]
+    fn test_expired_keys_are_pruned() {
+        let mut dedup = EventDedup::new(Duration::from_millis(500));
+        let t0 = Instant::now();
+
+        for i in 0..10 {
+            assert!(dedup.first_seen_at(&event(&format!("0x{}", i), "token1", "BUY_FILL"), t0));
+        }
+        assert!(dedup.first_seen_at(&event("0xnew", "token1", "BUY_FILL"), t0 + Duration::from_secs(1)));
+        assert_eq!(dedup.len(), 1);
+    }
+
+    #[test]
+    fn test_zero_window_disables_dedup() {
+        let mut dedup = EventDedup::new(Duration::ZERO);
+        let evt = event("0xabc", "token1", "BUY_FILL");
+
+        assert!(dedup.first_seen(&evt));
+        assert!(dedup.first_seen(&evt));
+        assert!(dedup.is_empty());
+    }
+}
@@ -0,0 +1,258 @@
+//! Per-event processing time, split by phase, for SLOW_EVENT warnings and `/metrics`
+//!
+//! `handle_event` times each phase of a whale event: the Gamma lookups before the order,
+//! the order submission, the post-trade book fetch and the DB/CSV recording. The deliberate
+//! POST_TRADE_BOOK_DELAY_MS wait is not counted. The last `EVENT_METRICS_WINDOW` events
+//! are kept for a rolling average and percentiles; lifetime counts cover every event.
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Events kept for the rolling average and percentiles
+pub const EVENT_METRICS_WINDOW: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventPhase {
+    /// Gamma lookups (live status, market status) before the order
+    Lookup,
+    /// Aggregation and order submission
+    Submit,
+    /// Post-trade order book fetch and market name lookup
+    Book,
+    /// Trader stats, DB queue and CSV row
+    Db,
+}
+
+impl EventPhase {
+    pub const ALL: [EventPhase; 4] = [EventPhase::Lookup, EventPhase::Submit, EventPhase::Book, EventPhase::Db];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventPhase::Lookup => "lookup",
+            EventPhase::Submit => "submit",
+            EventPhase::Book => "book",
+            EventPhase::Db => "db",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Time spent in each phase of one event
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EventTimings {
+    phases: [Duration; 4],
+}
+
+impl EventTimings {
+    /// Add `elapsed` to a phase (a phase may be timed in several pieces)
+    pub fn add(&mut self, phase: EventPhase, elapsed: Duration) {
+        self.phases[phase.index()] += elapsed;
+    }
+
+    pub fn phase(&self, phase: EventPhase) -> Duration {
+        self.phases[phase.index()]
+    }
+
+    pub fn total(&self) -> Duration {
+        self.phases.iter().sum()
+    }
+
+    /// Phase that took longest (the first one on ties)
+    pub fn dominant(&self) -> EventPhase {
+        EventPhase::ALL.into_iter()
+            .fold(EventPhase::Lookup, |best, phase| if self.phase(phase) > self.phase(best) { phase } else { best })
+    }
+}
+
+/// Average and percentiles over the rolling window, in milliseconds
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LatencyStats {
+    pub avg_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencyStats {
+    fn from_durations(mut durations: Vec<Duration>) -> Self {
+        if durations.is_empty() {
+            return Self::default();
+        }
+        durations.sort();
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        // Nearest rank
+        let pct = |p: f64| ms(durations[((p / 100.0 * durations.len() as f64).ceil() as usize).clamp(1, durations.len()) - 1]);
+        Self {
+            avg_ms: ms(durations.iter().sum::<Duration>()) / durations.len() as f64,
+            p50_ms: pct(50.0),
+            p95_ms: pct(95.0),
+            p99_ms: pct(99.0),
+            max_ms: ms(durations[durations.len() - 1]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PhaseStats {
+    pub lookup: LatencyStats,
+    pub submit: LatencyStats,
+    pub book: LatencyStats,
+    pub db: LatencyStats,
+}
+
+/// What `/metrics` reports about event processing
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EventMetricsSnapshot {
+    /// Events processed since startup
+    pub events: u64,
+    /// Events slower than SLOW_EVENT_MS since startup
+    pub slow_events: u64,
+    pub slow_threshold_ms: u64,
+    /// Events the stats below cover (the most recent, at most `EVENT_METRICS_WINDOW`)
+    pub window: usize,
+    pub total: LatencyStats,
+    pub phases: PhaseStats,
+}
+
+/// Rolling window of event timings plus lifetime counts
+#[derive(Debug)]
+pub struct EventMetrics {
+    recent: VecDeque<EventTimings>,
+    capacity: usize,
+    /// Zero: no event counts as slow
+    slow_threshold: Duration,
+    events: u64,
+    slow_events: u64,
+}
+
+impl EventMetrics {
+    pub fn new(capacity: usize, slow_threshold: Duration) -> Self {
+        Self { recent: VecDeque::with_capacity(capacity), capacity, slow_threshold, events: 0, slow_events: 0 }
+    }
+
+    pub fn set_slow_threshold(&mut self, slow_threshold: Duration) {
+        self.slow_threshold = slow_threshold;
+    }
+
+    pub fn slow_threshold(&self) -> Duration {
+        self.slow_threshold
+    }
+
+    /// Add one event's timings; true if it was slower than the threshold
+    pub fn observe(&mut self, timings: EventTimings) -> bool {
+        let slow = !self.slow_threshold.is_zero() && timings.total() > self.slow_threshold;
+        self.events += 1;
+        if slow {
+            self.slow_events += 1;
+        }
+        if self.recent.len() == self.capacity {
+            self.recent.pop_front();
+        }
+        if self.capacity > 0 {
+            self.recent.push_back(timings);
+        }
+        slow
+    }
+
+    pub fn snapshot(&self) -> EventMetricsSnapshot {
+        let stats = |f: &dyn Fn(&EventTimings) -> Duration| LatencyStats::from_durations(self.recent.iter().map(f).collect());
+        EventMetricsSnapshot {
+            events: self.events,
+            slow_events: self.slow_events,
+            slow_threshold_ms: self.slow_threshold.as_millis() as u64,
+            window: self.recent.len(),
+            total: stats(&|t| t.total()),
+            phases: PhaseStats {
+                lookup: stats(&|t| t.phase(EventPhase::Lookup)),
+                submit: stats(&|t| t.phase(EventPhase::Submit)),
+                book: stats(&|t| t.phase(EventPhase::Book)),
+                db: stats(&|t| t.phase(EventPhase::Db)),
+            },
+        }
+    }
+}
+
+static EVENT_METRICS: Lazy<Mutex<EventMetrics>> =
+    Lazy::new(|| Mutex::new(EventMetrics::new(EVENT_METRICS_WINDOW, Duration::ZERO)));
+
+/// Set the process-wide SLOW_EVENT_MS threshold (zero: never slow)
+pub fn init(slow_threshold: Duration) {
+    EVENT_METRICS.lock().unwrap_or_else(|e| e.into_inner()).set_slow_threshold(slow_threshold);
+}
+
+/// Add one event to the process-wide metrics; true if it was slow
+pub fn observe(timings: EventTimings) -> bool {
+    EVENT_METRICS.lock().unwrap_or_else(|e| e.into_inner()).observe(timings)
+}
+
+pub fn slow_threshold() -> Duration {
+    EVENT_METRICS.lock().unwrap_or_else(|e| e.into_inner()).slow_threshold()
+}
+
+pub fn snapshot() -> EventMetricsSnapshot {
+    EVENT_METRICS.lock().unwrap_or_else(|e| e.into_inner()).snapshot()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timings(lookup: u64, submit: u64, book: u64, db: u64) -> EventTimings {
+        let mut t = EventTimings::default();
+        t.add(EventPhase::Lookup, Duration::from_millis(lookup));
+        t.add(EventPhase::Submit, Duration::from_millis(submit));
+        t.add(EventPhase::Book, Duration::from_millis(book));
+        t.add(EventPhase::Db, Duration::from_millis(db));
+        t
+    }
+
+    #[test]
+    fn test_total_and_dominant_phase() {
+        let mut t = timings(20, 150, 900, 5);
+        assert_eq!(t.total(), Duration::from_millis(1075));
+        assert_eq!(t.dominant(), EventPhase::Book);
+
+        // Phases timed in pieces add up
+        t.add(EventPhase::Submit, Duration::from_millis(800));
+        assert_eq!(t.phase(EventPhase::Submit), Duration::from_millis(950));
+        assert_eq!(t.dominant(), EventPhase::Submit);
+        assert_eq!(EventTimings::default().dominant(), EventPhase::Lookup);
+    }
+
+    #[test]
+    fn test_accumulates_percentiles_and_slow_events() {
+        let mut metrics = EventMetrics::new(100, Duration::from_millis(500));
+        // Submits of 1..=100 ms, plus 10 ms of DB each
+        for ms in 1..=100 {
+            assert!(!metrics.observe(timings(0, ms, 0, 10)));
+        }
+        assert!(metrics.observe(timings(0, 50, 600, 0)), "650 ms is over the 500 ms threshold");
+
+        let snap = metrics.snapshot();
+        assert_eq!((snap.events, snap.slow_events, snap.window), (101, 1, 100));
+        assert_eq!(snap.slow_threshold_ms, 500);
+        // The window dropped the 1 ms submit and holds 2..=100 plus the slow 50
+        assert_eq!(snap.phases.submit.p50_ms, 50.0);
+        assert_eq!(snap.phases.submit.p95_ms, 95.0);
+        assert_eq!(snap.phases.submit.max_ms, 100.0);
+        assert_eq!(snap.phases.book.p99_ms, 0.0);
+        assert_eq!(snap.phases.book.max_ms, 600.0);
+        assert!((snap.phases.db.avg_ms - 9.9).abs() < 1e-9);
+        assert_eq!(snap.total.max_ms, 650.0);
+    }
+
+    #[test]
+    fn test_zero_threshold_never_slow() {
+        let mut metrics = EventMetrics::new(10, Duration::ZERO);
+        assert!(!metrics.observe(timings(0, 60_000, 0, 0)));
+        assert_eq!(metrics.snapshot().slow_events, 0);
+        assert_eq!(EventMetrics::new(10, Duration::ZERO).snapshot().total, LatencyStats::default());
+    }
+}
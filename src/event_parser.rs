@@ -0,0 +1,197 @@
+//! Decoding of OrdersFilled log notifications into whale events
+//!
+//! Shared by the live WebSocket loop, the HTTP replay of missed blocks and the backtest's
+//! `--from-raw-log` mode, so a recorded RAW_EVENT_LOG line decodes exactly as it did live.
+
+use alloy::primitives::U256;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::config::traders::TradersConfig;
+use crate::models::{OrderInfo, ParsedEvent, WsMessage};
+use crate::settings::{CopyPriceRange, MIN_WHALE_SHARES_TO_COPY, ORDERS_FILLED_EVENT_SIGNATURE, TARGET_TOPIC_HEX};
+
+thread_local! {
+    static TOKEN_ID_CACHE: RefCell<HashMap<[u8; 32], Arc<str>>> = RefCell::new(HashMap::with_capacity(256));
+}
+
+/// Decode one `eth_subscription` notification (or an eth_getLogs log wrapped as one)
+/// None for malformed messages, traders not in `traders` (or disabled), and non-fill logs.
+/// Without `traders` only TARGET_WHALE_ADDRESS is accepted (legacy mode).
+pub fn parse_event(message: String, traders: Option<&TradersConfig>) -> Option<ParsedEvent> {
+    let msg: WsMessage = serde_json::from_str(&message).ok()?;
+    let result = msg.params?.result?;
+
+    // just to double check!
+    if result.topics.len() < 3 { return None; }
+
+    // Extract trader address from topics[2]
+    // Format: 0x000000000000000000000000{40-char-address}
+    let trader_topic = result.topics.get(2)?;
+    let trader_address = extract_address_from_topic(trader_topic)?;
+
+    // Look up trader in config (if provided)
+    // Returns (label, min_shares, max_bet_usd, scaling_ratio, probabilistic_sizing, copy_price_range, observe) tuple
+    let (trader_label, trader_min_shares, trader_max_bet_usd, trader_scaling_ratio, trader_probabilistic_sizing, trader_copy_price_range, trader_observe) = if let Some(traders_cfg) = traders {
+        // Try to find trader by topic hex (case-insensitive for robustness)
+        // WebSocket may return different case than our stored topics
+        let topic_lower = trader_topic.to_lowercase();
+        if let Some(trader_cfg) = traders_cfg.get_by_topic(&topic_lower) {
+            if !trader_cfg.enabled {
+                return None; // Skip disabled traders
+            }
+            (trader_cfg.label.clone(), trader_cfg.min_shares, trader_cfg.max_bet_usd, trader_cfg.scaling_ratio, trader_cfg.probabilistic_sizing, trader_cfg.copy_price_range(), trader_cfg.observe)
+        } else {
+            // Debug: Log when we receive an event but don't match a trader
+            // This helps diagnose subscription/filtering issues
+            if std::env::var("DEBUG_EVENTS").is_ok() {
+                eprintln!("🔍 Event from unknown trader: {} (addr: {})", trader_topic, trader_address);
+            }
+            // Trader not in our config - skip
+            return None;
+        }
+    } else {
+        // No traders config provided (legacy mode or tests)
+        // Fall back to checking TARGET_TOPIC_HEX
+        let has_target = trader_topic.eq_ignore_ascii_case(TARGET_TOPIC_HEX.as_str());
+        if !has_target { return None; }
+        // Legacy mode uses global MIN_WHALE_SHARES_TO_COPY, SCALING_RATIO and copy price bounds
+        (String::new(), MIN_WHALE_SHARES_TO_COPY, None, 0.0, None, CopyPriceRange::default(), false)
+    };
+
+    let hex_data = &result.data;
+    if hex_data.len() < 2 + 64 * 4 { return None; }
+
+    let (maker_id, maker_bytes) = parse_u256_hex_slice_with_bytes(hex_data, 2, 66)?;
+    let (taker_id, taker_bytes) = parse_u256_hex_slice_with_bytes(hex_data, 66, 130)?;
+
+    let (clob_id, token_bytes, maker_amt, taker_amt, base_type) =
+        if maker_id.is_zero() && !taker_id.is_zero() {
+            let m = parse_u256_hex_slice(hex_data, 130, 194)?;
+            let t = parse_u256_hex_slice(hex_data, 194, 258)?;
+            (taker_id, taker_bytes, m, t, "BUY")
+        } else if taker_id.is_zero() && !maker_id.is_zero() {
+            let m = parse_u256_hex_slice(hex_data, 130, 194)?;
+            let t = parse_u256_hex_slice(hex_data, 194, 258)?;
+            (maker_id, maker_bytes, m, t, "SELL")
+        } else {
+            return None;
+        };
+
+    let shares = if base_type == "BUY" { u256_to_f64(&taker_amt)? } else { u256_to_f64(&maker_amt)? } / 1e6;
+    if shares <= 0.0 { return None; }
+    
+    let usd = if base_type == "BUY" { u256_to_f64(&maker_amt)? } else { u256_to_f64(&taker_amt)? } / 1e6;
+    let price = usd / shares;
+    if !(usd.is_finite() && price.is_finite()) { return None; }
+    
+    let mut order_type = base_type.to_string();
+    if result.topics[0].eq_ignore_ascii_case(ORDERS_FILLED_EVENT_SIGNATURE) {
+        order_type.push_str("_FILL");
+    }
+
+    Some(ParsedEvent {
+        block_number: result.block_number.as_deref()
+            .and_then(|s| u64::from_str_radix(s.trim_start_matches("0x"), 16).ok())
+            .unwrap_or_default(),
+        tx_hash: result.transaction_hash.unwrap_or_default(),
+        trader_address,
+        trader_label,
+        trader_min_shares,
+        trader_max_bet_usd,
+        trader_scaling_ratio,
+        trader_probabilistic_sizing,
+        trader_copy_price_range,
+        trader_observe,
+        order: OrderInfo {
+            order_type,
+            clob_token_id: u256_to_dec_cached(&token_bytes, &clob_id),
+            usd_value: usd,
+            shares,
+            price_per_share: price,
+        },
+    })
+}
+
+/// Extract 40-character address from a topic hex string
+/// Format: 0x000000000000000000000000{40-char-address}
+/// Returns normalized lowercase address without 0x prefix
+pub fn extract_address_from_topic(topic: &str) -> Option<String> {
+    let topic_clean = topic.trim().strip_prefix("0x").unwrap_or(topic.trim());
+    if topic_clean.len() != 64 {
+        return None;
+    }
+    // Last 40 characters are the address (first 24 are padding zeros)
+    let address = &topic_clean[24..64];
+    Some(address.to_lowercase())
+}
+
+// ============================================================================
+// Hex Parsing Helpers
+// ============================================================================
+
+#[inline]
+fn parse_u256_hex_slice_with_bytes(full: &str, start: usize, end: usize) -> Option<(U256, [u8; 32])> {
+    let slice = full.get(start..end)?;
+    let clean = slice.strip_prefix("0x").unwrap_or(slice);
+    if clean.len() > 64 { return None; }
+
+    let mut hex_buf = [b'0'; 64];
+    hex_buf[64 - clean.len()..].copy_from_slice(clean.as_bytes());
+
+    let mut out = [0u8; 32];
+    for i in 0..32 {
+        let hi = hex_nibble(hex_buf[i * 2])?;
+        let lo = hex_nibble(hex_buf[i * 2 + 1])?;
+        out[i] = (hi << 4) | lo;
+    }
+    Some((U256::from_be_slice(&out), out))
+}
+
+#[inline]
+fn parse_u256_hex_slice(full: &str, start: usize, end: usize) -> Option<U256> {
+    parse_u256_hex_slice_with_bytes(full, start, end).map(|(v, _)| v)
+}
+
+fn u256_to_dec_cached(bytes: &[u8; 32], val: &U256) -> Arc<str> {
+    TOKEN_ID_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if let Some(s) = cache.get(bytes) { return Arc::clone(s); }  // Cheap Arc clone
+        let s: Arc<str> = val.to_string().into();
+        cache.insert(*bytes, Arc::clone(&s));
+        s
+    })
+}
+
+fn u256_to_f64(v: &U256) -> Option<f64> {
+    if v.bit_len() <= 64 { Some(v.as_limbs()[0] as f64) }
+    else { v.to_string().parse().ok() }
+}
+
+// Hex nibble lookup table - 2-3x faster than branching
+const HEX_NIBBLE_LUT: [u8; 256] = {
+    let mut lut = [255u8; 256];
+    let mut i = b'0';
+    while i <= b'9' {
+        lut[i as usize] = i - b'0';
+        i += 1;
+    }
+    let mut i = b'a';
+    while i <= b'f' {
+        lut[i as usize] = i - b'a' + 10;
+        i += 1;
+    }
+    let mut i = b'A';
+    while i <= b'F' {
+        lut[i as usize] = i - b'A' + 10;
+        i += 1;
+    }
+    lut
+};
+
+#[inline(always)]
+fn hex_nibble(b: u8) -> Option<u8> {
+    let val = HEX_NIBBLE_LUT[b as usize];
+    if val == 255 { None } else { Some(val) }
+}
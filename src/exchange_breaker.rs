@@ -0,0 +1,128 @@
+//! Global circuit breaker for exchange failures
+//!
+//! Counts consecutive failed submissions across all tokens. After `threshold` in a
+//! row the breaker opens and orders are refused until the cooldown passes. The next
+//! order after the cooldown is a trial: a success closes the breaker, a failure
+//! reopens it immediately (the failure count is only reset by a success).
+
+use std::time::{Duration, Instant};
+
+/// State change caused by recording an outcome (for logging)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerTransition {
+    Opened { failures: u32 },
+    Closed,
+}
+
+/// The default never opens (threshold 0)
+#[derive(Default)]
+pub struct ExchangeBreaker {
+    /// Consecutive failures that open the breaker (0 = disabled)
+    threshold: u32,
+    cooldown: Duration,
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl ExchangeBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            consecutive_failures: 0,
+            open_until: None,
+        }
+    }
+
+    /// Time left in the cooldown if the breaker is open
+    pub fn open_remaining(&self, now: Instant) -> Option<Duration> {
+        self.open_until
+            .filter(|until| *until > now)
+            .map(|until| until - now)
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    pub fn record_success(&mut self) -> Option<BreakerTransition> {
+        let was_tripped = self.open_until.is_some();
+        self.consecutive_failures = 0;
+        self.open_until = None;
+        was_tripped.then_some(BreakerTransition::Closed)
+    }
+
+    pub fn record_failure(&mut self, now: Instant) -> Option<BreakerTransition> {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        if self.threshold == 0 || self.consecutive_failures < self.threshold {
+            return None;
+        }
+        self.open_until = Some(now + self.cooldown);
+        Some(BreakerTransition::Opened { failures: self.consecutive_failures })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COOLDOWN: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn test_opens_after_threshold_failures() {
+        let now = Instant::now();
+        let mut breaker = ExchangeBreaker::new(3, COOLDOWN);
+
+        assert_eq!(breaker.record_failure(now), None);
+        assert_eq!(breaker.record_failure(now), None);
+        assert!(breaker.open_remaining(now).is_none());
+
+        assert_eq!(breaker.record_failure(now), Some(BreakerTransition::Opened { failures: 3 }));
+        assert_eq!(breaker.open_remaining(now), Some(COOLDOWN));
+        assert!(breaker.open_remaining(now + Duration::from_secs(59)).is_some());
+    }
+
+    #[test]
+    fn test_success_resets_counter() {
+        let now = Instant::now();
+        let mut breaker = ExchangeBreaker::new(3, COOLDOWN);
+        breaker.record_failure(now);
+        breaker.record_failure(now);
+        assert_eq!(breaker.record_success(), None, "never opened, no transition");
+        assert_eq!(breaker.consecutive_failures(), 0);
+        assert_eq!(breaker.record_failure(now), None);
+    }
+
+    #[test]
+    fn test_cooldown_then_trial_order() {
+        let now = Instant::now();
+        let mut breaker = ExchangeBreaker::new(2, COOLDOWN);
+        breaker.record_failure(now);
+        breaker.record_failure(now);
+
+        // Cooldown over: a trial order is allowed
+        let later = now + COOLDOWN;
+        assert!(breaker.open_remaining(later).is_none());
+
+        // Trial fails: reopens straight away
+        assert_eq!(breaker.record_failure(later), Some(BreakerTransition::Opened { failures: 3 }));
+        assert_eq!(breaker.open_remaining(later), Some(COOLDOWN));
+
+        // Next trial succeeds: closed and reset
+        let much_later = later + COOLDOWN;
+        assert!(breaker.open_remaining(much_later).is_none());
+        assert_eq!(breaker.record_success(), Some(BreakerTransition::Closed));
+        assert_eq!(breaker.consecutive_failures(), 0);
+        assert_eq!(breaker.record_failure(much_later), None);
+    }
+
+    #[test]
+    fn test_zero_threshold_never_opens() {
+        let now = Instant::now();
+        let mut breaker = ExchangeBreaker::new(0, COOLDOWN);
+        for _ in 0..100 {
+            assert_eq!(breaker.record_failure(now), None);
+        }
+        assert!(breaker.open_remaining(now).is_none());
+    }
+}
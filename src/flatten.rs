@@ -0,0 +1,128 @@
+//! Close every position at once for a quick risk-off (POST /flatten, position_monitor --flatten)
+//!
+//! One order per position in the trade DB closes it completely: a SELL of a net-long position,
+//! a BUY of a net-short one. The bot submits them through its order engine as GTD orders with
+//! the live / non-live expiry and records the placed ones with `FLATTEN_STATUS`. Both entry
+//! points refuse to act without an explicit confirmation.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::persistence::Position;
+
+/// Status recorded for a placed flatten order
+pub const FLATTEN_STATUS: &str = "FLATTEN";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum FlattenSide {
+    Buy,
+    Sell,
+}
+
+impl FlattenSide {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FlattenSide::Buy => "BUY",
+            FlattenSide::Sell => "SELL",
+        }
+    }
+}
+
+/// The order that closes one position
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlattenOrder {
+    pub token_id: String,
+    pub side: FlattenSide,
+    pub shares: f64,
+}
+
+/// Full-exit orders for `positions`: SELL what is held, BUY back what is short; flat tokens are left out
+pub fn flatten_orders(positions: &[Position]) -> Vec<FlattenOrder> {
+    positions.iter()
+        .filter(|p| p.net_shares.is_finite() && p.net_shares != 0.0)
+        .map(|p| FlattenOrder {
+            token_id: p.token_id.clone(),
+            side: if p.net_shares > 0.0 { FlattenSide::Sell } else { FlattenSide::Buy },
+            shares: p.net_shares.abs(),
+        })
+        .collect()
+}
+
+/// What happened to one flatten order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlattenResult {
+    pub token_id: String,
+    pub side: FlattenSide,
+    pub shares: f64,
+    /// Bid (SELL) or ask (BUY) the order was priced from; None if the book couldn't be fetched
+    pub price: Option<f64>,
+    /// Order engine status, e.g. "200 OK [...]" or "SKIPPED_NO_PRICE"
+    pub status: String,
+}
+
+/// Body of a /flatten response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlattenResponse {
+    pub orders: Vec<FlattenResult>,
+}
+
+/// Sent by the API to the bot's flatten task, which answers with one result per position
+pub type FlattenRequest = tokio::sync::oneshot::Sender<Result<Vec<FlattenResult>, String>>;
+
+/// Ask a running bot's API (e.g. "http://127.0.0.1:8080") to flatten every position
+pub fn request_flatten(api_base: &str) -> Result<FlattenResponse> {
+    let url = format!("{}/flatten?confirm=true", api_base.trim_end_matches('/'));
+    let response = reqwest::blocking::Client::builder()
+        .no_proxy()
+        .timeout(std::time::Duration::from_secs(120))
+        .build()?
+        .post(&url)
+        .send()
+        .with_context(|| format!("Failed to reach the bot API at {}", url))?;
+    let status = response.status();
+    let body = response.text()?;
+    if !status.is_success() {
+        anyhow::bail!("POST /flatten failed: {} {}", status, body);
+    }
+    serde_json::from_str(&body).context("Unexpected /flatten response")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(token_id: &str, net_shares: f64) -> Position {
+        Position { token_id: token_id.to_string(), net_shares, avg_entry_price: Some(0.5), trade_count: 2 }
+    }
+
+    #[test]
+    fn test_flatten_orders_fully_exit_each_position() {
+        let orders = flatten_orders(&[
+            position("long", 37.25),
+            position("short", -12.5),
+            position("flat", 0.0),
+            position("broken", f64::NAN),
+        ]);
+
+        assert_eq!(orders, vec![
+            FlattenOrder { token_id: "long".to_string(), side: FlattenSide::Sell, shares: 37.25 },
+            FlattenOrder { token_id: "short".to_string(), side: FlattenSide::Buy, shares: 12.5 },
+        ]);
+        assert!(flatten_orders(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_flatten_result_serializes_side_in_caps() {
+        let result = FlattenResult {
+            token_id: "tok".to_string(),
+            side: FlattenSide::Sell,
+            shares: 10.0,
+            price: Some(0.42),
+            status: "SKIPPED_NO_POSITION".to_string(),
+        };
+        let json = serde_json::to_value(FlattenResponse { orders: vec![result.clone()] }).unwrap();
+        assert_eq!(json["orders"][0]["side"], "SELL");
+        assert_eq!(serde_json::from_value::<FlattenResponse>(json).unwrap().orders, vec![result]);
+    }
+}
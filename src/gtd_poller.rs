@@ -0,0 +1,173 @@
+//! Fill tracking for resting GTD orders
+//!
+//! The last resubmit attempt places a GTD order that rests on the book, so the POST
+//! response can't say how much of it fills. A poller checks the order's status until it
+//! is matched, cancelled/expired, or a timeout passes, and the realized fill is recorded
+//! then. Pollers are capped so a burst of GTD orders can't spawn unbounded tasks.
+
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Time between order status checks
+pub const GTD_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Keep polling this long past the order's expiry (the exchange expires orders lazily)
+pub const GTD_POLL_GRACE: Duration = Duration::from_secs(60);
+
+/// Maximum GTD orders followed at once; orders beyond this are logged but not tracked
+pub const MAX_GTD_POLLERS: usize = 32;
+
+/// Where a GTD order stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GtdState {
+    /// Resting on the book (possibly partly matched)
+    Open,
+    /// Fully matched
+    Filled,
+    /// Cancelled, expired or invalid; `size_matched` is final
+    Closed,
+}
+
+/// Parsed `GET /data/order/{id}` response
+#[derive(Debug, Clone, PartialEq)]
+pub struct GtdOrderStatus {
+    pub state: GtdState,
+    pub original_size: f64,
+    pub size_matched: f64,
+    pub price: f64,
+}
+
+impl GtdOrderStatus {
+    /// No further fills can happen
+    pub fn is_final(&self) -> bool {
+        self.state != GtdState::Open
+    }
+}
+
+#[derive(Deserialize)]
+struct OrderStatusResponse {
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    original_size: String,
+    #[serde(default)]
+    size_matched: String,
+    #[serde(default)]
+    price: String,
+}
+
+/// Parse an order status body; None if the order is unknown (`null`) or the body isn't an order
+///
+/// `MATCHED` (or a fully matched size) is filled; `LIVE`, `DELAYED` and `UNMATCHED` are
+/// still open; anything else (`CANCELED`, `CANCELED_MARKET_RESOLVED`, `INVALID`, ...)
+/// is closed with whatever matched so far.
+pub fn parse_order_status(body: &str) -> Option<GtdOrderStatus> {
+    let resp: OrderStatusResponse = serde_json::from_str::<Option<OrderStatusResponse>>(body).ok()??;
+    if resp.status.is_empty() {
+        return None;
+    }
+
+    let original_size = resp.original_size.parse().unwrap_or(0.0);
+    let size_matched = resp.size_matched.parse().unwrap_or(0.0);
+    let price = resp.price.parse().unwrap_or(0.0);
+
+    let fully_matched = original_size > 0.0 && size_matched >= original_size;
+    let state = match resp.status.to_ascii_uppercase().as_str() {
+        "MATCHED" => GtdState::Filled,
+        _ if fully_matched => GtdState::Filled,
+        "LIVE" | "DELAYED" | "UNMATCHED" => GtdState::Open,
+        _ => GtdState::Closed,
+    };
+
+    Some(GtdOrderStatus { state, original_size, size_matched, price })
+}
+
+/// Caps concurrent GTD pollers; clones share the cap
+#[derive(Clone)]
+pub struct GtdPollers {
+    permits: Arc<Semaphore>,
+}
+
+impl GtdPollers {
+    pub fn new(max_pollers: usize) -> Self {
+        Self { permits: Arc::new(Semaphore::new(max_pollers)) }
+    }
+
+    /// Claim a poller slot, held until the permit is dropped; None when all are busy
+    pub fn try_acquire(&self) -> Option<OwnedSemaphorePermit> {
+        Arc::clone(&self.permits).try_acquire_owned().ok()
+    }
+}
+
+impl Default for GtdPollers {
+    fn default() -> Self {
+        Self::new(MAX_GTD_POLLERS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order_body(status: &str, original: &str, matched: &str) -> String {
+        format!(
+            r#"{{"id": "0xabc", "status": "{}", "original_size": "{}", "size_matched": "{}", "price": "0.52", "side": "BUY"}}"#,
+            status, original, matched
+        )
+    }
+
+    #[test]
+    fn test_parse_order_status_states() {
+        let live = parse_order_status(&order_body("LIVE", "100", "0")).unwrap();
+        assert_eq!(live.state, GtdState::Open);
+        assert!(!live.is_final());
+
+        let partial = parse_order_status(&order_body("LIVE", "100", "40")).unwrap();
+        assert_eq!(partial.state, GtdState::Open);
+        assert_eq!(partial.size_matched, 40.0);
+
+        let matched = parse_order_status(&order_body("MATCHED", "100", "100")).unwrap();
+        assert_eq!(matched.state, GtdState::Filled);
+        assert_eq!(matched.price, 0.52);
+        assert!(matched.is_final());
+
+        let cancelled = parse_order_status(&order_body("CANCELED", "100", "25.5")).unwrap();
+        assert_eq!(cancelled.state, GtdState::Closed);
+        assert_eq!(cancelled.size_matched, 25.5);
+        assert_eq!(cancelled.original_size, 100.0);
+    }
+
+    #[test]
+    fn test_parse_order_status_fully_matched_and_case() {
+        // Fully matched size counts as filled whatever the status says
+        let full = parse_order_status(&order_body("live", "100", "100")).unwrap();
+        assert_eq!(full.state, GtdState::Filled);
+
+        let lower = parse_order_status(&order_body("matched", "10", "10")).unwrap();
+        assert_eq!(lower.state, GtdState::Filled);
+
+        let resolved = parse_order_status(&order_body("CANCELED_MARKET_RESOLVED", "10", "0")).unwrap();
+        assert_eq!(resolved.state, GtdState::Closed);
+    }
+
+    #[test]
+    fn test_parse_order_status_unknown_order() {
+        assert!(parse_order_status("null").is_none());
+        assert!(parse_order_status("{}").is_none());
+        assert!(parse_order_status("not json").is_none());
+    }
+
+    #[test]
+    fn test_pollers_are_capped() {
+        let pollers = GtdPollers::new(2);
+        let first = pollers.try_acquire().unwrap();
+        let _second = pollers.clone().try_acquire().unwrap();
+        assert!(pollers.try_acquire().is_none());
+
+        // Finished poller frees its slot
+        drop(first);
+        assert!(pollers.try_acquire().is_some());
+    }
+}
@@ -23,21 +23,39 @@ pub mod profiler;
 pub use profiler::{PROFILER, ops};
 pub mod aggregator;
 pub mod api;
+pub mod balance;
 pub mod clob_trades;
 pub mod config;
+pub mod creds;
+pub mod csv_log;
+pub mod event_dedup;
+pub mod event_metrics;
+pub mod event_parser;
+pub mod exchange_breaker;
+pub mod flatten;
+pub mod gtd_poller;
 pub mod live_positions;
+pub mod logging;
 pub mod market_cache;
 pub mod market_info;
 pub mod models;
+pub mod notify;
 pub mod persistence;
+pub mod pnl;
 pub mod portfolio;
 pub mod prices;
+pub mod raw_event_log;
+pub mod recent_trades;
+pub mod reconcile;
 pub mod relayer;
+pub mod replay;
 pub mod risk_guard;
 pub mod settings;
 pub mod soccer_markets;
 pub mod tennis_markets;
 pub mod trader_state;
+pub mod trailing_stop;
+pub mod ws_health;
 
 // TODO: Fix imports in resubmit_tests - commented out temporarily
 // #[cfg(test)]
@@ -130,6 +148,16 @@ pub struct OrderResponse {
     pub making_amount: String,
 }
 
+/// Response from DELETE /orders
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CancelOrdersResponse {
+    #[serde(default)]
+    pub canceled: Vec<String>,
+    /// Order ID -> reason it could not be cancelled
+    #[serde(default)]
+    pub not_canceled: HashMap<String, String>,
+}
+
 // ============================================================================
 // PREPARED CREDENTIALS
 // ============================================================================
@@ -406,23 +434,49 @@ impl RustClobClient {
         Ok(resp)
     }
 
-    pub fn create_order(&mut self, args: OrderArgs) -> Result<SignedOrder> {
-        profile!(ops::CREATE_ORDER);
+    /// Fetch one order's status and matched size (GET /data/order/{id})
+    pub fn get_order(&self, order_id: &str, creds: &PreparedCreds) -> Result<reqwest::blocking::Response> {
+        let path = format!("/data/order/{}", order_id);
+        let url = build_url_1(&self.host, &path);
+        let headers = self.l2_headers_fast("GET", &path, None, creds)?;
+        Ok(self.http.get(&url).headers(headers).send()?)
+    }
 
-        let tick = "0.01";
+    /// Fetch our open orders (GET /data/orders); also a cheap check that the API key is accepted
+    pub fn get_open_orders(&self, creds: &PreparedCreds) -> Result<reqwest::blocking::Response> {
+        let path = "/data/orders";
+        let url = build_url_1(&self.host, path);
+        let headers = self.l2_headers_fast("GET", path, None, creds)?;
+        Ok(self.http.get(&url).headers(headers).send()?)
+    }
 
+    /// Cancel a batch of orders by ID (DELETE /orders)
+    pub fn cancel_orders(
+        &self,
+        order_ids: &[String],
+        creds: &PreparedCreds,
+    ) -> Result<reqwest::blocking::Response> {
+        let path = "/orders";
+        let url = build_url_1(&self.host, path);
+        let body = serde_json::to_string(order_ids)?;
+        let headers = self.l2_headers_fast("DELETE", path, Some(&body), creds)?;
+        Ok(self.http.delete(&url).headers(headers).body(body).send()?)
+    }
+
+    /// Whether a token trades on the neg-risk exchange (market cache, then session cache, then GET /neg-risk)
+    pub fn is_neg_risk(&mut self, token_id: &str) -> Result<bool> {
         // Check global market cache first (periodically refreshed from disk)
-        let neg_risk = if let Some(n) = market_cache::is_neg_risk(&args.token_id) {
-            n
+        if let Some(n) = market_cache::is_neg_risk(token_id) {
+            Ok(n)
         }
         // Fallback: check client's internal cache (for previous API hits this session)
-        else if let Some(&n) = self.neg_risk_cache.get(&args.token_id) {
-            n
+        else if let Some(&n) = self.neg_risk_cache.get(token_id) {
+            Ok(n)
         }
         // Last resort: API call on complete cache miss
         else {
             profile!(ops::GET_NEG_RISK);
-            let url = build_url_query_1(&self.host, "/neg-risk", "token_id", &args.token_id);
+            let url = build_url_query_1(&self.host, "/neg-risk", "token_id", token_id);
             let resp = self
                 .http
                 .get(&url)
@@ -431,19 +485,31 @@ impl RustClobClient {
             let val: serde_json::Value = resp.json()?;
             let nr = val["neg_risk"].as_bool().unwrap_or(false);
             // Update both caches: global (persists across refreshes) and local (fast path)
-            market_cache::global_caches().set_neg_risk(args.token_id.clone(), nr);
-            self.neg_risk_cache.insert(args.token_id.clone(), nr);
-            nr
-        };
+            market_cache::global_caches().set_neg_risk(token_id.to_string(), nr);
+            self.neg_risk_cache.insert(token_id.to_string(), nr);
+            Ok(nr)
+        }
+    }
+
+    pub fn create_order(&mut self, args: OrderArgs) -> Result<SignedOrder> {
+        profile!(ops::CREATE_ORDER);
+
+        let tick = "0.01";
+
+        let neg_risk = self.is_neg_risk(&args.token_id)?;
 
         if !price_valid(args.price, tick) {
             return Err(anyhow!("price {} outside allowed range", args.price));
         }
+        if let Some(bps) = args.fee_rate_bps.filter(|bps| *bps < 0) {
+            return Err(anyhow!("fee rate {} bps must not be negative", bps));
+        }
 
+        // FAK and FOK are both market orders and share the same amount precision
         let is_fak = args
             .order_type
             .as_ref()
-            .map_or(true, |t| t.eq_ignore_ascii_case("FAK"));
+            .map_or(true, |t| t.eq_ignore_ascii_case("FAK") || t.eq_ignore_ascii_case("FOK"));
 
         let (side_code, maker_amt, taker_amt) = if args.side.eq_ignore_ascii_case("BUY") {
             get_order_amounts_buy(args.size, args.price, is_fak)?
@@ -480,7 +546,7 @@ impl RustClobClient {
             taker_amount: taker_amount_str,
             taker_amount_u256,
             side: side_code,
-            fee_rate_bps: FEE_RATE_ZERO.to_string(),
+            fee_rate_bps: args.fee_rate_bps.map_or_else(|| FEE_RATE_ZERO.to_string(), |bps| bps.to_string()),
             nonce: nonce_str,
             nonce_u256,
             signer: self.wallet_address_str.clone(),
@@ -562,6 +628,11 @@ impl RustClobClient {
         &self.http
     }
 
+    /// CLOB base URL this client talks to (no trailing slash)
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
     /// Returns the wallet address (derived from private key) - used as 'signer' in orders
     pub fn wallet_address(&self) -> &str {
         &self.wallet_address_str
@@ -687,6 +758,11 @@ pub struct SignedOrder {
 
 impl SignedOrder {
     pub fn post_body(&self, owner: &str, order_type: &str) -> String {
+        self.post_body_with(owner, order_type, false)
+    }
+
+    /// POST /order body; a post-only order is rejected instead of matching when it would cross the book
+    pub fn post_body_with(&self, owner: &str, order_type: &str, post_only: bool) -> String {
         JSON_BUF.with(|json_buf| {
             ITOA_BUF.with(|itoa_buf| {
                 let mut buf = json_buf.borrow_mut();
@@ -727,7 +803,11 @@ impl SignedOrder {
                 buf.push_str(owner);
                 buf.push_str(r#"","orderType":""#);
                 buf.push_str(order_type);
-                buf.push_str(r#""}"#);
+                if post_only {
+                    buf.push_str(r#"","postOnly":true}"#);
+                } else {
+                    buf.push_str(r#""}"#);
+                }
 
                 buf.clone()
             })
@@ -857,6 +937,39 @@ fn generate_seed() -> u128 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_signed_order_carries_fee_rate() {
+        let mut client = RustClobClient::new(
+            "http://127.0.0.1:1", 137,
+            "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef", None,
+        ).unwrap();
+        client.set_neg_risk("123456", false);
+        let args = |fee_rate_bps| OrderArgs {
+            token_id: "123456".to_string(),
+            price: 0.50,
+            size: 10.0,
+            side: "BUY".to_string(),
+            fee_rate_bps,
+            nonce: Some(0),
+            expiration: None,
+            taker: None,
+            order_type: Some("FAK".to_string()),
+        };
+
+        let default = client.create_order(args(None)).unwrap();
+        assert!(default.post_body("key", "FAK").contains(r#""feeRateBps":"0""#));
+
+        let with_fee = client.create_order(args(Some(100))).unwrap();
+        assert!(with_fee.post_body("key", "FAK").contains(r#""feeRateBps":"100""#));
+
+        let post_only: serde_json::Value = serde_json::from_str(&default.post_body_with("key", "GTD", true)).unwrap();
+        assert_eq!(post_only["orderType"], "GTD");
+        assert_eq!(post_only["postOnly"], true);
+        assert!(!default.post_body("key", "GTD").contains("postOnly"));
+
+        assert!(client.create_order(args(Some(-1))).is_err());
+    }
+
     #[test]
     fn test_order_amounts_buy_fak() {
         // Test FAK order: 108.68 shares @ 0.14
@@ -972,7 +1085,7 @@ fn order_typed_data(chain_id: u64, exchange: &str, data: &OrderData) -> Result<T
             r#"{{"name":"signatureType","type":"uint8"}}"#,
             r#"]}},"primaryType":"Order","#,
             r#""domain":{{"name":"Polymarket CTF Exchange","version":"1","chainId":{},"verifyingContract":"{}"}},"#,
-            r#""message":{{"salt":"{}","maker":"{}","signer":"{}","taker":"{}","tokenId":"{}","makerAmount":"{}","takerAmount":"{}","expiration":"{}","nonce":"{}","feeRateBps":"0","side":{},"signatureType":{}}}}}"#
+            r#""message":{{"salt":"{}","maker":"{}","signer":"{}","taker":"{}","tokenId":"{}","makerAmount":"{}","takerAmount":"{}","expiration":"{}","nonce":"{}","feeRateBps":"{}","side":{},"signatureType":{}}}}}"#
         ),
         chain_id,
         exchange,
@@ -985,6 +1098,7 @@ fn order_typed_data(chain_id: u64, exchange: &str, data: &OrderData) -> Result<T
         data.taker_amount_u256,
         data.expiration_u256,
         data.nonce_u256,
+        data.fee_rate_bps,
         data.side,
         data.signature_type,
     );
@@ -0,0 +1,587 @@
+//! Structured log output for trade summaries, heartbeats and resubmits
+//!
+//! `LOG_FORMAT=json` prints one JSON object per line with typed fields for log
+//! aggregators. The default text format keeps the colored human-readable lines.
+//! Each event builds its JSON fields from typed data, so color codes only ever
+//! appear in text output.
+
+use once_cell::sync::OnceCell;
+use serde_json::Value;
+use std::fmt::Write as _;
+
+use crate::event_metrics::{EventPhase, EventTimings};
+use crate::market_info::MarketMetadata;
+use crate::models::ResubmitRequest;
+
+const RESET: &str = "\x1b[0m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Colored human-readable lines (default)
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+impl LogFormat {
+    /// Parse LOG_FORMAT ("json" selects JSON, anything else is text)
+    pub fn parse(value: &str) -> Self {
+        if value.trim().eq_ignore_ascii_case("json") {
+            LogFormat::Json
+        } else {
+            LogFormat::Text
+        }
+    }
+}
+
+static LOG_FORMAT: OnceCell<LogFormat> = OnceCell::new();
+
+/// Set the process-wide log format (first call wins)
+pub fn init(format: LogFormat) {
+    let _ = LOG_FORMAT.set(format);
+}
+
+pub fn log_format() -> LogFormat {
+    LOG_FORMAT.get().copied().unwrap_or_default()
+}
+
+/// An event that renders as either a text line or a JSON object
+pub trait LogEvent {
+    /// Value of the "event" field in JSON output
+    const EVENT: &'static str;
+
+    /// Human-readable line (may contain ANSI colors)
+    fn text(&self) -> String;
+
+    /// Typed fields for JSON output (must be an object)
+    fn fields(&self) -> Value;
+}
+
+/// Render an event in the given format
+pub fn render<E: LogEvent>(event: &E, format: LogFormat) -> String {
+    match format {
+        LogFormat::Text => event.text(),
+        LogFormat::Json => {
+            let mut fields = event.fields();
+            if let Some(obj) = fields.as_object_mut() {
+                obj.insert("ts_ms".into(), chrono::Utc::now().timestamp_millis().into());
+                obj.insert("event".into(), E::EVENT.into());
+            }
+            fields.to_string()
+        }
+    }
+}
+
+/// Print an event to stdout in the configured format
+pub fn emit<E: LogEvent>(event: &E) {
+    println!("{}", render(event, log_format()));
+}
+
+/// Strip ANSI escape codes from a string
+pub fn strip_ansi_codes(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut in_escape = false;
+    for c in s.chars() {
+        if c == '\x1b' {
+            in_escape = true;
+        } else if in_escape {
+            if c == 'm' {
+                in_escape = false;
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Get ANSI color code based on fill percentage
+pub fn get_fill_color(filled: f64, requested: f64) -> &'static str {
+    if requested <= 0.0 { return "\x1b[31m"; }  // Red if no request
+    let pct = (filled / requested) * 100.0;
+    if pct < 50.0 { "\x1b[31m" }                // Red
+    else if pct < 75.0 { "\x1b[38;5;208m" }     // Orange
+    else if pct < 90.0 { "\x1b[33m" }           // Yellow
+    else { "\x1b[32m" }                          // Green
+}
+
+fn pct(filled: f64, total: f64) -> f64 {
+    if total > 0.0 { (filled / total) * 100.0 } else { 0.0 }
+}
+
+// ============================================================================
+// Trade summary
+// ============================================================================
+
+/// Per-trade summary line printed after each whale event is handled
+#[derive(Debug, Clone)]
+pub struct TradeSummaryLog {
+    pub block: u64,
+    pub token_id: String,
+    /// Whale order type, e.g. "BUY_FILL"
+    pub side: String,
+    pub whale_shares: f64,
+    pub whale_usd: f64,
+    pub fill_pct: Option<f64>,
+    /// Status category (SUCCESS, FAILED, SKIPPED_SMALL, ...)
+    pub status: String,
+    /// Full status text from order processing (may contain ANSI colors)
+    pub status_detail: String,
+    pub latency_ms: Option<i64>,
+    pub best: (String, String),
+    pub second: (String, String),
+    pub is_live: bool,
+    /// Sport tag for tennis/soccer markets
+    pub market_tag: Option<&'static str>,
+    /// Market title and outcome, when known
+    pub market: Option<MarketMetadata>,
+}
+
+impl LogEvent for TradeSummaryLog {
+    const EVENT: &'static str = "trade";
+
+    fn text(&self) -> String {
+        // Highlight best price in bright pink
+        let pink = "\x1b[38;5;199m";
+        let tag = match self.market_tag {
+            Some("TENNIS") => format!("{GREEN}(TENNIS){RESET} "),
+            Some(tag) => format!("\x1b[36m({tag}){RESET} "),
+            None => String::new(),
+        };
+        let live = if self.is_live {
+            format!("\x1b[34mlive: true{RESET}")
+        } else {
+            "live: false".to_string()
+        };
+        let market = match &self.market {
+            Some(m) => format!(" | {} [{}]", m.title, m.outcome),
+            None => String::new(),
+        };
+        format!(
+            "⚡ [B:{}] {}{} | ${:.0} | {} | best: {}{}{} @ {} | 2nd: {} @ {} | {}{}",
+            self.block, tag, self.side, self.whale_usd, self.status_detail,
+            pink, self.best.0, RESET, self.best.1, self.second.0, self.second.1, live, market
+        )
+    }
+
+    fn fields(&self) -> Value {
+        serde_json::json!({
+            "block": self.block,
+            "token_id": self.token_id,
+            "side": self.side,
+            "whale_shares": self.whale_shares,
+            "whale_usd": self.whale_usd,
+            "fill_pct": self.fill_pct,
+            "status": self.status,
+            "status_detail": strip_ansi_codes(&self.status_detail),
+            "latency_ms": self.latency_ms,
+            "best_price": self.best.0,
+            "best_size": self.best.1,
+            "second_price": self.second.0,
+            "second_size": self.second.1,
+            "is_live": self.is_live,
+            "market_tag": self.market_tag,
+            "market_title": self.market.as_ref().map(|m| &m.title),
+            "outcome": self.market.as_ref().map(|m| &m.outcome),
+        })
+    }
+}
+
+// ============================================================================
+// Heartbeat
+// ============================================================================
+
+/// Periodic liveness line with aggregate trader stats and account health
+#[derive(Debug, Clone, Copy)]
+pub struct HeartbeatLog {
+    pub traders: usize,
+    pub trades: u32,
+    pub successful: u32,
+    pub partial: u32,
+    pub failed: u32,
+    pub copied_usd: f64,
+    pub realized_pnl: f64,
+    /// Funder wallet USDC (None if the balance couldn't be read)
+    pub usdc_balance: Option<f64>,
+    /// Tokens with net shares in the trade DB (None without DB_ENABLED)
+    pub open_positions: Option<usize>,
+    /// GTD orders placed this session that are still resting
+    pub open_gtd_orders: usize,
+}
+
+impl LogEvent for HeartbeatLog {
+    const EVENT: &'static str = "heartbeat";
+
+    fn text(&self) -> String {
+        let mut line = format!(
+            "💓 Heartbeat: {} traders | {} trades today | {}/{}/{} (success/partial/failed) | ${:.2} total copied | ${:+.2} realized",
+            self.traders, self.trades, self.successful, self.partial, self.failed, self.copied_usd, self.realized_pnl
+        );
+        if let Some(balance) = self.usdc_balance {
+            let _ = write!(line, " | ${:.2} USDC", balance);
+        }
+        if let Some(open) = self.open_positions {
+            let _ = write!(line, " | {} open positions", open);
+        }
+        let _ = write!(line, " | {} GTD resting", self.open_gtd_orders);
+        line
+    }
+
+    fn fields(&self) -> Value {
+        serde_json::json!({
+            "traders": self.traders,
+            "trades": self.trades,
+            "successful": self.successful,
+            "partial": self.partial,
+            "failed": self.failed,
+            "copied_usd": self.copied_usd,
+            "realized_pnl": self.realized_pnl,
+            "usdc_balance": self.usdc_balance,
+            "open_positions": self.open_positions,
+            "open_gtd_orders": self.open_gtd_orders,
+        })
+    }
+}
+
+// ============================================================================
+// Slow events
+// ============================================================================
+
+/// An event whose processing took longer than SLOW_EVENT_MS
+#[derive(Debug, Clone)]
+pub struct SlowEventLog {
+    pub token_id: String,
+    pub side: String,
+    pub status: String,
+    pub timings: EventTimings,
+    pub threshold_ms: u64,
+}
+
+impl LogEvent for SlowEventLog {
+    const EVENT: &'static str = "slow_event";
+
+    fn text(&self) -> String {
+        let ms = |d: std::time::Duration| d.as_millis();
+        let mut phases = String::new();
+        for phase in EventPhase::ALL {
+            let _ = write!(phases, " {} {}ms", phase.as_str(), ms(self.timings.phase(phase)));
+        }
+        format!(
+            "{YELLOW}⚠️ SLOW_EVENT: {}ms > {}ms, mostly {} |{} | {} {} {}{RESET}",
+            ms(self.timings.total()), self.threshold_ms, self.timings.dominant().as_str(),
+            phases, self.side, self.token_id, self.status
+        )
+    }
+
+    fn fields(&self) -> Value {
+        let mut phases = serde_json::Map::new();
+        for phase in EventPhase::ALL {
+            phases.insert(format!("{}_ms", phase.as_str()), (self.timings.phase(phase).as_millis() as u64).into());
+        }
+        serde_json::json!({
+            "token_id": self.token_id,
+            "side": self.side,
+            "status": self.status,
+            "total_ms": self.timings.total().as_millis() as u64,
+            "threshold_ms": self.threshold_ms,
+            "dominant_phase": self.timings.dominant().as_str(),
+            "phases": phases,
+        })
+    }
+}
+
+// ============================================================================
+// Resubmit outcomes
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResubmitOutcome {
+    /// Next price would exceed the max price
+    Abort,
+    /// Final GTD order placed on the book
+    GtdSubmitted,
+    /// FAK partially filled, continuing with the remainder
+    Partial,
+    /// FAK filled
+    Success,
+    /// FAK found no match, trying the next attempt
+    Retry,
+    /// Out of attempts
+    Failed,
+    /// Submission error
+    Error,
+}
+
+impl ResubmitOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResubmitOutcome::Abort => "ABORT",
+            ResubmitOutcome::GtdSubmitted => "GTD_SUBMITTED",
+            ResubmitOutcome::Partial => "PARTIAL",
+            ResubmitOutcome::Success => "SUCCESS",
+            ResubmitOutcome::Retry => "RETRY",
+            ResubmitOutcome::Failed => "FAILED",
+            ResubmitOutcome::Error => "ERROR",
+        }
+    }
+}
+
+/// Outcome of one resubmit attempt
+#[derive(Debug, Clone)]
+pub struct ResubmitLog {
+    /// Logged from the resubmit chain (vs the first resubmit in the worker)
+    pub chain: bool,
+    pub outcome: ResubmitOutcome,
+    pub token_id: String,
+    pub side_is_buy: bool,
+    pub whale_shares: f64,
+    pub attempt: u8,
+    pub max_attempts: u8,
+    /// Price of this attempt (next price for Retry, rejected price for Abort)
+    pub price: f64,
+    pub max_price: f64,
+    /// Shares still to fill after this attempt
+    pub remaining: f64,
+    /// Total shares filled so far
+    pub filled: f64,
+    pub original_size: f64,
+    /// Error body for Failed/Error
+    pub detail: Option<String>,
+    /// Exchange order ID of an accepted attempt
+    pub order_id: Option<String>,
+}
+
+impl ResubmitLog {
+    /// Start from a request; fields default to its current state
+    pub fn new(req: &ResubmitRequest, chain: bool, outcome: ResubmitOutcome, max_attempts: u8) -> Self {
+        Self {
+            chain,
+            outcome,
+            token_id: req.token_id.clone(),
+            side_is_buy: req.side_is_buy,
+            whale_shares: req.whale_shares,
+            attempt: req.attempt,
+            max_attempts,
+            price: req.failed_price,
+            max_price: req.max_price,
+            remaining: req.size,
+            filled: req.cumulative_filled,
+            original_size: req.original_size,
+            detail: None,
+            order_id: None,
+        }
+    }
+
+    pub fn fill_pct(&self) -> f64 {
+        pct(self.filled, self.original_size)
+    }
+}
+
+impl LogEvent for ResubmitLog {
+    const EVENT: &'static str = "resubmit";
+
+    fn text(&self) -> String {
+        let prefix = if self.chain { "🔄 Resubmit chain" } else { "🔄 Resubmit" };
+        let detail = self.detail.as_deref().unwrap_or("");
+        let fill = format!("filled {:.2}/{:.2} ({:.0}%)", self.filled, self.original_size, self.fill_pct());
+        let order = self.order_id.as_deref().map(|id| format!(" | order {}", id)).unwrap_or_default();
+        match self.outcome {
+            ResubmitOutcome::Abort => format!(
+                "{} ABORT: attempt {} price {:.2} > max {:.2} | {}",
+                prefix, self.attempt, self.price, self.max_price, fill
+            ),
+            ResubmitOutcome::GtdSubmitted => format!(
+                "{GREEN}{} GTD SUBMITTED: attempt {} @ ≤{:.2} | size {:.2} | prior filled {:.2}/{:.2}{}{RESET}",
+                prefix, self.attempt, self.max_price, self.remaining, self.filled, self.original_size, order
+            ),
+            ResubmitOutcome::Partial => format!(
+                "{YELLOW}{} PARTIAL: attempt {} @ {:.2} | {} | remaining {:.2}{}{RESET}",
+                prefix, self.attempt, self.price, fill, self.remaining, order
+            ),
+            ResubmitOutcome::Success => format!(
+                "{GREEN}{} SUCCESS: attempt {} @ {:.2} | {}{}{RESET}",
+                prefix, self.attempt, self.price, fill, order
+            ),
+            ResubmitOutcome::Retry => format!(
+                "{}: attempt {} no match, retrying @ {:.2} (max attempts: {})",
+                prefix, self.attempt, self.price, self.max_attempts
+            ),
+            ResubmitOutcome::Failed => format!(
+                "{} FAILED: attempt {}/{} @ {:.2} | {}{}{RESET} | {}",
+                prefix, self.attempt, self.max_attempts, self.price,
+                get_fill_color(self.filled, self.original_size), fill, detail
+            ),
+            ResubmitOutcome::Error => format!(
+                "{} ERROR: attempt {} | {}{}{RESET} | {}",
+                prefix, self.attempt, get_fill_color(self.filled, self.original_size), fill, detail
+            ),
+        }
+    }
+
+    fn fields(&self) -> Value {
+        serde_json::json!({
+            "chain": self.chain,
+            "status": self.outcome.as_str(),
+            "token_id": self.token_id,
+            "side": if self.side_is_buy { "BUY" } else { "SELL" },
+            "whale_shares": self.whale_shares,
+            "attempt": self.attempt,
+            "max_attempts": self.max_attempts,
+            "price": self.price,
+            "max_price": self.max_price,
+            "remaining": self.remaining,
+            "filled": self.filled,
+            "original_size": self.original_size,
+            "fill_pct": self.fill_pct(),
+            "detail": self.detail.as_deref().map(strip_ansi_codes),
+            "order_id": self.order_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary() -> TradeSummaryLog {
+        TradeSummaryLog {
+            block: 12345678,
+            token_id: "tok".to_string(),
+            side: "BUY_FILL".to_string(),
+            whale_shares: 1000.0,
+            whale_usd: 500.0,
+            fill_pct: Some(100.0),
+            status: "SUCCESS".to_string(),
+            status_detail: "\x1b[32m200 OK [SCALED] | 5.00/5.00 filled @ 0.51\x1b[0m".to_string(),
+            latency_ms: Some(85),
+            best: ("0.52".to_string(), "100".to_string()),
+            second: ("0.53".to_string(), "200".to_string()),
+            is_live: true,
+            market_tag: Some("TENNIS"),
+            market: Some(MarketMetadata { title: "Will it rain?".to_string(), outcome: "Yes".to_string() }),
+        }
+    }
+
+    #[test]
+    fn test_log_format_parse() {
+        assert_eq!(LogFormat::parse("json"), LogFormat::Json);
+        assert_eq!(LogFormat::parse(" JSON "), LogFormat::Json);
+        assert_eq!(LogFormat::parse("text"), LogFormat::Text);
+        assert_eq!(LogFormat::parse(""), LogFormat::Text);
+    }
+
+    #[test]
+    fn test_trade_summary_json_has_typed_fields_and_no_ansi() {
+        let line = render(&summary(), LogFormat::Json);
+        assert!(!line.contains('\x1b'), "ANSI codes leaked into JSON: {}", line);
+        assert!(!line.contains('\n'));
+
+        let v: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(v["event"], "trade");
+        assert_eq!(v["block"], 12345678);
+        assert_eq!(v["token_id"], "tok");
+        assert_eq!(v["side"], "BUY_FILL");
+        assert_eq!(v["whale_shares"], 1000.0);
+        assert_eq!(v["fill_pct"], 100.0);
+        assert_eq!(v["status"], "SUCCESS");
+        assert_eq!(v["latency_ms"], 85);
+        assert_eq!(v["status_detail"], "200 OK [SCALED] | 5.00/5.00 filled @ 0.51");
+        assert_eq!(v["market_title"], "Will it rain?");
+        assert_eq!(v["outcome"], "Yes");
+    }
+
+    #[test]
+    fn test_trade_summary_text_keeps_colors() {
+        let line = render(&summary(), LogFormat::Text);
+        assert!(line.contains("\x1b[38;5;199m0.52"));
+        assert!(line.contains("(TENNIS)"));
+        assert!(line.contains("Will it rain? [Yes]"));
+    }
+
+    #[test]
+    fn test_resubmit_json_has_no_ansi() {
+        let req = ResubmitRequest {
+            token_id: "tok".to_string(),
+            trader_address: "0xwhale".to_string(),
+            whale_price: 0.50,
+            failed_price: 0.51,
+            size: 10.0,
+            whale_shares: 5000.0,
+            max_price: 0.52,
+            cumulative_filled: 2.0,
+            original_size: 10.0,
+            side_is_buy: true,
+            is_live: false,
+            attempt: 3,
+        };
+        let mut log = ResubmitLog::new(&req, true, ResubmitOutcome::Failed, 5);
+        log.detail = Some("no match".to_string());
+
+        assert!(render(&log, LogFormat::Text).contains('\x1b'));
+
+        let v: Value = serde_json::from_str(&render(&log, LogFormat::Json)).unwrap();
+        assert_eq!(v["event"], "resubmit");
+        assert_eq!(v["status"], "FAILED");
+        assert_eq!(v["attempt"], 3);
+        assert_eq!(v["fill_pct"], 20.0);
+        assert_eq!(v["detail"], "no match");
+        assert!(!v.to_string().contains('\x1b'));
+    }
+
+    #[test]
+    fn test_heartbeat_json() {
+        let hb = HeartbeatLog {
+            traders: 2, trades: 5, successful: 3, partial: 1, failed: 1,
+            copied_usd: 42.5, realized_pnl: -1.25,
+            usdc_balance: Some(310.456), open_positions: None, open_gtd_orders: 2,
+        };
+        let v: Value = serde_json::from_str(&render(&hb, LogFormat::Json)).unwrap();
+        assert_eq!(v["event"], "heartbeat");
+        assert_eq!(v["trades"], 5);
+        assert_eq!(v["realized_pnl"], -1.25);
+        assert_eq!(v["usdc_balance"], 310.456);
+        assert!(v["open_positions"].is_null());
+        assert_eq!(v["open_gtd_orders"], 2);
+    }
+
+    #[test]
+    fn test_slow_event_names_dominant_phase() {
+        let mut timings = EventTimings::default();
+        timings.add(EventPhase::Submit, std::time::Duration::from_millis(300));
+        timings.add(EventPhase::Book, std::time::Duration::from_millis(1900));
+        let slow = SlowEventLog {
+            token_id: "tok".to_string(), side: "BUY_FILL".to_string(), status: "SUCCESS".to_string(),
+            timings, threshold_ms: 2000,
+        };
+        assert_eq!(
+            strip_ansi_codes(&slow.text()),
+            "⚠️ SLOW_EVENT: 2200ms > 2000ms, mostly book | lookup 0ms submit 300ms book 1900ms db 0ms | BUY_FILL tok SUCCESS"
+        );
+        let v: Value = serde_json::from_str(&render(&slow, LogFormat::Json)).unwrap();
+        assert_eq!(v["event"], "slow_event");
+        assert_eq!(v["dominant_phase"], "book");
+        assert_eq!(v["phases"]["submit_ms"], 300);
+    }
+
+    #[test]
+    fn test_heartbeat_text_account_health() {
+        let mut hb = HeartbeatLog {
+            traders: 2, trades: 5, successful: 3, partial: 1, failed: 1,
+            copied_usd: 42.5, realized_pnl: -1.25,
+            usdc_balance: Some(310.456), open_positions: Some(4), open_gtd_orders: 2,
+        };
+        assert_eq!(
+            hb.text(),
+            "💓 Heartbeat: 2 traders | 5 trades today | 3/1/1 (success/partial/failed) | $42.50 total copied | $-1.25 realized \
+             | $310.46 USDC | 4 open positions | 2 GTD resting"
+        );
+
+        // Unknown balance and no DB: those parts are left out
+        hb.usdc_balance = None;
+        hb.open_positions = None;
+        assert!(hb.text().ends_with("$-1.25 realized | 2 GTD resting"));
+    }
+}
@@ -4,37 +4,55 @@
 use anyhow::{Result, anyhow};
 use chrono::{DateTime, Utc};
 use dotenvy::dotenv;
-use alloy::primitives::U256;
 use futures::{SinkExt, StreamExt};
 use rand::Rng;
-use pm_whale_follower::{ApiCreds, OrderArgs, RustClobClient, PreparedCreds, OrderResponse};
+use pm_whale_follower::{ApiCreds, CancelOrdersResponse, OrderArgs, RustClobClient, PreparedCreds, OrderResponse};
 use serde_json::Value;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Write as _;
-use std::fs::{File, OpenOptions};
-use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use tokio::sync::{mpsc, oneshot, Mutex};
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
 
-use pm_whale_follower::risk_guard::{RiskGuard, RiskGuardConfig, SafetyDecision, TradeSide, calc_liquidity_depth};
+use pm_whale_follower::risk_guard::{RiskGuard, RiskGuardConfig, RiskGuardSnapshot, SafetyDecision, TradeSide, calc_liquidity_depth};
 use pm_whale_follower::settings::*;
 use pm_whale_follower::market_cache;
+use pm_whale_follower::market_info::MarketMetadata;
+use pm_whale_follower::replay;
+use pm_whale_follower::event_parser::parse_event;
+use pm_whale_follower::raw_event_log::{self, RawEventLog};
+use pm_whale_follower::recent_trades::RecentTrades;
+use pm_whale_follower::reconcile::{find_discrepancies, RECONCILED_STATUS, RECONCILE_TOLERANCE_SHARES};
+use pm_whale_follower::live_positions::{FetchOptions, fetch_live_positions_with_options};
 use pm_whale_follower::tennis_markets;
 use pm_whale_follower::soccer_markets;
-use pm_whale_follower::persistence::{TradeStore, TradeRecord};
+use pm_whale_follower::persistence::{Position, TradeStore, TradeRecord, SHADOW_STATUS};
+use pm_whale_follower::prices::{PriceCache, PriceInfo};
+use pm_whale_follower::trailing_stop::TrailingPeaks;
+use pm_whale_follower::pnl::RealizedPnl;
 use pm_whale_follower::portfolio::{PortfolioTracker, PortfolioConfig};
+use pm_whale_follower::balance::{BalanceTracker, RpcUsdcBalance, BALANCE_CACHE_TTL};
+use pm_whale_follower::creds::{CREDS_REFRESH_MIN_INTERVAL, SharedCreds, load_or_derive, response_status, with_auth_retry};
+use pm_whale_follower::exchange_breaker::{BreakerTransition, ExchangeBreaker};
+use pm_whale_follower::gtd_poller::{GTD_POLL_GRACE, GTD_POLL_INTERVAL, GtdOrderStatus, GtdPollers, GtdState, MAX_GTD_POLLERS, parse_order_status};
 use pm_whale_follower::config::traders::TradersConfig;
 use pm_whale_follower::config::reloadable::ReloadableTraders;
 use pm_whale_follower::trader_state::{TraderManager, TradeStatus};
-use pm_whale_follower::aggregator::{TradeAggregator, AggregationConfig};
+use pm_whale_follower::aggregator::{AggregatedTrade, AggregationConfig, TradeAggregator};
 use pm_whale_follower::api::{ApiConfig, start_api_server_with_reload};
 use pm_whale_follower::models::*;
+use pm_whale_follower::csv_log::{self, CSV_HEADER, CsvLog};
+use pm_whale_follower::ws_health::WsHealth;
+use pm_whale_follower::flatten::{FLATTEN_STATUS, FlattenOrder, FlattenRequest, FlattenResult, FlattenSide, flatten_orders};
+use pm_whale_follower::event_dedup::EventDedup;
+use pm_whale_follower::event_metrics::{self, EventPhase, EventTimings};
+use pm_whale_follower::logging::{self, HeartbeatLog, ResubmitLog, ResubmitOutcome, SlowEventLog, TradeSummaryLog, get_fill_color, strip_ansi_codes};
+use pm_whale_follower::notify::{Notifier, Notifiers, TradeNotification, should_notify};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-const GAMMA_API_BASE: &str = "https://gamma-api.polymarket.com";
 
 // ============================================================================
 // Thread-local buffers 
@@ -43,9 +61,54 @@ const GAMMA_API_BASE: &str = "https://gamma-api.polymarket.com";
 thread_local! {
     static CSV_BUF: RefCell<String> = RefCell::new(String::with_capacity(512));
     static SANITIZE_BUF: RefCell<String> = RefCell::new(String::with_capacity(128));
-    static TOKEN_ID_CACHE: RefCell<HashMap<[u8; 32], Arc<str>>> = RefCell::new(HashMap::with_capacity(256));
 }
 
+/// GTD order IDs resting on the book from this session (cancelled on shutdown)
+type PlacedGtdOrders = Arc<std::sync::Mutex<HashSet<String>>>;
+
+/// How long the open positions are reused before re-reading the DB
+const OPEN_POSITIONS_TTL: Duration = Duration::from_secs(10);
+
+/// Held shares per token and when they were loaded
+type OpenPositionsCache = Option<(HashMap<String, f64>, std::time::Instant)>;
+
+/// Status prefix of an order held back by MIN_SUBMIT_INTERVAL_MS
+const DEFERRED_RATE_LIMIT: &str = "DEFERRED_RATE_LIMIT";
+
+/// Status of a rate-limited order moved into the aggregation window (it is recorded again when flushed)
+const AGG_DEFERRED_STATUS: &str = "AGG_DEFERRED";
+
+/// Status of a POST_ONLY copy resting on the book; its fills are recorded by the GTD poller
+const POST_ONLY_STATUS: &str = "POST_ONLY";
+
+/// Status of an exit or flatten GTD resting on the book; its fills are recorded by the GTD poller
+const EXIT_RESTING_STATUS: &str = "EXIT_RESTING";
+
+/// Status of a fill from an observe-only trader: recorded, never copied
+const OBSERVED_STATUS: &str = "OBSERVED";
+
+/// A resting order to follow until it fills or expires: the request it came from, the POST /order response
+/// and how to record its fills
+type GtdWatch = (ResubmitRequest, String, GtdFillLabel);
+
+/// When each token last had an order submitted (or reported in DRY_RUN), for MIN_SUBMIT_INTERVAL_MS
+type SubmitTimes = HashMap<String, std::time::Instant>;
+
+/// Whale shares per (trader, token) built from fills seen this session, for SCALE_ON_OPEN / SCALE_ON_ADD
+type WhaleHoldings = HashMap<(String, String), f64>;
+
+/// Consecutive BUY copies per token this session, for COPY_DECAY (a SELL copy clears the token)
+type CopyStreaks = HashMap<String, u32>;
+
+/// Realized P&L per trader from the last heartbeat refresh, for CONFIDENCE_SCALING
+type TraderPnl = Arc<std::sync::Mutex<HashMap<String, RealizedPnl>>>;
+
+/// Retention pruning runs on startup, then at most this often from the heartbeat
+const PRUNE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How often the heartbeat logs stats and refreshes realized P&L
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60);
+
 // ============================================================================
 // Order Engine 
 // ============================================================================
@@ -56,6 +119,9 @@ struct OrderEngine {
     #[allow(dead_code)]
     resubmit_tx: mpsc::UnboundedSender<ResubmitRequest>,
     enable_trading: bool,
+    /// Runtime pause toggled via POST /pause and /resume (separate from enable_trading)
+    trading_paused: Arc<AtomicBool>,
+    reply_timeouts: OrderReplyTimeouts,
 }
 
 impl OrderEngine {
@@ -63,13 +129,19 @@ impl OrderEngine {
         if !self.enable_trading {
             return "SKIPPED_DISABLED".into();
         }
+        // Pausing stops new orders, not the exits or an operator's flatten closing what we hold
+        if self.trading_paused.load(Ordering::Relaxed) && ExitKind::from_order_type(&evt.order.order_type).is_none() {
+            return "SKIPPED_PAUSED".into();
+        }
 
+        let reply_timeout = self.reply_timeouts.for_shares(evt.order.shares);
         let (resp_tx, resp_rx) = oneshot::channel();
         if let Err(e) = self.tx.try_send(WorkItem { event: evt, respond_to: resp_tx, is_live }) {
             return format!("QUEUE_ERR: {e}");
         }
 
-        match tokio::time::timeout(ORDER_REPLY_TIMEOUT, resp_rx).await {
+        // On timeout the receiver is dropped: the worker still finishes the order and its reply is discarded
+        match tokio::time::timeout(reply_timeout, resp_rx).await {
             Ok(Ok(msg)) => msg,
             Ok(Err(_)) => "WORKER_DROPPED".into(),
             Err(_) => "WORKER_TIMEOUT".into(),
@@ -84,7 +156,6 @@ impl OrderEngine {
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv().ok();
-    ensure_csv()?;
 
     // Initialize market data caches
     market_cache::init_caches();
@@ -93,6 +164,13 @@ async fn main() -> Result<()> {
     let _cache_refresh_handle = market_cache::spawn_cache_refresh_task();
 
     let cfg = Config::from_env()?;
+    logging::init(cfg.log_format);
+    csv_log::init(CsvLog::new(&cfg.csv_file, CSV_HEADER, cfg.csv_max_bytes))?;
+    if let Some(path) = &cfg.raw_event_log {
+        raw_event_log::init(RawEventLog::new(path));
+        println!("Raw event log: {}", path);
+    }
+    event_metrics::init(Duration::from_millis(cfg.slow_event_ms));
 
     // Create reloadable traders config for hot-reload support
     let reloadable_traders = ReloadableTraders::new(cfg.traders.clone());
@@ -109,11 +187,15 @@ async fn main() -> Result<()> {
             min_trades: 2,
             max_pending_usd: 500.0,
             bypass_threshold: cfg.agg_bypass_shares,
+            aggregate_per_trader: cfg.agg_per_trader,
+            max_price_drift: cfg.agg_max_price_drift,
         };
         let agg = Arc::new(Mutex::new(TradeAggregator::new(agg_config)));
         println!(
-            "Trade aggregation enabled: {}ms window, bypass threshold: {} shares",
-            cfg.agg_window_ms, cfg.agg_bypass_shares
+            "Trade aggregation enabled: {}ms window, bypass threshold: {} shares{}",
+            cfg.agg_window_ms,
+            cfg.agg_bypass_shares,
+            if cfg.agg_per_trader { ", per trader" } else { "" }
         );
         Some(agg)
     } else {
@@ -121,40 +203,78 @@ async fn main() -> Result<()> {
         None
     };
 
+    // Last retention prune, handed to the heartbeat once the startup prune has run
+    let mut last_prune = None;
+
     // Initialize trade persistence channel (if enabled)
     // Uses a dedicated background thread to handle SQLite operations
-    let (trade_tx, stats_persist_path) = if cfg.db_enabled {
+    let (trade_tx, stats_persist_path, persistence, recent_trades) = if cfg.db_enabled {
+        println!("Trade persistence enabled: {}", cfg.db_path);
+        if let Some(days) = cfg.retention_days {
+            println!("Trade retention: {} days", days);
+            if claim_prune(&mut last_prune) {
+                let db_path = cfg.db_path.clone();
+                let _ = tokio::task::spawn_blocking(move || prune_old_trades(&db_path, days)).await;
+            }
+        }
+        if cfg.reconcile_on_start {
+            let db_path = cfg.db_path.clone();
+            let wallet = cfg.funder_address.clone().unwrap_or_else(|| cfg.wallet_address.clone());
+            let write_adjustments = cfg.reconcile_write_adjustments;
+            let _ = tokio::task::spawn_blocking(move || reconcile_positions(&db_path, &wallet, write_adjustments)).await;
+        }
+        // After pruning and reconciling: everything recorded from here on passes through the worker
+        let recent_trades = (cfg.recent_trades_capacity > 0).then(|| {
+            println!("Recent trades buffer: last {} trades served from memory", cfg.recent_trades_capacity);
+            Arc::new(RecentTrades::new(cfg.recent_trades_capacity, chrono::Utc::now().timestamp_millis()))
+        });
+
         let db_path = cfg.db_path.clone();
         let (tx, rx) = mpsc::unbounded_channel::<TradeRecord>();
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let worker_recent_trades = recent_trades.clone();
 
         // Spawn a background thread for persistence (SQLite is not Send)
         std::thread::spawn(move || {
-            persistence_worker(rx, &db_path);
+            persistence_worker(rx, stop_rx, &db_path, worker_recent_trades);
         });
 
-        println!("Trade persistence enabled: {}", cfg.db_path);
-        (Some(tx), Some(cfg.db_path.clone()))
+        (Some(tx), Some(cfg.db_path.clone()), Some(PersistenceHandle { stop_tx }), recent_trades)
     } else {
         println!("Trade persistence disabled");
-        (None, None)
+        (None, None, None, None)
     };
 
+    // Shared between the order engine and the API's /pause and /resume
+    let trading_paused = Arc::new(AtomicBool::new(false));
+    // /flatten requests, answered once the order engine is running
+    let (flatten_tx, flatten_rx) = mpsc::unbounded_channel::<FlattenRequest>();
+    // Updated by the WS loop, read by /health
+    let ws_health = Arc::new(WsHealth::new(Duration::from_secs(cfg.ws_stale_secs), cfg.trading_hours.clone()));
+
     // Start HTTP API server (if enabled)
     if cfg.api_enabled {
         let api_config = ApiConfig {
             enabled: cfg.api_enabled,
             port: cfg.api_port,
+            live_prices: cfg.api_live_prices,
+            fee_per_trade_usd: cfg.fee_per_trade_usd,
+            price_ttl_secs: cfg.api_price_ttl_secs,
         };
         let api_db_path = stats_persist_path.clone();
 
-        match start_api_server_with_reload(api_config, api_db_path, Some(reloadable_traders.clone())).await {
+        match start_api_server_with_reload(api_config, api_db_path, recent_trades, Some(reloadable_traders.clone()), Arc::clone(&trading_paused), Some(Arc::clone(&ws_health)), Some(flatten_tx)).await {
             Ok(_handle) => {
                 println!("HTTP API server started on http://127.0.0.1:{}", cfg.api_port);
-                println!("  - GET /health - Health check");
+                println!("  - GET /health - Health check (503 if the WebSocket is stalled)");
                 println!("  - GET /positions - Current positions");
-                println!("  - GET /trades?limit=N&since=TS - Trade history");
+                println!("  - GET /trades?limit=N&since=TS&trader=&token=&status= - Trade history");
                 println!("  - GET /stats - Aggregation statistics");
+                println!("  - GET /traders - Per-trader realized P&L and win rate");
+                println!("  - GET /pnl?token=ID - Realized, unrealized and total P&L");
                 println!("  - POST /reload - Reload trader configuration");
+                println!("  - POST /pause, POST /resume - Pause/resume order submission");
+                println!("  - POST /flatten?confirm=true - Close every position");
             }
             Err(e) => {
                 eprintln!("Warning: Failed to start API server: {}", e);
@@ -162,46 +282,203 @@ async fn main() -> Result<()> {
         }
     }
 
+    let creds_path = ".clob_creds.json";
     let (client, creds) = build_worker_state(
         cfg.private_key.clone(),
         cfg.funder_address.clone(),
+        &cfg.api_endpoints.clob,
         ".clob_market_cache.json",
-        ".clob_creds.json",
+        creds_path,
     ).await?;
+    println!(
+        "Execution wallet: signer {}, funder {} (from {})",
+        client.wallet_address(), client.funder_address(), cfg.execution_key_source
+    );
 
-    let prepared_creds = PreparedCreds::from_api_creds(&creds)?;
-    let risk_config = cfg.risk_guard_config();
+    // Shared by all workers so a re-derived key (after a 401) reaches every one of them
+    let shared_creds = Arc::new(SharedCreds::new(
+        PreparedCreds::from_api_creds(&creds)?,
+        Some(creds_path.into()),
+        CREDS_REFRESH_MIN_INTERVAL,
+    ).with_signer(client.wallet_address()));
+    // Orders go out under this key, so check it now rather than on the first copy
+    if cfg.enable_trading && !cfg.mock_trading {
+        let (check_client, check_creds) = (client.clone(), Arc::clone(&shared_creds));
+        if tokio::task::spawn_blocking(move || check_api_creds(&check_client, &check_creds)).await?? {
+            println!("API key accepted for signer {}", client.wallet_address());
+        }
+    }
+    // Shared with the shutdown sequence, which saves its state for the next start
+    let risk_guard = Arc::new(std::sync::Mutex::new(load_risk_guard(
+        cfg.risk_guard_config(),
+        cfg.cb_state_file.as_deref(),
+        Duration::from_secs(cfg.cb_state_max_age_mins * 60),
+    )));
 
     // Initialize portfolio tracker for dynamic bet sizing (if configured)
-    let portfolio_tracker = cfg.max_bet_portfolio_percent.map(|percent| {
-        let portfolio_config = PortfolioConfig {
+    // Kelly sizing needs the bankroll even without a portfolio percent cap
+    let portfolio_config = (cfg.max_bet_portfolio_percent.is_some() || cfg.max_bet_shares_abs.is_some() || cfg.kelly_sizing.is_some()).then(|| {
+        if let Some(percent) = cfg.max_bet_portfolio_percent {
+            println!(
+                "Portfolio-based bet limit enabled: {:.1}% of portfolio, cache: {}s",
+                percent * 100.0, cfg.portfolio_cache_secs
+            );
+        }
+        if let Some(shares) = cfg.max_bet_shares_abs {
+            println!("Share-based bet limit enabled: {:.2} shares per order", shares);
+        }
+        if let Some(kelly) = cfg.kelly_sizing {
+            println!(
+                "Kelly sizing enabled: edge {:.3}, {:.2}x Kelly (BUYs sized from portfolio value)",
+                kelly.edge, kelly.fraction
+            );
+        }
+        PortfolioConfig {
             wallet_address: cfg.wallet_address.clone(),
             cache_duration_secs: cfg.portfolio_cache_secs,
-            max_bet_portfolio_percent: Some(percent),
+            max_bet_portfolio_percent: cfg.max_bet_portfolio_percent,
+            max_bet_shares_abs: cfg.max_bet_shares_abs,
+        }
+    });
+
+    // Skip BUYs the funder wallet can't cover (checked against on-chain USDC)
+    let balance_funder = cfg.balance_check_enabled.then(|| {
+        println!("USDC balance check enabled: reserve ${:.2}, cache {}s", cfg.usdc_reserve, BALANCE_CACHE_TTL.as_secs());
+        cfg.funder_address.clone().unwrap_or_else(|| cfg.wallet_address.clone())
+    });
+
+    // Both trackers own blocking HTTP clients, which can't be created on the runtime
+    let usdc_reserve = cfg.usdc_reserve;
+    let (portfolio_tracker, balance_tracker) = tokio::task::spawn_blocking(move || {
+        (
+            portfolio_config.map(|config| Arc::new(PortfolioTracker::new(config))),
+            balance_funder.map(|funder| {
+                Arc::new(BalanceTracker::new(Box::new(RpcUsdcBalance::new(&funder)), usdc_reserve, BALANCE_CACHE_TTL))
+            }),
+        )
+    }).await?;
+
+    let order_settings = OrderSettings { db_path: stats_persist_path.clone(), ..OrderSettings::from_config(&cfg) };
+    if let Some(fraction) = order_settings.spread_buffer_fraction {
+        println!("Dynamic spread buffer enabled: {:.0}% of spread (capped at tier buffer)", fraction * 100.0);
+    }
+    if let Some(usd) = order_settings.flat_usd {
+        println!("Flat sizing enabled: ${:.2} per copied trade", usd);
+    }
+    if order_settings.execution_tiers != ExecutionTiers::default() {
+        let tiers: Vec<String> = order_settings.execution_tiers.tiers().iter()
+            .map(|t| format!("{}+ {} +{:.2} {:.2}x", t.min_shares, t.order_action, t.price_buffer, t.size_multiplier))
+            .collect();
+        println!("Custom execution tiers: {}", tiers.join(", "));
+    }
+    if let Some(shadow) = &order_settings.shadow {
+        let sizing = match (shadow.kelly_sizing, shadow.flat_usd) {
+            (Some(kelly), _) => format!("Kelly {:.0}% of bankroll at edge {}", kelly.fraction * 100.0, kelly.edge),
+            (None, Some(usd)) => format!("${:.2} flat", usd),
+            (None, None) => match shadow.scaling_ratio {
+                Some(ratio) => format!("{}x whale size", ratio),
+                None => "per-trader scaling".to_string(),
+            },
         };
-        let tracker = PortfolioTracker::new(portfolio_config);
+        if cfg.db_enabled {
+            println!("👥 SHADOW_MODE: also recording {} as SHADOW rows (never submitted)", sizing);
+        } else {
+            eprintln!("Warning: SHADOW_MODE needs DB_ENABLED=true; shadow orders will not be recorded");
+        }
+    }
+    if order_settings.dry_run && !cfg.mock_trading {
+        println!("🧪 DRY_RUN: orders are priced and sized against live books but never submitted");
+    }
+    if !order_settings.token_blocklist.is_empty() || !order_settings.token_allowlist.is_empty() {
         println!(
-            "Portfolio-based bet limit enabled: {:.1}% of portfolio, cache: {}s",
-            percent * 100.0, cfg.portfolio_cache_secs
+            "Token filter: {} blocklisted, {} allowlisted{}",
+            order_settings.token_blocklist.len(),
+            order_settings.token_allowlist.len(),
+            if order_settings.token_allowlist.is_empty() { " (all others allowed)" } else { "" }
         );
-        Arc::new(tracker)
-    });
+    }
+    if !order_settings.trading_hours.is_empty() {
+        let windows: Vec<String> = order_settings.trading_hours.iter()
+            .map(|w| format!("{:02}:{:02}-{:02}:{:02}", w.start_min / 60, w.start_min % 60, w.end_min / 60, w.end_min % 60))
+            .collect();
+        println!("Trading hours (UTC): {}", windows.join(", "));
+    }
 
     let (order_tx, order_rx) = mpsc::channel(1024);
     let (resubmit_tx, resubmit_rx) = mpsc::unbounded_channel::<ResubmitRequest>();
+    let (gtd_watch_tx, gtd_watch_rx) = mpsc::unbounded_channel::<GtdWatch>();
 
     let client_arc = Arc::new(client);
-    let creds_arc = Arc::new(prepared_creds.clone());
 
-    start_order_worker(order_rx, client_arc.clone(), prepared_creds, cfg.enable_trading, cfg.mock_trading, risk_config, resubmit_tx.clone(), stats_persist_path.clone(), portfolio_tracker);
-
-    tokio::spawn(resubmit_worker(resubmit_rx, client_arc, creds_arc));
+    let shadow_tx = order_settings.shadow.and(trade_tx.clone());
+    // GTD orders resting on the book: added by the order and resubmit workers, removed by the fill pollers
+    let placed_gtd_orders = PlacedGtdOrders::default();
+    // Per-trader realized P&L: refreshed by the heartbeat, read by the order worker
+    let trader_pnl = TraderPnl::default();
+    let worker_state = OrderWorkerState {
+        placed_gtd_orders: Arc::clone(&placed_gtd_orders),
+        trader_pnl: Arc::clone(&trader_pnl),
+        breaker: ExchangeBreaker::new(cfg.exchange_breaker_failures, Duration::from_secs(cfg.exchange_breaker_cooldown_secs)),
+        resubmit_tx: Some(resubmit_tx.clone()),
+        gtd_watch_tx: Some(gtd_watch_tx),
+        portfolio_tracker,
+        balance_tracker,
+        ..OrderWorkerState::default()
+    };
+    start_order_worker(order_rx, client_arc.clone(), Arc::clone(&shared_creds), Arc::clone(&risk_guard), worker_state, order_settings, shadow_tx);
+
+    // Keep a client for cancelling resting GTD orders on shutdown
+    let shutdown_client = cfg.cancel_on_shutdown
+        .then(|| (Arc::clone(&client_arc), Arc::clone(&shared_creds), Arc::clone(&placed_gtd_orders)));
+
+    let gtd_recorder = GtdFillRecorder {
+        trade_tx: trade_tx.clone(),
+        trader_manager: Arc::clone(&trader_manager),
+        pollers: GtdPollers::default(),
+        gtd_expiry: cfg.gtd_expiry,
+        success_fill_pct: cfg.success_fill_pct,
+        placed_gtd_orders: Arc::clone(&placed_gtd_orders),
+    };
+    tokio::spawn(gtd_watch_worker(gtd_watch_rx, Arc::clone(&client_arc), Arc::clone(&shared_creds), gtd_recorder.clone()));
+    tokio::spawn(resubmit_worker(resubmit_rx, client_arc, shared_creds, gtd_recorder, cfg.resubmit_policy, cfg.fee_rate_bps));
 
     let order_engine = OrderEngine {
         tx: order_tx,
         resubmit_tx,
         enable_trading: cfg.enable_trading,
+        trading_paused,
+        reply_timeouts: cfg.order_reply_timeouts,
     };
+    tokio::spawn(flatten_worker(flatten_rx, stats_persist_path.clone(), cfg.api_endpoints.clob.clone(), order_engine.clone(), trade_tx.clone()));
+
+    match (cfg.position_exits, &stats_persist_path) {
+        (Some(exits), Some(db_path)) => {
+            if let Some(stop_loss) = exits.stop_loss {
+                println!("Stop-loss enabled: exit positions down {:.1}% from entry", stop_loss.loss_pct);
+            }
+            if let Some(trailing) = exits.trailing_stop {
+                println!(
+                    "Trailing stop enabled: exit positions down {:.1}% from their peak (state: {})",
+                    trailing.retrace_pct, cfg.trailing_stop_state_file
+                );
+            }
+            if let Some(take_profit) = exits.take_profit {
+                println!(
+                    "Take-profit enabled: sell {:.0}% of positions up {:.1}% from entry",
+                    take_profit.sell_fraction * 100.0, take_profit.gain_pct
+                );
+            }
+            println!("Position exits checked every {}s", exits.check_interval_secs);
+            spawn_position_exit_task(exits, db_path.clone(), cfg.api_endpoints.clob.clone(), PathBuf::from(&cfg.trailing_stop_state_file), order_engine.clone(), trade_tx.clone(), cfg.gtd_expiry);
+        }
+        (Some(_), None) => eprintln!("Warning: STOP_LOSS_PCT / TRAILING_STOP_PCT / TAKE_PROFIT_PCT need trade persistence (DB_ENABLED) to know positions; exits disabled"),
+        (None, _) => {}
+    }
+
+    let notifier = Notifiers::from_config(&cfg);
+    if !notifier.is_empty() {
+        println!("Notifications enabled: {} sink(s)", notifier.len());
+    }
 
     println!(
         "🚀 Starting trader. Trading: {}, Mock: {}",
@@ -214,6 +491,8 @@ async fn main() -> Result<()> {
         let order_engine_clone = order_engine.clone();
         let trade_tx_clone = trade_tx.clone();
         let trader_manager_clone = Arc::clone(&trader_manager);
+        let notifier_clone = notifier.clone();
+        let success_fill_pct = cfg.success_fill_pct;
         tokio::spawn(async move {
             let mut interval = tokio::time::interval(Duration::from_millis(100));
             loop {
@@ -239,17 +518,19 @@ async fn main() -> Result<()> {
 
                     // Convert aggregated trade to event and execute
                     let evt = aggregated.to_parsed_event();
-                    let status = order_engine_clone.submit(evt.clone(), is_live).await;
+                    // Not parked again: that would lose the window's trade count
+                    let status = submit_with_deferral(&order_engine_clone, evt.clone(), is_live, None).await;
                     println!("[AGG] Flush result: {}", status);
 
                     // Record the aggregated trade result to CSV and DB
                     record_aggregated_trade(
-                        &evt,
                         &status,
                         is_live,
                         &trade_tx_clone,
                         &trader_manager_clone,
-                        count,
+                        &notifier_clone,
+                        &aggregated,
+                        success_fill_pct,
                     ).await;
                 }
             }
@@ -267,12 +548,13 @@ async fn main() -> Result<()> {
                 sighup.recv().await;
                 println!("\n🔄 Received SIGHUP, reloading trader configuration...");
                 match reloadable_traders_sighup.reload().await {
-                    Ok(true) => {
-                        println!("✅ Configuration reloaded. WebSocket will reconnect with new traders.");
-                    }
-                    Ok(false) => {
+                    Ok(diff) if diff.is_empty() => {
                         println!("ℹ️ Configuration unchanged.");
                     }
+                    Ok(diff) => {
+                        println!("✅ Configuration reloaded: {}", diff.summary());
+                        println!("   WebSocket will reconnect if the trader list changed.");
+                    }
                     Err(e) => {
                         eprintln!("❌ Failed to reload configuration: {}", e);
                     }
@@ -282,75 +564,68 @@ async fn main() -> Result<()> {
         println!("SIGHUP handler registered (kill -HUP {} to reload config)", std::process::id());
     }
 
-    // Spawn signal handler for graceful shutdown
-    // Note: When the channel is dropped, the persistence worker will flush and exit
-    let aggregator_shutdown = aggregator.clone();
-    let order_engine_shutdown = order_engine.clone();
-    let trade_tx_shutdown = trade_tx.clone();
-    let trader_manager_shutdown = Arc::clone(&trader_manager);
+    // Spawn signal handler for graceful shutdown (SIGINT, and SIGTERM on Unix)
+    let shutdown_ctx = ShutdownContext {
+        aggregator: aggregator.clone(),
+        order_engine: order_engine.clone(),
+        trade_tx: trade_tx.clone(),
+        trader_manager: Arc::clone(&trader_manager),
+        notifier: notifier.clone(),
+        success_fill_pct: cfg.success_fill_pct,
+        cancel_client: shutdown_client,
+        risk_guard: cfg.cb_state_file.clone().map(|path| (Arc::clone(&risk_guard), PathBuf::from(path))),
+        persistence,
+    };
     tokio::spawn(async move {
-        if let Ok(()) = tokio::signal::ctrl_c().await {
-            println!("\nReceived shutdown signal, shutting down...");
-
-            // Flush any pending aggregations before shutdown
-            if let Some(agg) = aggregator_shutdown {
-                let pending_aggregations = {
-                    let mut agg_lock = agg.lock().await;
-                    agg_lock.flush_all()
-                };
-
-                if !pending_aggregations.is_empty() {
-                    println!(
-                        "[AGG] Shutdown: flushing {} pending aggregations",
-                        pending_aggregations.len()
-                    );
-
-                    // Execute pending aggregations before exit
-                    for aggregated in pending_aggregations {
-                        let token_id = aggregated.token_id.clone();
-                        let count = aggregated.trade_count;
-                        println!(
-                            "[AGG] Shutdown flush: {} trades -> {:.2} shares @ {:.4} avg",
-                            count, aggregated.total_shares, aggregated.avg_price
-                        );
-
-                        // Get market liveness from cache
-                        let is_live = market_cache::get_is_live(&token_id);
-
-                        // Execute the aggregated trade
-                        let evt = aggregated.to_parsed_event();
-                        let status = order_engine_shutdown.submit(evt.clone(), is_live).await;
-                        println!("[AGG] Shutdown result: {}", status);
-
-                        // Record the trade result to CSV and DB
-                        record_aggregated_trade(
-                            &evt,
-                            &status,
-                            is_live,
-                            &trade_tx_shutdown,
-                            &trader_manager_shutdown,
-                            count,
-                        ).await;
-                    }
-                }
-            }
-
-            std::process::exit(0);
-        }
+        let signal = wait_for_shutdown_signal().await;
+        println!("\nReceived {}, shutting down...", signal);
+        // Both handlers stay registered, so a second signal during the flush is absorbed
+        // instead of killing the process; the context is consumed, so this runs once
+        graceful_shutdown(shutdown_ctx).await;
+        std::process::exit(0);
     });
 
+    // Consecutive connection failures, reset once a subscription is confirmed
+    let mut reconnect_failures: u32 = 0;
+    // Failover order from WSS_URLS (or the single provider URL)
+    let mut endpoints = WsEndpoints::new(cfg.wss_urls.clone());
+    if endpoints.count() > 1 {
+        println!("🔀 {} WebSocket endpoints configured, starting on {}", endpoints.count(), endpoints.active_label());
+    }
+    // Last block seen across reconnects, for gap detection
+    let mut last_block_seen: Option<u64> = None;
+    // Outlives each connection so resends after a reconnect are caught too; shared with replays
+    let event_dedup = Arc::new(Mutex::new(EventDedup::new(Duration::from_millis(cfg.ws_dedup_window_ms))));
+    let mut heartbeat = HeartbeatState { last_prune, placed_gtd_orders, trader_pnl, balance: Default::default() };
+
     loop {
         // Check if config changed before connecting
         let current_gen = reloadable_traders.generation();
 
-        if let Err(e) = run_ws_loop(&cfg, &reloadable_traders, &order_engine, trade_tx.clone(), Arc::clone(&trader_manager), stats_persist_path.clone(), aggregator.clone(), &mut config_change_rx).await {
+        if let Err(e) = run_ws_loop(&cfg, endpoints.active(), &reloadable_traders, &order_engine, trade_tx.clone(), Arc::clone(&trader_manager), stats_persist_path.clone(), aggregator.clone(), &notifier, &ws_health, &event_dedup, &mut config_change_rx, &mut reconnect_failures, &mut last_block_seen, &mut heartbeat).await {
             // Check if error was due to config reload
             if reloadable_traders.generation() != current_gen {
+                // Intentional reconnect - don't count towards backoff
                 println!("🔄 Config changed, reconnecting with new traders...");
+                tokio::time::sleep(WS_RECONNECT_DELAY).await;
             } else {
-                eprintln!("⚠️ WS error: {e}. Reconnecting...");
+                reconnect_failures = reconnect_failures.saturating_add(1);
+                let failed = endpoints.active_label();
+                if endpoints.count() > 1 {
+                    endpoints.rotate();
+                    eprintln!("🔀 WS endpoint {} failed, rotating to {}", failed, endpoints.active_label());
+                }
+                // Try every endpoint once before backing off
+                match endpoints.reconnect_delay(reconnect_failures) {
+                    None => eprintln!("⚠️ WS error: {e}. Failing over now (attempt {})...", reconnect_failures),
+                    Some(backoff) => {
+                        let jitter = Duration::from_millis(rand::thread_rng().gen_range(0..=WS_RECONNECT_JITTER_MS));
+                        let delay = backoff + jitter;
+                        eprintln!("⚠️ WS error: {e}. Reconnecting in {:.1}s (attempt {})...", delay.as_secs_f64(), reconnect_failures);
+                        tokio::time::sleep(delay).await;
+                    }
+                }
             }
-            tokio::time::sleep(WS_RECONNECT_DELAY).await;
         }
     }
 }
@@ -362,12 +637,13 @@ async fn main() -> Result<()> {
 async fn build_worker_state(
     private_key: String,
     funder: Option<String>,
+    host: &str,
     cache_path: &str,
     creds_path: &str,
 ) -> Result<(RustClobClient, ApiCreds)> {
     let cache_path = cache_path.to_string();
     let creds_path = creds_path.to_string();
-    let host = CLOB_API_BASE.to_string();
+    let host = host.to_string();
 
     tokio::task::spawn_blocking(move || -> Result<(RustClobClient, ApiCreds)> {
         let mut client = RustClobClient::new(&host, 137, &private_key, funder.as_deref())?
@@ -376,39 +652,324 @@ async fn build_worker_state(
 
         let _ = client.prewarm_connections();
 
-        let creds: ApiCreds = if Path::new(&creds_path).exists() {
-            let data = std::fs::read_to_string(&creds_path)?;
-            serde_json::from_str(&data)?
-        } else {
-            let derived = client.derive_api_key(0)?;
-            std::fs::write(&creds_path, serde_json::to_string_pretty(&derived)?)?;
-            derived
-        };
+        // Keyed by signer: an EXECUTION_PRIVATE_KEY gets its own API key, not the one on file
+        let creds = load_or_derive(Path::new(&creds_path), client.wallet_address(), || client.derive_api_key(0))?;
 
         Ok((client, creds))
     }).await?
 }
 
-fn start_order_worker(
-    rx: mpsc::Receiver<WorkItem>,
-    client: Arc<RustClobClient>,
-    creds: PreparedCreds,
+/// Fail startup if the exchange rejects the API key for an authenticated request
+/// A 401 re-derives the key once (see `with_auth_retry`) before giving up. Any other
+/// failure (5xx, timeout) only warns, since the exchange may just be down; returns
+/// whether the key was confirmed.
+fn check_api_creds(client: &RustClobClient, creds: &SharedCreds) -> Result<bool> {
+    let resp = match with_auth_retry(creds, response_status, || client.derive_api_key(0), |creds| client.get_open_orders(creds)) {
+        Ok(resp) => resp,
+        Err(e) => {
+            eprintln!("⚠️ Could not check API key at startup (continuing): {}", e);
+            return Ok(false);
+        }
+    };
+    let status = resp.status();
+    if api_key_rejected(status.as_u16()) {
+        anyhow::bail!(
+            "API key for signer {} rejected: HTTP {} {}",
+            client.wallet_address(), status, resp.text().unwrap_or_default()
+        );
+    }
+    if !status.is_success() {
+        eprintln!("⚠️ Could not check API key at startup (continuing): HTTP {}", status);
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Statuses that mean the exchange refused the key itself (not just an outage)
+fn api_key_rejected(http_status: u16) -> bool {
+    matches!(http_status, 401 | 403)
+}
+
+/// Order options from Config, passed through to the order worker
+#[derive(Debug, Clone, Default)]
+struct OrderSettings {
+    /// ENABLE_TRADING: off skips every order
     enable_trading: bool,
+    /// MOCK_TRADING: log orders without pricing or submitting them
     mock_trading: bool,
-    risk_config: RiskGuardConfig,
-    resubmit_tx: mpsc::UnboundedSender<ResubmitRequest>,
+    /// Trade DB for held positions and caps (None = persistence off, checks skipped)
     db_path: Option<String>,
-    portfolio_tracker: Option<Arc<PortfolioTracker>>,
+    /// Fraction of the live spread used as buffer (None = fixed tier buffer)
+    spread_buffer_fraction: Option<f64>,
+    /// Reject orders priced too far from the whale's fill (None = disabled)
+    max_slippage: Option<MaxSlippage>,
+    /// BUY tiers at or above this whale size use FOK instead of FAK
+    fok_min_shares: Option<f64>,
+    /// BUY buffer, order type and size multiplier per whale-size tier
+    execution_tiers: ExecutionTiers,
+    /// Size BUYs from bankroll instead of whale size (None = scaled sizing)
+    kelly_sizing: Option<KellySizing>,
+    /// Fixed USD per copied trade instead of whale size (None = scaled sizing)
+    flat_usd: Option<f64>,
+    /// Fee rate signed into copy orders (None = 0, the server default)
+    fee_rate_bps: Option<i64>,
+    /// Skip BUYs opening a new token beyond this many open positions (None = unlimited)
+    max_open_positions: Option<usize>,
+    /// Max USD held in a single token (None = no cap)
+    max_exposure_per_market_usd: Option<f64>,
+    /// Token IDs never copied
+    token_blocklist: HashSet<String>,
+    /// If non-empty, the only token IDs copied
+    token_allowlist: HashSet<String>,
+    /// UTC windows when orders may be placed (empty = always)
+    trading_hours: Vec<TradingWindow>,
+    /// Whale-size scaling for traders without their own ratio
+    scaling_ratio: f64,
+    /// Stop just before submitting and report the order instead
+    dry_run: bool,
+    /// Skip neg-risk markets instead of routing them to the neg-risk exchange
+    skip_neg_risk: bool,
+    /// Smallest order placed (MIN_SHARE_COUNT / MIN_CASH_VALUE)
+    size_floor: SizeFloor,
+    /// Whale fill prices worth copying (per-trader bounds override these)
+    copy_price_range: CopyPriceRange,
+    /// Ratio multipliers for whale BUYs that open vs add to a position
+    entry_scaling: EntryScaling,
+    /// Orders on a token submitted sooner than this after the last one are deferred (zero = no limit)
+    min_submit_interval: Duration,
+    /// BUYs this close to a market's end time are skipped (zero = only closed / halted markets)
+    market_close_window: Duration,
+    /// Scale BUYs by the trader's realized win rate (None = off)
+    confidence_scaling: Option<ConfidenceScaling>,
+    /// Shrink consecutive BUY copies of a token geometrically (None = off)
+    copy_decay: Option<CopyDecay>,
+    /// GTD order lifetime for live vs other markets
+    gtd_expiry: GtdExpiry,
+    /// Second sizing strategy recorded as SHADOW rows, never submitted (None = off)
+    shadow: Option<ShadowStrategy>,
+    /// Copy with post-only GTDs resting at the whale's price (position exits still cross)
+    post_only: bool,
+}
+
+impl OrderSettings {
+    fn from_config(cfg: &Config) -> Self {
+        Self {
+            enable_trading: cfg.enable_trading,
+            mock_trading: cfg.mock_trading,
+            db_path: None,
+            spread_buffer_fraction: cfg.dynamic_spread_buffer.then_some(cfg.spread_buffer_fraction),
+            max_slippage: cfg.max_slippage,
+            fok_min_shares: cfg.fok_min_shares,
+            execution_tiers: cfg.execution_tiers.clone(),
+            kelly_sizing: cfg.kelly_sizing,
+            flat_usd: cfg.flat_usd,
+            fee_rate_bps: cfg.fee_rate_bps,
+            max_open_positions: cfg.max_open_positions,
+            max_exposure_per_market_usd: cfg.max_exposure_per_market_usd,
+            token_blocklist: cfg.token_blocklist.clone(),
+            token_allowlist: cfg.token_allowlist.clone(),
+            trading_hours: cfg.trading_hours.clone(),
+            scaling_ratio: cfg.scaling_ratio,
+            dry_run: cfg.dry_run,
+            skip_neg_risk: cfg.skip_neg_risk,
+            size_floor: cfg.size_floor,
+            copy_price_range: cfg.copy_price_range,
+            entry_scaling: cfg.entry_scaling,
+            min_submit_interval: Duration::from_millis(cfg.min_submit_interval_ms),
+            market_close_window: Duration::from_secs(cfg.market_close_window_secs),
+            confidence_scaling: cfg.confidence_scaling,
+            copy_decay: cfg.copy_decay,
+            gtd_expiry: cfg.gtd_expiry,
+            shadow: cfg.shadow_strategy,
+            post_only: cfg.post_only,
+        }
+    }
+}
+
+fn start_order_worker(
+    rx: mpsc::Receiver<WorkItem>,
+    client: Arc<RustClobClient>,
+    creds: Arc<SharedCreds>,
+    risk_guard: Arc<std::sync::Mutex<RiskGuard>>,
+    mut state: OrderWorkerState,
+    order_settings: OrderSettings,
+    shadow_tx: Option<mpsc::UnboundedSender<TradeRecord>>,
 ) {
     std::thread::spawn(move || {
-        let mut guard = RiskGuard::new(risk_config);
-        order_worker(rx, client, creds, enable_trading, mock_trading, &mut guard, resubmit_tx, db_path.as_deref(), portfolio_tracker);
+        order_worker(rx, client, creds, &risk_guard, &mut state, order_settings, shadow_tx);
     });
 }
 
+/// How long shutdown waits for the persistence worker to write buffered trades
+const PERSISTENCE_FLUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Asks the persistence worker to write everything it has received and stop
+struct PersistenceHandle {
+    /// Carries the channel the worker signals once the store is flushed
+    stop_tx: oneshot::Sender<oneshot::Sender<()>>,
+}
+
+impl PersistenceHandle {
+    /// Flush the trade store and wait for the worker to finish (bounded by PERSISTENCE_FLUSH_TIMEOUT)
+    async fn flush_and_stop(self) {
+        let (done_tx, done_rx) = oneshot::channel();
+        if self.stop_tx.send(done_tx).is_err() {
+            return; // Worker already gone
+        }
+        if tokio::time::timeout(PERSISTENCE_FLUSH_TIMEOUT, done_rx).await.is_err() {
+            eprintln!("Warning: Timed out waiting for trade store flush");
+        }
+    }
+}
+
+/// What the shutdown sequence flushes or cancels
+struct ShutdownContext {
+    aggregator: Option<Arc<Mutex<TradeAggregator>>>,
+    order_engine: OrderEngine,
+    trade_tx: Option<mpsc::UnboundedSender<TradeRecord>>,
+    trader_manager: Arc<Mutex<TraderManager>>,
+    notifier: Notifiers,
+    success_fill_pct: f64,
+    /// Set with CANCEL_ON_SHUTDOWN, with the GTD orders to cancel
+    cancel_client: Option<(Arc<RustClobClient>, Arc<SharedCreds>, PlacedGtdOrders)>,
+    /// Guard and CB_STATE_FILE path its state is saved to
+    risk_guard: Option<(Arc<std::sync::Mutex<RiskGuard>>, PathBuf)>,
+    persistence: Option<PersistenceHandle>,
+}
+
+/// New risk guard, restoring trips and sequences saved within `max_age` at `state_file`
+fn load_risk_guard(config: RiskGuardConfig, state_file: Option<&str>, max_age: Duration) -> RiskGuard {
+    let mut guard = RiskGuard::new(config);
+    let Some(path) = state_file.map(Path::new).filter(|p| p.exists()) else { return guard };
+
+    match RiskGuardSnapshot::load(path) {
+        Ok(snapshot) => match guard.restore(&snapshot, max_age) {
+            0 => {}
+            n => println!("Restored risk guard state for {} token(s) from {}", n, path.display()),
+        },
+        Err(e) => eprintln!("Warning: Ignoring risk guard state: {:#}", e),
+    }
+    guard
+}
+
+/// Write the guard's trips and recent large-trade sequences to `path`
+fn save_risk_guard_state(guard: &std::sync::Mutex<RiskGuard>, path: &Path) {
+    let snapshot = guard.lock().unwrap_or_else(|e| e.into_inner()).snapshot();
+    match snapshot.save(path) {
+        Ok(()) if !snapshot.tokens.is_empty() => {
+            println!("Saved risk guard state for {} token(s) to {}", snapshot.tokens.len(), path.display());
+        }
+        Ok(()) => {}
+        Err(e) => eprintln!("Warning: Failed to save risk guard state: {:#}", e),
+    }
+}
+
+/// Wait for SIGINT (Ctrl-C) or, on Unix, SIGTERM (systemd/Docker stop); returns the signal name
+async fn wait_for_shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => "SIGINT",
+                    _ = sigterm.recv() => "SIGTERM",
+                }
+            }
+            Err(e) => {
+                eprintln!("Warning: Failed to register SIGTERM handler: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                "SIGINT"
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "SIGINT"
+    }
+}
+
+/// Shutdown sequence: submit and record pending aggregations, cancel resting GTD orders
+/// (if configured), then flush the trade store
+async fn graceful_shutdown(ctx: ShutdownContext) {
+    // Flush any pending aggregations before shutdown
+    if let Some(agg) = &ctx.aggregator {
+        let pending_aggregations = {
+            let mut agg_lock = agg.lock().await;
+            agg_lock.flush_all()
+        };
+
+        if !pending_aggregations.is_empty() {
+            println!(
+                "[AGG] Shutdown: flushing {} pending aggregations",
+                pending_aggregations.len()
+            );
+
+            // Execute pending aggregations before exit
+            for aggregated in pending_aggregations {
+                let token_id = aggregated.token_id.clone();
+                let count = aggregated.trade_count;
+                println!(
+                    "[AGG] Shutdown flush: {} trades -> {:.2} shares @ {:.4} avg",
+                    count, aggregated.total_shares, aggregated.avg_price
+                );
+
+                // Get market liveness from cache
+                let is_live = market_cache::get_is_live(&token_id);
+
+                // Execute the aggregated trade
+                let evt = aggregated.to_parsed_event();
+                let status = submit_with_deferral(&ctx.order_engine, evt.clone(), is_live, None).await;
+                println!("[AGG] Shutdown result: {}", status);
+
+                // Record the trade result to CSV and DB
+                record_aggregated_trade(
+                    &status,
+                    is_live,
+                    &ctx.trade_tx,
+                    &ctx.trader_manager,
+                    &ctx.notifier,
+                    &aggregated,
+                    ctx.success_fill_pct,
+                ).await;
+            }
+        }
+    }
+
+    // Cancel GTD orders we left on the book so nothing fills unattended
+    if let Some((client, creds, placed)) = ctx.cancel_client {
+        let result = tokio::task::spawn_blocking(move || cancel_all_open_orders(&client, &creds.current(), &placed)).await;
+        match result {
+            Ok(Ok(0)) => {}
+            Ok(Ok(count)) => println!("Cancelled {} open GTD order(s)", count),
+            Ok(Err(e)) => eprintln!("Warning: Failed to cancel open orders: {}", e),
+            Err(e) => eprintln!("Warning: Cancel task failed: {}", e),
+        }
+    }
+
+    // Keep protective blocks in effect across a quick restart
+    if let Some((guard, path)) = ctx.risk_guard {
+        // The order worker holds the lock while an order is in flight
+        let _ = tokio::task::spawn_blocking(move || save_risk_guard_state(&guard, &path)).await;
+    }
+
+    // Write buffered trades (including the aggregations recorded above) before exit
+    if let Some(persistence) = ctx.persistence {
+        persistence.flush_and_stop().await;
+    }
+}
+
 /// Background worker for trade persistence
 /// Runs on a dedicated thread to avoid Send/Sync issues with rusqlite
-fn persistence_worker(rx: mpsc::UnboundedReceiver<TradeRecord>, db_path: &str) {
+/// Exits when every sender is dropped or a stop request arrives; either way the store is flushed
+/// Every record is also kept in `recent_trades` (the API's in-memory /trades buffer) when given
+fn persistence_worker(
+    rx: mpsc::UnboundedReceiver<TradeRecord>,
+    stop_rx: oneshot::Receiver<oneshot::Sender<()>>,
+    db_path: &str,
+    recent_trades: Option<Arc<RecentTrades>>,
+) {
     // Create TradeStore on this thread (SQLite connection is not Send)
     let store = match TradeStore::new(db_path) {
         Ok(s) => s,
@@ -424,144 +985,819 @@ fn persistence_worker(rx: mpsc::UnboundedReceiver<TradeRecord>, db_path: &str) {
         .build()
         .expect("Failed to create tokio runtime for persistence worker");
 
+    let spill_path = spill_path(db_path);
+    let remember = |record: &TradeRecord| {
+        if let Some(buffer) = &recent_trades {
+            buffer.push(record.clone());
+        }
+    };
     rt.block_on(async {
         let mut rx = rx;
-        while let Some(record) = rx.recv().await {
-            store.record_trade(record);
-        }
+        let mut stop_rx = stop_rx;
+        let done = loop {
+            tokio::select! {
+                record = rx.recv() => match record {
+                    Some(record) => {
+                        remember(&record);
+                        if store.record_trade(record).is_err() {
+                            flush_with_fallback(&store, &spill_path, DB_RETRY_BACKOFF).await;
+                        }
+                    }
+                    None => break None,
+                },
+                Ok(done) = &mut stop_rx => {
+                    // Take everything queued before the stop request
+                    while let Ok(record) = rx.try_recv() {
+                        remember(&record);
+                        let _ = store.record_trade(record); // Flushed (or spilled) below
+                    }
+                    break Some(done);
+                }
+            }
+        };
 
-        // Channel closed - flush remaining trades
-        match store.flush() {
-            Ok(count) if count > 0 => println!("Flushed {} trades to database on shutdown", count),
-            Ok(_) => {}
-            Err(e) => eprintln!("Warning: Failed to flush trade store on shutdown: {}", e),
+        // Channel closed or shutdown requested - flush remaining trades
+        let pending = store.buffered_count();
+        if flush_with_fallback(&store, &spill_path, DB_RETRY_BACKOFF).await && pending > 0 {
+            println!("Flushed {} trades to database on shutdown", pending);
+        }
+        if let Some(done) = done {
+            let _ = done.send(());
         }
     });
 }
 
-fn order_worker(
-    mut rx: mpsc::Receiver<WorkItem>,
-    client: Arc<RustClobClient>,
-    creds: PreparedCreds,
-    enable_trading: bool,
-    mock_trading: bool,
-    guard: &mut RiskGuard,
-    resubmit_tx: mpsc::UnboundedSender<ResubmitRequest>,
-    db_path: Option<&str>,
-    portfolio_tracker: Option<Arc<PortfolioTracker>>,
-) {
-    let mut client_mut = (*client).clone();
-    while let Some(work) = rx.blocking_recv() {
-        let status = process_order(&work.event.order, work.event.trader_min_shares, &mut client_mut, &creds, enable_trading, mock_trading, guard, &resubmit_tx, work.is_live, db_path, portfolio_tracker.as_ref());
-        let _ = work.respond_to.send(status);
+/// Flush attempts before buffered trades are spilled to the fallback file
+const DB_WRITE_ATTEMPTS: u32 = 3;
+
+/// Wait before the first flush retry (doubled for each further retry)
+const DB_RETRY_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Newline-JSON file next to the database that takes trades the database couldn't
+fn spill_path(db_path: &str) -> PathBuf {
+    PathBuf::from(format!("{}.spill.jsonl", db_path))
+}
+
+/// Write buffered trades to the database, retrying with backoff; if it keeps failing,
+/// spill them to `spill_path` so no trade is lost. Returns true if the database took them.
+async fn flush_with_fallback(store: &TradeStore, spill_path: &Path, backoff: Duration) -> bool {
+    let mut delay = backoff;
+    for attempt in 1..=DB_WRITE_ATTEMPTS {
+        match store.flush() {
+            Ok(_) => return true,
+            Err(e) => eprintln!("Warning: Failed to write trades to database (attempt {}/{}): {:#}", attempt, DB_WRITE_ATTEMPTS, e),
+        }
+        if attempt < DB_WRITE_ATTEMPTS {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+
+    match store.spill_buffered(spill_path) {
+        Ok(count) => eprintln!(
+            "🚨 DATABASE UNAVAILABLE: {} trade(s) written to {} instead (one JSON record per line)",
+            count, spill_path.display()
+        ),
+        // Still buffered: the next flush tries the database (and this file) again
+        Err(e) => eprintln!("🚨 DATABASE UNAVAILABLE and {:#}. {} trade(s) kept in memory.", e, store.buffered_count()),
     }
+    false
 }
 
 // ============================================================================
-// Order Processing
+// Position exits (stop-loss / take-profit)
 // ============================================================================
 
-fn process_order(
-    info: &OrderInfo,
-    trader_min_shares: f64,
-    client: &mut RustClobClient,
-    creds: &PreparedCreds,
-    enable_trading: bool,
-    mock_trading: bool,
-    guard: &mut RiskGuard,
-    resubmit_tx: &mpsc::UnboundedSender<ResubmitRequest>,
-    is_live: Option<bool>,
-    db_path: Option<&str>,
-    portfolio_tracker: Option<&Arc<PortfolioTracker>>,
-) -> String {
-    if !enable_trading { return "SKIPPED_DISABLED".into(); }
-    if mock_trading { return "MOCK_ONLY".into(); }
-
-    let side_is_buy = info.order_type.starts_with("BUY");
-    let whale_shares = info.shares;
-    let whale_price = info.price_per_share;
+/// Why a position is closed on price alone (or on request) rather than copying a whale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExitKind {
+    StopLoss,
+    TrailingStop,
+    TakeProfit,
+    /// POST /flatten: every position, long or short
+    Flatten,
+}
 
-    // For SELL orders, check if we have shares to sell
-    if !side_is_buy {
-        if let Some(path) = db_path {
-            match TradeStore::new(path) {
-                Ok(store) => {
-                    match store.get_positions() {
-                        Ok(positions) => {
-                            // Check if we have this token with positive shares
-                            let has_position = positions.iter()
-                                .any(|p| p.token_id == info.clob_token_id.as_ref() && p.net_shares > 0.0);
-                            if !has_position {
-                                return "SKIPPED_NO_POSITION".into();
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("Warning: Failed to check positions for SELL: {}", e);
-                            // Continue anyway - let the exchange reject if no position
-                        }
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Warning: Failed to open DB for position check: {}", e);
-                    // Continue anyway - let the exchange reject if no position
-                }
-            }
+/// Order type of a flatten BUY closing a net-short position (flatten SELLs use `ExitKind::order_type`)
+const FLATTEN_BUY_ORDER_TYPE: &str = "BUY_FLATTEN";
+
+impl ExitKind {
+    /// Order type given to process_order: a SELL that is not a copied whale fill
+    fn order_type(self) -> &'static str {
+        match self {
+            Self::StopLoss => "SELL_STOP_LOSS",
+            Self::TrailingStop => "SELL_TRAILING_STOP",
+            Self::TakeProfit => "SELL_TAKE_PROFIT",
+            Self::Flatten => "SELL_FLATTEN",
         }
-        // If no db_path, we can't check positions - let the exchange handle it
     }
 
-    // Skip small trades using per-trader threshold from traders.json
-    // Falls back to global MIN_WHALE_SHARES_TO_COPY if trader_min_shares is 0
-    let min_threshold = if trader_min_shares > 0.0 { trader_min_shares } else { MIN_WHALE_SHARES_TO_COPY };
-    if whale_shares < min_threshold {
-        return format!("SKIPPED_SMALL (<{:.0} shares)", min_threshold);
+    fn from_order_type(order_type: &str) -> Option<Self> {
+        if order_type == FLATTEN_BUY_ORDER_TYPE {
+            return Some(Self::Flatten);
+        }
+        [Self::StopLoss, Self::TrailingStop, Self::TakeProfit, Self::Flatten].into_iter().find(|kind| kind.order_type() == order_type)
     }
 
-    // Risk guard safety check
-    let eval = guard.check_fast(&info.clob_token_id, whale_shares);
-    match eval.decision {
-        SafetyDecision::Block => return format!("RISK_BLOCKED:{}", eval.reason.as_str()),
-        SafetyDecision::FetchBook => {
-            let side = if side_is_buy { TradeSide::Buy } else { TradeSide::Sell };
-            match fetch_book_depth_blocking(client, &info.clob_token_id, side, whale_price) {
-                Ok(depth) => {
-                    let final_eval = guard.check_with_book(&info.clob_token_id, eval.consecutive_large, depth);
-                    if final_eval.decision == SafetyDecision::Block {
-                        return format!("RISK_BLOCKED:{}", final_eval.reason.as_str());
-                    }
-                }
-                Err(e) => {
-                    guard.trip(&info.clob_token_id);
-                    return format!("RISK_BOOK_FAIL:{e}");
-                }
-            }
+    /// Status recorded for a placed exit
+    fn status(self) -> &'static str {
+        match self {
+            Self::StopLoss => "STOP_LOSS",
+            Self::TrailingStop => "TRAILING_STOP",
+            Self::TakeProfit => "TAKE_PROFIT",
+            Self::Flatten => FLATTEN_STATUS,
         }
-        SafetyDecision::Allow => {}
     }
+}
 
-    let (buffer, order_action, size_multiplier) = get_tier_params(whale_shares, side_is_buy, &info.clob_token_id);
+/// A SELL the exit task should place
+#[derive(Debug, Clone)]
+struct ExitOrder {
+    kind: ExitKind,
+    position: Position,
+    bid: f64,
+    shares: f64,
+}
 
-    // Polymarket valid price range: 0.01 to 0.99 (tick size 0.01)
-    let limit_price = if side_is_buy {
-        (whale_price + buffer).min(0.99)
-    } else {
-        (whale_price - buffer).max(0.01)
-    };
+/// Held positions to sell at their current bid: the whole position past STOP_LOSS_PCT or
+/// TRAILING_STOP_PCT off its peak (from `peaks`), or TAKE_PROFIT_FRACTION of it past
+/// TAKE_PROFIT_PCT unless it was already taken (`profit_taken`)
+fn position_exit_orders(
+    positions: Vec<Position>,
+    prices: &HashMap<String, PriceInfo>,
+    exits: PositionExits,
+    peaks: &TrailingPeaks,
+    profit_taken: &HashSet<String>,
+) -> Vec<ExitOrder> {
+    positions.into_iter()
+        .filter(|p| p.net_shares > 0.0)
+        .filter_map(|position| {
+            let entry = position.avg_entry_price?;
+            let bid = prices.get(&position.token_id)?.bid_price;
+            let trailing_hit = exits.trailing_stop.is_some_and(|trail| {
+                peaks.peak(&position.token_id).is_some_and(|peak| trail.is_triggered(peak, bid))
+            });
+            let (kind, shares) = if exits.stop_loss.is_some_and(|stop| stop.is_triggered(entry, bid)) {
+                (ExitKind::StopLoss, position.net_shares)
+            } else if trailing_hit {
+                (ExitKind::TrailingStop, position.net_shares)
+            } else {
+                let take = exits.take_profit
+                    .filter(|take| take.is_triggered(entry, bid) && !profit_taken.contains(&position.token_id))?;
+                (ExitKind::TakeProfit, take.sell_shares(position.net_shares))
+            };
+            (shares > 0.0).then_some(ExitOrder { kind, position, bid, shares })
+        })
+        .collect()
+}
 
-    // Calculate max bet in shares based on portfolio value (if configured)
-    let max_bet_shares = portfolio_tracker
-        .and_then(|tracker| tracker.get_max_bet_shares(limit_price));
+/// The exit as a SELL at the current bid, submitted like a whale fill
+fn exit_event(exit: &ExitOrder) -> ParsedEvent {
+    closing_event(exit.kind, exit.kind.order_type(), &exit.position.token_id, exit.shares, exit.bid)
+}
 
-    let (my_shares, size_type) = calculate_safe_size(whale_shares, limit_price, size_multiplier, max_bet_shares);
-    if my_shares == 0.0 {
-        return format!("SKIPPED_PROBABILITY ({})", size_type);
-    }
+/// The flatten order at `price` (bid for a SELL, ask for a BUY), submitted like a whale fill
+fn flatten_event(order: &FlattenOrder, price: f64) -> ParsedEvent {
+    let order_type = match order.side {
+        FlattenSide::Sell => ExitKind::Flatten.order_type(),
+        FlattenSide::Buy => FLATTEN_BUY_ORDER_TYPE,
+    };
+    closing_event(ExitKind::Flatten, order_type, &order.token_id, order.shares, price)
+}
+
+fn closing_event(kind: ExitKind, order_type: &str, token_id: &str, shares: f64, price: f64) -> ParsedEvent {
+    ParsedEvent {
+        block_number: 0,
+        tx_hash: String::new(),
+        trader_address: String::new(),
+        trader_label: kind.status().to_string(),
+        trader_min_shares: 0.0,
+        trader_max_bet_usd: None,
+        trader_scaling_ratio: 0.0,
+        trader_probabilistic_sizing: None,
+        trader_copy_price_range: CopyPriceRange::default(),
+        trader_observe: false,
+        order: OrderInfo {
+            order_type: order_type.to_string(),
+            clob_token_id: Arc::from(token_id),
+            usd_value: shares * price,
+            shares,
+            price_per_share: price,
+        },
+    }
+}
+
+/// Whether the order engine placed an exit or flatten order (filled, or resting for the GTD poller)
+fn closing_order_placed(status: &str) -> bool {
+    let category = parse_status_for_db(status).4;
+    category == "SUCCESS" || category == EXIT_RESTING_STATUS
+}
+
+/// Trade rows for an exit or flatten order that filled on submission, one per trader in `openers`
+/// (see `split_by_openers`); none unless it filled
+fn closing_trade_records(evt: &ParsedEvent, status: &str, is_live: Option<bool>, openers: &[(String, f64)]) -> Vec<TradeRecord> {
+    let Some(kind) = ExitKind::from_order_type(&evt.order.order_type) else {
+        return Vec::new();
+    };
+    let (our_shares, our_price, our_usd, fill_pct, category) = parse_status_for_db(status);
+    if category != "SUCCESS" {
+        return Vec::new();
+    }
+    let record = TradeRecord {
+        timestamp_ms: chrono::Utc::now().timestamp_millis(),
+        block_number: 0,
+        tx_hash: String::new(),
+        trader_address: String::new(),
+        token_id: evt.order.clob_token_id.to_string(),
+        side: if evt.order.order_type.starts_with("BUY") { "BUY".to_string() } else { "SELL".to_string() },
+        whale_shares: evt.order.shares,
+        whale_price: evt.order.price_per_share,
+        whale_usd: evt.order.usd_value,
+        our_shares,
+        our_price,
+        our_usd,
+        fill_pct,
+        status: kind.status().to_string(),
+        latency_ms: None,
+        is_live,
+        aggregation_count: None,
+        aggregation_window_ms: None,
+        order_id: parse_order_id(status),
+        contributing_traders: None,
+    };
+    split_by_openers(record, openers)
+}
+
+/// Attribute a closing fill to the traders whose lots it sells: one row each, sharing the fill
+/// by their open shares so `realized_pnl_by_trader` closes every copied trader's lots
+/// Without openers the row stays as it is
+fn split_by_openers(record: TradeRecord, openers: &[(String, f64)]) -> Vec<TradeRecord> {
+    let total: f64 = openers.iter().map(|(_, shares)| shares).sum();
+    if total <= 0.0 {
+        return vec![record];
+    }
+    openers.iter()
+        .map(|(trader, shares)| {
+            let part = shares / total;
+            TradeRecord {
+                trader_address: trader.clone(),
+                whale_shares: record.whale_shares * part,
+                whale_usd: record.whale_usd * part,
+                our_shares: record.our_shares.map(|s| s * part),
+                our_usd: record.our_usd.map(|usd| usd * part),
+                ..record.clone()
+            }
+        })
+        .collect()
+}
+
+/// Positions, their current prices, tokens already scaled out by take-profit, and each token's open shares by trader
+type ExitCheck = (Vec<Position>, HashMap<String, PriceInfo>, HashSet<String>, HashMap<String, Vec<(String, f64)>>);
+
+/// Every EXIT_CHECK_INTERVAL_SECS, price held positions and sell those past the stop-loss,
+/// trailing stop or take-profit
+/// Filled exits are recorded with their kind as status, per trader whose lots they sell (resting ones
+/// by the GTD poller); a token isn't exited again until its GTD has had time to fill, and
+/// take-profit fires once per position (until we buy more)
+fn spawn_position_exit_task(
+    exits: PositionExits,
+    db_path: String,
+    clob_base: String,
+    trailing_state: PathBuf,
+    order_engine: OrderEngine,
+    trade_tx: Option<mpsc::UnboundedSender<TradeRecord>>,
+    gtd_expiry: GtdExpiry,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(exits.check_interval_secs));
+        // Tokens with an exit placed, and when to try again if the position is still held
+        let mut retry_at: HashMap<String, tokio::time::Instant> = HashMap::new();
+        let mut peaks = if exits.trailing_stop.is_some() {
+            TrailingPeaks::load(&trailing_state).unwrap_or_else(|e| {
+                eprintln!("Warning: Ignoring trailing stop state: {:#}", e);
+                TrailingPeaks::default()
+            })
+        } else {
+            TrailingPeaks::default()
+        };
+        loop {
+            interval.tick().await;
+
+            let (db, clob) = (db_path.clone(), clob_base.clone());
+            let checked = tokio::task::spawn_blocking(move || -> Result<ExitCheck> {
+                let store = TradeStore::new(&db)?;
+                let positions = store.get_positions()?;
+                let profit_taken = store.tokens_with_status_since_last_buy(ExitKind::TakeProfit.status())?;
+                let openers = store.get_open_shares_by_trader()?;
+                let held: Vec<&str> = positions.iter()
+                    .filter(|p| p.net_shares > 0.0)
+                    .map(|p| p.token_id.as_str())
+                    .collect();
+                // TTL 0: every check prices the book fresh
+                let prices = PriceCache::with_host(0, &clob).get_or_fetch_many(&held);
+                Ok((positions, prices, profit_taken, openers))
+            }).await;
+            let (positions, prices, profit_taken, openers) = match checked {
+                Ok(Ok(checked)) => checked,
+                Ok(Err(e)) => {
+                    eprintln!("Warning: Position exit check failed: {}", e);
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!("Warning: Position exit task error: {}", e);
+                    continue;
+                }
+            };
+
+            if exits.trailing_stop.is_some() {
+                let before = peaks.clone();
+                // Closed positions start over from their next entry
+                peaks.retain_held(|token| positions.iter().any(|p| p.token_id == token && p.net_shares > 0.0));
+                for p in positions.iter().filter(|p| p.net_shares > 0.0) {
+                    if let (Some(entry), Some(price)) = (p.avg_entry_price, prices.get(&p.token_id)) {
+                        peaks.observe(&p.token_id, entry, price.bid_price);
+                    }
+                }
+                if peaks != before
+                    && let Err(e) = peaks.save(&trailing_state)
+                {
+                    eprintln!("Warning: Failed to save trailing stop state: {:#}", e);
+                }
+            }
+
+            let now = tokio::time::Instant::now();
+            retry_at.retain(|_, at| *at > now);
+            for exit in position_exit_orders(positions, &prices, exits, &peaks, &profit_taken) {
+                if retry_at.contains_key(&exit.position.token_id) {
+                    continue;
+                }
+                let entry = exit.position.avg_entry_price.unwrap_or_default();
+                println!(
+                    "🛑 {}: {} {:+.1}% ({:.2} -> {:.2}), selling {:.2}/{:.2} shares",
+                    exit.kind.status(), exit.position.token_id, (exit.bid - entry) / entry * 100.0,
+                    entry, exit.bid, exit.shares, exit.position.net_shares
+                );
+
+                let evt = exit_event(&exit);
+                let is_live = market_cache::get_is_live(&exit.position.token_id);
+                let status = order_engine.submit(evt.clone(), is_live).await;
+                println!("🛑 {} result: {}", exit.kind.status(), status);
+
+                if !closing_order_placed(&status) {
+                    continue;
+                }
+                retry_at.insert(exit.position.token_id.clone(), now + Duration::from_secs(gtd_expiry.secs(is_live.unwrap_or(false))) + GTD_POLL_GRACE);
+                if let Some(tx) = &trade_tx {
+                    let token_openers = openers.get(&exit.position.token_id).map_or(&[][..], Vec::as_slice);
+                    for record in closing_trade_records(&evt, &status, is_live, token_openers) {
+                        let _ = tx.send(record);
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Answer POST /flatten: close every position in the trade DB through the order engine
+async fn flatten_worker(
+    mut rx: mpsc::UnboundedReceiver<FlattenRequest>,
+    db_path: Option<String>,
+    clob_base: String,
+    order_engine: OrderEngine,
+    trade_tx: Option<mpsc::UnboundedSender<TradeRecord>>,
+) {
+    while let Some(reply) = rx.recv().await {
+        let result = match &db_path {
+            Some(path) => flatten_positions(path, &clob_base, &order_engine, &trade_tx).await.map_err(|e| format!("{:#}", e)),
+            None => Err("positions are unknown without trade persistence (DB_ENABLED)".to_string()),
+        };
+        let _ = reply.send(result);
+    }
+}
+
+/// Flatten orders, their current prices, and each token's open shares by trader
+type FlattenCheck = (Vec<FlattenOrder>, HashMap<String, PriceInfo>, HashMap<String, Vec<(String, f64)>>);
+
+/// Submit a full-exit GTD for each position at its current bid (SELL) or ask (BUY), recording filled ones as
+/// FLATTEN per trader whose lots they sell (resting ones via the GTD poller)
+async fn flatten_positions(
+    db_path: &str,
+    clob_base: &str,
+    order_engine: &OrderEngine,
+    trade_tx: &Option<mpsc::UnboundedSender<TradeRecord>>,
+) -> Result<Vec<FlattenResult>> {
+    let (db, clob) = (db_path.to_string(), clob_base.to_string());
+    let (orders, prices, openers) = tokio::task::spawn_blocking(move || -> Result<FlattenCheck> {
+        let store = TradeStore::new(&db)?;
+        let orders = flatten_orders(&store.get_positions()?);
+        let tokens: Vec<&str> = orders.iter().map(|o| o.token_id.as_str()).collect();
+        // TTL 0: price every book fresh
+        let prices = PriceCache::with_host(0, &clob).get_or_fetch_many(&tokens);
+        Ok((orders, prices, store.get_open_shares_by_trader()?))
+    }).await??;
+    println!("🧯 FLATTEN: closing {} position(s)", orders.len());
+
+    let mut results = Vec::with_capacity(orders.len());
+    for order in orders {
+        let price = prices.get(&order.token_id)
+            .map(|p| if order.side == FlattenSide::Sell { p.bid_price } else { p.ask_price })
+            .filter(|price| *price > 0.0);
+        let status = match price {
+            Some(price) => {
+                let evt = flatten_event(&order, price);
+                let is_live = market_cache::get_is_live(&order.token_id);
+                let status = order_engine.submit(evt.clone(), is_live).await;
+                if let Some(tx) = trade_tx {
+                    let token_openers = openers.get(&order.token_id).map_or(&[][..], Vec::as_slice);
+                    for record in closing_trade_records(&evt, &status, is_live, token_openers) {
+                        let _ = tx.send(record);
+                    }
+                }
+                status
+            }
+            None => "SKIPPED_NO_PRICE".to_string(),
+        };
+        println!("🧯 FLATTEN {} {:.2} {}: {}", order.side.as_str(), order.shares, order.token_id, status);
+        results.push(FlattenResult {
+            token_id: order.token_id,
+            side: order.side,
+            shares: order.shares,
+            price,
+            status: strip_ansi_codes(&status),
+        });
+    }
+    Ok(results)
+}
+
+/// Copy-path state and handles owned by the order worker, which handles one order at a time
+#[derive(Default)]
+struct OrderWorkerState {
+    /// Refuses orders while the exchange keeps rejecting them
+    breaker: ExchangeBreaker,
+    /// Unfilled FAK remainders, for the resubmit worker (None = not resubmitted)
+    resubmit_tx: Option<mpsc::UnboundedSender<ResubmitRequest>>,
+    /// Resting GTDs, for the fill pollers (None = not watched)
+    gtd_watch_tx: Option<mpsc::UnboundedSender<GtdWatch>>,
+    /// Bankroll for portfolio-percent caps and Kelly sizing (None = off)
+    portfolio_tracker: Option<Arc<PortfolioTracker>>,
+    /// Wallet balance checked before BUYs (None = off)
+    balance_tracker: Option<Arc<BalanceTracker>>,
+    last_submit: SubmitTimes,
+    whale_holdings: WhaleHoldings,
+    copy_streaks: CopyStreaks,
+    /// Tokens we currently hold, for MAX_OPEN_POSITIONS and MAX_EXPOSURE_PER_MARKET_USD (refreshed every OPEN_POSITIONS_TTL)
+    open_positions: OpenPositionsCache,
+    /// Shared with the resubmitter, the GTD fill pollers and the shutdown cancel
+    placed_gtd_orders: PlacedGtdOrders,
+    /// Refreshed by the heartbeat
+    trader_pnl: TraderPnl,
+}
+
+fn order_worker(
+    mut rx: mpsc::Receiver<WorkItem>,
+    client: Arc<RustClobClient>,
+    creds: Arc<SharedCreds>,
+    guard: &std::sync::Mutex<RiskGuard>,
+    state: &mut OrderWorkerState,
+    order_settings: OrderSettings,
+    shadow_tx: Option<mpsc::UnboundedSender<TradeRecord>>,
+) {
+    let mut client_mut = (*client).clone();
+    while let Some(work) = rx.blocking_recv() {
+        let mut guard = guard.lock().unwrap_or_else(|e| e.into_inner());
+        let status = process_order(&work.event, &mut client_mut, &creds, &mut guard, state, work.is_live, &order_settings);
+        let _ = work.respond_to.send(status);
+        drop(guard);
+
+        // After the reply, so the shadow strategy never delays the live order
+        if let (Some(shadow), Some(tx)) = (&order_settings.shadow, &shadow_tx)
+            && let Some((shares, price)) = shadow_order(&work.event, shadow, &order_settings, order_settings.db_path.as_deref(), state.portfolio_tracker.as_ref())
+        {
+            let _ = tx.send(shadow_record(&work.event, shares, price, work.is_live));
+        }
+    }
+}
+
+// ============================================================================
+// Order Processing
+// ============================================================================
+
+fn process_order(
+    event: &ParsedEvent,
+    client: &mut RustClobClient,
+    creds: &SharedCreds,
+    guard: &mut RiskGuard,
+    state: &mut OrderWorkerState,
+    is_live: Option<bool>,
+    order_settings: &OrderSettings,
+) -> String {
+    let info = &event.order;
+    let trader_address = event.trader_address.as_str();
+    let db_path = order_settings.db_path.as_deref();
+    let portfolio_tracker = state.portfolio_tracker.as_ref();
+    let balance_tracker = state.balance_tracker.as_ref();
+
+    // NaN/Inf or impossible prices would slip through every comparison below (NaN.min(0.99) is 0.99)
+    if let Some(field) = info.invalid_field() {
+        return format!(
+            "SKIPPED_INVALID_ORDER ({}: {} shares @ {} = ${})",
+            field, info.shares, info.price_per_share, info.usd_value
+        );
+    }
+
+    // Record every whale fill (even ones we skip) so later BUYs know whether the whale already held the token
+    // Stop-loss / take-profit exits sell what we hold regardless of the copy filters below
+    let position_exit = ExitKind::from_order_type(&info.order_type).is_some();
+    let whale_held_before = (order_settings.entry_scaling.is_enabled() && !position_exit).then(|| {
+        observe_whale_fill(&mut state.whale_holdings, trader_address, &info.clob_token_id, info.order_type.starts_with("BUY"), info.shares)
+    });
+
+    if !order_settings.enable_trading { return "SKIPPED_DISABLED".into(); }
+    if order_settings.mock_trading { return "MOCK_ONLY".into(); }
+
+    // Exchange rejecting everything (auth expiry, outage): don't submit until the cooldown passes
+    // Exits still try, so closing a position never waits on the cooldown
+    if !position_exit
+        && let Some(remaining) = state.breaker.open_remaining(std::time::Instant::now())
+    {
+        return format!("CIRCUIT_OPEN ({}s left)", remaining.as_secs().max(1));
+    }
+
+    if !position_exit && !is_within_trading_hours(Utc::now(), &order_settings.trading_hours) {
+        return "SKIPPED_OFF_HOURS".into();
+    }
+    if !position_exit
+        && let Some(status) = token_list_status(&info.clob_token_id, &order_settings.token_blocklist, &order_settings.token_allowlist)
+    {
+        return status.into();
+    }
+    // Resolved, halted or about-to-end markets: BUYs are wasted or stuck; SELLs and flatten BUYs still exit
+    if info.order_type.starts_with("BUY")
+        && !position_exit
+        && let Some(reason) = market_cache::get_market_status(&info.clob_token_id)
+            .and_then(|status| status.closing_reason(Utc::now().timestamp_millis(), order_settings.market_close_window))
+    {
+        return format!("SKIPPED_MARKET_CLOSING ({})", reason);
+    }
+    // Extreme odds: poor risk/reward and thin books
+    let price_range = order_settings.copy_price_range.with_trader(event.trader_copy_price_range);
+    if !position_exit && !price_range.contains(info.price_per_share) {
+        return format!("SKIPPED_PRICE_RANGE ({:.2} outside {})", info.price_per_share, price_range);
+    }
+    let entry_multiplier = match whale_held_before {
+        Some(held) if info.order_type.starts_with("BUY") => {
+            let multiplier = order_settings.entry_scaling.multiplier(held);
+            if multiplier == 0.0 {
+                return if held > 0.0 { "SKIPPED_WHALE_ADD".into() } else { "SKIPPED_WHALE_OPEN".into() };
+            }
+            multiplier
+        }
+        _ => 1.0,
+    };
+    if !position_exit
+        && let Some(status) = neg_risk_status(order_settings.skip_neg_risk, || client.is_neg_risk(&info.clob_token_id))
+    {
+        return status.into();
+    }
+
+    let side_is_buy = info.order_type.starts_with("BUY");
+    let whale_shares = info.shares;
+    let whale_price = info.price_per_share;
+
+    // For SELL orders, check if we have shares to sell
+    // Also look up the whale's prior position so the sell can be sized proportionally
+    let mut our_net_shares: Option<f64> = None;
+    let mut whale_prior_shares: Option<f64> = None;
+    // Traders whose lots an exit sells, for the GTD poller to attribute its fills
+    let mut exit_openers: Vec<(String, f64)> = Vec::new();
+    // If no db_path, we can't check positions - let the exchange handle it
+    if !side_is_buy
+        && let Some(path) = db_path
+    {
+        match TradeStore::new(path) {
+            Ok(store) => {
+                match store.get_positions() {
+                    Ok(positions) => {
+                        // Check if we have this token with positive shares
+                        let held = positions.iter()
+                            .find(|p| p.token_id == info.clob_token_id.as_ref() && p.net_shares > 0.0);
+                        let Some(held) = held else {
+                            return "SKIPPED_NO_POSITION".into();
+                        };
+                        our_net_shares = Some(held.net_shares);
+                        // An exit "whale" holds what we hold, so it sells exactly the exit's shares
+                        whale_prior_shares = if position_exit { Some(held.net_shares) } else { store
+                            .get_whale_net_shares(trader_address, &info.clob_token_id)
+                            .unwrap_or_else(|e| {
+                                eprintln!("Warning: Failed to load whale position for SELL: {}", e);
+                                None
+                            }) };
+                        if position_exit {
+                            exit_openers = store.get_open_shares_by_trader()
+                                .map(|mut open| open.remove(info.clob_token_id.as_ref()).unwrap_or_default())
+                                .unwrap_or_else(|e| {
+                                    eprintln!("Warning: Failed to load open lots for exit: {}", e);
+                                    Vec::new()
+                                });
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("Warning: Failed to check positions for SELL: {}", e);
+                        // Continue anyway - let the exchange reject if no position
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("Warning: Failed to open DB for position check: {}", e);
+                // Continue anyway - let the exchange reject if no position
+            }
+        }
+    }
+
+    // Don't open a new market once MAX_OPEN_POSITIONS are held (adding to a held token is fine)
+    if side_is_buy
+        && !position_exit
+        && let (Some(max), Some(path)) = (order_settings.max_open_positions, db_path)
+    {
+        let open = open_positions_check(&mut state.open_positions, &info.clob_token_id, max, || load_open_positions(path));
+        if let Err(count) = open {
+            return format!("SKIPPED_MAX_POSITIONS ({}/{} open)", count, max);
+        }
+    }
+
+    // Skip small trades using per-trader threshold from traders.json
+    // Falls back to global MIN_WHALE_SHARES_TO_COPY if the trader's min_shares is 0
+    let min_threshold = if event.trader_min_shares > 0.0 { event.trader_min_shares } else { MIN_WHALE_SHARES_TO_COPY };
+    if !position_exit && whale_shares < min_threshold {
+        return format!("SKIPPED_SMALL (<{:.0} shares)", min_threshold);
+    }
+
+    // Risk guard safety check (it judges whale fills; an exit sells what we hold)
+    if !position_exit {
+        let eval = guard.check_fast(&info.clob_token_id, whale_shares);
+        match eval.decision {
+            SafetyDecision::Block => return format!("RISK_BLOCKED:{}", eval.reason.as_str()),
+            SafetyDecision::FetchBook => {
+                let side = if side_is_buy { TradeSide::Buy } else { TradeSide::Sell };
+                let max_levels = guard.config().book_depth_levels;
+                match fetch_book_depth_blocking(client, &info.clob_token_id, side, whale_price, max_levels) {
+                    Ok(depth) => {
+                        let final_eval = guard.check_with_book(&info.clob_token_id, eval.consecutive_large, depth);
+                        if final_eval.decision == SafetyDecision::Block {
+                            return format!("RISK_BLOCKED:{}", final_eval.reason.as_str());
+                        }
+                    }
+                    Err(e) => {
+                        guard.trip(&info.clob_token_id);
+                        return format!("RISK_BOOK_FAIL:{e}");
+                    }
+                }
+            }
+            SafetyDecision::Allow => {}
+        }
+    }
+
+    // POST_ONLY rests at the whale's price instead of crossing; exits still have to get out
+    let post_only = order_settings.post_only && !position_exit;
+    let tier_params =
+        get_tier_params_with_fok(whale_shares, side_is_buy, &info.clob_token_id, order_settings.fok_min_shares, &order_settings.execution_tiers);
+    let (tier_buffer, order_action, size_multiplier) = if post_only {
+        post_only_params(tier_params)
+    } else if position_exit {
+        // Exits rest as GTDs with the live / non-live expiry (a flatten BUY too)
+        (tier_params.0, "GTD", tier_params.2)
+    } else {
+        tier_params
+    };
+
+    // Dynamic mode: size the buffer from the live spread, never above the tier buffer
+    // Falls back to the tier buffer if the book can't be fetched
+    let buffer = match order_settings.spread_buffer_fraction {
+        Some(fraction) if tier_buffer > 0.0 => fetch_spread_blocking(client, &info.clob_token_id)
+            .map(|spread| get_spread_buffer(spread, fraction, tier_buffer))
+            .unwrap_or(tier_buffer),
+        _ => tier_buffer,
+    };
+
+    // Polymarket valid price range: 0.01 to 0.99 (tick size 0.01)
+    let limit_price = if side_is_buy {
+        (whale_price + buffer).min(0.99)
+    } else {
+        (whale_price - buffer).max(0.01)
+    };
+
+    // Don't chase a book that has already moved away from the whale's price
+    if let Some(max) = order_settings.max_slippage
+        && max.is_exceeded(whale_price, limit_price, side_is_buy)
+    {
+        return format!("SKIPPED_SLIPPAGE ({:.2} vs whale {:.2}, max {})", limit_price, whale_price, max);
+    }
+
+    // Max bet in shares from the portfolio percent and/or MAX_BET_SHARES (if configured)
+    let max_bet_shares = portfolio_tracker
+        .and_then(|tracker| tracker.get_max_bet_shares(limit_price));
+
+    // Per-trader cap from traders.json, converted to shares at our limit price
+    let trader_max_shares = event.trader_max_bet_usd
+        .filter(|usd| *usd > 0.0)
+        .map(|usd| usd / limit_price.max(0.01));
+
+    // Exits are sized from the position below, never from the bankroll
+    let size_target = if position_exit {
+        None
+    } else {
+        match size_target(order_settings.kelly_sizing, order_settings.flat_usd, side_is_buy, limit_price, portfolio_tracker) {
+            Ok(target) => target,
+            Err(e) => return format!("SKIPPED_NO_BANKROLL (KELLY) | {}", e),
+        }
+    };
+
+    // Per-trader ratio from traders.json; 0 (aggregated/legacy events) falls back to the global ratio
+    // SCALE_ON_OPEN / SCALE_ON_ADD, CONFIDENCE_SCALING and COPY_DECAY adjust it for BUYs
+    let confidence = order_settings.confidence_scaling
+        .filter(|_| side_is_buy)
+        .map_or(1.0, |scaling| confidence_factor(&state.trader_pnl, trader_address, scaling));
+    let decay = order_settings.copy_decay
+        .filter(|_| side_is_buy && !position_exit)
+        .map_or(1.0, |decay| decay.multiplier(copy_streak(&state.copy_streaks, &info.clob_token_id)));
+    let scaling_ratio = entry_multiplier * confidence * decay * if event.trader_scaling_ratio > 0.0 { event.trader_scaling_ratio } else { order_settings.scaling_ratio };
+    let size_floor = order_settings.size_floor.with_probabilistic(event.trader_probabilistic_sizing);
+    let mut sized = calculate_safe_size(SizingInput {
+        whale_shares,
+        scaling_ratio,
+        price: limit_price,
+        size_multiplier,
+        max_bet_shares,
+        trader_max_shares,
+        target: size_target,
+        floor: size_floor,
+    });
+    if let Some(held) = our_net_shares {
+        sized = calculate_sell_size(whale_shares, whale_prior_shares, held, sized);
+    } else if position_exit && side_is_buy {
+        // A flatten BUY covers exactly the short, whatever the copy sizing
+        sized = (whale_shares, SizeType::Scaled);
+    }
+    let (my_shares, mut size_type) = sized;
+    if my_shares == 0.0 {
+        if matches!(size_type, SizeType::Kelly) {
+            return "SKIPPED_NO_EDGE (KELLY)".into();
+        }
+        if matches!(size_type, SizeType::BelowFloor) {
+            return format!("SKIPPED_BELOW_FLOOR ({})", size_floor.strategy.as_str());
+        }
+        if matches!(size_type, SizeType::CappedByTrader) {
+            return "SKIPPED_BELOW_FLOOR (trader cap)".into();
+        }
+        return format!("SKIPPED_PROBABILITY ({})", size_type);
+    }
+    let mut order_size = (my_shares * 100.0).floor() / 100.0;
+    if !(order_size.is_finite() && order_size > 0.0 && limit_price.is_finite() && limit_price > 0.0) {
+        return format!("SKIPPED_INVALID_SIZE ({} @ {})", order_size, limit_price);
+    }
+
+    // Keep this token's exposure under MAX_EXPOSURE_PER_MARKET_USD, trimming the BUY if the rest still fits
+    // Held shares are valued at our limit price (the current market, not the entry)
+    if side_is_buy
+        && !position_exit
+        && let (Some(cap), Some(path)) = (order_settings.max_exposure_per_market_usd, db_path)
+    {
+        let held_shares = with_open_positions(&mut state.open_positions, || load_open_positions(path), |held| {
+            held.get(info.clob_token_id.as_ref()).copied().unwrap_or(0.0)
+        }).unwrap_or(0.0);
+        let held_usd = held_shares * limit_price;
+        match fit_market_exposure(held_usd, order_size, limit_price, cap, order_settings.size_floor) {
+            Some(fitted) if fitted < order_size => {
+                order_size = fitted;
+                size_type = SizeType::CappedByMarket;
+            }
+            Some(_) => {}
+            None => return format!("SKIPPED_MARKET_EXPOSURE (${:.2} held, cap ${:.2})", held_usd, cap),
+        }
+    }
+
+    // Don't send BUYs the wallet can't pay for (exchange would reject them anyway)
+    if side_is_buy
+        && let Some(tracker) = balance_tracker
+        && let Some(available) = tracker.insufficient(order_size * limit_price)
+    {
+        return format!(
+            "SKIPPED_INSUFFICIENT_FUNDS (${:.2} needed, ${:.2} available)",
+            order_size * limit_price, available
+        );
+    }
+
+    // Bursts on one token: don't compete with the order we just sent; the caller defers this one
+    if let Err(wait) = submit_interval_check(&mut state.last_submit, &info.clob_token_id, order_settings.min_submit_interval, std::time::Instant::now()) {
+        // Rounded up so waiting it out always clears the interval
+        return format!("{} ({}ms)", DEFERRED_RATE_LIMIT, wait.as_micros().div_ceil(1000).max(1));
+    }
 
     // Calculate expiration for GTD orders (SELL orders always use GTD)
     // FAK orders don't need expiration (use None)
     let expiration = if order_action == "GTD" {
         use std::time::{SystemTime, UNIX_EPOCH};
-        let expiry_secs = get_gtd_expiry_secs(is_live.unwrap_or(false));
+        let expiry_secs = order_settings.gtd_expiry.secs(is_live.unwrap_or(false));
         let expiry_timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
@@ -574,18 +1810,32 @@ fn process_order(
     let args = OrderArgs {
         token_id: info.clob_token_id.to_string(),
         price: limit_price,
-        size: (my_shares * 100.0).floor() / 100.0,
+        size: order_size,
         side: if side_is_buy { "BUY".into() } else { "SELL".into() },
-        fee_rate_bps: None,
+        fee_rate_bps: order_settings.fee_rate_bps,
         nonce: Some(0),
         expiration,
         taker: None,
         order_type: Some(order_action.to_string()),
     };
 
+    // DRY_RUN: everything above ran against live data; report the order instead of sending it
+    if order_settings.dry_run {
+        return format!(
+            "WOULD_SUBMIT [{}] | {} {}{} {:.2} @ {:.2}{}{} | whale {:.1} @ {:.2}",
+            size_type, args.side, order_action, if post_only { " post-only" } else { "" }, args.size, args.price,
+            args.expiration.as_deref().map(|exp| format!(" (expires {})", exp)).unwrap_or_default(),
+            args.fee_rate_bps.map(|bps| format!(" fee {}bps", bps)).unwrap_or_default(),
+            whale_shares, whale_price
+        );
+    }
+
     match client.create_order(args).and_then(|signed| {
-        let body = signed.post_body(&creds.api_key, order_action);
-        client.post_order_fast(body, creds)
+        // Expired/revoked API key: re-derive once and resend the same signed order
+        with_auth_retry(creds, response_status, || client.derive_api_key(0), |creds| {
+            let body = signed.post_body_with(&creds.api_key, order_action, post_only);
+            client.post_order_fast(body, creds)
+        })
     }) {
         Ok(resp) => {
             let status = resp.status();
@@ -607,61 +1857,69 @@ fn process_order(
             } else {
                 None
             };
-
-            let mut underfill_msg: Option<String> = None;
             if let Some(ref resp) = order_resp {
-                if side_is_buy && order_action == "FAK" {
-                    let filled_shares: f64 = resp.taking_amount.parse().unwrap_or(0.0);
-                    let requested_shares = (my_shares * 100.0).floor() / 100.0;
-
-                    if filled_shares < requested_shares && filled_shares > 0.0 {
-                        let remaining_shares = requested_shares - filled_shares;
-
-                        let min_threshold = MIN_SHARE_COUNT.max(MIN_CASH_VALUE / limit_price);
-                        if remaining_shares >= min_threshold {
-                            let resubmit_buffer = get_resubmit_max_buffer(whale_shares);
-                            let max_price = (limit_price + resubmit_buffer).min(0.99);
-                            let req = ResubmitRequest {
-                                token_id: info.clob_token_id.to_string(),
-                                whale_price,
-                                failed_price: limit_price,  // Start at same price (already filled some)
-                                size: (remaining_shares * 100.0).floor() / 100.0,
-                                whale_shares,
-                                side_is_buy: true,
-                                attempt: 1,
-                                max_price,
-                                cumulative_filled: filled_shares,
-                                original_size: requested_shares,
-                                is_live: is_live.unwrap_or(false),
-                            };
-                            let _ = resubmit_tx.send(req);
-                            underfill_msg = Some(format!(
-                                " | \x1b[33mUNDERFILL: {:.2}/{:.2} filled, resubmit {:.2}\x1b[0m",
-                                filled_shares, my_shares, remaining_shares
-                            ));
-                        }
-                    }
+                track_gtd_order(&state.placed_gtd_orders, order_action, resp);
+            }
+            match exchange_outcome(status.as_u16(), &body_text) {
+                Some(true) => log_breaker_transition(state.breaker.record_success()),
+                Some(false) => log_breaker_transition(state.breaker.record_failure(std::time::Instant::now())),
+                None => {}
+            }
+            // Only orders the exchange accepted count towards COPY_DECAY
+            if status.is_success() && order_settings.copy_decay.is_some() {
+                note_copy(&mut state.copy_streaks, &info.clob_token_id, side_is_buy);
+            }
+
+            // A post-only order never takes liquidity, and an exit GTD that didn't match is resting:
+            // nothing has filled yet, the GTD poller records fills
+            let exit_resting = position_exit && order_action == "GTD" && order_resp.as_ref().is_some_and(|r| r.status != "matched");
+            if (post_only || exit_resting) && status.is_success() {
+                if let Some(tx) = &state.gtd_watch_tx {
+                    let req = ResubmitRequest {
+                        token_id: info.clob_token_id.to_string(),
+                        trader_address: trader_address.to_string(),
+                        whale_price,
+                        failed_price: limit_price,
+                        size: order_size,
+                        whale_shares,
+                        max_price: limit_price,
+                        cumulative_filled: 0.0,
+                        original_size: order_size,
+                        side_is_buy,
+                        is_live: is_live.unwrap_or(false),
+                        attempt: 1,
+                    };
+                    let label = GtdFillLabel {
+                        exit_status: ExitKind::from_order_type(&info.order_type).map(ExitKind::status),
+                        openers: exit_openers,
+                    };
+                    let _ = tx.send((req, body_text.clone(), label));
                 }
+                let order = order_resp.as_ref().and_then(|r| order_id_of(r)).map(|id| format!(" | order {}", id)).unwrap_or_default();
+                return format!(
+                    "{} [{}] | resting {:.2} @ {:.2} | whale {:.1} @ {:.2}{}",
+                    if exit_resting { EXIT_RESTING_STATUS } else { POST_ONLY_STATUS },
+                    size_type, order_size, limit_price, whale_shares, whale_price, order
+                );
             }
 
-            if status.as_u16() == 400 && body_text.contains("FAK") && side_is_buy {
-                let resubmit_buffer = get_resubmit_max_buffer(whale_shares);
-                let max_price = (limit_price + resubmit_buffer).min(0.99);
-                let rounded_size = (my_shares * 100.0).floor() / 100.0;
-                let req = ResubmitRequest {
-                    token_id: info.clob_token_id.to_string(),
-                    whale_price,
-                    failed_price: limit_price,
-                    size: rounded_size,
-                    whale_shares,
-                    side_is_buy: true,
-                    attempt: 1,
-                    max_price,
-                    cumulative_filled: 0.0,
-                    original_size: rounded_size,
-                    is_live: is_live.unwrap_or(false),
-                };
-                let _ = resubmit_tx.send(req);
+            let mut underfill_msg: Option<String> = None;
+            let submitted = SubmittedOrder { action: order_action, side_is_buy, limit_price, shares: my_shares, is_live: is_live.unwrap_or(false) };
+            if let Some(req) = fak_resubmit_request(
+                &submitted, status.as_u16(), &body_text, order_resp.as_ref(), info, trader_address, order_settings.size_floor,
+            ) {
+                if req.cumulative_filled > 0.0 {
+                    underfill_msg = Some(format!(
+                        " | \x1b[33mUNDERFILL: {:.2}/{:.2} filled, resubmit {:.2}\x1b[0m",
+                        req.cumulative_filled, my_shares, req.size
+                    ));
+                }
+                if let Some(tx) = &state.resubmit_tx {
+                    let _ = tx.send(req);
+                }
+            }
+            if order_action == "FOK" && !status.is_success() {
+                underfill_msg = Some(" | FOK killed (not fully fillable at limit)".to_string());
             }
 
             // Extract filled shares and actual fill price for display (reuse parsed response)
@@ -674,6 +1932,14 @@ fn process_order(
                 .unwrap_or_else(|| {
                     if status.is_success() { (my_shares, limit_price) } else { (0.0, limit_price) }
                 });
+            if side_is_buy
+                && let Some(tracker) = balance_tracker
+            {
+                tracker.record_spend(filled_shares * actual_fill_price);
+            }
+            if side_is_buy && filled_shares > 0.0 {
+                note_open_position(&mut state.open_positions, &info.clob_token_id, filled_shares);
+            }
 
             // Format with color-coded fill percentage
             let pink = "\x1b[38;5;199m";
@@ -685,6 +1951,9 @@ fn process_order(
                 "{} [{}] | {}{:.2}/{:.2}{} filled @ {}{:.2}{} | {}whale {:.1}{} @ {:.2}",
                 status_str, size_type, fill_color, filled_shares, my_shares, reset, pink, actual_fill_price, reset, whale_color, whale_shares, reset, whale_price
             );
+            if let Some(id) = order_resp.as_ref().and_then(|r| order_id_of(r)) {
+                base.push_str(&format!(" | order {}", id));
+            }
             if let Some(msg) = underfill_msg {
                 base.push_str(&msg);
             }
@@ -694,63 +1963,289 @@ fn process_order(
             base
         }
         Err(e) => {
+            log_breaker_transition(state.breaker.record_failure(std::time::Instant::now()));
             let chain: Vec<_> = e.chain().map(|c| c.to_string()).collect();
             format!("EXEC_FAIL: {} | chain: {}", e, chain.join(" -> "))
         }
     }
 }
 
-fn calculate_safe_size(whale_shares: f64, price: f64, size_multiplier: f64, max_bet_shares: Option<f64>) -> (f64, SizeType) {
-    let target_scaled = whale_shares * SCALING_RATIO * size_multiplier;
-    let safe_price = price.max(0.0001);
-    let required_floor = (MIN_CASH_VALUE / safe_price).max(MIN_SHARE_COUNT);
-
-    // Apply portfolio-based cap if configured
-    let target_capped = match max_bet_shares {
-        Some(max) if max > 0.0 && target_scaled > max => max,
-        _ => target_scaled,
-    };
+/// How a submission response counts for the exchange breaker
+/// Some(true) = accepted, Some(false) = exchange failure, None = FAK/FOK that found no liquidity
+/// or a post-only order that would have crossed (normal market outcomes, so they neither trip
+/// nor reset the breaker)
+fn exchange_outcome(http_status: u16, body_text: &str) -> Option<bool> {
+    if (200..300).contains(&http_status) {
+        Some(true)
+    } else if http_status == 400 && (body_text.contains("FAK") || body_text.contains("FOK") || body_text.contains("post-only")) {
+        None
+    } else {
+        Some(false)
+    }
+}
 
-    if target_capped >= required_floor {
-        // If we capped the size, indicate it in the size type
-        if max_bet_shares.is_some() && target_scaled > target_capped {
-            return (target_capped, SizeType::Capped);
-        }
-        return (target_capped, SizeType::Scaled);
+fn log_breaker_transition(transition: Option<BreakerTransition>) {
+    match transition {
+        Some(BreakerTransition::Opened { failures }) => eprintln!(
+            "🛑 Exchange circuit breaker OPEN after {} consecutive failures - pausing orders", failures
+        ),
+        Some(BreakerTransition::Closed) => println!("✅ Exchange circuit breaker closed - order accepted"),
+        None => {}
     }
+}
+
+/// A copy order as it was submitted
+#[derive(Debug, Clone, Copy)]
+struct SubmittedOrder<'a> {
+    /// FAK, FOK or GTD
+    action: &'a str,
+    side_is_buy: bool,
+    limit_price: f64,
+    /// Shares asked for, before flooring to 0.01
+    shares: f64,
+    is_live: bool,
+}
 
-    if !USE_PROBABILISTIC_SIZING {
-        return (required_floor, SizeType::Scaled);
+/// Resubmit request for the unfilled part of a FAK BUY, if there is one
+///
+/// A partial fill (200 with taking_amount below the requested size) resubmits the
+/// remainder; a 400 FAK "no match" resubmits the full size. FOK orders are
+/// all-or-nothing, so a kill has no partial fill to chase and they never resubmit.
+fn fak_resubmit_request(
+    order: &SubmittedOrder,
+    http_status: u16,
+    body_text: &str,
+    order_resp: Option<&OrderResponse>,
+    info: &OrderInfo,
+    trader_address: &str,
+    size_floor: SizeFloor,
+) -> Option<ResubmitRequest> {
+    let SubmittedOrder { action: order_action, side_is_buy, limit_price, shares: my_shares, is_live } = *order;
+    if !side_is_buy || order_action != "FAK" {
+        return None;
     }
 
-    let probability = target_capped / required_floor;
-    let pct = (probability * 100.0) as u8;
-    if rand::thread_rng().r#gen::<f64>() < probability {
-        (required_floor, SizeType::ProbHit(pct))
+    let requested_shares = (my_shares * 100.0).floor() / 100.0;
+    let (size, cumulative_filled) = if let Some(resp) = order_resp {
+        let filled_shares: f64 = resp.taking_amount.parse().unwrap_or(0.0);
+        if filled_shares >= requested_shares || filled_shares <= 0.0 {
+            return None;
+        }
+        let remaining_shares = requested_shares - filled_shares;
+        if remaining_shares < size_floor.required_shares(limit_price) {
+            return None;
+        }
+        ((remaining_shares * 100.0).floor() / 100.0, filled_shares)
+    } else if http_status == 400 && body_text.contains("FAK") {
+        (requested_shares, 0.0)
     } else {
-        (0.0, SizeType::ProbSkip(pct))
-    }
-}
+        return None;
+    };
 
-/// Get ANSI color code based on fill percentage
-fn get_fill_color(filled: f64, requested: f64) -> &'static str {
-    if requested <= 0.0 { return "\x1b[31m"; }  // Red if no request
-    let pct = (filled / requested) * 100.0;
-    if pct < 50.0 { "\x1b[31m" }                // Red
-    else if pct < 75.0 { "\x1b[38;5;208m" }     // Orange
-    else if pct < 90.0 { "\x1b[33m" }           // Yellow
-    else { "\x1b[32m" }                          // Green
+    let resubmit_buffer = get_resubmit_max_buffer(info.shares);
+    Some(ResubmitRequest {
+        token_id: info.clob_token_id.to_string(),
+        trader_address: trader_address.to_string(),
+        whale_price: info.price_per_share,
+        failed_price: limit_price,  // Start at same price (already filled some, or none available)
+        size,
+        whale_shares: info.shares,
+        side_is_buy: true,
+        attempt: 1,
+        max_price: (limit_price + resubmit_buffer).min(0.99),
+        cumulative_filled,
+        original_size: requested_shares,
+        is_live,
+    })
 }
 
-/// Get ANSI color code based on whale share count (gradient from small to large)
-fn get_whale_size_color(shares: f64) -> &'static str {
-    if shares < 500.0 { "\x1b[90m" }              // Gray (very small)
-    else if shares < 1000.0 { "\x1b[36m" }        // Cyan (small)
-    else if shares < 2000.0 { "\x1b[34m" }        // Blue (medium-small)
-    else if shares < 5000.0 { "\x1b[32m" }        // Green (medium)
-    else if shares < 8000.0 { "\x1b[33m" }        // Yellow (medium-large)
-    else if shares < 15000.0 { "\x1b[38;5;208m" } // Orange (large)
-    else { "\x1b[35m" }                           // Magenta (huge)
+/// Size a SELL as the fraction of our position the whale is selling of theirs
+///
+/// `whale_prior_shares` is the whale's position before this sell, as seen in our trade
+/// history. When it's unknown (or not positive) the buy-style `fallback` size is used.
+/// Either way the result never exceeds our held shares (floored to 0.01).
+fn calculate_sell_size(
+    whale_sell_shares: f64,
+    whale_prior_shares: Option<f64>,
+    our_net_shares: f64,
+    fallback: (f64, SizeType),
+) -> (f64, SizeType) {
+    let available = ((our_net_shares * 100.0).floor() / 100.0).max(0.0);
+    let (shares, size_type) = match whale_prior_shares {
+        Some(prior) if prior > 0.0 => {
+            let fraction = (whale_sell_shares / prior).min(1.0);
+            (our_net_shares * fraction, SizeType::ProportionalSell)
+        }
+        _ => fallback,
+    };
+    (shares.clamp(0.0, available), size_type)
+}
+
+/// Kelly or flat-USD order size replacing whale scaling, if either mode is configured
+///
+/// Kelly mode sizes BUYs from the bankroll; an unavailable portfolio value is an error (the BUY is
+/// skipped, never sized another way). Flat mode copies every trade with the configured USD.
+fn size_target(
+    kelly_sizing: Option<KellySizing>,
+    flat_usd: Option<f64>,
+    side_is_buy: bool,
+    limit_price: f64,
+    portfolio_tracker: Option<&Arc<PortfolioTracker>>,
+) -> Result<Option<SizeTarget>> {
+    if let Some(kelly) = kelly_sizing.filter(|_| side_is_buy) {
+        let bankroll = portfolio_tracker
+            .ok_or_else(|| anyhow!("no portfolio tracker"))?
+            .get_portfolio_value()?;
+        return Ok(Some(SizeTarget::Kelly(kelly.target_shares(bankroll, limit_price))));
+    }
+    Ok(flat_usd.map(SizeTarget::FlatUsd))
+}
+
+/// Shares and limit price the SHADOW_MODE strategy would order for a whale trade (None = no order)
+///
+/// Applies the live price range, minimum whale size, tier buffer and caps, but sizes with the
+/// shadow's own ratio or target; SELLs are sized from the shadow's own holdings. Session
+/// state (risk guard, trading hours, exposure caps, balance) is not consulted. Never submitted.
+fn shadow_order(
+    event: &ParsedEvent,
+    shadow: &ShadowStrategy,
+    order_settings: &OrderSettings,
+    db_path: Option<&str>,
+    portfolio_tracker: Option<&Arc<PortfolioTracker>>,
+) -> Option<(f64, f64)> {
+    let info = &event.order;
+    if info.invalid_field().is_some() || ExitKind::from_order_type(&info.order_type).is_some() {
+        return None;
+    }
+    let min_threshold = if event.trader_min_shares > 0.0 { event.trader_min_shares } else { MIN_WHALE_SHARES_TO_COPY };
+    let price_range = order_settings.copy_price_range.with_trader(event.trader_copy_price_range);
+    if info.shares < min_threshold || !price_range.contains(info.price_per_share) {
+        return None;
+    }
+
+    let side_is_buy = info.order_type.starts_with("BUY");
+    let (buffer, _, size_multiplier) =
+        get_tier_params_with_fok(info.shares, side_is_buy, &info.clob_token_id, order_settings.fok_min_shares, &order_settings.execution_tiers);
+    let limit_price = if side_is_buy {
+        (info.price_per_share + buffer).min(0.99)
+    } else {
+        (info.price_per_share - buffer).max(0.01)
+    };
+
+    // The shadow can only sell what it bought
+    let mut held = None;
+    let mut whale_prior_shares = None;
+    if !side_is_buy {
+        let store = TradeStore::new(db_path?)
+            .inspect_err(|e| eprintln!("Warning: Failed to open DB for shadow SELL: {}", e))
+            .ok()?;
+        held = Some(store.get_shadow_net_shares(&info.clob_token_id).ok().filter(|shares| *shares > 0.0)?);
+        whale_prior_shares = store.get_whale_net_shares(&event.trader_address, &info.clob_token_id).ok().flatten();
+    }
+
+    let max_bet_shares = portfolio_tracker.and_then(|tracker| tracker.get_max_bet_shares(limit_price));
+    let trader_max_shares = event.trader_max_bet_usd
+        .filter(|usd| *usd > 0.0)
+        .map(|usd| usd / limit_price.max(0.01));
+    // Like the live order, a Kelly shadow without a bankroll places nothing
+    let target = size_target(shadow.kelly_sizing, shadow.flat_usd, side_is_buy, limit_price, portfolio_tracker).ok()?;
+    let scaling_ratio = shadow.scaling_ratio.unwrap_or(
+        if event.trader_scaling_ratio > 0.0 { event.trader_scaling_ratio } else { order_settings.scaling_ratio }
+    );
+    let size_floor = order_settings.size_floor.with_probabilistic(event.trader_probabilistic_sizing);
+    let mut sized = calculate_safe_size(SizingInput {
+        whale_shares: info.shares,
+        scaling_ratio,
+        price: limit_price,
+        size_multiplier,
+        max_bet_shares,
+        trader_max_shares,
+        target,
+        floor: size_floor,
+    });
+    if let Some(held) = held {
+        sized = calculate_sell_size(info.shares, whale_prior_shares, held, sized);
+    }
+    let shares = (sized.0 * 100.0).floor() / 100.0;
+    (shares > 0.0).then_some((shares, limit_price))
+}
+
+/// SHADOW row for a shadow order, treated as fully filled at its limit price
+fn shadow_record(event: &ParsedEvent, shares: f64, price: f64, is_live: Option<bool>) -> TradeRecord {
+    TradeRecord {
+        timestamp_ms: chrono::Utc::now().timestamp_millis(),
+        block_number: event.block_number,
+        tx_hash: event.tx_hash.clone(),
+        trader_address: event.trader_address.clone(),
+        token_id: event.order.clob_token_id.to_string(),
+        side: if event.order.order_type.starts_with("BUY") { "BUY".to_string() } else { "SELL".to_string() },
+        whale_shares: event.order.shares,
+        whale_price: event.order.price_per_share,
+        whale_usd: event.order.usd_value,
+        our_shares: Some(shares),
+        our_price: Some(price),
+        our_usd: Some(shares * price),
+        fill_pct: Some(100.0),
+        status: SHADOW_STATUS.to_string(),
+        latency_ms: None,
+        is_live,
+        aggregation_count: None,
+        aggregation_window_ms: None,
+        order_id: None,
+        contributing_traders: None,
+    }
+}
+
+/// Skip status for neg-risk tokens when SKIP_NEG_RISK is set; `lookup` only runs when skipping is enabled
+/// A failed lookup lets the order through (create_order routes it to the right exchange anyway)
+fn neg_risk_status(skip_neg_risk: bool, lookup: impl FnOnce() -> anyhow::Result<bool>) -> Option<&'static str> {
+    if !skip_neg_risk {
+        return None;
+    }
+    match lookup() {
+        Ok(true) => Some("SKIPPED_NEGRISK"),
+        Ok(false) => None,
+        Err(e) => {
+            eprintln!("Warning: neg-risk lookup failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Skip status if TOKEN_BLOCKLIST/TOKEN_ALLOWLIST exclude the token (blocklist wins; empty allowlist allows all)
+fn token_list_status(token_id: &str, blocklist: &HashSet<String>, allowlist: &HashSet<String>) -> Option<&'static str> {
+    if blocklist.contains(token_id) {
+        Some("SKIPPED_BLOCKLIST")
+    } else if !allowlist.is_empty() && !allowlist.contains(token_id) {
+        Some("SKIPPED_NOT_ALLOWED")
+    } else {
+        None
+    }
+}
+
+/// Shares of a BUY at `price` that keep a market's exposure within `cap_usd`
+/// The full order if it fits, else the remaining room (floored to 0.01 shares) if that still meets
+/// the order floor, else None
+fn fit_market_exposure(held_usd: f64, order_shares: f64, price: f64, cap_usd: f64, floor: SizeFloor) -> Option<f64> {
+    let room_usd = cap_usd - held_usd;
+    // Tolerance so an order landing exactly on the cap isn't lost to float rounding
+    if order_shares * price <= room_usd + 1e-9 {
+        return Some(order_shares);
+    }
+    let safe_price = price.max(0.0001);
+    let fitted = (room_usd.max(0.0) / safe_price * 100.0).floor() / 100.0;
+    (fitted >= floor.required_shares(price)).then_some(fitted)
+}
+
+/// Get ANSI color code based on whale share count (gradient from small to large)
+fn get_whale_size_color(shares: f64) -> &'static str {
+    if shares < 500.0 { "\x1b[90m" }              // Gray (very small)
+    else if shares < 1000.0 { "\x1b[36m" }        // Cyan (small)
+    else if shares < 2000.0 { "\x1b[34m" }        // Blue (medium-small)
+    else if shares < 5000.0 { "\x1b[32m" }        // Green (medium)
+    else if shares < 8000.0 { "\x1b[33m" }        // Yellow (medium-large)
+    else if shares < 15000.0 { "\x1b[38;5;208m" } // Orange (large)
+    else { "\x1b[35m" }                           // Magenta (huge)
 }
 
 fn fetch_book_depth_blocking(
@@ -758,8 +2253,9 @@ fn fetch_book_depth_blocking(
     token_id: &str,
     side: TradeSide,
     threshold: f64,
+    max_levels: usize,
 ) -> Result<f64, &'static str> {
-    let url = format!("{}/book?token_id={}", CLOB_API_BASE, token_id);
+    let url = format!("{}/book?token_id={}", client.host(), token_id);
     let resp = client.http_client()
         .get(&url)
         .timeout(Duration::from_millis(500))
@@ -771,35 +2267,69 @@ fn fetch_book_depth_blocking(
     let book: Value = resp.json().map_err(|_| "PARSE")?;
     let key = if side == TradeSide::Buy { "asks" } else { "bids" };
 
-    // Stack array instead of Vec - avoids heap allocation for max 10 items
-    let mut levels: [(f64, f64); 10] = [(0.0, 0.0); 10];
-    let mut count = 0;
-    if let Some(arr) = book[key].as_array() {
-        for lvl in arr.iter().take(10) {
-            if let (Some(p), Some(s)) = (
-                lvl["price"].as_str().and_then(|s| s.parse().ok()),
-                lvl["size"].as_str().and_then(|s| s.parse().ok()),
-            ) {
-                levels[count] = (p, s);
-                count += 1;
-            }
+    let levels = parse_book_levels(&book[key], max_levels);
+    Ok(calc_liquidity_depth(side, &levels, threshold))
+}
+
+/// Fetch the current bid/ask spread for a token (blocking variant of `fetch_best_book`)
+fn fetch_spread_blocking(client: &RustClobClient, token_id: &str) -> Result<f64, &'static str> {
+    let url = format!("{}/book?token_id={}", client.host(), token_id);
+    let resp = client.http_client()
+        .get(&url)
+        .timeout(Duration::from_millis(500))
+        .send()
+        .map_err(|_| "NETWORK")?;
+
+    if !resp.status().is_success() { return Err("HTTP_ERROR"); }
+
+    let book: Value = resp.json().map_err(|_| "PARSE")?;
+    book_spread(&book).ok_or("EMPTY_BOOK")
+}
+
+/// Best ask minus best bid (None if either side is empty)
+fn book_spread(book: &Value) -> Option<f64> {
+    let best_bid = parse_book_levels(&book["bids"], usize::MAX)
+        .into_iter()
+        .map(|(p, _)| p)
+        .reduce(f64::max)?;
+    let best_ask = parse_book_levels(&book["asks"], usize::MAX)
+        .into_iter()
+        .map(|(p, _)| p)
+        .reduce(f64::min)?;
+    Some((best_ask - best_bid).max(0.0))
+}
+
+/// Parse up to `max_levels` (price, size) pairs from one side of a book response
+fn parse_book_levels(side_levels: &Value, max_levels: usize) -> Vec<(f64, f64)> {
+    let Some(arr) = side_levels.as_array() else { return Vec::new() };
+    let mut levels = Vec::with_capacity(max_levels.min(arr.len()));
+    for lvl in arr.iter().take(max_levels) {
+        if let (Some(p), Some(s)) = (
+            lvl["price"].as_str().and_then(|s| s.parse().ok()),
+            lvl["size"].as_str().and_then(|s| s.parse().ok()),
+        ) {
+            levels.push((p, s));
         }
     }
-
-    Ok(calc_liquidity_depth(side, &levels[..count], threshold))
+    levels
 }
 
 // ============================================================================
 // WebSocket Loop
 // ============================================================================
 
+/// True when `topic_count` trader topics exceed what is sent to the provider (TOPIC_FILTER_MAX_TRADERS)
+fn uses_client_side_filtering(topic_count: usize, max_traders: usize) -> bool {
+    topic_count > max_traders
+}
+
 /// Build WebSocket subscription message for monitoring trader events
 /// Returns JSON-RPC subscription message as string
-fn build_subscription_message(topic_filter: Vec<String>) -> String {
+fn build_subscription_message(topic_filter: Vec<String>, max_traders: usize) -> String {
     let topics_array: Value = if topic_filter.is_empty() {
         // No filter - should not happen in practice
         serde_json::json!([[ORDERS_FILLED_EVENT_SIGNATURE], Value::Null, Value::Null])
-    } else if topic_filter.len() > 10 {
+    } else if uses_client_side_filtering(topic_filter.len(), max_traders) {
         // Too many traders - use null filter and do client-side filtering
         serde_json::json!([[ORDERS_FILLED_EVENT_SIGNATURE], Value::Null, Value::Null])
     } else {
@@ -816,27 +2346,151 @@ fn build_subscription_message(topic_filter: Vec<String>) -> String {
     }).to_string()
 }
 
+/// Returns the inclusive range of skipped blocks if `current` is more than
+/// `threshold` blocks ahead of `last` (threshold 0 disables detection)
+fn detect_block_gap(last: Option<u64>, current: u64, threshold: u64) -> Option<(u64, u64)> {
+    let last = last?;
+    if threshold == 0 || current <= last {
+        return None;
+    }
+    let gap = current - last;
+    if gap > threshold {
+        Some((last + 1, current - 1))
+    } else {
+        None
+    }
+}
+
+/// Update the last seen block and, on the first event after a reconnect, warn if events may have been missed
+///
+/// Within one connection the subscription only delivers the followed traders' fills, so long
+/// quiet stretches between them are normal and say nothing about missed events.
+/// Returns the skipped block range, if any
+fn track_block_gap(last_block_seen: &mut Option<u64>, block: u64, threshold: u64, after_reconnect: bool) -> Option<(u64, u64)> {
+    let gap = if after_reconnect { detect_block_gap(*last_block_seen, block, threshold) } else { None };
+    if let Some((from, to)) = gap {
+        eprintln!(
+            "⚠️ Block gap across reconnect: {} blocks skipped ({}..={}). Whale trades may have been missed.",
+            to - from + 1, from, to
+        );
+    }
+    if last_block_seen.is_none_or(|last| block > last) {
+        *last_block_seen = Some(block);
+    }
+    gap
+}
+
+/// Everything needed to push replayed events through the normal pipeline
+struct ReplayContext {
+    rpc_url: String,
+    topic_filter: Vec<String>,
+    topic_filter_max_traders: usize,
+    traders: TradersConfig,
+    order_engine: OrderEngine,
+    http_client: reqwest::Client,
+    trade_tx: Option<mpsc::UnboundedSender<TradeRecord>>,
+    trader_manager: Arc<Mutex<TraderManager>>,
+    aggregator: Option<Arc<Mutex<TradeAggregator>>>,
+    event_dedup: Arc<Mutex<EventDedup>>,
+    db_path: Option<String>,
+    events: Arc<EventContext>,
+}
+
+/// Fetch fills from a skipped block range over HTTP and feed them into handle_event
+/// Events already seen on the WebSocket (EventDedup) or already in the TradeStore are skipped
+async fn replay_missed_events(from_block: u64, to_block: u64, ctx: ReplayContext) {
+    let Some((from, to)) = replay::clamp_replay_range(from_block, to_block) else { return };
+    // Large filters use client-side filtering, same as the WS subscription
+    let filter: &[String] = if uses_client_side_filtering(ctx.topic_filter.len(), ctx.topic_filter_max_traders) {
+        &[]
+    } else {
+        &ctx.topic_filter
+    };
+
+    let messages = match replay::fetch_missed_logs(&ctx.http_client, &ctx.rpc_url, from, to, filter).await {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("⚠️ Replay of blocks {}..={} failed: {}", from, to, e);
+            return;
+        }
+    };
+
+    let events: Vec<ParsedEvent> = {
+        let mut dedup = ctx.event_dedup.lock().await;
+        messages.into_iter()
+            .filter_map(|m| parse_event(m, Some(&ctx.traders)))
+            .filter(|evt| dedup.first_seen(evt))
+            .collect()
+    };
+
+    // Deduplicate against trades we already recorded
+    let events = match ctx.db_path.clone() {
+        Some(db_path) => tokio::task::spawn_blocking(move || {
+            let Ok(store) = TradeStore::new(&db_path) else { return events };
+            events.into_iter()
+                .filter(|e| !store.tx_hash_exists(&e.tx_hash).unwrap_or(false))
+                .collect()
+        }).await.unwrap_or_default(),
+        None => events,
+    };
+
+    println!("🔁 Replaying {} missed event(s) from blocks {}..={}", events.len(), from, to);
+
+    for evt in events {
+        let engine = ctx.order_engine.clone();
+        let client = ctx.http_client.clone();
+        let tx = ctx.trade_tx.clone();
+        let tm = Arc::clone(&ctx.trader_manager);
+        let agg = ctx.aggregator.clone();
+        let events = Arc::clone(&ctx.events);
+        tokio::spawn(async move { handle_event(evt, &engine, &client, &events, tx, tm, agg).await });
+    }
+}
+
+/// What the heartbeat keeps across WebSocket reconnects
+struct HeartbeatState {
+    /// Last retention prune (startup included)
+    last_prune: Option<std::time::Instant>,
+    /// For the open GTD order count
+    placed_gtd_orders: PlacedGtdOrders,
+    /// Refreshed with each stats persist, read by the order worker
+    trader_pnl: TraderPnl,
+    /// Funder USDC balance, refetched at most once per HEARTBEAT_INTERVAL (created off the runtime on first use)
+    balance: Arc<once_cell::sync::OnceCell<BalanceTracker>>,
+}
+
 async fn run_ws_loop(
     cfg: &Config,
+    wss_url: &str,
     reloadable_traders: &ReloadableTraders,
     order_engine: &OrderEngine,
     trade_tx: Option<mpsc::UnboundedSender<TradeRecord>>,
     trader_manager: Arc<Mutex<TraderManager>>,
     stats_persist_path: Option<String>,
     aggregator: Option<Arc<Mutex<TradeAggregator>>>,
+    notifier: &Notifiers,
+    ws_health: &WsHealth,
+    event_dedup: &Arc<Mutex<EventDedup>>,
     config_change_rx: &mut tokio::sync::watch::Receiver<u64>,
+    reconnect_failures: &mut u32,
+    last_block_seen: &mut Option<u64>,
+    heartbeat: &mut HeartbeatState,
 ) -> Result<()> {
-    let (mut ws, _) = connect_async(&cfg.wss_url).await?;
+    ws_health.record_connected();
+    let (mut ws, _) = connect_async(wss_url).await?;
 
     // Build topic filter from traders config
     let traders_config = reloadable_traders.read().await;
     let topic_filter = traders_config.build_topic_filter();
-    let sub = build_subscription_message(topic_filter.clone());
+    let sub = build_subscription_message(topic_filter.clone(), cfg.topic_filter_max_traders);
 
     // Log trader monitoring info with topic details for debugging
     let trader_count = traders_config.iter().filter(|t| t.enabled).count();
-    if topic_filter.len() > 10 {
-        println!("🔌 Connected. Subscribing to {} traders (client-side filtering)...", trader_count);
+    if uses_client_side_filtering(topic_filter.len(), cfg.topic_filter_max_traders) {
+        println!(
+            "🔌 Connected. Subscribing to {} traders (client-side filtering, more than TOPIC_FILTER_MAX_TRADERS={})...",
+            trader_count, cfg.topic_filter_max_traders
+        );
     } else {
         println!("🔌 Connected. Subscribing to {} trader(s)...", trader_count);
     }
@@ -852,63 +2506,112 @@ async fn run_ws_loop(
     let http_client = reqwest::Client::builder().no_proxy().build()?;
     let mut subscription_confirmed = false;
     let mut last_heartbeat = std::time::Instant::now();
-    let heartbeat_interval = Duration::from_secs(60);
+    let events = Arc::new(EventContext {
+        endpoints: cfg.api_endpoints.clone(),
+        notifier: notifier.clone(),
+        post_trade_book_delay: Duration::from_millis(cfg.post_trade_book_delay_ms),
+        success_fill_pct: cfg.success_fill_pct,
+    });
 
     // Get a snapshot of traders config for parsing events in this loop iteration
-    // When config changes, we'll exit and reconnect with the new config
-    let traders_snapshot = reloadable_traders.read().await.clone();
+    // When the trader list changes we exit and reconnect; other setting changes are picked up in place
+    let mut traders_snapshot = reloadable_traders.read().await.clone();
+
+    // Gaps are only measured across a reconnect (not the very first connection)
+    let mut first_event_after_reconnect = last_block_seen.is_some();
+    let mut replay_pending = cfg.replay_enabled && first_event_after_reconnect;
 
     loop {
-        // Check for config changes - if changed, exit loop to reconnect
+        // Check for config changes - if the subscribed traders changed, exit loop to reconnect
         if config_change_rx.has_changed().unwrap_or(false) {
             let _ = config_change_rx.borrow_and_update(); // Clear the changed flag
-            return Err(anyhow!("Config changed, reconnecting"));
+            let latest = reloadable_traders.read().await.clone();
+            if latest.build_topic_filter() != topic_filter {
+                return Err(anyhow!("Config changed, reconnecting"));
+            }
+            // Same traders: new scaling_ratio/min_shares/max_bet_usd apply from the next event
+            println!("🔄 Trader settings updated (no resubscribe needed)");
+            traders_snapshot = latest;
         }
 
         let msg = tokio::time::timeout(WS_PING_TIMEOUT, ws.next()).await
             .map_err(|_| anyhow!("WS timeout"))?
             .ok_or_else(|| anyhow!("WS closed"))??;
+        ws_health.record_message();
 
-        match msg {
+        let text = match msg {
             Message::Text(text) => {
                 // Check for subscription confirmation (first message after subscribing)
-                if !subscription_confirmed {
-                    if let Ok(v) = serde_json::from_str::<Value>(&text) {
-                        if v.get("id").and_then(|i| i.as_i64()) == Some(1) && v.get("result").is_some() {
-                            subscription_confirmed = true;
-                            println!("✅ Subscription confirmed. Listening for whale trades...");
-                        }
-                    }
+                if !subscription_confirmed
+                    && let Ok(v) = serde_json::from_str::<Value>(&text)
+                    && v.get("id").and_then(|i| i.as_i64()) == Some(1)
+                    && v.get("result").is_some()
+                {
+                    subscription_confirmed = true;
+                    ws_health.record_subscription_confirmed();
+                    *reconnect_failures = 0;
+                    println!("✅ Subscription confirmed. Listening for whale trades...");
                 }
+                Some(text)
+            }
+            Message::Binary(bin) => String::from_utf8(bin).ok(),
+            Message::Ping(d) => { ws.send(Message::Pong(d)).await?; None }
+            Message::Close(f) => return Err(anyhow!("WS closed: {:?}", f)),
+            _ => None,
+        };
 
-                if let Some(evt) = parse_event(text, Some(&traders_snapshot)) {
-                    let engine = order_engine.clone();
-                    let client = http_client.clone();
-                    let tx = trade_tx.clone();
-                    let tm = Arc::clone(&trader_manager);
-                    let agg = aggregator.clone();
-                    tokio::spawn(async move { handle_event(evt, &engine, &client, tx, tm, agg).await });
-                }
+        // Exactly what upstream sent, before parsing and trader filtering
+        if let Some(text) = &text
+            && raw_event_log::is_enabled()
+        {
+            raw_event_log::record(text.clone(), Utc::now().timestamp_millis());
+        }
+
+        if let Some(evt) = text.and_then(|t| parse_event(t, Some(&traders_snapshot))) {
+            // Same fill delivered twice (Text + Binary frame, or resent after a reconnect)
+            if !event_dedup.lock().await.first_seen(&evt) {
+                println!("⏭️ Duplicate event skipped: tx {} token {} {}", evt.tx_hash, evt.order.clob_token_id, evt.order.order_type);
+                continue;
             }
-            Message::Binary(bin) => {
-                if let Ok(text) = String::from_utf8(bin) {
-                    if let Some(evt) = parse_event(text, Some(&traders_snapshot)) {
-                        let engine = order_engine.clone();
-                        let client = http_client.clone();
-                        let tx = trade_tx.clone();
-                        let tm = Arc::clone(&trader_manager);
-                        let agg = aggregator.clone();
-                        tokio::spawn(async move { handle_event(evt, &engine, &client, tx, tm, agg).await });
-                    }
+
+            let last_seen_before = *last_block_seen;
+            track_block_gap(last_block_seen, evt.block_number, cfg.ws_block_gap_threshold, first_event_after_reconnect);
+            first_event_after_reconnect = false;
+
+            // First event after a reconnect: recover anything missed while disconnected,
+            // however short the gap (the warning threshold doesn't apply here)
+            if replay_pending {
+                replay_pending = false;
+                if let Some((from, to)) = replay::reconnect_range(last_seen_before, evt.block_number) {
+                    let ctx = ReplayContext {
+                        rpc_url: replay::http_url_from_wss(wss_url),
+                        topic_filter: topic_filter.clone(),
+                        topic_filter_max_traders: cfg.topic_filter_max_traders,
+                        traders: traders_snapshot.clone(),
+                        order_engine: order_engine.clone(),
+                        http_client: http_client.clone(),
+                        trade_tx: trade_tx.clone(),
+                        trader_manager: Arc::clone(&trader_manager),
+                        aggregator: aggregator.clone(),
+                        event_dedup: Arc::clone(event_dedup),
+                        db_path: stats_persist_path.clone(),
+                        events: Arc::clone(&events),
+                    };
+                    tokio::spawn(replay_missed_events(from, to, ctx));
                 }
             }
-            Message::Ping(d) => { ws.send(Message::Pong(d)).await?; }
-            Message::Close(f) => return Err(anyhow!("WS closed: {:?}", f)),
-            _ => {}
+
+            let engine = order_engine.clone();
+            let client = http_client.clone();
+            let tx = trade_tx.clone();
+            let tm = Arc::clone(&trader_manager);
+            let agg = aggregator.clone();
+            let events = Arc::clone(&events);
+            tokio::spawn(async move { handle_event(evt, &engine, &client, &events, tx, tm, agg).await });
         }
 
         // Periodic heartbeat to show bot is alive and check daily reset
-        if last_heartbeat.elapsed() >= heartbeat_interval {
+        if last_heartbeat.elapsed() >= HEARTBEAT_INTERVAL {
             // Check daily reset
             {
                 let mut manager = trader_manager.lock().await;
@@ -921,23 +2624,56 @@ async fn run_ws_loop(
                 manager.get_summary_stats()
             };
 
-            println!(
-                "💓 Heartbeat: {} traders | {} trades today | {}/{}/{} (success/partial/failed) | ${:.2} total copied",
-                stats.total_traders,
-                stats.total_trades,
-                stats.total_successful,
-                stats.total_partial,
-                stats.total_failed,
-                stats.total_copied_usd
-            );
+            // Account health reads RPC and the DB, so the line is emitted off the runtime
+            let funder = cfg.funder_address.clone().unwrap_or_else(|| cfg.wallet_address.clone());
+            let db_path = stats_persist_path.clone();
+            let open_gtd_orders = heartbeat.placed_gtd_orders.lock().map(|set| set.len()).unwrap_or(0);
+            let balance = Arc::clone(&heartbeat.balance);
+            tokio::task::spawn_blocking(move || {
+                let balance = balance.get_or_init(|| {
+                    BalanceTracker::new(Box::new(RpcUsdcBalance::new(&funder)), 0.0, HEARTBEAT_INTERVAL)
+                });
+                let usdc_balance = balance.balance()
+                    .inspect_err(|e| eprintln!("Warning: Failed to read USDC balance: {}", e))
+                    .ok();
+                let open_positions = db_path.and_then(|path| {
+                    TradeStore::new(&path)
+                        .and_then(|store| store.get_positions())
+                        .map(|positions| positions.iter().filter(|p| p.net_shares > 0.0).count())
+                        .inspect_err(|e| eprintln!("Warning: Failed to count open positions: {}", e))
+                        .ok()
+                });
+                logging::emit(&HeartbeatLog {
+                    traders: stats.total_traders,
+                    trades: stats.total_trades,
+                    successful: stats.total_successful,
+                    partial: stats.total_partial,
+                    failed: stats.total_failed,
+                    copied_usd: stats.total_copied_usd,
+                    realized_pnl: stats.total_realized_pnl,
+                    usdc_balance,
+                    open_positions,
+                    open_gtd_orders,
+                });
+            });
 
             // Persist trader stats to database (if enabled)
             if let Some(ref db_path) = stats_persist_path {
                 let db_path = db_path.clone();
                 let tm = Arc::clone(&trader_manager);
+                let trader_pnl = Arc::clone(&heartbeat.trader_pnl);
+                let fee_per_trade = cfg.fee_per_trade_usd;
                 tokio::task::spawn_blocking(move || {
                     if let Ok(store) = TradeStore::new(&db_path) {
-                        let manager = tokio::runtime::Handle::current().block_on(tm.lock());
+                        let pnl = store.get_realized_pnl_by_trader(fee_per_trade);
+                        let mut manager = tokio::runtime::Handle::current().block_on(tm.lock());
+                        match pnl {
+                            Ok(pnl) => {
+                                manager.apply_realized_pnl(&pnl);
+                                *trader_pnl.lock().unwrap() = pnl;
+                            }
+                            Err(e) => eprintln!("Warning: Failed to compute realized P&L: {}", e),
+                        }
                         if let Err(e) = manager.persist_to_db(&store) {
                             eprintln!("Warning: Failed to persist trader stats: {}", e);
                         }
@@ -945,27 +2681,264 @@ async fn run_ws_loop(
                 });
             }
 
+            // Daily retention pruning (if configured)
+            if let (Some(days), Some(db_path)) = (cfg.retention_days, &stats_persist_path)
+                && claim_prune(&mut heartbeat.last_prune)
+            {
+                let db_path = db_path.clone();
+                tokio::task::spawn_blocking(move || prune_old_trades(&db_path, days));
+            }
+
             last_heartbeat = std::time::Instant::now();
         }
     }
 }
 
+/// Ok if a BUY of `token_id` stays within `max` open positions, Err(open count) otherwise
+/// Tokens already held always pass. `load` reads held tokens when the cache is older than OPEN_POSITIONS_TTL;
+/// if it fails the order is allowed (the cap is a soft limit, not a safety check).
+fn open_positions_check(
+    cache: &mut OpenPositionsCache,
+    token_id: &str,
+    max: usize,
+    load: impl FnOnce() -> anyhow::Result<HashMap<String, f64>>,
+) -> Result<(), usize> {
+    with_open_positions(cache, load, |held| {
+        if held.contains_key(token_id) || held.len() < max {
+            Ok(())
+        } else {
+            Err(held.len())
+        }
+    }).unwrap_or(Ok(()))
+}
+
+/// `read` the held shares per token, reloading them with `load` once the cache is older than
+/// OPEN_POSITIONS_TTL. None (after a warning) if they can't be loaded.
+fn with_open_positions<T>(
+    cache: &mut OpenPositionsCache,
+    load: impl FnOnce() -> anyhow::Result<HashMap<String, f64>>,
+    read: impl FnOnce(&HashMap<String, f64>) -> T,
+) -> Option<T> {
+    if cache.as_ref().is_none_or(|(_, fetched_at)| fetched_at.elapsed() >= OPEN_POSITIONS_TTL) {
+        match load() {
+            Ok(held) => *cache = Some((held, std::time::Instant::now())),
+            Err(e) => {
+                eprintln!("Warning: Failed to load open positions: {}", e);
+                return None;
+            }
+        }
+    }
+    cache.as_ref().map(|(held, _)| read(held))
+}
+
+/// Net shares of every token held in the trade DB
+fn load_open_positions(db_path: &str) -> anyhow::Result<HashMap<String, f64>> {
+    Ok(TradeStore::new(db_path)?
+        .get_positions()?
+        .into_iter()
+        .filter(|p| p.net_shares > 0.0)
+        .map(|p| (p.token_id, p.net_shares))
+        .collect())
+}
+
+/// Ok (and the submission recorded) if `token_id` had no submission within `min_interval`,
+/// Err(time left) otherwise. A zero interval never limits.
+fn submit_interval_check(
+    times: &mut SubmitTimes,
+    token_id: &str,
+    min_interval: Duration,
+    now: std::time::Instant,
+) -> Result<(), Duration> {
+    if min_interval.is_zero() {
+        return Ok(());
+    }
+    times.retain(|_, last| now.saturating_duration_since(*last) < min_interval);
+    if let Some(last) = times.get(token_id) {
+        return Err(min_interval - now.saturating_duration_since(*last));
+    }
+    times.insert(token_id.to_string(), now);
+    Ok(())
+}
+
+/// Time left before a DEFERRED_RATE_LIMIT order may be submitted ("DEFERRED_RATE_LIMIT (120ms)")
+fn deferred_wait(status: &str) -> Option<Duration> {
+    let ms = status.strip_prefix(DEFERRED_RATE_LIMIT)?.trim().strip_prefix('(')?.strip_suffix("ms)")?;
+    ms.parse().ok().map(Duration::from_millis)
+}
+
+/// Submit `evt`; if the worker deferred it under MIN_SUBMIT_INTERVAL_MS, park it in the aggregator
+/// (flushed with its window) when one is given, otherwise wait out the interval and submit once more
+async fn submit_with_deferral(
+    order_engine: &OrderEngine,
+    evt: ParsedEvent,
+    is_live: Option<bool>,
+    aggregator: Option<&Arc<Mutex<TradeAggregator>>>,
+) -> String {
+    let status = order_engine.submit(evt.clone(), is_live).await;
+    let Some(wait) = deferred_wait(&status) else { return status };
+
+    if let Some(agg) = aggregator {
+        let side = if evt.order.order_type.starts_with("BUY") { "BUY" } else { "SELL" };
+        agg.lock().await.defer_trade(
+            evt.order.clob_token_id.to_string(), side.to_string(),
+            evt.order.shares, evt.order.price_per_share, evt.trader_address.clone(),
+        );
+        println!("[AGG] Deferred: {} submitted too recently, trade moved to aggregation window", evt.order.clob_token_id);
+        return AGG_DEFERRED_STATUS.into();
+    }
+    tokio::time::sleep(wait).await;
+    order_engine.submit(evt, is_live).await
+}
+
+/// CONFIDENCE_SCALING factor for `trader` from its realized closes (1.0 before the first P&L refresh)
+fn confidence_factor(pnl: &std::sync::Mutex<HashMap<String, RealizedPnl>>, trader: &str, scaling: ConfidenceScaling) -> f64 {
+    let pnl = pnl.lock().unwrap();
+    pnl.get(trader).map_or(1.0, |p| scaling.factor(p.winning_closes, p.losing_closes))
+}
+
+/// BUY copies of `token_id` placed since its last SELL copy (or startup)
+fn copy_streak(streaks: &CopyStreaks, token_id: &str) -> u32 {
+    streaks.get(token_id).copied().unwrap_or(0)
+}
+
+/// Count a copy the exchange accepted: BUYs extend the token's streak, SELLs end it
+fn note_copy(streaks: &mut CopyStreaks, token_id: &str, is_buy: bool) {
+    if is_buy {
+        *streaks.entry(token_id.to_string()).or_insert(0) += 1;
+    } else {
+        streaks.remove(token_id);
+    }
+}
+
+/// Apply a whale fill to the session holdings and return the shares held before it
+/// SELLs reduce the position; one sold down to zero is forgotten, so the next BUY counts as an open.
+fn observe_whale_fill(holdings: &mut WhaleHoldings, trader: &str, token_id: &str, is_buy: bool, shares: f64) -> f64 {
+    let key = (trader.to_lowercase(), token_id.to_string());
+    let before = holdings.get(&key).copied().unwrap_or(0.0);
+    let after = if is_buy { before + shares } else { before - shares };
+    if after > 0.0 {
+        holdings.insert(key, after);
+    } else {
+        holdings.remove(&key);
+    }
+    before
+}
+
+/// Count freshly bought shares as held until the next reload (the DB write is asynchronous)
+fn note_open_position(cache: &mut OpenPositionsCache, token_id: &str, shares: f64) {
+    if let Some((held, _)) = cache.as_mut() {
+        *held.entry(token_id.to_string()).or_default() += shares;
+    }
+}
+
+/// True (and records now) if no prune has run within PRUNE_INTERVAL
+fn claim_prune(last_prune: &mut Option<std::time::Instant>) -> bool {
+    if last_prune.is_some_and(|t| t.elapsed() < PRUNE_INTERVAL) {
+        return false;
+    }
+    *last_prune = Some(std::time::Instant::now());
+    true
+}
+
+/// Delete trades older than `retention_days` and VACUUM if anything was removed (blocking)
+fn prune_old_trades(db_path: &str, retention_days: u32) {
+    let cutoff_ms = Utc::now().timestamp_millis() - i64::from(retention_days) * 86_400_000;
+    let result = TradeStore::new(db_path).and_then(|store| {
+        // Carry balances forward before the trades they come from are deleted
+        store.snapshot_positions()?;
+        let removed = store.prune_before(cutoff_ms)?;
+        if removed > 0 {
+            store.vacuum()?;
+        }
+        Ok(removed)
+    });
+    match result {
+        Ok(0) => {}
+        Ok(removed) => println!("🧹 Pruned {} trades older than {} days", removed, retention_days),
+        Err(e) => eprintln!("Warning: Failed to prune old trades: {}", e),
+    }
+}
+
+/// Log tokens where DB positions differ from the wallet's holdings, optionally writing
+/// adjustment rows so the DB matches (blocking)
+fn reconcile_positions(db_path: &str, wallet: &str, write_adjustments: bool) {
+    let options = FetchOptions::new().with_limit(500).with_size_threshold(0.0);
+    let chain = match fetch_live_positions_with_options(wallet, &options) {
+        Ok(positions) => positions,
+        Err(e) => {
+            eprintln!("Warning: Position reconciliation skipped, failed to fetch wallet positions: {}", e);
+            return;
+        }
+    };
+    let result = TradeStore::new(db_path).and_then(|store| {
+        let found = find_discrepancies(&store.get_positions()?, &chain, RECONCILE_TOLERANCE_SHARES);
+        if write_adjustments {
+            let now_ms = Utc::now().timestamp_millis();
+            for d in &found {
+                store.insert_trade(&d.adjustment_record(now_ms))?;
+            }
+        }
+        Ok(found)
+    });
+    match result {
+        Ok(found) if found.is_empty() => println!("Position reconciliation: DB matches {} wallet positions", chain.len()),
+        Ok(found) => {
+            eprintln!("⚠️  Position reconciliation: {} token(s) differ from the wallet", found.len());
+            for d in &found {
+                eprintln!(
+                    "   {} {}: DB {:.2}, wallet {:.2} ({:+.2})",
+                    d.token_id, d.title, d.db_shares, d.chain_shares, d.diff()
+                );
+            }
+            if write_adjustments {
+                println!("Position reconciliation: wrote {} {} adjustment rows", found.len(), RECONCILED_STATUS);
+            }
+        }
+        Err(e) => eprintln!("Warning: Position reconciliation failed: {}", e),
+    }
+}
+
+/// What every handle_event task on a connection shares (built once per connection)
+struct EventContext {
+    endpoints: ApiEndpoints,
+    notifier: Notifiers,
+    /// Wait before fetching the post-trade book (zero = skip it)
+    post_trade_book_delay: Duration,
+    /// Fill percent at or above which an order counts as a success
+    success_fill_pct: f64,
+}
+
 async fn handle_event(
     evt: ParsedEvent,
     order_engine: &OrderEngine,
     http_client: &reqwest::Client,
+    ctx: &EventContext,
     trade_tx: Option<mpsc::UnboundedSender<TradeRecord>>,
     trader_manager: Arc<Mutex<TraderManager>>,
     aggregator: Option<Arc<Mutex<TradeAggregator>>>,
 ) {
+    let mut timings = EventTimings::default();
+    let lookup_start = std::time::Instant::now();
     // Check live status from cache, fallback to API lookup
     let is_live = match market_cache::get_is_live(&evt.order.clob_token_id) {
         Some(v) => Some(v),
-        None => fetch_is_live(&evt.order.clob_token_id, http_client).await,
+        None => fetch_is_live(&ctx.endpoints.gamma, &evt.order.clob_token_id, http_client).await,
     };
+    // BUYs are checked against the market's end time and flags; fetch_is_live may have just cached them
+    if evt.order.order_type.starts_with("BUY") && market_cache::get_market_status(&evt.order.clob_token_id).is_none() {
+        fetch_market_status(&ctx.endpoints.gamma, &evt.order.clob_token_id, http_client).await;
+    }
+
+    timings.add(EventPhase::Lookup, lookup_start.elapsed());
 
     // Aggregation logic (if enabled)
-    let status = if let Some(agg) = aggregator {
+    let submit_start = std::time::Instant::now();
+    // (trade count, window ms, traders) of the aggregated order this event executed in, if any
+    let mut aggregation: Option<(u32, u64, Vec<String>)> = None;
+    let status = if evt.trader_observe {
+        // Observe-only trader: logged and recorded below, never sent to the order worker
+        OBSERVED_STATUS.to_string()
+    } else if let Some(agg) = aggregator {
         let side = if evt.order.order_type.starts_with("BUY") { "BUY" } else { "SELL" };
         let shares = evt.order.shares;
         let price = evt.order.price_per_share;
@@ -991,9 +2964,14 @@ async fn handle_event(
                         aggregated.trade_count, aggregated.total_shares, aggregated.avg_price
                     );
                 }
+                aggregation = Some((
+                    aggregated.trade_count as u32,
+                    aggregated.window_elapsed.as_millis() as u64,
+                    aggregated.traders.clone(),
+                ));
                 // Execute the aggregated trade with combined shares and avg price
                 let agg_evt = aggregated.to_parsed_event();
-                order_engine.submit(agg_evt, is_live).await
+                submit_with_deferral(order_engine, agg_evt, is_live, Some(&agg)).await
             }
             None => {
                 // Trade added to pending window
@@ -1003,66 +2981,18 @@ async fn handle_event(
         }
     } else {
         // Aggregation disabled - execute immediately
-        order_engine.submit(evt.clone(), is_live).await
-    };
-
-    tokio::time::sleep(Duration::from_secs_f32(2.8)).await;
-
-    // Fetch order book for post-trade logging
-    let bests = fetch_best_book(&evt.order.clob_token_id, &evt.order.order_type, http_client).await;
-    let ((bp, bs), (sp, ss)) = bests.unwrap_or_else(|| (("N/A".into(), "N/A".into()), ("N/A".into(), "N/A".into())));
-    let is_live_bool = is_live.unwrap_or(false);
-
-    // Highlight best price in bright pink
-    let pink = "\x1b[38;5;199m";
-    let reset = "\x1b[0m";
-    let colored_bp = format!("{}{}{}", pink, bp, reset);
-
-    let live_display = if is_live_bool {
-        format!("\x1b[34mlive: true\x1b[0m")
-    } else {
-        "live: false".to_string()
-    };
-
-    // Tennis market indicator (green)
-    let tennis_display = if tennis_markets::get_tennis_token_buffer(&evt.order.clob_token_id) > 0.0 {
-        "\x1b[32m(TENNIS)\x1b[0m "
-    } else {
-        ""
+        submit_with_deferral(order_engine, evt.clone(), is_live, None).await
     };
+    let submit_elapsed = submit_start.elapsed();
+    timings.add(EventPhase::Submit, submit_elapsed);
+    let latency_ms = submit_elapsed.as_millis() as i64;
+    let db_start = std::time::Instant::now();
 
-    // Soccer market indicator (cyan)
-    let soccer_display = if soccer_markets::get_soccer_token_buffer(&evt.order.clob_token_id) > 0.0 {
-        "\x1b[36m(SOCCER)\x1b[0m "
-    } else {
-        ""
-    };
-
-    println!(
-        "⚡ [B:{}] {}{}{} | ${:.0} | {} | best: {} @ {} | 2nd: {} @ {} | {}",
-        evt.block_number, tennis_display, soccer_display, evt.order.order_type, evt.order.usd_value, status, colored_bp, bs, sp, ss, live_display
-    );
 
     // Parse status to determine trade outcome and record in trader manager
     let (our_shares_opt, our_price_opt, our_usd_opt, fill_pct_opt, trade_status_str) = parse_status_for_db(&status);
 
-    // Determine TradeStatus enum from status string
-    let trade_status = if trade_status_str == "SUCCESS" {
-        // Check fill percentage to distinguish full success from partial
-        if let Some(fill_pct) = fill_pct_opt {
-            if fill_pct >= 90.0 {
-                TradeStatus::Success
-            } else {
-                TradeStatus::Partial
-            }
-        } else {
-            TradeStatus::Success
-        }
-    } else if trade_status_str.starts_with("SKIPPED") {
-        TradeStatus::Skipped
-    } else {
-        TradeStatus::Failed
-    };
+    let trade_status = classify_trade_status(&trade_status_str, fill_pct_opt, ctx.success_fill_pct);
 
     // Record trade in trader manager (with USD amount from our execution)
     let usd_amount = our_usd_opt.unwrap_or(0.0);
@@ -1071,6 +3001,8 @@ async fn handle_event(
         manager.record_trade(&evt.trader_address, usd_amount, trade_status);
     }
 
+    notify_trade(&ctx.notifier, &evt, &trade_status_str, trade_status, our_shares_opt, our_price_opt, fill_pct_opt);
+
     // Record trade to database if persistence is enabled
     if let Some(tx) = trade_tx {
         let record = TradeRecord {
@@ -1087,16 +3019,67 @@ async fn handle_event(
             our_price: our_price_opt,
             our_usd: our_usd_opt,
             fill_pct: fill_pct_opt,
-            status: trade_status_str,
-            latency_ms: None, // Could be added with timing instrumentation
+            status: trade_status_str.clone(),
+            latency_ms: Some(latency_ms),
             is_live,
-            aggregation_count: None, // TODO: Set from aggregator when Phase 3 Step 3.2 integration complete
-            aggregation_window_ms: None, // TODO: Set from aggregator when Phase 3 Step 3.2 integration complete
+            aggregation_count: aggregation.as_ref().map(|(count, _, _)| *count),
+            aggregation_window_ms: aggregation.as_ref().map(|(_, window_ms, _)| *window_ms),
+            order_id: parse_order_id(&trade_status_str),
+            contributing_traders: aggregation.map(|(_, _, traders)| traders),
         };
 
         // Send to persistence worker (non-blocking)
         let _ = tx.send(record);
     }
+    timings.add(EventPhase::Db, db_start.elapsed());
+
+    // Post-trade book for the log: waiting lets the book settle after our fill so the logged
+    // levels reflect the market after the trade, but it delays the summary and CSV row by the
+    // same amount. The DB record above is already sent; 0 skips the fetch for the lowest latency.
+    let bests = if ctx.post_trade_book_delay.is_zero() {
+        None
+    } else {
+        tokio::time::sleep(ctx.post_trade_book_delay).await;
+        let book_start = std::time::Instant::now();
+        let book = fetch_best_book(&ctx.endpoints.clob, &evt.order.clob_token_id, &evt.order.order_type, http_client).await;
+        timings.add(EventPhase::Book, book_start.elapsed());
+        book
+    };
+    let ((bp, bs), (sp, ss)) = bests.unwrap_or_else(|| (("N/A".into(), "N/A".into()), ("N/A".into(), "N/A".into())));
+    let is_live_bool = is_live.unwrap_or(false);
+
+    // Usually cached by the is_live lookup; otherwise one Gamma call, after the order is out
+    let market_start = std::time::Instant::now();
+    let market = match market_cache::get_market_name(&evt.order.clob_token_id) {
+        Some(name) => Some(name),
+        None => fetch_market_name(&ctx.endpoints.gamma, &evt.order.clob_token_id, http_client).await,
+    };
+    timings.add(EventPhase::Book, market_start.elapsed());
+
+    let market_tag = if tennis_markets::get_tennis_token_buffer(&evt.order.clob_token_id) > 0.0 {
+        Some("TENNIS")
+    } else if soccer_markets::get_soccer_token_buffer(&evt.order.clob_token_id) > 0.0 {
+        Some("SOCCER")
+    } else {
+        None
+    };
+
+    logging::emit(&TradeSummaryLog {
+        block: evt.block_number,
+        token_id: evt.order.clob_token_id.to_string(),
+        side: evt.order.order_type.clone(),
+        whale_shares: evt.order.shares,
+        whale_usd: evt.order.usd_value,
+        fill_pct: fill_pct_opt,
+        status: trade_status_str.clone(),
+        status_detail: status.clone(),
+        latency_ms: Some(latency_ms),
+        best: (bp.clone(), bs.clone()),
+        second: (sp.clone(), ss.clone()),
+        is_live: is_live_bool,
+        market_tag,
+        market,
+    });
 
     let ts: DateTime<Utc> = Utc::now();
     let row = CSV_BUF.with(|buf| {
@@ -1115,10 +3098,75 @@ async fn handle_event(
             b.clone()
         })
     });
+    let csv_start = std::time::Instant::now();
     let _ = tokio::task::spawn_blocking(move || append_csv_row(row)).await;
-}
+    timings.add(EventPhase::Db, csv_start.elapsed());
 
-/// Parse the status string to extract execution details for database storage
+    if event_metrics::observe(timings) {
+        logging::emit(&SlowEventLog {
+            token_id: evt.order.clob_token_id.to_string(),
+            side: evt.order.order_type.clone(),
+            status: trade_status_str,
+            timings,
+            threshold_ms: event_metrics::slow_threshold().as_millis() as u64,
+        });
+    }
+}
+
+/// Push executed and risk-blocked outcomes to notification sinks (never blocks)
+fn notify_trade(
+    notifier: &Notifiers,
+    evt: &ParsedEvent,
+    status_category: &str,
+    trade_status: TradeStatus,
+    our_shares: Option<f64>,
+    our_price: Option<f64>,
+    fill_pct: Option<f64>,
+) {
+    if notifier.is_empty() { return; }
+
+    let status = if trade_status == TradeStatus::Partial { "PARTIAL" } else { status_category };
+    if !should_notify(status) { return; }
+
+    let trader_label = if evt.trader_label.is_empty() {
+        evt.trader_address.chars().take(10).collect()
+    } else {
+        evt.trader_label.clone()
+    };
+
+    notifier.notify(&TradeNotification {
+        trader_label,
+        side: if evt.order.order_type.starts_with("BUY") { "BUY".to_string() } else { "SELL".to_string() },
+        shares: our_shares.unwrap_or(evt.order.shares),
+        price: our_price.unwrap_or(evt.order.price_per_share),
+        fill_pct,
+        status: status.to_string(),
+    });
+}
+
+/// Trader-stats outcome of a `parse_status_for_db` category: SUCCESS fills below
+/// `success_fill_pct` percent (SUCCESS_FILL_PCT) are Partial; exactly at it is Success
+fn classify_trade_status(status_category: &str, fill_pct: Option<f64>, success_fill_pct: f64) -> TradeStatus {
+    if status_category == "SUCCESS" {
+        match fill_pct {
+            Some(pct) if pct < success_fill_pct => TradeStatus::Partial,
+            _ => TradeStatus::Success,
+        }
+    } else if status_category.starts_with("SKIPPED")
+        || status_category == "DRY_RUN"
+        || status_category == POST_ONLY_STATUS
+        || status_category == EXIT_RESTING_STATUS
+        || status_category == OBSERVED_STATUS
+        || status_category == AGG_DEFERRED_STATUS
+        || status_category == DEFERRED_RATE_LIMIT
+    {
+        TradeStatus::Skipped
+    } else {
+        TradeStatus::Failed
+    }
+}
+
+/// Parse the status string to extract execution details for database storage
 /// Returns (our_shares, our_price, our_usd, fill_pct, status_category)
 fn parse_status_for_db(status: &str) -> (Option<f64>, Option<f64>, Option<f64>, Option<f64>, String) {
     // Status contains ANSI color codes - strip them for parsing
@@ -1142,12 +3190,36 @@ fn parse_status_for_db(status: &str) -> (Option<f64>, Option<f64>, Option<f64>,
     if clean_status.starts_with("RISK_BLOCKED") {
         return (None, None, None, None, "RISK_BLOCKED".to_string());
     }
+    if clean_status.starts_with("CIRCUIT_OPEN") {
+        return (None, None, None, None, "CIRCUIT_OPEN".to_string());
+    }
     if clean_status.starts_with("EXEC_FAIL") || clean_status.starts_with("FAILED") {
         return (None, None, None, None, "FAILED".to_string());
     }
     if clean_status.starts_with("MOCK") {
         return (None, None, None, None, "MOCK".to_string());
     }
+    // Resting, nothing filled yet (fills arrive as separate GTD rows)
+    if clean_status.starts_with(POST_ONLY_STATUS) {
+        return (None, None, None, None, POST_ONLY_STATUS.to_string());
+    }
+    if clean_status.starts_with(EXIT_RESTING_STATUS) {
+        return (None, None, None, None, EXIT_RESTING_STATUS.to_string());
+    }
+    if clean_status == OBSERVED_STATUS {
+        return (None, None, None, None, OBSERVED_STATUS.to_string());
+    }
+    // Not submitted (yet): no order, nothing filled
+    if clean_status == AGG_DEFERRED_STATUS {
+        return (None, None, None, None, AGG_DEFERRED_STATUS.to_string());
+    }
+    if clean_status.starts_with(DEFERRED_RATE_LIMIT) {
+        return (None, None, None, None, DEFERRED_RATE_LIMIT.to_string());
+    }
+    // Nothing was bought, so no our_shares (would otherwise show up as a position)
+    if clean_status.starts_with("WOULD_SUBMIT") {
+        return (None, None, None, None, "DRY_RUN".to_string());
+    }
     if clean_status.contains("QUEUE_ERR") || clean_status.contains("WORKER") {
         return (None, None, None, None, "ERROR".to_string());
     }
@@ -1156,22 +3228,22 @@ fn parse_status_for_db(status: &str) -> (Option<f64>, Option<f64>, Option<f64>,
     (None, None, None, None, clean_status.chars().take(20).collect())
 }
 
-/// Strip ANSI escape codes from a string
-fn strip_ansi_codes(s: &str) -> String {
-    let mut result = String::with_capacity(s.len());
-    let mut in_escape = false;
-    for c in s.chars() {
-        if c == '\x1b' {
-            in_escape = true;
-        } else if in_escape {
-            if c == 'm' {
-                in_escape = false;
-            }
-        } else {
-            result.push(c);
-        }
-    }
-    result
+/// Exchange order ID from a status string ("... | order 0xabc | ..."), as added by process_order
+fn parse_order_id(status: &str) -> Option<String> {
+    strip_ansi_codes(status)
+        .split('|')
+        .find_map(|part| part.trim().strip_prefix("order ").map(|id| id.trim().to_string()))
+        .filter(|id| !id.is_empty())
+}
+
+/// Exchange order ID of a submitted order (None if the exchange didn't return one)
+fn order_id_of(resp: &OrderResponse) -> Option<&str> {
+    Some(resp.order_id.as_str()).filter(|id| !id.is_empty())
+}
+
+/// Exchange order ID from a POST /order response body
+fn body_order_id(body: &str) -> Option<String> {
+    serde_json::from_str::<OrderResponse>(body).ok().and_then(|r| order_id_of(&r).map(String::from))
 }
 
 /// Parse fill details from status string
@@ -1199,6 +3271,10 @@ fn parse_fill_details(status: &str) -> Option<(f64, f64, f64)> {
                     let filled: f64 = filled_str.parse().ok()?;
                     let requested: f64 = requested_str.parse().ok()?;
                     let price: f64 = price_str.split_whitespace().next()?.parse().ok()?;
+                    // "NaN" and "inf" parse too; never record them
+                    if !(filled.is_finite() && requested.is_finite() && price.is_finite()) {
+                        return None;
+                    }
 
                     return Some((filled, requested, price));
                 }
@@ -1211,32 +3287,20 @@ fn parse_fill_details(status: &str) -> Option<(f64, f64, f64)> {
 /// Record an aggregated trade result to CSV and database
 /// This is called by the background aggregation flush task after executing trades
 async fn record_aggregated_trade(
-    evt: &ParsedEvent,
     status: &str,
     is_live: Option<bool>,
     trade_tx: &Option<mpsc::UnboundedSender<TradeRecord>>,
     trader_manager: &Arc<Mutex<TraderManager>>,
-    aggregation_count: usize,
+    notifier: &Notifiers,
+    aggregated: &AggregatedTrade,
+    success_fill_pct: f64,
 ) {
+    let evt = &aggregated.to_parsed_event();
+    let aggregation_count = aggregated.trade_count;
     // Parse status to extract execution details
     let (our_shares_opt, our_price_opt, our_usd_opt, fill_pct_opt, trade_status_str) = parse_status_for_db(status);
 
-    // Determine TradeStatus enum from status string
-    let trade_status = if trade_status_str == "SUCCESS" {
-        if let Some(fill_pct) = fill_pct_opt {
-            if fill_pct >= 90.0 {
-                TradeStatus::Success
-            } else {
-                TradeStatus::Partial
-            }
-        } else {
-            TradeStatus::Success
-        }
-    } else if trade_status_str.starts_with("SKIPPED") {
-        TradeStatus::Skipped
-    } else {
-        TradeStatus::Failed
-    };
+    let trade_status = classify_trade_status(&trade_status_str, fill_pct_opt, success_fill_pct);
 
     // Record trade in trader manager
     let usd_amount = our_usd_opt.unwrap_or(0.0);
@@ -1245,6 +3309,8 @@ async fn record_aggregated_trade(
         manager.record_trade(&evt.trader_address, usd_amount, trade_status);
     }
 
+    notify_trade(notifier, evt, &trade_status_str, trade_status, our_shares_opt, our_price_opt, fill_pct_opt);
+
     // Record trade to database if persistence is enabled
     if let Some(tx) = trade_tx {
         let record = TradeRecord {
@@ -1265,7 +3331,9 @@ async fn record_aggregated_trade(
             latency_ms: None,
             is_live,
             aggregation_count: Some(aggregation_count as u32),
-            aggregation_window_ms: None,
+            aggregation_window_ms: Some(aggregated.window_elapsed.as_millis() as u64),
+            order_id: parse_order_id(&trade_status_str),
+            contributing_traders: Some(aggregated.traders.clone()),
         };
 
         // Send to persistence worker (non-blocking)
@@ -1309,33 +3377,23 @@ async fn record_aggregated_trade(
 async fn resubmit_worker(
     mut rx: mpsc::UnboundedReceiver<ResubmitRequest>,
     client: Arc<RustClobClient>,
-    creds: Arc<PreparedCreds>,
+    creds: Arc<SharedCreds>,
+    gtd_recorder: GtdFillRecorder,
+    policy: ResubmitPolicy,
+    fee_rate_bps: Option<i64>,
 ) {
     println!("🔄 Resubmitter worker started");
 
     while let Some(req) = rx.recv().await {
-        let max_attempts = get_max_resubmit_attempts(req.whale_shares);
+        let max_attempts = policy.max_attempts(req.whale_shares);
         let is_last_attempt = req.attempt >= max_attempts;
+        let (new_price, abort) = next_resubmit_price(&req, &policy);
 
-        // Calculate increment: chase only if should_increment_price returns true
-        let increment = if should_increment_price(req.whale_shares, req.attempt) {
-            RESUBMIT_PRICE_INCREMENT
-        } else {
-            0.0  // Flat retry
-        };
-        let new_price = if req.side_is_buy {
-            (req.failed_price + increment).min(0.99)
-        } else {
-            (req.failed_price - increment).max(0.01)
-        };
-
-        // Check if we've exceeded max buffer (skip check for GTD - last attempt always goes through)
-        if !is_last_attempt && req.side_is_buy && new_price > req.max_price {
-            let fill_pct = if req.original_size > 0.0 { (req.cumulative_filled / req.original_size) * 100.0 } else { 0.0 };
-            println!(
-                "🔄 Resubmit ABORT: attempt {} price {:.2} > max {:.2} | filled {:.2}/{:.2} ({:.0}%)",
-                req.attempt, new_price, req.max_price, req.cumulative_filled, req.original_size, fill_pct
-            );
+        // Exceeded max buffer (never for GTD - last attempt always goes through)
+        if abort {
+            let mut log = ResubmitLog::new(&req, false, ResubmitOutcome::Abort, max_attempts);
+            log.price = new_price;
+            logging::emit(&log);
             continue;
         }
 
@@ -1346,35 +3404,44 @@ async fn resubmit_worker(
         let attempt = req.attempt;
         let whale_price = req.whale_price;
         let max_price = req.max_price;
-        let is_live = req.is_live;
+        let gtd_expiry_secs = gtd_recorder.gtd_expiry.secs(req.is_live);
+        let placed = Arc::clone(&gtd_recorder.placed_gtd_orders);
 
         // Submit order: FAK for early attempts, GTD with expiry for last attempt
         let result = tokio::task::spawn_blocking(move || {
-            submit_resubmit_order_sync(&client_clone, &creds_clone, &token_id, new_price, size, is_live, is_last_attempt, max_price)
+            submit_resubmit_order_sync(&client_clone, &creds_clone, &placed, &token_id, new_price, size, gtd_expiry_secs, fee_rate_bps, is_last_attempt, max_price)
         }).await;
 
         match result {
-            Ok(Ok((true, _, filled_this_attempt))) => {
+            Ok(Ok((true, body, filled_this_attempt))) => {
+                let order_id = body_order_id(&body);
                 if is_last_attempt {
-                    // GTD order placed on book - we don't know fill amount yet
-                    println!(
-                        "\x1b[32m🔄 Resubmit GTD SUBMITTED: attempt {} @ ≤{:.2} | size {:.2} | prior filled {:.2}/{:.2}\x1b[0m",
-                        attempt, max_price, size, req.cumulative_filled, req.original_size
-                    );
+                    // GTD order placed on book - the poller records the fill once known
+                    let mut log = ResubmitLog::new(&req, false, ResubmitOutcome::GtdSubmitted, max_attempts);
+                    log.order_id = order_id;
+                    logging::emit(&log);
+                    spawn_gtd_fill_poller(&client, &creds, &gtd_recorder, &req, &body, GtdFillLabel::default());
                 } else {
                     // FAK order - check if partial fill
                     let total_filled = req.cumulative_filled + filled_this_attempt;
-                    let fill_pct = if req.original_size > 0.0 { (total_filled / req.original_size) * 100.0 } else { 0.0 };
                     let remaining = size - filled_this_attempt;
+                    let outcome = if remaining > 1.0 && filled_this_attempt > 0.0 {
+                        ResubmitOutcome::Partial
+                    } else {
+                        ResubmitOutcome::Success
+                    };
+                    let mut log = ResubmitLog::new(&req, false, outcome, max_attempts);
+                    log.price = new_price;
+                    log.filled = total_filled;
+                    log.remaining = remaining;
+                    log.order_id = order_id;
+                    logging::emit(&log);
 
                     // If partial fill, continue with remaining size
-                    if remaining > 1.0 && filled_this_attempt > 0.0 {
-                        println!(
-                            "\x1b[33m🔄 Resubmit PARTIAL: attempt {} @ {:.2} | filled {:.2}/{:.2} ({:.0}%) | remaining {:.2}\x1b[0m",
-                            attempt, new_price, total_filled, req.original_size, fill_pct, remaining
-                        );
+                    if outcome == ResubmitOutcome::Partial {
                         let next_req = ResubmitRequest {
                             token_id: req.token_id,
+                            trader_address: req.trader_address,
                             whale_price,
                             failed_price: new_price,
                             size: remaining,
@@ -1386,20 +3453,22 @@ async fn resubmit_worker(
                             original_size: req.original_size,
                             is_live: req.is_live,
                         };
-                        let _ = process_resubmit_chain(&client, &creds, next_req).await;
-                    } else {
-                        println!(
-                            "\x1b[32m🔄 Resubmit SUCCESS: attempt {} @ {:.2} | filled {:.2}/{:.2} ({:.0}%)\x1b[0m",
-                            attempt, new_price, total_filled, req.original_size, fill_pct
-                        );
+                        let _ = process_resubmit_chain(&client, &creds, &gtd_recorder, &policy, fee_rate_bps, next_req).await;
                     }
                 }
             }
             Ok(Ok((false, body, filled_this_attempt))) => {
                 if attempt < max_attempts {
+                    let next_increment = policy.increment(req.whale_shares, attempt + 1);
+                    let mut log = ResubmitLog::new(&req, false, ResubmitOutcome::Retry, max_attempts);
+                    log.price = new_price + next_increment;
+                    log.filled = req.cumulative_filled + filled_this_attempt;
+                    logging::emit(&log);
+
                     // Re-queue with updated price
                     let next_req = ResubmitRequest {
                         token_id: req.token_id,
+                        trader_address: req.trader_address,
                         whale_price,
                         failed_price: new_price,
                         size: req.size,
@@ -1411,46 +3480,35 @@ async fn resubmit_worker(
                         original_size: req.original_size,
                         is_live: req.is_live,
                     };
-                    let next_increment = if should_increment_price(req.whale_shares, attempt + 1) {
-                        RESUBMIT_PRICE_INCREMENT
-                    } else {
-                        0.0
-                    };
-                    println!(
-                        "🔄 Resubmit attempt {} failed (FAK), retrying @ {:.2} (max: {})",
-                        attempt, new_price + next_increment, max_attempts
-                    );
                     if req.whale_shares < 1000.0 {
                         tokio::time::sleep(Duration::from_millis(50)).await;
                     }
                     let _ = process_resubmit_chain(
                         &client,
                         &creds,
+                        &gtd_recorder,
+                        &policy,
+                        fee_rate_bps,
                         next_req,
                     ).await;
                 } else {
-                    let total_filled = req.cumulative_filled + filled_this_attempt;
-                    let fill_pct = if req.original_size > 0.0 { (total_filled / req.original_size) * 100.0 } else { 0.0 };
                     let error_msg = if DEBUG_FULL_ERRORS { body.clone() } else { body.chars().take(80).collect::<String>() };
-                    println!(
-                        "🔄 Resubmit FAILED: attempt {} @ {:.2} | filled {:.2}/{:.2} ({:.0}%) | {}",
-                        attempt, new_price, total_filled, req.original_size, fill_pct, error_msg
-                    );
+                    let mut log = ResubmitLog::new(&req, false, ResubmitOutcome::Failed, max_attempts);
+                    log.price = new_price;
+                    log.filled = req.cumulative_filled + filled_this_attempt;
+                    log.detail = Some(error_msg);
+                    logging::emit(&log);
                 }
             }
             Ok(Err(e)) => {
-                let fill_pct = if req.original_size > 0.0 { (req.cumulative_filled / req.original_size) * 100.0 } else { 0.0 };
-                println!(
-                    "🔄 Resubmit ERROR: attempt {} | filled {:.2}/{:.2} ({:.0}%) | {}",
-                    attempt, req.cumulative_filled, req.original_size, fill_pct, e
-                );
+                let mut log = ResubmitLog::new(&req, false, ResubmitOutcome::Error, max_attempts);
+                log.detail = Some(e.to_string());
+                logging::emit(&log);
             }
             Err(e) => {
-                let fill_pct = if req.original_size > 0.0 { (req.cumulative_filled / req.original_size) * 100.0 } else { 0.0 };
-                println!(
-                    "🔄 Resubmit TASK ERROR: filled {:.2}/{:.2} ({:.0}%) | {}",
-                    req.cumulative_filled, req.original_size, fill_pct, e
-                );
+                let mut log = ResubmitLog::new(&req, false, ResubmitOutcome::Error, max_attempts);
+                log.detail = Some(format!("task error: {}", e));
+                logging::emit(&log);
             }
         }
     }
@@ -1458,33 +3516,23 @@ async fn resubmit_worker(
 
 async fn process_resubmit_chain(
     client: &Arc<RustClobClient>,
-    creds: &Arc<PreparedCreds>,
+    creds: &Arc<SharedCreds>,
+    gtd_recorder: &GtdFillRecorder,
+    policy: &ResubmitPolicy,
+    fee_rate_bps: Option<i64>,
     mut req: ResubmitRequest,
 ) {
-    let max_attempts = get_max_resubmit_attempts(req.whale_shares);
+    let max_attempts = policy.max_attempts(req.whale_shares);
 
     while req.attempt <= max_attempts {
         let is_last_attempt = req.attempt >= max_attempts;
+        let (new_price, abort) = next_resubmit_price(&req, policy);
 
-        // Calculate increment: chase only if should_increment_price returns true
-        let increment = if should_increment_price(req.whale_shares, req.attempt) {
-            RESUBMIT_PRICE_INCREMENT
-        } else {
-            0.0  // Flat retry
-        };
-        let new_price = if req.side_is_buy {
-            (req.failed_price + increment).min(0.99)
-        } else {
-            (req.failed_price - increment).max(0.01)
-        };
-
-        // Check if we've exceeded max buffer (skip check for GTD - last attempt always goes through)
-        if !is_last_attempt && req.side_is_buy && new_price > req.max_price {
-            let fill_pct = if req.original_size > 0.0 { (req.cumulative_filled / req.original_size) * 100.0 } else { 0.0 };
-            println!(
-                "🔄 Resubmit chain ABORT: attempt {} price {:.2} > max {:.2} | filled {:.2}/{:.2} ({:.0}%)",
-                req.attempt, new_price, req.max_price, req.cumulative_filled, req.original_size, fill_pct
-            );
+        // Exceeded max buffer (never for GTD - last attempt always goes through)
+        if abort {
+            let mut log = ResubmitLog::new(&req, true, ResubmitOutcome::Abort, max_attempts);
+            log.price = new_price;
+            logging::emit(&log);
             return;
         }
 
@@ -1493,45 +3541,47 @@ async fn process_resubmit_chain(
         let token_id = req.token_id.clone();
         let size = req.size;
         let attempt = req.attempt;
-        let is_live = req.is_live;
+        let gtd_expiry_secs = gtd_recorder.gtd_expiry.secs(req.is_live);
         let max_price = req.max_price;
+        let placed = Arc::clone(&gtd_recorder.placed_gtd_orders);
 
         // Submit order: FAK for early attempts, GTD with expiry for last attempt
         let result = tokio::task::spawn_blocking(move || {
-            submit_resubmit_order_sync(&client_clone, &creds_clone, &token_id, new_price, size, is_live, is_last_attempt, max_price)
+            submit_resubmit_order_sync(&client_clone, &creds_clone, &placed, &token_id, new_price, size, gtd_expiry_secs, fee_rate_bps, is_last_attempt, max_price)
         }).await;
 
         match result {
-            Ok(Ok((true, _, filled_this_attempt))) => {
+            Ok(Ok((true, body, filled_this_attempt))) => {
+                let order_id = body_order_id(&body);
                 if is_last_attempt {
-                    // GTD order placed on book - we don't know fill amount yet
-                    println!(
-                        "\x1b[32m🔄 Resubmit chain GTD SUBMITTED: attempt {} @ ≤{:.2} | size {:.2} | prior filled {:.2}/{:.2}\x1b[0m",
-                        attempt, req.max_price, req.size, req.cumulative_filled, req.original_size
-                    );
+                    // GTD order placed on book - the poller records the fill once known
+                    let mut log = ResubmitLog::new(&req, true, ResubmitOutcome::GtdSubmitted, max_attempts);
+                    log.order_id = order_id;
+                    logging::emit(&log);
+                    spawn_gtd_fill_poller(client, creds, gtd_recorder, &req, &body, GtdFillLabel::default());
                     return;
                 } else {
                     // FAK order - check if partial fill
                     let total_filled = req.cumulative_filled + filled_this_attempt;
-                    let fill_pct = if req.original_size > 0.0 { (total_filled / req.original_size) * 100.0 } else { 0.0 };
                     let remaining = req.size - filled_this_attempt;
+                    let partial = remaining > 1.0 && filled_this_attempt > 0.0;
+
+                    let outcome = if partial { ResubmitOutcome::Partial } else { ResubmitOutcome::Success };
+                    let mut log = ResubmitLog::new(&req, true, outcome, max_attempts);
+                    log.price = new_price;
+                    log.filled = total_filled;
+                    log.remaining = remaining;
+                    log.order_id = order_id;
+                    logging::emit(&log);
 
                     // If partial fill, continue with remaining size
-                    if remaining > 1.0 && filled_this_attempt > 0.0 {
-                        println!(
-                            "\x1b[33m🔄 Resubmit chain PARTIAL: attempt {} @ {:.2} | filled {:.2}/{:.2} ({:.0}%) | remaining {:.2}\x1b[0m",
-                            attempt, new_price, total_filled, req.original_size, fill_pct, remaining
-                        );
+                    if partial {
                         req.cumulative_filled = total_filled;
                         req.size = remaining;
                         req.failed_price = new_price;
                         req.attempt += 1;
                         continue;
                     } else {
-                        println!(
-                            "\x1b[32m🔄 Resubmit chain SUCCESS: attempt {} @ {:.2} | filled {:.2}/{:.2} ({:.0}%)\x1b[0m",
-                            attempt, new_price, total_filled, req.original_size, fill_pct
-                        );
                         return;
                     }
                 }
@@ -1539,12 +3589,10 @@ async fn process_resubmit_chain(
             Ok(Ok((false, body, filled_this_attempt))) if body.contains("FAK") && attempt < max_attempts => {
                 // FAK failed (no liquidity), retry with next attempt
                 let next_attempt = attempt + 1;
-                let next_is_last = next_attempt >= max_attempts;
-                let next_type = if next_is_last { "GTD" } else { "FAK" };
-                println!(
-                    "🔄 Resubmit chain: attempt {} FAK no match, trying {} @ {:.2} (attempt {})",
-                    attempt, next_type, new_price, next_attempt
-                );
+                let mut log = ResubmitLog::new(&req, true, ResubmitOutcome::Retry, max_attempts);
+                log.price = new_price;
+                log.filled = req.cumulative_filled + filled_this_attempt;
+                logging::emit(&log);
                 req.cumulative_filled += filled_this_attempt;
                 req.failed_price = new_price;
                 req.attempt = next_attempt;
@@ -1555,49 +3603,53 @@ async fn process_resubmit_chain(
                 continue;
             }
             Ok(Ok((false, body, filled_this_attempt))) => {
-                let total_filled = req.cumulative_filled + filled_this_attempt;
-                let fill_pct = if req.original_size > 0.0 { (total_filled / req.original_size) * 100.0 } else { 0.0 };
-                let fill_color = get_fill_color(total_filled, req.original_size);
-                let reset = "\x1b[0m";
                 let error_msg = if DEBUG_FULL_ERRORS { body.clone() } else { body.chars().take(80).collect::<String>() };
-                println!(
-                    "🔄 Resubmit chain FAILED: attempt {}/{} @ {:.2} | {}filled {:.2}/{:.2} ({:.0}%){} | {}",
-                    attempt, max_attempts, new_price, fill_color, total_filled, req.original_size, fill_pct, reset, error_msg
-                );
+                let mut log = ResubmitLog::new(&req, true, ResubmitOutcome::Failed, max_attempts);
+                log.price = new_price;
+                log.filled = req.cumulative_filled + filled_this_attempt;
+                log.detail = Some(error_msg);
+                logging::emit(&log);
                 return;
             }
             Ok(Err(e)) => {
-                let fill_pct = if req.original_size > 0.0 { (req.cumulative_filled / req.original_size) * 100.0 } else { 0.0 };
-                let fill_color = get_fill_color(req.cumulative_filled, req.original_size);
-                let reset = "\x1b[0m";
-                println!(
-                    "🔄 Resubmit chain ERROR: attempt {} | {}filled {:.2}/{:.2} ({:.0}%){} | {}",
-                    attempt, fill_color, req.cumulative_filled, req.original_size, fill_pct, reset, e
-                );
+                let mut log = ResubmitLog::new(&req, true, ResubmitOutcome::Error, max_attempts);
+                log.detail = Some(e.to_string());
+                logging::emit(&log);
                 return;
             }
             Err(e) => {
-                let fill_pct = if req.original_size > 0.0 { (req.cumulative_filled / req.original_size) * 100.0 } else { 0.0 };
-                let fill_color = get_fill_color(req.cumulative_filled, req.original_size);
-                let reset = "\x1b[0m";
-                println!(
-                    "🔄 Resubmit chain TASK ERROR: {}filled {:.2}/{:.2} ({:.0}%){} | {}",
-                    fill_color, req.cumulative_filled, req.original_size, fill_pct, reset, e
-                );
+                let mut log = ResubmitLog::new(&req, true, ResubmitOutcome::Error, max_attempts);
+                log.detail = Some(format!("task error: {}", e));
+                logging::emit(&log);
                 return;
             }
         }
     }
 }
 
+/// Price for the next resubmit of `req` and whether it aborts at the price ceiling
+/// Chase attempts move the price by the policy increment; the last attempt (GTD) never aborts
+fn next_resubmit_price(req: &ResubmitRequest, policy: &ResubmitPolicy) -> (f64, bool) {
+    let increment = policy.increment(req.whale_shares, req.attempt);
+    let new_price = if req.side_is_buy {
+        (req.failed_price + increment).min(0.99)
+    } else {
+        (req.failed_price - increment).max(0.01)
+    };
+    let is_last_attempt = req.attempt >= policy.max_attempts(req.whale_shares);
+    (new_price, !is_last_attempt && req.side_is_buy && new_price > req.max_price)
+}
+
 /// Returns (success, body_text, filled_shares)
 fn submit_resubmit_order_sync(
     client: &RustClobClient,
-    creds: &PreparedCreds,
+    creds: &SharedCreds,
+    placed: &std::sync::Mutex<HashSet<String>>,
     token_id: &str,
     price: f64,
     size: f64,
-    is_live: bool,
+    gtd_expiry_secs: u64,
+    fee_rate_bps: Option<i64>,
     is_last_attempt: bool,
     max_price: f64,
 ) -> anyhow::Result<(bool, String, f64)> {
@@ -1607,14 +3659,13 @@ fn submit_resubmit_order_sync(
 
     // Only use GTD with expiry on the LAST attempt; earlier attempts use FAK
     let (expiration, order_type, final_price) = if is_last_attempt {
-        let expiry_secs = get_gtd_expiry_secs(is_live);
         let expiry_timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
-            .as_secs() + expiry_secs;
+            .as_secs() + gtd_expiry_secs;
 
         // For GTD, try to cross the spread by using min(max_price, best_ask)
-        let gtd_price = fetch_best_ask_sync(token_id)
+        let gtd_price = fetch_best_ask_sync(client.host(), token_id)
             .map(|best_ask| {
                 let crossed_price = best_ask.min(max_price);
                 if crossed_price > price {
@@ -1644,7 +3695,7 @@ fn submit_resubmit_order_sync(
         price: rounded_price,
         size: rounded_size,
         side: "BUY".into(),
-        fee_rate_bps: None,
+        fee_rate_bps,
         nonce: Some(0),
         expiration,
         taker: None,
@@ -1652,8 +3703,10 @@ fn submit_resubmit_order_sync(
     };
 
     let signed = client.create_order(args)?;
-    let body = signed.post_body(&creds.api_key, order_type);
-    let resp = client.post_order_fast(body, creds)?;
+    let resp = with_auth_retry(creds, response_status, || client.derive_api_key(0), |creds| {
+        let body = signed.post_body(&creds.api_key, order_type);
+        client.post_order_fast(body, creds)
+    })?;
 
     let status = resp.status();
     let body_text = resp.text().unwrap_or_default();
@@ -1661,9 +3714,17 @@ fn submit_resubmit_order_sync(
     // Parse filled amount from successful responses
     // GTD orders return taking_amount=0 since they're placed on book, not immediately filled
     // For GTD, return 0 - caller handles GTD success messaging separately
-    let filled_shares = if status.is_success() && order_type == "FAK" {
-        serde_json::from_str::<OrderResponse>(&body_text)
-            .ok()
+    let order_resp: Option<OrderResponse> = if status.is_success() {
+        serde_json::from_str(&body_text).ok()
+    } else {
+        None
+    };
+    if let Some(ref resp) = order_resp {
+        track_gtd_order(placed, order_type, resp);
+    }
+
+    let filled_shares = if order_type == "FAK" {
+        order_resp
             .and_then(|r| r.taking_amount.parse::<f64>().ok())
             .unwrap_or(0.0)
     } else {
@@ -1673,15 +3734,199 @@ fn submit_resubmit_order_sync(
     Ok((status.is_success(), body_text, filled_shares))
 }
 
-/// Fetch the best ask price from the order book (blocking/sync version)
+/// Remember a GTD order that is resting on the book (not immediately matched)
+fn track_gtd_order(placed: &std::sync::Mutex<HashSet<String>>, order_type: &str, resp: &OrderResponse) {
+    if order_type != "GTD" || !resp.success || resp.order_id.is_empty() || resp.status == "matched" {
+        return;
+    }
+    if let Ok(mut set) = placed.lock() {
+        set.insert(resp.order_id.clone());
+    }
+}
+
+/// Shared sinks for fills realized by resting GTD orders
+#[derive(Clone)]
+struct GtdFillRecorder {
+    trade_tx: Option<mpsc::UnboundedSender<TradeRecord>>,
+    trader_manager: Arc<Mutex<TraderManager>>,
+    pollers: GtdPollers,
+    /// Poll until the order's expiry (plus grace) has passed
+    gtd_expiry: GtdExpiry,
+    success_fill_pct: f64,
+    /// Resting GTD orders, each forgotten once its poller finishes
+    placed_gtd_orders: PlacedGtdOrders,
+}
+
+/// How a GTD fill is recorded: copies as SUCCESS for `req.trader_address`, exits under their
+/// own status for the traders whose lots they sell
+#[derive(Debug, Clone, Default)]
+struct GtdFillLabel {
+    /// STOP_LOSS, TAKE_PROFIT, ... for an exit or flatten order
+    exit_status: Option<&'static str>,
+    /// (trader, open shares) an exit sells, see `split_by_openers`
+    openers: Vec<(String, f64)>,
+}
+
+/// Follow a freshly placed GTD order until it fills, closes or outlives its expiry
+fn spawn_gtd_fill_poller(
+    client: &Arc<RustClobClient>,
+    creds: &Arc<SharedCreds>,
+    recorder: &GtdFillRecorder,
+    req: &ResubmitRequest,
+    body: &str,
+    label: GtdFillLabel,
+) {
+    let Some(order_id) = body_order_id(body) else { return };
+    let Some(permit) = recorder.pollers.try_acquire() else {
+        eprintln!("⚠️ GTD poller limit ({}) reached - fill for {} won't be recorded", MAX_GTD_POLLERS, order_id);
+        return;
+    };
+
+    let client = Arc::clone(client);
+    let creds = Arc::clone(creds);
+    let recorder = recorder.clone();
+    let req = req.clone();
+    let deadline = tokio::time::Instant::now()
+        + Duration::from_secs(recorder.gtd_expiry.secs(req.is_live))
+        + GTD_POLL_GRACE;
+
+    tokio::spawn(async move {
+        let _permit = permit;
+        let mut last_status: Option<GtdOrderStatus> = None;
+
+        while tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(GTD_POLL_INTERVAL).await;
+
+            let client_clone = Arc::clone(&client);
+            let creds_clone = Arc::clone(&creds);
+            let id = order_id.clone();
+            let fetched = tokio::task::spawn_blocking(move || -> Result<Option<GtdOrderStatus>> {
+                let resp = with_auth_retry(&creds_clone, response_status, || client_clone.derive_api_key(0), |c| {
+                    client_clone.get_order(&id, c)
+                })?;
+                Ok(parse_order_status(&resp.text().unwrap_or_default()))
+            }).await;
+
+            if let Ok(Ok(Some(status))) = fetched {
+                let is_final = status.is_final();
+                last_status = Some(status);
+                if is_final {
+                    break;
+                }
+            }
+        }
+
+        if let Ok(mut set) = recorder.placed_gtd_orders.lock() {
+            set.remove(&order_id);
+        }
+
+        match last_status {
+            Some(status) if status.size_matched > 0.0 => record_gtd_fill(&recorder, &req, &label, &order_id, &status).await,
+            Some(status) if status.state == GtdState::Open => {
+                println!("⌛ GTD {} still open after expiry, nothing matched", order_id);
+            }
+            Some(_) => println!("⌛ GTD {} closed unfilled", order_id),
+            None => eprintln!("⚠️ GTD {} status unavailable, fill not recorded", order_id),
+        }
+    });
+}
+
+/// Follow resting orders placed by the order worker (POST_ONLY copies, exits) until they fill or expire
+async fn gtd_watch_worker(
+    mut rx: mpsc::UnboundedReceiver<GtdWatch>,
+    client: Arc<RustClobClient>,
+    creds: Arc<SharedCreds>,
+    recorder: GtdFillRecorder,
+) {
+    while let Some((req, body, label)) = rx.recv().await {
+        spawn_gtd_fill_poller(&client, &creds, &recorder, &req, &body, label);
+    }
+}
+
+/// Record the realized fill of a GTD order in the trade DB and trader stats (copies only: exits
+/// aren't a trader's copy)
+async fn record_gtd_fill(recorder: &GtdFillRecorder, req: &ResubmitRequest, label: &GtdFillLabel, order_id: &str, status: &GtdOrderStatus) {
+    let price = if status.price > 0.0 { status.price } else { req.failed_price };
+    let usd = status.size_matched * price;
+    let fill_pct = if status.original_size > 0.0 {
+        status.size_matched / status.original_size * 100.0
+    } else {
+        100.0
+    };
+    let trade_status = classify_trade_status("SUCCESS", Some(fill_pct), recorder.success_fill_pct);
+
+    println!(
+        "✅ GTD {} filled {:.2}/{:.2} @ {:.2} (${:.2}, {:.0}%)",
+        order_id, status.size_matched, status.original_size, price, usd, fill_pct
+    );
+
+    if label.exit_status.is_none() {
+        let mut manager = recorder.trader_manager.lock().await;
+        manager.record_trade(&req.trader_address, usd, trade_status);
+    }
+
+    if let Some(tx) = &recorder.trade_tx {
+        let record = TradeRecord {
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            block_number: 0,
+            tx_hash: format!("GTD_{}", order_id),
+            trader_address: req.trader_address.clone(),
+            token_id: req.token_id.clone(),
+            side: if req.side_is_buy { "BUY".to_string() } else { "SELL".to_string() },
+            whale_shares: req.whale_shares,
+            whale_price: req.whale_price,
+            whale_usd: req.whale_shares * req.whale_price,
+            our_shares: Some(status.size_matched),
+            our_price: Some(price),
+            our_usd: Some(usd),
+            fill_pct: Some(fill_pct),
+            status: label.exit_status.unwrap_or("SUCCESS").to_string(),
+            latency_ms: None,
+            is_live: Some(req.is_live),
+            aggregation_count: None,
+            aggregation_window_ms: None,
+            order_id: Some(order_id.to_string()),
+            contributing_traders: None,
+        };
+        for record in split_by_openers(record, &label.openers) {
+            let _ = tx.send(record);
+        }
+    }
+}
+
+/// Cancel every GTD order placed during this session
+/// Returns the number of orders the exchange reports as cancelled
+fn cancel_all_open_orders(client: &RustClobClient, creds: &PreparedCreds, placed: &std::sync::Mutex<HashSet<String>>) -> Result<usize> {
+    let ids: Vec<String> = match placed.lock() {
+        Ok(mut set) => set.drain().collect(),
+        Err(_) => return Err(anyhow!("order tracker lock poisoned")),
+    };
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    let resp = client.cancel_orders(&ids, creds)?;
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(anyhow!("HTTP {}: {}", status, resp.text().unwrap_or_default()));
+    }
+
+    let parsed: CancelOrdersResponse = resp.json()?;
+    for (id, reason) in &parsed.not_canceled {
+        eprintln!("Warning: Order {} not cancelled: {}", id, reason);
+    }
+    Ok(parsed.canceled.len())
+}
+
+/// Fetch the best ask price from the order book at `clob_base` (blocking/sync version)
 /// Returns None if the book fetch fails or no asks are available
-fn fetch_best_ask_sync(token_id: &str) -> Option<f64> {
+fn fetch_best_ask_sync(clob_base: &str, token_id: &str) -> Option<f64> {
     let client = reqwest::blocking::Client::builder()
         .timeout(Duration::from_secs(2))
         .build()
         .ok()?;
 
-    let url = format!("{}/book?token_id={}", CLOB_API_BASE, token_id);
+    let url = format!("{}/book?token_id={}", clob_base, token_id);
     let resp = client.get(&url).send().ok()?;
     if !resp.status().is_success() {
         return None;
@@ -1698,23 +3943,45 @@ fn fetch_best_ask_sync(token_id: &str) -> Option<f64> {
         .min_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
 }
 
-async fn fetch_is_live(token_id: &str, client: &reqwest::Client) -> Option<bool> {
+async fn fetch_is_live(gamma_base: &str, token_id: &str, client: &reqwest::Client) -> Option<bool> {
     // Fetch market info to get slug
-    let market_url = format!("{}/markets?clob_token_ids={}", GAMMA_API_BASE, token_id);
+    let market_url = format!("{}/markets?clob_token_ids={}", gamma_base, token_id);
     let resp = client.get(&market_url).timeout(Duration::from_secs(2)).send().await.ok()?;
     let val: Value = resp.json().await.ok()?;
-    let slug = val.get(0)?.get("slug")?.as_str()?.to_string();
+    // Also caches negRisk so process_order can route or skip neg-risk markets without another lookup
+    let slug = market_cache::record_gamma_market(token_id, val.get(0)?)?;
 
     // Fetch live status from events API
-    let event_url = format!("{}/events/slug/{}", GAMMA_API_BASE, slug);
+    let event_url = format!("{}/events/slug/{}", gamma_base, slug);
     let resp = client.get(&event_url).timeout(Duration::from_secs(2)).send().await.ok()?;
     let val: Value = resp.json().await.ok()?;
 
     Some(val["live"].as_bool().unwrap_or(false))
 }
 
-async fn fetch_best_book(token_id: &str, order_type: &str, client: &reqwest::Client) -> Option<((String, String), (String, String))> {
-    let url = format!("{}/book?token_id={}", CLOB_API_BASE, token_id);
+/// Cache a token's market end time and closed / accepting-orders flags from Gamma
+/// On failure nothing is cached and the BUY goes ahead unchecked
+async fn fetch_market_status(gamma_base: &str, token_id: &str, client: &reqwest::Client) {
+    let url = format!("{}/markets?clob_token_ids={}", gamma_base, token_id);
+    let Ok(resp) = client.get(&url).timeout(Duration::from_secs(2)).send().await else { return };
+    let Ok(val) = resp.json::<Value>().await else { return };
+    if let Some(market) = val.get(0) {
+        market_cache::record_gamma_market(token_id, market);
+    }
+}
+
+/// Look up a token's market title and outcome on Gamma, caching it with the slug and neg_risk
+async fn fetch_market_name(gamma_base: &str, token_id: &str, client: &reqwest::Client) -> Option<MarketMetadata> {
+    let url = format!("{}/markets?clob_token_ids={}", gamma_base, token_id);
+    let resp = client.get(&url).timeout(Duration::from_secs(2)).send().await.ok()?;
+    let val: Value = resp.json().await.ok()?;
+    let market = val.get(0)?;
+    market_cache::record_gamma_market(token_id, market);
+    MarketMetadata::from_gamma_market(token_id, market)
+}
+
+async fn fetch_best_book(clob_base: &str, token_id: &str, order_type: &str, client: &reqwest::Client) -> Option<((String, String), (String, String))> {
+    let url = format!("{}/book?token_id={}", clob_base, token_id);
     let resp = client.get(&url).timeout(BOOK_REQ_TIMEOUT).send().await.ok()?;
     if !resp.status().is_success() { return None; }
     
@@ -1764,197 +4031,12 @@ async fn fetch_best_book(token_id: &str, order_type: &str, client: &reqwest::Cli
     Some(((best_price, best_size), (second_price, second_size)))
 }
 
-// ============================================================================
-// Event Parsing
-// ============================================================================
-
-fn parse_event(message: String, traders: Option<&TradersConfig>) -> Option<ParsedEvent> {
-    let msg: WsMessage = serde_json::from_str(&message).ok()?;
-    let result = msg.params?.result?;
-
-    // just to double check!
-    if result.topics.len() < 3 { return None; }
-
-    // Extract trader address from topics[2]
-    // Format: 0x000000000000000000000000{40-char-address}
-    let trader_topic = result.topics.get(2)?;
-    let trader_address = extract_address_from_topic(trader_topic)?;
-
-    // Look up trader in config (if provided)
-    // Returns (label, min_shares) tuple
-    let (trader_label, trader_min_shares) = if let Some(traders_cfg) = traders {
-        // Try to find trader by topic hex (case-insensitive for robustness)
-        // WebSocket may return different case than our stored topics
-        let topic_lower = trader_topic.to_lowercase();
-        if let Some(trader_cfg) = traders_cfg.get_by_topic(&topic_lower) {
-            if !trader_cfg.enabled {
-                return None; // Skip disabled traders
-            }
-            (trader_cfg.label.clone(), trader_cfg.min_shares)
-        } else {
-            // Debug: Log when we receive an event but don't match a trader
-            // This helps diagnose subscription/filtering issues
-            if std::env::var("DEBUG_EVENTS").is_ok() {
-                eprintln!("🔍 Event from unknown trader: {} (addr: {})", trader_topic, trader_address);
-            }
-            // Trader not in our config - skip
-            return None;
-        }
-    } else {
-        // No traders config provided (legacy mode or tests)
-        // Fall back to checking TARGET_TOPIC_HEX
-        let has_target = trader_topic.eq_ignore_ascii_case(TARGET_TOPIC_HEX.as_str());
-        if !has_target { return None; }
-        // Legacy mode uses global MIN_WHALE_SHARES_TO_COPY
-        (String::new(), MIN_WHALE_SHARES_TO_COPY)
-    };
-
-    let hex_data = &result.data;
-    if hex_data.len() < 2 + 64 * 4 { return None; }
-
-    let (maker_id, maker_bytes) = parse_u256_hex_slice_with_bytes(hex_data, 2, 66)?;
-    let (taker_id, taker_bytes) = parse_u256_hex_slice_with_bytes(hex_data, 66, 130)?;
-
-    let (clob_id, token_bytes, maker_amt, taker_amt, base_type) =
-        if maker_id.is_zero() && !taker_id.is_zero() {
-            let m = parse_u256_hex_slice(hex_data, 130, 194)?;
-            let t = parse_u256_hex_slice(hex_data, 194, 258)?;
-            (taker_id, taker_bytes, m, t, "BUY")
-        } else if taker_id.is_zero() && !maker_id.is_zero() {
-            let m = parse_u256_hex_slice(hex_data, 130, 194)?;
-            let t = parse_u256_hex_slice(hex_data, 194, 258)?;
-            (maker_id, maker_bytes, m, t, "SELL")
-        } else {
-            return None;
-        };
-
-    let shares = if base_type == "BUY" { u256_to_f64(&taker_amt)? } else { u256_to_f64(&maker_amt)? } / 1e6;
-    if shares <= 0.0 { return None; }
-    
-    let usd = if base_type == "BUY" { u256_to_f64(&maker_amt)? } else { u256_to_f64(&taker_amt)? } / 1e6;
-    let price = usd / shares;
-    
-    let mut order_type = base_type.to_string();
-    if result.topics[0].eq_ignore_ascii_case(ORDERS_FILLED_EVENT_SIGNATURE) {
-        order_type.push_str("_FILL");
-    }
-
-    Some(ParsedEvent {
-        block_number: result.block_number.as_deref()
-            .and_then(|s| u64::from_str_radix(s.trim_start_matches("0x"), 16).ok())
-            .unwrap_or_default(),
-        tx_hash: result.transaction_hash.unwrap_or_default(),
-        trader_address,
-        trader_label,
-        trader_min_shares,
-        order: OrderInfo {
-            order_type,
-            clob_token_id: u256_to_dec_cached(&token_bytes, &clob_id),
-            usd_value: usd,
-            shares,
-            price_per_share: price,
-        },
-    })
-}
-
-/// Extract 40-character address from a topic hex string
-/// Format: 0x000000000000000000000000{40-char-address}
-/// Returns normalized lowercase address without 0x prefix
-fn extract_address_from_topic(topic: &str) -> Option<String> {
-    let topic_clean = topic.trim().strip_prefix("0x").unwrap_or(topic.trim());
-    if topic_clean.len() != 64 {
-        return None;
-    }
-    // Last 40 characters are the address (first 24 are padding zeros)
-    let address = &topic_clean[24..64];
-    Some(address.to_lowercase())
-}
-
-// ============================================================================
-// Hex Parsing Helpers
-// ============================================================================
-
-#[inline]
-fn parse_u256_hex_slice_with_bytes(full: &str, start: usize, end: usize) -> Option<(U256, [u8; 32])> {
-    let slice = full.get(start..end)?;
-    let clean = slice.strip_prefix("0x").unwrap_or(slice);
-    if clean.len() > 64 { return None; }
-
-    let mut hex_buf = [b'0'; 64];
-    hex_buf[64 - clean.len()..].copy_from_slice(clean.as_bytes());
-
-    let mut out = [0u8; 32];
-    for i in 0..32 {
-        let hi = hex_nibble(hex_buf[i * 2])?;
-        let lo = hex_nibble(hex_buf[i * 2 + 1])?;
-        out[i] = (hi << 4) | lo;
-    }
-    Some((U256::from_be_slice(&out), out))
-}
-
-#[inline]
-fn parse_u256_hex_slice(full: &str, start: usize, end: usize) -> Option<U256> {
-    parse_u256_hex_slice_with_bytes(full, start, end).map(|(v, _)| v)
-}
-
-fn u256_to_dec_cached(bytes: &[u8; 32], val: &U256) -> Arc<str> {
-    TOKEN_ID_CACHE.with(|cache| {
-        let mut cache = cache.borrow_mut();
-        if let Some(s) = cache.get(bytes) { return Arc::clone(s); }  // Cheap Arc clone
-        let s: Arc<str> = val.to_string().into();
-        cache.insert(*bytes, Arc::clone(&s));
-        s
-    })
-}
-
-fn u256_to_f64(v: &U256) -> Option<f64> {
-    if v.bit_len() <= 64 { Some(v.as_limbs()[0] as f64) }
-    else { v.to_string().parse().ok() }
-}
-
-// Hex nibble lookup table - 2-3x faster than branching
-const HEX_NIBBLE_LUT: [u8; 256] = {
-    let mut lut = [255u8; 256];
-    let mut i = b'0';
-    while i <= b'9' {
-        lut[i as usize] = i - b'0';
-        i += 1;
-    }
-    let mut i = b'a';
-    while i <= b'f' {
-        lut[i as usize] = i - b'a' + 10;
-        i += 1;
-    }
-    let mut i = b'A';
-    while i <= b'F' {
-        lut[i as usize] = i - b'A' + 10;
-        i += 1;
-    }
-    lut
-};
-
-#[inline(always)]
-fn hex_nibble(b: u8) -> Option<u8> {
-    let val = HEX_NIBBLE_LUT[b as usize];
-    if val == 255 { None } else { Some(val) }
-}
-
 // ============================================================================
 // CSV Helpers
 // ============================================================================
 
-fn ensure_csv() -> Result<()> {
-    if !Path::new(CSV_FILE).exists() {
-        let mut f = File::create(CSV_FILE)?;
-        writeln!(f, "timestamp,block,clob_asset_id,usd_value,shares,price_per_share,direction,order_status,best_price,best_size,second_price,second_size,tx_hash,is_live")?;
-    }
-    Ok(())
-}
-
 fn append_csv_row(row: String) {
-    if let Ok(mut f) = OpenOptions::new().append(true).create(true).open(CSV_FILE) {
-        let _ = writeln!(f, "{}", row);
-    }
+    csv_log::append_row(&row);
 }
 
 #[inline]
@@ -2083,19 +4165,161 @@ mod tests {
         assert_eq!(event.trader_label, "");
     }
 
-    // Test extract_address_from_topic helper function
+    /// The same fill arriving twice within the dedup window yields one submission; after the window, another
     #[test]
-    fn test_extract_address_from_topic_valid() {
-        let topic = "0x000000000000000000000000abc123def456789012345678901234567890abcd";
-        let result = extract_address_from_topic(topic);
-        assert_eq!(result, Some("abc123def456789012345678901234567890abcd".to_string()));
+    fn test_duplicate_events_within_dedup_window_submit_once() {
+        use pm_whale_follower::config::traders::TraderConfig;
+
+        let trader = TraderConfig::new("abc123def456789012345678901234567890abcd", "Whale").unwrap();
+        let traders = TradersConfig::new(vec![trader.clone()]);
+        let message = serde_json::json!({
+            "params": {
+                "result": {
+                    "topics": [
+                        ORDERS_FILLED_EVENT_SIGNATURE,
+                        "0x0000000000000000000000000000000000000000000000000000000000000000",
+                        trader.topic_hex,
+                    ],
+                    "data": "0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000123456000000000000000000000000000000000000000000000000000000000000f4240000000000000000000000000000000000000000000000000000000000007a120",
+                    "blockNumber": "0x1234",
+                    "transactionHash": "0xabcdef"
+                }
+            }
+        }).to_string();
+
+        // Text frame, then the same payload as a Binary frame, then a resend after the window
+        let text_evt = parse_event(message.clone(), Some(&traders)).unwrap();
+        let binary_evt = parse_event(String::from_utf8(message.clone().into_bytes()).unwrap(), Some(&traders)).unwrap();
+        let resent_evt = parse_event(message, Some(&traders)).unwrap();
+
+        let mut dedup = EventDedup::new(Duration::from_millis(500));
+        let t0 = std::time::Instant::now();
+        let submissions = [
+            (text_evt, t0),
+            (binary_evt, t0 + Duration::from_millis(100)),
+            (resent_evt, t0 + Duration::from_millis(600)),
+        ]
+        .iter()
+        .filter(|(evt, at)| dedup.first_seen_at(evt, *at))
+        .count();
+
+        assert_eq!(submissions, 2);
     }
 
+    /// Per-trader scaling_ratio from traders config flows through the event into sizing
     #[test]
-    fn test_extract_address_from_topic_uppercase() {
-        let topic = "0x000000000000000000000000ABC123DEF456789012345678901234567890ABCD";
-        let result = extract_address_from_topic(topic);
-        assert_eq!(result, Some("abc123def456789012345678901234567890abcd".to_string()));
+    fn test_trader_scaling_ratio_drives_order_size() {
+        use pm_whale_follower::config::traders::TraderConfig;
+
+        let mut small = TraderConfig::new("abc123def456789012345678901234567890abcd", "Small").unwrap();
+        small.scaling_ratio = 0.02;
+        let mut big = TraderConfig::new("def456def456789012345678901234567890def4", "Big").unwrap();
+        big.scaling_ratio = 0.05;
+        let traders = TradersConfig::new(vec![small.clone(), big.clone()]);
+
+        let fill_from = |trader: &TraderConfig| serde_json::json!({
+            "params": {
+                "result": {
+                    "topics": [
+                        ORDERS_FILLED_EVENT_SIGNATURE,
+                        "0x0000000000000000000000000000000000000000000000000000000000000000",
+                        trader.topic_hex,
+                    ],
+                    "data": "0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000123456000000000000000000000000000000000000000000000000000000000000f4240000000000000000000000000000000000000000000000000000000000007a120",
+                    "blockNumber": "0x1234",
+                    "transactionHash": "0xabcdef"
+                }
+            }
+        }).to_string();
+
+        let small_event = parse_event(fill_from(&small), Some(&traders)).unwrap();
+        let big_event = parse_event(fill_from(&big), Some(&traders)).unwrap();
+        assert_eq!(small_event.trader_scaling_ratio, 0.02);
+        assert_eq!(big_event.trader_scaling_ratio, 0.05);
+
+        // Same whale fill (10000 shares @ 0.50), different copy sizes
+        let (small_shares, _) = calculate_safe_size(SizingInput::new(10000.0, small_event.trader_scaling_ratio, 0.50));
+        let (big_shares, _) = calculate_safe_size(SizingInput::new(10000.0, big_event.trader_scaling_ratio, 0.50));
+        assert_eq!(small_shares, 200.0);
+        assert_eq!(big_shares, 500.0);
+    }
+
+    #[tokio::test]
+    async fn test_observed_trader_is_recorded_but_never_copied() {
+        use pm_whale_follower::config::traders::TraderConfig;
+
+        let mut watched = TraderConfig::new("abc123def456789012345678901234567890abcd", "Candidate").unwrap();
+        watched.observe = true;
+        let traders = TradersConfig::new(vec![watched.clone()]);
+        // Still subscribed, so its fills reach the bot
+        assert_eq!(traders.build_topic_filter(), vec![watched.topic_hex.clone()]);
+
+        let message = serde_json::json!({
+            "params": {
+                "result": {
+                    "topics": [
+                        ORDERS_FILLED_EVENT_SIGNATURE,
+                        "0x0000000000000000000000000000000000000000000000000000000000000000",
+                        watched.topic_hex,
+                    ],
+                    "data": "0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000123456000000000000000000000000000000000000000000000000000000000000f4240000000000000000000000000000000000000000000000000000000000007a120",
+                    "blockNumber": "0x1234",
+                    "transactionHash": "0xobserved"
+                }
+            }
+        }).to_string();
+        let evt = parse_event(message, Some(&traders)).unwrap();
+        assert!(evt.trader_observe);
+
+        let (order_tx, mut order_rx) = mpsc::channel(16);
+        let (resubmit_tx, _resubmit_rx) = mpsc::unbounded_channel();
+        let engine = OrderEngine {
+            tx: order_tx,
+            resubmit_tx,
+            enable_trading: true,
+            trading_paused: Arc::new(AtomicBool::new(false)),
+            reply_timeouts: OrderReplyTimeouts::default(),
+        };
+        let (trade_tx, mut trade_rx) = mpsc::unbounded_channel();
+        let trader_manager = Arc::new(Mutex::new(TraderManager::new(&traders)));
+        let aggregator = Arc::new(Mutex::new(TradeAggregator::new(AggregationConfig::default())));
+        let ctx = EventContext {
+            endpoints: ApiEndpoints { clob: "http://127.0.0.1:1".to_string(), gamma: "http://127.0.0.1:1".to_string() },
+            notifier: Notifiers::default(),
+            post_trade_book_delay: Duration::ZERO,
+            success_fill_pct: SUCCESS_FILL_PCT_DEFAULT,
+        };
+
+        handle_event(
+            evt.clone(), &engine, &reqwest::Client::new(), &ctx, Some(trade_tx),
+            Arc::clone(&trader_manager), Some(Arc::clone(&aggregator)),
+        ).await;
+
+        assert!(order_rx.try_recv().is_err(), "observed fills never reach the order worker");
+        assert_eq!(aggregator.lock().await.pending_count(), 0);
+        let record = trade_rx.try_recv().unwrap();
+        assert_eq!(record.status, OBSERVED_STATUS);
+        assert_eq!((record.tx_hash.as_str(), record.whale_shares), ("0xobserved", evt.order.shares));
+        assert_eq!(record.our_shares, None);
+        let manager = trader_manager.lock().await;
+        assert_eq!(manager.get_state(&watched.address).unwrap().successful_trades, 0);
+    }
+
+    // Test extract_address_from_topic helper function
+    use pm_whale_follower::event_parser::extract_address_from_topic;
+
+    #[test]
+    fn test_extract_address_from_topic_valid() {
+        let topic = "0x000000000000000000000000abc123def456789012345678901234567890abcd";
+        let result = extract_address_from_topic(topic);
+        assert_eq!(result, Some("abc123def456789012345678901234567890abcd".to_string()));
+    }
+
+    #[test]
+    fn test_extract_address_from_topic_uppercase() {
+        let topic = "0x000000000000000000000000ABC123DEF456789012345678901234567890ABCD";
+        let result = extract_address_from_topic(topic);
+        assert_eq!(result, Some("abc123def456789012345678901234567890abcd".to_string()));
     }
 
     #[test]
@@ -2119,7 +4343,7 @@ mod tests {
             "0x000000000000000000000000abc123def456789012345678901234567890abcd".to_string()
         ];
 
-        let msg = build_subscription_message(topics);
+        let msg = build_subscription_message(topics, TOPIC_FILTER_MAX_TRADERS_DEFAULT);
         let parsed: Value = serde_json::from_str(&msg).unwrap();
 
         // Verify structure
@@ -2139,7 +4363,7 @@ mod tests {
             "0x000000000000000000000000def456def456789012345678901234567890def4".to_string(),
         ];
 
-        let msg = build_subscription_message(topics);
+        let msg = build_subscription_message(topics, TOPIC_FILTER_MAX_TRADERS_DEFAULT);
         let parsed: Value = serde_json::from_str(&msg).unwrap();
 
         // Verify topics array has both traders
@@ -2154,7 +4378,7 @@ mod tests {
             .map(|i| format!("0x{:064x}", i))
             .collect();
 
-        let msg = build_subscription_message(topics);
+        let msg = build_subscription_message(topics, TOPIC_FILTER_MAX_TRADERS_DEFAULT);
         let parsed: Value = serde_json::from_str(&msg).unwrap();
 
         // Verify topics[2] is null (client-side filtering)
@@ -2162,6 +4386,23 @@ mod tests {
         assert_eq!(topics_array[2], Value::Null);
     }
 
+    #[test]
+    fn test_topic_filter_threshold_boundary() {
+        let topics = |n: usize| -> Vec<String> { (0..n).map(|i| format!("0x{:064x}", i)).collect() };
+        let trader_filter = |msg: String| serde_json::from_str::<Value>(&msg).unwrap()["params"][1]["topics"][2].clone();
+
+        // At the threshold the provider still filters; one more switches to client-side
+        assert_eq!(trader_filter(build_subscription_message(topics(10), 10)).as_array().unwrap().len(), 10);
+        assert_eq!(trader_filter(build_subscription_message(topics(11), 10)), Value::Null);
+        assert!(!uses_client_side_filtering(10, 10));
+        assert!(uses_client_side_filtering(11, 10));
+
+        // A provider taking larger topic arrays keeps 11..=25 traders server-side
+        assert_eq!(trader_filter(build_subscription_message(topics(11), 25)).as_array().unwrap().len(), 11);
+        assert_eq!(trader_filter(build_subscription_message(topics(25), 25)).as_array().unwrap().len(), 25);
+        assert_eq!(trader_filter(build_subscription_message(topics(26), 25)), Value::Null);
+    }
+
     // -------------------------------------------------------------------------
     // Portfolio-based bet size cap tests
     // -------------------------------------------------------------------------
@@ -2170,7 +4411,7 @@ mod tests {
     fn test_calculate_safe_size_no_cap() {
         // Without a cap, should return scaled size
         // 10000 shares * 0.02 (SCALING_RATIO) * 1.0 (multiplier) = 200 shares
-        let (shares, size_type) = calculate_safe_size(10000.0, 0.50, 1.0, None);
+        let (shares, size_type) = calculate_safe_size(SizingInput::new(10000.0, SCALING_RATIO, 0.50));
         assert!((shares - 200.0).abs() < 0.01);
         assert!(matches!(size_type, SizeType::Scaled));
     }
@@ -2179,7 +4420,7 @@ mod tests {
     fn test_calculate_safe_size_with_cap_not_exceeded() {
         // Cap is higher than calculated size, should return scaled size
         // 5000 shares * 0.02 * 1.0 = 100 shares, cap = 200 shares
-        let (shares, size_type) = calculate_safe_size(5000.0, 0.50, 1.0, Some(200.0));
+        let (shares, size_type) = calculate_safe_size(SizingInput { max_bet_shares: Some(200.0), ..SizingInput::new(5000.0, SCALING_RATIO, 0.50) });
         assert!((shares - 100.0).abs() < 0.01);
         assert!(matches!(size_type, SizeType::Scaled)); // Not capped
     }
@@ -2188,7 +4429,7 @@ mod tests {
     fn test_calculate_safe_size_with_cap_exceeded() {
         // Cap is lower than calculated size, should return capped size
         // 10000 shares * 0.02 * 1.0 = 200 shares, cap = 50 shares
-        let (shares, size_type) = calculate_safe_size(10000.0, 0.50, 1.0, Some(50.0));
+        let (shares, size_type) = calculate_safe_size(SizingInput { max_bet_shares: Some(50.0), ..SizingInput::new(10000.0, SCALING_RATIO, 0.50) });
         assert!((shares - 50.0).abs() < 0.01);
         assert!(matches!(size_type, SizeType::Capped));
     }
@@ -2197,7 +4438,7 @@ mod tests {
     fn test_calculate_safe_size_with_multiplier_and_cap() {
         // 8000 shares * 0.02 * 1.25 (large trade multiplier) = 200 shares
         // Cap = 100 shares, should cap
-        let (shares, size_type) = calculate_safe_size(8000.0, 0.50, 1.25, Some(100.0));
+        let (shares, size_type) = calculate_safe_size(SizingInput { size_multiplier: 1.25, max_bet_shares: Some(100.0), ..SizingInput::new(8000.0, SCALING_RATIO, 0.50) });
         assert!((shares - 100.0).abs() < 0.01);
         assert!(matches!(size_type, SizeType::Capped));
     }
@@ -2206,7 +4447,7 @@ mod tests {
     fn test_calculate_safe_size_cap_at_exactly_scaled() {
         // Cap equals scaled size exactly, should NOT show as capped
         // 5000 shares * 0.02 * 1.0 = 100 shares, cap = 100 shares
-        let (shares, size_type) = calculate_safe_size(5000.0, 0.50, 1.0, Some(100.0));
+        let (shares, size_type) = calculate_safe_size(SizingInput { max_bet_shares: Some(100.0), ..SizingInput::new(5000.0, SCALING_RATIO, 0.50) });
         assert!((shares - 100.0).abs() < 0.01);
         assert!(matches!(size_type, SizeType::Scaled)); // Not capped because size == cap
     }
@@ -2214,8 +4455,2193 @@ mod tests {
     #[test]
     fn test_calculate_safe_size_cap_zero_disables() {
         // Cap of 0 should effectively disable capping (treated as no cap)
-        let (shares, _size_type) = calculate_safe_size(10000.0, 0.50, 1.0, Some(0.0));
+        let (shares, _size_type) = calculate_safe_size(SizingInput { max_bet_shares: Some(0.0), ..SizingInput::new(10000.0, SCALING_RATIO, 0.50) });
         // With cap=0, the condition `max > 0.0` fails, so no capping applied
         assert!((shares - 200.0).abs() < 0.01);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_calculate_safe_size_share_cap_with_percent_cap() {
+        use pm_whale_follower::portfolio::max_bet_shares;
+        // 10000 shares * 0.02 = 200 shares at 0.50; a $50 percent cap is 100 shares
+
+        // MAX_BET_SHARES=60 binds below the percent cap
+        let cap = max_bet_shares(Some(50.0), Some(60.0), 0.50);
+        let (shares, size_type) = calculate_safe_size(SizingInput { max_bet_shares: cap, ..SizingInput::new(10000.0, SCALING_RATIO, 0.50) });
+        assert!((shares - 60.0).abs() < 0.01);
+        assert!(matches!(size_type, SizeType::Capped));
+
+        // MAX_BET_SHARES=150 is looser, so the percent cap binds
+        let cap = max_bet_shares(Some(50.0), Some(150.0), 0.50);
+        let (shares, size_type) = calculate_safe_size(SizingInput { max_bet_shares: cap, ..SizingInput::new(10000.0, SCALING_RATIO, 0.50) });
+        assert!((shares - 100.0).abs() < 0.01);
+        assert!(matches!(size_type, SizeType::Capped));
+
+        // Both above the target: neither binds
+        let cap = max_bet_shares(Some(500.0), Some(300.0), 0.50);
+        let (shares, size_type) = calculate_safe_size(SizingInput { max_bet_shares: cap, ..SizingInput::new(10000.0, SCALING_RATIO, 0.50) });
+        assert!((shares - 200.0).abs() < 0.01);
+        assert!(matches!(size_type, SizeType::Scaled));
+    }
+
+    #[test]
+    fn test_size_floor_required_shares() {
+        let default = SizeFloor::default();
+        // $1.01 at 0.50 is ~2 shares, so the 5-share minimum binds
+        assert_eq!(default.required_shares(0.50), 5.0);
+        // At 0.10 the cash minimum binds
+        assert!((default.required_shares(0.10) - 10.1).abs() < 1e-9);
+
+        let raised = SizeFloor { min_shares: 20.0, ..default };
+        assert_eq!(raised.required_shares(0.50), 20.0);
+        assert!((raised.required_shares(0.01) - 101.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_calculate_safe_size_raised_min_share_count() {
+        // 250 * 0.02 = 5 shares: meets the default floor
+        let (shares, size_type) = calculate_safe_size(SizingInput::new(250.0, SCALING_RATIO, 0.50));
+        assert!((shares - 5.0).abs() < 0.01);
+        assert!(matches!(size_type, SizeType::Scaled));
+
+        // MIN_SHARE_COUNT=20: the same 5 shares is now 25% of the floor, rounded up to 20 or skipped
+        let raised = SizeFloor { min_shares: 20.0, ..SizeFloor::default() };
+        for _ in 0..50 {
+            match calculate_safe_size(SizingInput { floor: raised, ..SizingInput::new(250.0, SCALING_RATIO, 0.50) }) {
+                (shares, SizeType::ProbHit(25)) => assert_eq!(shares, 20.0),
+                (shares, SizeType::ProbSkip(25)) => assert_eq!(shares, 0.0),
+                other => panic!("unexpected sizing {:?}", other),
+            }
+        }
+
+        // Targets at or above the raised floor are unaffected
+        let (shares, size_type) = calculate_safe_size(SizingInput { floor: raised, ..SizingInput::new(1000.0, SCALING_RATIO, 0.50) });
+        assert!((shares - 20.0).abs() < 0.01);
+        assert!(matches!(size_type, SizeType::Scaled));
+    }
+
+    #[test]
+    fn test_calculate_safe_size_per_trader_probabilistic_toggle() {
+        // 100 * 0.02 = 2 shares at 0.50: 40% of the 5-share floor
+        let global = SizeFloor::default();
+        assert_eq!(global.strategy, SizingStrategy::Probabilistic);
+
+        let disabled = global.with_probabilistic(Some(false));
+        for _ in 0..50 {
+            let (shares, size_type) = calculate_safe_size(SizingInput { floor: disabled, ..SizingInput::new(100.0, SCALING_RATIO, 0.50) });
+            assert_eq!(shares, 5.0);
+            assert!(matches!(size_type, SizeType::Scaled));
+        }
+
+        // Enabled (or unset, falling back to the global default) rounds up only some of the time
+        for floor in [global.with_probabilistic(Some(true)), global.with_probabilistic(None)] {
+            let (mut hits, mut skips) = (0, 0);
+            for _ in 0..200 {
+                match calculate_safe_size(SizingInput { floor, ..SizingInput::new(100.0, SCALING_RATIO, 0.50) }) {
+                    (shares, SizeType::ProbHit(40)) => { assert_eq!(shares, 5.0); hits += 1; }
+                    (shares, SizeType::ProbSkip(40)) => { assert_eq!(shares, 0.0); skips += 1; }
+                    other => panic!("unexpected sizing {:?}", other),
+                }
+            }
+            assert!(hits > 0 && skips > 0, "hits={} skips={}", hits, skips);
+        }
+    }
+
+    #[test]
+    fn test_parse_book_levels_deep_book_sums_more_depth() {
+        // 25 ask levels at 0.60 with 100 shares each, all beyond a 0.50 threshold
+        let asks: Vec<Value> = (0..25)
+            .map(|_| serde_json::json!({"price": "0.60", "size": "100"}))
+            .collect();
+        let asks = Value::Array(asks);
+
+        let capped = parse_book_levels(&asks, 10);
+        let deep = parse_book_levels(&asks, 25);
+        assert_eq!(capped.len(), 10);
+        assert_eq!(deep.len(), 25);
+
+        let capped_depth = calc_liquidity_depth(TradeSide::Buy, &capped, 0.50);
+        let deep_depth = calc_liquidity_depth(TradeSide::Buy, &deep, 0.50);
+        assert!((capped_depth - 600.0).abs() < 0.01);
+        assert!((deep_depth - 1500.0).abs() < 0.01);
+        assert!(deep_depth > capped_depth);
+    }
+
+    fn book(bids: &[&str], asks: &[&str]) -> Value {
+        let side = |prices: &[&str]| -> Value {
+            prices.iter().map(|p| serde_json::json!({"price": p, "size": "100"})).collect()
+        };
+        serde_json::json!({"bids": side(bids), "asks": side(asks)})
+    }
+
+    #[test]
+    fn test_spread_buffer_tight_book() {
+        // Levels are unordered on purpose - best bid/ask must be picked by price
+        let tight = book(&["0.48", "0.50"], &["0.53", "0.51"]);
+        let spread = book_spread(&tight).unwrap();
+        assert!((spread - 0.01).abs() < 1e-9);
+
+        // Half of a 0.01 spread rounds down to no buffer - don't overpay
+        assert_eq!(get_spread_buffer(spread, 0.5, 0.01), 0.0);
+        // Full spread fits within the tier buffer
+        assert_eq!(get_spread_buffer(spread, 1.0, 0.02), 0.01);
+    }
+
+    #[test]
+    fn test_spread_buffer_wide_book() {
+        let wide = book(&["0.40"], &["0.50"]);
+        let spread = book_spread(&wide).unwrap();
+        assert!((spread - 0.10).abs() < 1e-9);
+
+        // 0.05 from the spread, capped at the tier buffer
+        assert_eq!(get_spread_buffer(spread, 0.5, 0.01), 0.01);
+        assert_eq!(get_spread_buffer(spread, 0.5, 0.10), 0.05);
+
+        // One-sided book has no spread
+        assert_eq!(book_spread(&book(&["0.40"], &[])), None);
+    }
+
+    #[test]
+    fn test_fok_tier_never_resubmits() {
+        let info = OrderInfo {
+            order_type: "BUY_FILL".to_string(),
+            clob_token_id: Arc::from("token"),
+            usd_value: 2500.0,
+            shares: 5000.0,
+            price_per_share: 0.50,
+        };
+        let partial = OrderResponse {
+            success: true,
+            error_msg: String::new(),
+            order_id: "0xorder".to_string(),
+            transactions_hashes: vec![],
+            status: "matched".to_string(),
+            taking_amount: "20".to_string(),
+            making_amount: "10.2".to_string(),
+        };
+        let (_, order_action, _) = get_tier_params_with_fok(info.shares, true, "token", Some(4000.0), &ExecutionTiers::default());
+        assert_eq!(order_action, "FOK");
+
+        let submitted = |action| SubmittedOrder { action, side_is_buy: true, limit_price: 0.51, shares: 100.0, is_live: false };
+
+        // FOK kill (400) and a (theoretical) partial fill: nothing to chase
+        let killed = "order couldn't be fully filled. FOK orders are fully filled or killed.";
+        assert!(fak_resubmit_request(&submitted(order_action), 400, killed, None, &info, "0xwhale", SizeFloor::default()).is_none());
+        assert!(fak_resubmit_request(&submitted(order_action), 200, "", Some(&partial), &info, "0xwhale", SizeFloor::default()).is_none());
+
+        // Same outcomes on a FAK tier do resubmit
+        let no_match = "no orders found to match with FAK order";
+        let req = fak_resubmit_request(&submitted("FAK"), 400, no_match, None, &info, "0xwhale", SizeFloor::default()).unwrap();
+        assert_eq!(req.size, 100.0);
+        assert_eq!(req.cumulative_filled, 0.0);
+
+        let req = fak_resubmit_request(&submitted("FAK"), 200, "", Some(&partial), &info, "0xwhale", SizeFloor::default()).unwrap();
+        assert_eq!(req.size, 80.0);
+        assert_eq!(req.cumulative_filled, 20.0);
+        assert_eq!(req.original_size, 100.0);
+    }
+
+    /// Walk a resubmit chain where every FAK attempt fails: (price per attempt, attempt that aborted)
+    fn simulate_failed_chain(policy: &ResubmitPolicy, whale_shares: f64, limit_price: f64, max_price: f64) -> (Vec<f64>, Option<u8>) {
+        let mut req = ResubmitRequest {
+            token_id: "token".to_string(),
+            trader_address: "0xwhale".to_string(),
+            whale_price: limit_price,
+            failed_price: limit_price,
+            size: 100.0,
+            whale_shares,
+            side_is_buy: true,
+            attempt: 1,
+            max_price,
+            cumulative_filled: 0.0,
+            original_size: 100.0,
+            is_live: false,
+        };
+        let mut prices = Vec::new();
+        while req.attempt <= policy.max_attempts(whale_shares) {
+            let (price, abort) = next_resubmit_price(&req, policy);
+            if abort {
+                return (prices, Some(req.attempt));
+            }
+            prices.push(price);
+            req.failed_price = price;
+            req.attempt += 1;
+        }
+        (prices, None)
+    }
+
+    #[test]
+    fn test_larger_resubmit_increment_reaches_ceiling_sooner() {
+        let policy = |increment: f64| ResubmitPolicy { price_increment: increment, ..ResubmitPolicy::default() };
+        let round = |prices: Vec<f64>| prices.iter().map(|p| (p * 100.0).round() / 100.0).collect::<Vec<_>>();
+
+        // Default 1-tick chase stays under a 2-tick ceiling for the whole chain
+        let (prices, aborted) = simulate_failed_chain(&policy(0.01), 5000.0, 0.50, 0.52);
+        assert_eq!(round(prices), vec![0.51; 5]);
+        assert_eq!(aborted, None);
+
+        // 2 ticks hits the ceiling on the first chase
+        let (prices, aborted) = simulate_failed_chain(&policy(0.02), 5000.0, 0.50, 0.52);
+        assert_eq!(round(prices)[0], 0.52);
+        assert_eq!(aborted, None);
+
+        // 3 ticks overshoots it and aborts immediately
+        let (prices, aborted) = simulate_failed_chain(&policy(0.03), 5000.0, 0.50, 0.52);
+        assert!(prices.is_empty());
+        assert_eq!(aborted, Some(1));
+    }
+
+    #[test]
+    fn test_resubmit_attempts_follow_policy() {
+        let policy = ResubmitPolicy { price_increment: 0.05, max_attempts_large: 2, max_attempts_small: 1 };
+
+        let (prices, _) = simulate_failed_chain(&policy, 5000.0, 0.50, 0.99);
+        assert_eq!(prices.len(), 2);
+        // Small trades never chase, so the increment doesn't matter
+        let (prices, _) = simulate_failed_chain(&policy, 500.0, 0.50, 0.50);
+        assert_eq!(prices, vec![0.50]);
+
+        // The last attempt (GTD) goes through even above the ceiling
+        let last = ResubmitPolicy { max_attempts_large: 1, ..policy };
+        let (prices, aborted) = simulate_failed_chain(&last, 5000.0, 0.50, 0.51);
+        assert_eq!((prices.len(), aborted), (1, None));
+    }
+
+    #[test]
+    fn test_track_gtd_order_only_tracks_resting_gtd() {
+        let placed = std::sync::Mutex::new(HashSet::new());
+        let resp = |id: &str, status: &str| OrderResponse {
+            success: true,
+            error_msg: String::new(),
+            order_id: id.to_string(),
+            transactions_hashes: vec![],
+            status: status.to_string(),
+            taking_amount: String::new(),
+            making_amount: String::new(),
+        };
+
+        track_gtd_order(&placed, "GTD", &resp("0xresting", "live"));
+        track_gtd_order(&placed, "GTD", &resp("0xfilled", "matched"));
+        track_gtd_order(&placed, "FAK", &resp("0xfak", "live"));
+        track_gtd_order(&placed, "GTD", &resp("", "live"));
+
+        let placed = placed.into_inner().unwrap();
+        assert_eq!(placed.len(), 1);
+        assert!(placed.contains("0xresting"));
+    }
+
+    #[test]
+    fn test_detect_block_gap() {
+        // First event - nothing to compare against
+        assert_eq!(detect_block_gap(None, 1000, 100), None);
+        // Within threshold
+        assert_eq!(detect_block_gap(Some(1000), 1100, 100), None);
+        // Same block or out-of-order events are not gaps
+        assert_eq!(detect_block_gap(Some(1000), 1000, 100), None);
+        assert_eq!(detect_block_gap(Some(1000), 990, 100), None);
+        // Beyond threshold - report skipped range
+        assert_eq!(detect_block_gap(Some(1000), 1150, 100), Some((1001, 1149)));
+        // Threshold 0 disables detection
+        assert_eq!(detect_block_gap(Some(1000), 5000, 0), None);
+    }
+
+    #[test]
+    fn test_track_block_gap_only_advances() {
+        let mut last = None;
+        track_block_gap(&mut last, 1000, 100, false);
+        assert_eq!(last, Some(1000));
+        track_block_gap(&mut last, 995, 100, false);
+        assert_eq!(last, Some(1000));
+        track_block_gap(&mut last, 1200, 100, false);
+        assert_eq!(last, Some(1200));
+    }
+
+    #[test]
+    fn test_track_block_gap_only_across_reconnect() {
+        // A quiet trader leaves long gaps between fills on a live connection
+        let mut last = Some(1000);
+        assert_eq!(track_block_gap(&mut last, 5000, 100, false), None);
+        assert_eq!(last, Some(5000));
+
+        // The same gap spanning a reconnect is reported
+        assert_eq!(track_block_gap(&mut last, 9000, 100, true), Some((5001, 8999)));
+        assert_eq!(last, Some(9000));
+    }
+
+    #[test]
+    fn test_calculate_safe_size_capped_by_trader() {
+        // 10000 shares * 0.02 = 200 shares, trader cap = 80 shares
+        let (shares, size_type) = calculate_safe_size(SizingInput { trader_max_shares: Some(80.0), ..SizingInput::new(10000.0, SCALING_RATIO, 0.50) });
+        assert!((shares - 80.0).abs() < 0.01);
+        assert!(matches!(size_type, SizeType::CappedByTrader));
+    }
+
+    #[test]
+    fn test_calculate_safe_size_trader_cap_below_floor_skips() {
+        // Trader cap of 3 shares ($1.50 at 0.50) is under the $1 / 5-share floor:
+        // no strategy may round up past the trader's max_bet_usd
+        for strategy in [SizingStrategy::RoundUp, SizingStrategy::Nearest, SizingStrategy::Probabilistic, SizingStrategy::Skip] {
+            let floor = SizeFloor { strategy, ..SizeFloor::default() };
+            for _ in 0..20 {
+                let (shares, size_type) = calculate_safe_size(SizingInput { trader_max_shares: Some(3.0), floor, ..SizingInput::new(10000.0, SCALING_RATIO, 0.50) });
+                assert_eq!(shares, 0.0, "{:?}", strategy);
+                assert!(matches!(size_type, SizeType::CappedByTrader));
+            }
+        }
+    }
+
+    #[test]
+    fn test_calculate_safe_size_tighter_cap_wins() {
+        // Portfolio cap (50) is tighter than trader cap (80) - portfolio cap applies
+        let (shares, size_type) = calculate_safe_size(SizingInput { max_bet_shares: Some(50.0), trader_max_shares: Some(80.0), ..SizingInput::new(10000.0, SCALING_RATIO, 0.50) });
+        assert!((shares - 50.0).abs() < 0.01);
+        assert!(matches!(size_type, SizeType::Capped));
+
+        // Trader cap (30) is tighter than portfolio cap (50)
+        let (shares, size_type) = calculate_safe_size(SizingInput { max_bet_shares: Some(50.0), trader_max_shares: Some(30.0), ..SizingInput::new(10000.0, SCALING_RATIO, 0.50) });
+        assert!((shares - 30.0).abs() < 0.01);
+        assert!(matches!(size_type, SizeType::CappedByTrader));
+    }
+
+    #[test]
+    fn test_calculate_safe_size_kelly() {
+        // Edge = 0: no bet, regardless of whale size
+        let no_edge = KellySizing { edge: 0.0, fraction: 0.25 };
+        let (shares, size_type) = calculate_safe_size(SizingInput { target: Some(SizeTarget::Kelly(no_edge.target_shares(1000.0, 0.50))), ..SizingInput::new(10000.0, SCALING_RATIO, 0.50) });
+        assert_eq!(shares, 0.0);
+        assert!(matches!(size_type, SizeType::Kelly));
+
+        // Small edge: 1% of a $1000 bankroll at 0.50 = 20 shares, independent of whale size
+        let small = KellySizing { edge: 0.02, fraction: 0.25 };
+        let (shares, size_type) = calculate_safe_size(SizingInput { target: Some(SizeTarget::Kelly(small.target_shares(1000.0, 0.50))), ..SizingInput::new(10000.0, SCALING_RATIO, 0.50) });
+        assert!((shares - 20.0).abs() < 0.01);
+        assert!(matches!(size_type, SizeType::Kelly));
+
+        // Still respects the minimum order floor ($1.01 at 0.50 -> probabilistic)
+        let (shares, size_type) = calculate_safe_size(SizingInput { target: Some(SizeTarget::Kelly(small.target_shares(50.0, 0.50))), ..SizingInput::new(10000.0, SCALING_RATIO, 0.50) });
+        assert!(shares == 0.0 || shares >= MIN_SHARE_COUNT);
+        assert!(matches!(size_type, SizeType::ProbHit(_) | SizeType::ProbSkip(_)));
+    }
+
+    #[test]
+    fn test_kelly_buy_without_bankroll_is_skipped() {
+        let kelly = Some(KellySizing { edge: 0.02, fraction: 0.25 });
+        assert!(size_target(kelly, None, true, 0.50, None).is_err());
+        // SELLs and flat sizing don't need the bankroll
+        assert!(size_target(kelly, None, false, 0.50, None).unwrap().is_none());
+        assert!(matches!(size_target(None, Some(5.0), true, 0.50, None).unwrap(), Some(SizeTarget::FlatUsd(_))));
+
+        // Never sized from the whale instead
+        let settings = OrderSettings { dry_run: true, kelly_sizing: kelly, ..OrderSettings::default() };
+        let status = block_on(engine_with(settings, None).submit(buy_event("kelly-no-bankroll"), Some(false)));
+        assert!(status.starts_with("SKIPPED_NO_BANKROLL (KELLY)"), "got: {}", status);
+        assert_eq!(parse_status_for_db(&status).4, "SKIPPED_NO_BANKROLL");
+    }
+
+    #[test]
+    fn test_calculate_safe_size_kelly_bankroll_cap_binds() {
+        // Full Kelly with a big edge wants 40% of a $1000 bankroll (800 shares at 0.50);
+        // a 2% portfolio cap (40 shares) binds
+        let aggressive = KellySizing { edge: 0.20, fraction: 1.0 };
+        let target = aggressive.target_shares(1000.0, 0.50);
+        assert!((target - 800.0).abs() < 0.01);
+
+        let (shares, size_type) = calculate_safe_size(SizingInput { max_bet_shares: Some(40.0), target: Some(SizeTarget::Kelly(target)), ..SizingInput::new(100.0, SCALING_RATIO, 0.50) });
+        assert!((shares - 40.0).abs() < 0.01);
+        assert!(matches!(size_type, SizeType::Capped));
+    }
+
+    #[test]
+    fn test_calculate_safe_size_flat_ignores_whale_size() {
+        // $10 per trade at 0.50 is 20 shares whatever the whale traded
+        for whale_shares in [50.0, 1_000.0, 250_000.0] {
+            let (shares, size_type) = calculate_safe_size(SizingInput { size_multiplier: 1.25, target: Some(SizeTarget::FlatUsd(10.0)), ..SizingInput::new(whale_shares, SCALING_RATIO, 0.50) });
+            assert!((shares - 20.0).abs() < 0.01, "whale {}: {}", whale_shares, shares);
+            assert!(matches!(size_type, SizeType::Flat));
+        }
+
+        // Same USD exposure at any price
+        for price in [0.05, 0.25, 0.80] {
+            let (shares, _) = calculate_safe_size(SizingInput { target: Some(SizeTarget::FlatUsd(10.0)), ..SizingInput::new(1_000.0, SCALING_RATIO, price) });
+            assert!((shares * price - 10.0).abs() < 0.01, "price {}: ${}", price, shares * price);
+        }
+
+        // Caps still apply, and a flat amount under the floor follows SIZING_STRATEGY
+        let (shares, size_type) = calculate_safe_size(SizingInput { max_bet_shares: Some(8.0), target: Some(SizeTarget::FlatUsd(10.0)), ..SizingInput::new(1_000.0, SCALING_RATIO, 0.50) });
+        assert!((shares - 8.0).abs() < 0.01);
+        assert!(matches!(size_type, SizeType::Capped));
+        let skip = SizeFloor { strategy: SizingStrategy::Skip, ..SizeFloor::default() };
+        let (shares, size_type) = calculate_safe_size(SizingInput { target: Some(SizeTarget::FlatUsd(0.50)), floor: skip, ..SizingInput::new(1_000.0, SCALING_RATIO, 0.50) });
+        assert_eq!(shares, 0.0);
+        assert!(matches!(size_type, SizeType::BelowFloor));
+    }
+
+    #[test]
+    fn test_dry_run_reports_order_without_submitting() {
+        use pm_whale_follower::ApiCreds;
+
+        // Unroutable host: a real submit would come back EXEC_FAIL
+        let mut client = RustClobClient::new(
+            "http://127.0.0.1:1", 137,
+            "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef", None,
+        ).unwrap();
+        let api_creds = ApiCreds {
+            api_key: "key".to_string(),
+            api_secret: "c2VjcmV0".to_string(),
+            api_passphrase: "pass".to_string(),
+        };
+        let creds = SharedCreds::new(PreparedCreds::from_api_creds(&api_creds).unwrap(), None, CREDS_REFRESH_MIN_INTERVAL);
+        let mut guard = RiskGuard::new(RiskGuardConfig::default());
+        let (resubmit_tx, mut resubmit_rx) = mpsc::unbounded_channel();
+        let mut state = OrderWorkerState { breaker: ExchangeBreaker::new(1, Duration::from_secs(60)), resubmit_tx: Some(resubmit_tx), ..OrderWorkerState::default() };
+        let settings = OrderSettings { enable_trading: true, dry_run: true, ..OrderSettings::default() };
+        let info = OrderInfo {
+            order_type: "BUY_FILL".to_string(),
+            clob_token_id: Arc::from("123456"),
+            usd_value: 250.0,
+            shares: 500.0,
+            price_per_share: 0.50,
+        };
+
+        let status = process_order(
+            &trader_event(info.clone(), "trader", None), &mut client, &creds,
+            &mut guard, &mut state, Some(false), &settings,
+        );
+
+        assert!(status.starts_with("WOULD_SUBMIT"), "got: {}", status);
+        assert!(status.contains("BUY FAK 10.00 @ 0.50"), "got: {}", status);
+        // Nothing reached the exchange: no failure counted, no resubmit queued
+        assert_eq!(state.breaker.consecutive_failures(), 0);
+        assert!(resubmit_rx.try_recv().is_err());
+        assert_eq!(parse_status_for_db(&status).4, "DRY_RUN");
+        assert!(!status.contains("fee"), "got: {}", status);
+
+        // A configured FEE_RATE_BPS goes into the order
+        let with_fee = OrderSettings { fee_rate_bps: Some(50), ..settings };
+        let status = process_order(
+            &trader_event(info.clone(), "trader", None), &mut client, &creds,
+            &mut guard, &mut state, Some(false), &with_fee,
+        );
+        assert!(status.contains("BUY FAK 10.00 @ 0.50 fee 50bps"), "got: {}", status);
+    }
+
+    #[test]
+    fn test_gtd_expiry_uses_configured_secs_by_liveness() {
+        use pm_whale_follower::ApiCreds;
+
+        let mut client = RustClobClient::new(
+            "http://127.0.0.1:1", 137,
+            "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef", None,
+        ).unwrap();
+        let api_creds = ApiCreds {
+            api_key: "key".to_string(),
+            api_secret: "c2VjcmV0".to_string(),
+            api_passphrase: "pass".to_string(),
+        };
+        let creds = SharedCreds::new(PreparedCreds::from_api_creds(&api_creds).unwrap(), None, CREDS_REFRESH_MIN_INTERVAL);
+        let mut guard = RiskGuard::new(RiskGuardConfig::default());
+        let mut state = OrderWorkerState { breaker: ExchangeBreaker::new(1, Duration::from_secs(60)), ..OrderWorkerState::default() };
+        let settings = OrderSettings {
+            enable_trading: true,
+            dry_run: true,
+            gtd_expiry: GtdExpiry { live_secs: 90, default_secs: 600 },
+            ..OrderSettings::default()
+        };
+        // SELLs are placed as GTD
+        let info = OrderInfo {
+            order_type: "SELL_FILL".to_string(),
+            clob_token_id: Arc::from("gtd_expiry_token"),
+            usd_value: 250.0,
+            shares: 500.0,
+            price_per_share: 0.50,
+        };
+
+        let mut expires_in = |is_live: bool| {
+            let status = process_order(
+                &trader_event(info.clone(), "trader", None), &mut client, &creds,
+                &mut guard, &mut state, Some(is_live), &settings,
+            );
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+            let expires: u64 = status.split("(expires ").nth(1)
+                .and_then(|rest| rest.split(')').next())
+                .and_then(|ts| ts.parse().ok())
+                .unwrap_or_else(|| panic!("no expiry in: {}", status));
+            expires - now
+        };
+
+        assert!((88..=90).contains(&expires_in(true)));
+        assert!((598..=600).contains(&expires_in(false)));
+    }
+
+    #[test]
+    fn test_pathological_fills_skip_instead_of_nan_orders() {
+        use pm_whale_follower::ApiCreds;
+
+        let mut client = RustClobClient::new(
+            "http://127.0.0.1:1", 137,
+            "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef", None,
+        ).unwrap();
+        let api_creds = ApiCreds {
+            api_key: "key".to_string(),
+            api_secret: "c2VjcmV0".to_string(),
+            api_passphrase: "pass".to_string(),
+        };
+        let creds = SharedCreds::new(PreparedCreds::from_api_creds(&api_creds).unwrap(), None, CREDS_REFRESH_MIN_INTERVAL);
+        let mut guard = RiskGuard::new(RiskGuardConfig::default());
+        let mut state = OrderWorkerState { breaker: ExchangeBreaker::new(1, Duration::from_secs(60)), ..OrderWorkerState::default() };
+        let settings = OrderSettings { enable_trading: true, dry_run: true, ..OrderSettings::default() };
+
+        let mut process = |order_type: &str, shares: f64, usd_value: f64| {
+            let info = OrderInfo {
+                order_type: order_type.to_string(),
+                clob_token_id: Arc::from("pathological_token"),
+                usd_value,
+                shares,
+                price_per_share: usd_value / shares,
+            };
+            process_order(
+                &trader_event(info.clone(), "trader", None), &mut client, &creds,
+                &mut guard, &mut state, None, &settings,
+            )
+        };
+
+        // Tiny shares, huge USD: price far above $1
+        let status = process("BUY_FILL", 0.000001, 1_000_000.0);
+        assert!(status.starts_with("SKIPPED_INVALID_ORDER (price"), "{}", status);
+        let status = process("SELL_FILL", 0.000001, 1_000_000.0);
+        assert!(status.starts_with("SKIPPED_INVALID_ORDER (price"), "{}", status);
+        // 0/0 and x/0
+        let status = process("BUY_FILL", 0.0, 0.0);
+        assert!(status.starts_with("SKIPPED_INVALID_ORDER (shares"), "{}", status);
+        let status = process("BUY_FILL", f64::INFINITY, 100.0);
+        assert!(status.starts_with("SKIPPED_INVALID_ORDER (shares"), "{}", status);
+        // Tiny but sane fill: skipped on size, never a NaN order
+        let status = process("BUY_FILL", 0.000001, 0.0000005);
+        assert!(status.starts_with("SKIPPED"), "{}", status);
+        assert!(!status.contains("NaN") && !status.contains("inf"), "{}", status);
+    }
+
+    #[test]
+    fn test_parse_fill_details_rejects_non_finite() {
+        assert_eq!(parse_fill_details("PARTIAL 50.00/100.00 filled @ 0.45"), Some((50.0, 100.0, 0.45)));
+        assert_eq!(parse_fill_details("PARTIAL NaN/100.00 filled @ 0.45"), None);
+        assert_eq!(parse_fill_details("PARTIAL 50.00/100.00 filled @ inf"), None);
+    }
+
+    #[test]
+    fn test_copy_price_range_filters_whale_price() {
+        use pm_whale_follower::ApiCreds;
+
+        let mut client = RustClobClient::new(
+            "http://127.0.0.1:1", 137,
+            "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef", None,
+        ).unwrap();
+        let api_creds = ApiCreds {
+            api_key: "key".to_string(),
+            api_secret: "c2VjcmV0".to_string(),
+            api_passphrase: "pass".to_string(),
+        };
+        let creds = SharedCreds::new(PreparedCreds::from_api_creds(&api_creds).unwrap(), None, CREDS_REFRESH_MIN_INTERVAL);
+        let mut guard = RiskGuard::new(RiskGuardConfig::default());
+        let mut state = OrderWorkerState { breaker: ExchangeBreaker::new(1, Duration::from_secs(60)), ..OrderWorkerState::default() };
+
+        let band = CopyPriceRange { min: Some(0.05), max: Some(0.95) };
+        let mut status_at = |price: f64, global: CopyPriceRange, trader: CopyPriceRange| {
+            let settings = OrderSettings { enable_trading: true, dry_run: true, copy_price_range: global, ..OrderSettings::default() };
+            let info = OrderInfo {
+                order_type: "BUY_FILL".to_string(),
+                clob_token_id: Arc::from("123456"),
+                usd_value: 500.0 * price,
+                shares: 500.0,
+                price_per_share: price,
+            };
+            process_order(
+                // Probabilistic sizing off so cheap fills always round up to an order
+                &ParsedEvent { trader_copy_price_range: trader, ..trader_event(info, "trader", Some(false)) }, &mut client, &creds,
+                &mut guard, &mut state, Some(false), &settings,
+            )
+        };
+
+        let below = status_at(0.02, band, CopyPriceRange::default());
+        assert_eq!(below, "SKIPPED_PRICE_RANGE (0.02 outside 0.05-0.95)");
+        let above = status_at(0.98, band, CopyPriceRange::default());
+        assert!(above.starts_with("SKIPPED_PRICE_RANGE"), "got: {}", above);
+
+        let inside = status_at(0.50, band, CopyPriceRange::default());
+        assert!(inside.starts_with("WOULD_SUBMIT"), "got: {}", inside);
+        let unset = status_at(0.02, CopyPriceRange::default(), CopyPriceRange::default());
+        assert!(unset.starts_with("WOULD_SUBMIT"), "got: {}", unset);
+
+        // Per-trader bounds override the global band
+        let trader_wide = CopyPriceRange { min: Some(0.01), max: None };
+        assert!(status_at(0.02, band, trader_wide).starts_with("WOULD_SUBMIT"));
+        let trader_narrow = CopyPriceRange { min: Some(0.60), max: None };
+        assert!(status_at(0.50, band, trader_narrow).starts_with("SKIPPED_PRICE_RANGE"));
+    }
+
+    #[test]
+    fn test_observe_whale_fill_tracks_open_and_add() {
+        let mut holdings = WhaleHoldings::default();
+
+        assert_eq!(observe_whale_fill(&mut holdings, "0xWhale", "tok", true, 100.0), 0.0, "first BUY is an open");
+        assert_eq!(observe_whale_fill(&mut holdings, "0xwhale", "tok", true, 50.0), 100.0, "second BUY adds");
+        assert_eq!(observe_whale_fill(&mut holdings, "0xwhale", "other", true, 10.0), 0.0, "other token is separate");
+        assert_eq!(observe_whale_fill(&mut holdings, "0xother", "tok", true, 10.0), 0.0, "other trader is separate");
+
+        // Partial sell keeps the position; selling out (or oversold) forgets it
+        assert_eq!(observe_whale_fill(&mut holdings, "0xwhale", "tok", false, 30.0), 150.0);
+        assert_eq!(observe_whale_fill(&mut holdings, "0xwhale", "tok", false, 500.0), 120.0);
+        assert_eq!(observe_whale_fill(&mut holdings, "0xwhale", "tok", true, 20.0), 0.0, "re-entry is an open");
+
+        // A SELL of a token never seen bought doesn't leave a negative position behind
+        observe_whale_fill(&mut holdings, "0xwhale", "unseen", false, 40.0);
+        assert_eq!(observe_whale_fill(&mut holdings, "0xwhale", "unseen", true, 5.0), 0.0);
+    }
+
+    #[test]
+    fn test_entry_scaling_skips_and_scales_adds() {
+        use pm_whale_follower::ApiCreds;
+
+        let mut client = RustClobClient::new(
+            "http://127.0.0.1:1", 137,
+            "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef", None,
+        ).unwrap();
+        let api_creds = ApiCreds {
+            api_key: "key".to_string(),
+            api_secret: "c2VjcmV0".to_string(),
+            api_passphrase: "pass".to_string(),
+        };
+        let creds = SharedCreds::new(PreparedCreds::from_api_creds(&api_creds).unwrap(), None, CREDS_REFRESH_MIN_INTERVAL);
+        let mut guard = RiskGuard::new(RiskGuardConfig::default());
+        let mut state = OrderWorkerState { breaker: ExchangeBreaker::new(1, Duration::from_secs(60)), ..OrderWorkerState::default() };
+
+        let mut status_for = |token: &str, scaling: EntryScaling| {
+            let settings = OrderSettings { enable_trading: true, dry_run: true, entry_scaling: scaling, ..OrderSettings::default() };
+            let info = OrderInfo {
+                order_type: "BUY_FILL".to_string(),
+                clob_token_id: Arc::from(token),
+                usd_value: 500.0,
+                shares: 1000.0,
+                price_per_share: 0.5,
+            };
+            process_order(
+                &trader_event(info.clone(), "0xentrywhale", Some(false)), &mut client, &creds,
+                &mut guard, &mut state, Some(false), &settings,
+            )
+        };
+
+        let skip_adds = EntryScaling { scale_on_open: 1.0, scale_on_add: 0.0 };
+        let open = status_for("entry-scaling-1", skip_adds);
+        assert!(open.starts_with("WOULD_SUBMIT"), "got: {}", open);
+        assert_eq!(status_for("entry-scaling-1", skip_adds), "SKIPPED_WHALE_ADD");
+
+        let skip_opens = EntryScaling { scale_on_open: 0.0, scale_on_add: 1.0 };
+        assert_eq!(status_for("entry-scaling-2", skip_opens), "SKIPPED_WHALE_OPEN");
+        let add = status_for("entry-scaling-2", skip_opens);
+        assert!(add.starts_with("WOULD_SUBMIT"), "got: {}", add);
+
+        // Doubling opens doubles the order size
+        let shares_of = |status: &str| -> f64 {
+            status.split(" @ ").next().and_then(|s| s.rsplit(' ').next()).unwrap().parse().unwrap()
+        };
+        let baseline = status_for("entry-scaling-3", EntryScaling::default());
+        let doubled = status_for("entry-scaling-4", EntryScaling { scale_on_open: 2.0, scale_on_add: 1.0 });
+        assert_eq!(shares_of(&doubled), 2.0 * shares_of(&baseline), "{} vs {}", doubled, baseline);
+    }
+
+    #[test]
+    fn test_copy_decay_shrinks_repeated_buys_until_a_sell() {
+        use pm_whale_follower::ApiCreds;
+
+        let (base, _paths) = spawn_mock_api(accepting_exchange);
+        let mut client = RustClobClient::new(
+            &base, 137,
+            "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef", None,
+        ).unwrap();
+        let mut offline = RustClobClient::new(
+            "http://127.0.0.1:1", 137,
+            "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef", None,
+        ).unwrap();
+        let api_creds = ApiCreds {
+            api_key: "key".to_string(),
+            api_secret: "c2VjcmV0".to_string(),
+            api_passphrase: "pass".to_string(),
+        };
+        let creds = SharedCreds::new(PreparedCreds::from_api_creds(&api_creds).unwrap(), None, CREDS_REFRESH_MIN_INTERVAL);
+        let mut guard = RiskGuard::new(RiskGuardConfig::default());
+        let mut state = OrderWorkerState { breaker: ExchangeBreaker::new(100, Duration::from_secs(60)), ..OrderWorkerState::default() };
+
+        let live = OrderSettings { enable_trading: true, copy_decay: Some(CopyDecay { factor: 0.5 }), ..OrderSettings::default() };
+        let dry_run = OrderSettings { dry_run: true, ..live.clone() };
+        let mut status_for = |client: &mut RustClobClient, settings: &OrderSettings, token: &str, order_type: &str| {
+            let info = OrderInfo {
+                order_type: order_type.to_string(),
+                clob_token_id: Arc::from(token),
+                usd_value: 750.0,
+                shares: 1500.0,
+                price_per_share: 0.5,
+            };
+            process_order(
+                &trader_event(info.clone(), "0xdecaywhale", Some(false)), client, &creds,
+                &mut guard, &mut state, Some(false), settings,
+            )
+        };
+        // Requested size from "200 OK [..] | filled/requested filled @ .."
+        let shares_of = |status: &str| -> f64 {
+            let status = strip_ansi_codes(status);
+            assert!(status.starts_with("200 OK"), "got: {}", status);
+            let fill = status.split(" | ").nth(1).unwrap();
+            fill.split('/').nth(1).and_then(|s| s.split_whitespace().next()).unwrap().parse().unwrap()
+        };
+
+        let first = shares_of(&status_for(&mut client, &live, "copy-decay-1", "BUY_FILL"));
+        assert_eq!(first, 30.0, "first copy is full size");
+        assert_eq!(shares_of(&status_for(&mut client, &live, "copy-decay-1", "BUY_FILL")), first * 0.5);
+        assert_eq!(shares_of(&status_for(&mut client, &live, "copy-decay-1", "BUY_FILL")), first * 0.25);
+        // Other tokens keep their own streak
+        assert_eq!(shares_of(&status_for(&mut client, &live, "copy-decay-2", "BUY_FILL")), first);
+
+        // Only accepted orders count: neither a DRY_RUN report nor a failed submission extends the streak
+        let reported = status_for(&mut client, &dry_run, "copy-decay-2", "BUY_FILL");
+        assert!(reported.starts_with("WOULD_SUBMIT"), "got: {}", reported);
+        let failed = status_for(&mut offline, &live, "copy-decay-2", "BUY_FILL");
+        assert!(!strip_ansi_codes(&failed).starts_with("200 OK"), "got: {}", failed);
+        assert_eq!(shares_of(&status_for(&mut client, &live, "copy-decay-2", "BUY_FILL")), first * 0.5);
+
+        // A SELL copy (without a DB, sized from the whale) resets the streak
+        shares_of(&status_for(&mut client, &live, "copy-decay-1", "SELL_FILL"));
+        assert_eq!(shares_of(&status_for(&mut client, &live, "copy-decay-1", "BUY_FILL")), first);
+        assert_eq!(shares_of(&status_for(&mut client, &live, "copy-decay-1", "BUY_FILL")), first * 0.5);
+    }
+
+    #[test]
+    fn test_confidence_scaling_sizes_by_win_rate() {
+        use pm_whale_follower::ApiCreds;
+
+        let mut client = RustClobClient::new(
+            "http://127.0.0.1:1", 137,
+            "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef", None,
+        ).unwrap();
+        let api_creds = ApiCreds {
+            api_key: "key".to_string(),
+            api_secret: "c2VjcmV0".to_string(),
+            api_passphrase: "pass".to_string(),
+        };
+        let creds = SharedCreds::new(PreparedCreds::from_api_creds(&api_creds).unwrap(), None, CREDS_REFRESH_MIN_INTERVAL);
+        let mut guard = RiskGuard::new(RiskGuardConfig::default());
+        let mut state = OrderWorkerState { breaker: ExchangeBreaker::new(1, Duration::from_secs(60)), ..OrderWorkerState::default() };
+
+        {
+            let mut pnl = state.trader_pnl.lock().unwrap();
+            let record = |wins, losses| RealizedPnl { realized_pnl: 0.0, winning_closes: wins, losing_closes: losses };
+            pnl.insert("confidence-winner".to_string(), record(9, 1));
+            pnl.insert("confidence-loser".to_string(), record(2, 8));
+            pnl.insert("confidence-new".to_string(), record(3, 0));
+        }
+        let settings = OrderSettings {
+            enable_trading: true,
+            dry_run: true,
+            confidence_scaling: Some(ConfidenceScaling { min_factor: 0.5, max_factor: 1.5, min_samples: 5 }),
+            ..OrderSettings::default()
+        };
+        let mut shares_for = |trader: &str| -> f64 {
+            let info = OrderInfo {
+                order_type: "BUY_FILL".to_string(),
+                clob_token_id: Arc::from("123456"),
+                usd_value: 1000.0,
+                shares: 2000.0,
+                price_per_share: 0.5,
+            };
+            let status = process_order(
+                &trader_event(info.clone(), trader, Some(false)), &mut client, &creds,
+                &mut guard, &mut state, Some(false), &settings,
+            );
+            assert!(status.starts_with("WOULD_SUBMIT"), "got: {}", status);
+            status.split(" @ ").next().and_then(|s| s.rsplit(' ').next()).unwrap().parse().unwrap()
+        };
+
+        let neutral = shares_for("confidence-unknown");
+        assert_eq!(shares_for("confidence-new"), neutral, "below min samples");
+        assert_eq!(shares_for("confidence-winner"), neutral * 1.5, "90% wins: capped boost");
+        assert_eq!(shares_for("confidence-loser"), neutral * 0.5, "20% wins: floored cut");
+    }
+
+    fn held(token_id: &str, net_shares: f64, avg_entry_price: Option<f64>) -> Position {
+        Position { token_id: token_id.to_string(), net_shares, avg_entry_price, trade_count: 1 }
+    }
+
+    fn bid(bid_price: f64) -> PriceInfo {
+        PriceInfo { bid_price, ask_price: bid_price + 0.01, timestamp: std::time::Instant::now() }
+    }
+
+    fn exits(stop_loss: Option<StopLoss>, take_profit: Option<TakeProfit>) -> PositionExits {
+        PositionExits { stop_loss, trailing_stop: None, take_profit, check_interval_secs: 60 }
+    }
+
+    /// A trading window that opens an hour from now, so orders are off-hours
+    fn closed_trading_hours() -> Vec<TradingWindow> {
+        use chrono::Timelike;
+
+        let now = Utc::now();
+        let minute_of_day = (now.hour() * 60 + now.minute()) as u16;
+        vec![TradingWindow { start_min: (minute_of_day + 60) % 1440, end_min: (minute_of_day + 120) % 1440 }]
+    }
+
+    #[test]
+    fn test_stop_loss_exits_positions_past_threshold() {
+        let exits = exits(Some(StopLoss { loss_pct: 20.0 }), None);
+        let prices = HashMap::from([
+            ("sl_down".to_string(), bid(0.30)),
+            ("sl_small_dip".to_string(), bid(0.45)),
+            ("sl_no_entry".to_string(), bid(0.10)),
+            ("sl_short".to_string(), bid(0.10)),
+        ]);
+        let positions = vec![
+            held("sl_down", 40.0, Some(0.50)),      // -40%: exit
+            held("sl_small_dip", 40.0, Some(0.50)), // -10%: hold
+            held("sl_no_entry", 40.0, None),        // entry unknown: hold
+            held("sl_short", -5.0, Some(0.50)),     // nothing to sell
+            held("sl_unpriced", 40.0, Some(0.50)),  // no price: hold
+        ];
+
+        let orders = position_exit_orders(positions, &prices, exits, &TrailingPeaks::default(), &HashSet::new());
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].kind, ExitKind::StopLoss);
+        assert_eq!(orders[0].position.token_id, "sl_down");
+        assert_eq!(orders[0].bid, 0.30);
+
+        let event = exit_event(&orders[0]);
+        assert_eq!(event.order.order_type, "SELL_STOP_LOSS");
+        assert_eq!(event.order.shares, 40.0);
+        assert_eq!(event.order.price_per_share, 0.30);
+    }
+
+    #[test]
+    fn test_stop_loss_no_exit_when_above_threshold() {
+        let exits = exits(Some(StopLoss { loss_pct: 20.0 }), None);
+        let prices = HashMap::from([("sl_flat".to_string(), bid(0.41)), ("sl_up".to_string(), bid(0.70))]);
+        let positions = vec![held("sl_flat", 40.0, Some(0.50)), held("sl_up", 10.0, Some(0.50))];
+
+        assert!(position_exit_orders(positions, &prices, exits, &TrailingPeaks::default(), &HashSet::new()).is_empty());
+    }
+
+    #[test]
+    fn test_take_profit_sells_fraction_past_threshold() {
+        let exits = exits(
+            Some(StopLoss { loss_pct: 20.0 }),
+            Some(TakeProfit { gain_pct: 50.0, sell_fraction: 0.5 }),
+        );
+        let prices = HashMap::from([
+            ("tp_up".to_string(), bid(0.80)),
+            ("tp_modest".to_string(), bid(0.60)),
+            ("tp_taken".to_string(), bid(0.90)),
+            ("tp_down".to_string(), bid(0.30)),
+        ]);
+        let positions = vec![
+            held("tp_up", 33.33, Some(0.50)),     // +60%: sell half
+            held("tp_modest", 40.0, Some(0.50)),  // +20%: below threshold
+            held("tp_taken", 40.0, Some(0.50)),   // +80% but already scaled out
+            held("tp_down", 40.0, Some(0.50)),    // -40%: stop-loss sells all
+        ];
+        let taken = HashSet::from(["tp_taken".to_string()]);
+
+        let orders = position_exit_orders(positions, &prices, exits, &TrailingPeaks::default(), &taken);
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].kind, ExitKind::TakeProfit);
+        assert_eq!(orders[0].position.token_id, "tp_up");
+        assert_eq!(orders[0].shares, 16.66);
+        assert_eq!(orders[1].kind, ExitKind::StopLoss);
+        assert_eq!(orders[1].shares, 40.0);
+
+        let event = exit_event(&orders[0]);
+        assert_eq!(ExitKind::from_order_type(&event.order.order_type), Some(ExitKind::TakeProfit));
+        assert_eq!(event.order.shares, 16.66);
+        assert_eq!(event.order.price_per_share, 0.80);
+    }
+
+    #[test]
+    fn test_trailing_stop_exits_on_retrace_from_peak() {
+        let exits = PositionExits {
+            trailing_stop: Some(TrailingStop { retrace_pct: 20.0 }),
+            ..exits(Some(StopLoss { loss_pct: 30.0 }), None)
+        };
+        let mut peaks = TrailingPeaks::default();
+        // Ran from 0.50 up to 0.90, now back at 0.70 (-22% off peak, still +40% on entry)
+        peaks.observe("ts_gave_back", 0.50, 0.90);
+        peaks.observe("ts_gave_back", 0.50, 0.70);
+        // Ran up to 0.80, now 0.72 (-10% off peak)
+        peaks.observe("ts_holding", 0.50, 0.80);
+        peaks.observe("ts_holding", 0.50, 0.72);
+        // Never rose: the peak is the entry, 0.45 is only -10%
+        peaks.observe("ts_flat", 0.50, 0.45);
+
+        let prices = HashMap::from([
+            ("ts_gave_back".to_string(), bid(0.70)),
+            ("ts_holding".to_string(), bid(0.72)),
+            ("ts_flat".to_string(), bid(0.45)),
+            ("ts_untracked".to_string(), bid(0.10)),
+        ]);
+        let positions = vec![
+            held("ts_gave_back", 25.0, Some(0.50)),
+            held("ts_holding", 25.0, Some(0.50)),
+            held("ts_flat", 25.0, Some(0.50)),
+            held("ts_untracked", 25.0, Some(0.50)), // no peak yet; fixed stop-loss still applies
+        ];
+
+        let orders = position_exit_orders(positions, &prices, exits, &peaks, &HashSet::new());
+        let kinds: Vec<_> = orders.iter().map(|o| (o.position.token_id.as_str(), o.kind, o.shares)).collect();
+        assert_eq!(kinds, [("ts_gave_back", ExitKind::TrailingStop, 25.0), ("ts_untracked", ExitKind::StopLoss, 25.0)]);
+    }
+
+    #[test]
+    fn test_exit_sells_exit_shares_and_needs_position() {
+        use pm_whale_follower::ApiCreds;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("trades.db").to_string_lossy().into_owned();
+        let store = TradeStore::new(&db_path).unwrap();
+        store.record_trade(TradeRecord {
+            timestamp_ms: 1_700_000_000_000,
+            block_number: 1,
+            tx_hash: "0x1".to_string(),
+            trader_address: "abc".to_string(),
+            token_id: "sl_held".to_string(),
+            side: "BUY".to_string(),
+            whale_shares: 1000.0,
+            whale_price: 0.5,
+            whale_usd: 500.0,
+            our_shares: Some(37.5),
+            our_price: Some(0.5),
+            our_usd: Some(18.75),
+            fill_pct: Some(100.0),
+            status: "SUCCESS".to_string(),
+            latency_ms: None,
+            is_live: None,
+            aggregation_count: None,
+            aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
+        }).unwrap();
+        store.flush().unwrap();
+
+        let mut client = RustClobClient::new(
+            "http://127.0.0.1:1", 137,
+            "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef", None,
+        ).unwrap();
+        let api_creds = ApiCreds {
+            api_key: "key".to_string(),
+            api_secret: "c2VjcmV0".to_string(),
+            api_passphrase: "pass".to_string(),
+        };
+        let creds = SharedCreds::new(PreparedCreds::from_api_creds(&api_creds).unwrap(), None, CREDS_REFRESH_MIN_INTERVAL);
+        let mut guard = RiskGuard::new(RiskGuardConfig::default());
+        let mut state = OrderWorkerState { breaker: ExchangeBreaker::new(1, Duration::from_secs(60)), ..OrderWorkerState::default() };
+        // Copy filters a whale SELL this small and cheap would fail, and it is off-hours for a
+        // blocklisted token; the exit ignores all of it
+        let settings = OrderSettings {
+            enable_trading: true,
+            db_path: Some(db_path.clone()),
+            dry_run: true,
+            copy_price_range: CopyPriceRange { min: Some(0.40), max: None },
+            trading_hours: closed_trading_hours(),
+            token_blocklist: HashSet::from(["sl_held".to_string()]),
+            ..OrderSettings::default()
+        };
+
+        let mut exit = |kind: ExitKind, token: &str, shares: f64| {
+            let evt = exit_event(&ExitOrder { kind, position: held(token, 37.5, Some(0.5)), bid: 0.30, shares });
+            process_order(
+                &evt,
+                &mut client, &creds, &mut guard, &mut state, Some(false), &settings,
+            )
+        };
+
+        // Stop-loss: the whole position
+        let status = exit(ExitKind::StopLoss, "sl_held", 37.5);
+        assert!(status.starts_with("WOULD_SUBMIT"), "got: {}", status);
+        assert!(status.contains("SELL GTD 37.50 @"), "got: {}", status);
+
+        // Partial take-profit: just the scaled-out shares
+        let status = exit(ExitKind::TakeProfit, "sl_held", 18.75);
+        assert!(status.contains("SELL GTD 18.75 @"), "got: {}", status);
+
+        assert_eq!(exit(ExitKind::StopLoss, "sl_not_held", 37.5), "SKIPPED_NO_POSITION");
+    }
+
+    #[test]
+    fn test_flatten_closes_longs_and_shorts_in_full() {
+        use pm_whale_follower::ApiCreds;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("trades.db").to_string_lossy().into_owned();
+        let store = TradeStore::new(&db_path).unwrap();
+        let trade = |token: &str, side: &str, shares: f64| TradeRecord {
+            timestamp_ms: 1_700_000_000_000,
+            block_number: 1,
+            tx_hash: format!("0x{}", token),
+            trader_address: "abc".to_string(),
+            token_id: token.to_string(),
+            side: side.to_string(),
+            whale_shares: 1000.0,
+            whale_price: 0.5,
+            whale_usd: 500.0,
+            our_shares: Some(shares),
+            our_price: Some(0.5),
+            our_usd: Some(shares * 0.5),
+            fill_pct: Some(100.0),
+            status: "SUCCESS".to_string(),
+            latency_ms: None,
+            is_live: None,
+            aggregation_count: None,
+            aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
+        };
+        store.record_trade(trade("flat_long", "BUY", 37.5)).unwrap();
+        // More sold than recorded as bought: net short
+        store.record_trade(trade("flat_short", "SELL", 12.5)).unwrap();
+        store.flush().unwrap();
+
+        let mut client = RustClobClient::new(
+            "http://127.0.0.1:1", 137,
+            "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef", None,
+        ).unwrap();
+        let api_creds = ApiCreds {
+            api_key: "key".to_string(),
+            api_secret: "c2VjcmV0".to_string(),
+            api_passphrase: "pass".to_string(),
+        };
+        let creds = SharedCreds::new(PreparedCreds::from_api_creds(&api_creds).unwrap(), None, CREDS_REFRESH_MIN_INTERVAL);
+        let mut guard = RiskGuard::new(RiskGuardConfig::default());
+        let mut state = OrderWorkerState { breaker: ExchangeBreaker::new(1, Duration::from_secs(60)), ..OrderWorkerState::default() };
+        // Copy sizing and decay would shrink a BUY; a flatten covers the short exactly
+        let settings = OrderSettings {
+            enable_trading: true,
+            db_path: Some(db_path.clone()),
+            dry_run: true,
+            scaling_ratio: 0.02,
+            copy_decay: Some(CopyDecay { factor: 0.5 }),
+            ..OrderSettings::default()
+        };
+
+        let mut orders = flatten_orders(&store.get_positions().unwrap());
+        orders.sort_by(|a, b| a.token_id.cmp(&b.token_id));
+        let statuses: Vec<String> = orders.iter().map(|order| {
+            let evt = flatten_event(order, 0.30);
+            process_order(
+                &evt,
+                &mut client, &creds, &mut guard, &mut state, Some(false), &settings,
+            )
+        }).collect();
+
+        assert!(statuses[0].contains("SELL GTD 37.50 @"), "got: {}", statuses[0]);
+        assert!(statuses[1].contains("BUY GTD 12.50 @"), "got: {}", statuses[1]);
+
+        // Filled flatten orders are recorded as FLATTEN on their own side, against the trader whose lots they sell
+        let openers = store.get_open_shares_by_trader().unwrap();
+        let evt = flatten_event(&orders[0], 0.30);
+        let records = closing_trade_records(&evt, "200 OK [SCALED] | 37.50/37.50 filled @ 0.29 | whale 37.5 @ 0.30", Some(false), &openers["flat_long"]);
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(
+            (record.trader_address.as_str(), record.side.as_str(), record.status.as_str(), record.our_shares),
+            ("abc", "SELL", FLATTEN_STATUS, Some(37.5))
+        );
+        let evt = flatten_event(&orders[1], 0.30);
+        let records = closing_trade_records(&evt, "200 OK [SCALED] | 12.50/12.50 filled @ 0.31 | whale 12.5 @ 0.30", Some(false), &[]);
+        assert_eq!((records[0].side.as_str(), records[0].status.as_str(), records[0].our_shares), ("BUY", FLATTEN_STATUS, Some(12.5)));
+        assert!(closing_trade_records(&evt, &statuses[1], Some(false), &[]).is_empty(), "DRY_RUN places nothing");
+    }
+
+    #[test]
+    fn test_flatten_ignores_the_copy_gates() {
+        use pm_whale_follower::ApiCreds;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("trades.db").to_string_lossy().into_owned();
+        let store = TradeStore::new(&db_path).unwrap();
+        let trade = |token: &str, side: &str, shares: f64| TradeRecord {
+            timestamp_ms: 1_700_000_000_000,
+            block_number: 1,
+            tx_hash: format!("0x{}", token),
+            trader_address: "abc".to_string(),
+            token_id: token.to_string(),
+            side: side.to_string(),
+            whale_shares: 1000.0,
+            whale_price: 0.5,
+            whale_usd: 500.0,
+            our_shares: Some(shares),
+            our_price: Some(0.5),
+            our_usd: Some(shares * 0.5),
+            fill_pct: Some(100.0),
+            status: "SUCCESS".to_string(),
+            latency_ms: None,
+            is_live: None,
+            aggregation_count: None,
+            aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
+        };
+        store.record_trade(trade("gated_long", "BUY", 37.5)).unwrap();
+        store.record_trade(trade("gated_short", "SELL", 12.5)).unwrap();
+        store.flush().unwrap();
+
+        let mut client = RustClobClient::new(
+            "http://127.0.0.1:1", 137,
+            "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef", None,
+        ).unwrap();
+        let api_creds = ApiCreds {
+            api_key: "key".to_string(),
+            api_secret: "c2VjcmV0".to_string(),
+            api_passphrase: "pass".to_string(),
+        };
+        let creds = SharedCreds::new(PreparedCreds::from_api_creds(&api_creds).unwrap(), None, CREDS_REFRESH_MIN_INTERVAL);
+        let mut guard = RiskGuard::new(RiskGuardConfig::default());
+        // Open breaker, off-hours, both tokens blocklisted and Kelly without a bankroll: a copy would stop at each
+        let mut state = OrderWorkerState { breaker: ExchangeBreaker::new(1, Duration::from_secs(60)), ..OrderWorkerState::default() };
+        state.breaker.record_failure(std::time::Instant::now());
+        let settings = OrderSettings {
+            enable_trading: true,
+            db_path: Some(db_path.clone()),
+            dry_run: true,
+            trading_hours: closed_trading_hours(),
+            token_blocklist: HashSet::from(["gated_long".to_string(), "gated_short".to_string()]),
+            kelly_sizing: Some(KellySizing::DEFAULT),
+            ..OrderSettings::default()
+        };
+
+        let mut orders = flatten_orders(&store.get_positions().unwrap());
+        orders.sort_by(|a, b| a.token_id.cmp(&b.token_id));
+        let statuses: Vec<String> = orders.iter().map(|order| {
+            let evt = flatten_event(order, 0.30);
+            process_order(
+                &evt,
+                &mut client, &creds, &mut guard, &mut state, Some(false), &settings,
+            )
+        }).collect();
+
+        assert!(statuses[0].contains("SELL GTD 37.50 @"), "got: {}", statuses[0]);
+        assert!(statuses[1].contains("BUY GTD 12.50 @"), "got: {}", statuses[1]);
+    }
+
+    #[test]
+    fn test_split_by_openers_shares_the_fill_by_open_lots() {
+        let evt = exit_event(&ExitOrder {
+            kind: ExitKind::StopLoss,
+            position: held("split-tok", 40.0, Some(0.50)),
+            bid: 0.30,
+            shares: 40.0,
+        });
+        let openers = vec![("0xa".to_string(), 30.0), ("0xb".to_string(), 10.0)];
+        let records = closing_trade_records(&evt, "200 OK [SCALED] | 40.00/40.00 filled @ 0.30 | whale 40.0 @ 0.30", Some(false), &openers);
+
+        let split: Vec<(&str, Option<f64>, Option<f64>)> = records.iter()
+            .map(|r| (r.trader_address.as_str(), r.our_shares, r.our_usd))
+            .collect();
+        assert_eq!(split, vec![("0xa", Some(30.0), Some(9.0)), ("0xb", Some(10.0), Some(3.0))]);
+        assert!(records.iter().all(|r| r.side == "SELL" && r.status == "STOP_LOSS" && r.our_price == Some(0.30)));
+
+        // Nobody's lots to close: one unattributed row
+        let records = closing_trade_records(&evt, "200 OK [SCALED] | 40.00/40.00 filled @ 0.30 | whale 40.0 @ 0.30", Some(false), &[]);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].trader_address, "");
+    }
+
+    #[test]
+    fn test_submit_interval_check() {
+        let mut times = SubmitTimes::default();
+        let interval = Duration::from_millis(500);
+        let t0 = std::time::Instant::now();
+
+        assert_eq!(submit_interval_check(&mut times, "tok", interval, t0), Ok(()));
+        assert_eq!(submit_interval_check(&mut times, "tok", interval, t0 + Duration::from_millis(200)), Err(Duration::from_millis(300)));
+        assert_eq!(submit_interval_check(&mut times, "other", interval, t0 + Duration::from_millis(200)), Ok(()));
+        // Deferred attempts don't push the window out
+        assert_eq!(submit_interval_check(&mut times, "tok", interval, t0 + Duration::from_millis(500)), Ok(()));
+
+        // Zero interval: no limit, nothing recorded
+        let mut unlimited = SubmitTimes::default();
+        assert_eq!(submit_interval_check(&mut unlimited, "tok", Duration::ZERO, t0), Ok(()));
+        assert_eq!(submit_interval_check(&mut unlimited, "tok", Duration::ZERO, t0), Ok(()));
+        assert!(unlimited.is_empty());
+    }
+
+    #[test]
+    fn test_deferred_wait() {
+        assert_eq!(deferred_wait("DEFERRED_RATE_LIMIT (120ms)"), Some(Duration::from_millis(120)));
+        assert_eq!(deferred_wait("WOULD_SUBMIT [Scaled] | BUY FAK 20.00 @ 0.51"), None);
+        assert_eq!(deferred_wait("DEFERRED_RATE_LIMIT"), None);
+    }
+
+    /// Dry-run order worker with MIN_SUBMIT_INTERVAL_MS = `interval_ms`
+    fn dry_run_engine(interval_ms: u64) -> OrderEngine {
+        let settings = OrderSettings {
+            dry_run: true,
+            min_submit_interval: Duration::from_millis(interval_ms),
+            ..OrderSettings::default()
+        };
+        engine_with(settings, None)
+    }
+
+    /// Order worker against an unreachable CLOB, sending shadow rows to `shadow_tx`
+    fn engine_with(settings: OrderSettings, shadow_tx: Option<mpsc::UnboundedSender<TradeRecord>>) -> OrderEngine {
+        use pm_whale_follower::ApiCreds;
+
+        let client = RustClobClient::new(
+            "http://127.0.0.1:1", 137,
+            "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef", None,
+        ).unwrap();
+        let api_creds = ApiCreds {
+            api_key: "key".to_string(),
+            api_secret: "c2VjcmV0".to_string(),
+            api_passphrase: "pass".to_string(),
+        };
+        let creds = SharedCreds::new(PreparedCreds::from_api_creds(&api_creds).unwrap(), None, CREDS_REFRESH_MIN_INTERVAL);
+        let (tx, rx) = mpsc::channel(16);
+        let (resubmit_tx, _resubmit_rx) = mpsc::unbounded_channel();
+        let (gtd_watch_tx, _gtd_watch_rx) = mpsc::unbounded_channel();
+        let state = OrderWorkerState {
+            breaker: ExchangeBreaker::new(1, Duration::from_secs(60)),
+            resubmit_tx: Some(resubmit_tx.clone()),
+            gtd_watch_tx: Some(gtd_watch_tx),
+            ..OrderWorkerState::default()
+        };
+        start_order_worker(
+            rx, Arc::new(client), Arc::new(creds),
+            Arc::new(std::sync::Mutex::new(RiskGuard::new(RiskGuardConfig::default()))),
+            state, OrderSettings { enable_trading: true, ..settings }, shadow_tx,
+        );
+        OrderEngine { tx, resubmit_tx, enable_trading: true, trading_paused: Arc::new(AtomicBool::new(false)), reply_timeouts: OrderReplyTimeouts::default() }
+    }
+
+    fn buy_event(token: &str) -> ParsedEvent {
+        ParsedEvent {
+            block_number: 1,
+            tx_hash: "0xabc".to_string(),
+            trader_address: "0xtrader".to_string(),
+            trader_label: String::new(),
+            trader_min_shares: 0.0,
+            trader_max_bet_usd: None,
+            trader_scaling_ratio: 0.02,
+            trader_probabilistic_sizing: Some(false),
+            trader_copy_price_range: CopyPriceRange::default(),
+            trader_observe: false,
+            order: OrderInfo {
+                order_type: "BUY_FILL".to_string(),
+                clob_token_id: Arc::from(token),
+                usd_value: 500.0,
+                shares: 1000.0,
+                price_per_share: 0.5,
+            },
+        }
+    }
+
+    /// `info` as a fill from `trader`, with its traders.json probabilistic_sizing override
+    fn trader_event(info: OrderInfo, trader: &str, probabilistic_sizing: Option<bool>) -> ParsedEvent {
+        ParsedEvent {
+            trader_address: trader.to_string(),
+            trader_probabilistic_sizing: probabilistic_sizing,
+            order: info,
+            ..buy_event("")
+        }
+    }
+
+    /// The blocking CLOB client can't be built inside a runtime, so these drive their own
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(future)
+    }
+
+    #[test]
+    fn test_closing_market_skips_buys_but_not_sells() {
+        let now_ms = Utc::now().timestamp_millis();
+        let status = |end_ms: i64, closed: bool| market_cache::MarketStatus {
+            end_ms: Some(end_ms),
+            closed,
+            accepting_orders: true,
+            fetched_at: now_ms as u64 / 1000,
+        };
+        let caches = market_cache::global_caches();
+        caches.set_market_status("closing-resolved".to_string(), status(now_ms - 60_000, true));
+        caches.set_market_status("closing-soon".to_string(), status(now_ms + 120_000, false));
+        caches.set_market_status("closing-later".to_string(), status(now_ms + 3_600_000, false));
+
+        let settings = OrderSettings {
+            dry_run: true,
+            market_close_window: Duration::from_secs(600),
+            ..OrderSettings::default()
+        };
+        let engine = engine_with(settings, None);
+        block_on(async {
+            let buy = engine.submit(buy_event("closing-resolved"), Some(false)).await;
+            assert_eq!(buy, "SKIPPED_MARKET_CLOSING (closed)");
+            let buy = engine.submit(buy_event("closing-soon"), Some(false)).await;
+            assert!(buy.starts_with("SKIPPED_MARKET_CLOSING (ends in 1"), "got: {}", buy);
+            let buy = engine.submit(buy_event("closing-later"), Some(false)).await;
+            assert!(buy.starts_with("WOULD_SUBMIT"), "got: {}", buy);
+
+            // Exits from a resolving market still go out
+            let mut sell = buy_event("closing-resolved");
+            sell.order.order_type = "SELL_FILL".to_string();
+            let sell = engine.submit(sell, Some(false)).await;
+            assert!(sell.starts_with("WOULD_SUBMIT"), "got: {}", sell);
+        });
+    }
+
+    #[test]
+    fn test_post_only_rests_at_whale_price() {
+        let mut big = buy_event("post-only-tok");
+        big.order.shares = 5000.0;
+        big.order.usd_value = 2500.0;
+
+        // Normally the 4000+ tier crosses with a FAK one tick above the whale
+        let crossing = dry_run_engine(0);
+        let status = block_on(crossing.submit(big.clone(), Some(false)));
+        assert!(status.contains("| BUY FAK 125.00 @ 0.51"), "got: {}", status);
+
+        let settings = OrderSettings { dry_run: true, post_only: true, ..OrderSettings::default() };
+        let engine = engine_with(settings, None);
+        block_on(async {
+            let status = engine.submit(big.clone(), Some(false)).await;
+            assert!(status.contains("| BUY GTD post-only 125.00 @ 0.50 (expires "), "got: {}", status);
+
+            // Position exits still cross
+            let mut exit = big.clone();
+            exit.order.order_type = ExitKind::StopLoss.order_type().to_string();
+            let status = engine.submit(exit, Some(false)).await;
+            assert!(!status.contains("post-only"), "got: {}", status);
+        });
+
+        let (shares, price, usd, fill_pct, category) =
+            parse_status_for_db("POST_ONLY [SCALED] | resting 125.00 @ 0.50 | whale 5000.0 @ 0.50 | order 0xabc");
+        assert_eq!((shares, price, usd, fill_pct), (None, None, None, None));
+        assert_eq!(category, POST_ONLY_STATUS);
+    }
+
+    #[test]
+    fn test_shadow_orders_are_recorded_but_never_submitted() {
+        let (shadow_tx, mut shadow_rx) = mpsc::unbounded_channel();
+        let settings = OrderSettings {
+            dry_run: true,
+            shadow: Some(ShadowStrategy { scaling_ratio: None, kelly_sizing: None, flat_usd: Some(10.0) }),
+            ..OrderSettings::default()
+        };
+        let engine = engine_with(settings, Some(shadow_tx));
+
+        // The worker only reports the live order, sized as usual
+        let status = block_on(engine.submit(buy_event("shadow-1"), Some(false)));
+        assert!(status.starts_with("WOULD_SUBMIT"), "got: {}", status);
+        let live_price: f64 = status.split(" @ ").nth(1).and_then(|s| s.split_whitespace().next()).unwrap().parse().unwrap();
+
+        let shadow = shadow_rx.blocking_recv().unwrap();
+        assert_eq!(shadow.status, SHADOW_STATUS);
+        assert_eq!(shadow.order_id, None);
+        assert_eq!(shadow.our_price, Some(live_price));
+        assert!((shadow.our_usd.unwrap() - 10.0).abs() < 0.01, "{:?}", shadow.our_usd);
+
+        // Stored apart from live trades: no position, no live fill
+        let store = TradeStore::new(":memory:").unwrap();
+        store.insert_trade(&shadow).unwrap();
+        assert_eq!(store.get_trade_count().unwrap(), 0);
+        assert!(store.get_positions().unwrap().is_empty());
+        assert_eq!(store.get_shadow_fills_before(i64::MAX).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_shadow_sells_from_its_own_holdings() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("trades.db");
+        let store = TradeStore::new(&db).unwrap();
+        let shadow = ShadowStrategy { scaling_ratio: Some(0.05), kelly_sizing: None, flat_usd: None };
+        let settings = OrderSettings { shadow: Some(shadow), ..OrderSettings::default() };
+        let buy = buy_event("shadow-sell");
+        let mut sell = buy_event("shadow-sell");
+        sell.order.order_type = "SELL_FILL".to_string();
+        sell.order.shares = 500.0;
+
+        // Nothing bought by the shadow yet, whatever the live side holds
+        assert_eq!(shadow_order(&sell, &shadow, &settings, db.to_str(), None), None);
+
+        let (bought, price) = shadow_order(&buy, &shadow, &settings, db.to_str(), None).unwrap();
+        store.insert_trade(&shadow_record(&buy, bought, price, None)).unwrap();
+        // The whale's BUY as the live side recorded it (skipped, but the whale position counts)
+        store.insert_trade(&TradeRecord {
+            status: "SKIPPED_SMALL".to_string(),
+            our_shares: None,
+            our_price: None,
+            our_usd: None,
+            ..shadow_record(&buy, 0.0, 0.0, None)
+        }).unwrap();
+
+        // The whale sells half its position: so does the shadow
+        let (sold, _) = shadow_order(&sell, &shadow, &settings, db.to_str(), None).unwrap();
+        assert_eq!(sold, ((bought / 2.0) * 100.0).floor() / 100.0);
+    }
+
+    #[test]
+    fn test_same_token_burst_is_deferred_to_aggregator() {
+        let engine = dry_run_engine(60_000);
+        block_on(async {
+            let aggregator = Arc::new(Mutex::new(TradeAggregator::new(AggregationConfig::default())));
+
+            let first = submit_with_deferral(&engine, buy_event("submit-limit-1"), Some(false), Some(&aggregator)).await;
+            assert!(first.starts_with("WOULD_SUBMIT"), "got: {}", first);
+            let second = submit_with_deferral(&engine, buy_event("submit-limit-1"), Some(false), Some(&aggregator)).await;
+            assert_eq!(second, AGG_DEFERRED_STATUS);
+            assert_eq!(aggregator.lock().await.pending_count(), 1);
+
+            // Other tokens aren't limited
+            let other = submit_with_deferral(&engine, buy_event("submit-limit-2"), Some(false), Some(&aggregator)).await;
+            assert!(other.starts_with("WOULD_SUBMIT"), "got: {}", other);
+        });
+    }
+
+    #[test]
+    fn test_spaced_submissions_both_proceed() {
+        let engine = dry_run_engine(100);
+        block_on(async {
+            let first = engine.submit(buy_event("submit-limit-3"), Some(false)).await;
+            assert!(first.starts_with("WOULD_SUBMIT"), "got: {}", first);
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            let second = engine.submit(buy_event("submit-limit-3"), Some(false)).await;
+            assert!(second.starts_with("WOULD_SUBMIT"), "got: {}", second);
+
+            // Without an aggregator a burst waits out the interval instead of being dropped
+            let started = std::time::Instant::now();
+            let third = submit_with_deferral(&engine, buy_event("submit-limit-3"), Some(false), None).await;
+            assert!(third.starts_with("WOULD_SUBMIT"), "got: {}", third);
+            assert!(started.elapsed() >= Duration::from_millis(50));
+        });
+    }
+
+    #[test]
+    fn test_slow_worker_times_out_and_late_reply_is_discarded() {
+        block_on(async {
+            let (tx, mut rx) = mpsc::channel(16);
+            let (resubmit_tx, _resubmit_rx) = mpsc::unbounded_channel();
+            let reply_timeouts = OrderReplyTimeouts { large: Duration::from_millis(300), small: Duration::from_millis(30) };
+            let engine = OrderEngine { tx, resubmit_tx, enable_trading: true, trading_paused: Arc::new(AtomicBool::new(false)), reply_timeouts };
+
+            let started = std::time::Instant::now();
+            assert_eq!(engine.submit(buy_event("reply-timeout"), Some(false)).await, "WORKER_TIMEOUT");
+            assert!(started.elapsed() < Duration::from_millis(300), "small trade uses the short timeout");
+
+            // The worker still gets the order; its late reply goes nowhere
+            let work = rx.recv().await.unwrap();
+            assert!(work.respond_to.send("200 OK".to_string()).is_err());
+
+            let mut large = buy_event("reply-timeout");
+            large.order.shares = 5000.0;
+            let started = std::time::Instant::now();
+            assert_eq!(engine.submit(large, Some(false)).await, "WORKER_TIMEOUT");
+            assert!(started.elapsed() >= Duration::from_millis(300), "large trade waits longer");
+        });
+    }
+
+    /// Legacy TARGET_WHALE_ADDRESS events carry no per-trader bounds, and the default config filters nothing
+    #[test]
+    fn test_legacy_path_has_no_price_filter() {
+        unsafe {
+            std::env::set_var("TARGET_WHALE_ADDRESS", "def456def456789012345678901234567890def4");
+        }
+        let message = serde_json::json!({
+            "params": {
+                "result": {
+                    "topics": [
+                        ORDERS_FILLED_EVENT_SIGNATURE,
+                        "0x0000000000000000000000000000000000000000000000000000000000000000",
+                        "0x000000000000000000000000def456def456789012345678901234567890def4"
+                    ],
+                    "data": "0x00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000123456000000000000000000000000000000000000000000000000000000000000f4240000000000000000000000000000000000000000000000000000000000007a120",
+                    "blockNumber": "0x1234",
+                    "transactionHash": "0xabcdef"
+                }
+            }
+        }).to_string();
+
+        let event = parse_event(message, None).unwrap();
+        assert_eq!(event.trader_copy_price_range, CopyPriceRange::default());
+        let effective = OrderSettings::default().copy_price_range.with_trader(event.trader_copy_price_range);
+        assert!(effective.contains(0.01) && effective.contains(0.99));
+    }
+
+    #[test]
+    fn test_exchange_outcome_classification() {
+        assert_eq!(exchange_outcome(200, "{}"), Some(true));
+        assert_eq!(exchange_outcome(401, "Unauthorized/Invalid api key"), Some(false));
+        assert_eq!(exchange_outcome(500, "internal error"), Some(false));
+        assert_eq!(exchange_outcome(400, "not enough balance / allowance"), Some(false));
+        // Unfilled FAK/FOK is a market outcome, not an exchange failure
+        assert_eq!(exchange_outcome(400, "no orders found to match with FAK order"), None);
+        assert_eq!(exchange_outcome(400, "order couldn't be fully filled. FOK orders are fully filled or killed."), None);
+    }
+
+    #[test]
+    fn test_exchange_breaker_trips_on_rejections() {
+        let now = std::time::Instant::now();
+        let mut breaker = ExchangeBreaker::new(3, Duration::from_secs(30));
+        // FAK no-match responses in between neither trip nor reset the breaker
+        for (status, body) in [(500, "down"), (400, "no orders found to match with FAK order"), (401, "bad key"), (503, "down")] {
+            match exchange_outcome(status, body) {
+                Some(true) => { breaker.record_success(); }
+                Some(false) => { breaker.record_failure(now); }
+                None => {}
+            }
+        }
+        assert!(breaker.open_remaining(now).is_some());
+        assert!(breaker.open_remaining(now + Duration::from_secs(30)).is_none());
+    }
+
+    #[test]
+    fn test_parse_status_for_db_circuit_open() {
+        // The countdown isn't part of the stored status
+        for status in ["CIRCUIT_OPEN (45s left)", "CIRCUIT_OPEN (3s left)"] {
+            assert_eq!(parse_status_for_db(status), (None, None, None, None, "CIRCUIT_OPEN".to_string()));
+        }
+    }
+
+    #[test]
+    fn test_token_list_status() {
+        let none = HashSet::new();
+        let only_a = HashSet::from(["a".to_string()]);
+        let only_b = HashSet::from(["b".to_string()]);
+
+        // Empty lists are no-ops
+        assert_eq!(token_list_status("a", &none, &none), None);
+        // Blocklist only
+        assert_eq!(token_list_status("a", &only_a, &none), Some("SKIPPED_BLOCKLIST"));
+        assert_eq!(token_list_status("b", &only_a, &none), None);
+        // Allowlist only
+        assert_eq!(token_list_status("a", &none, &only_a), None);
+        assert_eq!(token_list_status("b", &none, &only_a), Some("SKIPPED_NOT_ALLOWED"));
+        // Both: blocklist takes precedence
+        assert_eq!(token_list_status("a", &only_a, &only_a), Some("SKIPPED_BLOCKLIST"));
+        assert_eq!(token_list_status("b", &only_a, &only_b), None);
+        assert_eq!(token_list_status("c", &only_a, &only_b), Some("SKIPPED_NOT_ALLOWED"));
+    }
+
+    #[test]
+    fn test_neg_risk_status() {
+        // Routing mode: never looks the token up
+        assert_eq!(neg_risk_status(false, || panic!("lookup with skipping disabled")), None);
+        // Skip mode
+        assert_eq!(neg_risk_status(true, || Ok(true)), Some("SKIPPED_NEGRISK"));
+        assert_eq!(neg_risk_status(true, || Ok(false)), None);
+        // Lookup failure doesn't block the order
+        assert_eq!(neg_risk_status(true, || Err(anyhow!("timeout"))), None);
+    }
+
+    #[test]
+    fn test_fit_market_exposure_under_cap() {
+        // $50 held + $10 order under a $100 cap
+        assert_eq!(fit_market_exposure(50.0, 20.0, 0.50, 100.0, SizeFloor::default()), Some(20.0));
+        // No position yet
+        assert_eq!(fit_market_exposure(0.0, 100.0, 0.50, 100.0, SizeFloor::default()), Some(100.0));
+    }
+
+    #[test]
+    fn test_fit_market_exposure_exactly_at_cap() {
+        // $90 held + $10 order lands exactly on the cap
+        assert_eq!(fit_market_exposure(90.0, 20.0, 0.50, 100.0, SizeFloor::default()), Some(20.0));
+        // Already at the cap: nothing more fits
+        assert_eq!(fit_market_exposure(100.0, 20.0, 0.50, 100.0, SizeFloor::default()), None);
+    }
+
+    #[test]
+    fn test_fit_market_exposure_over_cap() {
+        // $80 held + $50 order: trimmed to the $20 of room (40 shares)
+        assert_eq!(fit_market_exposure(80.0, 100.0, 0.50, 100.0, SizeFloor::default()), Some(40.0));
+        // $98 held: $2 of room is 4 shares, under the 5-share minimum
+        assert_eq!(fit_market_exposure(98.0, 100.0, 0.50, 100.0, SizeFloor::default()), None);
+        // Position already worth more than the cap
+        assert_eq!(fit_market_exposure(150.0, 10.0, 0.50, 100.0, SizeFloor::default()), None);
+    }
+
+    #[test]
+    fn test_open_positions_check_limits_new_tokens() {
+        let mut cache = None;
+        let held = || Ok(HashMap::from([("token1".to_string(), 10.0), ("token2".to_string(), 5.0)]));
+
+        // Under the limit: a new token opens
+        assert_eq!(open_positions_check(&mut cache, "token3", 3, held), Ok(()));
+        note_open_position(&mut cache, "token3", 20.0);
+
+        // At the limit: the 4th new token is skipped, adding to a held one is fine
+        let no_reload = || -> anyhow::Result<HashMap<String, f64>> { panic!("cache should be reused") };
+        assert_eq!(open_positions_check(&mut cache, "token4", 3, no_reload), Err(3));
+        assert_eq!(open_positions_check(&mut cache, "token1", 3, no_reload), Ok(()));
+        assert_eq!(open_positions_check(&mut cache, "token3", 3, no_reload), Ok(()));
+
+        // The exposure cap reads held shares from the same cache, including fresh fills
+        note_open_position(&mut cache, "token1", 2.5);
+        let mut shares = |token: &str| with_open_positions(&mut cache, no_reload, |held| held.get(token).copied().unwrap_or(0.0));
+        assert_eq!(shares("token1"), Some(12.5));
+        assert_eq!(shares("token3"), Some(20.0));
+        assert_eq!(shares("token4"), Some(0.0));
+    }
+
+    #[test]
+    fn test_open_positions_check_allows_on_load_failure() {
+        let mut cache = None;
+        assert_eq!(open_positions_check(&mut cache, "token1", 1, || Err(anyhow::anyhow!("db locked"))), Ok(()));
+    }
+
+    #[test]
+    fn test_claim_prune_runs_once_per_interval() {
+        let mut last = None;
+        assert!(claim_prune(&mut last));
+        assert!(!claim_prune(&mut last));
+
+        // A prune older than the interval is due again
+        last = std::time::Instant::now().checked_sub(PRUNE_INTERVAL);
+        assert!(claim_prune(&mut last));
+    }
+
+    #[test]
+    fn test_calculate_sell_size_full_exit() {
+        // Whale sells its whole 1000-share position: we sell all 40.5 of ours
+        let (shares, size_type) = calculate_sell_size(1000.0, Some(1000.0), 40.5, (20.0, SizeType::Scaled));
+        assert!((shares - 40.5).abs() < 1e-9);
+        assert!(matches!(size_type, SizeType::ProportionalSell));
+    }
+
+    #[test]
+    fn test_calculate_sell_size_half_exit() {
+        let (shares, size_type) = calculate_sell_size(500.0, Some(1000.0), 40.0, (20.0, SizeType::Scaled));
+        assert!((shares - 20.0).abs() < 1e-9);
+        assert!(matches!(size_type, SizeType::ProportionalSell));
+    }
+
+    #[test]
+    fn test_calculate_sell_size_oversell_clamp() {
+        // Whale sells more than we saw it buy: treated as a full exit
+        let (shares, _) = calculate_sell_size(1500.0, Some(1000.0), 40.567, (20.0, SizeType::Scaled));
+        assert!((shares - 40.56).abs() < 1e-9, "clamped to held shares floored to 0.01");
+
+        // Unknown whale position: buy-style size, but never more than we hold
+        let (shares, size_type) = calculate_sell_size(5000.0, None, 12.0, (100.0, SizeType::Scaled));
+        assert!((shares - 12.0).abs() < 1e-9);
+        assert!(matches!(size_type, SizeType::Scaled));
+
+        // Whale position net short/flat in our history: fall back as well
+        let (shares, _) = calculate_sell_size(500.0, Some(0.0), 40.0, (10.0, SizeType::Scaled));
+        assert!((shares - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_persistence_stop_flushes_buffered_trades() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("trades.db").to_string_lossy().into_owned();
+
+        let (tx, rx) = mpsc::unbounded_channel::<TradeRecord>();
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let worker_path = db_path.clone();
+        let recent_trades = Arc::new(RecentTrades::new(10, 0));
+        let worker_recent_trades = Arc::clone(&recent_trades);
+        let worker = std::thread::spawn(move || persistence_worker(rx, stop_rx, &worker_path, Some(worker_recent_trades)));
+
+        // Fewer than the store's buffer size, so nothing is written until the flush
+        for i in 0..3 {
+            tx.send(TradeRecord {
+                timestamp_ms: 1_700_000_000_000 + i,
+                block_number: 1,
+                tx_hash: format!("0x{}", i),
+                trader_address: "abc".to_string(),
+                token_id: "tok".to_string(),
+                side: "BUY".to_string(),
+                whale_shares: 100.0,
+                whale_price: 0.5,
+                whale_usd: 50.0,
+                our_shares: None,
+                our_price: None,
+                our_usd: None,
+                fill_pct: None,
+                status: "SKIPPED".to_string(),
+                latency_ms: None,
+                is_live: None,
+                aggregation_count: None,
+                aggregation_window_ms: None,
+                order_id: None,
+                contributing_traders: None,
+            }).unwrap();
+        }
+
+        // Senders are still alive (as in the running bot); the stop request alone flushes
+        let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        rt.block_on(PersistenceHandle { stop_tx }.flush_and_stop());
+        worker.join().unwrap();
+
+        let store = TradeStore::new(&db_path).unwrap();
+        assert_eq!(store.get_recent_trades(10).unwrap().len(), 3);
+        assert_eq!(recent_trades.len(), 3, "the /trades buffer sees what the worker records");
+        drop(tx);
+    }
+
+    #[tokio::test]
+    async fn test_unwritable_db_spills_trades_to_fallback_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("trades.db").to_string_lossy().into_owned();
+        let store = TradeStore::with_buffer_size(&db_path, 2).unwrap();
+        // Every insert fails, as on a full disk
+        rusqlite::Connection::open(&db_path).unwrap().execute_batch(
+            "CREATE TRIGGER fail_insert BEFORE INSERT ON trades BEGIN SELECT RAISE(FAIL, 'disk full'); END;"
+        ).unwrap();
+
+        let trade = |i: i64| TradeRecord {
+            timestamp_ms: 1_700_000_000_000 + i,
+            block_number: 1,
+            tx_hash: format!("0x{}", i),
+            trader_address: "abc".to_string(),
+            token_id: "tok".to_string(),
+            side: "BUY".to_string(),
+            whale_shares: 100.0,
+            whale_price: 0.5,
+            whale_usd: 50.0,
+            our_shares: Some(10.0),
+            our_price: Some(0.5),
+            our_usd: Some(5.0),
+            fill_pct: Some(100.0),
+            status: "SUCCESS".to_string(),
+            latency_ms: None,
+            is_live: Some(true),
+            aggregation_count: None,
+            aggregation_window_ms: None,
+            order_id: Some(format!("0xorder{}", i)),
+            contributing_traders: None,
+        };
+        store.record_trade(trade(1)).unwrap();
+        // The auto-flush fails and keeps both trades
+        assert!(store.record_trade(trade(2)).is_err());
+        assert_eq!(store.buffered_count(), 2);
+
+        let spill = spill_path(&db_path);
+        assert!(!flush_with_fallback(&store, &spill, Duration::ZERO).await);
+        assert_eq!(store.buffered_count(), 0);
+
+        let spilled: Vec<TradeRecord> = std::fs::read_to_string(&spill).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let orders: Vec<Option<&str>> = spilled.iter().map(|t| t.order_id.as_deref()).collect();
+        assert_eq!(orders, vec![Some("0xorder1"), Some("0xorder2")]);
+
+        // Once the database recovers, new trades go there again
+        rusqlite::Connection::open(&db_path).unwrap().execute_batch("DROP TRIGGER fail_insert;").unwrap();
+        store.record_trade(trade(3)).unwrap();
+        assert!(flush_with_fallback(&store, &spill, Duration::ZERO).await);
+        assert_eq!(store.get_recent_trades(10).unwrap().len(), 1);
+    }
+
+    /// Minimal HTTP server for REST lookups: answers each GET with `respond(path)` as JSON
+    /// and records the requested paths
+    fn spawn_mock_api(respond: fn(&str) -> String) -> (String, Arc<std::sync::Mutex<Vec<String>>>) {
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let paths = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&paths);
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':')
+                        && name.eq_ignore_ascii_case("content-length")
+                    {
+                        content_length = value.trim().parse().unwrap_or(0);
+                    }
+                }
+                // Drain a POST body so closing the socket doesn't reset the connection
+                let mut body = vec![0; content_length];
+                std::io::Read::read_exact(&mut reader, &mut body).unwrap();
+                let path = request_line.split_whitespace().nth(1).unwrap_or("").to_string();
+                let payload = respond(&path);
+                recorded.lock().unwrap().push(path);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    payload.len(),
+                    payload
+                );
+            }
+        });
+
+        (base, paths)
+    }
+
+    fn canned_book(_path: &str) -> String {
+        serde_json::json!({
+            "bids": [{ "price": "0.40", "size": "120" }, { "price": "0.41", "size": "80" }],
+            "asks": [{ "price": "0.45", "size": "30" }, { "price": "0.43", "size": "50" }, { "price": "0.47", "size": "10" }],
+        }).to_string()
+    }
+
+    fn accepting_exchange(path: &str) -> String {
+        if path.starts_with("/neg-risk") {
+            return r#"{"neg_risk":false}"#.to_string();
+        }
+        serde_json::json!({
+            "success": true,
+            "errorMsg": "",
+            "orderID": "0xfeedbeef",
+            "transactionsHashes": ["0xabc"],
+            "status": "matched",
+            "takingAmount": "10",
+            "makingAmount": "5",
+        }).to_string()
+    }
+
+    fn resting_exchange(path: &str) -> String {
+        if path.starts_with("/neg-risk") {
+            return r#"{"neg_risk":false}"#.to_string();
+        }
+        serde_json::json!({
+            "success": true,
+            "errorMsg": "",
+            "orderID": "0xrest1",
+            "transactionsHashes": [],
+            "status": "live",
+            "takingAmount": "0",
+            "makingAmount": "0",
+        }).to_string()
+    }
+
+    /// An exit GTD left on the book isn't recorded as filled: the GTD poller follows it and
+    /// records its fills under the exit status for the traders whose lots it sells
+    #[test]
+    fn test_resting_exit_gtd_goes_to_gtd_poller() {
+        use pm_whale_follower::ApiCreds;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("trades.db").to_string_lossy().into_owned();
+        let store = TradeStore::new(&db_path).unwrap();
+        let buy = |trader: &str, shares: f64| TradeRecord {
+            timestamp_ms: 1_700_000_000_000,
+            block_number: 1,
+            tx_hash: format!("0x{}", trader),
+            trader_address: trader.to_string(),
+            token_id: "rest-tok".to_string(),
+            side: "BUY".to_string(),
+            whale_shares: 1000.0,
+            whale_price: 0.5,
+            whale_usd: 500.0,
+            our_shares: Some(shares),
+            our_price: Some(0.5),
+            our_usd: Some(shares * 0.5),
+            fill_pct: Some(100.0),
+            status: "SUCCESS".to_string(),
+            latency_ms: None,
+            is_live: None,
+            aggregation_count: None,
+            aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
+        };
+        store.record_trade(buy("0xa", 30.0)).unwrap();
+        store.record_trade(buy("0xb", 10.0)).unwrap();
+        store.flush().unwrap();
+
+        let (base, _paths) = spawn_mock_api(resting_exchange);
+        let mut client = RustClobClient::new(
+            &base, 137,
+            "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef", None,
+        ).unwrap();
+        let api_creds = ApiCreds {
+            api_key: "key".to_string(),
+            api_secret: "c2VjcmV0".to_string(),
+            api_passphrase: "pass".to_string(),
+        };
+        let creds = SharedCreds::new(PreparedCreds::from_api_creds(&api_creds).unwrap(), None, CREDS_REFRESH_MIN_INTERVAL);
+        let mut guard = RiskGuard::new(RiskGuardConfig::default());
+        let (gtd_watch_tx, mut gtd_watch_rx) = mpsc::unbounded_channel();
+        let mut state = OrderWorkerState { breaker: ExchangeBreaker::new(1, Duration::from_secs(60)), gtd_watch_tx: Some(gtd_watch_tx), ..OrderWorkerState::default() };
+
+        let evt = exit_event(&ExitOrder {
+            kind: ExitKind::StopLoss,
+            position: held("rest-tok", 40.0, Some(0.50)),
+            bid: 0.30,
+            shares: 40.0,
+        });
+        let settings = OrderSettings { enable_trading: true, db_path: Some(db_path.clone()), ..OrderSettings::default() };
+        let status = process_order(&evt, &mut client, &creds, &mut guard, &mut state, Some(false), &settings);
+
+        assert!(status.starts_with(EXIT_RESTING_STATUS), "got: {}", status);
+        assert!(closing_order_placed(&status));
+        assert!(closing_trade_records(&evt, &status, Some(false), &[]).is_empty(), "nothing filled yet");
+        assert_eq!(classify_trade_status(EXIT_RESTING_STATUS, None, 90.0), TradeStatus::Skipped);
+
+        let (req, body, label) = gtd_watch_rx.try_recv().unwrap();
+        assert_eq!(body_order_id(&body).as_deref(), Some("0xrest1"));
+        assert!(!req.side_is_buy);
+        assert_eq!(label.exit_status, Some("STOP_LOSS"));
+        assert_eq!(label.openers, vec![("0xa".to_string(), 30.0), ("0xb".to_string(), 10.0)]);
+    }
+
+    /// A successful submission's order ID ends up in the status and the DB record built from it
+    #[test]
+    fn test_process_order_captures_order_id() {
+        use pm_whale_follower::ApiCreds;
+
+        let (base, paths) = spawn_mock_api(accepting_exchange);
+        let mut client = RustClobClient::new(
+            &base, 137,
+            "0x0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef", None,
+        ).unwrap();
+        let api_creds = ApiCreds {
+            api_key: "key".to_string(),
+            api_secret: "c2VjcmV0".to_string(),
+            api_passphrase: "pass".to_string(),
+        };
+        let creds = SharedCreds::new(PreparedCreds::from_api_creds(&api_creds).unwrap(), None, CREDS_REFRESH_MIN_INTERVAL);
+        let mut guard = RiskGuard::new(RiskGuardConfig::default());
+        let mut state = OrderWorkerState { breaker: ExchangeBreaker::new(1, Duration::from_secs(60)), ..OrderWorkerState::default() };
+        let info = OrderInfo {
+            order_type: "BUY_FILL".to_string(),
+            clob_token_id: Arc::from("424242"),
+            usd_value: 250.0,
+            shares: 500.0,
+            price_per_share: 0.50,
+        };
+
+        let settings = OrderSettings { enable_trading: true, ..OrderSettings::default() };
+        let status = process_order(
+            &trader_event(info.clone(), "trader", Some(false)), &mut client, &creds,
+            &mut guard, &mut state, Some(false), &settings,
+        );
+
+        assert!(strip_ansi_codes(&status).starts_with("200 OK"), "got: {}", status);
+        assert!(paths.lock().unwrap().iter().any(|p| p == "/order"));
+        assert_eq!(parse_order_id(&status).as_deref(), Some("0xfeedbeef"));
+        // Fill parsing still works with the extra segment
+        assert_eq!(parse_status_for_db(&status).4, "SUCCESS");
+    }
+
+    /// Derives "derived" for any signer and accepts it on authenticated requests
+    fn creds_exchange(path: &str) -> String {
+        if path.starts_with("/auth/derive-api-key") {
+            return r#"{"apiKey":"derived","secret":"c2VjcmV0","passphrase":"pass"}"#.to_string();
+        }
+        if path == "/data/orders" {
+            return "[]".to_string();
+        }
+        "{}".to_string()
+    }
+
+    #[tokio::test]
+    async fn test_execution_key_gets_its_own_api_key() {
+        let (base, paths) = spawn_mock_api(creds_exchange);
+        let dir = tempfile::tempdir().unwrap();
+        let creds_path = dir.path().join(".clob_creds.json");
+        let cache_path = dir.path().join(".clob_market_cache.json");
+        // Left behind by the monitored wallet's key
+        std::fs::write(
+            &creds_path,
+            r#"{"apiKey":"monitor","secret":"c2VjcmV0","passphrase":"pass","address":"0x70997970c51812dc3a010c7d01b50e0d17dc79c8"}"#,
+        ).unwrap();
+        let derives = || paths.lock().unwrap().iter().filter(|p| p.starts_with("/auth/derive-api-key")).count();
+        let build = || build_worker_state(
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80".to_string(), None,
+            &base, cache_path.to_str().unwrap(), creds_path.to_str().unwrap(),
+        );
+
+        let (client, creds) = build().await.unwrap();
+        assert_eq!(creds.api_key, "derived");
+        assert_eq!(derives(), 1);
+        let saved = std::fs::read_to_string(&creds_path).unwrap();
+        assert!(saved.contains(client.wallet_address()), "signer not recorded: {}", saved);
+
+        // The next start reuses the key saved for this signer
+        assert_eq!(build().await.unwrap().1.api_key, "derived");
+        assert_eq!(derives(), 1);
+
+        // The startup check makes one authenticated request with it
+        let shared = SharedCreds::new(PreparedCreds::from_api_creds(&creds).unwrap(), None, CREDS_REFRESH_MIN_INTERVAL);
+        assert!(tokio::task::spawn_blocking(move || check_api_creds(&client, &shared)).await.unwrap().unwrap());
+        assert!(paths.lock().unwrap().iter().any(|p| p == "/data/orders"));
+    }
+
+    #[test]
+    fn test_startup_key_check_only_aborts_on_rejection() {
+        assert!(api_key_rejected(401));
+        assert!(api_key_rejected(403));
+        // Outages and throttling shouldn't stop the bot from starting
+        assert!(!api_key_rejected(500));
+        assert!(!api_key_rejected(503));
+        assert!(!api_key_rejected(429));
+        assert!(!api_key_rejected(200));
+    }
+
+    #[test]
+    fn test_parse_order_id() {
+        let status = "\x1b[32m200 OK [SCALED] | 5.00/5.00 filled @ 0.45 | whale 500.0 @ 0.44 | order 0xabc\x1b[0m";
+        assert_eq!(parse_order_id(status).as_deref(), Some("0xabc"));
+        assert_eq!(parse_order_id("200 OK [SCALED] | 5.00/5.00 filled @ 0.45 | whale 500.0 @ 0.44"), None);
+        assert_eq!(parse_order_id("SKIPPED_SMALL (<10 shares)"), None);
+        assert_eq!(body_order_id(r#"{"success":true,"orderID":"0x1"}"#).as_deref(), Some("0x1"));
+        assert_eq!(body_order_id(r#"{"success":false,"orderID":""}"#), None);
+    }
+
+    #[tokio::test]
+    async fn test_combined_window_records_every_contributing_trader() {
+        let mut aggregator = TradeAggregator::new(AggregationConfig::default());
+        for trader in ["0xtrader1", "0xtrader2", "0xtrader1", "0xtrader3"] {
+            assert!(aggregator.add_trade("0xabc123".into(), "BUY".into(), 100.0, 0.50, trader.into()).is_none());
+        }
+        let aggregated = aggregator.flush_all().pop().unwrap();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let trader_manager = Arc::new(Mutex::new(TraderManager::new(&TradersConfig::new(Vec::new()))));
+        record_aggregated_trade(
+            "SKIPPED_SMALL (<10 shares)",
+            Some(false),
+            &Some(tx),
+            &trader_manager,
+            &Notifiers::default(),
+            &aggregated,
+            SUCCESS_FILL_PCT_DEFAULT,
+        ).await;
+
+        let record = rx.try_recv().unwrap();
+        // trader_address keeps only the first trader; the rest are in contributing_traders
+        assert_eq!(record.trader_address, "0xtrader1");
+        assert_eq!(record.aggregation_count, Some(4));
+        assert_eq!(
+            record.contributing_traders,
+            Some(vec!["0xtrader1".to_string(), "0xtrader2".to_string(), "0xtrader3".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_live_fill_at_success_fill_pct_counts_as_success() {
+        // 18/20 is 90%, 17/20 is 85%
+        let at_90 = parse_status_for_db("200 OK [SCALED] | 18.00/20.00 filled @ 0.45 | whale 1000.0 @ 0.44");
+        let at_85 = parse_status_for_db("200 OK [SCALED] | 17.00/20.00 filled @ 0.45 | whale 1000.0 @ 0.44");
+        let classify = |(_, _, _, fill_pct, category): &(Option<f64>, Option<f64>, Option<f64>, Option<f64>, String), threshold| {
+            classify_trade_status(category, *fill_pct, threshold)
+        };
+
+        assert_eq!(classify(&at_90, SUCCESS_FILL_PCT_DEFAULT), TradeStatus::Success);
+        assert_eq!(classify(&at_85, SUCCESS_FILL_PCT_DEFAULT), TradeStatus::Partial);
+        assert_eq!(classify(&at_85, 85.0), TradeStatus::Success);
+        assert_eq!(classify(&at_90, 95.0), TradeStatus::Partial);
+        // Unknown fill and non-fills aren't affected by the threshold
+        assert_eq!(classify_trade_status("SUCCESS", None, 100.0), TradeStatus::Success);
+        assert_eq!(classify_trade_status("SKIPPED_SMALL", Some(0.0), 90.0), TradeStatus::Skipped);
+        assert_eq!(classify_trade_status(POST_ONLY_STATUS, None, 90.0), TradeStatus::Skipped);
+        assert_eq!(classify_trade_status("FAILED", Some(95.0), 90.0), TradeStatus::Failed);
+    }
+
+    #[test]
+    fn test_deferred_statuses_are_skipped() {
+        for status in [AGG_DEFERRED_STATUS, "DEFERRED_RATE_LIMIT (120ms)"] {
+            let (shares, _, _, fill_pct, category) = parse_status_for_db(status);
+            assert_eq!(shares, None, "{}", status);
+            assert_eq!(classify_trade_status(&category, fill_pct, SUCCESS_FILL_PCT_DEFAULT), TradeStatus::Skipped, "{}", status);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_aggregated_fill_at_success_fill_pct_counts_as_success() {
+        use pm_whale_follower::config::traders::TraderConfig;
+
+        let trader = TraderConfig::new("abc123def456789012345678901234567890abcd", "Whale").unwrap();
+        let trader_manager = Arc::new(Mutex::new(TraderManager::new(&TradersConfig::new(vec![trader.clone()]))));
+        let mut aggregator = TradeAggregator::new(AggregationConfig::default());
+        aggregator.add_trade("0xabc123".into(), "BUY".into(), 1000.0, 0.50, trader.address.clone());
+        let aggregated = aggregator.flush_all().pop().unwrap();
+
+        // 17/20 is 85%: success at an 85 threshold, partial at the default 90
+        for threshold in [85.0, SUCCESS_FILL_PCT_DEFAULT] {
+            record_aggregated_trade(
+                "200 OK [SCALED] | 17.00/20.00 filled @ 0.50 | whale 1000.0 @ 0.50",
+                Some(false),
+                &None,
+                &trader_manager,
+                &Notifiers::default(),
+                &aggregated,
+                threshold,
+            ).await;
+        }
+
+        let manager = trader_manager.lock().await;
+        let state = manager.get_state(&trader.address).unwrap();
+        assert_eq!((state.successful_trades, state.partial_trades), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_best_book_uses_configured_clob_base() {
+        let (base, paths) = spawn_mock_api(canned_book);
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+
+        let ((bp, bs), (sp, ss)) = fetch_best_book(&base, "tok1", "BUY_FILL", &client).await.unwrap();
+        assert_eq!((bp.as_str(), bs.as_str()), ("\"0.43\"", "\"50\""));
+        assert_eq!((sp.as_str(), ss.as_str()), ("\"0.45\"", "\"30\""));
+
+        let ((bp, _), (sp, _)) = fetch_best_book(&base, "tok1", "SELL_FILL", &client).await.unwrap();
+        assert_eq!((bp.as_str(), sp.as_str()), ("\"0.41\"", "\"0.40\""));
+
+        assert_eq!(paths.lock().unwrap()[0], "/book?token_id=tok1");
+    }
+
+    #[test]
+    fn test_fetch_best_ask_sync_uses_configured_clob_base() {
+        let (base, paths) = spawn_mock_api(canned_book);
+
+        assert_eq!(fetch_best_ask_sync(&base, "tok2"), Some(0.43));
+        assert_eq!(paths.lock().unwrap().as_slice(), ["/book?token_id=tok2"]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_is_live_uses_configured_gamma_base() {
+        fn gamma(path: &str) -> String {
+            if path.starts_with("/markets") {
+                serde_json::json!([{ "slug": "mock-event", "negRisk": false }]).to_string()
+            } else {
+                serde_json::json!({ "live": true }).to_string()
+            }
+        }
+        let (base, paths) = spawn_mock_api(gamma);
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+
+        assert_eq!(fetch_is_live(&base, "mock_live_tok", &client).await, Some(true));
+        assert_eq!(
+            paths.lock().unwrap().as_slice(),
+            ["/markets?clob_token_ids=mock_live_tok", "/events/slug/mock-event"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_market_name_populates_cache() {
+        fn gamma(_path: &str) -> String {
+            serde_json::json!([{
+                "slug": "mock-name-event",
+                "question": "Will it rain?",
+                "clobTokenIds": "[\"mock_name_yes\",\"mock_name_no\"]",
+                "outcomes": "[\"Yes\",\"No\"]",
+            }]).to_string()
+        }
+        let (base, paths) = spawn_mock_api(gamma);
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+
+        // Miss: nothing cached until the Gamma lookup
+        assert_eq!(market_cache::get_market_name("mock_name_no"), None);
+        let name = fetch_market_name(&base, "mock_name_no", &client).await.unwrap();
+        assert_eq!(name.title, "Will it rain?");
+        assert_eq!(name.outcome, "No");
+
+        // Hit: served from the cache with no further request
+        assert_eq!(market_cache::get_market_name("mock_name_no"), Some(name));
+        assert_eq!(market_cache::get_slug("mock_name_no").as_deref(), Some("mock-name-event"));
+        assert_eq!(paths.lock().unwrap().len(), 1);
+    }
+}
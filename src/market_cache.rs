@@ -2,11 +2,14 @@
 /// Handles caching of market data, tokens, and live status
 
 use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::RwLock;
 use std::time::{Duration, Instant};
 
+use crate::market_info::MarketMetadata;
+
 // ============================================================================
 // Cache Configuration
 // ============================================================================
@@ -14,12 +17,19 @@ use std::time::{Duration, Instant};
 /// How often to refresh caches (in seconds)
 pub const CACHE_REFRESH_INTERVAL_SECS: u64 = 30 * 60; // 30 minutes
 
+/// How long a cached market title/outcome is trusted before it is looked up again
+pub const MARKET_NAME_TTL_SECS: u64 = 24 * 60 * 60; // 24 hours
+
+/// How long a market's trading status is trusted before it is looked up again
+pub const MARKET_STATUS_TTL_SECS: u64 = 5 * 60; // 5 minutes
+
 /// Cache file paths
 const NEG_RISK_CACHE_PATH: &str = ".clob_market_cache.json";
 const SLUG_CACHE_PATH: &str = ".clob_slug_cache.json";
 const ATP_TOKENS_CACHE_PATH: &str = ".atp_token_categories.json";
 const LIGUE1_TOKENS_CACHE_PATH: &str = ".ligue1_tokens.json";
 const LIVE_CACHE_PATH: &str = ".live_cache.json";
+const MARKET_NAMES_CACHE_PATH: &str = ".market_names_cache.json";
 
 /// Price buffer adjustments for specialized markets
 const ATP_BUFFER: f64 = 0.01;
@@ -41,12 +51,71 @@ pub struct MarketCaches {
     pub ligue1_tokens: RwLock<FxHashMap<String, ()>>,
     /// Token ID -> live status (for GTD expiry calculation)
     pub live_status: RwLock<FxHashMap<String, bool>>,
+    /// Token ID -> market title and outcome (for human-readable logs and trade history)
+    pub market_names: RwLock<FxHashMap<String, CachedMarketName>>,
+    /// Token ID -> end time and closed / accepting-orders flags (for skipping BUYs into closing markets)
+    pub market_status: RwLock<FxHashMap<String, MarketStatus>>,
     /// Last refresh timestamp (Unix seconds)
     pub last_refresh: AtomicU64,
     /// Cache statistics
     pub stats: CacheStats,
 }
 
+/// Market title/outcome with the Unix time it was fetched
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedMarketName {
+    pub title: String,
+    pub outcome: String,
+    pub fetched_at: u64,
+}
+
+/// Trading state of a market from Gamma (`endDate`, `closed`, `acceptingOrders`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarketStatus {
+    /// Scheduled end as Unix ms (None if Gamma has no parseable endDate)
+    pub end_ms: Option<i64>,
+    pub closed: bool,
+    pub accepting_orders: bool,
+    /// Unix seconds when fetched
+    pub fetched_at: u64,
+}
+
+impl MarketStatus {
+    /// Read from a Gamma `/markets` entry; missing flags mean open and accepting orders
+    pub fn from_gamma_market(market: &serde_json::Value, fetched_at: u64) -> Self {
+        let flag = |key: &str, default: bool| market.get(key).and_then(|v| v.as_bool()).unwrap_or(default);
+        Self {
+            end_ms: market.get("endDate")
+                .and_then(|v| v.as_str())
+                .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+                .map(|end| end.timestamp_millis()),
+            closed: flag("closed", false),
+            accepting_orders: flag("acceptingOrders", true),
+            fetched_at,
+        }
+    }
+
+    /// Why a BUY at `now_ms` should be skipped: closed, not accepting orders, or ending within
+    /// `window` (zero = the end time is ignored). None if the market is open for buying.
+    pub fn closing_reason(&self, now_ms: i64, window: Duration) -> Option<String> {
+        if self.closed {
+            return Some("closed".to_string());
+        }
+        if !self.accepting_orders {
+            return Some("not accepting orders".to_string());
+        }
+        let end_ms = self.end_ms.filter(|_| !window.is_zero())?;
+        let left_ms = end_ms - now_ms;
+        if left_ms <= 0 {
+            Some("ended".to_string())
+        } else if left_ms <= window.as_millis() as i64 {
+            Some(format!("ends in {}s", left_ms / 1000))
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct CacheStats {
     pub neg_risk_count: AtomicU64,
@@ -54,6 +123,9 @@ pub struct CacheStats {
     pub atp_count: AtomicU64,
     pub ligue1_count: AtomicU64,
     pub live_count: AtomicU64,
+    pub name_count: AtomicU64,
+    pub name_hits: AtomicU64,
+    pub name_misses: AtomicU64,
     pub refresh_count: AtomicU64,
     pub last_refresh_duration_ms: AtomicU64,
 }
@@ -66,6 +138,8 @@ impl MarketCaches {
             atp_tokens: RwLock::new(FxHashMap::default()),
             ligue1_tokens: RwLock::new(FxHashMap::default()),
             live_status: RwLock::new(FxHashMap::default()),
+            market_names: RwLock::new(FxHashMap::default()),
+            market_status: RwLock::new(FxHashMap::default()),
             last_refresh: AtomicU64::new(0),
             stats: CacheStats::default(),
         }
@@ -141,15 +215,19 @@ impl MarketCaches {
             }
         }
 
+        // Market names are written by the bot itself: merge the file with what this
+        // process looked up, drop expired entries, and persist the result
+        result.names_loaded = self.load_market_names_from(MARKET_NAMES_CACHE_PATH);
+        self.prune_expired_names(unix_now_secs());
+        if let Err(e) = self.save_market_names_to(MARKET_NAMES_CACHE_PATH) {
+            eprintln!("⚠️ Failed to save market name cache: {}", e);
+        }
+
         let elapsed = start.elapsed();
         result.load_time_ms = elapsed.as_millis() as u64;
 
         // Update last refresh timestamp
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        self.last_refresh.store(now, Ordering::Relaxed);
+        self.last_refresh.store(unix_now_secs(), Ordering::Relaxed);
         self.stats.refresh_count.fetch_add(1, Ordering::Relaxed);
         self.stats.last_refresh_duration_ms.store(result.load_time_ms, Ordering::Relaxed);
 
@@ -198,6 +276,102 @@ impl MarketCaches {
         self.live_status.read().ok()?.get(token_id).copied()
     }
 
+    /// Get market title and outcome for token, if cached and not expired
+    pub fn get_market_name(&self, token_id: &str) -> Option<MarketMetadata> {
+        self.get_market_name_at(token_id, unix_now_secs())
+    }
+
+    /// `get_market_name` as of `now` (Unix seconds); counts a cache hit or miss
+    pub fn get_market_name_at(&self, token_id: &str, now: u64) -> Option<MarketMetadata> {
+        let found = self.market_names.read().ok().and_then(|cache| {
+            cache
+                .get(token_id)
+                .filter(|n| now.saturating_sub(n.fetched_at) < MARKET_NAME_TTL_SECS)
+                .map(|n| MarketMetadata { title: n.title.clone(), outcome: n.outcome.clone() })
+        });
+        let counter = if found.is_some() { &self.stats.name_hits } else { &self.stats.name_misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+
+    /// Insert market title and outcome for a token, fetched now
+    pub fn set_market_name(&self, token_id: String, name: MarketMetadata) {
+        self.set_market_name_at(token_id, name, unix_now_secs());
+    }
+
+    /// Insert market title and outcome for a token, fetched at `fetched_at` (Unix seconds)
+    pub fn set_market_name_at(&self, token_id: String, name: MarketMetadata, fetched_at: u64) {
+        if let Ok(mut cache) = self.market_names.write() {
+            cache.insert(token_id, CachedMarketName { title: name.title, outcome: name.outcome, fetched_at });
+            self.stats.name_count.store(cache.len() as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Drop market names older than the TTL as of `now`; returns how many were removed
+    pub fn prune_expired_names(&self, now: u64) -> usize {
+        let Ok(mut cache) = self.market_names.write() else { return 0 };
+        let before = cache.len();
+        cache.retain(|_, n| now.saturating_sub(n.fetched_at) < MARKET_NAME_TTL_SECS);
+        self.stats.name_count.store(cache.len() as u64, Ordering::Relaxed);
+        before - cache.len()
+    }
+
+    /// Merge market names from a cache file, keeping the newer entry per token; returns entries read
+    pub fn load_market_names_from(&self, path: impl AsRef<std::path::Path>) -> usize {
+        let Ok(data) = std::fs::read_to_string(path) else { return 0 };
+        let Ok(map) = serde_json::from_str::<HashMap<String, CachedMarketName>>(&data) else { return 0 };
+        let count = map.len();
+        if let Ok(mut cache) = self.market_names.write() {
+            for (token_id, name) in map {
+                let newer = cache.get(&token_id).is_none_or(|cur| name.fetched_at > cur.fetched_at);
+                if newer {
+                    cache.insert(token_id, name);
+                }
+            }
+            self.stats.name_count.store(cache.len() as u64, Ordering::Relaxed);
+        }
+        count
+    }
+
+    /// Write market names to a cache file (skipped while the cache is empty)
+    pub fn save_market_names_to(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let snapshot: HashMap<String, CachedMarketName> = match self.market_names.read() {
+            Ok(cache) if !cache.is_empty() => cache.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            _ => return Ok(()),
+        };
+        std::fs::write(path, serde_json::to_string(&snapshot)?)
+    }
+
+    /// Load market names from the default cache file
+    pub fn load_market_names(&self) -> usize {
+        self.load_market_names_from(MARKET_NAMES_CACHE_PATH)
+    }
+
+    /// Save market names to the default cache file
+    pub fn save_market_names(&self) -> std::io::Result<()> {
+        self.save_market_names_to(MARKET_NAMES_CACHE_PATH)
+    }
+
+    /// Trading status of a token's market, if cached within `MARKET_STATUS_TTL_SECS`
+    pub fn get_market_status(&self, token_id: &str) -> Option<MarketStatus> {
+        self.get_market_status_at(token_id, unix_now_secs())
+    }
+
+    /// `get_market_status` as of `now` (Unix seconds)
+    pub fn get_market_status_at(&self, token_id: &str, now: u64) -> Option<MarketStatus> {
+        self.market_status.read().ok()?
+            .get(token_id)
+            .filter(|s| now.saturating_sub(s.fetched_at) < MARKET_STATUS_TTL_SECS)
+            .copied()
+    }
+
+    /// Insert the trading status of a token's market
+    pub fn set_market_status(&self, token_id: String, status: MarketStatus) {
+        if let Ok(mut cache) = self.market_status.write() {
+            cache.insert(token_id, status);
+        }
+    }
+
     /// Insert neg_risk value for a token (for dynamic updates)
     pub fn set_neg_risk(&self, token_id: String, neg_risk: bool) {
         if let Ok(mut cache) = self.neg_risk.write() {
@@ -212,14 +386,32 @@ impl MarketCaches {
         }
     }
 
+    /// Cache slug, neg_risk, market name and trading status from a Gamma `/markets` entry; returns the slug
+    /// `negRisk` is only stored when present, so a missing flag doesn't mask a later CLOB lookup
+    pub fn record_gamma_market(&self, token_id: &str, market: &serde_json::Value) -> Option<String> {
+        self.set_market_status(token_id.to_string(), MarketStatus::from_gamma_market(market, unix_now_secs()));
+        if let Some(neg_risk) = market.get("negRisk").and_then(|v| v.as_bool()) {
+            self.set_neg_risk(token_id.to_string(), neg_risk);
+        }
+        if let Some(name) = MarketMetadata::from_gamma_market(token_id, market) {
+            self.set_market_name(token_id.to_string(), name);
+        }
+        let slug = market.get("slug")?.as_str()?.to_string();
+        self.set_slug(token_id.to_string(), slug.clone());
+        Some(slug)
+    }
+
     /// Get cache statistics summary
     pub fn get_stats_summary(&self) -> String {
         format!(
-            "Caches: neg_risk={}, slugs={}, atp={}, ligue1={}, refreshes={}",
+            "Caches: neg_risk={}, slugs={}, atp={}, ligue1={}, names={} (hits={}, misses={}), refreshes={}",
             self.stats.neg_risk_count.load(Ordering::Relaxed),
             self.stats.slug_count.load(Ordering::Relaxed),
             self.stats.atp_count.load(Ordering::Relaxed),
             self.stats.ligue1_count.load(Ordering::Relaxed),
+            self.stats.name_count.load(Ordering::Relaxed),
+            self.stats.name_hits.load(Ordering::Relaxed),
+            self.stats.name_misses.load(Ordering::Relaxed),
             self.stats.refresh_count.load(Ordering::Relaxed),
         )
     }
@@ -227,14 +419,17 @@ impl MarketCaches {
     /// Check if cache refresh is needed
     pub fn needs_refresh(&self) -> bool {
         let last = self.last_refresh.load(Ordering::Relaxed);
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        now - last >= CACHE_REFRESH_INTERVAL_SECS
+        unix_now_secs() - last >= CACHE_REFRESH_INTERVAL_SECS
     }
 }
 
+fn unix_now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
 impl Default for MarketCaches {
     fn default() -> Self {
         Self::new()
@@ -248,6 +443,7 @@ pub struct CacheLoadResult {
     pub atp_loaded: usize,
     pub ligue1_loaded: usize,
     pub live_loaded: usize,
+    pub names_loaded: usize,
     pub load_time_ms: u64,
 }
 
@@ -255,13 +451,14 @@ impl std::fmt::Display for CacheLoadResult {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Loaded caches in {}ms: neg_risk={}, slugs={}, atp={}, ligue1={}, live={}",
+            "Loaded caches in {}ms: neg_risk={}, slugs={}, atp={}, ligue1={}, live={}, names={}",
             self.load_time_ms,
             self.neg_risk_loaded,
             self.slugs_loaded,
             self.atp_loaded,
             self.ligue1_loaded,
-            self.live_loaded
+            self.live_loaded,
+            self.names_loaded
         )
     }
 }
@@ -347,12 +544,30 @@ pub fn is_neg_risk(token_id: &str) -> Option<bool> {
     global_caches().is_neg_risk(token_id)
 }
 
+/// Cache slug and neg_risk from a Gamma market entry (convenience function)
+#[inline]
+pub fn record_gamma_market(token_id: &str, market: &serde_json::Value) -> Option<String> {
+    global_caches().record_gamma_market(token_id, market)
+}
+
 /// Get is_live for a token (convenience function)
 #[inline]
 pub fn get_is_live(token_id: &str) -> Option<bool> {
     global_caches().get_is_live(token_id)
 }
 
+/// Get the cached trading status of a token's market (convenience function)
+#[inline]
+pub fn get_market_status(token_id: &str) -> Option<MarketStatus> {
+    global_caches().get_market_status(token_id)
+}
+
+/// Get market title and outcome for a token (convenience function)
+#[inline]
+pub fn get_market_name(token_id: &str) -> Option<MarketMetadata> {
+    global_caches().get_market_name(token_id)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -382,6 +597,129 @@ mod tests {
         assert_eq!(caches.get_slug("unknown"), None);
     }
 
+    #[test]
+    fn test_record_gamma_market_populates_cache() {
+        let caches = MarketCaches::new();
+        let market = serde_json::json!({"slug": "will-it-rain", "negRisk": true, "clobTokenIds": "[\"tok1\",\"tok2\"]"});
+        assert_eq!(caches.record_gamma_market("tok1", &market), Some("will-it-rain".to_string()));
+        assert_eq!(caches.is_neg_risk("tok1"), Some(true));
+        assert_eq!(caches.get_slug("tok1"), Some("will-it-rain".to_string()));
+
+        // Standard market is cached as not neg-risk
+        let standard = serde_json::json!({"slug": "plain-market", "negRisk": false});
+        caches.record_gamma_market("tok3", &standard);
+        assert_eq!(caches.is_neg_risk("tok3"), Some(false));
+
+        // No flag: neg_risk stays unknown, slug still cached
+        let no_flag = serde_json::json!({"slug": "older-market"});
+        assert_eq!(caches.record_gamma_market("tok4", &no_flag), Some("older-market".to_string()));
+        assert_eq!(caches.is_neg_risk("tok4"), None);
+    }
+
+    #[test]
+    fn test_record_gamma_market_caches_market_name() {
+        let caches = MarketCaches::new();
+        let market = serde_json::json!({
+            "slug": "will-it-rain",
+            "question": "Will it rain?",
+            "clobTokenIds": "[\"tok1\",\"tok2\"]",
+            "outcomes": "[\"Yes\",\"No\"]",
+        });
+        caches.record_gamma_market("tok2", &market);
+        let name = caches.get_market_name("tok2").unwrap();
+        assert_eq!(name.title, "Will it rain?");
+        assert_eq!(name.outcome, "No");
+    }
+
+    #[test]
+    fn test_market_status_from_gamma() {
+        let caches = MarketCaches::new();
+        let market = serde_json::json!({
+            "slug": "will-it-rain",
+            "endDate": "2024-06-01T12:00:00Z",
+            "closed": false,
+            "acceptingOrders": true,
+        });
+        caches.record_gamma_market("tok1", &market);
+        let status = caches.get_market_status("tok1").unwrap();
+        assert_eq!(status.end_ms, Some(1_717_243_200_000));
+        assert!(!status.closed && status.accepting_orders);
+
+        // Missing fields: open, accepting, no end time
+        let bare = MarketStatus::from_gamma_market(&serde_json::json!({"endDate": "soon"}), 0);
+        assert_eq!(bare, MarketStatus { end_ms: None, closed: false, accepting_orders: true, fetched_at: 0 });
+
+        // Stale entries are looked up again
+        caches.set_market_status("tok2".to_string(), MarketStatus { fetched_at: 1_000, ..bare });
+        assert!(caches.get_market_status_at("tok2", 1_000 + MARKET_STATUS_TTL_SECS - 1).is_some());
+        assert!(caches.get_market_status_at("tok2", 1_000 + MARKET_STATUS_TTL_SECS).is_none());
+    }
+
+    #[test]
+    fn test_market_closing_reason() {
+        let end_ms = 1_000_000_000;
+        let open = MarketStatus { end_ms: Some(end_ms), closed: false, accepting_orders: true, fetched_at: 0 };
+        let window = Duration::from_secs(600);
+
+        assert_eq!(open.closing_reason(end_ms - 601_000, window), None);
+        assert_eq!(open.closing_reason(end_ms - 600_000, window).as_deref(), Some("ends in 600s"));
+        assert_eq!(open.closing_reason(end_ms - 30_000, window).as_deref(), Some("ends in 30s"));
+        assert_eq!(open.closing_reason(end_ms, window).as_deref(), Some("ended"));
+        assert_eq!(open.closing_reason(end_ms + 3_600_000, window).as_deref(), Some("ended"));
+        // No window: the end time alone never skips (sports markets trade past endDate)
+        assert_eq!(open.closing_reason(end_ms + 3_600_000, Duration::ZERO), None);
+        // Unknown end time
+        assert_eq!(MarketStatus { end_ms: None, ..open }.closing_reason(end_ms, window), None);
+
+        // Closed or halted markets are skipped whatever the window or end time
+        let closed = MarketStatus { closed: true, ..open };
+        assert_eq!(closed.closing_reason(0, Duration::ZERO).as_deref(), Some("closed"));
+        let halted = MarketStatus { accepting_orders: false, end_ms: None, ..open };
+        assert_eq!(halted.closing_reason(0, window).as_deref(), Some("not accepting orders"));
+    }
+
+    #[test]
+    fn test_market_name_hit_miss_and_ttl() {
+        let caches = MarketCaches::new();
+        let name = MarketMetadata { title: "Will it rain?".to_string(), outcome: "Yes".to_string() };
+        caches.set_market_name_at("tok1".to_string(), name.clone(), 1_000);
+
+        // Miss: unknown token
+        assert_eq!(caches.get_market_name_at("unknown", 1_000), None);
+        // Hit: within the TTL
+        assert_eq!(caches.get_market_name_at("tok1", 1_000 + MARKET_NAME_TTL_SECS - 1), Some(name));
+        // Miss: expired
+        assert_eq!(caches.get_market_name_at("tok1", 1_000 + MARKET_NAME_TTL_SECS), None);
+
+        assert_eq!(caches.stats.name_hits.load(Ordering::Relaxed), 1);
+        assert_eq!(caches.stats.name_misses.load(Ordering::Relaxed), 2);
+
+        assert_eq!(caches.prune_expired_names(1_000 + MARKET_NAME_TTL_SECS), 1);
+        assert!(caches.market_names.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_market_names_persist_and_merge_newer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("names.json");
+        let old = MarketMetadata { title: "Old title".to_string(), outcome: "Yes".to_string() };
+        let new = MarketMetadata { title: "New title".to_string(), outcome: "Yes".to_string() };
+
+        let writer = MarketCaches::new();
+        writer.set_market_name_at("tok1".to_string(), new.clone(), 2_000);
+        writer.save_market_names_to(&path).unwrap();
+
+        let reader = MarketCaches::new();
+        reader.set_market_name_at("tok1".to_string(), old, 1_000);
+        assert_eq!(reader.load_market_names_from(&path), 1);
+        assert_eq!(reader.get_market_name_at("tok1", 2_000), Some(new));
+
+        // Empty caches don't create a file
+        let empty_path = dir.path().join("empty.json");
+        MarketCaches::new().save_market_names_to(&empty_path).unwrap();
+        assert!(!empty_path.exists());
+    }
+
     #[test]
     fn test_buffer_constants() {
         // Buffer constants should be defined
@@ -17,7 +17,7 @@
 
 use anyhow::Result;
 use reqwest::blocking::Client;
-use serde::Deserialize;
+use serde_json::Value;
 use std::time::Duration;
 
 /// Market metadata for a token
@@ -27,15 +27,20 @@ pub struct MarketMetadata {
     pub outcome: String,
 }
 
-/// Gamma API response structure (returns an array of markets directly)
-type GammaMarketResponse = Vec<GammaMarket>;
-
-#[derive(Debug, Deserialize)]
-struct GammaMarket {
-    question: String,
-    #[serde(rename = "clobTokenIds")]
-    clob_token_ids: String, // JSON string containing array of token IDs
-    outcomes: String,        // JSON string containing array of outcome names
+impl MarketMetadata {
+    /// Title and this token's outcome from a Gamma `/markets` entry
+    ///
+    /// `clobTokenIds` and `outcomes` are JSON-encoded string arrays in the same order.
+    pub fn from_gamma_market(token_id: &str, market: &Value) -> Option<Self> {
+        let title = market.get("question")?.as_str()?;
+        let token_ids: Vec<String> = serde_json::from_str(market.get("clobTokenIds")?.as_str()?).ok()?;
+        let outcomes: Vec<String> = serde_json::from_str(market.get("outcomes")?.as_str()?).ok()?;
+        let index = token_ids.iter().position(|id| id == token_id)?;
+        Some(Self {
+            title: title.to_string(),
+            outcome: outcomes.get(index)?.clone(),
+        })
+    }
 }
 
 /// Market information fetcher
@@ -74,30 +79,13 @@ impl MarketInfo {
             return Ok(None);
         }
 
-        let markets: GammaMarketResponse = response.json()?;
-
-        // Should get exactly one market for a specific token ID query
-        if let Some(market) = markets.first() {
-            // Parse the JSON strings for token IDs and outcomes
-            let token_ids: Vec<String> = serde_json::from_str(&market.clob_token_ids)
-                .unwrap_or_default();
-            let outcomes: Vec<String> = serde_json::from_str(&market.outcomes)
-                .unwrap_or_default();
-
-            // Find the index of our token ID
-            if let Some(index) = token_ids.iter().position(|id| id == token_id) {
-                // Get the corresponding outcome name
-                if let Some(outcome) = outcomes.get(index) {
-                    return Ok(Some(MarketMetadata {
-                        title: market.question.clone(),
-                        outcome: outcome.clone(),
-                    }));
-                }
-            }
-        }
+        let markets: Vec<Value> = response.json()?;
 
-        // Token ID not found in response
-        Ok(None)
+        // Should get exactly one market for a specific token ID query;
+        // None if the token isn't in it
+        Ok(markets
+            .first()
+            .and_then(|market| MarketMetadata::from_gamma_market(token_id, market)))
     }
 }
 
@@ -168,6 +156,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_from_gamma_market_picks_token_outcome() {
+        let market = serde_json::json!({
+            "question": "Will it rain?",
+            "clobTokenIds": "[\"tok_yes\",\"tok_no\"]",
+            "outcomes": "[\"Yes\",\"No\"]",
+        });
+        let no = MarketMetadata::from_gamma_market("tok_no", &market).unwrap();
+        assert_eq!(no.title, "Will it rain?");
+        assert_eq!(no.outcome, "No");
+        assert!(MarketMetadata::from_gamma_market("other", &market).is_none());
+        assert!(MarketMetadata::from_gamma_market("tok_yes", &serde_json::json!({"slug": "x"})).is_none());
+    }
+
     #[test]
     fn test_market_metadata_clone() {
         let metadata = MarketMetadata {
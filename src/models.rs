@@ -5,6 +5,7 @@ use serde::Deserialize;
 use std::fmt;
 use std::sync::Arc;
 use tokio::sync::oneshot;
+use crate::settings::CopyPriceRange;
 
 /// Parsed order information from blockchain events
 #[derive(Debug, Clone)]
@@ -16,6 +17,24 @@ pub struct OrderInfo {
     pub price_per_share: f64,
 }
 
+impl OrderInfo {
+    /// The field that makes this fill unusable for sizing, if any: shares must be finite and
+    /// positive, the price finite and within (0, 1] (an outcome share never pays more than $1),
+    /// and the USD value finite
+    pub fn invalid_field(&self) -> Option<&'static str> {
+        if !(self.shares.is_finite() && self.shares > 0.0) {
+            return Some("shares");
+        }
+        if !(self.price_per_share.is_finite() && self.price_per_share > 0.0 && self.price_per_share <= 1.0) {
+            return Some("price");
+        }
+        if !(self.usd_value.is_finite() && self.usd_value >= 0.0) {
+            return Some("usd");
+        }
+        None
+    }
+}
+
 /// Fully parsed blockchain event ready for processing
 #[derive(Debug, Clone)]
 pub struct ParsedEvent {
@@ -26,11 +45,24 @@ pub struct ParsedEvent {
     pub trader_address: String,
     /// Human-friendly label for the trader (e.g., "Whale1", "TopTrader")
     /// Empty string if trader is unknown or not yet populated
-    #[allow(dead_code)] // Reserved for future logging/metrics use
     pub trader_label: String,
     /// Minimum shares threshold for this trader (from traders.json min_shares)
     /// Used to filter out small trades on a per-trader basis
     pub trader_min_shares: f64,
+    /// Maximum USD per copied trade for this trader (from traders.json max_bet_usd)
+    /// None means no per-trader cap
+    pub trader_max_bet_usd: Option<f64>,
+    /// Fraction of the whale's size to copy for this trader (from traders.json scaling_ratio)
+    /// 0.0 means use the global SCALING_RATIO
+    pub trader_scaling_ratio: f64,
+    /// Per-trader probabilistic sizing override (from traders.json probabilistic_sizing)
+    /// None means use the global USE_PROBABILISTIC_SIZING
+    pub trader_probabilistic_sizing: Option<bool>,
+    /// Per-trader copy price bounds (from traders.json min_copy_price/max_copy_price)
+    /// Unset bounds fall back to the global MIN_COPY_PRICE/MAX_COPY_PRICE
+    pub trader_copy_price_range: CopyPriceRange,
+    /// Observe-only trader (from traders.json observe): recorded as OBSERVED, never copied
+    pub trader_observe: bool,
     pub order: OrderInfo,
 }
 
@@ -47,8 +79,14 @@ pub struct WorkItem {
 pub enum SizeType {
     Scaled,
     Capped,        // Size was capped by portfolio-based bet limit
+    CappedByTrader, // Size was capped by per-trader max_bet_usd
     ProbHit(u8),   // percentage
     ProbSkip(u8),  // percentage
+    ProportionalSell, // Same fraction of our position as the whale sold of theirs
+    Kelly,         // Fractional-Kelly share of bankroll (SIZING_MODE=kelly)
+    CappedByMarket, // Trimmed to fit MAX_EXPOSURE_PER_MARKET_USD
+    BelowFloor,    // Under the minimum order size and not rounded up (SIZING_STRATEGY)
+    Flat,          // Fixed USD per trade (SIZING_MODE=flat)
 }
 
 /// Request to resubmit a failed FAK order 
@@ -56,6 +94,7 @@ pub enum SizeType {
 #[derive(Debug, Clone)]
 pub struct ResubmitRequest {
     pub token_id: String,       // 24 bytes
+    pub trader_address: String, // Whale being copied (attributes GTD fills)
     pub whale_price: f64,       // Original whale price
     pub failed_price: f64,      // Price that failed (our limit)
     pub size: f64,              // Order size in shares
@@ -73,8 +112,14 @@ impl fmt::Display for SizeType {
         match self {
             SizeType::Scaled => f.write_str("SCALED"),
             SizeType::Capped => f.write_str("CAPPED"),
+            SizeType::CappedByTrader => f.write_str("CAPPED_TRADER"),
             SizeType::ProbHit(pct) => write!(f, "PROB_HIT ({}%)", pct),
             SizeType::ProbSkip(pct) => write!(f, "PROB_SKIP ({}%)", pct),
+            SizeType::ProportionalSell => f.write_str("PROP_SELL"),
+            SizeType::Kelly => f.write_str("KELLY"),
+            SizeType::CappedByMarket => f.write_str("CAPPED_MARKET"),
+            SizeType::BelowFloor => f.write_str("BELOW_FLOOR"),
+            SizeType::Flat => f.write_str("FLAT"),
         }
     }
 }
@@ -111,6 +156,27 @@ pub struct LogResult {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_order_info_invalid_field() {
+        let order = |shares: f64, price_per_share: f64, usd_value: f64| OrderInfo {
+            order_type: "BUY_FILL".to_string(),
+            clob_token_id: Arc::from("123456"),
+            usd_value,
+            shares,
+            price_per_share,
+        };
+        assert_eq!(order(500.0, 0.20, 100.0).invalid_field(), None);
+        assert_eq!(order(500.0, 1.0, 500.0).invalid_field(), None);
+
+        assert_eq!(order(0.0, 0.20, 0.0).invalid_field(), Some("shares"));
+        assert_eq!(order(f64::NAN, 0.20, 100.0).invalid_field(), Some("shares"));
+        // Tiny shares, huge USD: a price no outcome share can have
+        assert_eq!(order(0.000001, 1e12, 1e6).invalid_field(), Some("price"));
+        assert_eq!(order(500.0, f64::INFINITY, f64::INFINITY).invalid_field(), Some("price"));
+        assert_eq!(order(500.0, 0.0, 0.0).invalid_field(), Some("price"));
+        assert_eq!(order(500.0, 0.20, f64::NAN).invalid_field(), Some("usd"));
+    }
+
     /// Test that ParsedEvent can hold trader address information
     #[test]
     fn test_parsed_event_has_trader_address() {
@@ -120,6 +186,11 @@ mod tests {
             trader_address: "abc123def456789012345678901234567890abcd".to_string(),
             trader_label: "Whale1".to_string(),
             trader_min_shares: 75.0,
+            trader_max_bet_usd: None,
+            trader_scaling_ratio: 0.0,
+            trader_probabilistic_sizing: None,
+            trader_copy_price_range: CopyPriceRange::default(),
+            trader_observe: false,
             order: OrderInfo {
                 order_type: "BUY_FILL".to_string(),
                 clob_token_id: Arc::from("123456"),
@@ -143,6 +214,11 @@ mod tests {
             trader_address: String::new(),
             trader_label: String::new(),
             trader_min_shares: 0.0,
+            trader_max_bet_usd: None,
+            trader_scaling_ratio: 0.0,
+            trader_probabilistic_sizing: None,
+            trader_copy_price_range: CopyPriceRange::default(),
+            trader_observe: false,
             order: OrderInfo {
                 order_type: "BUY_FILL".to_string(),
                 clob_token_id: Arc::from("123456"),
@@ -165,6 +241,11 @@ mod tests {
             trader_address: "def456def456789012345678901234567890def4".to_string(),
             trader_label: "TopTrader".to_string(),
             trader_min_shares: 100.0,
+            trader_max_bet_usd: None,
+            trader_scaling_ratio: 0.0,
+            trader_probabilistic_sizing: None,
+            trader_copy_price_range: CopyPriceRange::default(),
+            trader_observe: false,
             order: OrderInfo {
                 order_type: "SELL_FILL".to_string(),
                 clob_token_id: Arc::from("789012"),
@@ -0,0 +1,333 @@
+//! Push notifications for executed and blocked trades
+//!
+//! Sinks implement the `Notifier` trait. Each sink owns a bounded channel drained
+//! by a background task that does the HTTP call, so `notify` never blocks the
+//! trade path. When the channel is full the message is dropped.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::settings::Config;
+
+/// Messages buffered per sink before new ones are dropped
+pub const NOTIFY_CHANNEL_CAPACITY: usize = 64;
+
+/// Timeout for a single notification HTTP request
+pub const NOTIFY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Trade outcome passed to every sink
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeNotification {
+    pub trader_label: String,
+    pub side: String,
+    /// Our filled shares (whale shares if nothing was filled)
+    pub shares: f64,
+    /// Our fill price (whale price if nothing was filled)
+    pub price: f64,
+    pub fill_pct: Option<f64>,
+    /// SUCCESS, PARTIAL, FAILED or RISK_BLOCKED
+    pub status: String,
+}
+
+impl TradeNotification {
+    /// Notional of the trade in USD
+    pub fn usd_value(&self) -> f64 {
+        self.shares * self.price
+    }
+
+    /// Human-readable message body (plain text, no ANSI codes)
+    pub fn format_text(&self) -> String {
+        let icon = match self.status.as_str() {
+            "SUCCESS" => "✅",
+            "PARTIAL" => "🟡",
+            "RISK_BLOCKED" => "🛑",
+            _ => "❌",
+        };
+        let fill = self.fill_pct
+            .map(|p| format!(" ({:.0}% filled)", p))
+            .unwrap_or_default();
+        format!(
+            "{} {} {} {:.2} shares @ {:.4}{}\nTrader: {}",
+            icon, self.status, self.side, self.shares, self.price, fill, self.trader_label
+        )
+    }
+}
+
+/// Only executions and risk blocks are worth a push (skips would be noise)
+pub fn should_notify(status: &str) -> bool {
+    matches!(status, "SUCCESS" | "PARTIAL" | "FAILED" | "RISK_BLOCKED")
+}
+
+/// A notification sink
+pub trait Notifier: Send + Sync {
+    /// Queue a notification. Must not block; may drop under backpressure.
+    fn notify(&self, notification: &TradeNotification);
+}
+
+/// Spawn a background task that POSTs queued JSON bodies to `url`
+fn spawn_json_poster(client: reqwest::Client, url: String) -> mpsc::Sender<Value> {
+    let (tx, mut rx) = mpsc::channel::<Value>(NOTIFY_CHANNEL_CAPACITY);
+    tokio::spawn(async move {
+        while let Some(body) = rx.recv().await {
+            match client.post(&url).json(&body).timeout(NOTIFY_TIMEOUT).send().await {
+                Ok(resp) if !resp.status().is_success() => {
+                    eprintln!("⚠️ Notification failed: HTTP {}", resp.status());
+                }
+                Err(e) => eprintln!("⚠️ Notification failed: {}", e),
+                Ok(_) => {}
+            }
+        }
+    });
+    tx
+}
+
+// ============================================================================
+// Telegram
+// ============================================================================
+
+/// Posts messages to a Telegram chat via the Bot API
+pub struct TelegramSink {
+    chat_id: String,
+    tx: mpsc::Sender<Value>,
+}
+
+impl TelegramSink {
+    /// Must be called from within a tokio runtime (spawns the sender task)
+    pub fn new(client: reqwest::Client, bot_token: &str, chat_id: &str) -> Self {
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", bot_token);
+        Self {
+            chat_id: chat_id.to_string(),
+            tx: spawn_json_poster(client, url),
+        }
+    }
+
+    /// sendMessage request body
+    pub fn build_payload(chat_id: &str, notification: &TradeNotification) -> Value {
+        serde_json::json!({
+            "chat_id": chat_id,
+            "text": notification.format_text(),
+            "disable_web_page_preview": true,
+        })
+    }
+}
+
+impl Notifier for TelegramSink {
+    fn notify(&self, notification: &TradeNotification) {
+        // Full channel means the API is slow or down - drop rather than stall trading
+        let _ = self.tx.try_send(Self::build_payload(&self.chat_id, notification));
+    }
+}
+
+// ============================================================================
+// Discord
+// ============================================================================
+
+/// Embed colors by status
+pub const DISCORD_COLOR_SUCCESS: u32 = 0x2ECC71;
+pub const DISCORD_COLOR_PARTIAL: u32 = 0xF1C40F;
+pub const DISCORD_COLOR_FAILED: u32 = 0xE74C3C;
+
+/// Posts embeds to a Discord webhook
+pub struct DiscordSink {
+    /// Trades below this notional are not announced
+    min_usd: f64,
+    tx: mpsc::Sender<Value>,
+}
+
+impl DiscordSink {
+    /// Must be called from within a tokio runtime (spawns the sender task)
+    pub fn new(client: reqwest::Client, webhook_url: &str, min_usd: f64) -> Self {
+        Self {
+            min_usd,
+            tx: spawn_json_poster(client, webhook_url.to_string()),
+        }
+    }
+
+    pub fn embed_color(status: &str) -> u32 {
+        match status {
+            "SUCCESS" => DISCORD_COLOR_SUCCESS,
+            "PARTIAL" => DISCORD_COLOR_PARTIAL,
+            _ => DISCORD_COLOR_FAILED,
+        }
+    }
+
+    /// Webhook request body with a single embed
+    pub fn build_payload(notification: &TradeNotification) -> Value {
+        let fill = notification.fill_pct
+            .map(|p| format!("{:.0}%", p))
+            .unwrap_or_else(|| "-".to_string());
+
+        serde_json::json!({
+            "embeds": [{
+                "title": format!("{} {}", notification.status, notification.side),
+                "color": Self::embed_color(&notification.status),
+                "fields": [
+                    { "name": "Trader", "value": notification.trader_label, "inline": true },
+                    { "name": "Shares", "value": format!("{:.2}", notification.shares), "inline": true },
+                    { "name": "Price", "value": format!("{:.4}", notification.price), "inline": true },
+                    { "name": "Fill", "value": fill, "inline": true },
+                    { "name": "USD", "value": format!("${:.2}", notification.usd_value()), "inline": true },
+                ]
+            }]
+        })
+    }
+}
+
+impl Notifier for DiscordSink {
+    fn notify(&self, notification: &TradeNotification) {
+        if notification.usd_value() < self.min_usd {
+            return;
+        }
+        // Full channel means the webhook is slow or down - drop rather than stall trading
+        let _ = self.tx.try_send(Self::build_payload(notification));
+    }
+}
+
+// ============================================================================
+// Fan-out
+// ============================================================================
+
+/// All configured sinks (cheap to clone; no-op when empty)
+#[derive(Clone, Default)]
+pub struct Notifiers {
+    sinks: Arc<Vec<Box<dyn Notifier>>>,
+}
+
+impl Notifiers {
+    pub fn new(sinks: Vec<Box<dyn Notifier>>) -> Self {
+        Self { sinks: Arc::new(sinks) }
+    }
+
+    /// Build sinks from settings. Must be called from within a tokio runtime.
+    pub fn from_config(cfg: &Config) -> Self {
+        let mut sinks: Vec<Box<dyn Notifier>> = Vec::new();
+        let client = reqwest::Client::new();
+
+        if let (Some(token), Some(chat_id)) = (&cfg.telegram_bot_token, &cfg.telegram_chat_id) {
+            sinks.push(Box::new(TelegramSink::new(client.clone(), token, chat_id)));
+        }
+
+        if let Some(url) = &cfg.discord_webhook_url {
+            sinks.push(Box::new(DiscordSink::new(client, url, cfg.discord_min_usd)));
+        }
+
+        Self::new(sinks)
+    }
+
+    pub fn len(&self) -> usize {
+        self.sinks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+}
+
+impl Notifier for Notifiers {
+    fn notify(&self, notification: &TradeNotification) {
+        for sink in self.sinks.iter() {
+            sink.notify(notification);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(status: &str, fill_pct: Option<f64>) -> TradeNotification {
+        TradeNotification {
+            trader_label: "Whale1".to_string(),
+            side: "BUY".to_string(),
+            shares: 12.5,
+            price: 0.51,
+            fill_pct,
+            status: status.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_format_text_includes_trade_fields() {
+        let text = sample("SUCCESS", Some(100.0)).format_text();
+        assert!(text.contains("SUCCESS"));
+        assert!(text.contains("BUY 12.50 shares @ 0.5100"));
+        assert!(text.contains("(100% filled)"));
+        assert!(text.contains("Trader: Whale1"));
+        assert!(!text.contains('\x1b'), "no ANSI codes in notifications");
+
+        let blocked = sample("RISK_BLOCKED", None).format_text();
+        assert!(blocked.contains("RISK_BLOCKED"));
+        assert!(!blocked.contains("filled"));
+    }
+
+    #[test]
+    fn test_should_notify() {
+        assert!(should_notify("SUCCESS"));
+        assert!(should_notify("PARTIAL"));
+        assert!(should_notify("RISK_BLOCKED"));
+        assert!(!should_notify("SKIPPED_SMALL"));
+        assert!(!should_notify("AGG_PENDING"));
+    }
+
+    #[test]
+    fn test_telegram_drops_when_channel_full() {
+        // No receiver task: the channel fills and further sends must not block
+        let (tx, mut rx) = mpsc::channel(1);
+        let sink = TelegramSink { chat_id: "42".to_string(), tx };
+
+        sink.notify(&sample("SUCCESS", Some(100.0)));
+        sink.notify(&sample("FAILED", None));
+
+        let queued = rx.try_recv().unwrap();
+        assert_eq!(queued["chat_id"], "42");
+        assert!(queued["text"].as_str().unwrap().contains("SUCCESS"));
+        assert!(rx.try_recv().is_err(), "second message should have been dropped");
+    }
+
+    #[test]
+    fn test_discord_embed_per_status() {
+        for (status, color) in [
+            ("SUCCESS", DISCORD_COLOR_SUCCESS),
+            ("PARTIAL", DISCORD_COLOR_PARTIAL),
+            ("FAILED", DISCORD_COLOR_FAILED),
+            ("RISK_BLOCKED", DISCORD_COLOR_FAILED),
+        ] {
+            let payload = DiscordSink::build_payload(&sample(status, Some(50.0)));
+            let embed = &payload["embeds"][0];
+            assert_eq!(embed["title"], format!("{} BUY", status));
+            assert_eq!(embed["color"], color);
+            assert_eq!(embed["fields"][0]["value"], "Whale1");
+            assert_eq!(embed["fields"][1]["value"], "12.50");
+            assert_eq!(embed["fields"][2]["value"], "0.5100");
+            assert_eq!(embed["fields"][3]["value"], "50%");
+            assert_eq!(embed["fields"][4]["value"], "$6.38");
+        }
+
+        let no_fill = DiscordSink::build_payload(&sample("RISK_BLOCKED", None));
+        assert_eq!(no_fill["embeds"][0]["fields"][3]["value"], "-");
+    }
+
+    #[test]
+    fn test_discord_skips_trades_below_min_usd() {
+        let (tx, mut rx) = mpsc::channel(4);
+        let sink = DiscordSink { min_usd: 10.0, tx };
+
+        sink.notify(&sample("SUCCESS", Some(100.0))); // $6.38 - below threshold
+        assert!(rx.try_recv().is_err());
+
+        let mut big = sample("SUCCESS", Some(100.0));
+        big.shares = 100.0;
+        sink.notify(&big); // $51.00
+        assert!(rx.try_recv().is_ok());
+    }
+
+    #[test]
+    fn test_empty_notifiers_is_noop() {
+        let n = Notifiers::default();
+        assert!(n.is_empty());
+        n.notify(&sample("SUCCESS", None));
+    }
+}
@@ -0,0 +1,193 @@
+// migrations.rs - Ordered schema migrations for TradeStore
+//
+// The applied version is tracked in `PRAGMA user_version`. On open, every
+// migration with a higher version runs in order, each in its own transaction
+// together with the version bump. Steps must be idempotent: databases created
+// before versioning existed start at 0 and replay everything.
+
+use anyhow::{Result, Context};
+use rusqlite::{Connection, params};
+
+/// A single schema upgrade step
+pub struct Migration {
+    /// Version recorded once this step has been applied (strictly increasing)
+    pub version: i64,
+    pub description: &'static str,
+    pub apply: fn(&Connection) -> Result<()>,
+}
+
+/// All migrations, oldest first. Append new steps; never edit applied ones.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial trades and trader_stats schema",
+        apply: initial_schema,
+    },
+    Migration {
+        version: 2,
+        description: "trades aggregation_window_ms, trader_stats P&L columns",
+        apply: add_analytics_columns,
+    },
+    Migration {
+        version: 3,
+        description: "position_snapshots table",
+        apply: position_snapshots,
+    },
+    Migration {
+        version: 4,
+        description: "per-trader and per-token timestamp indexes",
+        apply: timestamp_indexes,
+    },
+    Migration {
+        version: 5,
+        description: "trades order_id column",
+        apply: add_order_id,
+    },
+    Migration {
+        version: 6,
+        description: "trades contributing_traders column",
+        apply: add_contributing_traders,
+    },
+    Migration {
+        version: 7,
+        description: "shadow_trades table",
+        apply: shadow_trades,
+    },
+];
+
+/// Version of the newest migration
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// Version currently recorded in the database (0 if never migrated)
+pub fn schema_version(conn: &Connection) -> Result<i64> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+        .context("Failed to read schema version")
+}
+
+/// Apply all pending migrations in order
+///
+/// # Returns
+/// * `Result<usize>` - Number of migrations applied
+pub fn run_migrations(conn: &Connection) -> Result<usize> {
+    let current = schema_version(conn)?;
+    if current > latest_version() {
+        anyhow::bail!(
+            "Database schema version {} is newer than this build supports ({})",
+            current, latest_version()
+        );
+    }
+
+    let mut applied = 0;
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let tx = conn.unchecked_transaction()
+            .context("Failed to begin migration transaction")?;
+        (migration.apply)(&tx).with_context(|| {
+            format!("Migration {} ({}) failed", migration.version, migration.description)
+        })?;
+        // PRAGMA does not accept bound parameters
+        tx.execute_batch(&format!("PRAGMA user_version = {}", migration.version))
+            .context("Failed to record schema version")?;
+        tx.commit().context("Failed to commit migration")?;
+        applied += 1;
+    }
+
+    Ok(applied)
+}
+
+/// Add a column unless it already exists (SQLite has no ADD COLUMN IF NOT EXISTS)
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+        params![table, column],
+        |row| row.get::<_, i64>(0).map(|c| c > 0),
+    ).with_context(|| format!("Failed to inspect {} columns", table))?;
+
+    if !exists {
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+            .with_context(|| format!("Failed to add {}.{}", table, column))?;
+    }
+    Ok(())
+}
+
+fn initial_schema(conn: &Connection) -> Result<()> {
+    conn.execute_batch(include_str!("schema.sql"))
+        .context("Failed to initialize schema")
+}
+
+fn add_analytics_columns(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "trades", "aggregation_window_ms", "INTEGER")?;
+    add_column_if_missing(conn, "trader_stats", "realized_pnl", "REAL DEFAULT 0")?;
+    add_column_if_missing(conn, "trader_stats", "winning_trades", "INTEGER DEFAULT 0")?;
+    add_column_if_missing(conn, "trader_stats", "losing_trades", "INTEGER DEFAULT 0")
+}
+
+/// Per-token totals carried over from pruned trades
+/// Only the latest snapshot is kept; trades with id > last_trade_id are applied on top
+fn position_snapshots(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS position_snapshots (
+            token_id TEXT PRIMARY KEY,
+            snapshot_ms INTEGER NOT NULL,        -- When the snapshot was taken
+            last_trade_id INTEGER NOT NULL,      -- Highest trades.id included
+            net_shares REAL NOT NULL,
+            buy_shares REAL NOT NULL,            -- Total BUY shares (for avg entry)
+            buy_usd REAL,                        -- Total BUY USD (for avg entry)
+            trade_count INTEGER NOT NULL
+        );"
+    ).context("Failed to create position_snapshots")
+}
+
+/// Composite indexes serve both the filter and ORDER BY timestamp_ms DESC.
+/// They cover the single-column trader/token indexes, which are dropped.
+fn timestamp_indexes(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_trades_trader_timestamp ON trades(trader_address, timestamp_ms DESC);
+         CREATE INDEX IF NOT EXISTS idx_trades_token_timestamp ON trades(token_id, timestamp_ms DESC);
+         DROP INDEX IF EXISTS idx_trades_trader;
+         DROP INDEX IF EXISTS idx_trades_token;"
+    ).context("Failed to create timestamp indexes")
+}
+
+/// Exchange order ID, for later status queries, cancels and reconciliation
+fn add_order_id(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "trades", "order_id", "TEXT")
+}
+
+/// JSON array of the traders behind an aggregated copy, for attribution
+fn add_contributing_traders(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "trades", "contributing_traders", "TEXT")
+}
+
+/// Orders a second sizing strategy would have placed (SHADOW_MODE); never submitted.
+/// Kept out of trades so live positions, exposure and P&L never see them.
+fn shadow_trades(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS shadow_trades (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp_ms INTEGER NOT NULL,
+            block_number INTEGER NOT NULL,
+            tx_hash TEXT NOT NULL,
+            trader_address TEXT NOT NULL,
+            token_id TEXT NOT NULL,
+            side TEXT NOT NULL CHECK (side IN ('BUY', 'SELL')),
+            whale_shares REAL NOT NULL,
+            whale_price REAL NOT NULL,
+            whale_usd REAL NOT NULL,
+            our_shares REAL,                     -- Shares the shadow strategy would have ordered
+            our_price REAL,                      -- Limit price the live order used
+            our_usd REAL,
+            fill_pct REAL,
+            status TEXT NOT NULL,                -- Always SHADOW
+            latency_ms INTEGER,
+            is_live BOOLEAN,
+            aggregation_count INTEGER,
+            aggregation_window_ms INTEGER,
+            order_id TEXT,
+            contributing_traders TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_shadow_trades_token_timestamp ON shadow_trades(token_id, timestamp_ms DESC);"
+    ).context("Failed to create shadow_trades")
+}
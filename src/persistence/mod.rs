@@ -3,9 +3,10 @@
 // This module provides non-blocking trade persistence with <1ms latency on the hot path.
 // Uses WAL mode for concurrent reads during writes and buffered writes for performance.
 
+mod migrations;
 mod store;
 
-pub use store::{TradeStore, TradeRecord, Position, AggregationStats};
+pub use store::{TradeStore, TradeRecord, TradeFilter, Position, AggregationStats, SHADOW_STATUS};
 
 #[cfg(test)]
 mod tests {
@@ -121,6 +122,8 @@ mod tests {
             is_live: Some(true),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         };
 
         // Verify all fields are accessible and have correct values
@@ -164,6 +167,8 @@ mod tests {
             is_live: Some(true),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         };
 
         // Verify failed trade characteristics
@@ -196,6 +201,8 @@ mod tests {
             is_live: Some(true),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         };
 
         let cloned = original.clone();
@@ -233,6 +240,8 @@ mod tests {
             is_live: Some(true),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         };
 
         // Insert the trade
@@ -283,6 +292,8 @@ mod tests {
             is_live: Some(false),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         }
     }
 
@@ -308,6 +319,8 @@ mod tests {
             is_live: Some(false),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         }
     }
 
@@ -318,7 +331,7 @@ mod tests {
     #[test]
     fn test_record_trade_buffered() {
         let store = TradeStore::with_buffer_size(":memory:", 10).unwrap();
-        store.record_trade(make_test_trade("token1", "BUY", 100.0));
+        store.record_trade(make_test_trade("token1", "BUY", 100.0)).unwrap();
 
         // Verify trade is in buffer, not in DB yet
         let count: i64 = store.conn
@@ -331,7 +344,7 @@ mod tests {
     fn test_auto_flush_at_buffer_size() {
         let store = TradeStore::with_buffer_size(":memory:", 5).unwrap();
         for i in 0..5 {
-            store.record_trade(make_test_trade(&format!("token{}", i), "BUY", 100.0));
+            store.record_trade(make_test_trade(&format!("token{}", i), "BUY", 100.0)).unwrap();
         }
         // After 5 trades with buffer_size=5, should auto-flush
         let count: i64 = store.conn
@@ -343,8 +356,8 @@ mod tests {
     #[test]
     fn test_manual_flush() {
         let store = TradeStore::with_buffer_size(":memory:", 50).unwrap();
-        store.record_trade(make_test_trade("token1", "BUY", 100.0));
-        store.record_trade(make_test_trade("token2", "SELL", 50.0));
+        store.record_trade(make_test_trade("token1", "BUY", 100.0)).unwrap();
+        store.record_trade(make_test_trade("token2", "SELL", 50.0)).unwrap();
 
         let flushed = store.flush().unwrap();
         assert_eq!(flushed, 2);
@@ -366,7 +379,7 @@ mod tests {
     #[test]
     fn test_flush_idempotent() {
         let store = TradeStore::with_buffer_size(":memory:", 50).unwrap();
-        store.record_trade(make_test_trade("token1", "BUY", 100.0));
+        store.record_trade(make_test_trade("token1", "BUY", 100.0)).unwrap();
 
         let flushed1 = store.flush().unwrap();
         assert_eq!(flushed1, 1);
@@ -387,7 +400,7 @@ mod tests {
         let store = TradeStore::with_buffer_size(":memory:", 100).unwrap();
 
         for i in 0..10 {
-            store.record_trade(make_test_trade(&format!("token{}", i), "BUY", 100.0));
+            store.record_trade(make_test_trade(&format!("token{}", i), "BUY", 100.0)).unwrap();
         }
 
         let flushed = store.flush().unwrap();
@@ -399,6 +412,204 @@ mod tests {
         assert_eq!(count, 10, "All 10 trades should be in DB");
     }
 
+    #[test]
+    fn test_order_id_round_trip() {
+        let store = TradeStore::new(":memory:").unwrap();
+        let mut placed = make_trade_with_our_shares("token1", "BUY", 10.0, 0.50);
+        placed.order_id = Some("0xorder123".to_string());
+        let skipped = make_test_trade("token2", "BUY", 100.0);
+
+        store.insert_trade(&placed).unwrap();
+        store.insert_trade(&skipped).unwrap();
+        store.insert_trades_batch(std::slice::from_ref(&placed)).unwrap();
+
+        let trades = store.get_recent_trades(10).unwrap();
+        let ids: Vec<Option<&str>> = trades.iter().map(|t| t.order_id.as_deref()).collect();
+        assert_eq!(ids.iter().filter(|id| **id == Some("0xorder123")).count(), 2);
+        assert_eq!(ids.iter().filter(|id| id.is_none()).count(), 1);
+    }
+
+    #[test]
+    fn test_failed_flush_keeps_trades_buffered() {
+        let store = TradeStore::new(":memory:").unwrap();
+        store.conn.execute_batch(
+            "CREATE TRIGGER fail_second BEFORE INSERT ON trades WHEN NEW.token_id = 'token2'
+             BEGIN SELECT RAISE(FAIL, 'disk full'); END;"
+        ).unwrap();
+        store.record_trade(make_test_trade("token1", "BUY", 100.0)).unwrap();
+        store.record_trade(make_test_trade("token2", "BUY", 100.0)).unwrap();
+
+        // One transaction: the first trade isn't written on its own
+        assert!(store.flush().is_err());
+        assert_eq!(store.buffered_count(), 2);
+        assert!(store.get_recent_trades(10).unwrap().is_empty());
+
+        let dir = tempfile::tempdir().unwrap();
+        let spill = dir.path().join("trades.spill.jsonl");
+        assert_eq!(store.spill_buffered(&spill).unwrap(), 2);
+        assert_eq!(store.buffered_count(), 0);
+        let lines: Vec<TradeRecord> = fs::read_to_string(&spill).unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.iter().map(|t| t.token_id.as_str()).collect::<Vec<_>>(), vec!["token1", "token2"]);
+    }
+
+    #[test]
+    fn test_contributing_traders_round_trip() {
+        let store = TradeStore::new(":memory:").unwrap();
+        let mut aggregated = make_trade_with_our_shares("token1", "BUY", 10.0, 0.50);
+        aggregated.aggregation_count = Some(3);
+        aggregated.contributing_traders = Some(vec!["0xa".to_string(), "0xb".to_string(), "0xc".to_string()]);
+
+        store.insert_trade(&aggregated).unwrap();
+        store.insert_trade(&make_test_trade("token2", "BUY", 100.0)).unwrap();
+
+        let trades = store.get_recent_trades(10).unwrap();
+        let traders: Vec<Option<usize>> = trades.iter().map(|t| t.contributing_traders.as_ref().map(Vec::len)).collect();
+        assert!(traders.contains(&Some(3)));
+        assert!(traders.contains(&None));
+
+        let stored: String = store.conn
+            .query_row("SELECT contributing_traders FROM trades WHERE token_id = 'token1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stored, r#"["0xa","0xb","0xc"]"#);
+    }
+
+    #[test]
+    fn test_shadow_rows_stay_out_of_live_tables() {
+        let store = TradeStore::new(":memory:").unwrap();
+        let shadow = |side: &str, shares: f64, price: f64| TradeRecord {
+            status: SHADOW_STATUS.to_string(),
+            ..make_trade_with_our_shares("token1", side, shares, price)
+        };
+        store.insert_trade(&make_trade_with_our_shares("token1", "BUY", 10.0, 0.50)).unwrap();
+        store.insert_trades_batch(&[shadow("BUY", 40.0, 0.50), shadow("SELL", 15.0, 0.60)]).unwrap();
+
+        // Live views only see the live fill
+        assert_eq!(store.get_trade_count().unwrap(), 1);
+        assert_eq!(store.get_fills().unwrap().len(), 1);
+        let positions = store.get_positions().unwrap();
+        assert_eq!(positions.len(), 1);
+        assert!((positions[0].net_shares - 10.0).abs() < 1e-9);
+
+        let shadow_fills = store.get_shadow_fills_before(i64::MAX).unwrap();
+        assert_eq!(shadow_fills.iter().map(|f| f.shares).collect::<Vec<_>>(), vec![40.0, 15.0]);
+        assert!((store.get_shadow_net_shares("token1").unwrap() - 25.0).abs() < 1e-9);
+        assert_eq!(store.get_shadow_net_shares("token2").unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_get_trades_filtered() {
+        let store = TradeStore::new(":memory:").unwrap();
+        let whale_a = "0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+        let whale_b = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+        // (trader, token, status), one second apart
+        let rows = [
+            (whale_a, "token1", "SUCCESS"),
+            (whale_a, "token2", "SKIPPED"),
+            (whale_b, "token1", "SUCCESS"),
+            (whale_b, "token1", "FAILED"),
+            (whale_a, "token1", "SUCCESS"),
+        ];
+        for (i, (trader, token, status)) in rows.iter().enumerate() {
+            let mut trade = make_test_trade(token, "BUY", 100.0);
+            trade.timestamp_ms = 1_700_000_000_000 + i as i64 * 1000;
+            trade.trader_address = trader.to_string();
+            trade.status = status.to_string();
+            store.insert_trade(&trade).unwrap();
+        }
+
+        let query = |trader: Option<&str>, token: Option<&str>, status: Option<&str>, since_ms: Option<i64>| -> Vec<i64> {
+            let filter = TradeFilter {
+                trader: trader.map(String::from),
+                token: token.map(String::from),
+                status: status.map(String::from),
+                since_ms,
+            };
+            store.get_trades_filtered(&filter, 50).unwrap().iter()
+                .map(|t| (t.timestamp_ms - 1_700_000_000_000) / 1000)
+                .collect()
+        };
+
+        assert_eq!(query(None, None, None, None), vec![4, 3, 2, 1, 0]);
+        // Trader matches with or without 0x, in any case
+        assert_eq!(query(Some(whale_a), None, None, None), vec![4, 1, 0]);
+        assert_eq!(query(Some("0xBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBB"), None, None, None), vec![3, 2]);
+        assert_eq!(query(None, Some("token1"), None, None), vec![4, 3, 2, 0]);
+        assert_eq!(query(None, None, Some("success"), None), vec![4, 2, 0]);
+        assert_eq!(query(None, None, None, Some(1_700_000_003_000)), vec![4, 3]);
+        assert_eq!(query(Some(whale_a), Some("token1"), None, None), vec![4, 0]);
+        assert_eq!(query(Some(whale_b), None, Some("FAILED"), None), vec![3]);
+        assert_eq!(query(None, Some("token1"), Some("SUCCESS"), Some(1_700_000_001_000)), vec![4, 2]);
+        assert_eq!(query(Some(whale_a), Some("token1"), Some("SUCCESS"), Some(1_700_000_001_000)), vec![4]);
+        assert!(query(Some(whale_b), Some("token2"), None, None).is_empty());
+
+        // Limit applies after filtering
+        let filter = TradeFilter { token: Some("token1".to_string()), ..Default::default() };
+        assert_eq!(store.get_trades_filtered(&filter, 2).unwrap().len(), 2);
+    }
+
+    // ============================================================================
+    // Batch Insert Tests
+    // ============================================================================
+
+    #[test]
+    fn test_insert_trades_batch() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TradeStore::new(dir.path().join("batch.db")).unwrap();
+
+        let records: Vec<TradeRecord> = (0..1000i64)
+            .map(|i| {
+                let mut trade = make_test_trade(&format!("token{}", i % 10), "BUY", 100.0 + i as f64);
+                trade.timestamp_ms = 1_700_000_000_000 + i;
+                trade.tx_hash = format!("0xbatch{}", i);
+                trade
+            })
+            .collect();
+
+        assert_eq!(store.insert_trades_batch(&records).unwrap(), 1000);
+        assert_eq!(store.get_trade_count().unwrap(), 1000);
+
+        let (token_id, whale_shares, timestamp_ms): (String, f64, i64) = store.conn.query_row(
+            "SELECT token_id, whale_shares, timestamp_ms FROM trades WHERE tx_hash = ?1",
+            params!["0xbatch537"],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).unwrap();
+        assert_eq!(token_id, "token7");
+        assert_eq!(whale_shares, 637.0);
+        assert_eq!(timestamp_ms, 1_700_000_000_537);
+
+        assert_eq!(store.insert_trades_batch(&[]).unwrap(), 0);
+    }
+
+    #[test]
+    #[ignore] // Benchmark - run with: cargo test --lib test_insert_trades_batch_faster -- --ignored --nocapture
+    fn test_insert_trades_batch_faster_than_single_inserts() {
+        let dir = tempfile::tempdir().unwrap();
+        let records: Vec<TradeRecord> = (0..1000)
+            .map(|i| make_test_trade(&format!("token{}", i), "BUY", 100.0))
+            .collect();
+
+        let looped = TradeStore::new(dir.path().join("loop.db")).unwrap();
+        let start = std::time::Instant::now();
+        for record in &records {
+            looped.insert_trade(record).unwrap();
+        }
+        let loop_elapsed = start.elapsed();
+
+        let batched = TradeStore::new(dir.path().join("batch.db")).unwrap();
+        let start = std::time::Instant::now();
+        batched.insert_trades_batch(&records).unwrap();
+        let batch_elapsed = start.elapsed();
+
+        assert_eq!(looped.get_trade_count().unwrap(), batched.get_trade_count().unwrap());
+        assert!(
+            batch_elapsed * 2 < loop_elapsed,
+            "batch {:?} should be well under loop {:?}", batch_elapsed, loop_elapsed
+        );
+    }
+
     // ============================================================================
     // Query Methods Tests - get_recent_trades
     // ============================================================================
@@ -444,6 +655,26 @@ mod tests {
         assert_eq!(trades.len(), 3);
     }
 
+    #[test]
+    fn test_get_trades_between_bounds() {
+        let store = TradeStore::new(":memory:").unwrap();
+
+        for (i, ts) in [999, 1_000, 1_500, 1_999, 2_000].into_iter().enumerate() {
+            let mut trade = make_test_trade(&format!("token{}", i), "BUY", 100.0);
+            trade.timestamp_ms = ts;
+            store.insert_trade(&trade).unwrap();
+        }
+
+        // Start inclusive, end exclusive, oldest first
+        let trades = store.get_trades_between(1_000, 2_000).unwrap();
+        let stamps: Vec<i64> = trades.iter().map(|t| t.timestamp_ms).collect();
+        assert_eq!(stamps, vec![1_000, 1_500, 1_999]);
+
+        // Adjacent ranges don't overlap
+        assert_eq!(store.get_trades_between(2_000, 3_000).unwrap().len(), 1);
+        assert_eq!(store.get_trades_between(0, 1_000).unwrap().len(), 1);
+    }
+
     // ============================================================================
     // Position Tests - get_positions
     // ============================================================================
@@ -536,6 +767,342 @@ mod tests {
         assert_eq!(positions.len(), 0, "Zero position should not appear");
     }
 
+    #[test]
+    fn test_prune_before_deletes_only_old_trades() {
+        let store = TradeStore::new(":memory:").unwrap();
+
+        // Each token is bought and sold out 100ms later
+        for (i, ts) in [1_000, 2_000, 3_000].into_iter().enumerate() {
+            for (side, offset) in [("BUY", 0), ("SELL", 100)] {
+                let mut trade = make_trade_with_our_shares(&format!("token{}", i), side, 10.0, 0.5);
+                trade.timestamp_ms = ts + offset;
+                store.insert_trade(&trade).unwrap();
+            }
+        }
+
+        // Cutoff is exclusive: token1's BUY at exactly 2000 is kept
+        assert_eq!(store.prune_before(2_000).unwrap(), 2);
+        assert_eq!(store.get_trade_count().unwrap(), 4);
+
+        // token1 is still open at 2050 (its SELL comes later), so its BUY stays
+        assert_eq!(store.prune_before(2_050).unwrap(), 0);
+        store.vacuum().unwrap();
+        assert_eq!(store.get_trade_count().unwrap(), 4);
+    }
+
+    #[test]
+    fn test_prune_before_keeps_tokens_open_at_cutoff() {
+        let pruned = TradeStore::new(":memory:").unwrap();
+        let unpruned = TradeStore::new(":memory:").unwrap();
+
+        let trades = [
+            (1_000, "0xaaa", "token1", "BUY", 100.0, 0.50),
+            (2_000, "0xaaa", "token1", "SELL", 40.0, 0.70), // token1 still open at the cutoff
+            (3_000, "0xbbb", "token2", "BUY", 40.0, 0.30),
+            (4_000, "0xbbb", "token2", "SELL", 40.0, 0.35), // closed
+            (9_000, "0xaaa", "token1", "SELL", 60.0, 0.80),
+        ];
+        for (ts, trader, token, side, shares, price) in trades {
+            let mut trade = make_trade_with_our_shares(token, side, shares, price);
+            trade.timestamp_ms = ts;
+            trade.trader_address = trader.to_string();
+            pruned.insert_trade(&trade).unwrap();
+            unpruned.insert_trade(&trade).unwrap();
+        }
+
+        pruned.snapshot_positions().unwrap();
+        assert_eq!(pruned.prune_before(5_000).unwrap(), 2, "only token2's rows go");
+
+        // The last SELL still closes token1's lots, and the whale's holdings are intact
+        let realized = pruned.get_realized_pnl_by_token(0.0).unwrap();
+        assert_eq!(realized["token1"], unpruned.get_realized_pnl_by_token(0.0).unwrap()["token1"]);
+        assert_eq!(
+            pruned.get_realized_pnl_by_trader(0.0).unwrap()["0xaaa"],
+            unpruned.get_realized_pnl_by_trader(0.0).unwrap()["0xaaa"]
+        );
+        assert_eq!(pruned.get_whale_net_shares("0xaaa", "token1").unwrap(), Some(0.0));
+        assert_eq!(position_summary(&pruned), position_summary(&unpruned));
+    }
+
+    /// (token, net_shares, avg_entry_price, trade_count) sorted by token
+    fn position_summary(store: &TradeStore) -> Vec<(String, f64, Option<f64>, i32)> {
+        let mut positions: Vec<_> = store.get_positions().unwrap().into_iter()
+            .map(|p| (p.token_id, p.net_shares, p.avg_entry_price.map(|v| (v * 1e9).round() / 1e9), p.trade_count))
+            .collect();
+        positions.sort_by(|a, b| a.0.cmp(&b.0));
+        positions
+    }
+
+    #[test]
+    fn test_migration_creates_position_snapshots() {
+        let store = TradeStore::new(":memory:").unwrap();
+        assert!(store.table_exists("position_snapshots").unwrap());
+        assert_eq!(migrations::schema_version(&store.conn).unwrap(), migrations::latest_version());
+    }
+
+    #[test]
+    fn test_old_schema_database_upgrades_in_place() {
+        let db_path = temp_db_path();
+        cleanup_db(&db_path);
+
+        // Pre-versioning layout: no aggregation_window_ms, no trader_stats P&L columns
+        {
+            let conn = rusqlite::Connection::open(&db_path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE trades (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    timestamp_ms INTEGER NOT NULL,
+                    block_number INTEGER NOT NULL,
+                    tx_hash TEXT NOT NULL,
+                    trader_address TEXT NOT NULL,
+                    token_id TEXT NOT NULL,
+                    side TEXT NOT NULL CHECK (side IN ('BUY', 'SELL')),
+                    whale_shares REAL NOT NULL,
+                    whale_price REAL NOT NULL,
+                    whale_usd REAL NOT NULL,
+                    our_shares REAL,
+                    our_price REAL,
+                    our_usd REAL,
+                    fill_pct REAL,
+                    status TEXT NOT NULL,
+                    latency_ms INTEGER,
+                    is_live BOOLEAN,
+                    is_aggregated BOOLEAN DEFAULT FALSE,
+                    aggregation_count INTEGER DEFAULT 1
+                );
+                CREATE TABLE trader_stats (
+                    trader_address TEXT PRIMARY KEY,
+                    label TEXT,
+                    total_trades INTEGER DEFAULT 0,
+                    successful_trades INTEGER DEFAULT 0,
+                    failed_trades INTEGER DEFAULT 0,
+                    total_copied_usd REAL DEFAULT 0,
+                    last_trade_ts INTEGER,
+                    daily_reset_ts INTEGER,
+                    created_at INTEGER NOT NULL,
+                    updated_at INTEGER NOT NULL
+                );
+                INSERT INTO trades (timestamp_ms, block_number, tx_hash, trader_address, token_id, side,
+                                    whale_shares, whale_price, whale_usd, our_shares, our_price, our_usd, status)
+                VALUES (1000, 1, '0xold', '0xwhale', 'token1', 'BUY', 100, 0.5, 50, 10, 0.5, 5, 'SUCCESS');
+                INSERT INTO trader_stats (trader_address, label, total_trades, daily_reset_ts, created_at, updated_at)
+                VALUES ('0xwhale', 'Old', 7, 1000, 1000, 1000);"
+            ).unwrap();
+        }
+
+        let store = TradeStore::new(&db_path).unwrap();
+        assert_eq!(store.get_schema_version().unwrap(), migrations::latest_version());
+        assert!(store.get_table_columns("trades").unwrap().contains(&"aggregation_window_ms".to_string()));
+        assert!(store.get_table_columns("trades").unwrap().contains(&"order_id".to_string()));
+        assert!(store.get_table_columns("trades").unwrap().contains(&"contributing_traders".to_string()));
+        assert!(store.get_table_columns("trader_stats").unwrap().contains(&"realized_pnl".to_string()));
+        assert!(store.table_exists("position_snapshots").unwrap());
+
+        // Existing rows survive and new writes use the added columns
+        assert_eq!(store.get_trade_count().unwrap(), 1);
+        let positions = store.get_positions().unwrap();
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].net_shares, 10.0);
+        let stats = store.get_all_trader_stats().unwrap();
+        assert_eq!((stats[0].1.as_str(), stats[0].2), ("Old", 7));
+
+        let mut trade = make_test_trade("token2", "BUY", 50.0);
+        trade.aggregation_window_ms = Some(250);
+        store.insert_trade(&trade).unwrap();
+        assert_eq!(store.get_trade_count().unwrap(), 2);
+        drop(store);
+
+        // Reopening is a no-op
+        let store = TradeStore::new(&db_path).unwrap();
+        assert_eq!(migrations::run_migrations(&store.conn).unwrap(), 0);
+        assert_eq!(store.get_trade_count().unwrap(), 2);
+        drop(store);
+
+        cleanup_db(&db_path);
+    }
+
+    #[test]
+    fn test_newer_schema_version_is_rejected() {
+        let db_path = temp_db_path();
+        cleanup_db(&db_path);
+
+        {
+            let conn = rusqlite::Connection::open(&db_path).unwrap();
+            conn.execute_batch(&format!("PRAGMA user_version = {}", migrations::latest_version() + 1)).unwrap();
+        }
+        assert!(TradeStore::new(&db_path).is_err());
+
+        cleanup_db(&db_path);
+    }
+
+    #[test]
+    fn test_snapshot_then_prune_preserves_positions() {
+        let pruned = TradeStore::new(":memory:").unwrap();
+        let unpruned = TradeStore::new(":memory:").unwrap();
+
+        let trades = [
+            (1_000, "token1", "BUY", 100.0, 0.50),
+            (2_000, "token1", "BUY", 50.0, 0.60),
+            (3_000, "token2", "BUY", 40.0, 0.30),
+            (4_000, "token1", "SELL", 30.0, 0.70),
+            (5_000, "token2", "SELL", 40.0, 0.35), // closed
+        ];
+        for (ts, token, side, shares, price) in trades {
+            let mut trade = make_trade_with_our_shares(token, side, shares, price);
+            trade.timestamp_ms = ts;
+            pruned.insert_trade(&trade).unwrap();
+            unpruned.insert_trade(&trade).unwrap();
+        }
+
+        pruned.snapshot_positions().unwrap();
+        // token1 is still open, so only closed token2 is pruned
+        assert_eq!(pruned.prune_before(i64::MAX).unwrap(), 2);
+        assert_eq!(position_summary(&pruned), position_summary(&unpruned));
+
+        // Later trades apply on top of the snapshot, and a second snapshot folds them in
+        for (token, side, shares, price) in [("token1", "SELL", 20.0, 0.80), ("token3", "BUY", 10.0, 0.20)] {
+            let trade = make_trade_with_our_shares(token, side, shares, price);
+            pruned.insert_trade(&trade).unwrap();
+            unpruned.insert_trade(&trade).unwrap();
+        }
+        assert_eq!(position_summary(&pruned), position_summary(&unpruned));
+
+        pruned.snapshot_positions().unwrap();
+        pruned.prune_before(i64::MAX).unwrap();
+        let summary = position_summary(&pruned);
+        assert_eq!(summary, position_summary(&unpruned));
+        assert_eq!(summary.len(), 2);
+        assert_eq!(summary[0].1, 100.0); // token1: 150 bought - 50 sold
+        assert_eq!(summary[0].2, Some(0.533333333)); // (50 + 30) / 150
+    }
+
+    #[test]
+    fn test_snapshot_without_new_trades_is_noop() {
+        let store = TradeStore::new(":memory:").unwrap();
+        store.snapshot_positions().unwrap();
+        assert!(store.get_positions().unwrap().is_empty());
+
+        store.insert_trade(&make_trade_with_our_shares("token1", "BUY", 10.0, 0.5)).unwrap();
+        store.snapshot_positions().unwrap();
+        store.snapshot_positions().unwrap();
+        assert_eq!(position_summary(&store), vec![("token1".to_string(), 10.0, Some(0.5), 1)]);
+    }
+
+    #[test]
+    fn test_get_trades_by_trader() {
+        let store = TradeStore::new(":memory:").unwrap();
+
+        for (i, trader) in ["0xaaa", "0xbbb", "0xaaa", "0xaaa"].into_iter().enumerate() {
+            let mut trade = make_test_trade(&format!("token{}", i), "BUY", 10.0);
+            trade.trader_address = trader.to_string();
+            trade.timestamp_ms = 1_000 * (i as i64 + 1);
+            store.insert_trade(&trade).unwrap();
+        }
+
+        let trades = store.get_trades_by_trader("0xaaa", 2).unwrap();
+        let tokens: Vec<_> = trades.iter().map(|t| t.token_id.as_str()).collect();
+        assert_eq!(tokens, vec!["token3", "token2"]); // newest first, limited
+
+        assert_eq!(store.get_trades_by_trader("0xbbb", 10).unwrap().len(), 1);
+        assert!(store.get_trades_by_trader("0xccc", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_trader_query_uses_timestamp_index() {
+        let store = TradeStore::new(":memory:").unwrap();
+
+        let plan: Vec<String> = store.conn
+            .prepare("EXPLAIN QUERY PLAN SELECT * FROM trades WHERE trader_address = ?1 ORDER BY timestamp_ms DESC LIMIT 50")
+            .unwrap()
+            .query_map(params!["0xaaa"], |row| row.get::<_, String>(3))
+            .unwrap()
+            .collect::<std::result::Result<_, _>>()
+            .unwrap();
+        let plan = plan.join("\n");
+
+        assert!(plan.contains("idx_trades_trader_timestamp"), "plan: {}", plan);
+        assert!(!plan.contains("TEMP B-TREE"), "ORDER BY should come from the index: {}", plan);
+    }
+
+    #[test]
+    #[ignore] // Benchmark - run with: cargo test --lib bench_trades_by_trader -- --ignored --nocapture
+    fn bench_trades_by_trader_100k() {
+        use std::time::Instant;
+
+        let store = TradeStore::new(":memory:").unwrap();
+        let tx = store.conn.unchecked_transaction().unwrap();
+        for i in 0..100_000i64 {
+            let mut trade = make_test_trade(&format!("token{}", i % 500), "BUY", 10.0);
+            trade.trader_address = format!("0x{:040x}", i % 200);
+            trade.timestamp_ms = i;
+            store.insert_trade(&trade).unwrap();
+        }
+        tx.commit().unwrap();
+
+        let target = format!("0x{:040x}", 7);
+        let start = Instant::now();
+        let scanned: Vec<_> = store.get_recent_trades(100_000).unwrap()
+            .into_iter()
+            .filter(|t| t.trader_address == target)
+            .take(50)
+            .collect();
+        let scan_elapsed = start.elapsed();
+
+        let start = Instant::now();
+        let indexed = store.get_trades_by_trader(&target, 50).unwrap();
+        let indexed_elapsed = start.elapsed();
+
+        assert_eq!(scanned.len(), 50);
+        assert_eq!(
+            scanned.iter().map(|t| t.timestamp_ms).collect::<Vec<_>>(),
+            indexed.iter().map(|t| t.timestamp_ms).collect::<Vec<_>>()
+        );
+        println!("100k rows: load+filter {:?}, indexed query {:?}", scan_elapsed, indexed_elapsed);
+        assert!(indexed_elapsed < scan_elapsed);
+    }
+
+    #[test]
+    fn test_get_whale_net_shares() {
+        let store = TradeStore::new(":memory:").unwrap();
+        let trader = "0x1234567890abcdef1234567890abcdef12345678";
+
+        assert_eq!(store.get_whale_net_shares(trader, "token1").unwrap(), None);
+
+        // Uncopied whale trades count too
+        for (side, shares) in [("BUY", 1000.0), ("BUY", 500.0), ("SELL", 300.0)] {
+            store.insert_trade(&make_test_trade("token1", side, shares)).unwrap();
+        }
+
+        assert_eq!(store.get_whale_net_shares(trader, "token1").unwrap(), Some(1200.0));
+        assert_eq!(store.get_whale_net_shares("0xother", "token1").unwrap(), None);
+    }
+
+    #[test]
+    fn test_tokens_with_status_since_last_buy() {
+        let store = TradeStore::new(":memory:").unwrap();
+        let trade = |token: &str, side: &str, status: &str, ts: i64| {
+            let mut t = make_test_trade(token, side, 100.0);
+            t.timestamp_ms = ts;
+            t.our_shares = Some(10.0);
+            t.status = status.to_string();
+            t
+        };
+
+        // token1: scaled out after the last BUY
+        store.insert_trade(&trade("token1", "BUY", "SUCCESS", 1_000)).unwrap();
+        store.insert_trade(&trade("token1", "SELL", "TAKE_PROFIT", 2_000)).unwrap();
+        // token2: bought again after scaling out, so it is re-armed
+        store.insert_trade(&trade("token2", "BUY", "SUCCESS", 1_000)).unwrap();
+        store.insert_trade(&trade("token2", "SELL", "TAKE_PROFIT", 2_000)).unwrap();
+        store.insert_trade(&trade("token2", "BUY", "SUCCESS", 3_000)).unwrap();
+        // token3: never scaled out
+        store.insert_trade(&trade("token3", "BUY", "SUCCESS", 1_000)).unwrap();
+
+        let tokens = store.tokens_with_status_since_last_buy("TAKE_PROFIT").unwrap();
+        assert_eq!(tokens, ["token1".to_string()].into_iter().collect());
+        assert!(store.tokens_with_status_since_last_buy("STOP_LOSS").unwrap().is_empty());
+    }
+
     #[test]
     fn test_get_positions_avg_entry_price() {
         let store = TradeStore::new(":memory:").unwrap();
@@ -581,6 +1148,8 @@ mod tests {
             is_live: Some(true),
             aggregation_count: Some(3),
             aggregation_window_ms: Some(750),
+            order_id: None,
+            contributing_traders: None,
         };
 
         // Verify aggregation fields are accessible
@@ -610,6 +1179,8 @@ mod tests {
             is_live: Some(true),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         };
 
         // Verify non-aggregated trade has None for aggregation fields
@@ -644,6 +1215,8 @@ mod tests {
             is_live: Some(true),
             aggregation_count: Some(3),
             aggregation_window_ms: Some(750),
+            order_id: None,
+            contributing_traders: None,
         };
 
         // Insert the trade
@@ -689,6 +1262,8 @@ mod tests {
             is_live: Some(true),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         };
 
         // Insert the trade
@@ -731,6 +1306,8 @@ mod tests {
                 is_live: Some(true),
                 aggregation_count: None, // Not aggregated
                 aggregation_window_ms: None,
+                order_id: None,
+                contributing_traders: None,
             };
             store.insert_trade(&record).expect("Failed to insert trade");
         }
@@ -779,6 +1356,8 @@ mod tests {
                 is_live: Some(true),
                 aggregation_count: *count,
                 aggregation_window_ms: *window,
+                order_id: None,
+                contributing_traders: None,
             };
             store.insert_trade(&record).expect("Failed to insert trade");
         }
@@ -804,6 +1383,8 @@ mod tests {
                 is_live: Some(true),
                 aggregation_count: None,
                 aggregation_window_ms: None,
+                order_id: None,
+                contributing_traders: None,
             };
             store.insert_trade(&record).expect("Failed to insert trade");
         }
@@ -818,6 +1399,68 @@ mod tests {
         assert_eq!(stats.total_trades_combined, 9);
         // Average: 9 / 3 = 3.0 trades per aggregation
         assert!((stats.avg_trades_per_aggregation - 3.0).abs() < 0.01);
+        // Average window: (750 + 500 + 800) / 3
+        assert!((stats.avg_window_ms - 683.33).abs() < 0.01);
+
+        cleanup_db(&db_path);
+    }
+
+    #[test]
+    fn test_get_aggregation_stats_since_excludes_older_rows() {
+        let db_path = temp_db_path();
+        cleanup_db(&db_path);
+
+        let store = TradeStore::new(&db_path).expect("Failed to create store");
+
+        // (timestamp, aggregation_count, window): two old rows, then two in the window
+        let trades = [
+            (1706000000000, Some(5), Some(900)),
+            (1706000001000, None, None),
+            (1706003600000, Some(2), Some(400)),
+            (1706003601000, None, None),
+        ];
+        for (i, (timestamp_ms, count, window)) in trades.iter().enumerate() {
+            let record = TradeRecord {
+                timestamp_ms: *timestamp_ms,
+                block_number: 12345678,
+                tx_hash: format!("0xwin{}", i),
+                trader_address: "0x1234567890abcdef1234567890abcdef12345678".to_string(),
+                token_id: "123456".to_string(),
+                side: "BUY".to_string(),
+                whale_shares: 500.0,
+                whale_price: 0.45,
+                whale_usd: 225.0,
+                our_shares: Some(500.0),
+                our_price: Some(0.46),
+                our_usd: Some(230.0),
+                fill_pct: Some(100.0),
+                status: "SUCCESS".to_string(),
+                latency_ms: Some(85),
+                is_live: Some(true),
+                aggregation_count: *count,
+                aggregation_window_ms: *window,
+                order_id: None,
+                contributing_traders: None,
+            };
+            store.insert_trade(&record).expect("Failed to insert trade");
+        }
+
+        let stats = store.get_aggregation_stats_since(1706003600000).expect("Failed to get stats");
+        assert_eq!(stats.total_orders, 2);
+        assert_eq!(stats.aggregated_orders, 1);
+        assert_eq!(stats.total_trades_combined, 2);
+        assert!((stats.avg_trades_per_aggregation - 2.0).abs() < 0.01);
+        assert!((stats.avg_window_ms - 400.0).abs() < 0.01);
+
+        // All-time stats still see every row
+        let all = store.get_aggregation_stats().expect("Failed to get stats");
+        assert_eq!(all.total_orders, 4);
+        assert_eq!(all.total_trades_combined, 7);
+
+        // A window after every row is empty
+        let none = store.get_aggregation_stats_since(1706003602000).expect("Failed to get stats");
+        assert_eq!(none.total_orders, 0);
+        assert_eq!(none.avg_window_ms, 0.0);
 
         cleanup_db(&db_path);
     }
@@ -834,6 +1477,7 @@ mod tests {
         assert_eq!(stats.aggregated_orders, 0);
         assert_eq!(stats.total_trades_combined, 0);
         assert_eq!(stats.avg_trades_per_aggregation, 0.0);
+        assert_eq!(stats.avg_window_ms, 0.0);
 
         cleanup_db(&db_path);
     }
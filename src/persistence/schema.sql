@@ -1,5 +1,5 @@
 -- SQLite schema for Polymarket copy trading bot persistence
--- Version: 1.0
+-- Version: 1.0 (migration 1; later changes are ALTER steps in migrations.rs)
 -- Optimized for: <100ms reads, WAL mode for concurrent access
 
 -- Main trades table: Stores all executed trade attempts
@@ -72,6 +72,9 @@ CREATE TABLE IF NOT EXISTS trader_stats (
     successful_trades INTEGER DEFAULT 0,
     failed_trades INTEGER DEFAULT 0,
     total_copied_usd REAL DEFAULT 0,
+    realized_pnl REAL DEFAULT 0,             -- FIFO realized P&L on copied trades
+    winning_trades INTEGER DEFAULT 0,        -- Closing sells with positive P&L
+    losing_trades INTEGER DEFAULT 0,         -- Closing sells with zero/negative P&L
     last_trade_ts INTEGER,                   -- Last trade timestamp
     daily_reset_ts INTEGER,                  -- Last daily reset timestamp
     created_at INTEGER NOT NULL,
@@ -3,13 +3,133 @@
 // Provides TradeStore for managing trade persistence with:
 // - WAL mode for concurrent reads
 // - NORMAL synchronous mode for performance
-// - Schema initialization and validation
+// - Schema initialization and versioned migrations (see migrations.rs)
 
 use anyhow::{Result, Context};
 use rusqlite::{Connection, params};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
 use std::path::Path;
 use std::sync::Mutex;
 
+use super::migrations;
+use crate::pnl::{Fill, RealizedPnl, open_shares_by_trader, realized_pnl_by_token, realized_pnl_by_trader, tokens_with_open_lots};
+
+/// Status of orders a shadow strategy would have placed (SHADOW_MODE)
+///
+/// Rows with this status go to the `shadow_trades` table, never to `trades`, so live
+/// positions, exposure and realized P&L can't see them.
+pub const SHADOW_STATUS: &str = "SHADOW";
+
+/// Insert one trade into `$table`, binding columns in `TRADE_COLUMNS` order
+macro_rules! insert_trade_sql {
+    ($table:literal) => {
+        concat!("INSERT INTO ", $table, " (
+        timestamp_ms, block_number, tx_hash, trader_address, token_id,
+        side, whale_shares, whale_price, whale_usd,
+        our_shares, our_price, our_usd, fill_pct,
+        status, latency_ms, is_live, aggregation_count, aggregation_window_ms, order_id,
+        contributing_traders
+    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)")
+    };
+}
+
+const INSERT_TRADE_SQL: &str = insert_trade_sql!("trades");
+const INSERT_SHADOW_SQL: &str = insert_trade_sql!("shadow_trades");
+
+/// Insert statement for a record: SHADOW rows go to `shadow_trades`
+fn insert_sql(record: &TradeRecord) -> &'static str {
+    if record.status == SHADOW_STATUS { INSERT_SHADOW_SQL } else { INSERT_TRADE_SQL }
+}
+
+/// Columns read by `trade_from_row`, in order
+const TRADE_COLUMNS: &str = "timestamp_ms, block_number, tx_hash, trader_address, token_id,
+    side, whale_shares, whale_price, whale_usd,
+    our_shares, our_price, our_usd, fill_pct,
+    status, latency_ms, is_live, aggregation_count, aggregation_window_ms, order_id,
+    contributing_traders";
+
+/// Map a row selected with `TRADE_COLUMNS` to a TradeRecord
+fn trade_from_row(row: &rusqlite::Row) -> rusqlite::Result<TradeRecord> {
+    Ok(TradeRecord {
+        timestamp_ms: row.get(0)?,
+        block_number: row.get::<_, i64>(1)? as u64,
+        tx_hash: row.get(2)?,
+        trader_address: row.get(3)?,
+        token_id: row.get(4)?,
+        side: row.get(5)?,
+        whale_shares: row.get(6)?,
+        whale_price: row.get(7)?,
+        whale_usd: row.get(8)?,
+        our_shares: row.get(9)?,
+        our_price: row.get(10)?,
+        our_usd: row.get(11)?,
+        fill_pct: row.get(12)?,
+        status: row.get(13)?,
+        latency_ms: row.get(14)?,
+        is_live: row.get(15)?,
+        aggregation_count: row.get::<_, Option<i64>>(16)?.map(|c| c as u32),
+        aggregation_window_ms: row.get::<_, Option<i64>>(17)?.map(|w| w as u64),
+        order_id: row.get(18)?,
+        // Stored as a JSON array; an unreadable value is treated as missing
+        contributing_traders: row.get::<_, Option<String>>(19)?
+            .and_then(|json| serde_json::from_str(&json).ok()),
+    })
+}
+
+/// Run a prepared `insert_sql` statement for one record
+fn execute_insert(stmt: &mut rusqlite::CachedStatement, record: &TradeRecord) -> Result<()> {
+    stmt.execute(params![
+        record.timestamp_ms,
+        record.block_number as i64,
+        &record.tx_hash,
+        &record.trader_address,
+        &record.token_id,
+        &record.side,
+        record.whale_shares,
+        record.whale_price,
+        record.whale_usd,
+        record.our_shares,
+        record.our_price,
+        record.our_usd,
+        record.fill_pct,
+        &record.status,
+        record.latency_ms,
+        record.is_live,
+        record.aggregation_count.map(|c| c as i64),
+        record.aggregation_window_ms.map(|w| w as i64),
+        &record.order_id,
+        record.contributing_traders.as_ref().map(|traders| serde_json::json!(traders).to_string()),
+    ]).context("Failed to insert trade record")?;
+    Ok(())
+}
+
+/// Per-token totals from the latest snapshot plus every filled trade after it
+const POSITION_TOTALS_SQL: &str = "
+    WITH combined AS (
+        SELECT token_id, net_shares, buy_shares, buy_usd, trade_count
+        FROM position_snapshots
+        UNION ALL
+        SELECT
+            token_id,
+            CASE WHEN side = 'BUY' THEN our_shares ELSE -our_shares END,
+            CASE WHEN side = 'BUY' THEN our_shares ELSE 0 END,
+            CASE WHEN side = 'BUY' THEN our_usd ELSE 0 END,
+            1
+        FROM trades
+        WHERE our_shares IS NOT NULL
+          AND id > (SELECT COALESCE(MAX(last_trade_id), 0) FROM position_snapshots)
+    )
+    SELECT
+        token_id,
+        SUM(net_shares) as net_shares,
+        SUM(buy_shares) as buy_shares,
+        SUM(buy_usd) as buy_usd,
+        SUM(trade_count) as trade_count
+    FROM combined
+    GROUP BY token_id";
+
 /// Aggregated position for a token
 #[derive(Debug, Clone)]
 pub struct Position {
@@ -30,6 +150,21 @@ pub struct AggregationStats {
     pub total_trades_combined: u32,
     /// Average number of trades per aggregated order
     pub avg_trades_per_aggregation: f64,
+    /// Average time aggregated orders spent in their window, in ms (0.0 if none recorded)
+    pub avg_window_ms: f64,
+}
+
+/// Filters for `TradeStore::get_trades_filtered`; None matches every trade
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TradeFilter {
+    /// Trader address, with or without 0x (case-insensitive)
+    pub trader: Option<String>,
+    /// Exact token ID
+    pub token: Option<String>,
+    /// Stored status such as SUCCESS or SKIPPED (case-insensitive)
+    pub status: Option<String>,
+    /// Only trades at or after this unix ms timestamp
+    pub since_ms: Option<i64>,
 }
 
 /// TradeRecord represents a single trade execution record
@@ -41,7 +176,7 @@ pub struct AggregationStats {
 /// - Aggregation analytics (if trade was aggregated)
 ///
 /// Fields with Option<T> are nullable in the database (e.g., our_* fields for failed trades)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TradeRecord {
     /// Unix timestamp in milliseconds
     pub timestamp_ms: i64,
@@ -79,6 +214,10 @@ pub struct TradeRecord {
     pub aggregation_count: Option<u32>,
     /// Duration of aggregation window in milliseconds (None = not aggregated)
     pub aggregation_window_ms: Option<u64>,
+    /// CLOB order ID of our order (None if nothing was submitted or the exchange returned none)
+    pub order_id: Option<String>,
+    /// Deduped addresses of every trader in an aggregated window (None = not aggregated)
+    pub contributing_traders: Option<Vec<String>>,
 }
 
 /// TradeStore manages SQLite database connection for trade persistence
@@ -126,10 +265,8 @@ impl TradeStore {
              PRAGMA synchronous=NORMAL;"
         ).context("Failed to configure database settings")?;
 
-        // Initialize schema
-        let schema_sql = include_str!("schema.sql");
-        conn.execute_batch(schema_sql)
-            .context("Failed to initialize schema")?;
+        // Create or upgrade the schema
+        migrations::run_migrations(&conn)?;
 
         Ok(TradeStore {
             conn,
@@ -138,6 +275,11 @@ impl TradeStore {
         })
     }
 
+    /// Get applied schema version (for testing)
+    pub fn get_schema_version(&self) -> Result<i64> {
+        migrations::schema_version(&self.conn)
+    }
+
     /// Get current journal mode (for testing)
     pub fn get_journal_mode(&self) -> Result<String> {
         let mode: String = self.conn
@@ -218,35 +360,37 @@ impl TradeStore {
     /// # Returns
     /// * `Result<()>` - Ok if inserted successfully, Err otherwise
     pub fn insert_trade(&self, record: &TradeRecord) -> Result<()> {
-        self.conn.execute(
-            "INSERT INTO trades (
-                timestamp_ms, block_number, tx_hash, trader_address, token_id,
-                side, whale_shares, whale_price, whale_usd,
-                our_shares, our_price, our_usd, fill_pct,
-                status, latency_ms, is_live, aggregation_count, aggregation_window_ms
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
-            params![
-                record.timestamp_ms,
-                record.block_number as i64,
-                &record.tx_hash,
-                &record.trader_address,
-                &record.token_id,
-                &record.side,
-                record.whale_shares,
-                record.whale_price,
-                record.whale_usd,
-                record.our_shares,
-                record.our_price,
-                record.our_usd,
-                record.fill_pct,
-                &record.status,
-                record.latency_ms,
-                record.is_live,
-                record.aggregation_count.map(|c| c as i64),
-                record.aggregation_window_ms.map(|w| w as i64),
-            ],
-        ).context("Failed to insert trade record")?;
-        Ok(())
+        let mut stmt = self.conn.prepare_cached(insert_sql(record))
+            .context("Failed to prepare trade insert")?;
+        execute_insert(&mut stmt, record)
+    }
+
+    /// Insert many trades in a single transaction (replay, backtest dumps, imports)
+    ///
+    /// Much faster than calling `insert_trade` per row: one prepared statement and one
+    /// commit for the whole batch. All-or-nothing: if any row fails, none are inserted.
+    /// The live hot path should keep using `record_trade`.
+    ///
+    /// # Arguments
+    /// * `records` - The trade records to insert
+    ///
+    /// # Returns
+    /// * `Result<usize>` - Number of trades inserted
+    pub fn insert_trades_batch(&self, records: &[TradeRecord]) -> Result<usize> {
+        if records.is_empty() {
+            return Ok(0);
+        }
+
+        let tx = self.conn.unchecked_transaction()
+            .context("Failed to begin batch insert transaction")?;
+        for record in records {
+            let mut stmt = tx.prepare_cached(insert_sql(record))
+                .context("Failed to prepare trade insert")?;
+            execute_insert(&mut stmt, record)?;
+        }
+        tx.commit().context("Failed to commit batch insert")?;
+
+        Ok(records.len())
     }
 
     /// Record a trade using buffered writes for sub-millisecond performance
@@ -257,21 +401,27 @@ impl TradeStore {
     ///
     /// # Arguments
     /// * `record` - The trade record to buffer
-    pub fn record_trade(&self, record: TradeRecord) {
+    ///
+    /// # Returns
+    /// * `Result<()>` - Error if the auto-flush failed (the trades stay buffered)
+    pub fn record_trade(&self, record: TradeRecord) -> Result<()> {
         let mut buffer = self.write_buffer.lock().unwrap();
         buffer.push(record);
 
         // Auto-flush when buffer reaches capacity
         if buffer.len() >= self.buffer_size {
             drop(buffer); // Release lock before flushing
-            let _ = self.flush(); // Ignore errors in auto-flush
+            self.flush()?;
         }
+        Ok(())
     }
 
     /// Flush all buffered trades to the database
     ///
     /// Returns the number of trades that were persisted.
     /// This is a synchronous operation that blocks until all trades are written.
+    /// The trades are written in one transaction; if it fails none are written and
+    /// all of them stay buffered for the next attempt.
     ///
     /// # Returns
     /// * `Result<usize>` - Number of trades flushed, or error
@@ -284,17 +434,118 @@ impl TradeStore {
 
         // Take ownership of buffered trades and clear the buffer
         let trades = buffer.drain(..).collect::<Vec<_>>();
-        let count = trades.len();
         drop(buffer); // Release lock during I/O
 
-        // Insert all trades
-        for trade in trades {
-            self.insert_trade(&trade)?;
+        match self.insert_trades_batch(&trades) {
+            Ok(count) => Ok(count),
+            Err(e) => {
+                // Ahead of anything buffered meanwhile, so order is kept
+                self.write_buffer.lock().unwrap().splice(0..0, trades);
+                Err(e)
+            }
         }
+    }
 
+    /// Number of trades waiting in the write buffer
+    pub fn buffered_count(&self) -> usize {
+        self.write_buffer.lock().unwrap().len()
+    }
+
+    /// Append buffered trades to a newline-delimited JSON file and clear the buffer
+    ///
+    /// Last resort when the database can't be written: the file keeps the trades until
+    /// they can be imported. The buffer is only cleared once the file write succeeded.
+    ///
+    /// # Returns
+    /// * `Result<usize>` - Number of trades spilled
+    pub fn spill_buffered(&self, path: &Path) -> Result<usize> {
+        let mut buffer = self.write_buffer.lock().unwrap();
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+
+        let mut lines = String::new();
+        for trade in buffer.iter() {
+            lines.push_str(&serde_json::to_string(trade)?);
+            lines.push('\n');
+        }
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut f| f.write_all(lines.as_bytes()))
+            .with_context(|| format!("Failed to spill trades to {}", path.display()))?;
+
+        let count = buffer.len();
+        buffer.clear();
         Ok(count)
     }
 
+    /// Delete trades older than a cutoff in tokens that were flat at the cutoff
+    ///
+    /// A token's old trades are kept while, as of the cutoff, a FIFO lot is still open in it
+    /// (account-wide or for one trader) or a followed trader still holds it, so FIFO matching
+    /// and `get_whale_net_shares` stay exact. Net positions come from `position_snapshots`
+    /// (call `snapshot_positions` first). Realized P&L of the deleted trades no longer counts.
+    ///
+    /// # Arguments
+    /// * `cutoff_ms` - Trades with `timestamp_ms` strictly below this are deleted
+    ///
+    /// # Returns
+    /// * `Result<usize>` - Number of trades deleted (shadow rows included)
+    pub fn prune_before(&self, cutoff_ms: i64) -> Result<usize> {
+        let mut trades_open = tokens_with_open_lots(&self.fills_before("trades", cutoff_ms)?);
+        trades_open.extend(self.tokens_with_whale_position_before(cutoff_ms)?);
+        let shadow_open = tokens_with_open_lots(&self.fills_before("shadow_trades", cutoff_ms)?);
+
+        let tx = self.conn.unchecked_transaction()
+            .context("Failed to begin prune transaction")?;
+        let mut deleted = 0;
+        for (table, open) in [("trades", &trades_open), ("shadow_trades", &shadow_open)] {
+            let tokens = {
+                let mut stmt = tx.prepare(&format!("SELECT DISTINCT token_id FROM {} WHERE timestamp_ms < ?1", table))
+                    .context("Failed to prepare prune candidates query")?;
+                stmt.query_map(params![cutoff_ms], |row| row.get::<_, String>(0))
+                    .context("Failed to query prune candidates")?
+                    .collect::<std::result::Result<Vec<_>, _>>()
+                    .context("Failed to collect prune candidates")?
+            };
+            let mut delete = tx.prepare(&format!("DELETE FROM {} WHERE timestamp_ms < ?1 AND token_id = ?2", table))
+                .context("Failed to prepare prune")?;
+            for token_id in tokens.iter().filter(|t| !open.contains(*t)) {
+                deleted += delete.execute(params![cutoff_ms, token_id])
+                    .with_context(|| format!("Failed to prune {}", table))?;
+            }
+        }
+        tx.commit().context("Failed to commit prune")?;
+        Ok(deleted)
+    }
+
+    /// Tokens where some trader's whale trades before `before_ms` don't net to zero
+    fn tokens_with_whale_position_before(&self, before_ms: i64) -> Result<HashSet<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT token_id FROM (
+                 SELECT token_id, SUM(CASE WHEN side = 'BUY' THEN whale_shares ELSE -whale_shares END) AS net
+                 FROM trades
+                 WHERE timestamp_ms < ?1
+                 GROUP BY trader_address, token_id
+             )
+             WHERE ABS(net) > 1e-9"
+        ).context("Failed to prepare whale position query")?;
+
+        let tokens = stmt.query_map(params![before_ms], |row| row.get(0))
+            .context("Failed to execute whale position query")?
+            .collect::<std::result::Result<HashSet<String>, _>>()
+            .context("Failed to collect tokens")?;
+
+        Ok(tokens)
+    }
+
+    /// Rebuild the database file to reclaim space freed by deletes
+    pub fn vacuum(&self) -> Result<()> {
+        self.conn.execute_batch("VACUUM").context("Failed to vacuum database")
+    }
+
     /// Get recent trades ordered by timestamp descending
     ///
     /// # Arguments
@@ -303,63 +554,130 @@ impl TradeStore {
     /// # Returns
     /// * `Result<Vec<TradeRecord>>` - Recent trades, most recent first
     pub fn get_recent_trades(&self, limit: usize) -> Result<Vec<TradeRecord>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT timestamp_ms, block_number, tx_hash, trader_address, token_id,
-                    side, whale_shares, whale_price, whale_usd,
-                    our_shares, our_price, our_usd, fill_pct,
-                    status, latency_ms, is_live, aggregation_count, aggregation_window_ms
-             FROM trades
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM trades
              ORDER BY timestamp_ms DESC
-             LIMIT ?1"
-        ).context("Failed to prepare get_recent_trades query")?;
-
-        let trades = stmt.query_map(params![limit as i64], |row| {
-            Ok(TradeRecord {
-                timestamp_ms: row.get(0)?,
-                block_number: row.get::<_, i64>(1)? as u64,
-                tx_hash: row.get(2)?,
-                trader_address: row.get(3)?,
-                token_id: row.get(4)?,
-                side: row.get(5)?,
-                whale_shares: row.get(6)?,
-                whale_price: row.get(7)?,
-                whale_usd: row.get(8)?,
-                our_shares: row.get(9)?,
-                our_price: row.get(10)?,
-                our_usd: row.get(11)?,
-                fill_pct: row.get(12)?,
-                status: row.get(13)?,
-                latency_ms: row.get(14)?,
-                is_live: row.get(15)?,
-                aggregation_count: row.get::<_, Option<i64>>(16)?.map(|c| c as u32),
-                aggregation_window_ms: row.get::<_, Option<i64>>(17)?.map(|w| w as u64),
-            })
-        })
-        .context("Failed to execute get_recent_trades query")?
-        .collect::<std::result::Result<Vec<_>, _>>()
-        .context("Failed to collect trade records")?;
+             LIMIT ?1",
+            TRADE_COLUMNS
+        )).context("Failed to prepare get_recent_trades query")?;
+
+        let trades = stmt.query_map(params![limit as i64], trade_from_row)
+            .context("Failed to execute get_recent_trades query")?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to collect trade records")?;
+
+        Ok(trades)
+    }
+
+    /// Get trades in a time range, oldest first
+    ///
+    /// # Arguments
+    /// * `start_ms` - Inclusive lower bound on `timestamp_ms`
+    /// * `end_ms` - Exclusive upper bound on `timestamp_ms`
+    pub fn get_trades_between(&self, start_ms: i64, end_ms: i64) -> Result<Vec<TradeRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM trades
+             WHERE timestamp_ms >= ?1 AND timestamp_ms < ?2
+             ORDER BY timestamp_ms ASC, id ASC",
+            TRADE_COLUMNS
+        )).context("Failed to prepare get_trades_between query")?;
+
+        let trades = stmt.query_map(params![start_ms, end_ms], trade_from_row)
+            .context("Failed to execute get_trades_between query")?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to collect trade records")?;
+
+        Ok(trades)
+    }
+
+    /// Get most recent trades for one trader (newest first)
+    /// Uses the (trader_address, timestamp_ms) index rather than scanning all trades
+    ///
+    /// # Arguments
+    /// * `trader` - Full trader address (stored lowercase)
+    /// * `limit` - Maximum number of trades to return
+    pub fn get_trades_by_trader(&self, trader: &str, limit: usize) -> Result<Vec<TradeRecord>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM trades
+             WHERE trader_address = ?1
+             ORDER BY timestamp_ms DESC
+             LIMIT ?2",
+            TRADE_COLUMNS
+        )).context("Failed to prepare get_trades_by_trader query")?;
+
+        let trades = stmt.query_map(params![trader, limit as i64], trade_from_row)
+            .context("Failed to execute get_trades_by_trader query")?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to collect trade records")?;
+
+        Ok(trades)
+    }
+
+    /// Get most recent trades matching `filter` (newest first)
+    /// Each filter is pushed into the WHERE clause, so trader and token queries use their indexes
+    ///
+    /// # Arguments
+    /// * `filter` - Unset fields match every trade
+    /// * `limit` - Maximum number of trades to return
+    pub fn get_trades_filtered(&self, filter: &TradeFilter, limit: usize) -> Result<Vec<TradeRecord>> {
+        let mut clauses = Vec::new();
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+        if let Some(trader) = &filter.trader {
+            // Addresses are stored with or without the 0x prefix depending on the writer
+            let hex = trader.strip_prefix("0x").unwrap_or(trader).to_lowercase();
+            clauses.push("trader_address IN (?, ?)");
+            values.push(format!("0x{}", hex).into());
+            values.push(hex.into());
+        }
+        if let Some(token) = &filter.token {
+            clauses.push("token_id = ?");
+            values.push(token.clone().into());
+        }
+        if let Some(status) = &filter.status {
+            clauses.push("status = ?");
+            values.push(status.to_uppercase().into());
+        }
+        if let Some(since_ms) = filter.since_ms {
+            clauses.push("timestamp_ms >= ?");
+            values.push(since_ms.into());
+        }
+        let where_clause = if clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", clauses.join(" AND "))
+        };
+        values.push((limit as i64).into());
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM trades
+             {}
+             ORDER BY timestamp_ms DESC
+             LIMIT ?",
+            TRADE_COLUMNS, where_clause
+        )).context("Failed to prepare get_trades_filtered query")?;
+
+        let trades = stmt.query_map(rusqlite::params_from_iter(values), trade_from_row)
+            .context("Failed to execute get_trades_filtered query")?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context("Failed to collect trade records")?;
 
         Ok(trades)
     }
 
     /// Get current positions aggregated from trades
+    /// Starts from the latest position snapshot (if any) and applies only newer trades,
+    /// so balances survive pruning of snapshotted trades.
     /// Only returns positions with non-zero net shares
     ///
     /// # Returns
     /// * `Result<Vec<Position>>` - Current positions with non-zero holdings
     pub fn get_positions(&self) -> Result<Vec<Position>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT
-                token_id,
-                SUM(CASE WHEN side = 'BUY' THEN our_shares ELSE -our_shares END) as net_shares,
-                SUM(CASE WHEN side = 'BUY' THEN our_usd ELSE 0 END) /
-                    NULLIF(SUM(CASE WHEN side = 'BUY' THEN our_shares ELSE 0 END), 0) as avg_entry_price,
-                COUNT(*) as trade_count
-             FROM trades
-             WHERE our_shares IS NOT NULL
-             GROUP BY token_id
-             HAVING ABS(net_shares) > 0.0001"
-        ).context("Failed to prepare get_positions query")?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT token_id, net_shares, buy_usd / NULLIF(buy_shares, 0) as avg_entry_price, trade_count
+             FROM ({})
+             WHERE ABS(net_shares) > 0.0001",
+            POSITION_TOTALS_SQL
+        )).context("Failed to prepare get_positions query")?;
 
         let positions = stmt.query_map([], |row| {
             Ok(Position {
@@ -376,6 +694,82 @@ impl TradeStore {
         Ok(positions)
     }
 
+    /// Snapshot per-token position totals so older trades can be pruned safely
+    ///
+    /// Replaces the previous snapshot with totals covering every trade up to the
+    /// current highest trade id. No-op if there are no trades since the last snapshot.
+    pub fn snapshot_positions(&self) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()
+            .context("Failed to begin snapshot transaction")?;
+
+        let last_trade_id: i64 = tx.query_row(
+            "SELECT COALESCE(MAX(id), 0) FROM trades", [], |row| row.get(0),
+        ).context("Failed to read last trade id")?;
+        let snapshot_id: i64 = tx.query_row(
+            "SELECT COALESCE(MAX(last_trade_id), 0) FROM position_snapshots", [], |row| row.get(0),
+        ).context("Failed to read snapshot watermark")?;
+        if last_trade_id <= snapshot_id {
+            return Ok(());
+        }
+
+        // Build the new snapshot in a temp table: the totals read position_snapshots
+        tx.execute_batch(&format!(
+            "CREATE TEMP TABLE new_snapshot AS {};
+             DELETE FROM position_snapshots;",
+            POSITION_TOTALS_SQL
+        )).context("Failed to compute position snapshot")?;
+        tx.execute(
+            "INSERT INTO position_snapshots
+                (token_id, snapshot_ms, last_trade_id, net_shares, buy_shares, buy_usd, trade_count)
+             SELECT token_id, ?1, ?2, net_shares, buy_shares, buy_usd, trade_count FROM new_snapshot",
+            params![chrono::Utc::now().timestamp_millis(), last_trade_id],
+        ).context("Failed to write position snapshot")?;
+        tx.execute_batch("DROP TABLE new_snapshot")
+            .context("Failed to drop temp snapshot")?;
+
+        tx.commit().context("Failed to commit position snapshot")
+    }
+
+    /// Get a trader's net position in a token from the whale trades we've observed
+    ///
+    /// Counts every recorded whale trade (copied or not): BUYs add, SELLs subtract.
+    ///
+    /// # Returns
+    /// * `Result<Option<f64>>` - Net whale shares, or None if we've never seen this trader trade the token
+    pub fn get_whale_net_shares(&self, trader_address: &str, token_id: &str) -> Result<Option<f64>> {
+        let net: Option<f64> = self.conn.query_row(
+            "SELECT SUM(CASE WHEN side = 'BUY' THEN whale_shares ELSE -whale_shares END)
+             FROM trades
+             WHERE trader_address = ?1 AND token_id = ?2",
+            params![trader_address, token_id],
+            |row| row.get(0),
+        ).context("Failed to query whale net shares")?;
+
+        Ok(net)
+    }
+
+    /// Tokens with a trade of `status` recorded since our latest filled BUY on them
+    ///
+    /// Lets a one-off exit (e.g. a partial take-profit) fire once per position:
+    /// buying more re-arms it.
+    pub fn tokens_with_status_since_last_buy(&self, status: &str) -> Result<HashSet<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT t.token_id FROM trades t
+             WHERE t.status = ?1
+               AND t.timestamp_ms >= COALESCE((
+                   SELECT MAX(b.timestamp_ms) FROM trades b
+                   WHERE b.token_id = t.token_id AND b.side = 'BUY' AND b.our_shares > 0
+               ), 0)"
+        ).context("Failed to prepare status-since-buy query")?;
+
+        let tokens = stmt.query_map(params![status], |row| row.get(0))
+            .context("Failed to execute status-since-buy query")?
+            .collect::<std::result::Result<HashSet<String>, _>>()
+            .context("Failed to collect tokens")?;
+
+        Ok(tokens)
+    }
+
     /// Update or insert trader statistics
     ///
     /// # Arguments
@@ -434,6 +828,119 @@ impl TradeStore {
         Ok(())
     }
 
+    /// Update realized P&L columns for a trader
+    ///
+    /// Only updates an existing trader_stats row (call `upsert_trader_stats` first)
+    ///
+    /// # Arguments
+    /// * `address` - Trader address
+    /// * `realized_pnl` - FIFO realized P&L in USD
+    /// * `winning_trades` - Closing sells with positive P&L
+    /// * `losing_trades` - Closing sells with zero/negative P&L
+    pub fn update_trader_pnl(
+        &self,
+        address: &str,
+        realized_pnl: f64,
+        winning_trades: u32,
+        losing_trades: u32,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE trader_stats
+             SET realized_pnl = ?2, winning_trades = ?3, losing_trades = ?4
+             WHERE trader_address = ?1",
+            params![address, realized_pnl, winning_trades, losing_trades],
+        ).context("Failed to update trader P&L")?;
+
+        Ok(())
+    }
+
+    /// Get all filled trades in chronological order (for P&L matching)
+    ///
+    /// # Returns
+    /// * `Result<Vec<Fill>>` - Trades with our execution details, oldest first
+    pub fn get_fills(&self) -> Result<Vec<Fill>> {
+        self.get_fills_before(i64::MAX)
+    }
+
+    /// Get filled trades with `timestamp_ms` strictly below `before_ms`, oldest first
+    /// (realized P&L up to a point in time)
+    pub fn get_fills_before(&self, before_ms: i64) -> Result<Vec<Fill>> {
+        self.fills_before("trades", before_ms)
+    }
+
+    /// Shadow strategy fills (SHADOW_MODE) below `before_ms`, oldest first, treating every
+    /// shadow order as fully filled at its limit price
+    pub fn get_shadow_fills_before(&self, before_ms: i64) -> Result<Vec<Fill>> {
+        self.fills_before("shadow_trades", before_ms)
+    }
+
+    fn fills_before(&self, table: &str, before_ms: i64) -> Result<Vec<Fill>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT trader_address, token_id, side, our_shares, our_price
+             FROM {}
+             WHERE our_shares IS NOT NULL AND our_price IS NOT NULL AND our_shares > 0
+               AND timestamp_ms < ?1
+             ORDER BY timestamp_ms ASC, id ASC",
+            table
+        )).context("Failed to prepare get_fills query")?;
+
+        let fills = stmt.query_map(params![before_ms], |row| {
+            Ok(Fill {
+                trader_address: row.get(0)?,
+                token_id: row.get(1)?,
+                side: row.get(2)?,
+                shares: row.get(3)?,
+                price: row.get(4)?,
+            })
+        })
+        .context("Failed to execute get_fills query")?
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("Failed to collect fills")?;
+
+        Ok(fills)
+    }
+
+    /// Net shares the shadow strategy holds in a token (0 if it never traded it)
+    pub fn get_shadow_net_shares(&self, token_id: &str) -> Result<f64> {
+        self.conn.query_row(
+            "SELECT COALESCE(SUM(CASE WHEN side = 'BUY' THEN our_shares ELSE -our_shares END), 0)
+             FROM shadow_trades
+             WHERE token_id = ?1 AND our_shares IS NOT NULL",
+            params![token_id],
+            |row| row.get(0),
+        ).context("Failed to query shadow net shares")
+    }
+
+    /// Get FIFO realized P&L per originating trader
+    ///
+    /// # Arguments
+    /// * `fee_per_trade` - Estimated USD fee charged per fill (FEE_PER_TRADE_USD)
+    ///
+    /// # Returns
+    /// * `Result<HashMap<trader_address, RealizedPnl>>`
+    pub fn get_realized_pnl_by_trader(&self, fee_per_trade: f64) -> Result<HashMap<String, RealizedPnl>> {
+        Ok(realized_pnl_by_trader(&self.get_fills()?, fee_per_trade))
+    }
+
+    /// Get FIFO realized P&L per token (account-wide, all traders)
+    ///
+    /// # Arguments
+    /// * `fee_per_trade` - Estimated USD fee charged per fill (FEE_PER_TRADE_USD)
+    ///
+    /// # Returns
+    /// * `Result<HashMap<token_id, RealizedPnl>>`
+    pub fn get_realized_pnl_by_token(&self, fee_per_trade: f64) -> Result<HashMap<String, RealizedPnl>> {
+        Ok(realized_pnl_by_token(&self.get_fills()?, fee_per_trade))
+    }
+
+    /// Shares each copied trader still has open per token (FIFO, as in `get_realized_pnl_by_trader`)
+    ///
+    /// # Returns
+    /// * `Result<HashMap<token_id, Vec<(trader_address, shares)>>>` - Open tokens only, ordered by trader
+    pub fn get_open_shares_by_trader(&self) -> Result<HashMap<String, Vec<(String, f64)>>> {
+        Ok(open_shares_by_trader(&self.get_fills()?))
+    }
+
     /// Get all trader statistics
     ///
     /// # Returns
@@ -529,28 +1036,40 @@ impl TradeStore {
     /// - Number of orders that were aggregated
     /// - Total trades combined through aggregation
     /// - Average trades per aggregated order
+    /// - Average aggregation window duration
     ///
     /// # Returns
     /// * `Result<AggregationStats>` - Aggregation efficiency statistics
     pub fn get_aggregation_stats(&self) -> Result<AggregationStats> {
+        self.get_aggregation_stats_since(i64::MIN)
+    }
+
+    /// Get aggregation efficiency statistics over trades at or after a timestamp
+    ///
+    /// # Arguments
+    /// * `since_ms` - Unix timestamp in milliseconds
+    ///
+    /// # Returns
+    /// * `Result<AggregationStats>` - Aggregation efficiency statistics for the window
+    pub fn get_aggregation_stats_since(&self, since_ms: i64) -> Result<AggregationStats> {
         // Get total order count
         let total_orders: i64 = self.conn.query_row(
-            "SELECT COUNT(*) FROM trades",
-            [],
+            "SELECT COUNT(*) FROM trades WHERE timestamp_ms >= ?1",
+            params![since_ms],
             |row| row.get(0),
         ).context("Failed to count total orders")?;
 
         // Get count of aggregated orders (where aggregation_count > 1)
         let aggregated_orders: i64 = self.conn.query_row(
-            "SELECT COUNT(*) FROM trades WHERE aggregation_count IS NOT NULL AND aggregation_count > 1",
-            [],
+            "SELECT COUNT(*) FROM trades WHERE timestamp_ms >= ?1 AND aggregation_count IS NOT NULL AND aggregation_count > 1",
+            params![since_ms],
             |row| row.get(0),
         ).context("Failed to count aggregated orders")?;
 
         // Get sum of all aggregation_count values (total trades combined)
         let total_trades_combined: Option<i64> = self.conn.query_row(
-            "SELECT SUM(aggregation_count) FROM trades WHERE aggregation_count IS NOT NULL AND aggregation_count > 1",
-            [],
+            "SELECT SUM(aggregation_count) FROM trades WHERE timestamp_ms >= ?1 AND aggregation_count IS NOT NULL AND aggregation_count > 1",
+            params![since_ms],
             |row| row.get(0),
         ).context("Failed to sum aggregation counts")?;
 
@@ -561,11 +1080,19 @@ impl TradeStore {
             0.0
         };
 
+        // Average window duration over aggregated orders that recorded one
+        let avg_window_ms: Option<f64> = self.conn.query_row(
+            "SELECT AVG(aggregation_window_ms) FROM trades WHERE timestamp_ms >= ?1 AND aggregation_count > 1 AND aggregation_window_ms IS NOT NULL",
+            params![since_ms],
+            |row| row.get(0),
+        ).context("Failed to average aggregation windows")?;
+
         Ok(AggregationStats {
             total_orders: total_orders as u32,
             aggregated_orders: aggregated_orders as u32,
             total_trades_combined: total_trades_combined.unwrap_or(0) as u32,
             avg_trades_per_aggregation,
+            avg_window_ms: avg_window_ms.unwrap_or(0.0),
         })
     }
 
@@ -0,0 +1,443 @@
+//! FIFO realized P&L matching and position valuation
+//! Matches SELL fills against earlier BUY lots (first in, first out) to compute realized P&L
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+/// An open BUY lot waiting to be matched by a SELL
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lot {
+    pub shares: f64,
+    pub price: f64,
+}
+
+/// Result of matching a SELL fill against open lots
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SellMatch {
+    /// Shares that found a matching BUY lot
+    pub matched_shares: f64,
+    /// Realized P&L on the matched shares
+    pub realized_pnl: f64,
+}
+
+/// Realized P&L totals for one key (trader or token)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RealizedPnl {
+    /// Total realized P&L in USD
+    pub realized_pnl: f64,
+    /// SELL fills that closed lots at a profit
+    pub winning_closes: u32,
+    /// SELL fills that closed lots at a loss (or break-even)
+    pub losing_closes: u32,
+}
+
+impl RealizedPnl {
+    /// Fraction of closing SELLs that were profitable (None if nothing closed yet)
+    pub fn win_rate(&self) -> Option<f64> {
+        let closes = self.winning_closes + self.losing_closes;
+        if closes == 0 {
+            None
+        } else {
+            Some(self.winning_closes as f64 / closes as f64)
+        }
+    }
+
+    fn record(&mut self, m: SellMatch) {
+        if m.matched_shares <= 0.0 {
+            return;
+        }
+        self.realized_pnl += m.realized_pnl;
+        if m.realized_pnl > 0.0 {
+            self.winning_closes += 1;
+        } else {
+            self.losing_closes += 1;
+        }
+    }
+}
+
+/// FIFO lot matcher keyed by an arbitrary lot key (e.g. token, or trader+token)
+#[derive(Debug, Default)]
+pub struct FifoMatcher {
+    lots: HashMap<String, VecDeque<Lot>>,
+}
+
+impl FifoMatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a BUY lot
+    pub fn buy(&mut self, key: &str, shares: f64, price: f64) {
+        if shares <= 0.0 {
+            return;
+        }
+        self.lots.entry(key.to_string()).or_default().push_back(Lot { shares, price });
+    }
+
+    /// Match a SELL against the oldest open lots for `key`
+    /// Shares without a matching lot are ignored (no known cost basis)
+    pub fn sell(&mut self, key: &str, shares: f64, price: f64) -> SellMatch {
+        let mut result = SellMatch::default();
+        let Some(queue) = self.lots.get_mut(key) else { return result };
+
+        let mut remaining = shares;
+        while remaining > 0.0 {
+            let Some(lot) = queue.front_mut() else { break };
+            let take = remaining.min(lot.shares);
+            result.matched_shares += take;
+            result.realized_pnl += (price - lot.price) * take;
+            lot.shares -= take;
+            remaining -= take;
+            if lot.shares <= 1e-9 {
+                queue.pop_front();
+            }
+        }
+        result
+    }
+
+    /// Open lots for a key, oldest first
+    pub fn open_lots(&self, key: &str) -> impl Iterator<Item = &Lot> {
+        self.lots.get(key).into_iter().flatten()
+    }
+}
+
+/// A filled trade as needed for P&L matching
+#[derive(Debug, Clone)]
+pub struct Fill {
+    pub trader_address: String,
+    pub token_id: String,
+    pub side: String,
+    pub shares: f64,
+    pub price: f64,
+}
+
+/// Apply one fill to the matcher, charging `fee_per_trade` USD per fill
+///
+/// A BUY's fee is spread over its shares as extra cost basis; a SELL's fee comes off
+/// its proceeds. Returns the SELL match (None for BUYs).
+fn apply_fill(matcher: &mut FifoMatcher, key: &str, fill: &Fill, fee_per_trade: f64) -> Option<SellMatch> {
+    if fill.side == "BUY" {
+        if fill.shares > 0.0 {
+            matcher.buy(key, fill.shares, fill.price + fee_per_trade / fill.shares);
+        }
+        return None;
+    }
+
+    let mut m = matcher.sell(key, fill.shares, fill.price);
+    if m.matched_shares > 0.0 {
+        m.realized_pnl -= fee_per_trade;
+    }
+    Some(m)
+}
+
+/// Realized P&L per originating trader, net of `fee_per_trade` USD per fill
+///
+/// Lots are kept per (trader, token), so a SELL copied from a trader only closes
+/// BUYs that were copied from that same trader. `fills` must be in chronological order.
+pub fn realized_pnl_by_trader(fills: &[Fill], fee_per_trade: f64) -> HashMap<String, RealizedPnl> {
+    let mut matcher = FifoMatcher::new();
+    let mut totals: HashMap<String, RealizedPnl> = HashMap::new();
+
+    for fill in fills {
+        let key = format!("{}:{}", fill.trader_address, fill.token_id);
+        if let Some(m) = apply_fill(&mut matcher, &key, fill, fee_per_trade) {
+            totals.entry(fill.trader_address.clone()).or_default().record(m);
+        }
+    }
+
+    totals
+}
+
+/// Realized P&L per token for the whole account, net of `fee_per_trade` USD per fill
+///
+/// Lots are kept per token regardless of which trader the fill was copied from.
+/// `fills` must be in chronological order.
+pub fn realized_pnl_by_token(fills: &[Fill], fee_per_trade: f64) -> HashMap<String, RealizedPnl> {
+    let mut matcher = FifoMatcher::new();
+    let mut totals: HashMap<String, RealizedPnl> = HashMap::new();
+
+    for fill in fills {
+        if let Some(m) = apply_fill(&mut matcher, &fill.token_id, fill, fee_per_trade) {
+            totals.entry(fill.token_id.clone()).or_default().record(m);
+        }
+    }
+
+    totals
+}
+
+/// BUY lots still open per token after matching `fills` like [`realized_pnl_by_token`]
+///
+/// Lot prices include the BUY fee share. Tokens with nothing left open are left out.
+pub fn open_lots_by_token(fills: &[Fill], fee_per_trade: f64) -> HashMap<String, Vec<Lot>> {
+    let mut matcher = FifoMatcher::new();
+    for fill in fills {
+        apply_fill(&mut matcher, &fill.token_id, fill, fee_per_trade);
+    }
+
+    matcher.lots
+        .into_iter()
+        .filter(|(_, lots)| !lots.is_empty())
+        .map(|(token_id, lots)| (token_id, lots.into()))
+        .collect()
+}
+
+/// Shares each trader still has open per token after matching `fills` like [`realized_pnl_by_trader`]
+///
+/// token_id -> (trader, open shares), ordered by trader. Traders with nothing left open are left out.
+pub fn open_shares_by_trader(fills: &[Fill]) -> HashMap<String, Vec<(String, f64)>> {
+    let mut matcher = FifoMatcher::new();
+    let mut pairs: BTreeSet<(&str, &str)> = BTreeSet::new();
+    for fill in fills {
+        apply_fill(&mut matcher, &format!("{}:{}", fill.trader_address, fill.token_id), fill, 0.0);
+        pairs.insert((&fill.trader_address, &fill.token_id));
+    }
+
+    let mut open: HashMap<String, Vec<(String, f64)>> = HashMap::new();
+    for (trader, token_id) in pairs {
+        let shares: f64 = matcher.open_lots(&format!("{}:{}", trader, token_id)).map(|lot| lot.shares).sum();
+        if shares > 0.0 {
+            open.entry(token_id.to_string()).or_default().push((trader.to_string(), shares));
+        }
+    }
+    open
+}
+
+/// Tokens with a BUY lot still open after matching `fills`, either account-wide
+/// (as in [`realized_pnl_by_token`]) or for one trader (as in [`realized_pnl_by_trader`])
+pub fn tokens_with_open_lots(fills: &[Fill]) -> HashSet<String> {
+    let mut by_token = FifoMatcher::new();
+    let mut by_trader = FifoMatcher::new();
+    let mut trader_keys: HashMap<String, &str> = HashMap::new();
+    for fill in fills {
+        let key = format!("{}:{}", fill.trader_address, fill.token_id);
+        apply_fill(&mut by_token, &fill.token_id, fill, 0.0);
+        apply_fill(&mut by_trader, &key, fill, 0.0);
+        trader_keys.insert(key, &fill.token_id);
+    }
+
+    let mut open: HashSet<String> = by_token.lots.iter()
+        .filter(|(_, lots)| !lots.is_empty())
+        .map(|(token_id, _)| token_id.clone())
+        .collect();
+    open.extend(trader_keys.into_iter()
+        .filter(|(key, _)| by_trader.open_lots(key).next().is_some())
+        .map(|(_, token_id)| token_id.to_string()));
+    open
+}
+
+/// Calculate the market value of a position
+///
+/// # Arguments
+/// * `net_shares` - Net shares (positive for long, negative for short)
+/// * `bid_price` - Current bid price (what you'd sell at)
+/// * `ask_price` - Current ask price (what you'd buy at)
+///
+/// # Returns
+/// * `f64` - Position value in USD
+pub fn calculate_position_value(net_shares: f64, bid_price: f64, ask_price: f64) -> f64 {
+    if net_shares > 0.0 {
+        // LONG position: value is what we'd get selling at bid
+        net_shares * bid_price
+    } else if net_shares < 0.0 {
+        // SHORT position: value is the obligation (cost to close at ask)
+        net_shares.abs() * ask_price
+    } else {
+        // Zero shares
+        0.0
+    }
+}
+
+/// Calculate the cost basis of a position
+///
+/// # Arguments
+/// * `net_shares` - Net shares (positive for long, negative for short)
+/// * `avg_entry_price` - Average entry price
+///
+/// # Returns
+/// * `Option<f64>` - Cost basis in USD, or None if avg_entry_price is None
+pub fn calculate_cost_basis(net_shares: f64, avg_entry_price: Option<f64>) -> Option<f64> {
+    avg_entry_price.map(|entry| net_shares.abs() * entry)
+}
+
+/// Calculate unrealized P&L for a position
+///
+/// # Arguments
+/// * `net_shares` - Net shares (positive for long, negative for short)
+/// * `avg_entry` - Average entry price
+/// * `bid_price` - Current bid price (what you'd sell at)
+/// * `ask_price` - Current ask price (what you'd buy at)
+///
+/// # Returns
+/// * `Option<f64>` - Unrealized P&L in USD, or None if avg_entry is None
+pub fn calculate_unrealized_pnl(
+    net_shares: f64,
+    avg_entry: Option<f64>,
+    bid_price: f64,
+    ask_price: f64,
+) -> Option<f64> {
+    avg_entry.map(|entry| {
+        if net_shares > 0.0 {
+            // LONG position: use bid price (what you'd sell at)
+            (bid_price - entry) * net_shares
+        } else {
+            // SHORT position: use ask price (what you'd buy at to close)
+            (entry - ask_price) * net_shares.abs()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(trader: &str, token: &str, side: &str, shares: f64, price: f64) -> Fill {
+        Fill {
+            trader_address: trader.to_string(),
+            token_id: token.to_string(),
+            side: side.to_string(),
+            shares,
+            price,
+        }
+    }
+
+    #[test]
+    fn test_fifo_matches_oldest_lot_first() {
+        let mut m = FifoMatcher::new();
+        m.buy("t", 10.0, 0.40);
+        m.buy("t", 10.0, 0.60);
+
+        // Sells 15: 10 @ 0.40 and 5 @ 0.60
+        let r = m.sell("t", 15.0, 0.50);
+        assert!((r.matched_shares - 15.0).abs() < 1e-9);
+        assert!((r.realized_pnl - (10.0 * 0.10 - 5.0 * 0.10)).abs() < 1e-9);
+
+        let left: Vec<_> = m.open_lots("t").copied().collect();
+        assert_eq!(left.len(), 1);
+        assert!((left[0].shares - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sell_without_lots_is_unmatched() {
+        let mut m = FifoMatcher::new();
+        let r = m.sell("t", 10.0, 0.50);
+        assert_eq!(r.matched_shares, 0.0);
+        assert_eq!(r.realized_pnl, 0.0);
+    }
+
+    #[test]
+    fn test_realized_pnl_by_trader() {
+        let fills = vec![
+            fill("a", "tok", "BUY", 10.0, 0.40),
+            fill("b", "tok", "BUY", 10.0, 0.70),
+            fill("a", "tok", "SELL", 10.0, 0.60), // a: +2.00 win
+            fill("b", "tok", "SELL", 5.0, 0.50),  // b: -1.00 loss
+            fill("b", "tok", "SELL", 5.0, 0.80),  // b: +0.50 win
+            fill("c", "tok", "SELL", 5.0, 0.90),  // c: no lots, ignored
+        ];
+        let pnl = realized_pnl_by_trader(&fills, 0.0);
+
+        let a = pnl["a"];
+        assert!((a.realized_pnl - 2.0).abs() < 1e-9);
+        assert_eq!(a.win_rate(), Some(1.0));
+
+        let b = pnl["b"];
+        assert!((b.realized_pnl - (-0.5)).abs() < 1e-9);
+        assert_eq!(b.winning_closes, 1);
+        assert_eq!(b.losing_closes, 1);
+        assert_eq!(b.win_rate(), Some(0.5));
+
+        assert_eq!(pnl["c"].win_rate(), None);
+    }
+
+    #[test]
+    fn test_realized_pnl_by_token_ignores_trader() {
+        let fills = vec![
+            fill("a", "tok1", "BUY", 10.0, 0.40),
+            fill("b", "tok1", "SELL", 10.0, 0.60), // closes a's lot: +2.00
+            fill("a", "tok2", "BUY", 10.0, 0.50),
+            fill("a", "tok2", "SELL", 4.0, 0.25),  // -1.00
+        ];
+        let pnl = realized_pnl_by_token(&fills, 0.0);
+
+        assert!((pnl["tok1"].realized_pnl - 2.0).abs() < 1e-9);
+        assert!((pnl["tok2"].realized_pnl - (-1.0)).abs() < 1e-9);
+        assert_eq!(pnl["tok2"].losing_closes, 1);
+    }
+
+    #[test]
+    fn test_realized_pnl_zero_fee_unchanged() {
+        let fills = vec![
+            fill("a", "tok", "BUY", 10.0, 0.40),
+            fill("a", "tok", "SELL", 10.0, 0.60),
+        ];
+        assert_eq!(realized_pnl_by_token(&fills, 0.0)["tok"].realized_pnl, (0.60 - 0.40) * 10.0);
+        assert_eq!(realized_pnl_by_trader(&fills, 0.0)["a"].realized_pnl, (0.60 - 0.40) * 10.0);
+    }
+
+    #[test]
+    fn test_realized_pnl_net_of_fees() {
+        let fills = vec![
+            fill("a", "tok", "BUY", 10.0, 0.40),
+            fill("a", "tok", "BUY", 10.0, 0.40),
+            fill("a", "tok", "SELL", 10.0, 0.60), // +2.00 - buy fee 0.25 - sell fee 0.25
+            fill("a", "tok", "SELL", 10.0, 0.43), // +0.30 - 0.25 - 0.25 = -0.20: a loss net of fees
+            fill("a", "tok", "SELL", 5.0, 0.90),  // no lots left: no P&L, no fee
+        ];
+        let by_token = realized_pnl_by_token(&fills, 0.25);
+        assert!((by_token["tok"].realized_pnl - 1.3).abs() < 1e-9);
+        assert_eq!(by_token["tok"].winning_closes, 1);
+        assert_eq!(by_token["tok"].losing_closes, 1);
+
+        let by_trader = realized_pnl_by_trader(&fills, 0.25);
+        assert!((by_trader["a"].realized_pnl - 1.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_open_lots_by_token() {
+        let fills = vec![
+            fill("a", "tok1", "BUY", 10.0, 0.40),
+            fill("b", "tok1", "BUY", 10.0, 0.60),
+            fill("a", "tok1", "SELL", 15.0, 0.50),
+            fill("a", "tok2", "BUY", 4.0, 0.20),
+            fill("a", "tok2", "SELL", 4.0, 0.30),
+        ];
+        let open = open_lots_by_token(&fills, 0.0);
+        assert_eq!(open.len(), 1, "tok2 is closed");
+        assert_eq!(open["tok1"], vec![Lot { shares: 5.0, price: 0.60 }]);
+
+        // The BUY fee is part of the lot price
+        let with_fee = open_lots_by_token(&fills[..2], 0.5);
+        assert_eq!(with_fee["tok1"][1].price, 0.60 + 0.05);
+    }
+
+    #[test]
+    fn test_open_shares_by_trader() {
+        let fills = vec![
+            fill("b", "tok1", "BUY", 10.0, 0.60),
+            fill("a", "tok1", "BUY", 10.0, 0.40),
+            fill("a", "tok1", "SELL", 4.0, 0.50),
+            // b's SELL can't close a's lot in tok2
+            fill("a", "tok2", "BUY", 4.0, 0.20),
+            fill("b", "tok2", "SELL", 4.0, 0.30),
+            fill("a", "tok3", "BUY", 4.0, 0.20),
+            fill("a", "tok3", "SELL", 4.0, 0.30),
+        ];
+        let open = open_shares_by_trader(&fills);
+        assert_eq!(open.len(), 2, "tok3 is closed");
+        assert_eq!(open["tok1"], vec![("a".to_string(), 6.0), ("b".to_string(), 10.0)]);
+        assert_eq!(open["tok2"], vec![("a".to_string(), 4.0)]);
+    }
+
+    #[test]
+    fn test_tokens_with_open_lots() {
+        let fills = vec![
+            fill("a", "tok1", "BUY", 10.0, 0.40),
+            fill("a", "tok1", "SELL", 10.0, 0.50),
+            // Flat account-wide, but b's SELL can't close a's lot
+            fill("a", "tok2", "BUY", 4.0, 0.20),
+            fill("b", "tok2", "SELL", 4.0, 0.30),
+            fill("a", "tok3", "BUY", 4.0, 0.20),
+        ];
+        let open = tokens_with_open_lots(&fills);
+        assert_eq!(open, HashSet::from(["tok2".to_string(), "tok3".to_string()]));
+    }
+}
@@ -12,6 +12,7 @@
 ///     wallet_address: "0x1234...".to_string(),
 ///     cache_duration_secs: 300, // 5 minutes
 ///     max_bet_portfolio_percent: Some(0.02), // 2% max bet
+///     max_bet_shares_abs: Some(100.0), // and never more than 100 shares
 /// };
 ///
 /// let tracker = PortfolioTracker::new(config);
@@ -45,6 +46,9 @@ pub struct PortfolioConfig {
     /// Maximum bet as percentage of portfolio (e.g., 0.02 = 2%)
     /// None means no portfolio-based limit
     pub max_bet_portfolio_percent: Option<f64>,
+    /// Maximum bet in shares regardless of price
+    /// None means no share limit; with a percent limit too, the smaller one applies
+    pub max_bet_shares_abs: Option<f64>,
 }
 
 impl Default for PortfolioConfig {
@@ -53,6 +57,7 @@ impl Default for PortfolioConfig {
             wallet_address: String::new(),
             cache_duration_secs: 300, // 5 minutes
             max_bet_portfolio_percent: None, // Disabled by default
+            max_bet_shares_abs: None,
         }
     }
 }
@@ -103,12 +108,11 @@ impl PortfolioTracker {
         Some(portfolio_value * percent)
     }
 
-    /// Get the maximum bet size in shares based on portfolio value and price
-    /// Returns None if portfolio-based limiting is disabled or if fetch fails
+    /// Get the maximum bet size in shares: the percent cap at `price` and the absolute
+    /// share cap, whichever is smaller
+    /// Returns None if both are disabled (or only the percent cap is set and the fetch fails)
     pub fn get_max_bet_shares(&self, price: f64) -> Option<f64> {
-        let max_usd = self.get_max_bet_usd()?;
-        let safe_price = price.max(0.01);
-        Some(max_usd / safe_price)
+        max_bet_shares(self.get_max_bet_usd(), self.config.max_bet_shares_abs, price)
     }
 
     /// Get current portfolio value, using cache if valid
@@ -163,18 +167,14 @@ impl PortfolioTracker {
             max_bet_usd: self.config.max_bet_portfolio_percent
                 .map(|p| cached.total_value_usd * p),
             max_bet_percent: self.config.max_bet_portfolio_percent,
+            max_bet_shares_abs: self.config.max_bet_shares_abs,
             cache_age_secs: cached.fetched_at.elapsed().as_secs(),
         })
     }
 
-    /// Fetch USDC balance from blockchain
-    fn fetch_usdc_balance(&self) -> Result<f64> {
-        let usdc_e = self.get_token_balance(USDC_POLYGON)?;
-        let usdc_native = self.get_token_balance(USDC_NATIVE)?;
-
-        // Both have 6 decimals
-        let total = (usdc_e + usdc_native) as f64 / 1_000_000.0;
-        Ok(total)
+    /// Fetch USDC balance of the tracked wallet from blockchain (uncached)
+    pub fn fetch_usdc_balance(&self) -> Result<f64> {
+        fetch_usdc_balance(&self.http_client, &self.config.wallet_address)
     }
 
     /// Fetch positions value from Polymarket Data API
@@ -183,46 +183,64 @@ impl PortfolioTracker {
         let summary = LivePositionsSummary::from_positions(&positions);
         Ok(summary.total_value)
     }
+}
 
-    /// Get ERC20 token balance via RPC
-    fn get_token_balance(&self, token: &str) -> Result<u128> {
-        let owner = &self.config.wallet_address;
-        let owner_padded = format!("{:0>64}", owner.trim_start_matches("0x").to_lowercase());
-        let data = format!("0x70a08231{}", owner_padded);
-
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0",
-            method: "eth_call",
-            params: vec![
-                serde_json::json!({
-                    "to": token,
-                    "data": data
-                }),
-                serde_json::json!("latest"),
-            ],
-            id: 1,
-        };
+/// The more restrictive of a USD cap (converted to shares at `price`) and an absolute share cap
+pub fn max_bet_shares(max_bet_usd: Option<f64>, max_bet_shares_abs: Option<f64>, price: f64) -> Option<f64> {
+    let percent_shares = max_bet_usd.map(|usd| usd / price.max(0.01));
+    match (percent_shares, max_bet_shares_abs) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
 
-        let response: JsonRpcResponse = self.http_client
-            .post(POLYGON_RPC)
-            .json(&request)
-            .send()
-            .context("Failed to send RPC request")?
-            .json()
-            .context("Failed to parse RPC response")?;
+/// Fetch USDC balance (USDC.e + native USDC) of `wallet` from blockchain
+pub fn fetch_usdc_balance(client: &Client, wallet: &str) -> Result<f64> {
+    let usdc_e = get_token_balance(client, wallet, USDC_POLYGON)?;
+    let usdc_native = get_token_balance(client, wallet, USDC_NATIVE)?;
 
-        if let Some(error) = response.error {
-            anyhow::bail!("RPC error: {:?}", error);
-        }
+    // Both have 6 decimals
+    let total = (usdc_e + usdc_native) as f64 / 1_000_000.0;
+    Ok(total)
+}
 
-        let hex = response.result.unwrap_or_else(|| "0x0".to_string());
-        let hex_clean = hex.trim_start_matches("0x");
-        if hex_clean.is_empty() || hex_clean == "0" {
-            return Ok(0);
-        }
+/// Get ERC20 token balance via RPC
+fn get_token_balance(client: &Client, owner: &str, token: &str) -> Result<u128> {
+    let owner_padded = format!("{:0>64}", owner.trim_start_matches("0x").to_lowercase());
+    let data = format!("0x70a08231{}", owner_padded);
+
+    let request = JsonRpcRequest {
+        jsonrpc: "2.0",
+        method: "eth_call",
+        params: vec![
+            serde_json::json!({
+                "to": token,
+                "data": data
+            }),
+            serde_json::json!("latest"),
+        ],
+        id: 1,
+    };
+
+    let response: JsonRpcResponse = client
+        .post(POLYGON_RPC)
+        .json(&request)
+        .send()
+        .context("Failed to send RPC request")?
+        .json()
+        .context("Failed to parse RPC response")?;
+
+    if let Some(error) = response.error {
+        anyhow::bail!("RPC error: {:?}", error);
+    }
 
-        Ok(u128::from_str_radix(hex_clean, 16).unwrap_or(0))
+    let hex = response.result.unwrap_or_else(|| "0x0".to_string());
+    let hex_clean = hex.trim_start_matches("0x");
+    if hex_clean.is_empty() || hex_clean == "0" {
+        return Ok(0);
     }
+
+    Ok(u128::from_str_radix(hex_clean, 16).unwrap_or(0))
 }
 
 /// Detailed portfolio breakdown
@@ -238,6 +256,8 @@ pub struct PortfolioDetails {
     pub max_bet_usd: Option<f64>,
     /// Maximum bet as percentage of portfolio
     pub max_bet_percent: Option<f64>,
+    /// Maximum bet in shares (if share limit enabled)
+    pub max_bet_shares_abs: Option<f64>,
     /// How old the cached value is (seconds)
     pub cache_age_secs: u64,
 }
@@ -295,6 +315,7 @@ mod tests {
             wallet_address: "0x1234".to_string(),
             cache_duration_secs: 60,
             max_bet_portfolio_percent: Some(0.05), // 5%
+            max_bet_shares_abs: None,
         };
         assert_eq!(config.max_bet_portfolio_percent, Some(0.05));
     }
@@ -305,10 +326,32 @@ mod tests {
             wallet_address: String::new(),
             cache_duration_secs: 300,
             max_bet_portfolio_percent: None, // Disabled
+            max_bet_shares_abs: None,
         };
         let tracker = PortfolioTracker::new(config);
 
         // Should return None when disabled
         assert!(tracker.get_max_bet_usd().is_none());
+        assert!(tracker.get_max_bet_shares(0.50).is_none());
+    }
+
+    #[test]
+    fn test_share_cap_without_percent_needs_no_fetch() {
+        let config = PortfolioConfig { max_bet_shares_abs: Some(100.0), ..PortfolioConfig::default() };
+        let tracker = PortfolioTracker::new(config);
+        assert_eq!(tracker.get_max_bet_shares(0.50), Some(100.0));
+        assert_eq!(tracker.get_max_bet_shares(0.05), Some(100.0));
+    }
+
+    #[test]
+    fn test_max_bet_shares_takes_tighter_cap() {
+        // $20 at 0.50 = 40 shares; a 25-share cap binds
+        assert_eq!(max_bet_shares(Some(20.0), Some(25.0), 0.50), Some(25.0));
+        // $20 at 0.80 = 25 shares; the percent cap binds under 40 shares
+        assert_eq!(max_bet_shares(Some(20.0), Some(40.0), 0.80), Some(25.0));
+        // Either alone
+        assert_eq!(max_bet_shares(Some(20.0), None, 0.50), Some(40.0));
+        assert_eq!(max_bet_shares(None, Some(25.0), 0.50), Some(25.0));
+        assert_eq!(max_bet_shares(None, None, 0.50), None);
     }
 }
@@ -1,52 +1,93 @@
-/// Price fetcher module for Polymarket CLOB API
-/// Provides caching and rate limiting for price data
-///
-/// # Features
-///
-/// - **Caching**: Price data is cached with configurable TTL (default: 30 seconds)
-/// - **Rate Limiting**: Automatic rate limiting (default: 10 requests/second)
-/// - **Batch Fetching**: Fetch multiple token prices efficiently
-/// - **Fallback**: Option to use stale cache when API fails
-///
-/// # Example
-///
-/// ```no_run
-/// use pm_whale_follower::prices::PriceCache;
-///
-/// // Create cache with 30-second TTL
-/// let mut cache = PriceCache::new(30);
-///
-/// // Fetch a single price (with caching and rate limiting)
-/// if let Ok(price) = cache.get_or_fetch_price("token_id_123") {
-///     println!("Bid: {}, Ask: {}", price.bid_price, price.ask_price);
-/// }
-///
-/// // Fetch multiple prices in batch
-/// let tokens = vec!["token1", "token2", "token3"];
-/// let prices = cache.get_or_fetch_prices_batch(&tokens);
-/// println!("Fetched {} prices", prices.len());
-///
-/// // Use fallback to stale cache if API fails
-/// if let Some(price) = cache.get_or_fetch_price_with_fallback("token_id") {
-///     println!("Price (may be stale): {}", price.bid_price);
-/// }
-/// ```
-///
-/// # Custom Configuration
-///
-/// ```no_run
-/// use pm_whale_follower::prices::PriceCache;
-///
-/// // Custom TTL and rate limit
-/// let cache = PriceCache::new(60)  // 60 second TTL
-///     .with_rate_limit(5);         // 5 requests/second
-/// ```
+//! Price fetcher module for Polymarket CLOB API
+//! Provides caching and rate limiting for price data
+//!
+//! # Features
+//!
+//! - **Caching**: Price data is cached with configurable TTL (default: 30 seconds)
+//! - **Rate Limiting**: Automatic rate limiting (default: 10 requests/second)
+//! - **Batch Fetching**: Fetch multiple token prices efficiently
+//! - **Multi-book Requests**: `get_or_fetch_many` fetches all stale tokens with one
+//!   `POST /books` call per `BOOKS_BATCH_SIZE` tokens instead of one request each
+//! - **Fallback**: Option to use stale cache when API fails
+//! - **Live Feed**: Optional `PriceFeed` streams best bid/ask for held tokens from the
+//!   CLOB market WebSocket; `PriceCache::with_feed` consults it before any REST request
+//!
+//! # Example
+//!
+//! ```no_run
+//! use pm_whale_follower::prices::PriceCache;
+//!
+//! // Create cache with 30-second TTL
+//! let mut cache = PriceCache::new(30);
+//!
+//! // Fetch a single price (with caching and rate limiting)
+//! if let Ok(price) = cache.get_or_fetch_price("token_id_123") {
+//!     println!("Bid: {}, Ask: {}", price.bid_price, price.ask_price);
+//! }
+//!
+//! // Fetch multiple prices in batch
+//! let tokens = vec!["token1", "token2", "token3"];
+//! let prices = cache.get_or_fetch_prices_batch(&tokens);
+//! println!("Fetched {} prices", prices.len());
+//!
+//! // Fetch many prices with a single /books request (cached tokens are skipped)
+//! let prices = cache.get_or_fetch_many(&tokens);
+//!
+//! // Use fallback to stale cache if API fails
+//! if let Some(price) = cache.get_or_fetch_price_with_fallback("token_id") {
+//!     println!("Price (may be stale): {}", price.bid_price);
+//! }
+//! ```
+//!
+//! # Custom Configuration
+//!
+//! ```no_run
+//! use pm_whale_follower::prices::PriceCache;
+//!
+//! // Custom TTL and rate limit
+//! let cache = PriceCache::new(60)  // 60 second TTL
+//!     .with_rate_limit(5);         // 5 requests/second
+//! ```
+//!
+//! # Live Feed
+//!
+//! ```no_run
+//! use pm_whale_follower::prices::{PriceCache, PriceFeed, MARKET_WS_URL};
+//!
+//! let mut cache = PriceCache::new(30).with_feed(PriceFeed::start(MARKET_WS_URL));
+//!
+//! // Subscribe to the held tokens; REST is used until the feed has a price
+//! let tokens = vec!["token1", "token2"];
+//! cache.track_tokens(&tokens);
+//! let prices = cache.get_or_fetch_many(&tokens);
+//! ```
 
 use anyhow::{anyhow, Result};
+use futures::{SinkExt, StreamExt};
 use reqwest::blocking::Client;
 use serde::Deserialize;
+use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+
+/// Maximum tokens per `POST /books` request
+pub const BOOKS_BATCH_SIZE: usize = 100;
+
+/// CLOB market-data WebSocket channel
+pub const MARKET_WS_URL: &str = "wss://ws-subscriptions-clob.polymarket.com/ws/market";
+
+/// The market channel drops connections that stay silent longer than this
+const FEED_PING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Reconnect after this long without any message (pings included)
+const FEED_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Reconnect backoff bounds
+const FEED_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const FEED_BACKOFF_MAX: Duration = Duration::from_secs(60);
 
 /// Price information for a token
 #[derive(Debug, Clone)]
@@ -59,6 +100,9 @@ pub struct PriceInfo {
 /// API response for order book
 #[derive(Debug, Deserialize)]
 struct BookResponse {
+    /// Token ID (present in /books responses)
+    #[serde(default)]
+    asset_id: Option<String>,
     bids: Vec<BookLevel>,
     asks: Vec<BookLevel>,
 }
@@ -70,6 +114,265 @@ struct BookLevel {
     size: String,
 }
 
+/// Best bid/ask update for one token from a market channel message
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuoteUpdate {
+    pub token_id: String,
+    pub bid_price: f64,
+    pub ask_price: f64,
+}
+
+/// Best bid/ask updates carried by a market channel message
+///
+/// Handles `book` snapshots (sent on subscribe and after trades), `price_change` events
+/// with `best_bid`/`best_ask`, and `best_bid_ask` events, singly or in a JSON array.
+/// Anything else (PONG, `last_trade_price`, tick size changes) yields nothing.
+pub fn parse_market_message(text: &str) -> Vec<QuoteUpdate> {
+    let Ok(value) = serde_json::from_str::<Value>(text) else {
+        return Vec::new();
+    };
+    let events = match value {
+        Value::Array(events) => events,
+        event => vec![event],
+    };
+
+    let mut updates = Vec::new();
+    for event in &events {
+        match event.get("event_type").and_then(|t| t.as_str()) {
+            Some("book") => updates.extend(quote_from_book_event(event)),
+            Some("price_change") => {
+                let changes = event.get("price_changes").and_then(|c| c.as_array());
+                for change in changes.into_iter().flatten() {
+                    updates.extend(quote_from_best_fields(change));
+                }
+            }
+            Some("best_bid_ask") => updates.extend(quote_from_best_fields(event)),
+            _ => {}
+        }
+    }
+    updates
+}
+
+/// Parse a price that may be sent as a string or a number
+fn json_price(value: &Value) -> Option<f64> {
+    match value {
+        Value::String(s) => s.parse().ok(),
+        v => v.as_f64(),
+    }
+}
+
+/// Best bid (highest) and best ask (lowest) from a `book` event; level order isn't relied on
+fn quote_from_book_event(event: &Value) -> Option<QuoteUpdate> {
+    let token_id = event.get("asset_id")?.as_str()?;
+    let best = |side: &str, pick: fn(f64, f64) -> f64| {
+        event
+            .get(side)
+            .and_then(|levels| levels.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|level| level.get("price").and_then(json_price))
+            .reduce(pick)
+    };
+
+    // Same defaults as an empty REST book: no bids = 0.0, no asks = 1.0
+    let bid_price = best("bids", f64::max);
+    let ask_price = best("asks", f64::min);
+
+    Some(QuoteUpdate {
+        token_id: token_id.to_string(),
+        bid_price: bid_price.unwrap_or(0.0),
+        ask_price: ask_price.unwrap_or(1.0),
+    })
+}
+
+/// Quote from an object carrying `asset_id`, `best_bid` and `best_ask`
+fn quote_from_best_fields(event: &Value) -> Option<QuoteUpdate> {
+    Some(QuoteUpdate {
+        token_id: event.get("asset_id")?.as_str()?.to_string(),
+        bid_price: json_price(event.get("best_bid")?)?,
+        ask_price: json_price(event.get("best_ask")?)?,
+    })
+}
+
+/// Live best bid/ask for held tokens, streamed from the CLOB market WebSocket
+///
+/// `start` runs the subscription on its own thread (with its own runtime), so the feed
+/// works from both blocking binaries and async handlers. `set_tokens` changes the
+/// subscribed set; the connection is re-established with the new set. Prices are only
+/// served while the connection that produced them is up, so a dropped feed falls back
+/// to REST instead of serving frozen quotes. Clones share the same feed; it shuts down
+/// when the last clone is dropped.
+#[derive(Clone)]
+pub struct PriceFeed {
+    prices: Arc<RwLock<HashMap<String, PriceInfo>>>,
+    tokens: Arc<watch::Sender<Vec<String>>>,
+}
+
+impl PriceFeed {
+    /// A feed with no connection (prices only arrive through `apply`)
+    fn new() -> Self {
+        let (tokens, _) = watch::channel(Vec::new());
+        Self {
+            prices: Arc::new(RwLock::new(HashMap::new())),
+            tokens: Arc::new(tokens),
+        }
+    }
+
+    /// Start streaming from `ws_url` (normally `MARKET_WS_URL`); nothing is subscribed until `set_tokens`
+    pub fn start(ws_url: &str) -> Self {
+        let feed = Self::new();
+        let prices = Arc::clone(&feed.prices);
+        let tokens_rx = feed.tokens.subscribe();
+        let ws_url = ws_url.to_string();
+
+        std::thread::Builder::new()
+            .name("price-feed".to_string())
+            .spawn(move || {
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .expect("Failed to create price feed runtime");
+                runtime.block_on(run_feed(ws_url, prices, tokens_rx));
+            })
+            .expect("Failed to spawn price feed thread");
+
+        feed
+    }
+
+    /// Subscribe to exactly these tokens (resubscribes only if the set changed)
+    pub fn set_tokens(&self, token_ids: &[&str]) {
+        let mut wanted: Vec<String> = token_ids.iter().map(|t| t.to_string()).collect();
+        wanted.sort();
+        wanted.dedup();
+        self.tokens.send_if_modified(|current| {
+            if *current == wanted {
+                return false;
+            }
+            *current = wanted;
+            true
+        });
+    }
+
+    /// Latest live price for a token, if the feed has one
+    pub fn get(&self, token_id: &str) -> Option<PriceInfo> {
+        self.prices.read().ok()?.get(token_id).cloned()
+    }
+}
+
+fn apply_updates(prices: &RwLock<HashMap<String, PriceInfo>>, updates: Vec<QuoteUpdate>) {
+    if updates.is_empty() {
+        return;
+    }
+    let Ok(mut prices) = prices.write() else { return };
+    let now = Instant::now();
+    for update in updates {
+        prices.insert(update.token_id, PriceInfo {
+            bid_price: update.bid_price,
+            ask_price: update.ask_price,
+            timestamp: now,
+        });
+    }
+}
+
+fn clear_prices(prices: &RwLock<HashMap<String, PriceInfo>>) {
+    if let Ok(mut prices) = prices.write() {
+        prices.clear();
+    }
+}
+
+/// Connect/subscribe loop; exits once every `PriceFeed` handle is dropped
+async fn run_feed(
+    ws_url: String,
+    prices: Arc<RwLock<HashMap<String, PriceInfo>>>,
+    mut tokens_rx: watch::Receiver<Vec<String>>,
+) {
+    let mut backoff = FEED_BACKOFF_MIN;
+    loop {
+        let tokens = tokens_rx.borrow_and_update().clone();
+        if tokens.is_empty() {
+            // Nothing held: stay disconnected until there is something to watch
+            if tokens_rx.changed().await.is_err() {
+                return;
+            }
+            continue;
+        }
+
+        let result = stream_prices(&ws_url, &tokens, &prices, &mut tokens_rx, &mut backoff).await;
+        // Quotes from a closed connection can't be trusted to stay current
+        clear_prices(&prices);
+
+        match result {
+            // Held set changed: resubscribe straight away
+            Ok(()) => {}
+            Err(e) => {
+                eprintln!("⚠️ Price feed disconnected: {} (retrying in {}s)", e, backoff.as_secs());
+                tokio::select! {
+                    _ = tokio::time::sleep(backoff) => {}
+                    changed = tokens_rx.changed() => {
+                        if changed.is_err() {
+                            return;
+                        }
+                    }
+                }
+                backoff = (backoff * 2).min(FEED_BACKOFF_MAX);
+            }
+        }
+
+        if tokens_rx.has_changed().is_err() {
+            return;
+        }
+    }
+}
+
+/// One connection: subscribe to `tokens` and apply updates until the held set changes (Ok) or the socket fails (Err)
+async fn stream_prices(
+    ws_url: &str,
+    tokens: &[String],
+    prices: &RwLock<HashMap<String, PriceInfo>>,
+    tokens_rx: &mut watch::Receiver<Vec<String>>,
+    backoff: &mut Duration,
+) -> Result<()> {
+    let (mut ws, _) = connect_async(ws_url).await?;
+    let sub = serde_json::json!({ "assets_ids": tokens, "type": "market" });
+    ws.send(Message::Text(sub.to_string())).await?;
+
+    let mut ping = tokio::time::interval(FEED_PING_INTERVAL);
+    ping.tick().await;
+
+    loop {
+        tokio::select! {
+            // Held set changed (or every handle dropped; the caller notices and exits)
+            _ = tokens_rx.changed() => {
+                let _ = ws.close(None).await;
+                return Ok(());
+            }
+            _ = ping.tick() => {
+                ws.send(Message::Text("PING".to_string())).await?;
+            }
+            msg = tokio::time::timeout(FEED_READ_TIMEOUT, ws.next()) => {
+                let msg = msg
+                    .map_err(|_| anyhow!("price feed timeout"))?
+                    .ok_or_else(|| anyhow!("price feed closed"))??;
+                let text = match msg {
+                    Message::Text(text) => text,
+                    Message::Binary(bin) => String::from_utf8_lossy(&bin).into_owned(),
+                    Message::Ping(d) => {
+                        ws.send(Message::Pong(d)).await?;
+                        continue;
+                    }
+                    Message::Close(f) => return Err(anyhow!("price feed closed: {:?}", f)),
+                    _ => continue,
+                };
+                let updates = parse_market_message(&text);
+                if !updates.is_empty() {
+                    *backoff = FEED_BACKOFF_MIN;
+                    apply_updates(prices, updates);
+                }
+            }
+        }
+    }
+}
+
 /// Cache for price data with TTL support
 pub struct PriceCache {
     ttl_seconds: u64,
@@ -80,6 +383,8 @@ pub struct PriceCache {
     min_request_interval: Duration,
     /// Last API request timestamp
     last_request: Option<Instant>,
+    /// Live WebSocket prices, consulted before the cache and REST
+    feed: Option<PriceFeed>,
 }
 
 impl PriceCache {
@@ -103,6 +408,20 @@ impl PriceCache {
             // 10 requests per second = 100ms between requests
             min_request_interval: Duration::from_millis(100),
             last_request: None,
+            feed: None,
+        }
+    }
+
+    /// Serve live prices from `feed` when it has them
+    pub fn with_feed(mut self, feed: PriceFeed) -> Self {
+        self.feed = Some(feed);
+        self
+    }
+
+    /// Point the live feed (if any) at the currently held tokens
+    pub fn track_tokens(&self, token_ids: &[&str]) {
+        if let Some(feed) = &self.feed {
+            feed.set_tokens(token_ids);
         }
     }
 
@@ -126,8 +445,11 @@ impl PriceCache {
         self.last_request = Some(Instant::now());
     }
 
-    /// Get cached price if still valid (within TTL)
+    /// Get live feed price, or cached price if still valid (within TTL)
     pub fn get_price(&self, token_id: &str) -> Option<PriceInfo> {
+        if let Some(live) = self.feed.as_ref().and_then(|feed| feed.get(token_id)) {
+            return Some(live);
+        }
         if let Some(price_info) = self.cache.get(token_id) {
             let age_seconds = price_info.timestamp.elapsed().as_secs();
             if age_seconds < self.ttl_seconds {
@@ -142,6 +464,27 @@ impl PriceCache {
         self.cache.insert(token_id, price_info);
     }
 
+    /// Best bid (highest buy price) and best ask (lowest sell price) from a book
+    fn price_from_book(book: &BookResponse) -> PriceInfo {
+        let bid_price = book
+            .bids
+            .first()
+            .and_then(|level| level.price.parse::<f64>().ok())
+            .unwrap_or(0.0);
+
+        let ask_price = book
+            .asks
+            .first()
+            .and_then(|level| level.price.parse::<f64>().ok())
+            .unwrap_or(1.0);
+
+        PriceInfo {
+            bid_price,
+            ask_price,
+            timestamp: Instant::now(),
+        }
+    }
+
     /// Fetch price from CLOB API
     pub fn fetch_price(&mut self, token_id: &str) -> Result<PriceInfo> {
         // Apply rate limiting before making request
@@ -158,25 +501,7 @@ impl PriceCache {
         }
 
         let book: BookResponse = response.json()?;
-
-        // Get best bid (highest buy price) and best ask (lowest sell price)
-        let bid_price = book
-            .bids
-            .first()
-            .and_then(|level| level.price.parse::<f64>().ok())
-            .unwrap_or(0.0);
-
-        let ask_price = book
-            .asks
-            .first()
-            .and_then(|level| level.price.parse::<f64>().ok())
-            .unwrap_or(1.0);
-
-        let price_info = PriceInfo {
-            bid_price,
-            ask_price,
-            timestamp: Instant::now(),
-        };
+        let price_info = Self::price_from_book(&book);
 
         // Update cache with fresh data
         self.set_price(token_id.to_string(), price_info.clone());
@@ -258,6 +583,79 @@ impl PriceCache {
 
         results
     }
+
+    /// Fetch order books for several tokens with one `POST /books` request
+    /// Returns prices for the tokens present in the response and updates the cache
+    pub fn fetch_books(&mut self, token_ids: &[&str]) -> Result<HashMap<String, PriceInfo>> {
+        self.apply_rate_limit();
+
+        let body: Vec<serde_json::Value> = token_ids
+            .iter()
+            .map(|id| serde_json::json!({ "token_id": id }))
+            .collect();
+        let url = format!("{}/books", self.host);
+        let response = self.client.post(&url).json(&body).send()?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Failed to fetch books: HTTP {}",
+                response.status()
+            ));
+        }
+
+        let books: Vec<BookResponse> = response.json()?;
+        let mut results = HashMap::with_capacity(books.len());
+        for book in &books {
+            if let Some(token_id) = &book.asset_id {
+                let price_info = Self::price_from_book(book);
+                self.set_price(token_id.clone(), price_info.clone());
+                results.insert(token_id.clone(), price_info);
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Get prices for many tokens, fetching every expired/missing token in bulk
+    ///
+    /// Cached tokens (within TTL) are not refetched. The rest are requested in chunks
+    /// of `BOOKS_BATCH_SIZE` via `POST /books`, so 50 positions cost one round trip
+    /// instead of 50 rate-limited requests (~5s at the default 10 req/s).
+    /// Tokens that can't be fetched fall back to stale cache if available.
+    pub fn get_or_fetch_many(&mut self, token_ids: &[&str]) -> HashMap<String, PriceInfo> {
+        let mut results = HashMap::with_capacity(token_ids.len());
+        let mut missing: Vec<&str> = Vec::new();
+
+        for token_id in token_ids {
+            if results.contains_key(*token_id) || missing.contains(token_id) {
+                continue;
+            }
+            match self.get_price(token_id) {
+                Some(cached) => {
+                    results.insert(token_id.to_string(), cached);
+                }
+                None => missing.push(token_id),
+            }
+        }
+
+        for chunk in missing.chunks(BOOKS_BATCH_SIZE) {
+            match self.fetch_books(chunk) {
+                Ok(fetched) => results.extend(fetched),
+                Err(e) => eprintln!("Failed to fetch prices for {} tokens: {}", chunk.len(), e),
+            }
+        }
+
+        // Stale fallback for anything the API didn't return
+        for token_id in missing {
+            if !results.contains_key(token_id)
+                && let Some(stale) = self.cache.get(token_id)
+            {
+                results.insert(token_id.to_string(), stale.clone());
+            }
+        }
+
+        results
+    }
 }
 
 #[cfg(test)]
@@ -560,6 +958,210 @@ mod tests {
         assert_eq!(price.ask_price, 0.66);
     }
 
+    /// Minimal /books server: answers every POST with a book per requested token
+    /// and records the request bodies
+    fn spawn_books_server() -> (String, std::sync::Arc<std::sync::Mutex<Vec<serde_json::Value>>>) {
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let host = format!("http://{}", listener.local_addr().unwrap());
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                    if let Some(len) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        content_length = len.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0u8; content_length];
+                reader.read_exact(&mut body).unwrap();
+                let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+                let books: Vec<serde_json::Value> = body
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|req| serde_json::json!({
+                        "asset_id": req["token_id"],
+                        "bids": [{ "price": "0.40", "size": "10" }],
+                        "asks": [{ "price": "0.42", "size": "10" }],
+                    }))
+                    .collect();
+                recorded.lock().unwrap().push(body);
+
+                let payload = serde_json::to_string(&books).unwrap();
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    payload.len(),
+                    payload
+                );
+            }
+        });
+
+        (host, requests)
+    }
+
+    #[test]
+    fn test_get_or_fetch_many_uses_single_request() {
+        let (host, requests) = spawn_books_server();
+        let mut cache = PriceCache::with_host(30, &host);
+
+        let token_ids: Vec<String> = (0..50).map(|i| format!("token{}", i)).collect();
+        let tokens: Vec<&str> = token_ids.iter().map(|s| s.as_str()).collect();
+
+        // 50 serial fetches would take >= 4.9s at the default 10 req/s
+        let start = Instant::now();
+        let results = cache.get_or_fetch_many(&tokens);
+        let elapsed = start.elapsed();
+
+        assert_eq!(results.len(), 50);
+        assert_eq!(results["token7"].bid_price, 0.40);
+        assert_eq!(results["token7"].ask_price, 0.42);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+        assert!(elapsed < Duration::from_secs(1), "batch took {:?}", elapsed);
+    }
+
+    #[test]
+    fn test_get_or_fetch_many_skips_cached_tokens() {
+        let (host, requests) = spawn_books_server();
+        let mut cache = PriceCache::with_host(30, &host);
+        cache.set_price(
+            "cached".to_string(),
+            PriceInfo {
+                bid_price: 0.70,
+                ask_price: 0.71,
+                timestamp: Instant::now(),
+            },
+        );
+
+        let results = cache.get_or_fetch_many(&["cached", "fresh", "fresh"]);
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results["cached"].bid_price, 0.70);
+        assert_eq!(results["fresh"].bid_price, 0.40);
+
+        // Only the uncached token is requested, once
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0], serde_json::json!([{ "token_id": "fresh" }]));
+
+        // Fresh result is now cached
+        assert!(cache.get_price("fresh").is_some());
+    }
+
+    #[test]
+    fn test_get_or_fetch_many_falls_back_to_stale_cache() {
+        let mut cache = PriceCache::with_host(1, "http://invalid-host.example.com");
+        cache.set_price(
+            "stale".to_string(),
+            PriceInfo {
+                bid_price: 0.65,
+                ask_price: 0.66,
+                timestamp: Instant::now(),
+            },
+        );
+        thread::sleep(Duration::from_secs(2));
+
+        let results = cache.get_or_fetch_many(&["stale", "unknown"]);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results["stale"].bid_price, 0.65);
+    }
+
+    #[test]
+    fn test_parse_market_message_book_snapshot() {
+        let msg = r#"[{
+            "event_type": "book",
+            "asset_id": "tok1",
+            "bids": [{ "price": "0.40", "size": "10" }, { "price": "0.44", "size": "5" }],
+            "asks": [{ "price": "0.50", "size": "10" }, { "price": "0.46", "size": "5" }]
+        }, {
+            "event_type": "book",
+            "asset_id": "tok2",
+            "bids": [],
+            "asks": []
+        }]"#;
+
+        let updates = parse_market_message(msg);
+        assert_eq!(updates, vec![
+            QuoteUpdate { token_id: "tok1".to_string(), bid_price: 0.44, ask_price: 0.46 },
+            QuoteUpdate { token_id: "tok2".to_string(), bid_price: 0.0, ask_price: 1.0 },
+        ]);
+    }
+
+    #[test]
+    fn test_parse_market_message_price_change_and_best_bid_ask() {
+        let price_change = r#"{
+            "event_type": "price_change",
+            "market": "0xmarket",
+            "price_changes": [
+                { "asset_id": "tok1", "price": "0.45", "size": "20", "side": "BUY", "best_bid": "0.45", "best_ask": "0.47" },
+                { "asset_id": "tok2", "price": "0.55", "size": "0", "side": "SELL" }
+            ]
+        }"#;
+        assert_eq!(parse_market_message(price_change), vec![
+            QuoteUpdate { token_id: "tok1".to_string(), bid_price: 0.45, ask_price: 0.47 },
+        ]);
+
+        let best = r#"{ "event_type": "best_bid_ask", "asset_id": "tok3", "best_bid": 0.61, "best_ask": "0.63" }"#;
+        assert_eq!(parse_market_message(best), vec![
+            QuoteUpdate { token_id: "tok3".to_string(), bid_price: 0.61, ask_price: 0.63 },
+        ]);
+    }
+
+    #[test]
+    fn test_parse_market_message_ignores_other_messages() {
+        assert!(parse_market_message("PONG").is_empty());
+        assert!(parse_market_message("[]").is_empty());
+        assert!(parse_market_message(r#"{"event_type": "last_trade_price", "asset_id": "tok1", "price": "0.5"}"#).is_empty());
+    }
+
+    #[test]
+    fn test_feed_price_takes_precedence_over_rest() {
+        let feed = PriceFeed::new();
+        let mut cache = PriceCache::with_host(30, "http://invalid-host.example.com").with_feed(feed.clone());
+
+        // No live price yet: REST is tried (and fails)
+        assert!(cache.get_or_fetch_price("tok1").is_err());
+
+        apply_updates(&feed.prices, vec![QuoteUpdate { token_id: "tok1".to_string(), bid_price: 0.52, ask_price: 0.54 }]);
+        let price = cache.get_or_fetch_price("tok1").unwrap();
+        assert_eq!(price.bid_price, 0.52);
+        assert_eq!(price.ask_price, 0.54);
+
+        // A dropped connection clears live prices rather than serving them frozen
+        clear_prices(&feed.prices);
+        assert!(cache.get_price("tok1").is_none());
+    }
+
+    #[test]
+    fn test_feed_set_tokens_notifies_only_on_change() {
+        let feed = PriceFeed::new();
+        let mut rx = feed.tokens.subscribe();
+
+        feed.set_tokens(&["b", "a", "a"]);
+        assert!(rx.has_changed().unwrap());
+        assert_eq!(*rx.borrow_and_update(), vec!["a".to_string(), "b".to_string()]);
+
+        // Same set in a different order: no resubscribe
+        feed.set_tokens(&["a", "b"]);
+        assert!(!rx.has_changed().unwrap());
+
+        feed.set_tokens(&["a"]);
+        assert!(rx.has_changed().unwrap());
+    }
+
     #[test]
     fn test_fallback_returns_none_when_no_cache() {
         let mut cache = PriceCache::with_host(30, "http://invalid-host.example.com");
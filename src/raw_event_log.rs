@@ -0,0 +1,167 @@
+//! Raw WebSocket event log (RAW_EVENT_LOG) for post-mortems and offline replay
+//!
+//! Every log notification received is appended as one JSON line, before parsing and
+//! trader filtering, so the file holds exactly what the upstream sent. The backtest's
+//! `--from-raw-log` wraps each line back into a notification and decodes it with
+//! `event_parser::parse_event`, reproducing what the bot saw live.
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::models::WsMessage;
+
+/// One received log, as recorded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RawEvent {
+    /// Unix ms when the frame was received
+    pub received_ms: i64,
+    /// Hex block number as sent (e.g. "0x4b1a2c")
+    pub block: Option<String>,
+    pub tx_hash: Option<String>,
+    pub topics: Vec<String>,
+    pub data: String,
+}
+
+impl RawEvent {
+    /// The `result` of a log notification; None for anything else (subscription acks, errors)
+    pub fn from_message(message: &str, received_ms: i64) -> Option<Self> {
+        let msg: WsMessage = serde_json::from_str(message).ok()?;
+        let result = msg.params?.result?;
+        Some(Self {
+            received_ms,
+            block: result.block_number,
+            tx_hash: result.transaction_hash,
+            topics: result.topics,
+            data: result.data,
+        })
+    }
+
+    /// Rebuild the `eth_subscription` notification `parse_event` expects
+    pub fn to_message(&self) -> String {
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "eth_subscription",
+            "params": {
+                "result": {
+                    "topics": self.topics,
+                    "data": self.data,
+                    "blockNumber": self.block,
+                    "transactionHash": self.tx_hash,
+                }
+            }
+        }).to_string()
+    }
+}
+
+pub struct RawEventLog {
+    path: PathBuf,
+    /// Appends come from several blocking threads; keep lines whole
+    write_lock: Mutex<()>,
+}
+
+impl RawEventLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), write_lock: Mutex::new(()) }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, event: &RawEvent) -> io::Result<()> {
+        let line = serde_json::to_string(event)?;
+        let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut f = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(f, "{}", line)
+    }
+}
+
+/// Read a raw event log, oldest first; malformed lines are skipped and counted
+pub fn read(path: impl AsRef<Path>) -> io::Result<(Vec<RawEvent>, usize)> {
+    let file = std::fs::File::open(path)?;
+    let mut events = Vec::new();
+    let mut malformed = 0;
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<RawEvent>(&line) {
+            Ok(event) => events.push(event),
+            Err(_) => malformed += 1,
+        }
+    }
+    // Blocking writers can land a few ms out of order
+    events.sort_by_key(|e| e.received_ms);
+    Ok((events, malformed))
+}
+
+static RAW_EVENT_LOG: OnceCell<RawEventLog> = OnceCell::new();
+
+/// Set the process-wide raw event log (first call wins)
+pub fn init(log: RawEventLog) {
+    let _ = RAW_EVENT_LOG.set(log);
+}
+
+pub fn is_enabled() -> bool {
+    RAW_EVENT_LOG.get().is_some()
+}
+
+/// Record a received frame off the async runtime (no-op before `init` or for non-log frames)
+pub fn record(message: String, received_ms: i64) {
+    if !is_enabled() {
+        return;
+    }
+    tokio::task::spawn_blocking(move || {
+        let Some(log) = RAW_EVENT_LOG.get() else { return };
+        let Some(event) = RawEvent::from_message(&message, received_ms) else { return };
+        if let Err(e) = log.append(&event) {
+            eprintln!("Warning: Failed to write raw event to {}: {}", log.path().display(), e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTIFICATION: &str = r#"{"jsonrpc":"2.0","method":"eth_subscription","params":{"subscription":"0x1","result":{"topics":["0xd0a08e8c493f9c94f29311604c9de1b4e8c8d4c06bd0c789af57f2d65bfec0f6","0x00","0x000000000000000000000000def456def456789012345678901234567890def4"],"data":"0x01","blockNumber":"0x1234","transactionHash":"0xabcdef"}}}"#;
+
+    #[test]
+    fn test_round_trip_through_notification() {
+        let event = RawEvent::from_message(NOTIFICATION, 1_700_000_000_000).unwrap();
+        assert_eq!(event.block.as_deref(), Some("0x1234"));
+        assert_eq!(event.tx_hash.as_deref(), Some("0xabcdef"));
+        assert_eq!(event.topics.len(), 3);
+
+        let rebuilt = RawEvent::from_message(&event.to_message(), event.received_ms).unwrap();
+        assert_eq!(rebuilt, event);
+    }
+
+    #[test]
+    fn test_non_log_frames_are_ignored() {
+        assert!(RawEvent::from_message(r#"{"jsonrpc":"2.0","id":1,"result":"0xsub"}"#, 0).is_none());
+        assert!(RawEvent::from_message("not json", 0).is_none());
+    }
+
+    #[test]
+    fn test_append_and_read_sorted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("raw.jsonl");
+        let log = RawEventLog::new(&path);
+
+        let later = RawEvent::from_message(NOTIFICATION, 2_000).unwrap();
+        let earlier = RawEvent { tx_hash: Some("0x01".into()), ..RawEvent::from_message(NOTIFICATION, 1_000).unwrap() };
+        log.append(&later).unwrap();
+        log.append(&earlier).unwrap();
+        std::fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"garbage\n").unwrap();
+
+        let (events, malformed) = read(&path).unwrap();
+        assert_eq!(events, vec![earlier, later]);
+        assert_eq!(malformed, 1);
+    }
+}
@@ -0,0 +1,173 @@
+//! In-memory ring buffer of the most recently recorded trades (RECENT_TRADES_CAPACITY)
+//!
+//! The API's `/trades` is mostly asked for the last few trades. Every record sent to the
+//! persistence worker is also kept here, newest last, so those queries are answered
+//! without opening SQLite. The buffer knows from when it holds every trade: since it was
+//! created, or since the newest trade it has dropped. A query it can't answer completely
+//! (not enough matches and a range reaching back before that point) returns None, and
+//! the caller falls through to the database.
+
+use std::collections::VecDeque;
+use std::sync::RwLock;
+
+use crate::persistence::{SHADOW_STATUS, TradeFilter, TradeRecord};
+
+pub struct RecentTrades {
+    inner: RwLock<Ring>,
+}
+
+struct Ring {
+    trades: VecDeque<TradeRecord>,
+    capacity: usize,
+    /// Every trade with `timestamp_ms` at or after this is in the buffer
+    complete_since_ms: i64,
+}
+
+impl RecentTrades {
+    /// Empty buffer of `capacity` trades, complete from `start_ms` (trades recorded before are only in the DB)
+    pub fn new(capacity: usize, start_ms: i64) -> Self {
+        Self {
+            inner: RwLock::new(Ring {
+                trades: VecDeque::with_capacity(capacity),
+                capacity,
+                complete_since_ms: start_ms,
+            }),
+        }
+    }
+
+    /// Add a trade, dropping the oldest once full; SHADOW rows are not live trades and are ignored
+    pub fn push(&self, record: TradeRecord) {
+        if record.status == SHADOW_STATUS {
+            return;
+        }
+        let mut ring = self.inner.write().unwrap_or_else(|e| e.into_inner());
+        if ring.capacity == 0 {
+            return;
+        }
+        if ring.trades.len() == ring.capacity
+            && let Some(dropped) = ring.trades.pop_front()
+        {
+            ring.complete_since_ms = ring.complete_since_ms.max(dropped.timestamp_ms + 1);
+        }
+        ring.trades.push_back(record);
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.read().unwrap_or_else(|e| e.into_inner()).trades.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Up to `limit` trades matching `filter`, newest first, as `TradeStore::get_trades_filtered`
+    /// would return them; None if older trades the buffer no longer (or never) held could match
+    pub fn query(&self, filter: &TradeFilter, limit: usize) -> Option<Vec<TradeRecord>> {
+        let ring = self.inner.read().unwrap_or_else(|e| e.into_inner());
+        let trader = filter.trader.as_ref().map(|t| t.strip_prefix("0x").unwrap_or(t).to_lowercase());
+        let status = filter.status.as_ref().map(|s| s.to_uppercase());
+
+        let mut matches: Vec<&TradeRecord> = ring.trades.iter().rev()
+            .filter(|t| trader.as_ref().is_none_or(|hex| t.trader_address.strip_prefix("0x").unwrap_or(&t.trader_address) == hex))
+            .filter(|t| filter.token.as_ref().is_none_or(|token| &t.token_id == token))
+            .filter(|t| status.as_ref().is_none_or(|status| &t.status == status))
+            .filter(|t| filter.since_ms.is_none_or(|since| t.timestamp_ms >= since))
+            .collect();
+        // Stable: equal timestamps stay newest-recorded first
+        matches.sort_by_key(|t| std::cmp::Reverse(t.timestamp_ms));
+
+        let complete = matches.len() >= limit
+            || filter.since_ms.is_some_and(|since| since >= ring.complete_since_ms);
+        complete.then(|| matches.into_iter().take(limit).cloned().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(timestamp_ms: i64, trader: &str, status: &str) -> TradeRecord {
+        TradeRecord {
+            timestamp_ms,
+            block_number: 1,
+            tx_hash: format!("0x{}", timestamp_ms),
+            trader_address: trader.to_string(),
+            token_id: "tok".to_string(),
+            side: "BUY".to_string(),
+            whale_shares: 100.0,
+            whale_price: 0.5,
+            whale_usd: 50.0,
+            our_shares: Some(2.0),
+            our_price: Some(0.5),
+            our_usd: Some(1.0),
+            fill_pct: Some(100.0),
+            status: status.to_string(),
+            latency_ms: None,
+            is_live: Some(false),
+            aggregation_count: None,
+            aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
+        }
+    }
+
+    fn timestamps(trades: &[TradeRecord]) -> Vec<i64> {
+        trades.iter().map(|t| t.timestamp_ms).collect()
+    }
+
+    #[test]
+    fn test_wraps_around_keeping_newest() {
+        let recent = RecentTrades::new(3, 1_000);
+        for ts in 1_001..=1_005 {
+            recent.push(trade(ts, "0xabc", "SUCCESS"));
+        }
+        assert_eq!(recent.len(), 3);
+
+        let all = TradeFilter::default();
+        assert_eq!(timestamps(&recent.query(&all, 3).unwrap()), vec![1_005, 1_004, 1_003]);
+        assert_eq!(timestamps(&recent.query(&all, 2).unwrap()), vec![1_005, 1_004]);
+        // Still complete after the dropped 1_002
+        let since = TradeFilter { since_ms: Some(1_003), ..TradeFilter::default() };
+        assert_eq!(timestamps(&recent.query(&since, 50).unwrap()), vec![1_005, 1_004, 1_003]);
+    }
+
+    #[test]
+    fn test_falls_through_when_range_predates_buffer() {
+        let recent = RecentTrades::new(3, 1_000);
+        let all = TradeFilter::default();
+        // Nothing recorded yet: older trades may be in the DB
+        assert!(recent.query(&all, 1).is_none());
+
+        recent.push(trade(1_001, "0xabc", "SUCCESS"));
+        recent.push(trade(1_002, "abc", "FAILED"));
+        assert!(recent.query(&all, 50).is_none(), "fewer than asked, DB may hold more");
+        // A range starting after the buffer began is complete even when short
+        let since = TradeFilter { since_ms: Some(1_000), ..TradeFilter::default() };
+        assert_eq!(recent.query(&since, 50).unwrap().len(), 2);
+        let before = TradeFilter { since_ms: Some(999), ..TradeFilter::default() };
+        assert!(recent.query(&before, 50).is_none());
+
+        // Once 1_001 is dropped only ranges after it can be answered
+        recent.push(trade(1_003, "0xabc", "SUCCESS"));
+        recent.push(trade(1_004, "0xabc", "SUCCESS"));
+        assert!(recent.query(&since, 50).is_none());
+        let after_drop = TradeFilter { since_ms: Some(1_002), ..TradeFilter::default() };
+        assert_eq!(timestamps(&recent.query(&after_drop, 50).unwrap()), vec![1_004, 1_003, 1_002]);
+    }
+
+    #[test]
+    fn test_filters_match_the_store() {
+        let recent = RecentTrades::new(10, 0);
+        recent.push(trade(1, "0xABC", "SUCCESS"));
+        recent.push(trade(2, "abc", "FAILED"));
+        recent.push(trade(3, "0xdef", "SUCCESS"));
+        recent.push(TradeRecord { status: SHADOW_STATUS.to_string(), ..trade(4, "0xabc", "") });
+
+        let by_trader = TradeFilter { trader: Some("0xabc".to_string()), since_ms: Some(0), ..TradeFilter::default() };
+        assert_eq!(timestamps(&recent.query(&by_trader, 10).unwrap()), vec![2]);
+        let by_status = TradeFilter { status: Some("success".to_string()), since_ms: Some(0), ..TradeFilter::default() };
+        assert_eq!(timestamps(&recent.query(&by_status, 10).unwrap()), vec![3, 1]);
+        // SHADOW rows live in their own table, not /trades
+        assert_eq!(recent.len(), 3);
+    }
+}
@@ -0,0 +1,188 @@
+//! Startup check of DB positions against the wallet's actual holdings (RECONCILE_ON_START)
+//!
+//! Positions in the DB are summed from the trades the bot recorded, so they drift when
+//! something fills unseen (a GTD order filling after shutdown, a manual trade). The
+//! Data API reports what the wallet really holds; every token where the two disagree
+//! is a discrepancy. With RECONCILE_WRITE_ADJUSTMENTS an adjustment trade row
+//! (status RECONCILED) moves the DB position onto the on-chain balance.
+
+use crate::live_positions::LivePosition;
+use crate::persistence::{Position, TradeRecord};
+use std::collections::BTreeMap;
+
+/// Share differences at or below this are rounding, not drift
+pub const RECONCILE_TOLERANCE_SHARES: f64 = 0.01;
+
+/// Status of adjustment rows
+pub const RECONCILED_STATUS: &str = "RECONCILED";
+
+/// A token whose DB net shares differ from the wallet's holdings
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionDiscrepancy {
+    pub token_id: String,
+    /// Net shares summed from recorded trades (0 if the DB has no position)
+    pub db_shares: f64,
+    /// Shares the wallet holds (0 if the Data API has no position)
+    pub chain_shares: f64,
+    /// Average entry from the DB, else from the Data API
+    pub avg_entry_price: Option<f64>,
+    /// Market title from the Data API (empty for DB-only tokens)
+    pub title: String,
+}
+
+impl PositionDiscrepancy {
+    fn new(token_id: &str) -> Self {
+        Self { token_id: token_id.to_string(), db_shares: 0.0, chain_shares: 0.0, avg_entry_price: None, title: String::new() }
+    }
+
+    /// Shares the DB is missing (negative: the DB holds more than the wallet)
+    pub fn diff(&self) -> f64 {
+        self.chain_shares - self.db_shares
+    }
+
+    /// Trade row that moves the DB position onto the on-chain balance
+    ///
+    /// A BUY for missing shares, a SELL for surplus ones. `our_price` is left empty so the
+    /// row is not a fill for realized P&L; BUY rows carry `our_usd` at the average entry so
+    /// the position's average entry price doesn't change.
+    pub fn adjustment_record(&self, timestamp_ms: i64) -> TradeRecord {
+        let diff = self.diff();
+        let shares = diff.abs();
+        let is_buy = diff > 0.0;
+        TradeRecord {
+            timestamp_ms,
+            block_number: 0,
+            tx_hash: format!("reconcile-{}", timestamp_ms),
+            trader_address: String::new(),
+            token_id: self.token_id.clone(),
+            side: if is_buy { "BUY" } else { "SELL" }.to_string(),
+            whale_shares: 0.0,
+            whale_price: 0.0,
+            whale_usd: 0.0,
+            our_shares: Some(shares),
+            our_price: None,
+            our_usd: if is_buy { self.avg_entry_price.map(|price| shares * price) } else { None },
+            fill_pct: None,
+            status: RECONCILED_STATUS.to_string(),
+            latency_ms: None,
+            is_live: Some(true),
+            aggregation_count: None,
+            aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
+        }
+    }
+}
+
+/// Tokens where DB and wallet differ by more than `tolerance` shares, sorted by token ID
+///
+/// Data API rows are keyed by `asset` (the CTF token ID); several rows for one token are summed.
+pub fn find_discrepancies(db: &[Position], chain: &[LivePosition], tolerance: f64) -> Vec<PositionDiscrepancy> {
+    let mut tokens: BTreeMap<&str, PositionDiscrepancy> = BTreeMap::new();
+    for position in db {
+        let d = tokens.entry(&position.token_id).or_insert_with(|| PositionDiscrepancy::new(&position.token_id));
+        d.db_shares += position.net_shares;
+        d.avg_entry_price = position.avg_entry_price.or(d.avg_entry_price);
+    }
+    for held in chain.iter().filter(|p| !p.asset.is_empty()) {
+        let d = tokens.entry(&held.asset).or_insert_with(|| PositionDiscrepancy::new(&held.asset));
+        d.chain_shares += held.size;
+        if d.avg_entry_price.is_none() && held.avg_price > 0.0 {
+            d.avg_entry_price = Some(held.avg_price);
+        }
+        if d.title.is_empty() {
+            d.title = held.title.clone();
+        }
+    }
+    tokens.into_values().filter(|d| d.diff().abs() > tolerance).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::TradeStore;
+
+    fn db(token_id: &str, net_shares: f64, avg_entry_price: f64) -> Position {
+        Position { token_id: token_id.into(), net_shares, avg_entry_price: Some(avg_entry_price), trade_count: 1 }
+    }
+
+    fn chain(token_id: &str, size: f64, avg_price: f64) -> LivePosition {
+        serde_json::from_value(serde_json::json!({
+            "asset": token_id, "size": size, "avgPrice": avg_price, "title": format!("Market {}", token_id),
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_matching_positions_have_no_discrepancy() {
+        let found = find_discrepancies(
+            &[db("a", 10.0, 0.50), db("b", 5.0, 0.20)],
+            &[chain("a", 10.0, 0.50), chain("b", 5.005, 0.20)],
+            RECONCILE_TOLERANCE_SHARES,
+        );
+        assert!(found.is_empty(), "{:?}", found);
+    }
+
+    #[test]
+    fn test_discrepancies_on_either_side() {
+        let found = find_discrepancies(
+            // "a": a GTD filled after shutdown; "b": sold outside the bot; "c": closed on chain
+            &[db("a", 10.0, 0.50), db("b", 8.0, 0.30), db("c", 4.0, 0.70)],
+            &[chain("a", 25.0, 0.48), chain("b", 3.0, 0.30), chain("d", 6.0, 0.10)],
+            RECONCILE_TOLERANCE_SHARES,
+        );
+        let diffs: Vec<(&str, f64)> = found.iter().map(|d| (d.token_id.as_str(), d.diff())).collect();
+        assert_eq!(diffs, vec![("a", 15.0), ("b", -5.0), ("c", -4.0), ("d", 6.0)]);
+
+        // DB entry wins; a token only on chain takes the Data API's
+        assert_eq!(found[0].avg_entry_price, Some(0.50));
+        assert_eq!(found[3].avg_entry_price, Some(0.10));
+        assert_eq!(found[3].title, "Market d");
+        assert_eq!(found[2].title, "");
+    }
+
+    #[test]
+    fn test_adjustment_rows_move_db_onto_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TradeStore::new(dir.path().join("trades.db")).unwrap();
+        let seed = |token_id: &str, shares: f64| TradeRecord {
+            timestamp_ms: 500,
+            block_number: 1,
+            tx_hash: format!("0x{}", token_id),
+            trader_address: "0xwhale".into(),
+            token_id: token_id.into(),
+            side: "BUY".into(),
+            whale_shares: shares * 50.0,
+            whale_price: 0.50,
+            whale_usd: shares * 25.0,
+            our_shares: Some(shares),
+            our_price: Some(0.50),
+            our_usd: Some(shares * 0.50),
+            fill_pct: Some(100.0),
+            status: "SUCCESS".into(),
+            latency_ms: None,
+            is_live: Some(true),
+            aggregation_count: None,
+            aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
+        };
+        store.insert_trade(&seed("a", 10.0)).unwrap();
+        store.insert_trade(&seed("b", 8.0)).unwrap();
+
+        let found = find_discrepancies(
+            &store.get_positions().unwrap(),
+            &[chain("a", 25.0, 0.48), chain("b", 3.0, 0.50)],
+            RECONCILE_TOLERANCE_SHARES,
+        );
+        for d in &found {
+            store.insert_trade(&d.adjustment_record(1_000)).unwrap();
+        }
+
+        let positions = store.get_positions().unwrap();
+        assert!(find_discrepancies(&positions, &[chain("a", 25.0, 0.48), chain("b", 3.0, 0.50)], RECONCILE_TOLERANCE_SHARES).is_empty());
+        // The average entry is unchanged and the adjustments are not P&L fills
+        let a = positions.iter().find(|p| p.token_id == "a").unwrap();
+        assert!((a.avg_entry_price.unwrap() - 0.50).abs() < 1e-9);
+        assert_eq!(store.get_fills().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,172 @@
+//! HTTP replay of whale fills missed during WebSocket disconnects
+//!
+//! After every reconnect, the blocks from the last one seen before the drop up
+//! to the first one seen after it are queried over HTTP with `eth_getLogs`
+//! (both ends included, since either may hold fills that were never delivered).
+//! Each returned log is wrapped in the same shape as a WebSocket
+//! `eth_subscription` notification so it can be decoded by the existing event
+//! parser and fed into the normal trade handling pipeline, through the same
+//! dedup as live events.
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+use crate::settings::{MONITORED_ADDRESSES, ORDERS_FILLED_EVENT_SIGNATURE};
+
+/// Maximum block span per `eth_getLogs` request (providers reject large ranges)
+pub const MAX_BLOCKS_PER_REQUEST: u64 = 500;
+
+/// Never replay more than this many blocks (older fills are too stale to copy)
+pub const MAX_REPLAY_BLOCKS: u64 = 2000;
+
+/// Derive the HTTP JSON-RPC endpoint from the WebSocket URL
+/// (Alchemy and Chainstack serve both on the same path)
+pub fn http_url_from_wss(wss_url: &str) -> String {
+    if let Some(rest) = wss_url.strip_prefix("wss://") {
+        format!("https://{}", rest)
+    } else if let Some(rest) = wss_url.strip_prefix("ws://") {
+        format!("http://{}", rest)
+    } else {
+        wss_url.to_string()
+    }
+}
+
+/// Inclusive block range to replay after a reconnect: from the last block seen before the drop
+/// to the first block seen after it. None on the very first connection.
+pub fn reconnect_range(last_seen: Option<u64>, first_new_block: u64) -> Option<(u64, u64)> {
+    let last_seen = last_seen?;
+    (first_new_block >= last_seen).then_some((last_seen, first_new_block))
+}
+
+/// Clamp a skipped block range to the most recent `MAX_REPLAY_BLOCKS` blocks
+pub fn clamp_replay_range(from_block: u64, to_block: u64) -> Option<(u64, u64)> {
+    if from_block > to_block {
+        return None;
+    }
+    let span = to_block - from_block + 1;
+    if span > MAX_REPLAY_BLOCKS {
+        Some((to_block + 1 - MAX_REPLAY_BLOCKS, to_block))
+    } else {
+        Some((from_block, to_block))
+    }
+}
+
+/// Build an `eth_getLogs` request for OrdersFilled events in a block range
+///
+/// Uses the same topic layout as the WebSocket subscription. An empty topic
+/// filter matches all makers (client-side filtering happens in the parser).
+pub fn build_get_logs_request(from_block: u64, to_block: u64, topic_filter: &[String]) -> Value {
+    let maker_topics = if topic_filter.is_empty() {
+        Value::Null
+    } else {
+        serde_json::json!(topic_filter)
+    };
+
+    serde_json::json!({
+        "jsonrpc": "2.0", "id": 1, "method": "eth_getLogs",
+        "params": [{
+            "address": MONITORED_ADDRESSES,
+            "fromBlock": format!("0x{:x}", from_block),
+            "toBlock": format!("0x{:x}", to_block),
+            "topics": [[ORDERS_FILLED_EVENT_SIGNATURE], Value::Null, maker_topics]
+        }]
+    })
+}
+
+/// Wrap a raw log object as a WebSocket subscription notification
+pub fn wrap_log_as_ws_message(log: &Value) -> String {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eth_subscription",
+        "params": { "result": log }
+    }).to_string()
+}
+
+/// Fetch OrdersFilled logs for an inclusive block range, returned as
+/// WebSocket-shaped messages ready for `parse_event`
+pub async fn fetch_missed_logs(
+    client: &reqwest::Client,
+    rpc_url: &str,
+    from_block: u64,
+    to_block: u64,
+    topic_filter: &[String],
+) -> Result<Vec<String>> {
+    let mut messages = Vec::new();
+    let mut start = from_block;
+
+    while start <= to_block {
+        let end = (start + MAX_BLOCKS_PER_REQUEST - 1).min(to_block);
+        let body = build_get_logs_request(start, end, topic_filter);
+
+        let resp: Value = client.post(rpc_url)
+            .json(&body)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if let Some(err) = resp.get("error") {
+            return Err(anyhow!("eth_getLogs error: {}", err));
+        }
+
+        if let Some(logs) = resp["result"].as_array() {
+            messages.extend(logs.iter().map(wrap_log_as_ws_message));
+        }
+
+        start = end + 1;
+    }
+
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_url_from_wss() {
+        assert_eq!(
+            http_url_from_wss("wss://polygon-mainnet.g.alchemy.com/v2/key"),
+            "https://polygon-mainnet.g.alchemy.com/v2/key"
+        );
+        assert_eq!(http_url_from_wss("ws://localhost:8545"), "http://localhost:8545");
+        assert_eq!(http_url_from_wss("https://rpc.example"), "https://rpc.example");
+    }
+
+    #[test]
+    fn test_reconnect_range() {
+        assert_eq!(reconnect_range(None, 1000), None);
+        // Both the last seen and first new blocks are included, however small the gap
+        assert_eq!(reconnect_range(Some(1000), 1003), Some((1000, 1003)));
+        assert_eq!(reconnect_range(Some(1000), 1000), Some((1000, 1000)));
+        assert_eq!(reconnect_range(Some(1000), 990), None);
+    }
+
+    #[test]
+    fn test_clamp_replay_range() {
+        assert_eq!(clamp_replay_range(100, 200), Some((100, 200)));
+        assert_eq!(clamp_replay_range(200, 100), None);
+        assert_eq!(clamp_replay_range(0, 9999), Some((9999 + 1 - MAX_REPLAY_BLOCKS, 9999)));
+    }
+
+    #[test]
+    fn test_build_get_logs_request() {
+        let topics = vec!["0xabc".to_string()];
+        let req = build_get_logs_request(255, 256, &topics);
+        assert_eq!(req["method"], "eth_getLogs");
+        assert_eq!(req["params"][0]["fromBlock"], "0xff");
+        assert_eq!(req["params"][0]["toBlock"], "0x100");
+        assert_eq!(req["params"][0]["topics"][0][0], ORDERS_FILLED_EVENT_SIGNATURE);
+        assert_eq!(req["params"][0]["topics"][2][0], "0xabc");
+
+        let req = build_get_logs_request(1, 2, &[]);
+        assert!(req["params"][0]["topics"][2].is_null());
+    }
+
+    #[test]
+    fn test_wrap_log_as_ws_message() {
+        let log = serde_json::json!({"topics": ["0x1"], "data": "0x", "blockNumber": "0x10"});
+        let msg: Value = serde_json::from_str(&wrap_log_as_ws_message(&log)).unwrap();
+        assert_eq!(msg["params"]["result"]["blockNumber"], "0x10");
+    }
+}
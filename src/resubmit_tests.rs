@@ -44,6 +44,7 @@ fn should_resubmit_underfill(
     let rounded_size = (remaining_shares * 100.0).round() / 100.0;
     let req = ResubmitRequest {
         token_id: token_id.to_string(),
+        trader_address: "0xwhale".to_string(),
         whale_price,
         failed_price: limit_price, // Start at same price for underfills
         size: rounded_size,
@@ -73,6 +74,7 @@ fn should_resubmit_fak_failure(
 
     ResubmitRequest {
         token_id: token_id.to_string(),
+        trader_address: "0xwhale".to_string(),
         whale_price,
         failed_price: limit_price,
         size: rounded_size,
@@ -262,6 +264,7 @@ fn test_tier_8000_plus_chases_once_then_flat() {
     // 4000+ tier: buffer 0.01
     let mut req = ResubmitRequest {
         token_id: "token".into(),
+        trader_address: "0xwhale".to_string(),
         whale_price: 0.50,
         failed_price: 0.51, // Initial limit (0.50 + 0.01 buffer)
         size: 100.0,
@@ -306,6 +309,7 @@ fn test_tier_4000_to_8000_chases_once_then_flat() {
     // 4000+ tier gets 0.01 tier buffer and 0.01 resubmit buffer
     let mut req = ResubmitRequest {
         token_id: "token".into(),
+        trader_address: "0xwhale".to_string(),
         whale_price: 0.50,
         failed_price: 0.51, // Initial limit (0.50 + 0.01 tier buffer for 4000+)
         size: 100.0,
@@ -353,6 +357,7 @@ fn test_tier_small_never_chases() {
     // Verify price progression - all flat at initial price
     let mut req = ResubmitRequest {
         token_id: "token".into(),
+        trader_address: "0xwhale".to_string(),
         whale_price: 0.50,
         failed_price: 0.50, // Initial limit (no tier buffer for 1000+)
         size: 10.0,
@@ -432,6 +437,7 @@ fn test_price_ceiling_abort() {
     // Note: 8000+ with attempt 2 is flat (no increment), so stays at 0.52
     let req_at_ceiling = ResubmitRequest {
         token_id: "token".into(),
+        trader_address: "0xwhale".to_string(),
         whale_price: 0.50,
         failed_price: 0.52,
         size: 100.0,
@@ -452,6 +458,7 @@ fn test_price_ceiling_abort() {
     // Use attempt 1 which does chase for >= 4000
     let req_over_ceiling = ResubmitRequest {
         token_id: "token".into(),
+        trader_address: "0xwhale".to_string(),
         whale_price: 0.50,
         failed_price: 0.53, // At this price, chase would go to 0.54
         size: 100.0,
@@ -488,6 +495,7 @@ fn test_full_resubmit_sequence_8000_plus() {
 
     let mut req = ResubmitRequest {
         token_id: "token".into(),
+        trader_address: "0xwhale".to_string(),
         whale_price: 0.50,
         failed_price: initial_limit,
         size: 100.0,
@@ -532,6 +540,7 @@ fn test_full_resubmit_sequence_3000() {
 
     let mut req = ResubmitRequest {
         token_id: "token".into(),
+        trader_address: "0xwhale".to_string(),
         whale_price: 0.50,
         failed_price: initial_limit,
         size: 50.0,
@@ -574,6 +583,7 @@ fn test_full_resubmit_sequence_small() {
 
     let mut req = ResubmitRequest {
         token_id: "token".into(),
+        trader_address: "0xwhale".to_string(),
         whale_price: 0.50,
         failed_price: initial_limit,
         size: 10.0,
@@ -701,6 +711,7 @@ fn test_sell_orders_not_resubmitted() {
 
     let req = ResubmitRequest {
         token_id: "token".into(),
+        trader_address: "0xwhale".to_string(),
         whale_price: 0.50,
         failed_price: 0.49,
         size: 100.0,
@@ -727,6 +738,7 @@ fn test_price_clamping_near_boundaries() {
     // Test near 0.99 boundary for buys
     let req_high = ResubmitRequest {
         token_id: "token".into(),
+        trader_address: "0xwhale".to_string(),
         whale_price: 0.98,
         failed_price: 0.985,
         size: 100.0,
@@ -744,6 +756,7 @@ fn test_price_clamping_near_boundaries() {
     // Test near 0.01 boundary for sells (if ever implemented)
     let req_low = ResubmitRequest {
         token_id: "token".into(),
+        trader_address: "0xwhale".to_string(),
         whale_price: 0.02,
         failed_price: 0.015,
         size: 100.0,
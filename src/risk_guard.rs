@@ -3,8 +3,12 @@
 /// Risk management and safety guard for trade execution
 /// Provides protection against dangerous market conditions
 
+use anyhow::Context;
 use rustc_hash::FxHashMap;
-use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 // =============================================================================
 // Type Definitions
@@ -66,6 +70,8 @@ pub struct RiskGuardConfig {
     pub sequence_window: Duration,
     pub min_depth_beyond_usd: f64,
     pub trip_duration: Duration,
+    /// Number of order book levels to sum when checking depth
+    pub book_depth_levels: usize,
 }
 
 impl Default for RiskGuardConfig {
@@ -76,6 +82,7 @@ impl Default for RiskGuardConfig {
             sequence_window: Duration::from_secs(40),
             min_depth_beyond_usd: 200.0,
             trip_duration: Duration::from_secs(60 * 60 * 5), // 5 hours
+            book_depth_levels: 10,
         }
     }
 }
@@ -104,6 +111,62 @@ impl Default for TokenState {
     }
 }
 
+// =============================================================================
+// Persisted State
+// =============================================================================
+
+/// One token's guard state with wall-clock times (Unix ms) so it survives a restart
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenSnapshot {
+    /// (timestamp_ms, whale_shares) of recent large trades
+    pub large_trades: Vec<(i64, f64)>,
+    pub tripped_until_ms: Option<i64>,
+}
+
+/// Guard state written on shutdown and restored on the next start
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct RiskGuardSnapshot {
+    pub saved_at_ms: i64,
+    pub tokens: HashMap<String, TokenSnapshot>,
+}
+
+impl RiskGuardSnapshot {
+    /// Read a snapshot from a JSON file
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&raw).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Write the snapshot as JSON
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Current wall-clock time in Unix milliseconds
+fn unix_now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Wall-clock ms for an Instant, relative to a (now, now_ms) pair
+fn instant_to_ms(t: Instant, now: Instant, now_ms: i64) -> i64 {
+    match t.checked_duration_since(now) {
+        Some(ahead) => now_ms + ahead.as_millis() as i64,
+        None => now_ms - now.duration_since(t).as_millis() as i64,
+    }
+}
+
+/// Instant for a wall-clock ms, relative to a (now, now_ms) pair (None if it predates the monotonic clock)
+fn ms_to_instant(ms: i64, now: Instant, now_ms: i64) -> Option<Instant> {
+    let delta = Duration::from_millis(ms.abs_diff(now_ms));
+    if ms >= now_ms { now.checked_add(delta) } else { now.checked_sub(delta) }
+}
+
 // =============================================================================
 // Circuit Breaker
 // =============================================================================
@@ -124,8 +187,12 @@ impl RiskGuard {
     /// Hot path - no allocations if token exists
     #[inline]
     pub fn check_fast(&mut self, token_id: &str, whale_shares: f64) -> SafetyEvaluation {
-        let now = Instant::now();
-        
+        self.check_fast_at(token_id, whale_shares, Instant::now())
+    }
+
+    /// `check_fast` at a given time (backtests replay recorded timestamps)
+    #[inline]
+    pub fn check_fast_at(&mut self, token_id: &str, whale_shares: f64, now: Instant) -> SafetyEvaluation {
         // Use entry API - single lookup instead of get_mut + insert + get_mut
         let state = self.tokens.entry(token_id.to_string()).or_insert_with(TokenState::new);
         
@@ -220,6 +287,76 @@ impl RiskGuard {
         }
     }
     
+    pub fn config(&self) -> &RiskGuardConfig {
+        &self.config
+    }
+
+    /// Per-token state worth keeping: active trips and large trades still inside the sequence window
+    pub fn snapshot(&self) -> RiskGuardSnapshot {
+        self.snapshot_at(Instant::now(), unix_now_ms())
+    }
+
+    /// `snapshot` with an explicit clock (tests)
+    pub fn snapshot_at(&self, now: Instant, now_ms: i64) -> RiskGuardSnapshot {
+        let cutoff = now.checked_sub(self.config.sequence_window);
+        let tokens = self
+            .tokens
+            .iter()
+            .filter_map(|(token_id, state)| {
+                let tripped_until_ms = state
+                    .tripped_until
+                    .filter(|until| *until > now)
+                    .map(|until| instant_to_ms(until, now, now_ms));
+                let large_trades: Vec<(i64, f64)> = state
+                    .large_trades
+                    .iter()
+                    .filter(|(ts, _)| cutoff.is_none_or(|c| *ts > c))
+                    .map(|(ts, shares)| (instant_to_ms(*ts, now, now_ms), *shares))
+                    .collect();
+                if tripped_until_ms.is_none() && large_trades.is_empty() {
+                    return None;
+                }
+                Some((token_id.clone(), TokenSnapshot { large_trades, tripped_until_ms }))
+            })
+            .collect();
+
+        RiskGuardSnapshot { saved_at_ms: now_ms, tokens }
+    }
+
+    /// Load a snapshot saved within `max_age`; returns the number of tokens restored (0 if stale)
+    pub fn restore(&mut self, snapshot: &RiskGuardSnapshot, max_age: Duration) -> usize {
+        self.restore_at(snapshot, max_age, Instant::now(), unix_now_ms())
+    }
+
+    /// `restore` with an explicit clock (tests)
+    pub fn restore_at(&mut self, snapshot: &RiskGuardSnapshot, max_age: Duration, now: Instant, now_ms: i64) -> usize {
+        if now_ms.saturating_sub(snapshot.saved_at_ms) > max_age.as_millis() as i64 {
+            return 0;
+        }
+
+        let mut restored = 0;
+        for (token_id, token) in &snapshot.tokens {
+            let tripped_until = token
+                .tripped_until_ms
+                .filter(|ms| *ms > now_ms)
+                .and_then(|ms| ms_to_instant(ms, now, now_ms));
+            let large_trades: Vec<(Instant, f64)> = token
+                .large_trades
+                .iter()
+                .filter_map(|(ms, shares)| ms_to_instant(*ms, now, now_ms).map(|ts| (ts, *shares)))
+                .collect();
+            if tripped_until.is_none() && large_trades.is_empty() {
+                continue;
+            }
+
+            let state = self.tokens.entry(token_id.clone()).or_default();
+            state.tripped_until = state.tripped_until.max(tripped_until);
+            state.large_trades.extend(large_trades);
+            restored += 1;
+        }
+        restored
+    }
+    
     pub fn trip(&mut self, token_id: &str) {
         if let Some(state) = self.tokens.get_mut(token_id) {
             state.tripped_until = Some(Instant::now() + self.config.trip_duration);
@@ -354,4 +491,89 @@ mod tests {
         // 0.55 * 200 + 0.60 * 150 = 110 + 90 = 200
         assert!((depth - 200.0).abs() < 1.0);
     }
+
+    #[test]
+    fn test_snapshot_round_trip_keeps_trip_and_sequence() {
+        let config = RiskGuardConfig {
+            trip_duration: Duration::from_secs(600),
+            consecutive_trigger: 3,
+            ..Default::default()
+        };
+        let mut guard = RiskGuard::new(config.clone());
+        guard.check_with_book("tripped", 3, 50.0);
+        guard.check_fast("busy", 2000.0);
+        guard.check_fast("busy", 2000.0);
+        guard.check_fast("quiet", 100.0); // nothing worth saving
+
+        let saved_ms = 1_700_000_000_000;
+        let snapshot = guard.snapshot_at(Instant::now(), saved_ms);
+        assert_eq!(snapshot.tokens.len(), 2);
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let loaded: RiskGuardSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, snapshot);
+
+        // Restart a minute later
+        let mut restarted = RiskGuard::new(config);
+        let restored = restarted.restore_at(&loaded, Duration::from_secs(600), Instant::now(), saved_ms + 60_000);
+        assert_eq!(restored, 2);
+
+        let eval = restarted.check_fast("tripped", 100.0);
+        assert_eq!(eval.decision, SafetyDecision::Block);
+
+        // Two earlier large trades (60s ago) fall outside the default 40s window
+        let eval = restarted.check_fast("busy", 2000.0);
+        assert_eq!(eval.consecutive_large, 1);
+    }
+
+    #[test]
+    fn test_restore_keeps_sequence_within_window() {
+        let config = RiskGuardConfig { consecutive_trigger: 3, ..Default::default() };
+        let mut guard = RiskGuard::new(config.clone());
+        guard.check_fast("busy", 2000.0);
+        guard.check_fast("busy", 2000.0);
+
+        let saved_ms = 1_700_000_000_000;
+        let snapshot = guard.snapshot_at(Instant::now(), saved_ms);
+
+        let mut restarted = RiskGuard::new(config);
+        restarted.restore_at(&snapshot, Duration::from_secs(600), Instant::now(), saved_ms + 5_000);
+        let eval = restarted.check_fast("busy", 2000.0);
+        assert_eq!(eval.consecutive_large, 3);
+        assert_eq!(eval.decision, SafetyDecision::FetchBook);
+    }
+
+    #[test]
+    fn test_restore_discards_stale_snapshot() {
+        let mut guard = RiskGuard::new(RiskGuardConfig {
+            trip_duration: Duration::from_secs(60 * 60),
+            ..Default::default()
+        });
+        guard.check_with_book("tripped", 3, 50.0);
+
+        let saved_ms = 1_700_000_000_000;
+        let snapshot = guard.snapshot_at(Instant::now(), saved_ms);
+
+        // Trip is still running, but the file is older than the staleness window
+        let mut restarted = RiskGuard::new(RiskGuardConfig::default());
+        let restored = restarted.restore_at(&snapshot, Duration::from_secs(10 * 60), Instant::now(), saved_ms + 11 * 60_000);
+        assert_eq!(restored, 0);
+        assert_eq!(restarted.check_fast("tripped", 100.0).decision, SafetyDecision::Allow);
+    }
+
+    #[test]
+    fn test_restore_skips_expired_trip() {
+        let mut guard = RiskGuard::new(RiskGuardConfig {
+            trip_duration: Duration::from_secs(30),
+            ..Default::default()
+        });
+        guard.check_with_book("tripped", 3, 50.0);
+
+        let saved_ms = 1_700_000_000_000;
+        let snapshot = guard.snapshot_at(Instant::now(), saved_ms);
+
+        let mut restarted = RiskGuard::new(RiskGuardConfig::default());
+        let restored = restarted.restore_at(&snapshot, Duration::from_secs(600), Instant::now(), saved_ms + 60_000);
+        assert_eq!(restored, 0);
+    }
 }
\ No newline at end of file
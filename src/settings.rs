@@ -3,19 +3,24 @@
 
 use alloy::signers::local::PrivateKeySigner;
 use anyhow::{Context, Result};
+use chrono::{DateTime, Timelike, Utc};
+use std::collections::HashSet;
 use std::env;
 use std::path::Path;
 use std::time::Duration;
+use crate::models::SizeType;
 use crate::risk_guard;
 use crate::tennis_markets;
 use crate::soccer_markets;
 use crate::config::traders::TradersConfig;
+use crate::logging::LogFormat;
 
 // ============================================================================
 // Blockchain Constants
 // ============================================================================
 
 use once_cell::sync::Lazy;
+use rand::Rng;
 
 pub const ORDERS_FILLED_EVENT_SIGNATURE: &str =
     "0xd0a08e8c493f9c94f29311604c9de1b4e8c8d4c06bd0c789af57f2d65bfec0f6";
@@ -43,8 +48,9 @@ pub const MONITORED_ADDRESSES: [&str; 3] = [
 // API & File Constants
 // ============================================================================
 
-pub const CLOB_API_BASE: &str = "https://clob.polymarket.com";
-pub const CSV_FILE: &str = "matches_optimized.csv";
+pub const CLOB_API_BASE: &str = "https://clob.polymarket.com";  // Default for CLOB_API_BASE env
+pub const GAMMA_API_BASE: &str = "https://gamma-api.polymarket.com";  // Default for GAMMA_API_BASE env
+pub const CSV_FILE: &str = "matches_optimized.csv";  // Default for CSV_FILE env
 
 // Debug flag - set to true to print full API error messages (remove after debugging)
 pub const DEBUG_FULL_ERRORS: bool = true;
@@ -54,10 +60,12 @@ pub const DEBUG_FULL_ERRORS: bool = true;
 // ============================================================================
 
 pub const PRICE_BUFFER: f64 = 0.00;
-pub const SCALING_RATIO: f64 = 0.02;  // 2% base scaling
-pub const MIN_CASH_VALUE: f64 = 1.01;
-pub const MIN_SHARE_COUNT: f64 = 5.0;  // Polymarket minimum order size is 5 shares
+pub const SCALING_RATIO: f64 = 0.02;  // 2% base scaling (SCALING_RATIO env overrides)
+pub const MIN_CASH_VALUE: f64 = 1.01;  // Default for MIN_CASH_VALUE env
+pub const MIN_SHARE_COUNT: f64 = 5.0;  // Polymarket minimum order size is 5 shares (default for MIN_SHARE_COUNT env)
 pub const USE_PROBABILISTIC_SIZING: bool = true;
+pub const FEE_PER_TRADE_USD: f64 = 0.02;  // Estimated fee per fill (FEE_PER_TRADE_USD env overrides)
+pub const API_PRICE_TTL_SECS: u64 = 30;  // How long /positions and /pnl reuse a fetched price (API_PRICE_TTL_SECS env overrides)
 
 // Minimum whale trade size to copy (skip trades below this)
 // Note: Per-trader min_shares in traders.json takes precedence over this global default
@@ -73,19 +81,224 @@ pub fn should_skip_trade(whale_shares: f64) -> bool {
 // Timeouts
 // ============================================================================
 
-pub const ORDER_REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+pub const ORDER_REPLY_TIMEOUT: Duration = Duration::from_secs(10);  // Default for ORDER_REPLY_TIMEOUT_MS env
+/// Whale trades >= 4000 shares may run the whole resubmit chain (ORDER_REPLY_TIMEOUT_LARGE_MS env)
+pub const ORDER_REPLY_TIMEOUT_LARGE: Duration = Duration::from_secs(20);
+
+/// How long a caller waits for the order worker's reply before giving up with WORKER_TIMEOUT
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrderReplyTimeouts {
+    /// Whale trades >= 4000 shares
+    pub large: Duration,
+    /// Smaller whale trades
+    pub small: Duration,
+}
+
+impl OrderReplyTimeouts {
+    /// ORDER_REPLY_TIMEOUT_LARGE_MS / ORDER_REPLY_TIMEOUT_MS env
+    pub fn from_env() -> Result<Self> {
+        let default = Self::default();
+        let timeouts = Self {
+            large: Duration::from_millis(env_parse_limit("ORDER_REPLY_TIMEOUT_LARGE_MS", default.large.as_millis() as u64)?),
+            small: Duration::from_millis(env_parse_limit("ORDER_REPLY_TIMEOUT_MS", default.small.as_millis() as u64)?),
+        };
+        timeouts.validate()?;
+        Ok(timeouts)
+    }
+
+    /// Both timeouts must be positive
+    pub fn validate(&self) -> Result<()> {
+        if self.large.is_zero() || self.small.is_zero() {
+            anyhow::bail!("ORDER_REPLY_TIMEOUT_MS and ORDER_REPLY_TIMEOUT_LARGE_MS must be at least 1");
+        }
+        Ok(())
+    }
+
+    /// Timeout for a whale trade of `whale_shares` (same 4000-share split as the resubmit tiers)
+    #[inline]
+    pub fn for_shares(&self, whale_shares: f64) -> Duration {
+        if whale_shares >= 4000.0 { self.large } else { self.small }
+    }
+}
+
+impl Default for OrderReplyTimeouts {
+    fn default() -> Self {
+        Self { large: ORDER_REPLY_TIMEOUT_LARGE, small: ORDER_REPLY_TIMEOUT }
+    }
+}
 
 // ============================================================================
 // Resubmitter Configuration (for FAK failures)
 // ============================================================================
 
-pub const RESUBMIT_PRICE_INCREMENT: f64 = 0.01;
+pub const RESUBMIT_PRICE_INCREMENT: f64 = 0.01;  // Default for RESUBMIT_PRICE_INCREMENT env
+pub const RESUBMIT_MAX_ATTEMPTS_LARGE: u8 = 5;  // Whale trades >= 4000 shares (RESUBMIT_MAX_ATTEMPTS_LARGE env)
+pub const RESUBMIT_MAX_ATTEMPTS_SMALL: u8 = 4;  // Smaller whale trades (RESUBMIT_MAX_ATTEMPTS_SMALL env)
+/// Price tick the resubmit increment must be a multiple of
+pub const PRICE_TICK: f64 = 0.01;
 
 // Tier-based max resubmit attempts (4000+ gets 5, others get 4)
 #[inline]
 pub fn get_max_resubmit_attempts(whale_shares: f64) -> u8 {
-    if whale_shares >= 4000.0 { 5 }
-    else { 4 }
+    ResubmitPolicy::default().max_attempts(whale_shares)
+}
+
+/// Order book levels summed by the circuit breaker's depth check by default
+pub const CB_BOOK_DEPTH_LEVELS_DEFAULT: usize = 10;
+
+/// Parse CB_BOOK_DEPTH_LEVELS: a whole number of levels, at least 1 (0 would count every book as empty)
+pub fn parse_cb_book_depth_levels(value: &str) -> Result<usize> {
+    match value.trim().parse::<usize>() {
+        Ok(levels) if levels >= 1 => Ok(levels),
+        _ => anyhow::bail!("CB_BOOK_DEPTH_LEVELS must be a whole number of levels, at least 1, got {:?}", value),
+    }
+}
+
+/// Parse MAX_EXPOSURE_PER_MARKET_USD: a USD amount above 0
+pub fn parse_max_exposure_per_market_usd(value: &str) -> Result<f64> {
+    match value.trim().parse::<f64>() {
+        Ok(usd) if usd.is_finite() && usd > 0.0 => Ok(usd),
+        _ => anyhow::bail!("MAX_EXPOSURE_PER_MARKET_USD must be a USD amount above 0, got {:?}", value),
+    }
+}
+
+/// Highest FEE_RATE_BPS accepted (10%)
+pub const FEE_RATE_BPS_MAX: i64 = 1000;
+
+/// Parse FEE_RATE_BPS: whole basis points from 0 to FEE_RATE_BPS_MAX
+pub fn parse_fee_rate_bps(value: &str) -> Result<i64> {
+    match value.trim().parse::<i64>() {
+        Ok(bps) if (0..=FEE_RATE_BPS_MAX).contains(&bps) => Ok(bps),
+        _ => anyhow::bail!("FEE_RATE_BPS must be a whole number of basis points from 0 to {}, got {:?}", FEE_RATE_BPS_MAX, value),
+    }
+}
+
+/// Most trader topics sent to the WS provider by default; more switch to client-side filtering
+pub const TOPIC_FILTER_MAX_TRADERS_DEFAULT: usize = 10;
+
+/// Parse TOPIC_FILTER_MAX_TRADERS: a whole number of trader topics, at least 1
+pub fn parse_topic_filter_max_traders(value: &str) -> Result<usize> {
+    match value.trim().parse::<usize>() {
+        Ok(max) if max >= 1 => Ok(max),
+        _ => anyhow::bail!("TOPIC_FILTER_MAX_TRADERS must be a whole number of traders, at least 1, got {:?}", value),
+    }
+}
+
+/// Fill percentage at or above which a filled order counts as SUCCESS rather than PARTIAL
+pub const SUCCESS_FILL_PCT_DEFAULT: f64 = 90.0;
+
+/// Parse SUCCESS_FILL_PCT: a percentage above 0 and at most 100
+pub fn parse_success_fill_pct(value: &str) -> Result<f64> {
+    match value.trim().parse::<f64>() {
+        Ok(pct) if pct > 0.0 && pct <= 100.0 => Ok(pct),
+        _ => anyhow::bail!("SUCCESS_FILL_PCT must be a percentage above 0 and at most 100, got {:?}", value),
+    }
+}
+
+/// Validate a private key from `var` (PRIVATE_KEY or EXECUTION_PRIVATE_KEY): 64 hex characters,
+/// an optional 0x prefix and surrounding whitespace allowed. Returns the bare hex.
+pub fn parse_private_key(var: &str, value: &str) -> Result<String> {
+    let key_clean = value.trim().strip_prefix("0x").unwrap_or(value.trim());
+    if key_clean.len() != 64 {
+        anyhow::bail!(
+            "{} must be exactly 64 hex characters (found {}).\n\
+            Remove any '0x' prefix. Current value starts with: {}",
+            var,
+            key_clean.len(),
+            if key_clean.len() > 10 { format!("{}...", &key_clean[..10]) } else { key_clean.to_string() }
+        );
+    }
+    if !key_clean.chars().all(|c| c.is_ascii_hexdigit()) {
+        anyhow::bail!("{} contains invalid characters. Must be hexadecimal (0-9, a-f, A-F).", var);
+    }
+    Ok(key_clean.to_string())
+}
+
+/// Validate a funder address from `var` (FUNDER_ADDRESS or EXECUTION_FUNDER): 40 hex characters
+/// with an optional 0x prefix. Returns it trimmed.
+pub fn parse_funder_address(var: &str, value: &str) -> Result<String> {
+    let funder = value.trim().to_string();
+    let addr_clean = funder.strip_prefix("0x").unwrap_or(&funder);
+    if addr_clean.len() != 40 {
+        anyhow::bail!(
+            "{} must be exactly 40 hex characters (found {}).\n\
+            Current value: {}",
+            var,
+            addr_clean.len(),
+            if addr_clean.len() > 20 { format!("{}...", &addr_clean[..20]) } else { addr_clean.to_string() }
+        );
+    }
+    if !addr_clean.chars().all(|c| c.is_ascii_hexdigit()) {
+        anyhow::bail!("{} contains invalid characters. Must be hexadecimal (0-9, a-f, A-F).", var);
+    }
+    Ok(funder)
+}
+
+/// Checksummed address that signs orders for a validated (bare hex) private key
+pub fn derive_wallet_address(key_clean: &str) -> Result<String> {
+    let wallet: PrivateKeySigner = format!("0x{}", key_clean).parse()
+        .context("Failed to parse private key for wallet address derivation")?;
+    Ok(format!("{}", wallet.address()))
+}
+
+/// How FAK failures are chased: price step on chase attempts and attempts per whale-size tier
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResubmitPolicy {
+    pub price_increment: f64,
+    /// Attempts for whale trades >= 4000 shares (the last one is GTD)
+    pub max_attempts_large: u8,
+    /// Attempts for smaller whale trades
+    pub max_attempts_small: u8,
+}
+
+impl ResubmitPolicy {
+    /// RESUBMIT_PRICE_INCREMENT / RESUBMIT_MAX_ATTEMPTS_LARGE / RESUBMIT_MAX_ATTEMPTS_SMALL env
+    pub fn from_env() -> Result<Self> {
+        let default = Self::default();
+        let policy = Self {
+            price_increment: env_parse_limit("RESUBMIT_PRICE_INCREMENT", default.price_increment)?,
+            max_attempts_large: env_parse_limit("RESUBMIT_MAX_ATTEMPTS_LARGE", default.max_attempts_large)?,
+            max_attempts_small: env_parse_limit("RESUBMIT_MAX_ATTEMPTS_SMALL", default.max_attempts_small)?,
+        };
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    /// Increment must be a positive whole number of ticks below 1.0; each tier needs at least one attempt
+    pub fn validate(&self) -> Result<()> {
+        let ticks = self.price_increment / PRICE_TICK;
+        if !(self.price_increment > 0.0 && self.price_increment < 1.0) || (ticks - ticks.round()).abs() > 1e-9 {
+            anyhow::bail!(
+                "RESUBMIT_PRICE_INCREMENT must be a positive multiple of the {} tick below 1.0, got {}",
+                PRICE_TICK, self.price_increment
+            );
+        }
+        if self.max_attempts_large == 0 || self.max_attempts_small == 0 {
+            anyhow::bail!("RESUBMIT_MAX_ATTEMPTS_LARGE and RESUBMIT_MAX_ATTEMPTS_SMALL must be at least 1");
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn max_attempts(&self, whale_shares: f64) -> u8 {
+        if whale_shares >= 4000.0 { self.max_attempts_large } else { self.max_attempts_small }
+    }
+
+    /// Price step for this attempt (0 on flat retries, see should_increment_price)
+    #[inline]
+    pub fn increment(&self, whale_shares: f64, attempt: u8) -> f64 {
+        if should_increment_price(whale_shares, attempt) { self.price_increment } else { 0.0 }
+    }
+}
+
+impl Default for ResubmitPolicy {
+    fn default() -> Self {
+        Self {
+            price_increment: RESUBMIT_PRICE_INCREMENT,
+            max_attempts_large: RESUBMIT_MAX_ATTEMPTS_LARGE,
+            max_attempts_small: RESUBMIT_MAX_ATTEMPTS_SMALL,
+        }
+    }
 }
 
 /// Returns true if this attempt should increment price, false for flat retry
@@ -100,10 +313,54 @@ pub fn should_increment_price(whale_shares: f64, attempt: u8) -> bool {
     }
 }
 
-#[inline]
-pub fn get_gtd_expiry_secs(is_live: bool) -> u64 {
-    if is_live { 61 }    
-    else { 1800 }        
+/// Polymarket rejects GTD expirations less than a minute out; one extra second covers clock skew
+pub const GTD_EXPIRY_MIN_SECS: u64 = 61;
+/// Longest GTD expiry accepted from config (30 days)
+pub const GTD_EXPIRY_MAX_SECS: u64 = 30 * 86_400;
+
+/// How long GTD orders rest on the book, by market liveness
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GtdExpiry {
+    /// Live (in-play) markets move fast: expire almost immediately
+    pub live_secs: u64,
+    /// Everything else
+    pub default_secs: u64,
+}
+
+impl GtdExpiry {
+    /// GTD_EXPIRY_LIVE_SECS / GTD_EXPIRY_SECS env
+    pub fn from_env() -> Result<Self> {
+        let default = Self::default();
+        let expiry = Self {
+            live_secs: env_parse_limit("GTD_EXPIRY_LIVE_SECS", default.live_secs)?,
+            default_secs: env_parse_limit("GTD_EXPIRY_SECS", default.default_secs)?,
+        };
+        expiry.validate()?;
+        Ok(expiry)
+    }
+
+    /// Both values within GTD_EXPIRY_MIN_SECS..=GTD_EXPIRY_MAX_SECS
+    pub fn validate(&self) -> Result<()> {
+        let range = GTD_EXPIRY_MIN_SECS..=GTD_EXPIRY_MAX_SECS;
+        if !range.contains(&self.live_secs) || !range.contains(&self.default_secs) {
+            anyhow::bail!(
+                "GTD_EXPIRY_LIVE_SECS and GTD_EXPIRY_SECS must be between {} and {}, got {} and {}",
+                GTD_EXPIRY_MIN_SECS, GTD_EXPIRY_MAX_SECS, self.live_secs, self.default_secs
+            );
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn secs(&self, is_live: bool) -> u64 {
+        if is_live { self.live_secs } else { self.default_secs }
+    }
+}
+
+impl Default for GtdExpiry {
+    fn default() -> Self {
+        Self { live_secs: 61, default_secs: 1800 }
+    }
 }
 
 // Tier-based max buffer for resubmits (on top of initial tier buffer)
@@ -118,12 +375,90 @@ pub fn get_resubmit_max_buffer(whale_shares: f64) -> f64 {
 pub const BOOK_REQ_TIMEOUT: Duration = Duration::from_millis(2500);
 pub const WS_PING_TIMEOUT: Duration = Duration::from_secs(300);
 pub const WS_RECONNECT_DELAY: Duration = Duration::from_secs(3);
+pub const WS_RECONNECT_MAX_DELAY: Duration = Duration::from_secs(60);
+pub const WS_RECONNECT_JITTER_MS: u64 = 1000;
+
+/// Exponential reconnect backoff: min(WS_RECONNECT_DELAY * 2^attempt, WS_RECONNECT_MAX_DELAY)
+/// Jitter is added by the caller so this stays deterministic
+#[inline]
+pub fn next_backoff(attempt: u32) -> Duration {
+    let factor = 1u32.checked_shl(attempt).unwrap_or(u32::MAX);
+    WS_RECONNECT_DELAY
+        .checked_mul(factor)
+        .unwrap_or(WS_RECONNECT_MAX_DELAY)
+        .min(WS_RECONNECT_MAX_DELAY)
+}
+
+/// WebSocket endpoints in failover order, and the one currently in use
+#[derive(Debug, Clone)]
+pub struct WsEndpoints {
+    urls: Vec<String>,
+    active: usize,
+}
+
+impl WsEndpoints {
+    /// Starts on the first URL; `urls` must not be empty (Config guarantees at least one)
+    pub fn new(urls: Vec<String>) -> Self {
+        assert!(!urls.is_empty(), "at least one WebSocket URL is required");
+        Self { urls, active: 0 }
+    }
+
+    pub fn active(&self) -> &str {
+        &self.urls[self.active]
+    }
+
+    /// Number of configured endpoints
+    pub fn count(&self) -> usize {
+        self.urls.len()
+    }
+
+    /// Switch to the next endpoint, wrapping around after the last
+    pub fn rotate(&mut self) -> &str {
+        self.active = (self.active + 1) % self.urls.len();
+        self.active()
+    }
+
+    /// "#2 host" for logs; provider keys live in the URL path so they stay out of the output
+    pub fn active_label(&self) -> String {
+        let url = self.active();
+        let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+        let host = rest.split(['/', '?']).next().unwrap_or(rest);
+        format!("#{} {}", self.active + 1, host)
+    }
+
+    /// Wait before reconnecting after `failures` consecutive failures (this one included)
+    /// None = fail over straight away; backoff only applies once every endpoint has failed this round
+    pub fn reconnect_delay(&self, failures: u32) -> Option<Duration> {
+        let count = self.urls.len() as u32;
+        if !failures.is_multiple_of(count) {
+            return None;
+        }
+        Some(next_backoff((failures / count).saturating_sub(1)))
+    }
+}
+
+/// Parse WSS_URLS: comma-separated ws:// or wss:// URLs (blank entries ignored)
+pub fn parse_ws_urls(raw: &str) -> Result<Vec<String>> {
+    let urls: Vec<String> = raw
+        .split(',')
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(str::to_string)
+        .collect();
+    if urls.is_empty() {
+        anyhow::bail!("no WebSocket URLs given");
+    }
+    if let Some(bad) = urls.iter().find(|url| !url.starts_with("wss://") && !url.starts_with("ws://")) {
+        anyhow::bail!("'{}' is not a ws:// or wss:// URL", bad);
+    }
+    Ok(urls)
+}
 
 // ============================================================================
 // Execution Tiers
 // ============================================================================
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct ExecutionTier {
     pub min_shares: f64,
     pub price_buffer: f64,
@@ -152,31 +487,916 @@ pub const EXECUTION_TIERS: [ExecutionTier; 3] = [
     },
 ];
 
-/// Get tier params for a given trade size
-/// Returns (buffer, order_action, size_multiplier)
-#[inline]
-pub fn get_tier_params(whale_shares: f64, side_is_buy: bool, token_id: &str) -> (f64, &'static str, f64) {
-    if !side_is_buy {
-        return (PRICE_BUFFER, "GTD", 1.0);
+/// One EXECUTION_TIERS entry as written in the env
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExecutionTierEntry {
+    min_shares: f64,
+    buffer: f64,
+    order_type: String,
+    size_multiplier: f64,
+}
+
+/// BUY execution tiers, largest `min_shares` first; the first tier the whale trade reaches applies
+///
+/// Trades below the smallest tier use PRICE_BUFFER, FAK and a 1.0x multiplier.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionTiers(Vec<ExecutionTier>);
+
+impl ExecutionTiers {
+    /// EXECUTION_TIERS env (JSON), else the built-in table
+    pub fn from_env() -> Result<Self> {
+        match env_non_empty("EXECUTION_TIERS") {
+            Some(raw) => Self::parse(&raw),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Parse a JSON array of `{"min_shares", "buffer", "order_type", "size_multiplier"}`
+    pub fn parse(raw: &str) -> Result<Self> {
+        let entries: Vec<ExecutionTierEntry> = serde_json::from_str(raw).context(
+            "EXECUTION_TIERS must be a JSON array of {\"min_shares\", \"buffer\", \"order_type\", \"size_multiplier\"}",
+        )?;
+        let tiers = entries
+            .into_iter()
+            .map(|entry| {
+                let order_action = match entry.order_type.trim().to_uppercase().as_str() {
+                    "FAK" => "FAK",
+                    "FOK" => "FOK",
+                    _ => anyhow::bail!("EXECUTION_TIERS order_type must be FAK or FOK, got '{}'", entry.order_type),
+                };
+                Ok(ExecutionTier {
+                    min_shares: entry.min_shares,
+                    price_buffer: entry.buffer,
+                    order_action,
+                    size_multiplier: entry.size_multiplier,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let tiers = Self(tiers);
+        tiers.validate()?;
+        Ok(tiers)
+    }
+
+    /// At least one tier, sorted by strictly decreasing `min_shares`, with sane values
+    pub fn validate(&self) -> Result<()> {
+        if self.0.is_empty() {
+            anyhow::bail!("EXECUTION_TIERS must have at least one tier");
+        }
+        for tier in &self.0 {
+            if !(tier.min_shares.is_finite() && tier.min_shares > 0.0) {
+                anyhow::bail!("EXECUTION_TIERS min_shares must be positive, got {}", tier.min_shares);
+            }
+            if !(tier.price_buffer.is_finite() && (0.0..1.0).contains(&tier.price_buffer)) {
+                anyhow::bail!("EXECUTION_TIERS buffer must be at least 0 and below 1, got {}", tier.price_buffer);
+            }
+            if !(tier.size_multiplier.is_finite() && tier.size_multiplier > 0.0) {
+                anyhow::bail!("EXECUTION_TIERS size_multiplier must be positive, got {}", tier.size_multiplier);
+            }
+        }
+        if let Some(pair) = self.0.windows(2).find(|pair| pair[0].min_shares <= pair[1].min_shares) {
+            anyhow::bail!(
+                "EXECUTION_TIERS must be sorted by min_shares, largest first, without duplicates ({} before {})",
+                pair[0].min_shares, pair[1].min_shares
+            );
+        }
+        Ok(())
+    }
+
+    /// Tier for a whale trade of `whale_shares` (None below the smallest tier)
+    #[inline]
+    pub fn lookup(&self, whale_shares: f64) -> Option<&ExecutionTier> {
+        self.0.iter().find(|tier| whale_shares >= tier.min_shares)
+    }
+
+    pub fn tiers(&self) -> &[ExecutionTier] {
+        &self.0
+    }
+}
+
+impl Default for ExecutionTiers {
+    fn default() -> Self {
+        Self(EXECUTION_TIERS.to_vec())
+    }
+}
+
+/// Get tier params for a given trade size from the built-in tiers
+/// Returns (buffer, order_action, size_multiplier)
+#[inline]
+pub fn get_tier_params(whale_shares: f64, side_is_buy: bool, token_id: &str) -> (f64, &'static str, f64) {
+    get_tier_params_with_fok(whale_shares, side_is_buy, token_id, None, &ExecutionTiers::default())
+}
+
+/// Get tier params from `tiers`, switching BUY tiers at or above `fok_min_shares` from FAK to FOK
+///
+/// FAK (Fill-And-Kill) takes whatever liquidity is available at our limit and cancels
+/// the rest, so partial fills are resubmitted for the remainder. FOK (Fill-Or-Kill)
+/// fills the whole order at our limit or nothing at all; a kill leaves no remainder,
+/// so FOK orders are never resubmitted. SELLs always use GTD.
+#[inline]
+pub fn get_tier_params_with_fok(
+    whale_shares: f64,
+    side_is_buy: bool,
+    token_id: &str,
+    fok_min_shares: Option<f64>,
+    tiers: &ExecutionTiers,
+) -> (f64, &'static str, f64) {
+    if !side_is_buy {
+        return (PRICE_BUFFER, "GTD", 1.0);
+    }
+
+    // Small buys below every tier use FAK (Fill and Kill)
+    let (base_buffer, order_action, size_multiplier) = tiers
+        .lookup(whale_shares)
+        .map_or((PRICE_BUFFER, "FAK", 1.0), |tier| (tier.price_buffer, tier.order_action, tier.size_multiplier));
+
+    // Apply sport-specific price adjustments
+    let tennis_buffer = tennis_markets::get_tennis_token_buffer(token_id);
+    let soccer_buffer = soccer_markets::get_soccer_token_buffer(token_id);
+    let total_buffer = base_buffer + tennis_buffer + soccer_buffer;
+
+    let order_action = match fok_min_shares {
+        Some(min) if whale_shares >= min => "FOK",
+        _ => order_action,
+    };
+
+    (total_buffer, order_action, size_multiplier)
+}
+
+/// Order type of POST_ONLY copies: rests on the book until filled or expired
+pub const POST_ONLY_ORDER_ACTION: &str = "GTD";
+
+/// Tier params for a POST_ONLY copy: no buffer (the order rests at the whale's price) and a GTD
+///
+/// The tier's size multiplier still applies; its buffer, order type and the sport buffers don't.
+#[inline]
+pub fn post_only_params(tier_params: (f64, &'static str, f64)) -> (f64, &'static str, f64) {
+    let (_, _, size_multiplier) = tier_params;
+    (0.0, POST_ONLY_ORDER_ACTION, size_multiplier)
+}
+
+/// How a copy scaled below the minimum order size is handled (SIZING_STRATEGY)
+///
+/// Targets at or above the floor are always sized exactly; only sub-floor targets differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SizingStrategy {
+    /// Round up to the floor with probability target/floor, else skip
+    #[default]
+    Probabilistic,
+    /// Always round up to the floor
+    RoundUp,
+    /// Always skip
+    Skip,
+    /// Round up at half the floor or more, else skip
+    Nearest,
+}
+
+impl SizingStrategy {
+    /// "probabilistic", "round_up", "skip" or "nearest" (case-insensitive, '-' or '_')
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "probabilistic" => Some(Self::Probabilistic),
+            "round_up" | "up" => Some(Self::RoundUp),
+            "skip" => Some(Self::Skip),
+            "nearest" => Some(Self::Nearest),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Probabilistic => "probabilistic",
+            Self::RoundUp => "round_up",
+            Self::Skip => "skip",
+            Self::Nearest => "nearest",
+        }
+    }
+}
+
+/// Smallest order we place (MIN_SHARE_COUNT shares and MIN_CASH_VALUE USD)
+/// and how copies below it are handled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SizeFloor {
+    pub min_shares: f64,
+    pub min_cash: f64,
+    /// Rule for sub-floor targets
+    pub strategy: SizingStrategy,
+}
+
+impl SizeFloor {
+    /// MIN_SHARE_COUNT / MIN_CASH_VALUE / SIZING_STRATEGY env; minimums must be positive numbers
+    pub fn from_env() -> Result<Self> {
+        let default = Self::default();
+        let strategy = match env_non_empty("SIZING_STRATEGY") {
+            Some(value) => SizingStrategy::parse(&value).ok_or_else(|| anyhow::anyhow!(
+                "SIZING_STRATEGY must be probabilistic, round_up, skip or nearest, got {:?}", value
+            ))?,
+            None => default.strategy,
+        };
+        Ok(Self {
+            min_shares: positive_limit("MIN_SHARE_COUNT", env_parse_limit("MIN_SHARE_COUNT", default.min_shares)?)?,
+            min_cash: positive_limit("MIN_CASH_VALUE", env_parse_limit("MIN_CASH_VALUE", default.min_cash)?)?,
+            strategy,
+        })
+    }
+
+    /// Apply a per-trader probabilistic_sizing override: true is probabilistic, false always
+    /// rounds up (None keeps this floor's strategy)
+    pub fn with_probabilistic(self, probabilistic: Option<bool>) -> Self {
+        let strategy = match probabilistic {
+            Some(true) => SizingStrategy::Probabilistic,
+            Some(false) => SizingStrategy::RoundUp,
+            None => self.strategy,
+        };
+        Self { strategy, ..self }
+    }
+
+    /// Shares needed at `price` to meet both minimums
+    pub fn required_shares(&self, price: f64) -> f64 {
+        (self.min_cash / price.max(0.0001)).max(self.min_shares)
+    }
+}
+
+impl Default for SizeFloor {
+    fn default() -> Self {
+        let strategy = if USE_PROBABILISTIC_SIZING { SizingStrategy::Probabilistic } else { SizingStrategy::RoundUp };
+        Self { min_shares: MIN_SHARE_COUNT, min_cash: MIN_CASH_VALUE, strategy }
+    }
+}
+
+/// Scaling multipliers for whale BUYs by whether the whale is opening or adding to a position
+///
+/// Whether the whale already held the token is only known from fills seen this session,
+/// so the first BUY after startup always counts as an open.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EntryScaling {
+    /// Multiplier when the whale had no position in the token (1.0 = unchanged)
+    pub scale_on_open: f64,
+    /// Multiplier when the whale already held the token (1.0 = unchanged, 0 = skip adds)
+    pub scale_on_add: f64,
+}
+
+impl EntryScaling {
+    /// SCALE_ON_OPEN / SCALE_ON_ADD env (default 1.0 each)
+    pub fn from_env() -> Result<Self> {
+        let scaling = Self {
+            scale_on_open: env_parse_limit("SCALE_ON_OPEN", 1.0)?,
+            scale_on_add: env_parse_limit("SCALE_ON_ADD", 1.0)?,
+        };
+        if !(scaling.scale_on_open >= 0.0 && scaling.scale_on_add >= 0.0) {
+            anyhow::bail!(
+                "SCALE_ON_OPEN and SCALE_ON_ADD must not be negative, got {} and {}",
+                scaling.scale_on_open, scaling.scale_on_add
+            );
+        }
+        Ok(scaling)
+    }
+
+    /// Off unless a multiplier differs from 1.0 (whale holdings aren't tracked then)
+    pub fn is_enabled(&self) -> bool {
+        self.scale_on_open != 1.0 || self.scale_on_add != 1.0
+    }
+
+    /// Multiplier for a whale BUY, given the shares the whale held before it
+    pub fn multiplier(&self, whale_prior_shares: f64) -> f64 {
+        if whale_prior_shares > 0.0 { self.scale_on_add } else { self.scale_on_open }
+    }
+}
+
+impl Default for EntryScaling {
+    fn default() -> Self {
+        Self { scale_on_open: 1.0, scale_on_add: 1.0 }
+    }
+}
+
+/// Band of whale fill prices worth copying; fills outside it are skipped with SKIPPED_PRICE_RANGE
+/// Bounds are inclusive; None on either side means no bound
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CopyPriceRange {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl CopyPriceRange {
+    /// MIN_COPY_PRICE / MAX_COPY_PRICE env (unset = no bound)
+    pub fn from_env() -> Result<Self> {
+        let bound = |key: &str| env_non_empty(key)
+            .map(|v| v.parse::<f64>().with_context(|| format!("Invalid {}: {}", key, v)))
+            .transpose();
+        let range = Self { min: bound("MIN_COPY_PRICE")?, max: bound("MAX_COPY_PRICE")? };
+        range.validate().map_err(|e| anyhow::anyhow!("MIN_COPY_PRICE / MAX_COPY_PRICE: {}", e))?;
+        Ok(range)
+    }
+
+    /// Each bound must be a price strictly between 0 and 1, and min must not exceed max
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        for bound in [self.min, self.max].into_iter().flatten() {
+            if !(bound > 0.0 && bound < 1.0) {
+                return Err(format!("copy price bound {} must be greater than 0 and less than 1", bound));
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min, self.max)
+            && min > max
+        {
+            return Err(format!("min copy price {} is above max copy price {}", min, max));
+        }
+        Ok(())
+    }
+
+    /// Apply a per-trader override; each bound the trader sets replaces the global one
+    pub fn with_trader(self, trader: CopyPriceRange) -> Self {
+        Self { min: trader.min.or(self.min), max: trader.max.or(self.max) }
+    }
+
+    pub fn contains(&self, price: f64) -> bool {
+        self.min.is_none_or(|min| price >= min) && self.max.is_none_or(|max| price <= max)
+    }
+}
+
+impl std::fmt::Display for CopyPriceRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.2}-{:.2}", self.min.unwrap_or(0.0), self.max.unwrap_or(1.0))
+    }
+}
+
+/// Polymarket REST base URLs (CLOB for books/orders, Gamma for market metadata)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiEndpoints {
+    pub clob: String,
+    pub gamma: String,
+}
+
+impl ApiEndpoints {
+    /// CLOB_API_BASE / GAMMA_API_BASE env (e.g. a staging host or proxy); trailing slashes are dropped
+    pub fn from_env() -> Self {
+        let base = |key: &str, default: &str| {
+            env_non_empty(key).unwrap_or_else(|| default.to_string()).trim_end_matches('/').to_string()
+        };
+        Self {
+            clob: base("CLOB_API_BASE", CLOB_API_BASE),
+            gamma: base("GAMMA_API_BASE", GAMMA_API_BASE),
+        }
+    }
+}
+
+impl Default for ApiEndpoints {
+    fn default() -> Self {
+        Self { clob: CLOB_API_BASE.to_string(), gamma: GAMMA_API_BASE.to_string() }
+    }
+}
+
+/// Order size that replaces scaling the whale (SIZING_MODE=kelly / flat)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SizeTarget {
+    /// Fractional-Kelly shares from the bankroll; 0 or less means no edge, no bet
+    Kelly(f64),
+    /// Fixed USD per trade, bought at our limit price whatever the whale's size (FLAT_USD)
+    FlatUsd(f64),
+}
+
+/// Everything `calculate_safe_size` needs to size one whale trade
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SizingInput {
+    pub whale_shares: f64,
+    pub scaling_ratio: f64,
+    /// Our limit price
+    pub price: f64,
+    /// Tier multiplier from `get_tier_params`
+    pub size_multiplier: f64,
+    /// Portfolio cap in shares (MAX_BET_SHARES / MAX_BET_PORTFOLIO_PERCENT)
+    pub max_bet_shares: Option<f64>,
+    /// Per-trader `max_bet_usd` in shares at `price`
+    pub trader_max_shares: Option<f64>,
+    /// Kelly or flat-USD target replacing whale scaling
+    pub target: Option<SizeTarget>,
+    pub floor: SizeFloor,
+}
+
+impl SizingInput {
+    /// Scale `whale_shares` by `scaling_ratio` at `price`: multiplier 1, no caps or target, default floor
+    pub fn new(whale_shares: f64, scaling_ratio: f64, price: f64) -> Self {
+        Self {
+            whale_shares,
+            scaling_ratio,
+            price,
+            size_multiplier: 1.0,
+            max_bet_shares: None,
+            trader_max_shares: None,
+            target: None,
+            floor: SizeFloor::default(),
+        }
+    }
+}
+
+/// Our order size for a whale trade and which rule decided it
+///
+/// Scales the whale (or takes the Kelly / flat target), applies the portfolio and per-trader caps,
+/// and below the `floor` minimum rounds up or skips according to `floor.strategy`.
+pub fn calculate_safe_size(input: SizingInput) -> (f64, SizeType) {
+    calculate_safe_size_with_rng(input, &mut rand::thread_rng())
+}
+
+/// `calculate_safe_size` drawing the probabilistic round-up from `rng` (seed it for reproducible sizing)
+pub fn calculate_safe_size_with_rng<R: Rng + ?Sized>(input: SizingInput, rng: &mut R) -> (f64, SizeType) {
+    let SizingInput { whale_shares, scaling_ratio, price, size_multiplier, max_bet_shares, trader_max_shares, target, floor } = input;
+    // Kelly (from bankroll) or flat USD target replaces the whale-scaled target; no edge means no bet
+    let (target_scaled, uncapped_type) = match target {
+        Some(SizeTarget::Kelly(shares)) if shares <= 0.0 => return (0.0, SizeType::Kelly),
+        Some(SizeTarget::Kelly(shares)) => (shares, SizeType::Kelly),
+        Some(SizeTarget::FlatUsd(usd)) => (usd / price.max(0.01), SizeType::Flat),
+        None => (whale_shares * scaling_ratio * size_multiplier, SizeType::Scaled),
+    };
+    let required_floor = floor.required_shares(price);
+
+    // Apply portfolio-based cap if configured
+    let portfolio_capped = match max_bet_shares {
+        Some(max) if max > 0.0 && target_scaled > max => max,
+        _ => target_scaled,
+    };
+
+    // Apply per-trader cap on top of the portfolio cap
+    let target_capped = match trader_max_shares {
+        Some(max) if max > 0.0 && portfolio_capped > max => max,
+        _ => portfolio_capped,
+    };
+
+    if target_capped >= required_floor {
+        // If we capped the size, indicate which cap was binding
+        if target_capped < portfolio_capped {
+            return (target_capped, SizeType::CappedByTrader);
+        }
+        if max_bet_shares.is_some() && target_scaled > target_capped {
+            return (target_capped, SizeType::Capped);
+        }
+        return (target_capped, uncapped_type);
+    }
+
+    // Rounding up to the floor would break the trader's max_bet_usd
+    if target_capped < portfolio_capped {
+        return (0.0, SizeType::CappedByTrader);
+    }
+
+    round_to_floor(target_capped, required_floor, uncapped_type, floor.strategy, rng)
+}
+
+/// Size a target below the floor: the floor itself or nothing, per `strategy`
+fn round_to_floor<R: Rng + ?Sized>(
+    target: f64,
+    required_floor: f64,
+    uncapped_type: SizeType,
+    strategy: SizingStrategy,
+    rng: &mut R,
+) -> (f64, SizeType) {
+    let fraction = target / required_floor;
+    match strategy {
+        SizingStrategy::Probabilistic => {
+            let pct = (fraction * 100.0) as u8;
+            if rng.r#gen::<f64>() < fraction {
+                (required_floor, SizeType::ProbHit(pct))
+            } else {
+                (0.0, SizeType::ProbSkip(pct))
+            }
+        }
+        SizingStrategy::RoundUp => (required_floor, uncapped_type),
+        SizingStrategy::Skip => (0.0, SizeType::BelowFloor),
+        SizingStrategy::Nearest if fraction >= 0.5 => (required_floor, uncapped_type),
+        SizingStrategy::Nearest => (0.0, SizeType::BelowFloor),
+    }
+}
+
+/// Maximum adverse distance between our limit price and the whale's price
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MaxSlippage {
+    /// Absolute price difference (e.g. 0.02)
+    Absolute(f64),
+    /// Percent of the whale price (e.g. 5.0 = 5%)
+    Percent(f64),
+}
+
+impl MaxSlippage {
+    /// Parse "0.02" (absolute) or "5%" (percent of whale price)
+    pub fn parse(value: &str) -> Result<Self> {
+        let trimmed = value.trim();
+        let slippage = match trimmed.strip_suffix('%') {
+            Some(pct) => pct.trim().parse().ok().map(MaxSlippage::Percent),
+            None => trimmed.parse().ok().map(MaxSlippage::Absolute),
+        };
+        match slippage {
+            Some(max @ (MaxSlippage::Absolute(v) | MaxSlippage::Percent(v))) if v.is_finite() && v >= 0.0 => Ok(max),
+            _ => anyhow::bail!("MAX_SLIPPAGE must be a non-negative price (e.g. 0.02) or percent (e.g. 5%), got {:?}", value),
+        }
+    }
+
+    /// True if the limit price is worse than the whale price by more than the maximum
+    /// (BUY: paying more, SELL: receiving less)
+    pub fn is_exceeded(&self, whale_price: f64, limit_price: f64, side_is_buy: bool) -> bool {
+        let adverse = if side_is_buy { limit_price - whale_price } else { whale_price - limit_price };
+        let max = match *self {
+            MaxSlippage::Absolute(v) => v,
+            MaxSlippage::Percent(pct) => whale_price * pct / 100.0,
+        };
+        // Epsilon so a limit exactly at the boundary (0.51 vs 0.50, max 0.01) passes
+        adverse > max + 1e-9
+    }
+}
+
+impl std::fmt::Display for MaxSlippage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MaxSlippage::Absolute(v) => write!(f, "{:.2}", v),
+            MaxSlippage::Percent(v) => write!(f, "{}%", v),
+        }
+    }
+}
+
+/// MAX_BET_PORTFOLIO_PERCENT applied with SIZING_MODE=kelly when it isn't set (5%)
+pub const KELLY_MAX_BET_PORTFOLIO_PERCENT_DEFAULT: f64 = 0.05;
+
+/// Fractional-Kelly BUY sizing (SIZING_MODE=kelly)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KellySizing {
+    /// Estimated edge per trade: our win probability minus the price paid (KELLY_EDGE)
+    pub edge: f64,
+    /// Fraction of full Kelly to stake (KELLY_FRACTION)
+    pub fraction: f64,
+}
+
+impl KellySizing {
+    pub const DEFAULT: Self = Self { edge: 0.02, fraction: 0.25 };
+
+    /// Read the edge and fraction from `edge_key` / `fraction_key`, falling back to `default`
+    /// Edge must be in [0, 1) and fraction in (0, 1]; anything else fails startup.
+    fn from_env(edge_key: &str, fraction_key: &str, default: Self) -> Result<Self> {
+        let edge: f64 = env_parse_limit(edge_key, default.edge)?;
+        if !(0.0..1.0).contains(&edge) {
+            anyhow::bail!("{} must be at least 0 and below 1, got {}", edge_key, edge);
+        }
+        let fraction: f64 = env_parse_limit(fraction_key, default.fraction)?;
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            anyhow::bail!("{} must be in (0, 1], got {}", fraction_key, fraction);
+        }
+        Ok(Self { edge, fraction })
+    }
+
+    /// Fraction of bankroll to stake on a BUY at `price`
+    /// Full Kelly for a share paying 1 with win probability p = price + edge (at most 1) is
+    /// (p - price) / (1 - price); scaled by `fraction` and capped at the whole bankroll. Zero when there is no edge.
+    pub fn bankroll_fraction(&self, price: f64) -> f64 {
+        if !(self.edge > 0.0 && self.fraction > 0.0) {
+            return 0.0;
+        }
+        let price = price.clamp(0.01, 0.99);
+        let win_probability = (price + self.edge).min(1.0);
+        (self.fraction * (win_probability - price) / (1.0 - price)).min(1.0)
+    }
+
+    /// Shares to buy at `price` for the given bankroll
+    pub fn target_shares(&self, bankroll_usd: f64, price: f64) -> f64 {
+        bankroll_usd.max(0.0) * self.bankroll_fraction(price) / price.max(0.01)
+    }
+}
+
+/// How BUYs are sized (SIZING_MODE)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SizingMode {
+    /// A share of the whale's size (unset or `scaled`)
+    Scaled,
+    /// Fraction of the bankroll (`kelly` with KELLY_EDGE / KELLY_FRACTION)
+    Kelly(KellySizing),
+    /// Fixed USD per copied trade (`flat` with FLAT_USD)
+    Flat(f64),
+}
+
+impl SizingMode {
+    /// SIZING_MODE is scaled (default), kelly or flat; any other value fails startup
+    pub fn from_env() -> Result<Self> {
+        let Some(mode) = env_non_empty("SIZING_MODE") else {
+            return Ok(Self::Scaled);
+        };
+        match mode.to_ascii_lowercase().as_str() {
+            "scaled" => Ok(Self::Scaled),
+            "kelly" => Ok(Self::Kelly(KellySizing::from_env("KELLY_EDGE", "KELLY_FRACTION", KellySizing::DEFAULT)?)),
+            "flat" => {
+                let usd: f64 = env_parse_limit("FLAT_USD", 0.0)?;
+                if !(usd.is_finite() && usd > 0.0) {
+                    anyhow::bail!("SIZING_MODE=flat requires FLAT_USD greater than 0 (got {})", usd);
+                }
+                Ok(Self::Flat(usd))
+            }
+            _ => anyhow::bail!("SIZING_MODE must be scaled, kelly or flat, got {}", mode),
+        }
+    }
+}
+
+/// Win-rate-weighted BUY sizing (CONFIDENCE_SCALING=true)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfidenceScaling {
+    /// Smallest factor applied, for traders who mostly lose (CONFIDENCE_MIN_FACTOR)
+    pub min_factor: f64,
+    /// Largest factor applied, for traders who mostly win (CONFIDENCE_MAX_FACTOR)
+    pub max_factor: f64,
+    /// Closed trades needed before the win rate counts (CONFIDENCE_MIN_SAMPLES)
+    pub min_samples: u32,
+}
+
+impl ConfidenceScaling {
+    /// CONFIDENCE_SCALING=true enables it; bounds default to 0.5-1.5 after 10 closes
+    pub fn from_env() -> Result<Option<Self>> {
+        if !env_parse_bool("CONFIDENCE_SCALING", false) {
+            return Ok(None);
+        }
+        let scaling = Self {
+            min_factor: env_parse_limit("CONFIDENCE_MIN_FACTOR", 0.5)?,
+            max_factor: env_parse_limit("CONFIDENCE_MAX_FACTOR", 1.5)?,
+            min_samples: env_parse_limit("CONFIDENCE_MIN_SAMPLES", 10)?,
+        };
+        if !(scaling.min_factor >= 0.0 && scaling.min_factor <= scaling.max_factor) {
+            anyhow::bail!(
+                "CONFIDENCE_MIN_FACTOR must be between 0 and CONFIDENCE_MAX_FACTOR, got {} and {}",
+                scaling.min_factor, scaling.max_factor
+            );
+        }
+        Ok(Some(scaling))
+    }
+
+    /// Multiplier for a trader with `wins` profitable and `losses` unprofitable closes
+    /// Twice the win rate (50% = 1.0) clamped to the bounds; 1.0 until `min_samples` closes
+    pub fn factor(&self, wins: u32, losses: u32) -> f64 {
+        let closes = wins + losses;
+        if closes == 0 || closes < self.min_samples {
+            return 1.0;
+        }
+        let win_rate = wins as f64 / closes as f64;
+        (2.0 * win_rate).clamp(self.min_factor, self.max_factor)
+    }
+}
+
+/// Geometric shrink of repeated BUY copies on one token (COPY_DECAY=true)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CopyDecay {
+    /// Multiplier per earlier consecutive BUY copy of the token, above 0 and below 1 (COPY_DECAY_FACTOR)
+    pub factor: f64,
+}
+
+impl CopyDecay {
+    /// COPY_DECAY=true enables it; COPY_DECAY_FACTOR defaults to 0.5
+    pub fn from_env() -> Result<Option<Self>> {
+        if !env_parse_bool("COPY_DECAY", false) {
+            return Ok(None);
+        }
+        let factor = env_parse_limit("COPY_DECAY_FACTOR", 0.5)?;
+        if !(factor > 0.0 && factor < 1.0) {
+            anyhow::bail!("COPY_DECAY_FACTOR must be above 0 and below 1, got {}", factor);
+        }
+        Ok(Some(Self { factor }))
+    }
+
+    /// Size multiplier for a BUY after `prior_copies` consecutive BUY copies of the same token
+    pub fn multiplier(&self, prior_copies: u32) -> f64 {
+        self.factor.powi(prior_copies.min(i32::MAX as u32) as i32)
+    }
+}
+
+/// Second sizing strategy recorded next to the live one for A/B comparison (SHADOW_MODE=true)
+///
+/// Its orders are stored as SHADOW rows in their own table and never submitted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowStrategy {
+    /// Whale-size scaling for every trader (SHADOW_SCALING_RATIO; unset = the live per-trader / global ratio)
+    pub scaling_ratio: Option<f64>,
+    /// Size BUYs as a fraction of bankroll (SHADOW_SIZING_MODE=kelly; SHADOW_KELLY_EDGE / SHADOW_KELLY_FRACTION)
+    pub kelly_sizing: Option<KellySizing>,
+    /// Copy every trade with this many USD (SHADOW_SIZING_MODE=flat with SHADOW_FLAT_USD)
+    pub flat_usd: Option<f64>,
+}
+
+impl ShadowStrategy {
+    /// SHADOW_MODE=true enables it; SHADOW_SIZING_MODE is scaled (default), kelly or flat
+    pub fn from_env() -> Result<Option<Self>> {
+        if !env_parse_bool("SHADOW_MODE", false) {
+            return Ok(None);
+        }
+        let scaling_ratio = match env_non_empty("SHADOW_SCALING_RATIO") {
+            Some(v) => match v.parse::<f64>() {
+                Ok(ratio) if ratio > 0.0 && ratio <= 1.0 => Some(ratio),
+                _ => anyhow::bail!("SHADOW_SCALING_RATIO must be in (0, 1], got {}", v),
+            },
+            None => None,
+        };
+        let mode = env_non_empty("SHADOW_SIZING_MODE").unwrap_or_else(|| "scaled".to_string());
+        let (kelly_sizing, flat_usd) = match mode.to_ascii_lowercase().as_str() {
+            "scaled" => (None, None),
+            "kelly" => {
+                let live = KellySizing::from_env("KELLY_EDGE", "KELLY_FRACTION", KellySizing::DEFAULT)?;
+                (Some(KellySizing::from_env("SHADOW_KELLY_EDGE", "SHADOW_KELLY_FRACTION", live)?), None)
+            }
+            "flat" => {
+                let usd: f64 = env_parse("SHADOW_FLAT_USD", 0.0);
+                if !(usd.is_finite() && usd > 0.0) {
+                    anyhow::bail!("SHADOW_SIZING_MODE=flat requires SHADOW_FLAT_USD greater than 0 (got {})", usd);
+                }
+                (None, Some(usd))
+            }
+            _ => anyhow::bail!("SHADOW_SIZING_MODE must be scaled, kelly or flat, got {}", mode),
+        };
+        Ok(Some(Self { scaling_ratio, kelly_sizing, flat_usd }))
+    }
+}
+
+/// Sells of held positions made on price alone, independent of the whale
+/// (STOP_LOSS_PCT / TRAILING_STOP_PCT / TAKE_PROFIT_PCT)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionExits {
+    pub stop_loss: Option<StopLoss>,
+    pub trailing_stop: Option<TrailingStop>,
+    pub take_profit: Option<TakeProfit>,
+    /// Seconds between position checks (EXIT_CHECK_INTERVAL_SECS)
+    pub check_interval_secs: u64,
+}
+
+impl PositionExits {
+    /// None unless one of the exits is set; positions are checked every 60s by default
+    pub fn from_env() -> Result<Option<Self>> {
+        let stop_loss = StopLoss::from_env()?;
+        let trailing_stop = TrailingStop::from_env()?;
+        let take_profit = TakeProfit::from_env()?;
+        if stop_loss.is_none() && trailing_stop.is_none() && take_profit.is_none() {
+            return Ok(None);
+        }
+        let check_interval_secs = env_parse_limit("EXIT_CHECK_INTERVAL_SECS", 60)?;
+        if check_interval_secs == 0 {
+            anyhow::bail!("EXIT_CHECK_INTERVAL_SECS must be at least 1");
+        }
+        Ok(Some(Self { stop_loss, trailing_stop, take_profit, check_interval_secs }))
+    }
+}
+
+/// Auto-sell held positions that fall too far below entry (STOP_LOSS_PCT)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StopLoss {
+    /// Unrealized loss, in percent of the average entry price, that triggers the exit
+    pub loss_pct: f64,
+}
+
+impl StopLoss {
+    /// STOP_LOSS_PCT enables it (unset/0 = off)
+    pub fn from_env() -> Result<Option<Self>> {
+        let loss_pct: f64 = env_parse_limit("STOP_LOSS_PCT", 0.0)?;
+        if loss_pct == 0.0 {
+            return Ok(None);
+        }
+        if !(loss_pct > 0.0 && loss_pct <= 100.0) {
+            anyhow::bail!("STOP_LOSS_PCT must be between 0 and 100, got {}", loss_pct);
+        }
+        Ok(Some(Self { loss_pct }))
+    }
+
+    /// Whether selling at `bid` now would lose at least `loss_pct` against `avg_entry`
+    pub fn is_triggered(&self, avg_entry: f64, bid: f64) -> bool {
+        if avg_entry <= 0.0 || bid <= 0.0 {
+            return false;
+        }
+        // Tolerance so a loss exactly at the threshold (0.50 -> 0.40 at 20%) still counts
+        (avg_entry - bid) / avg_entry * 100.0 >= self.loss_pct - 1e-9
+    }
+}
+
+/// Auto-sell held positions that give back too much from their peak since entry (TRAILING_STOP_PCT)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrailingStop {
+    /// Drop from the peak price, in percent of the peak, that triggers the exit
+    pub retrace_pct: f64,
+}
+
+impl TrailingStop {
+    /// TRAILING_STOP_PCT enables it (unset/0 = off)
+    pub fn from_env() -> Result<Option<Self>> {
+        let retrace_pct: f64 = env_parse_limit("TRAILING_STOP_PCT", 0.0)?;
+        if retrace_pct == 0.0 {
+            return Ok(None);
+        }
+        if !(retrace_pct > 0.0 && retrace_pct <= 100.0) {
+            anyhow::bail!("TRAILING_STOP_PCT must be between 0 and 100, got {}", retrace_pct);
+        }
+        Ok(Some(Self { retrace_pct }))
+    }
+
+    /// Whether `bid` is at least `retrace_pct` below `peak`
+    pub fn is_triggered(&self, peak: f64, bid: f64) -> bool {
+        if peak <= 0.0 || bid <= 0.0 {
+            return false;
+        }
+        (peak - bid) / peak * 100.0 >= self.retrace_pct - 1e-9
+    }
+}
+
+/// Sell all or part of a held position once it is far enough above entry (TAKE_PROFIT_PCT)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TakeProfit {
+    /// Unrealized gain, in percent of the average entry price, that triggers the sale
+    pub gain_pct: f64,
+    /// Share of the position sold when it triggers (TAKE_PROFIT_FRACTION, 1.0 = all)
+    pub sell_fraction: f64,
+}
+
+impl TakeProfit {
+    /// TAKE_PROFIT_PCT enables it (unset/0 = off); the whole position is sold by default
+    pub fn from_env() -> Result<Option<Self>> {
+        let gain_pct: f64 = env_parse_limit("TAKE_PROFIT_PCT", 0.0)?;
+        if gain_pct == 0.0 {
+            return Ok(None);
+        }
+        if !(gain_pct > 0.0 && gain_pct.is_finite()) {
+            anyhow::bail!("TAKE_PROFIT_PCT must be positive, got {}", gain_pct);
+        }
+        let sell_fraction: f64 = env_parse_limit("TAKE_PROFIT_FRACTION", 1.0)?;
+        if !(sell_fraction > 0.0 && sell_fraction <= 1.0) {
+            anyhow::bail!("TAKE_PROFIT_FRACTION must be in (0, 1], got {}", sell_fraction);
+        }
+        Ok(Some(Self { gain_pct, sell_fraction }))
+    }
+
+    /// Whether selling at `bid` now would gain at least `gain_pct` over `avg_entry`
+    pub fn is_triggered(&self, avg_entry: f64, bid: f64) -> bool {
+        if avg_entry <= 0.0 || bid <= 0.0 {
+            return false;
+        }
+        (bid - avg_entry) / avg_entry * 100.0 >= self.gain_pct - 1e-9
+    }
+
+    /// Shares to sell out of `net_shares`, rounded down to the 0.01 share step
+    pub fn sell_shares(&self, net_shares: f64) -> f64 {
+        // Nudge so 33.33 * 1.0 isn't floored to 33.32 by float error
+        ((net_shares * self.sell_fraction * 100.0 + 1e-6).floor() / 100.0).max(0.0)
+    }
+}
+
+/// Daily UTC window from TRADING_HOURS, in minutes since midnight
+/// Start is inclusive, end exclusive; start > end crosses midnight, start == end covers the whole day
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TradingWindow {
+    pub start_min: u16,
+    pub end_min: u16,
+}
+
+impl TradingWindow {
+    /// Parse "HH:MM-HH:MM" (24:00 is accepted as an end time)
+    pub fn parse(raw: &str) -> Result<Self> {
+        let (start, end) = raw.split_once('-')
+            .with_context(|| format!("expected HH:MM-HH:MM, got '{}'", raw.trim()))?;
+        Ok(Self {
+            start_min: parse_hhmm(start)?,
+            end_min: parse_hhmm(end)?,
+        })
     }
 
-    // Get base tier params - direct if-else is faster than iterator for 3 tiers
-    let (base_buffer, order_action, size_multiplier) = if whale_shares >= 4000.0 {
-        (0.01, "FAK", 1.25)
-    } else if whale_shares >= 2000.0 {
-        (0.01, "FAK", 1.0)
-    } else if whale_shares >= 1000.0 {
-        (0.0, "FAK", 1.0)
-    } else {
-        (PRICE_BUFFER, "FAK", 1.0)  // Small buys use FAK (Fill and Kill)
-    };
+    pub fn contains(&self, minute_of_day: u16) -> bool {
+        if self.start_min <= self.end_min {
+            self.start_min == self.end_min
+                || (self.start_min..self.end_min).contains(&minute_of_day)
+        } else {
+            minute_of_day >= self.start_min || minute_of_day < self.end_min
+        }
+    }
+}
 
-    // Apply sport-specific price adjustments
-    let tennis_buffer = tennis_markets::get_tennis_token_buffer(token_id);
-    let soccer_buffer = soccer_markets::get_soccer_token_buffer(token_id);
-    let total_buffer = base_buffer + tennis_buffer + soccer_buffer;
+fn parse_hhmm(raw: &str) -> Result<u16> {
+    let raw = raw.trim();
+    let (h, m) = raw.split_once(':')
+        .with_context(|| format!("expected HH:MM, got '{}'", raw))?;
+    let (h, m): (u16, u16) = (
+        h.parse().with_context(|| format!("invalid hour in '{}'", raw))?,
+        m.parse().with_context(|| format!("invalid minute in '{}'", raw))?,
+    );
+    if m > 59 || h > 24 || (h == 24 && m > 0) {
+        anyhow::bail!("time out of range: '{}'", raw);
+    }
+    Ok(h * 60 + m)
+}
 
-    (total_buffer, order_action, size_multiplier)
+/// Parse comma-separated TRADING_HOURS windows
+pub fn parse_trading_hours(raw: &str) -> Result<Vec<TradingWindow>> {
+    raw.split(',')
+        .filter(|w| !w.trim().is_empty())
+        .map(TradingWindow::parse)
+        .collect()
+}
+
+/// True if `now` falls in any window (no windows = always trading)
+pub fn is_within_trading_hours(now: DateTime<Utc>, windows: &[TradingWindow]) -> bool {
+    let minute_of_day = (now.hour() * 60 + now.minute()) as u16;
+    windows.is_empty() || windows.iter().any(|w| w.contains(minute_of_day))
+}
+
+/// Price buffer sized from the live spread (DYNAMIC_SPREAD_BUFFER mode)
+/// spread * fraction, floored to the 0.01 tick and capped at the tier buffer
+#[inline]
+pub fn get_spread_buffer(spread: f64, fraction: f64, tier_buffer: f64) -> f64 {
+    if !spread.is_finite() || spread <= 0.0 {
+        return 0.0;
+    }
+    let raw = ((spread * fraction * 100.0) + 1e-9).floor() / 100.0;
+    raw.clamp(0.0, tier_buffer.max(0.0))
 }
 
 // ============================================================================
@@ -190,13 +1410,50 @@ pub struct Config {
     /// Optional separate funder address. If None, funder is derived from private_key.
     /// Only set this if you have delegation configured on Polymarket.
     pub funder_address: Option<String>,
+    /// Env var `private_key` came from: EXECUTION_PRIVATE_KEY when set, else PRIVATE_KEY
+    pub execution_key_source: &'static str,
 
     // WebSocket
-    pub wss_url: String,
+    /// Endpoints in failover order (WSS_URLS, or the single provider URL); never empty
+    pub wss_urls: Vec<String>,
+    /// Warn when the first event after a reconnect is more than this many blocks past the last one before it (0 = disabled)
+    pub ws_block_gap_threshold: u64,
+    /// Replay missed fills over HTTP (eth_getLogs) after every reconnect
+    pub replay_enabled: bool,
+    /// /health reports degraded after this long without a WS message inside TRADING_HOURS (WS_STALE_SECS, default 300; 0 = disabled)
+    pub ws_stale_secs: u64,
+    /// Drop repeats of the same (tx_hash, token, order type) seen within this window (WS_DEDUP_WINDOW_MS, default 30000; 0 = disabled)
+    pub ws_dedup_window_ms: u64,
+    /// Subscribe with a server-side topic filter for up to this many traders; above it every fill is
+    /// received and filtered locally (TOPIC_FILTER_MAX_TRADERS, default 10)
+    pub topic_filter_max_traders: usize,
+    /// Minimum time between order submissions on the same token (MIN_SUBMIT_INTERVAL_MS, default 0 = no limit)
+    pub min_submit_interval_ms: u64,
+    /// Skip BUYs this close to a market's Gamma end time (MARKET_CLOSE_WINDOW_SECS, default 0 = ignore end time;
+    /// closed markets and markets not accepting orders are always skipped)
+    pub market_close_window_secs: u64,
+    /// Copy with post-only GTD orders at the whale's price instead of crossing the spread (POST_ONLY, default: false)
+    pub post_only: bool,
+    /// Fills at or above this percentage of the order count as SUCCESS, below as PARTIAL (SUCCESS_FILL_PCT, default 90)
+    pub success_fill_pct: f64,
 
     // Trading flags
     pub enable_trading: bool,
     pub mock_trading: bool,
+    /// Run the full order pipeline against live books but stop before submitting (DRY_RUN)
+    pub dry_run: bool,
+    /// Skip neg-risk markets (SKIPPED_NEGRISK) instead of routing them to the neg-risk exchange
+    pub skip_neg_risk: bool,
+    /// Cancel GTD orders placed this session on Ctrl-C (default: true)
+    pub cancel_on_shutdown: bool,
+
+    // Logging
+    /// Output format for trade summaries, heartbeats and resubmits (LOG_FORMAT=json)
+    pub log_format: LogFormat,
+    /// Wait before fetching the post-trade book for the CSV/summary (0 = skip the fetch)
+    pub post_trade_book_delay_ms: u64,
+    /// Warn with SLOW_EVENT when handling one whale event takes longer than this (SLOW_EVENT_MS, default 2000; 0 = never)
+    pub slow_event_ms: u64,
 
     // Risk guard (circuit breaker)
     pub cb_large_trade_shares: f64,
@@ -204,10 +1461,94 @@ pub struct Config {
     pub cb_sequence_window_secs: u64,
     pub cb_min_depth_usd: f64,
     pub cb_trip_duration_secs: u64,
+    pub cb_book_depth_levels: usize,
+    /// Risk guard trips and large-trade sequences are saved here on shutdown and restored on start
+    /// (CB_STATE_FILE, default risk_guard_state.json; empty = don't persist)
+    pub cb_state_file: Option<String>,
+    /// Saved risk guard state older than this is ignored on start (CB_STATE_MAX_AGE_MINS, default 30)
+    pub cb_state_max_age_mins: u64,
+    /// Consecutive failed order submissions that pause trading (EXCHANGE_BREAKER_FAILURES; 0 = disabled)
+    pub exchange_breaker_failures: u32,
+    /// How long trading stays paused once the exchange breaker opens (EXCHANGE_BREAKER_COOLDOWN_SECS)
+    pub exchange_breaker_cooldown_secs: u64,
+
+    // Pricing
+    /// Size the price buffer from the live bid/ask spread (capped at the tier buffer)
+    pub dynamic_spread_buffer: bool,
+    /// Fraction of the observed spread used as buffer when DYNAMIC_SPREAD_BUFFER is on
+    pub spread_buffer_fraction: f64,
+    /// Reject orders whose limit price is worse than the whale price by more than this
+    /// (MAX_SLIPPAGE="0.02" absolute or "5%" of whale price; unset = disabled)
+    pub max_slippage: Option<MaxSlippage>,
+    /// BUY tiers at or above this whale size use FOK instead of FAK (FOK_MIN_SHARES; unset = never)
+    pub fok_min_shares: Option<f64>,
+    /// Size BUYs as a fraction of bankroll (SIZING_MODE=kelly; unset = scale from whale size)
+    pub kelly_sizing: Option<KellySizing>,
+    /// Copy every trade with this many USD (SIZING_MODE=flat with FLAT_USD; unset = scale from whale size)
+    pub flat_usd: Option<f64>,
+    /// Fee rate signed into every order (FEE_RATE_BPS; unset = 0, the server default)
+    pub fee_rate_bps: Option<i64>,
+    /// Scale BUYs by the trader's realized win rate (CONFIDENCE_SCALING; unset = off)
+    pub confidence_scaling: Option<ConfidenceScaling>,
+    /// Shrink consecutive BUY copies of one token geometrically (COPY_DECAY; unset = off)
+    pub copy_decay: Option<CopyDecay>,
+    /// Second sizing strategy recorded as SHADOW rows, never submitted (SHADOW_MODE; unset = off)
+    pub shadow_strategy: Option<ShadowStrategy>,
+    /// Stop-loss / take-profit sells of held positions (STOP_LOSS_PCT, TAKE_PROFIT_PCT; unset = off)
+    pub position_exits: Option<PositionExits>,
+    /// Where trailing-stop peaks are kept across restarts (TRAILING_STOP_STATE_FILE)
+    pub trailing_stop_state_file: String,
+    /// Skip BUYs that would open a new token beyond this many open positions (MAX_OPEN_POSITIONS; unset/0 = unlimited)
+    pub max_open_positions: Option<usize>,
+    /// Cap on USD held in any single token; BUYs are trimmed or skipped to stay under it (MAX_EXPOSURE_PER_MARKET_USD; unset = no cap)
+    pub max_exposure_per_market_usd: Option<f64>,
+    /// Token IDs never copied (TOKEN_BLOCKLIST, comma-separated; takes precedence over the allowlist)
+    pub token_blocklist: HashSet<String>,
+    /// If non-empty, only these token IDs are copied (TOKEN_ALLOWLIST, comma-separated)
+    pub token_allowlist: HashSet<String>,
+    /// Daily UTC windows when orders may be placed (TRADING_HOURS, e.g. "08:00-12:00,22:00-02:00"; empty = always)
+    pub trading_hours: Vec<TradingWindow>,
+    /// Global whale-size scaling for traders without their own scaling_ratio (SCALING_RATIO, default 0.02)
+    pub scaling_ratio: f64,
+    /// Smallest order placed (MIN_SHARE_COUNT, default 5; MIN_CASH_VALUE, default 1.01)
+    pub size_floor: SizeFloor,
+    /// FAK resubmit chasing (RESUBMIT_PRICE_INCREMENT, RESUBMIT_MAX_ATTEMPTS_LARGE/SMALL)
+    pub resubmit_policy: ResubmitPolicy,
+    pub order_reply_timeouts: OrderReplyTimeouts,
+    /// GTD order lifetime for live vs other markets (GTD_EXPIRY_LIVE_SECS / GTD_EXPIRY_SECS)
+    pub gtd_expiry: GtdExpiry,
+    /// BUY buffer, order type and size multiplier per whale-size tier (EXECUTION_TIERS)
+    pub execution_tiers: ExecutionTiers,
+    /// Only copy whale fills priced inside this band (MIN_COPY_PRICE / MAX_COPY_PRICE; unset = no bound)
+    pub copy_price_range: CopyPriceRange,
+    /// Scale whale BUYs that open a position vs add to one (SCALE_ON_OPEN / SCALE_ON_ADD, default 1.0; session-local)
+    pub entry_scaling: EntryScaling,
+    /// Estimated USD fee per fill, deducted from realized P&L (FEE_PER_TRADE_USD, default 0.02; 0 = fee-free)
+    pub fee_per_trade_usd: f64,
+
+    // Trade CSV log
+    /// Path of the trade CSV (CSV_FILE, default matches_optimized.csv)
+    pub csv_file: String,
+    /// Rotate the trade CSV once it reaches this many bytes (CSV_MAX_BYTES; unset/0 = never)
+    pub csv_max_bytes: Option<u64>,
+    /// Append every received log notification as a JSON line here (RAW_EVENT_LOG; unset = off)
+    pub raw_event_log: Option<String>,
+
+    // Endpoints
+    /// Polymarket REST endpoints (CLOB_API_BASE / GAMMA_API_BASE; default production hosts)
+    pub api_endpoints: ApiEndpoints,
 
     // Database persistence settings
     pub db_enabled: bool,
     pub db_path: String,
+    /// Delete trades older than this many days on startup and daily (RETENTION_DAYS; unset/0 = keep all)
+    pub retention_days: Option<u32>,
+    /// Compare DB positions with the wallet's holdings on startup (RECONCILE_ON_START, default: false)
+    pub reconcile_on_start: bool,
+    /// Write an adjustment row per discrepancy found (RECONCILE_WRITE_ADJUSTMENTS, default: false)
+    pub reconcile_write_adjustments: bool,
+    /// Most recent trades kept in memory to answer /trades without SQLite (RECENT_TRADES_CAPACITY, default 500; 0 = disabled)
+    pub recent_trades_capacity: usize,
 
     // Trader configuration (multi-trader monitoring)
     pub traders: TradersConfig,
@@ -216,17 +1557,36 @@ pub struct Config {
     pub agg_enabled: bool,
     pub agg_window_ms: u64,
     pub agg_bypass_shares: f64,
+    pub agg_per_trader: bool,
+    pub agg_max_price_drift: Option<f64>,
 
     // HTTP API settings
     pub api_enabled: bool,
     pub api_port: u16,
+    /// Price /pnl from the CLOB market WebSocket instead of polling REST (API_LIVE_PRICES, default: false)
+    pub api_live_prices: bool,
+    /// Seconds /positions and /pnl reuse a fetched price (API_PRICE_TTL_SECS, default: 30)
+    pub api_price_ttl_secs: u64,
+
+    // Notifications (Telegram needs token + chat ID, Discord needs a webhook URL)
+    pub telegram_bot_token: Option<String>,
+    pub telegram_chat_id: Option<String>,
+    pub discord_webhook_url: Option<String>,
+    /// Only announce trades at or above this USD notional on Discord
+    pub discord_min_usd: f64,
 
     // Portfolio-based bet sizing
     /// Maximum bet as percentage of portfolio (e.g., 0.02 = 2%)
     /// None means no portfolio-based limit (disabled by default)
     pub max_bet_portfolio_percent: Option<f64>,
+    /// Maximum bet in shares regardless of price (MAX_BET_SHARES; None = no share limit)
+    pub max_bet_shares_abs: Option<f64>,
     /// How long to cache portfolio value in seconds (default: 300 = 5 minutes)
     pub portfolio_cache_secs: u64,
+    /// Skip BUYs the funder wallet cannot pay for (BALANCE_CHECK_ENABLED, default: false)
+    pub balance_check_enabled: bool,
+    /// USDC left untouched by the balance check (USDC_RESERVE, default: 0)
+    pub usdc_reserve: f64,
     /// Wallet address for portfolio tracking (derived from private key)
     pub wallet_address: String,
 }
@@ -254,61 +1614,41 @@ impl Config {
             );
         }
         
-        let private_key = env::var("PRIVATE_KEY")
-            .context("PRIVATE_KEY env var is required. Add it to your .env file.\n\
-                     Format: 64-character hex string (no 0x prefix)\n\
-                     Example: 0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef")?;
-        
-        // Validate private key format
-        let key_clean = private_key.trim().strip_prefix("0x").unwrap_or(private_key.trim());
-        if key_clean.len() != 64 {
-            anyhow::bail!(
-                "PRIVATE_KEY must be exactly 64 hex characters (found {}).\n\
-                Remove any '0x' prefix. Current value starts with: {}",
-                key_clean.len(),
-                if key_clean.len() > 10 { format!("{}...", &key_clean[..10]) } else { key_clean.to_string() }
-            );
-        }
-        if !key_clean.chars().all(|c| c.is_ascii_hexdigit()) {
-            anyhow::bail!("PRIVATE_KEY contains invalid characters. Must be hexadecimal (0-9, a-f, A-F).");
-        }
+        let sizing_mode = SizingMode::from_env()?;
+
+        // The execution wallet can be set apart from PRIVATE_KEY, e.g. one per follower instance
+        let (key_source, private_key) = match env_non_empty("EXECUTION_PRIVATE_KEY") {
+            Some(key) => ("EXECUTION_PRIVATE_KEY", key),
+            None => ("PRIVATE_KEY", env::var("PRIVATE_KEY")
+                .context("PRIVATE_KEY env var is required. Add it to your .env file.\n\
+                         Format: 64-character hex string (no 0x prefix)\n\
+                         Example: 0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef")?),
+        };
+        let key_clean = parse_private_key(key_source, &private_key)?;
 
         // Check if user wants to use a separate funder address (advanced use case with delegation)
         let use_separate_funder = env::var("USE_SEPARATE_FUNDER")
             .map(|v| v.eq_ignore_ascii_case("true") || v == "1")
             .unwrap_or(false);
 
-        let funder_address = if use_separate_funder {
+        let funder_address = if let Some(funder) = env_non_empty("EXECUTION_FUNDER") {
+            Some(parse_funder_address("EXECUTION_FUNDER", &funder)?)
+        } else if use_separate_funder {
             // Separate funder mode: require and validate FUNDER_ADDRESS
             let funder_raw = env::var("FUNDER_ADDRESS")
                 .context("USE_SEPARATE_FUNDER=true but FUNDER_ADDRESS not set.\n\
                          Set FUNDER_ADDRESS to your delegated funder wallet address,\n\
                          or remove USE_SEPARATE_FUNDER to derive funder from PRIVATE_KEY.")?;
-
-            // Trim whitespace and normalize the address
-            let funder = funder_raw.trim().to_string();
-
-            // Validate funder address format
-            let addr_clean = funder.strip_prefix("0x").unwrap_or(&funder);
-            if addr_clean.len() != 40 {
-                anyhow::bail!(
-                    "FUNDER_ADDRESS must be exactly 40 hex characters (found {}).\n\
-                    Current value: {}",
-                    addr_clean.len(),
-                    if addr_clean.len() > 20 { format!("{}...", &addr_clean[..20]) } else { addr_clean.to_string() }
-                );
-            }
-            if !addr_clean.chars().all(|c| c.is_ascii_hexdigit()) {
-                anyhow::bail!("FUNDER_ADDRESS contains invalid characters. Must be hexadecimal (0-9, a-f, A-F).");
-            }
-            Some(funder)
+            Some(parse_funder_address("FUNDER_ADDRESS", &funder_raw)?)
         } else {
             // Default mode: funder will be derived from private key (recommended)
             None
         };
         
-        // WebSocket URL from either provider
-        let wss_url = if let Ok(key) = env::var("ALCHEMY_API_KEY") {
+        // WebSocket URLs: explicit failover list, or a single URL from either provider
+        let wss_urls = if let Some(raw) = env_non_empty("WSS_URLS") {
+            parse_ws_urls(&raw).context("Invalid WSS_URLS")?
+        } else if let Ok(key) = env::var("ALCHEMY_API_KEY") {
             let key = key.trim();
             if key.is_empty() || key == "your_alchemy_api_key_here" {
                 anyhow::bail!(
@@ -317,7 +1657,7 @@ impl Config {
                     Then add it to your .env file"
                 );
             }
-            format!("wss://polygon-mainnet.g.alchemy.com/v2/{}", key)
+            vec![format!("wss://polygon-mainnet.g.alchemy.com/v2/{}", key)]
         } else if let Ok(key) = env::var("CHAINSTACK_API_KEY") {
             let key = key.trim();
             if key.is_empty() || key == "your_chainstack_api_key_here" {
@@ -327,12 +1667,13 @@ impl Config {
                     Or use ALCHEMY_API_KEY instead (recommended for beginners)"
                 );
             }
-            format!("wss://polygon-mainnet.core.chainstack.com/{}", key)
+            vec![format!("wss://polygon-mainnet.core.chainstack.com/{}", key)]
         } else {
             anyhow::bail!(
                 "WebSocket API key required!\n\
                 \n\
-                Set either ALCHEMY_API_KEY or CHAINSTACK_API_KEY in your .env file.\n\
+                Set either ALCHEMY_API_KEY or CHAINSTACK_API_KEY in your .env file\n\
+                (or WSS_URLS with one or more comma-separated WebSocket URLs).\n\
                 \n\
                 Recommended (beginners): ALCHEMY_API_KEY\n\
                 1. Sign up at https://www.alchemy.com/\n\
@@ -392,13 +1733,11 @@ impl Config {
 
         // Load trader configuration (from file or env var)
         let traders = TradersConfig::load()
+            .and_then(|t| t.validate().map(|_| t))
             .map_err(|e| anyhow::anyhow!("Failed to load trader configuration: {}", e))?;
 
         // Derive wallet address from private key for portfolio tracking
-        let key_with_prefix = format!("0x{}", key_clean);
-        let wallet: PrivateKeySigner = key_with_prefix.parse()
-            .context("Failed to parse private key for wallet address derivation")?;
-        let wallet_address = format!("{}", wallet.address());
+        let wallet_address = derive_wallet_address(&key_clean)?;
 
         // Parse portfolio-based bet sizing settings
         // MAX_BET_PORTFOLIO_PERCENT: e.g., "0.02" for 2% of portfolio
@@ -415,27 +1754,123 @@ impl Config {
             })
             .filter(|&p| p > 0.0 && p <= 1.0); // Validate: must be between 0 and 1
 
+        let (kelly_sizing, flat_usd) = match sizing_mode {
+            SizingMode::Scaled => (None, None),
+            SizingMode::Kelly(kelly) => (Some(kelly), None),
+            SizingMode::Flat(usd) => (None, Some(usd)),
+        };
+        // Kelly stakes near 0.99 approach the whole KELLY_FRACTION, so kelly mode is never uncapped
+        let max_bet_portfolio_percent = max_bet_portfolio_percent
+            .or(kelly_sizing.map(|_| KELLY_MAX_BET_PORTFOLIO_PERCENT_DEFAULT));
+
         Ok(Self {
             private_key,
             funder_address,
-            wss_url,
+            execution_key_source: key_source,
+            wss_urls,
+            ws_block_gap_threshold: env_parse("WS_BLOCK_GAP_THRESHOLD", 100),
+            replay_enabled: env_parse_bool("REPLAY_ENABLED", false),
+            ws_stale_secs: env_parse("WS_STALE_SECS", 300),
+            ws_dedup_window_ms: env_parse("WS_DEDUP_WINDOW_MS", 30_000),
+            topic_filter_max_traders: env_non_empty("TOPIC_FILTER_MAX_TRADERS")
+                .map(|v| parse_topic_filter_max_traders(&v))
+                .transpose()?
+                .unwrap_or(TOPIC_FILTER_MAX_TRADERS_DEFAULT),
+            min_submit_interval_ms: env_parse("MIN_SUBMIT_INTERVAL_MS", 0),
+            market_close_window_secs: env_parse_limit("MARKET_CLOSE_WINDOW_SECS", 0)?,
+            post_only: env_parse_bool("POST_ONLY", false),
+            success_fill_pct: env_non_empty("SUCCESS_FILL_PCT")
+                .map(|v| parse_success_fill_pct(&v))
+                .transpose()?
+                .unwrap_or(SUCCESS_FILL_PCT_DEFAULT),
             enable_trading,
             mock_trading,
+            dry_run: env_parse_bool("DRY_RUN", false),
+            skip_neg_risk: env_parse_bool("SKIP_NEG_RISK", false),
+            cancel_on_shutdown: env_parse_bool("CANCEL_ON_SHUTDOWN", true),
+            log_format: LogFormat::parse(&env::var("LOG_FORMAT").unwrap_or_default()),
+            post_trade_book_delay_ms: env_parse("POST_TRADE_BOOK_DELAY_MS", 2800),
+            slow_event_ms: env_parse("SLOW_EVENT_MS", 2000),
             cb_large_trade_shares: env_parse("CB_LARGE_TRADE_SHARES", 1500.0),
             cb_consecutive_trigger: env_parse("CB_CONSECUTIVE_TRIGGER", 2u8),
             cb_sequence_window_secs: env_parse("CB_SEQUENCE_WINDOW_SECS", 30),
             cb_min_depth_usd: env_parse("CB_MIN_DEPTH_USD", 200.0),
             cb_trip_duration_secs: env_parse("CB_TRIP_DURATION_SECS", 120),
+            cb_book_depth_levels: env_non_empty("CB_BOOK_DEPTH_LEVELS")
+                .map(|v| parse_cb_book_depth_levels(&v))
+                .transpose()?
+                .unwrap_or(CB_BOOK_DEPTH_LEVELS_DEFAULT),
+            cb_state_file: match env::var("CB_STATE_FILE") {
+                Ok(v) => Some(v.trim().to_string()).filter(|v| !v.is_empty()),
+                Err(_) => Some("risk_guard_state.json".to_string()),
+            },
+            cb_state_max_age_mins: env_parse("CB_STATE_MAX_AGE_MINS", 30),
+            exchange_breaker_failures: env_parse_limit("EXCHANGE_BREAKER_FAILURES", 5)?,
+            exchange_breaker_cooldown_secs: env_parse_limit("EXCHANGE_BREAKER_COOLDOWN_SECS", 60)?,
+            dynamic_spread_buffer: env_parse_bool("DYNAMIC_SPREAD_BUFFER", false),
+            spread_buffer_fraction: limit_in_range("SPREAD_BUFFER_FRACTION", env_parse_limit("SPREAD_BUFFER_FRACTION", 0.5)?, 0.0, 1.0)?,
+            max_slippage: env_non_empty("MAX_SLIPPAGE").map(|v| MaxSlippage::parse(&v)).transpose()?,
+            fok_min_shares: env_parse_limit_opt("FOK_MIN_SHARES")?.map(|v| non_negative_limit("FOK_MIN_SHARES", v)).transpose()?,
+            kelly_sizing,
+            flat_usd,
+            fee_rate_bps: env_non_empty("FEE_RATE_BPS").map(|v| parse_fee_rate_bps(&v)).transpose()?,
+            confidence_scaling: ConfidenceScaling::from_env()?,
+            copy_decay: CopyDecay::from_env()?,
+            shadow_strategy: ShadowStrategy::from_env()?,
+            position_exits: PositionExits::from_env()?,
+            trailing_stop_state_file: env_non_empty("TRAILING_STOP_STATE_FILE")
+                .unwrap_or_else(|| ".trailing_stop_state.json".to_string()),
+            max_open_positions: Some(env_parse_limit::<usize>("MAX_OPEN_POSITIONS", 0)?).filter(|n| *n > 0),
+            max_exposure_per_market_usd: env_non_empty("MAX_EXPOSURE_PER_MARKET_USD")
+                .map(|v| parse_max_exposure_per_market_usd(&v))
+                .transpose()?,
+            token_blocklist: env_token_set("TOKEN_BLOCKLIST"),
+            token_allowlist: env_token_set("TOKEN_ALLOWLIST"),
+            trading_hours: env_non_empty("TRADING_HOURS")
+                .map(|v| parse_trading_hours(&v))
+                .transpose()
+                .context("Invalid TRADING_HOURS")?
+                .unwrap_or_default(),
+            scaling_ratio: scaling_ratio_from_env()?,
+            size_floor: SizeFloor::from_env()?,
+            resubmit_policy: ResubmitPolicy::from_env()?,
+            order_reply_timeouts: OrderReplyTimeouts::from_env()?,
+            gtd_expiry: GtdExpiry::from_env()?,
+            execution_tiers: ExecutionTiers::from_env()?,
+            copy_price_range: CopyPriceRange::from_env()?,
+            entry_scaling: EntryScaling::from_env()?,
+            fee_per_trade_usd: fee_per_trade_usd_from_env()?,
+            csv_file: env_non_empty("CSV_FILE").unwrap_or_else(|| CSV_FILE.to_string()),
+            csv_max_bytes: Some(env_parse::<u64>("CSV_MAX_BYTES", 0)).filter(|b| *b > 0),
+            raw_event_log: env_non_empty("RAW_EVENT_LOG"),
+            api_endpoints: ApiEndpoints::from_env(),
             db_enabled: env_parse("DB_ENABLED", true),
             db_path: env::var("DB_PATH").unwrap_or_else(|_| "trades.db".to_string()),
+            retention_days: Some(env_parse::<u32>("RETENTION_DAYS", 0)).filter(|d| *d > 0),
+            reconcile_on_start: env_parse_bool("RECONCILE_ON_START", false),
+            reconcile_write_adjustments: env_parse_bool("RECONCILE_WRITE_ADJUSTMENTS", false),
+            recent_trades_capacity: env_parse("RECENT_TRADES_CAPACITY", 500),
             traders,
             agg_enabled: env_parse_bool("AGG_ENABLED", false),
             agg_window_ms: env_parse("AGG_WINDOW_MS", 800),
             agg_bypass_shares: env_parse("AGG_BYPASS_SHARES", 4000.0),
+            agg_per_trader: env_parse_bool("AGG_PER_TRADER", false),
+            agg_max_price_drift: env_parse_limit_opt("AGG_MAX_PRICE_DRIFT")?
+                .map(|v| limit_in_range("AGG_MAX_PRICE_DRIFT", v, 0.0, 1.0))
+                .transpose()?,
             api_enabled: env_parse_bool("API_ENABLED", false),
             api_port: env_parse("API_PORT", 8080),
+            api_live_prices: env_parse_bool("API_LIVE_PRICES", false),
+            api_price_ttl_secs: env_parse_limit("API_PRICE_TTL_SECS", API_PRICE_TTL_SECS)?,
+            telegram_bot_token: env_non_empty("TELEGRAM_BOT_TOKEN"),
+            telegram_chat_id: env_non_empty("TELEGRAM_CHAT_ID"),
+            discord_webhook_url: env_non_empty("DISCORD_WEBHOOK_URL"),
+            discord_min_usd: env_parse("DISCORD_MIN_USD", 0.0),
             max_bet_portfolio_percent,
+            max_bet_shares_abs: Some(non_negative_limit("MAX_BET_SHARES", env_parse_limit("MAX_BET_SHARES", 0.0)?)?).filter(|shares| *shares > 0.0),
             portfolio_cache_secs: env_parse("PORTFOLIO_CACHE_SECS", 300),
+            balance_check_enabled: env_parse_bool("BALANCE_CHECK_ENABLED", false),
+            usdc_reserve: non_negative_limit("USDC_RESERVE", env_parse_limit("USDC_RESERVE", 0.0)?)?,
             wallet_address,
         })
     }
@@ -448,6 +1883,7 @@ impl Config {
             sequence_window: Duration::from_secs(self.cb_sequence_window_secs),
             min_depth_beyond_usd: self.cb_min_depth_usd,
             trip_duration: Duration::from_secs(self.cb_trip_duration_secs),
+            book_depth_levels: self.cb_book_depth_levels,
         }
     }
 }
@@ -460,6 +1896,43 @@ fn env_parse<T: std::str::FromStr>(key: &str, default: T) -> T {
         .unwrap_or(default)
 }
 
+/// Parse a risk limit env var: unset/blank = default, a value that doesn't parse fails startup
+/// (a typo must not quietly turn the guard off)
+fn env_parse_limit<T: std::str::FromStr>(key: &str, default: T) -> Result<T> {
+    Ok(env_parse_limit_opt(key)?.unwrap_or(default))
+}
+
+/// Like `env_parse_limit` for limits that are off when unset (None)
+fn env_parse_limit_opt<T: std::str::FromStr>(key: &str) -> Result<Option<T>> {
+    env_non_empty(key)
+        .map(|v| v.parse().map_err(|_| anyhow::anyhow!("{} must be a number, got {:?}", key, v)))
+        .transpose()
+}
+
+/// Reject a negative (or NaN/infinite) limit instead of treating it as "off"
+fn non_negative_limit(key: &str, value: f64) -> Result<f64> {
+    if !(value.is_finite() && value >= 0.0) {
+        anyhow::bail!("{} must be 0 or more, got {}", key, value);
+    }
+    Ok(value)
+}
+
+/// Reject a zero, negative (or NaN/infinite) limit
+fn positive_limit(key: &str, value: f64) -> Result<f64> {
+    if !(value.is_finite() && value > 0.0) {
+        anyhow::bail!("{} must be greater than 0, got {}", key, value);
+    }
+    Ok(value)
+}
+
+/// Reject a limit outside `lo..=hi` (or NaN) instead of running with it
+fn limit_in_range(key: &str, value: f64, lo: f64, hi: f64) -> Result<f64> {
+    if !(lo..=hi).contains(&value) {
+        anyhow::bail!("{} must be between {} and {}, got {}", key, lo, hi, value);
+    }
+    Ok(value)
+}
+
 /// Parse boolean env var with support for "true", "1", "false", "0"
 fn env_parse_bool(key: &str, default: bool) -> bool {
     env::var(key)
@@ -467,6 +1940,54 @@ fn env_parse_bool(key: &str, default: bool) -> bool {
         .unwrap_or(default)
 }
 
+/// Read an optional string env var, treating blank values as unset
+fn env_non_empty(key: &str) -> Option<String> {
+    env::var(key)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// SCALING_RATIO env, which must be in (0, 1]; unset = the 2% default
+pub fn scaling_ratio_from_env() -> Result<f64> {
+    let ratio: f64 = env_parse_limit("SCALING_RATIO", SCALING_RATIO)?;
+    if !(ratio > 0.0 && ratio <= 1.0) {
+        anyhow::bail!("SCALING_RATIO must be in (0, 1], got {}", ratio);
+    }
+    Ok(ratio)
+}
+
+/// Base scaling ratio: SCALING_RATIO env if valid, else the 2% default
+/// Also the default for traders.json entries without their own scaling_ratio. The bot itself
+/// never runs on the fallback: Config::from_env fails startup on a bad SCALING_RATIO.
+pub fn default_scaling_ratio() -> f64 {
+    scaling_ratio_from_env().unwrap_or(SCALING_RATIO)
+}
+
+/// FEE_PER_TRADE_USD env, which must be 0 or more; unset = the $0.02 default
+pub fn fee_per_trade_usd_from_env() -> Result<f64> {
+    non_negative_limit("FEE_PER_TRADE_USD", env_parse_limit("FEE_PER_TRADE_USD", FEE_PER_TRADE_USD)?)
+}
+
+/// Per-fill fee estimate for the reporting tools: FEE_PER_TRADE_USD env if valid, else the $0.02 default
+/// The bot itself fails startup on a bad value (see Config::from_env).
+pub fn fee_per_trade_usd() -> f64 {
+    fee_per_trade_usd_from_env().unwrap_or(FEE_PER_TRADE_USD)
+}
+
+/// Read a comma-separated list of token IDs (blank entries ignored; unset = empty)
+fn env_token_set(key: &str) -> HashSet<String> {
+    env::var(key).map(|v| parse_token_list(&v)).unwrap_or_default()
+}
+
+fn parse_token_list(raw: &str) -> HashSet<String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -685,6 +2206,189 @@ mod tests {
     //   4000+: chase on attempt 1 only, flat on 2+
     //   <4000: never chase
     // -------------------------------------------------------------------------
+    #[test]
+    fn test_resubmit_policy_validation() {
+        let with = |price_increment: f64| ResubmitPolicy { price_increment, ..ResubmitPolicy::default() };
+        assert!(ResubmitPolicy::default().validate().is_ok());
+        assert!(with(0.03).validate().is_ok());
+        assert!(with(0.10).validate().is_ok());
+
+        assert!(with(0.015).validate().is_err(), "not a whole number of ticks");
+        assert!(with(0.0).validate().is_err());
+        assert!(with(-0.01).validate().is_err());
+        assert!(with(1.0).validate().is_err());
+
+        let no_attempts = ResubmitPolicy { max_attempts_small: 0, ..ResubmitPolicy::default() };
+        assert!(no_attempts.validate().is_err());
+    }
+
+    #[test]
+    fn test_parse_cb_book_depth_levels() {
+        assert_eq!(parse_cb_book_depth_levels("1").unwrap(), 1);
+        assert_eq!(parse_cb_book_depth_levels(" 25 ").unwrap(), 25);
+        for bad in ["0", "-1", "2.5", "ten", ""] {
+            assert!(parse_cb_book_depth_levels(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_parse_max_exposure_per_market_usd() {
+        assert_eq!(parse_max_exposure_per_market_usd("250").unwrap(), 250.0);
+        assert_eq!(parse_max_exposure_per_market_usd(" 12.5 ").unwrap(), 12.5);
+        for bad in ["0", "-5", "$100", "1,000", "inf", ""] {
+            assert!(parse_max_exposure_per_market_usd(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_parse_fee_rate_bps() {
+        assert_eq!(parse_fee_rate_bps("0").unwrap(), 0);
+        assert_eq!(parse_fee_rate_bps(" 100 ").unwrap(), 100);
+        assert_eq!(parse_fee_rate_bps("1000").unwrap(), FEE_RATE_BPS_MAX);
+        for bad in ["-1", "1001", "2.5", "1%", ""] {
+            assert!(parse_fee_rate_bps(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_parse_topic_filter_max_traders() {
+        assert_eq!(parse_topic_filter_max_traders("1").unwrap(), 1);
+        assert_eq!(parse_topic_filter_max_traders(" 25 ").unwrap(), 25);
+        for bad in ["0", "-1", "2.5", "ten", ""] {
+            assert!(parse_topic_filter_max_traders(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_execution_key_derives_its_wallet() {
+        // Well-known development key (Hardhat / Anvil account 0)
+        let key = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+        assert_eq!(parse_private_key("EXECUTION_PRIVATE_KEY", &format!(" 0x{} ", key)).unwrap(), key);
+        assert_eq!(derive_wallet_address(key).unwrap(), "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+
+        let short = parse_private_key("EXECUTION_PRIVATE_KEY", "abc123").unwrap_err().to_string();
+        assert!(short.starts_with("EXECUTION_PRIVATE_KEY must be exactly 64"), "{}", short);
+        assert!(parse_private_key("PRIVATE_KEY", &"g".repeat(64)).is_err());
+        // 64 hex characters, but zero isn't a valid secp256k1 key
+        assert!(derive_wallet_address(&"0".repeat(64)).is_err());
+
+        assert_eq!(
+            parse_funder_address("EXECUTION_FUNDER", "0x70997970C51812dc3A010C7d01b50e0d17dc79C8\n").unwrap(),
+            "0x70997970C51812dc3A010C7d01b50e0d17dc79C8"
+        );
+        let bad = parse_funder_address("EXECUTION_FUNDER", "0x1234").unwrap_err().to_string();
+        assert!(bad.starts_with("EXECUTION_FUNDER must be exactly 40"), "{}", bad);
+    }
+
+    #[test]
+    fn test_parse_success_fill_pct() {
+        assert_eq!(parse_success_fill_pct("85").unwrap(), 85.0);
+        assert_eq!(parse_success_fill_pct(" 100 ").unwrap(), 100.0);
+        assert_eq!(parse_success_fill_pct("0.5").unwrap(), 0.5);
+        for bad in ["0", "-5", "100.1", "90%", ""] {
+            assert!(parse_success_fill_pct(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_gtd_expiry_by_liveness_and_range() {
+        let expiry = GtdExpiry::default();
+        assert_eq!(expiry.secs(true), 61);
+        assert_eq!(expiry.secs(false), 1800);
+        assert!(expiry.validate().is_ok());
+
+        let configured = GtdExpiry { live_secs: 120, default_secs: 3600 };
+        assert_eq!(configured.secs(true), 120);
+        assert_eq!(configured.secs(false), 3600);
+
+        // Under a minute is rejected by Polymarket; over 30 days is a typo
+        assert!(GtdExpiry { live_secs: 60, ..expiry }.validate().is_err());
+        assert!(GtdExpiry { default_secs: 0, ..expiry }.validate().is_err());
+        assert!(GtdExpiry { default_secs: GTD_EXPIRY_MAX_SECS + 1, ..expiry }.validate().is_err());
+        assert!(GtdExpiry { live_secs: GTD_EXPIRY_MIN_SECS, default_secs: GTD_EXPIRY_MAX_SECS }.validate().is_ok());
+    }
+
+    #[test]
+    fn test_resubmit_policy_defaults_match_tiers() {
+        let policy = ResubmitPolicy::default();
+        assert_eq!(policy.max_attempts(4000.0), get_max_resubmit_attempts(4000.0));
+        assert_eq!(policy.max_attempts(3999.0), get_max_resubmit_attempts(3999.0));
+        assert_eq!(policy.increment(5000.0, 1), RESUBMIT_PRICE_INCREMENT);
+        assert_eq!(policy.increment(5000.0, 2), 0.0);
+        assert_eq!(policy.increment(1000.0, 1), 0.0);
+    }
+
+    #[test]
+    fn test_order_reply_timeout_by_tier() {
+        let timeouts = OrderReplyTimeouts::default();
+        assert_eq!(timeouts.for_shares(10_000.0), ORDER_REPLY_TIMEOUT_LARGE);
+        assert_eq!(timeouts.for_shares(4000.0), ORDER_REPLY_TIMEOUT_LARGE);
+        assert_eq!(timeouts.for_shares(3999.0), ORDER_REPLY_TIMEOUT);
+        assert_eq!(timeouts.for_shares(500.0), ORDER_REPLY_TIMEOUT);
+        assert_eq!(timeouts.for_shares(0.0), ORDER_REPLY_TIMEOUT);
+
+        let custom = OrderReplyTimeouts { large: Duration::from_secs(45), small: Duration::from_millis(2500) };
+        assert_eq!(custom.for_shares(4500.0), Duration::from_secs(45));
+        assert_eq!(custom.for_shares(1000.0), Duration::from_millis(2500));
+        assert!(custom.validate().is_ok());
+        assert!(OrderReplyTimeouts { small: Duration::ZERO, ..custom }.validate().is_err());
+    }
+
+    #[test]
+    fn test_entry_scaling_multiplier() {
+        let scaling = EntryScaling { scale_on_open: 1.5, scale_on_add: 0.5 };
+        assert!(scaling.is_enabled());
+        assert_eq!(scaling.multiplier(0.0), 1.5, "fresh open");
+        assert_eq!(scaling.multiplier(250.0), 0.5, "add to existing");
+
+        let skip_adds = EntryScaling { scale_on_add: 0.0, ..EntryScaling::default() };
+        assert_eq!(skip_adds.multiplier(0.0), 1.0);
+        assert_eq!(skip_adds.multiplier(10.0), 0.0);
+
+        // Default: both 1.0, mode off
+        let default = EntryScaling::default();
+        assert!(!default.is_enabled());
+        assert_eq!(default.multiplier(0.0), default.multiplier(100.0));
+    }
+
+    #[test]
+    fn test_copy_price_range_contains() {
+        let band = CopyPriceRange { min: Some(0.05), max: Some(0.95) };
+        assert!(!band.contains(0.02), "below min");
+        assert!(!band.contains(0.98), "above max");
+        assert!(band.contains(0.50));
+        // Bounds are inclusive
+        assert!(band.contains(0.05) && band.contains(0.95));
+
+        // Unset: no filtering
+        let unset = CopyPriceRange::default();
+        assert!(unset.contains(0.01) && unset.contains(0.99));
+
+        // One-sided
+        let floor_only = CopyPriceRange { min: Some(0.10), max: None };
+        assert!(!floor_only.contains(0.05));
+        assert!(floor_only.contains(0.99));
+    }
+
+    #[test]
+    fn test_copy_price_range_trader_override() {
+        let global = CopyPriceRange { min: Some(0.05), max: Some(0.95) };
+        // A trader bound replaces the global one; unset trader bounds keep it
+        let merged = global.with_trader(CopyPriceRange { min: Some(0.20), max: None });
+        assert_eq!(merged, CopyPriceRange { min: Some(0.20), max: Some(0.95) });
+        assert_eq!(global.with_trader(CopyPriceRange::default()), global);
+        assert_eq!(merged.to_string(), "0.20-0.95");
+    }
+
+    #[test]
+    fn test_copy_price_range_validation() {
+        assert!(CopyPriceRange::default().validate().is_ok());
+        assert!(CopyPriceRange { min: Some(0.05), max: Some(0.95) }.validate().is_ok());
+        assert!(CopyPriceRange { min: Some(0.95), max: Some(0.05) }.validate().is_err());
+        assert!(CopyPriceRange { min: Some(0.0), max: None }.validate().is_err());
+        assert!(CopyPriceRange { min: None, max: Some(1.5) }.validate().is_err());
+    }
+
     #[test]
     fn test_should_increment_price() {
         // 4000+ (includes 8000+): chase on attempt 1 only, flat on 2+
@@ -755,6 +2459,302 @@ mod tests {
     // -------------------------------------------------------------------------
     // Test: DB Settings - defaults and environment variable parsing
     // -------------------------------------------------------------------------
+    #[test]
+    fn test_fok_tier_override() {
+        let token_id = "test_token";
+        let tiers = ExecutionTiers::default();
+
+        // Disabled: every BUY tier stays FAK
+        assert_eq!(get_tier_params_with_fok(5000.0, true, token_id, None, &tiers).1, "FAK");
+
+        // FOK from the 2000+ tier up; buffer and multiplier are unchanged
+        let fok = Some(2000.0);
+        assert_eq!(get_tier_params_with_fok(4000.0, true, token_id, fok, &tiers), (0.01, "FOK", 1.25));
+        assert_eq!(get_tier_params_with_fok(2000.0, true, token_id, fok, &tiers).1, "FOK");
+        assert_eq!(get_tier_params_with_fok(1999.0, true, token_id, fok, &tiers).1, "FAK");
+
+        // SELLs are never FOK
+        assert_eq!(get_tier_params_with_fok(5000.0, false, token_id, fok, &tiers).1, "GTD");
+    }
+
+    #[test]
+    fn test_post_only_never_applies_tier_buffer() {
+        let token_id = "test_token";
+        let tiers = ExecutionTiers::default();
+        let fok = Some(2000.0);
+
+        for shares in [10.0, 1000.0, 2000.0, 4000.0, 20000.0] {
+            let tier = get_tier_params_with_fok(shares, true, token_id, fok, &tiers);
+            assert_eq!(post_only_params(tier), (0.0, "GTD", tier.2), "{} shares", shares);
+        }
+        // The 4000+ tier's multiplier is kept, its buffer and FOK are not
+        assert_eq!(post_only_params(get_tier_params_with_fok(4000.0, true, token_id, fok, &tiers)), (0.0, "GTD", 1.25));
+        assert_eq!(post_only_params(get_tier_params_with_fok(5000.0, false, token_id, fok, &tiers)), (0.0, "GTD", 1.0));
+    }
+
+    #[test]
+    fn test_custom_execution_tiers_boundaries() {
+        let tiers = ExecutionTiers::parse(r#"[
+            {"min_shares": 10000, "buffer": 0.02, "order_type": "fok", "size_multiplier": 1.5},
+            {"min_shares": 500, "buffer": 0.01, "order_type": "FAK", "size_multiplier": 0.8}
+        ]"#).unwrap();
+        let token_id = "test_token";
+        let params = |shares: f64| get_tier_params_with_fok(shares, true, token_id, None, &tiers);
+
+        assert_eq!(params(10000.0), (0.02, "FOK", 1.5));
+        assert_eq!(params(9999.9), (0.01, "FAK", 0.8));
+        assert_eq!(params(500.0), (0.01, "FAK", 0.8));
+        // Below the smallest tier: defaults
+        assert_eq!(params(499.9), (PRICE_BUFFER, "FAK", 1.0));
+        // The built-in 4000 tier no longer exists
+        assert_eq!(params(4000.0), (0.01, "FAK", 0.8));
+        assert_eq!(get_tier_params_with_fok(20000.0, false, token_id, None, &tiers), (PRICE_BUFFER, "GTD", 1.0));
+    }
+
+    #[test]
+    fn test_execution_tiers_validation() {
+        let tier = |min: f64, buffer: f64, order_type: &str, mult: f64| {
+            format!(r#"{{"min_shares":{},"buffer":{},"order_type":"{}","size_multiplier":{}}}"#, min, buffer, order_type, mult)
+        };
+        let parse = |tiers: &[String]| ExecutionTiers::parse(&format!("[{}]", tiers.join(",")));
+
+        assert!(parse(&[tier(4000.0, 0.01, "FAK", 1.25), tier(1000.0, 0.0, "FAK", 1.0)]).is_ok());
+        // Ascending or duplicate min_shares
+        assert!(parse(&[tier(1000.0, 0.0, "FAK", 1.0), tier(4000.0, 0.01, "FAK", 1.25)]).is_err());
+        assert!(parse(&[tier(1000.0, 0.0, "FAK", 1.0), tier(1000.0, 0.01, "FAK", 1.25)]).is_err());
+        // Bad values
+        assert!(parse(&[tier(1000.0, 0.0, "GTC", 1.0)]).is_err());
+        assert!(parse(&[tier(1000.0, -0.01, "FAK", 1.0)]).is_err());
+        assert!(parse(&[tier(1000.0, 0.0, "FAK", 0.0)]).is_err());
+        assert!(parse(&[tier(0.0, 0.0, "FAK", 1.0)]).is_err());
+        assert!(parse(&[]).is_err());
+        assert!(ExecutionTiers::parse(r#"[{"min_shares":1000,"buffer":0,"order_type":"FAK"}]"#).is_err());
+        assert!(ExecutionTiers::parse("4000:0.01").is_err());
+
+        // The built-in table passes its own validation
+        assert!(ExecutionTiers::default().validate().is_ok());
+        assert_eq!(ExecutionTiers::default().tiers(), &EXECUTION_TIERS);
+    }
+
+    #[test]
+    fn test_max_slippage_parse() {
+        assert_eq!(MaxSlippage::parse("0.02").unwrap(), MaxSlippage::Absolute(0.02));
+        assert_eq!(MaxSlippage::parse(" 5% ").unwrap(), MaxSlippage::Percent(5.0));
+        for bad in ["-1", "abc", "", "2 %%", "0,02", "NaN"] {
+            assert!(MaxSlippage::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_max_slippage_absolute_boundary() {
+        let max = MaxSlippage::Absolute(0.01);
+        // BUY: exactly at the limit passes, one tick beyond is rejected
+        assert!(!max.is_exceeded(0.50, 0.51, true));
+        assert!(max.is_exceeded(0.50, 0.52, true));
+        // Paying less than the whale is never slippage
+        assert!(!max.is_exceeded(0.50, 0.40, true));
+        // SELL: mirror on the downside
+        assert!(!max.is_exceeded(0.50, 0.49, false));
+        assert!(max.is_exceeded(0.50, 0.48, false));
+        assert!(!max.is_exceeded(0.50, 0.60, false));
+    }
+
+    #[test]
+    fn test_max_slippage_percent_boundary() {
+        let max = MaxSlippage::Percent(4.0);
+        // 4% of 0.50 = 0.02
+        assert!(!max.is_exceeded(0.50, 0.52, true));
+        assert!(max.is_exceeded(0.50, 0.53, true));
+        assert!(!max.is_exceeded(0.50, 0.48, false));
+        assert!(max.is_exceeded(0.50, 0.47, false));
+    }
+
+    #[test]
+    fn test_copy_decay_multiplier_is_geometric() {
+        let decay = CopyDecay { factor: 0.5 };
+        assert_eq!(decay.multiplier(0), 1.0);
+        assert_eq!(decay.multiplier(1), 0.5);
+        assert_eq!(decay.multiplier(2), 0.25);
+        assert_eq!(decay.multiplier(3), 0.125);
+        assert_eq!(CopyDecay { factor: 0.8 }.multiplier(2), 0.8 * 0.8);
+        assert_eq!(decay.multiplier(u32::MAX), 0.0);
+    }
+
+    #[test]
+    fn test_confidence_factor() {
+        let scaling = ConfidenceScaling { min_factor: 0.5, max_factor: 1.5, min_samples: 10 };
+
+        // Too few closes: neutral whatever the record
+        assert_eq!(scaling.factor(0, 0), 1.0);
+        assert_eq!(scaling.factor(9, 0), 1.0);
+        assert_eq!(scaling.factor(0, 9), 1.0);
+
+        // High win rate boosts, capped at max_factor
+        assert_eq!(scaling.factor(7, 3), 1.4);
+        assert_eq!(scaling.factor(10, 0), 1.5);
+        // Low win rate reduces, floored at min_factor
+        assert_eq!(scaling.factor(4, 6), 0.8);
+        assert_eq!(scaling.factor(1, 9), 0.5);
+        // Coin flip is neutral
+        assert_eq!(scaling.factor(5, 5), 1.0);
+
+        // min_samples 0 still needs one close to have a rate
+        let eager = ConfidenceScaling { min_samples: 0, ..scaling };
+        assert_eq!(eager.factor(0, 0), 1.0);
+        assert_eq!(eager.factor(1, 0), 1.5);
+    }
+
+    #[test]
+    fn test_stop_loss_trigger() {
+        let stop = StopLoss { loss_pct: 20.0 };
+
+        // Down 25% from a 0.40 entry: exit
+        assert!(stop.is_triggered(0.40, 0.30));
+        // Exactly at the threshold: exit
+        assert!(stop.is_triggered(0.50, 0.40));
+        // Down 10%, flat, or up: hold
+        assert!(!stop.is_triggered(0.40, 0.36));
+        assert!(!stop.is_triggered(0.40, 0.40));
+        assert!(!stop.is_triggered(0.40, 0.55));
+        // No usable entry or bid: never triggers
+        assert!(!stop.is_triggered(0.0, 0.10));
+        assert!(!stop.is_triggered(0.40, 0.0));
+    }
+
+    #[test]
+    fn test_trailing_stop_trigger() {
+        let trail = TrailingStop { retrace_pct: 20.0 };
+
+        // Peaked at 0.80: 0.64 is exactly 20% off, 0.70 only 12.5%
+        assert!(trail.is_triggered(0.80, 0.64));
+        assert!(trail.is_triggered(0.80, 0.50));
+        assert!(!trail.is_triggered(0.80, 0.70));
+        assert!(!trail.is_triggered(0.80, 0.80));
+        assert!(!trail.is_triggered(0.0, 0.50));
+    }
+
+    #[test]
+    fn test_take_profit_trigger_and_size() {
+        let half = TakeProfit { gain_pct: 50.0, sell_fraction: 0.5 };
+
+        // Up 60% / exactly 50%: sell; up 25% or down: hold
+        assert!(half.is_triggered(0.40, 0.64));
+        assert!(half.is_triggered(0.40, 0.60));
+        assert!(!half.is_triggered(0.40, 0.50));
+        assert!(!half.is_triggered(0.40, 0.30));
+        assert!(!half.is_triggered(0.0, 0.60));
+
+        // Half of the holding, rounded down to the share step
+        assert_eq!(half.sell_shares(40.0), 20.0);
+        assert_eq!(half.sell_shares(33.33), 16.66);
+        let all = TakeProfit { sell_fraction: 1.0, ..half };
+        assert_eq!(all.sell_shares(33.33), 33.33);
+    }
+
+    #[test]
+    fn test_kelly_sizing_from_env_rejects_bad_values() {
+        let (edge, fraction) = ("KELLY_EDGE_TEST_RANGE", "KELLY_FRACTION_TEST_RANGE");
+        assert_eq!(KellySizing::from_env(edge, fraction, KellySizing::DEFAULT).unwrap(), KellySizing::DEFAULT);
+        for (key, bad) in [(edge, "-0.01"), (edge, "1"), (edge, "0.o2"), (fraction, "0"), (fraction, "1.5"), (fraction, "quarter")] {
+            unsafe { std::env::set_var(key, bad); }
+            assert!(KellySizing::from_env(edge, fraction, KellySizing::DEFAULT).is_err(), "{}={}", key, bad);
+            unsafe { std::env::remove_var(key); }
+        }
+        unsafe { std::env::set_var(edge, "0"); }
+        assert_eq!(KellySizing::from_env(edge, fraction, KellySizing::DEFAULT).unwrap().edge, 0.0);
+        unsafe { std::env::remove_var(edge); }
+    }
+
+    #[test]
+    fn test_kelly_bankroll_fraction() {
+        // No edge, no bet
+        let none = KellySizing { edge: 0.0, fraction: 0.25 };
+        assert_eq!(none.bankroll_fraction(0.50), 0.0);
+        assert_eq!(none.target_shares(1000.0, 0.50), 0.0);
+        assert_eq!(KellySizing { edge: -0.05, fraction: 0.25 }.bankroll_fraction(0.50), 0.0);
+
+        // Small edge: quarter Kelly of 0.02 / (1 - 0.50) = 1% of bankroll
+        let small = KellySizing { edge: 0.02, fraction: 0.25 };
+        assert!((small.bankroll_fraction(0.50) - 0.01).abs() < 1e-12);
+        assert!((small.target_shares(1000.0, 0.50) - 20.0).abs() < 1e-9); // $10 / 0.50
+
+        // The win probability is at most 1: at 0.98 and 0.99 a 0.02 edge means a sure win,
+        // so quarter Kelly stakes a quarter of the bankroll, not half
+        assert!((small.bankroll_fraction(0.98) - 0.25).abs() < 1e-12);
+        assert!((small.bankroll_fraction(0.99) - 0.25).abs() < 1e-12);
+        assert!((small.bankroll_fraction(1.0) - 0.25).abs() < 1e-12);
+
+        // Never stake more than the bankroll
+        let huge = KellySizing { edge: 0.05, fraction: 1.0 };
+        assert_eq!(huge.bankroll_fraction(0.99), 1.0);
+        assert!((huge.target_shares(100.0, 0.99) - 101.0101).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_next_backoff_grows_exponentially_and_caps() {
+        assert_eq!(next_backoff(0), WS_RECONNECT_DELAY);
+        assert_eq!(next_backoff(1), WS_RECONNECT_DELAY * 2);
+        assert_eq!(next_backoff(2), WS_RECONNECT_DELAY * 4);
+        assert_eq!(next_backoff(10), WS_RECONNECT_MAX_DELAY);
+        // Huge attempt counts must not overflow
+        assert_eq!(next_backoff(u32::MAX), WS_RECONNECT_MAX_DELAY);
+    }
+
+    #[test]
+    fn test_ws_endpoints_rotate_and_wrap() {
+        let mut endpoints = WsEndpoints::new(vec![
+            "wss://a.example/key1".to_string(),
+            "wss://b.example/key2".to_string(),
+            "wss://c.example/key3".to_string(),
+        ]);
+        assert_eq!(endpoints.count(), 3);
+        assert_eq!(endpoints.active(), "wss://a.example/key1");
+        assert_eq!(endpoints.rotate(), "wss://b.example/key2");
+        assert_eq!(endpoints.rotate(), "wss://c.example/key3");
+        assert_eq!(endpoints.rotate(), "wss://a.example/key1");
+
+        // Single endpoint: rotation keeps it
+        let mut single = WsEndpoints::new(vec!["wss://only.example".to_string()]);
+        assert_eq!(single.rotate(), "wss://only.example");
+    }
+
+    #[test]
+    fn test_ws_endpoints_label_hides_key() {
+        let mut endpoints = WsEndpoints::new(vec![
+            "wss://polygon-mainnet.g.alchemy.com/v2/secret".to_string(),
+            "ws://localhost:8546".to_string(),
+        ]);
+        assert_eq!(endpoints.active_label(), "#1 polygon-mainnet.g.alchemy.com");
+        endpoints.rotate();
+        assert_eq!(endpoints.active_label(), "#2 localhost:8546");
+    }
+
+    #[test]
+    fn test_ws_endpoints_backoff_after_full_round() {
+        let endpoints = WsEndpoints::new(vec!["wss://a".to_string(), "wss://b".to_string(), "wss://c".to_string()]);
+        // First two failures fail over immediately, the third (all tried) backs off
+        assert_eq!(endpoints.reconnect_delay(1), None);
+        assert_eq!(endpoints.reconnect_delay(2), None);
+        assert_eq!(endpoints.reconnect_delay(3), Some(next_backoff(0)));
+        assert_eq!(endpoints.reconnect_delay(4), None);
+        assert_eq!(endpoints.reconnect_delay(6), Some(next_backoff(1)));
+
+        // Single endpoint keeps the plain exponential backoff
+        let single = WsEndpoints::new(vec!["wss://a".to_string()]);
+        assert_eq!(single.reconnect_delay(1), Some(next_backoff(0)));
+        assert_eq!(single.reconnect_delay(2), Some(next_backoff(1)));
+    }
+
+    #[test]
+    fn test_parse_ws_urls() {
+        assert_eq!(
+            parse_ws_urls(" wss://a.example/k , ,ws://b.example ").unwrap(),
+            vec!["wss://a.example/k".to_string(), "ws://b.example".to_string()]
+        );
+        assert!(parse_ws_urls(" , ").is_err());
+        assert!(parse_ws_urls("wss://a.example,https://b.example").is_err());
+    }
+
     #[test]
     fn test_db_enabled_defaults_to_true() {
         // Clear any existing env var
@@ -806,24 +2806,90 @@ mod tests {
         let _test_config = Config {
             private_key: "test".to_string(),
             funder_address: None,
-            wss_url: "test".to_string(),
+            execution_key_source: "PRIVATE_KEY",
+            wss_urls: vec!["test".to_string()],
+            ws_block_gap_threshold: 100,
+            replay_enabled: false,
+            ws_stale_secs: 300,
+            ws_dedup_window_ms: 30_000,
+            topic_filter_max_traders: TOPIC_FILTER_MAX_TRADERS_DEFAULT,
+            min_submit_interval_ms: 0,
+            market_close_window_secs: 0,
+            post_only: false,
+            success_fill_pct: SUCCESS_FILL_PCT_DEFAULT,
             enable_trading: true,
             mock_trading: false,
+            dry_run: false,
+            skip_neg_risk: false,
+            cancel_on_shutdown: true,
+            log_format: LogFormat::Text,
+            post_trade_book_delay_ms: 2800,
+            slow_event_ms: 2000,
             cb_large_trade_shares: 1500.0,
             cb_consecutive_trigger: 2,
             cb_sequence_window_secs: 30,
             cb_min_depth_usd: 200.0,
             cb_trip_duration_secs: 120,
+            cb_book_depth_levels: 10,
+            cb_state_file: None,
+            cb_state_max_age_mins: 30,
+            exchange_breaker_failures: 5,
+            exchange_breaker_cooldown_secs: 60,
+            dynamic_spread_buffer: false,
+            spread_buffer_fraction: 0.5,
+            max_slippage: None,
+            fok_min_shares: None,
+            kelly_sizing: None,
+            flat_usd: None,
+            fee_rate_bps: None,
+            confidence_scaling: None,
+            copy_decay: None,
+            position_exits: None,
+            trailing_stop_state_file: ".trailing_stop_state.json".to_string(),
+            max_open_positions: None,
+            max_exposure_per_market_usd: None,
+            token_blocklist: HashSet::new(),
+            token_allowlist: HashSet::new(),
+            trading_hours: Vec::new(),
+            scaling_ratio: SCALING_RATIO,
+            size_floor: SizeFloor::default(),
+            resubmit_policy: ResubmitPolicy::default(),
+            order_reply_timeouts: OrderReplyTimeouts::default(),
+            gtd_expiry: GtdExpiry::default(),
+            execution_tiers: ExecutionTiers::default(),
+            shadow_strategy: None,
+            copy_price_range: CopyPriceRange::default(),
+            entry_scaling: EntryScaling::default(),
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            csv_file: CSV_FILE.to_string(),
+            csv_max_bytes: None,
+            raw_event_log: None,
+            api_endpoints: ApiEndpoints::default(),
             db_enabled: false,
             db_path: "/test/path.db".to_string(),
+            retention_days: None,
+            reconcile_on_start: false,
+            reconcile_write_adjustments: false,
+            recent_trades_capacity: 0,
             traders: TradersConfig::new(vec![]),
             agg_enabled: false,
             agg_window_ms: 800,
             agg_bypass_shares: 4000.0,
+            agg_per_trader: false,
+            agg_max_price_drift: None,
             api_enabled: false,
             api_port: 8080,
+            api_live_prices: false,
+            api_price_ttl_secs: API_PRICE_TTL_SECS,
+            telegram_bot_token: None,
+            telegram_chat_id: None,
+            discord_webhook_url: None,
+            discord_min_usd: 0.0,
             max_bet_portfolio_percent: None,
+            max_bet_shares_abs: None,
             portfolio_cache_secs: 300,
+            balance_check_enabled: false,
+            usdc_reserve: 0.0,
             wallet_address: "0x1234".to_string(),
         };
 
@@ -842,24 +2908,90 @@ mod tests {
         let _test_config = Config {
             private_key: "test".to_string(),
             funder_address: None,
-            wss_url: "test".to_string(),
+            execution_key_source: "PRIVATE_KEY",
+            wss_urls: vec!["test".to_string()],
+            ws_block_gap_threshold: 100,
+            replay_enabled: false,
+            ws_stale_secs: 300,
+            ws_dedup_window_ms: 30_000,
+            topic_filter_max_traders: TOPIC_FILTER_MAX_TRADERS_DEFAULT,
+            min_submit_interval_ms: 0,
+            market_close_window_secs: 0,
+            post_only: false,
+            success_fill_pct: SUCCESS_FILL_PCT_DEFAULT,
             enable_trading: true,
             mock_trading: false,
+            dry_run: false,
+            skip_neg_risk: false,
+            cancel_on_shutdown: true,
+            log_format: LogFormat::Text,
+            post_trade_book_delay_ms: 2800,
+            slow_event_ms: 2000,
             cb_large_trade_shares: 1500.0,
             cb_consecutive_trigger: 2,
             cb_sequence_window_secs: 30,
             cb_min_depth_usd: 200.0,
             cb_trip_duration_secs: 120,
+            cb_book_depth_levels: 10,
+            cb_state_file: None,
+            cb_state_max_age_mins: 30,
+            exchange_breaker_failures: 5,
+            exchange_breaker_cooldown_secs: 60,
+            dynamic_spread_buffer: false,
+            spread_buffer_fraction: 0.5,
+            max_slippage: None,
+            fok_min_shares: None,
+            kelly_sizing: None,
+            flat_usd: None,
+            fee_rate_bps: None,
+            confidence_scaling: None,
+            copy_decay: None,
+            position_exits: None,
+            trailing_stop_state_file: ".trailing_stop_state.json".to_string(),
+            max_open_positions: None,
+            max_exposure_per_market_usd: None,
+            token_blocklist: HashSet::new(),
+            token_allowlist: HashSet::new(),
+            trading_hours: Vec::new(),
+            scaling_ratio: SCALING_RATIO,
+            size_floor: SizeFloor::default(),
+            resubmit_policy: ResubmitPolicy::default(),
+            order_reply_timeouts: OrderReplyTimeouts::default(),
+            gtd_expiry: GtdExpiry::default(),
+            execution_tiers: ExecutionTiers::default(),
+            shadow_strategy: None,
+            copy_price_range: CopyPriceRange::default(),
+            entry_scaling: EntryScaling::default(),
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            csv_file: CSV_FILE.to_string(),
+            csv_max_bytes: None,
+            raw_event_log: None,
+            api_endpoints: ApiEndpoints::default(),
             db_enabled: false,
             db_path: "/test/path.db".to_string(),
+            retention_days: None,
+            reconcile_on_start: false,
+            reconcile_write_adjustments: false,
+            recent_trades_capacity: 0,
             traders,
             agg_enabled: false,
             agg_window_ms: 800,
             agg_bypass_shares: 4000.0,
+            agg_per_trader: false,
+            agg_max_price_drift: None,
             api_enabled: false,
             api_port: 8080,
+            api_live_prices: false,
+            api_price_ttl_secs: API_PRICE_TTL_SECS,
+            telegram_bot_token: None,
+            telegram_chat_id: None,
+            discord_webhook_url: None,
+            discord_min_usd: 0.0,
             max_bet_portfolio_percent: None,
+            max_bet_shares_abs: None,
             portfolio_cache_secs: 300,
+            balance_check_enabled: false,
+            usdc_reserve: 0.0,
             wallet_address: "0x1234".to_string(),
         };
     }
@@ -927,24 +3059,90 @@ mod tests {
         let _test_config = Config {
             private_key: "test".to_string(),
             funder_address: None,
-            wss_url: "test".to_string(),
+            execution_key_source: "PRIVATE_KEY",
+            wss_urls: vec!["test".to_string()],
+            ws_block_gap_threshold: 100,
+            replay_enabled: false,
+            ws_stale_secs: 300,
+            ws_dedup_window_ms: 30_000,
+            topic_filter_max_traders: TOPIC_FILTER_MAX_TRADERS_DEFAULT,
+            min_submit_interval_ms: 0,
+            market_close_window_secs: 0,
+            post_only: false,
+            success_fill_pct: SUCCESS_FILL_PCT_DEFAULT,
             enable_trading: true,
             mock_trading: false,
+            dry_run: false,
+            skip_neg_risk: false,
+            cancel_on_shutdown: true,
+            log_format: LogFormat::Text,
+            post_trade_book_delay_ms: 2800,
+            slow_event_ms: 2000,
             cb_large_trade_shares: 1500.0,
             cb_consecutive_trigger: 2,
             cb_sequence_window_secs: 30,
             cb_min_depth_usd: 200.0,
             cb_trip_duration_secs: 120,
+            cb_book_depth_levels: 10,
+            cb_state_file: None,
+            cb_state_max_age_mins: 30,
+            exchange_breaker_failures: 5,
+            exchange_breaker_cooldown_secs: 60,
+            dynamic_spread_buffer: false,
+            spread_buffer_fraction: 0.5,
+            max_slippage: None,
+            fok_min_shares: None,
+            kelly_sizing: None,
+            flat_usd: None,
+            fee_rate_bps: None,
+            confidence_scaling: None,
+            copy_decay: None,
+            position_exits: None,
+            trailing_stop_state_file: ".trailing_stop_state.json".to_string(),
+            max_open_positions: None,
+            max_exposure_per_market_usd: None,
+            token_blocklist: HashSet::new(),
+            token_allowlist: HashSet::new(),
+            trading_hours: Vec::new(),
+            scaling_ratio: SCALING_RATIO,
+            size_floor: SizeFloor::default(),
+            resubmit_policy: ResubmitPolicy::default(),
+            order_reply_timeouts: OrderReplyTimeouts::default(),
+            gtd_expiry: GtdExpiry::default(),
+            execution_tiers: ExecutionTiers::default(),
+            shadow_strategy: None,
+            copy_price_range: CopyPriceRange::default(),
+            entry_scaling: EntryScaling::default(),
+            fee_per_trade_usd: FEE_PER_TRADE_USD,
+            csv_file: CSV_FILE.to_string(),
+            csv_max_bytes: None,
+            raw_event_log: None,
+            api_endpoints: ApiEndpoints::default(),
             db_enabled: false,
             db_path: "/test/path.db".to_string(),
+            retention_days: None,
+            reconcile_on_start: false,
+            reconcile_write_adjustments: false,
+            recent_trades_capacity: 0,
             traders,
             agg_enabled: false,
             agg_window_ms: 800,
             agg_bypass_shares: 4000.0,
+            agg_per_trader: false,
+            agg_max_price_drift: None,
             api_enabled: false,
             api_port: 8080,
+            api_live_prices: false,
+            api_price_ttl_secs: API_PRICE_TTL_SECS,
+            telegram_bot_token: None,
+            telegram_chat_id: None,
+            discord_webhook_url: None,
+            discord_min_usd: 0.0,
             max_bet_portfolio_percent: None,
+            max_bet_shares_abs: None,
             portfolio_cache_secs: 300,
+            balance_check_enabled: false,
+            usdc_reserve: 0.0,
             wallet_address: "0x1234".to_string(),
         };
     }
@@ -1012,6 +3210,43 @@ mod tests {
         assert!(!enabled, "API should be disabled by default when env var not set");
     }
 
+    #[test]
+    fn test_risk_limit_typo_fails_instead_of_disabling() {
+        unsafe { std::env::set_var("MAX_OPEN_POSITIONS_TEST_TYPO", "2o"); }
+        let err = env_parse_limit::<usize>("MAX_OPEN_POSITIONS_TEST_TYPO", 0).unwrap_err();
+        assert!(err.to_string().contains("MAX_OPEN_POSITIONS_TEST_TYPO"), "got: {}", err);
+        unsafe { std::env::set_var("MAX_OPEN_POSITIONS_TEST_TYPO", " 20 "); }
+        assert_eq!(env_parse_limit::<usize>("MAX_OPEN_POSITIONS_TEST_TYPO", 0).unwrap(), 20);
+        unsafe { std::env::remove_var("MAX_OPEN_POSITIONS_TEST_TYPO"); }
+
+        unsafe { std::env::set_var("FOK_MIN_SHARES_TEST_TYPO", "4k"); }
+        assert!(env_parse_limit_opt::<f64>("FOK_MIN_SHARES_TEST_TYPO").is_err());
+        unsafe { std::env::remove_var("FOK_MIN_SHARES_TEST_TYPO"); }
+        assert_eq!(env_parse_limit_opt::<f64>("FOK_MIN_SHARES_TEST_TYPO").unwrap(), None);
+
+        // Unset keeps the default; a negative amount is rejected too
+        assert_eq!(env_parse_limit::<usize>("MAX_OPEN_POSITIONS_TEST_NONEXISTENT", 0).unwrap(), 0);
+        assert!(non_negative_limit("USDC_RESERVE", -5.0).is_err());
+        assert_eq!(non_negative_limit("USDC_RESERVE", 0.0).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_limit_in_range() {
+        assert_eq!(limit_in_range("SPREAD_BUFFER_FRACTION", 0.5, 0.0, 1.0).unwrap(), 0.5);
+        assert_eq!(limit_in_range("SPREAD_BUFFER_FRACTION", 1.0, 0.0, 1.0).unwrap(), 1.0);
+        assert!(limit_in_range("SPREAD_BUFFER_FRACTION", -0.1, 0.0, 1.0).is_err());
+        assert!(limit_in_range("SPREAD_BUFFER_FRACTION", 1.5, 0.0, 1.0).is_err());
+        assert!(limit_in_range("SPREAD_BUFFER_FRACTION", f64::NAN, 0.0, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_positive_limit() {
+        assert_eq!(positive_limit("MIN_SHARE_COUNT", 5.0).unwrap(), 5.0);
+        for bad in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(positive_limit("MIN_SHARE_COUNT", bad).is_err(), "{}", bad);
+        }
+    }
+
     // -------------------------------------------------------------------------
     // Portfolio Bet Sizing Tests
     // -------------------------------------------------------------------------
@@ -1095,4 +3330,196 @@ mod tests {
         let max_bet_shares: f64 = max_bet_usd / price;
         assert!((max_bet_shares - 200.0).abs() < 0.001);
     }
+
+    #[test]
+    fn test_parse_token_list() {
+        let tokens = parse_token_list(" 123 ,456,, 789 ,");
+        assert_eq!(tokens, HashSet::from(["123".to_string(), "456".to_string(), "789".to_string()]));
+        assert!(parse_token_list("").is_empty());
+        assert!(parse_token_list(" , ").is_empty());
+    }
+
+    #[test]
+    fn test_token_lists_empty_by_default() {
+        unsafe { std::env::remove_var("TOKEN_LIST_TEST_UNSET"); }
+        assert!(env_token_set("TOKEN_LIST_TEST_UNSET").is_empty());
+    }
+
+    fn utc(hour: u32, minute: u32) -> DateTime<Utc> {
+        use chrono::TimeZone;
+        Utc.with_ymd_and_hms(2025, 6, 1, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_trading_hours() {
+        let windows = parse_trading_hours("08:00-12:30, 22:00-02:00").unwrap();
+        assert_eq!(windows, vec![
+            TradingWindow { start_min: 480, end_min: 750 },
+            TradingWindow { start_min: 1320, end_min: 120 },
+        ]);
+        assert_eq!(parse_trading_hours("00:00-24:00").unwrap()[0].end_min, 1440);
+        assert!(parse_trading_hours("").unwrap().is_empty());
+
+        assert!(parse_trading_hours("8-12").is_err());
+        assert!(parse_trading_hours("08:00").is_err());
+        assert!(parse_trading_hours("25:00-26:00").is_err());
+        assert!(parse_trading_hours("08:60-09:00").is_err());
+    }
+
+    #[test]
+    fn test_trading_hours_inside_outside() {
+        let windows = parse_trading_hours("08:00-12:00,14:00-16:00").unwrap();
+        assert!(is_within_trading_hours(utc(9, 30), &windows));
+        assert!(is_within_trading_hours(utc(15, 0), &windows));
+        assert!(!is_within_trading_hours(utc(13, 0), &windows));
+        assert!(!is_within_trading_hours(utc(3, 0), &windows));
+    }
+
+    #[test]
+    fn test_trading_hours_boundaries() {
+        let windows = parse_trading_hours("08:00-12:00").unwrap();
+        assert!(is_within_trading_hours(utc(8, 0), &windows), "start is inclusive");
+        assert!(is_within_trading_hours(utc(11, 59), &windows));
+        assert!(!is_within_trading_hours(utc(12, 0), &windows), "end is exclusive");
+        assert!(!is_within_trading_hours(utc(7, 59), &windows));
+    }
+
+    #[test]
+    fn test_trading_hours_wrap_midnight() {
+        let windows = parse_trading_hours("22:00-02:00").unwrap();
+        assert!(is_within_trading_hours(utc(23, 30), &windows));
+        assert!(is_within_trading_hours(utc(0, 0), &windows));
+        assert!(is_within_trading_hours(utc(1, 59), &windows));
+        assert!(!is_within_trading_hours(utc(2, 0), &windows));
+        assert!(!is_within_trading_hours(utc(12, 0), &windows));
+    }
+
+    #[test]
+    fn test_trading_hours_empty_and_full_day() {
+        assert!(is_within_trading_hours(utc(3, 0), &[]));
+        let all_day = parse_trading_hours("00:00-24:00,06:00-06:00").unwrap();
+        for w in &all_day {
+            assert!(is_within_trading_hours(utc(3, 0), std::slice::from_ref(w)));
+            assert!(is_within_trading_hours(utc(23, 59), std::slice::from_ref(w)));
+        }
+    }
+
+    #[test]
+    fn test_default_scaling_ratio() {
+        // Unset in tests (setting it would leak into traders.json defaults elsewhere)
+        assert_eq!(default_scaling_ratio(), SCALING_RATIO);
+        assert_eq!(scaling_ratio_from_env().unwrap(), SCALING_RATIO);
+    }
+
+    #[test]
+    fn test_default_fee_per_trade_usd() {
+        // Unset in tests, like SCALING_RATIO
+        assert_eq!(fee_per_trade_usd_from_env().unwrap(), FEE_PER_TRADE_USD);
+        assert_eq!(fee_per_trade_usd(), FEE_PER_TRADE_USD);
+    }
+
+    /// Size `target` shares at 0.50 (5-share floor) with a fresh copy of `seed`'s RNG
+    fn seeded_size(target: f64, seed: u64) -> (f64, SizeType) {
+        use rand::SeedableRng;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        calculate_safe_size_with_rng(SizingInput::new(target, 1.0, 0.50), &mut rng)
+    }
+
+    #[test]
+    fn test_seeded_rng_hit_skip_boundary() {
+        use rand::SeedableRng;
+        let seed = 42;
+        let draw: f64 = rand::rngs::StdRng::seed_from_u64(seed).r#gen();
+
+        // Probability above the draw hits, below it skips
+        let above = draw + (1.0 - draw) / 2.0;
+        let below = draw / 2.0;
+        let (shares, size_type) = seeded_size(above * 5.0, seed);
+        assert_eq!(shares, 5.0);
+        assert!(matches!(size_type, SizeType::ProbHit(pct) if pct == (above * 100.0) as u8));
+        let (shares, size_type) = seeded_size(below * 5.0, seed);
+        assert_eq!(shares, 0.0);
+        assert!(matches!(size_type, SizeType::ProbSkip(pct) if pct == (below * 100.0) as u8));
+    }
+
+    #[test]
+    fn test_seeded_rng_is_reproducible() {
+        // 2 of 5 shares: 40%; the same seed always gives the same outcome
+        let outcomes: Vec<bool> = (0..20u64)
+            .map(|seed| matches!(seeded_size(2.0, seed).1, SizeType::ProbHit(40)))
+            .collect();
+        let again: Vec<bool> = (0..20u64)
+            .map(|seed| matches!(seeded_size(2.0, seed).1, SizeType::ProbHit(40)))
+            .collect();
+        assert_eq!(outcomes, again);
+        assert!(outcomes.contains(&true) && outcomes.contains(&false));
+    }
+
+    /// Size `target` shares at 0.50 (5-share floor) under `strategy`
+    fn strategy_size(target: f64, strategy: SizingStrategy) -> (f64, SizeType) {
+        let floor = SizeFloor { strategy, ..SizeFloor::default() };
+        calculate_safe_size(SizingInput { floor, ..SizingInput::new(target, 1.0, 0.50) })
+    }
+
+    #[test]
+    fn test_sizing_strategy_round_up() {
+        for target in [1.0, 2.0, 4.0] {
+            let (shares, size_type) = strategy_size(target, SizingStrategy::RoundUp);
+            assert_eq!(shares, 5.0);
+            assert!(matches!(size_type, SizeType::Scaled));
+        }
+    }
+
+    #[test]
+    fn test_sizing_strategy_skip() {
+        for target in [1.0, 2.0, 4.0] {
+            assert!(matches!(strategy_size(target, SizingStrategy::Skip), (0.0, SizeType::BelowFloor)));
+        }
+    }
+
+    #[test]
+    fn test_sizing_strategy_nearest() {
+        // Half the 5-share floor rounds up, less is skipped
+        assert!(matches!(strategy_size(2.5, SizingStrategy::Nearest), (5.0, SizeType::Scaled)));
+        assert!(matches!(strategy_size(4.0, SizingStrategy::Nearest), (5.0, SizeType::Scaled)));
+        assert!(matches!(strategy_size(2.0, SizingStrategy::Nearest), (0.0, SizeType::BelowFloor)));
+    }
+
+    #[test]
+    fn test_sizing_strategy_probabilistic() {
+        let (mut hits, mut skips) = (0, 0);
+        for _ in 0..200 {
+            match strategy_size(2.0, SizingStrategy::Probabilistic) {
+                (shares, SizeType::ProbHit(40)) => { assert_eq!(shares, 5.0); hits += 1; }
+                (shares, SizeType::ProbSkip(40)) => { assert_eq!(shares, 0.0); skips += 1; }
+                other => panic!("unexpected sizing {:?}", other),
+            }
+        }
+        assert!(hits > 0 && skips > 0, "hits={} skips={}", hits, skips);
+    }
+
+    #[test]
+    fn test_sizing_strategy_exact_at_or_above_floor() {
+        for strategy in [SizingStrategy::Probabilistic, SizingStrategy::RoundUp, SizingStrategy::Skip, SizingStrategy::Nearest] {
+            let (shares, size_type) = strategy_size(7.5, strategy);
+            assert_eq!(shares, 7.5);
+            assert!(matches!(size_type, SizeType::Scaled));
+        }
+    }
+
+    #[test]
+    fn test_sizing_strategy_parse() {
+        assert_eq!(SizingStrategy::parse("probabilistic"), Some(SizingStrategy::Probabilistic));
+        assert_eq!(SizingStrategy::parse(" Round-Up "), Some(SizingStrategy::RoundUp));
+        assert_eq!(SizingStrategy::parse("skip"), Some(SizingStrategy::Skip));
+        assert_eq!(SizingStrategy::parse("NEAREST"), Some(SizingStrategy::Nearest));
+        assert_eq!(SizingStrategy::parse("sometimes"), None);
+        assert_eq!(SizingStrategy::default(), SizingStrategy::Probabilistic);
+
+        // Per-trader probabilistic_sizing maps onto the strategy
+        let floor = SizeFloor { strategy: SizingStrategy::Skip, ..SizeFloor::default() };
+        assert_eq!(floor.with_probabilistic(None).strategy, SizingStrategy::Skip);
+        assert_eq!(floor.with_probabilistic(Some(true)).strategy, SizingStrategy::Probabilistic);
+        assert_eq!(floor.with_probabilistic(Some(false)).strategy, SizingStrategy::RoundUp);
+    }
 }
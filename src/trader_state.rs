@@ -5,6 +5,7 @@ use std::collections::HashMap;
 use std::time::Instant;
 use chrono::{DateTime, Utc};
 use crate::config::traders::TradersConfig;
+use crate::pnl::RealizedPnl;
 
 /// Status of a trade execution
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -25,6 +26,12 @@ pub struct TraderState {
     pub successful_trades: u32,
     pub failed_trades: u32,
     pub partial_trades: u32,
+    /// FIFO realized P&L on trades copied from this trader
+    pub realized_pnl: f64,
+    /// Closing sells with positive realized P&L
+    pub winning_trades: u32,
+    /// Closing sells with zero/negative realized P&L
+    pub losing_trades: u32,
     pub last_trade_ts: Option<Instant>,
     pub daily_reset_ts: DateTime<Utc>,
 }
@@ -40,10 +47,23 @@ impl TraderState {
             successful_trades: 0,
             failed_trades: 0,
             partial_trades: 0,
+            realized_pnl: 0.0,
+            winning_trades: 0,
+            losing_trades: 0,
             last_trade_ts: None,
             daily_reset_ts: Utc::now(),
         }
     }
+
+    /// Fraction of closing sells that were profitable (None if nothing closed yet)
+    pub fn win_rate(&self) -> Option<f64> {
+        let closes = self.winning_trades + self.losing_trades;
+        if closes == 0 {
+            None
+        } else {
+            Some(self.winning_trades as f64 / closes as f64)
+        }
+    }
 }
 
 /// Manager for all trader states
@@ -92,6 +112,17 @@ impl TraderManager {
         }
     }
 
+    /// Updates realized P&L from a FIFO computation keyed by trader address
+    /// Traders missing from `pnl` are reset to zero
+    pub fn apply_realized_pnl(&mut self, pnl: &HashMap<String, RealizedPnl>) {
+        for (address, state) in self.states.iter_mut() {
+            let p = pnl.get(address).copied().unwrap_or_default();
+            state.realized_pnl = p.realized_pnl;
+            state.winning_trades = p.winning_closes;
+            state.losing_trades = p.losing_closes;
+        }
+    }
+
     /// Gets state for a specific trader
     pub fn get_state(&self, address: &str) -> Option<&TraderState> {
         self.states.get(address)
@@ -125,6 +156,9 @@ pub struct ManagerStats {
     pub total_failed: u32,
     pub total_partial: u32,
     pub total_copied_usd: f64,
+    pub total_realized_pnl: f64,
+    pub total_winning: u32,
+    pub total_losing: u32,
 }
 
 impl TraderManager {
@@ -137,6 +171,9 @@ impl TraderManager {
             total_failed: 0,
             total_partial: 0,
             total_copied_usd: 0.0,
+            total_realized_pnl: 0.0,
+            total_winning: 0,
+            total_losing: 0,
         };
 
         for state in self.states.values() {
@@ -145,6 +182,9 @@ impl TraderManager {
             stats.total_failed += state.failed_trades;
             stats.total_partial += state.partial_trades;
             stats.total_copied_usd += state.total_copied_usd;
+            stats.total_realized_pnl += state.realized_pnl;
+            stats.total_winning += state.winning_trades;
+            stats.total_losing += state.losing_trades;
         }
 
         stats
@@ -178,6 +218,12 @@ impl TraderManager {
                 last_trade_ts,
                 daily_reset_ts,
             )?;
+            store.update_trader_pnl(
+                &state.address,
+                state.realized_pnl,
+                state.winning_trades,
+                state.losing_trades,
+            )?;
         }
         Ok(())
     }
@@ -511,4 +557,41 @@ mod tests {
         assert_eq!(stats.total_partial, 1);
         assert_eq!(stats.total_copied_usd, 375.0);
     }
+
+    #[test]
+    fn test_apply_realized_pnl_updates_states_and_summary() {
+        let trader1 = TraderConfig::new(
+            "abc123def456789012345678901234567890abcd",
+            "Whale1",
+        ).unwrap();
+
+        let trader2 = TraderConfig::new(
+            "def456def456789012345678901234567890def4",
+            "Whale2",
+        ).unwrap();
+
+        let config = TradersConfig::new(vec![trader1, trader2]);
+        let mut manager = TraderManager::new(&config);
+
+        let mut pnl = HashMap::new();
+        pnl.insert(
+            "abc123def456789012345678901234567890abcd".to_string(),
+            RealizedPnl { realized_pnl: 12.5, winning_closes: 3, losing_closes: 1 },
+        );
+        manager.apply_realized_pnl(&pnl);
+
+        let state1 = manager.get_state("abc123def456789012345678901234567890abcd").unwrap();
+        assert_eq!(state1.realized_pnl, 12.5);
+        assert_eq!(state1.win_rate(), Some(0.75));
+
+        // Trader without closed positions has no win rate yet
+        let state2 = manager.get_state("def456def456789012345678901234567890def4").unwrap();
+        assert_eq!(state2.realized_pnl, 0.0);
+        assert_eq!(state2.win_rate(), None);
+
+        let stats = manager.get_summary_stats();
+        assert_eq!(stats.total_realized_pnl, 12.5);
+        assert_eq!(stats.total_winning, 3);
+        assert_eq!(stats.total_losing, 1);
+    }
 }
@@ -0,0 +1,109 @@
+//! Peak prices of open positions for the trailing stop (TRAILING_STOP_PCT)
+//!
+//! Each held token remembers the highest bid seen since entry, starting from the entry
+//! price itself. The map is saved to a small JSON file after every check so a restart
+//! doesn't forget how far a position had run up. Tokens no longer held are dropped, so a
+//! position that is closed and later reopened starts again from its new entry.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrailingPeaks {
+    /// Token ID -> highest price seen since entry
+    peaks: HashMap<String, f64>,
+}
+
+impl TrailingPeaks {
+    /// Read peaks from a JSON file; a missing file is an empty state
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&raw).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Write peaks as JSON
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Record the current bid of a held token and return its peak since entry
+    pub fn observe(&mut self, token_id: &str, avg_entry: f64, bid: f64) -> f64 {
+        let peak = self.peaks.entry(token_id.to_string()).or_insert(avg_entry);
+        *peak = peak.max(bid);
+        *peak
+    }
+
+    pub fn peak(&self, token_id: &str) -> Option<f64> {
+        self.peaks.get(token_id).copied()
+    }
+
+    /// Forget tokens that are no longer held; returns how many were dropped
+    pub fn retain_held(&mut self, is_held: impl Fn(&str) -> bool) -> usize {
+        let before = self.peaks.len();
+        self.peaks.retain(|token_id, _| is_held(token_id));
+        before - self.peaks.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.peaks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peaks.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peak_starts_at_entry_and_only_rises() {
+        let mut peaks = TrailingPeaks::default();
+
+        // Below entry: the peak is the entry itself
+        assert_eq!(peaks.observe("tok", 0.50, 0.45), 0.50);
+        assert_eq!(peaks.observe("tok", 0.50, 0.70), 0.70);
+        // A retracement doesn't lower it
+        assert_eq!(peaks.observe("tok", 0.50, 0.60), 0.70);
+        assert_eq!(peaks.peak("tok"), Some(0.70));
+    }
+
+    #[test]
+    fn test_closed_position_resets_on_reopen() {
+        let mut peaks = TrailingPeaks::default();
+        peaks.observe("tok", 0.50, 0.90);
+        peaks.observe("other", 0.30, 0.35);
+
+        assert_eq!(peaks.retain_held(|token| token == "other"), 1);
+        assert_eq!(peaks.peak("tok"), None);
+
+        // Reopened at a new entry: the old 0.90 peak is gone
+        assert_eq!(peaks.observe("tok", 0.40, 0.42), 0.42);
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trailing.json");
+        assert!(TrailingPeaks::load(&path).unwrap().is_empty());
+
+        let mut peaks = TrailingPeaks::default();
+        peaks.observe("tok", 0.50, 0.80);
+        peaks.save(&path).unwrap();
+
+        let loaded = TrailingPeaks::load(&path).unwrap();
+        assert_eq!(loaded, peaks);
+        assert_eq!(loaded.len(), 1);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(TrailingPeaks::load(&path).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+//! WebSocket liveness for the /health readiness probe
+//!
+//! The WS loop records every frame it receives and each subscription confirmation;
+//! /health reports `degraded` when the subscription on the current connection was never
+//! confirmed, or when no frame arrived within WS_STALE_SECS during TRADING_HOURS.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+
+use crate::settings::{TradingWindow, is_within_trading_hours};
+
+/// Shared between the WS loop (writer) and the API (reader)
+#[derive(Debug)]
+pub struct WsHealth {
+    /// No frame for this long is stale (zero = never stale)
+    stale_after: Duration,
+    /// Staleness only counts inside these windows (empty = always)
+    trading_hours: Vec<TradingWindow>,
+    /// Unix ms of the last frame received (0 = none yet)
+    last_message_ms: AtomicI64,
+    /// Unix ms of the subscription confirmation on the current connection (0 = not confirmed)
+    confirmed_ms: AtomicI64,
+}
+
+/// WebSocket section of the /health response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WsStatus {
+    pub healthy: bool,
+    pub subscription_confirmed: bool,
+    /// Seconds since the last frame (null before the first one)
+    pub last_message_age_secs: Option<u64>,
+    pub stale_after_secs: u64,
+    pub in_trading_hours: bool,
+    /// Why the connection is considered degraded (empty when healthy)
+    pub issues: Vec<String>,
+}
+
+impl WsHealth {
+    pub fn new(stale_after: Duration, trading_hours: Vec<TradingWindow>) -> Self {
+        Self {
+            stale_after,
+            trading_hours,
+            last_message_ms: AtomicI64::new(0),
+            confirmed_ms: AtomicI64::new(0),
+        }
+    }
+
+    /// New connection: its subscription has to be confirmed again
+    pub fn record_connected(&self) {
+        self.confirmed_ms.store(0, Ordering::Relaxed);
+    }
+
+    /// Any frame received (text, binary or ping)
+    pub fn record_message(&self) {
+        self.last_message_ms.store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+    }
+
+    pub fn record_subscription_confirmed(&self) {
+        self.confirmed_ms.store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+    }
+
+    pub fn status(&self) -> WsStatus {
+        self.status_at(Utc::now())
+    }
+
+    pub fn status_at(&self, now: DateTime<Utc>) -> WsStatus {
+        let subscription_confirmed = self.confirmed_ms.load(Ordering::Relaxed) > 0;
+        let last_message_ms = self.last_message_ms.load(Ordering::Relaxed);
+        let last_message_age_secs = (last_message_ms > 0)
+            .then(|| ((now.timestamp_millis() - last_message_ms).max(0) / 1000) as u64);
+        let in_trading_hours = is_within_trading_hours(now, &self.trading_hours);
+        let stale_after_secs = self.stale_after.as_secs();
+
+        let mut issues = Vec::new();
+        if !subscription_confirmed {
+            issues.push("subscription not confirmed".to_string());
+        }
+        if stale_after_secs > 0 && in_trading_hours {
+            match last_message_age_secs {
+                Some(age) if age > stale_after_secs => {
+                    issues.push(format!("no message for {}s (limit {}s)", age, stale_after_secs));
+                }
+                Some(_) => {}
+                None => issues.push("no message received yet".to_string()),
+            }
+        }
+
+        WsStatus {
+            healthy: issues.is_empty(),
+            subscription_confirmed,
+            last_message_age_secs,
+            stale_after_secs,
+            in_trading_hours,
+            issues,
+        }
+    }
+
+    #[cfg(test)]
+    fn set_last_message_ms(&self, ms: i64) {
+        self.last_message_ms.store(ms, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::parse_trading_hours;
+    use chrono::TimeZone;
+
+    fn at(h: u32, m: u32, s: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 6, 2, h, m, s).unwrap()
+    }
+
+    #[test]
+    fn test_unconfirmed_subscription_is_degraded() {
+        let health = WsHealth::new(Duration::from_secs(60), Vec::new());
+        health.record_message();
+
+        let status = health.status();
+        assert!(!status.healthy);
+        assert!(!status.subscription_confirmed);
+        assert_eq!(status.issues, vec!["subscription not confirmed"]);
+    }
+
+    #[test]
+    fn test_stale_messages_are_degraded() {
+        let health = WsHealth::new(Duration::from_secs(60), Vec::new());
+        health.record_subscription_confirmed();
+        health.set_last_message_ms(at(10, 0, 0).timestamp_millis());
+
+        let fresh = health.status_at(at(10, 0, 45));
+        assert!(fresh.healthy);
+        assert_eq!(fresh.last_message_age_secs, Some(45));
+
+        let stale = health.status_at(at(10, 2, 0));
+        assert!(!stale.healthy);
+        assert_eq!(stale.last_message_age_secs, Some(120));
+        assert_eq!(stale.issues, vec!["no message for 120s (limit 60s)"]);
+    }
+
+    #[test]
+    fn test_staleness_ignored_outside_trading_hours() {
+        let hours = parse_trading_hours("08:00-12:00").unwrap();
+        let health = WsHealth::new(Duration::from_secs(60), hours);
+        health.record_subscription_confirmed();
+        health.set_last_message_ms(at(11, 0, 0).timestamp_millis());
+
+        assert!(!health.status_at(at(11, 30, 0)).healthy);
+        let overnight = health.status_at(at(22, 0, 0));
+        assert!(overnight.healthy);
+        assert!(!overnight.in_trading_hours);
+    }
+
+    #[test]
+    fn test_reconnect_requires_new_confirmation() {
+        let health = WsHealth::new(Duration::ZERO, Vec::new());
+        health.record_subscription_confirmed();
+        assert!(health.status().healthy);
+
+        health.record_connected();
+        let status = health.status();
+        assert!(!status.healthy);
+        // Zero window disables the staleness check
+        assert_eq!(status.issues, vec!["subscription not confirmed"]);
+    }
+}
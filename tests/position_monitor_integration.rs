@@ -46,6 +46,8 @@ fn make_test_trade(token_id: &str, side: &str, our_shares: f64, our_price: f64)
         is_live: Some(false),
         aggregation_count: None,
         aggregation_window_ms: None,
+        order_id: None,
+        contributing_traders: None,
     }
 }
 
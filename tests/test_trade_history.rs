@@ -37,6 +37,8 @@ fn test_trade_history_binary_with_data() {
             is_live: Some(true),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         },
         TradeRecord {
             timestamp_ms: 1704067260000, // 2024-01-01 00:01:00
@@ -57,6 +59,8 @@ fn test_trade_history_binary_with_data() {
             is_live: Some(true),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         },
         TradeRecord {
             timestamp_ms: 1704067320000, // 2024-01-01 00:02:00
@@ -77,6 +81,8 @@ fn test_trade_history_binary_with_data() {
             is_live: Some(true),
             aggregation_count: None,
             aggregation_window_ms: None,
+            order_id: None,
+            contributing_traders: None,
         },
     ];
 